@@ -0,0 +1,196 @@
+//! Posting-rate early warning.  Monitor channels flood with fragmentary
+//! messages ("бахнуло", "летить", "+") right as an attack starts, often
+//! before any post names a threat.  A sudden spike in how many messages all
+//! watched channels post, compared with the preceding hour, triggers a
+//! heads-up notice.
+
+use std::collections::{HashSet, VecDeque};
+
+/// Header of the notice; also used to tell it apart from alerts.
+pub const SPIKE_HEADER: &str = "📈 Активність каналів різко зросла";
+
+#[derive(Debug, Clone)]
+pub struct ActivityCfg {
+    /// Spike threshold as a multiple of the baseline rate.
+    pub factor: f64,
+    /// Window the spike is measured over, seconds.
+    pub window_secs: i64,
+    /// Preceding span the baseline rate is taken from, seconds.
+    pub baseline_secs: i64,
+    /// Messages the window needs at least, whatever the baseline.
+    pub min_messages: usize,
+    /// Distinct channels those messages must come from.
+    pub min_channels: usize,
+    /// Quiet time after a notice, seconds.
+    pub cooldown_secs: i64,
+}
+
+impl ActivityCfg {
+    /// `None` unless `ACTIVITY_SPIKE_FACTOR` is set.
+    ///
+    /// | Env var                  | Default | Description                            |
+    /// |--------------------------|---------|----------------------------------------|
+    /// | `ACTIVITY_SPIKE_FACTOR`  | `0`     | Spike = this × baseline rate (0 = off) |
+    /// | `ACTIVITY_WINDOW_SECS`   | `300`   | Window the spike is measured over      |
+    /// | `ACTIVITY_BASELINE_MINS` | `60`    | Preceding span giving the baseline     |
+    /// | `ACTIVITY_MIN_MESSAGES`  | `15`    | Messages a spike needs at least        |
+    /// | `ACTIVITY_MIN_CHANNELS`  | `3`     | Channels a spike must span             |
+    /// | `ACTIVITY_COOLDOWN_SECS` | `1800`  | Quiet time after a notice              |
+    pub fn from_env() -> Option<Self> {
+        let num = |key: &str, default: f64| -> f64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let factor = num("ACTIVITY_SPIKE_FACTOR", 0.0);
+        if factor <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            factor,
+            window_secs: (num("ACTIVITY_WINDOW_SECS", 300.0) as i64).max(1),
+            baseline_secs: (num("ACTIVITY_BASELINE_MINS", 60.0) as i64).max(1) * 60,
+            min_messages: num("ACTIVITY_MIN_MESSAGES", 15.0) as usize,
+            min_channels: num("ACTIVITY_MIN_CHANNELS", 3.0) as usize,
+            cooldown_secs: num("ACTIVITY_COOLDOWN_SECS", 1800.0) as i64,
+        })
+    }
+}
+
+/// A detected spike.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spike {
+    /// Messages in the window.
+    pub messages: usize,
+    /// Channels they came from.
+    pub channels: usize,
+    /// Baseline messages per window.
+    pub baseline: f64,
+    pub window_secs: i64,
+}
+
+impl Spike {
+    pub fn notice(&self) -> String {
+        format!(
+            "{SPIKE_HEADER}\n\
+             {} повідомлень з {} каналів за {} хв (зазвичай ~{:.0}).\n\
+             Можливий початок атаки — загроз ще не названо, будьте уважні.",
+            self.messages,
+            self.channels,
+            (self.window_secs / 60).max(1),
+            self.baseline
+        )
+    }
+}
+
+pub struct ActivityMonitor {
+    cfg: ActivityCfg,
+    /// `(unix ts, channel_id)` of recent messages.
+    posts: VecDeque<(i64, i64)>,
+    /// First message seen; no baseline exists before a full span passed.
+    first_ts: Option<i64>,
+    last_notice: Option<i64>,
+}
+
+impl ActivityMonitor {
+    pub fn new(cfg: ActivityCfg) -> Self {
+        Self {
+            cfg,
+            posts: VecDeque::new(),
+            first_ts: None,
+            last_notice: None,
+        }
+    }
+
+    /// Record a message posted at `ts`; returns the spike it completes.
+    pub fn observe(&mut self, ts: i64, channel_id: i64) -> Option<Spike> {
+        let span = self.cfg.window_secs + self.cfg.baseline_secs;
+        self.first_ts = Some(self.first_ts.map_or(ts, |first| first.min(ts)));
+        self.posts.push_back((ts, channel_id));
+        let horizon = ts - span;
+        while self.posts.front().is_some_and(|(t, _)| *t < horizon) {
+            self.posts.pop_front();
+        }
+        if self.first_ts.is_some_and(|first| ts - first < span)
+            || self
+                .last_notice
+                .is_some_and(|last| ts - last < self.cfg.cooldown_secs)
+        {
+            return None;
+        }
+
+        let window_start = ts - self.cfg.window_secs;
+        let (mut recent, mut before) = (0usize, 0usize);
+        let mut channels = HashSet::new();
+        for &(t, channel) in &self.posts {
+            if t > ts {
+                continue;
+            }
+            if t > window_start {
+                recent += 1;
+                channels.insert(channel);
+            } else {
+                before += 1;
+            }
+        }
+        let baseline = before as f64 * self.cfg.window_secs as f64 / self.cfg.baseline_secs as f64;
+        // A quiet hour makes any burst a huge multiple; one message per
+        // window is the floor.
+        let threshold = (self.cfg.factor * baseline.max(1.0)).max(self.cfg.min_messages as f64);
+        if (recent as f64) < threshold || channels.len() < self.cfg.min_channels {
+            return None;
+        }
+        self.last_notice = Some(ts);
+        Some(Spike {
+            messages: recent,
+            channels: channels.len(),
+            baseline,
+            window_secs: self.cfg.window_secs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posting_rate_spike_needs_baseline_volume_and_spread() {
+        let cfg = ActivityCfg {
+            factor: 5.0,
+            window_secs: 300,
+            baseline_secs: 3600,
+            min_messages: 10,
+            min_channels: 3,
+            cooldown_secs: 1800,
+        };
+        let mut monitor = ActivityMonitor::new(cfg.clone());
+        // Quiet hour: one message every 10 minutes.
+        for i in 0..7 {
+            assert_eq!(monitor.observe(i * 600, 1), None);
+        }
+        let t0 = 3900;
+        // Burst from a single channel is not enough.
+        for i in 0..12 {
+            assert_eq!(monitor.observe(t0 + i * 10, 1), None);
+        }
+        // Two more channels join: the spike fires once…
+        let spike = (0..3)
+            .find_map(|i| monitor.observe(t0 + 130 + i, 2 + i % 2))
+            .expect("spike");
+        assert_eq!(spike.channels, 3);
+        assert!(spike.messages >= 13);
+        assert!(spike.notice().contains("Активність каналів різко зросла"));
+        // …and stays quiet during the cooldown.
+        for i in 0..20 {
+            assert_eq!(monitor.observe(t0 + 140 + i, 4), None);
+        }
+
+        // Nothing fires before a full baseline span was seen.
+        let mut fresh = ActivityMonitor::new(cfg);
+        for i in 0..30 {
+            assert_eq!(fresh.observe(i, i % 5), None);
+        }
+    }
+}
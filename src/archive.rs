@@ -0,0 +1,835 @@
+//! Message archive: every channel message seen by any mode is stored in
+//! SQLite with an FTS5 index, so the tool doubles as a small OSINT research
+//! database (see `search` mode).
+//!
+//! Forwarded alerts are grouped into waves and later damage reports
+//! ("влучання в енергооб'єкт") are linked to the wave that preceded them, so
+//! analysts can ask which alerts ended in confirmed strikes.
+//!
+//! Channel analytics report each channel's language/script mix over time.
+//!
+//! [`compact`] keeps multi-month archives manageable: the same text posted
+//! by several channels is stored once, with the other copies kept as
+//! `message_sources` attributions.
+
+use crate::filter::script::{ScriptCounts, detect_script};
+use crate::filter::{AlertInfo, Proximity};
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use sqlite::State;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+// ---------------------------------------------------------------------------
+// Shared database handle
+// ---------------------------------------------------------------------------
+
+pub type SharedArchive = Arc<Mutex<sqlite::Connection>>;
+
+/// Path of the archive database from `ARCHIVE_DB_PATH`.  Archiving is
+/// disabled when the variable is unset or empty.
+pub fn archive_path_from_env() -> Option<String> {
+    std::env::var("ARCHIVE_DB_PATH")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Open (or create) the archive database and ensure the schema exists.
+pub fn open_archive(path: &str) -> Result<SharedArchive> {
+    let conn =
+        sqlite::open(path).with_context(|| format!("failed to open archive DB at {path}"))?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS messages (
+            id            INTEGER PRIMARY KEY,
+            timestamp     INTEGER NOT NULL,
+            channel_id    INTEGER NOT NULL,
+            channel_title TEXT    NOT NULL,
+            text          TEXT    NOT NULL,
+            UNIQUE (channel_id, timestamp, text)
+         );
+         CREATE INDEX IF NOT EXISTS messages_ts ON messages (timestamp);
+         CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+            text,
+            content = 'messages',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2'
+         );
+         CREATE TRIGGER IF NOT EXISTS messages_ai AFTER INSERT ON messages BEGIN
+            INSERT INTO messages_fts (rowid, text) VALUES (new.id, new.text);
+         END;
+         CREATE TRIGGER IF NOT EXISTS messages_ad AFTER DELETE ON messages BEGIN
+            INSERT INTO messages_fts (messages_fts, rowid, text)
+            VALUES ('delete', old.id, old.text);
+         END;
+         CREATE TABLE IF NOT EXISTS alerts (
+            id            INTEGER PRIMARY KEY,
+            timestamp     INTEGER NOT NULL,
+            channel_id    INTEGER NOT NULL,
+            channel_title TEXT    NOT NULL,
+            wave_id       INTEGER NOT NULL,
+            threats       TEXT    NOT NULL,
+            proximity     TEXT    NOT NULL,
+            text          TEXT    NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS alerts_ts ON alerts (timestamp);
+         CREATE TABLE IF NOT EXISTS damage_reports (
+            id            INTEGER PRIMARY KEY,
+            timestamp     INTEGER NOT NULL,
+            channel_id    INTEGER NOT NULL,
+            channel_title TEXT    NOT NULL,
+            proximity     TEXT    NOT NULL,
+            text          TEXT    NOT NULL,
+            alert_id      INTEGER REFERENCES alerts (id),
+            wave_id       INTEGER
+         );
+         CREATE INDEX IF NOT EXISTS damage_reports_wave ON damage_reports (wave_id);
+         CREATE TABLE IF NOT EXISTS message_sources (
+            message_id    INTEGER NOT NULL,
+            timestamp     INTEGER NOT NULL,
+            channel_id    INTEGER NOT NULL,
+            channel_title TEXT    NOT NULL,
+            PRIMARY KEY (message_id, channel_id, timestamp)
+         );",
+    )?;
+    info!("Archive DB opened at {path}");
+    Ok(Arc::new(Mutex::new(conn)))
+}
+
+/// Open the archive configured via `ARCHIVE_DB_PATH`, if any.
+pub fn open_from_env() -> Result<Option<SharedArchive>> {
+    archive_path_from_env()
+        .map(|path| open_archive(&path))
+        .transpose()
+}
+
+/// A single archived channel message.
+#[derive(Debug, Clone)]
+pub struct ArchivedMessage {
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub channel_title: String,
+    pub text: String,
+}
+
+/// Store one message.  Exact duplicates (same channel, timestamp and text)
+/// are ignored, so re-running a dump or replay is harmless.
+pub fn archive_message(db: &SharedArchive, msg: &ArchivedMessage) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "INSERT OR IGNORE INTO messages (timestamp, channel_id, channel_title, text)
+         VALUES (?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, msg.timestamp))?;
+    stmt.bind((2, msg.channel_id))?;
+    stmt.bind((3, msg.channel_title.as_str()))?;
+    stmt.bind((4, msg.text.as_str()))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Delete messages, alerts and damage reports older than `before_ts`.
+/// Returns the number of messages removed.
+pub fn prune_before(db: &SharedArchive, before_ts: i64) -> Result<usize> {
+    let db = db.lock().unwrap();
+    let mut removed = 0;
+    for table in ["messages", "alerts", "damage_reports", "message_sources"] {
+        let mut stmt = db.prepare(format!("DELETE FROM {table} WHERE timestamp < ?"))?;
+        stmt.bind((1, before_ts))?;
+        stmt.next()?;
+        drop(stmt);
+        if table == "messages" {
+            removed = db.change_count();
+        }
+    }
+    db.execute("DELETE FROM message_sources WHERE message_id NOT IN (SELECT id FROM messages)")?;
+    Ok(removed)
+}
+
+// ---------------------------------------------------------------------------
+// Compaction
+// ---------------------------------------------------------------------------
+
+/// What [`compact`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactStats {
+    /// Duplicate messages folded into the first archived copy.
+    pub duplicates: usize,
+    /// Messages left.
+    pub messages: i64,
+}
+
+/// Fold messages older than `before_ts` whose text was archived before into
+/// that first copy, recording each fold as a `message_sources` row, then
+/// rebuild the FTS index and reclaim the space.
+pub fn compact(db: &SharedArchive, before_ts: i64) -> Result<CompactStats> {
+    let db = db.lock().unwrap();
+    db.execute("BEGIN")?;
+    let result = (|| -> Result<usize> {
+        db.execute("DROP TABLE IF EXISTS temp.duplicates")?;
+        let mut stmt = db.prepare(
+            "CREATE TEMP TABLE duplicates AS
+             SELECT m.id AS id, k.keep AS keep FROM messages m
+             JOIN (SELECT text, MIN(id) AS keep FROM messages WHERE timestamp < :before
+                   GROUP BY text HAVING COUNT(*) > 1) k ON k.text = m.text
+             WHERE m.id != k.keep AND m.timestamp < :before",
+        )?;
+        stmt.bind((":before", before_ts))?;
+        stmt.next()?;
+        drop(stmt);
+        let mut stmt = db.prepare("SELECT COUNT(*) FROM duplicates")?;
+        stmt.next()?;
+        let duplicates = stmt.read::<i64, _>(0)? as usize;
+        drop(stmt);
+        // Attributions of an earlier compaction move along with their message.
+        db.execute(
+            "INSERT OR IGNORE INTO message_sources
+                (message_id, timestamp, channel_id, channel_title)
+             SELECT d.keep, m.timestamp, m.channel_id, m.channel_title
+             FROM duplicates d JOIN messages m ON m.id = d.id;
+             UPDATE OR IGNORE message_sources SET message_id =
+                (SELECT keep FROM duplicates WHERE id = message_sources.message_id)
+             WHERE message_id IN (SELECT id FROM duplicates);
+             DELETE FROM message_sources WHERE message_id IN (SELECT id FROM duplicates);
+             DELETE FROM messages WHERE id IN (SELECT id FROM duplicates);
+             DROP TABLE temp.duplicates;",
+        )?;
+        Ok(duplicates)
+    })();
+    db.execute(if result.is_ok() { "COMMIT" } else { "ROLLBACK" })?;
+    let duplicates = result?;
+    db.execute(
+        "INSERT INTO messages_fts (messages_fts) VALUES ('rebuild');
+         INSERT INTO messages_fts (messages_fts) VALUES ('optimize');
+         VACUUM;",
+    )?;
+    let mut stmt = db.prepare("SELECT COUNT(*) FROM messages")?;
+    stmt.next()?;
+    Ok(CompactStats {
+        duplicates,
+        messages: stmt.read(0)?,
+    })
+}
+
+/// Channels that posted the same text as the archived message `(channel_id,
+/// timestamp, text)` and were folded into it by [`compact`], oldest first.
+pub fn other_sources(db: &SharedArchive, msg: &ArchivedMessage) -> Result<Vec<(i64, String)>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "SELECT s.timestamp, s.channel_title FROM message_sources s
+         JOIN messages m ON m.id = s.message_id
+         WHERE m.channel_id = ? AND m.timestamp = ? AND m.text = ?
+         ORDER BY s.timestamp",
+    )?;
+    stmt.bind((1, msg.channel_id))?;
+    stmt.bind((2, msg.timestamp))?;
+    stmt.bind((3, msg.text.as_str()))?;
+    let mut out = Vec::new();
+    while let State::Row = stmt.next()? {
+        out.push((stmt.read(0)?, stmt.read(1)?));
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Alert waves and damage correlation
+// ---------------------------------------------------------------------------
+
+/// Time limits used to group alerts into waves and to attach damage reports.
+#[derive(Debug, Clone, Copy)]
+pub struct CorrelationCfg {
+    /// Alerts closer than this continue the current wave.
+    pub wave_gap_secs: i64,
+    /// How long after an alert a damage report may still be attributed to it.
+    pub damage_window_secs: i64,
+}
+
+impl CorrelationCfg {
+    /// | Env var                           | Default | Description                        |
+    /// |-----------------------------------|---------|------------------------------------|
+    /// | `ARCHIVE_WAVE_GAP_SECS`           | `3600`  | Max quiet gap inside one wave      |
+    /// | `DAMAGE_CORRELATION_WINDOW_SECS`  | `10800` | Max delay from alert to damage report |
+    pub fn from_env() -> Self {
+        fn secs(key: &str, default: i64) -> i64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &i64| *v > 0)
+                .unwrap_or(default)
+        }
+        Self {
+            wave_gap_secs: secs("ARCHIVE_WAVE_GAP_SECS", 3600),
+            damage_window_secs: secs("DAMAGE_CORRELATION_WINDOW_SECS", 10800),
+        }
+    }
+}
+
+fn threats_column(info: &AlertInfo) -> String {
+    info.threats
+        .iter()
+        .map(|t| t.variant_name())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn proximity_column(proximity: Proximity, nationwide: bool) -> &'static str {
+    if nationwide {
+        return "Nationwide";
+    }
+    match proximity {
+        Proximity::District => "District",
+        Proximity::City => "City",
+        Proximity::Oblast => "Oblast",
+        Proximity::None => "None",
+    }
+}
+
+/// Store a forwarded alert and return its wave id.  The alert joins the
+/// previous wave unless the gap is larger than `wave_gap_secs` or the
+/// previous alert was an all-clear.
+pub fn record_alert(
+    db: &SharedArchive,
+    msg: &ArchivedMessage,
+    info: &AlertInfo,
+    cfg: &CorrelationCfg,
+) -> Result<i64> {
+    let db = db.lock().unwrap();
+
+    let mut prev = db.prepare(
+        "SELECT wave_id, timestamp, threats FROM alerts ORDER BY timestamp DESC, id DESC LIMIT 1",
+    )?;
+    let mut wave_id = None;
+    if let State::Row = prev.next()? {
+        let prev_wave = prev.read::<i64, _>(0)?;
+        let prev_ts = prev.read::<i64, _>(1)?;
+        let prev_threats = prev.read::<String, _>(2)?;
+        if msg.timestamp - prev_ts <= cfg.wave_gap_secs && prev_threats != "AllClear" {
+            wave_id = Some(prev_wave);
+        }
+    }
+    drop(prev);
+
+    let mut stmt = db.prepare(
+        "INSERT INTO alerts (timestamp, channel_id, channel_title, wave_id, threats, proximity, text)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, msg.timestamp))?;
+    stmt.bind((2, msg.channel_id))?;
+    stmt.bind((3, msg.channel_title.as_str()))?;
+    stmt.bind((4, wave_id.unwrap_or(0)))?;
+    stmt.bind((5, threats_column(info).as_str()))?;
+    stmt.bind((6, proximity_column(info.proximity, info.nationwide)))?;
+    stmt.bind((7, msg.text.as_str()))?;
+    stmt.next()?;
+    drop(stmt);
+
+    let alert_id = {
+        let mut q = db.prepare("SELECT last_insert_rowid()")?;
+        q.next()?;
+        q.read::<i64, _>(0)?
+    };
+    match wave_id {
+        Some(id) => Ok(id),
+        None => {
+            // First alert of a new wave: the wave is named after it.
+            let mut upd = db.prepare("UPDATE alerts SET wave_id = ? WHERE id = ?")?;
+            upd.bind((1, alert_id))?;
+            upd.bind((2, alert_id))?;
+            upd.next()?;
+            Ok(alert_id)
+        }
+    }
+}
+
+/// Store a damage report and link it to the most recent preceding alert
+/// within `damage_window_secs`, preferring alerts at the same proximity
+/// level (region).  Returns the linked wave id, if any.
+pub fn record_damage_report(
+    db: &SharedArchive,
+    msg: &ArchivedMessage,
+    proximity: Proximity,
+    cfg: &CorrelationCfg,
+) -> Result<Option<i64>> {
+    let db = db.lock().unwrap();
+    let prox = proximity_column(proximity, false);
+
+    let mut find = db.prepare(
+        "SELECT id, wave_id FROM alerts
+         WHERE timestamp <= :ts AND timestamp >= :since AND threats != 'AllClear'
+         ORDER BY (proximity = :prox) DESC, timestamp DESC
+         LIMIT 1",
+    )?;
+    find.bind((":ts", msg.timestamp))?;
+    find.bind((":since", msg.timestamp - cfg.damage_window_secs))?;
+    find.bind((":prox", prox))?;
+    let link = if let State::Row = find.next()? {
+        Some((find.read::<i64, _>(0)?, find.read::<i64, _>(1)?))
+    } else {
+        None
+    };
+    drop(find);
+
+    let mut stmt = db.prepare(
+        "INSERT INTO damage_reports
+            (timestamp, channel_id, channel_title, proximity, text, alert_id, wave_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, msg.timestamp))?;
+    stmt.bind((2, msg.channel_id))?;
+    stmt.bind((3, msg.channel_title.as_str()))?;
+    stmt.bind((4, prox))?;
+    stmt.bind((5, msg.text.as_str()))?;
+    stmt.bind((6, link.map(|(alert_id, _)| alert_id)))?;
+    stmt.bind((7, link.map(|(_, wave_id)| wave_id)))?;
+    stmt.next()?;
+    Ok(link.map(|(_, wave_id)| wave_id))
+}
+
+/// An alert wave that was followed by at least one damage report.
+#[derive(Debug, Clone)]
+pub struct ConfirmedStrike {
+    pub wave_id: i64,
+    pub wave_start: i64,
+    /// Distinct threat kinds seen during the wave (comma-separated).
+    pub threats: String,
+    pub alert_count: i64,
+    pub reports: Vec<ArchivedMessage>,
+}
+
+/// Waves in `[from_ts, to_ts)` that have linked damage reports, newest first.
+pub fn confirmed_strikes(
+    db: &SharedArchive,
+    from_ts: Option<i64>,
+    to_ts: Option<i64>,
+    limit: usize,
+) -> Result<Vec<ConfirmedStrike>> {
+    let db = db.lock().unwrap();
+    let mut waves = db.prepare(
+        "SELECT a.wave_id, MIN(a.timestamp), GROUP_CONCAT(DISTINCT a.threats), COUNT(*)
+         FROM alerts a
+         WHERE a.wave_id IN (SELECT wave_id FROM damage_reports WHERE wave_id IS NOT NULL)
+         GROUP BY a.wave_id
+         HAVING MIN(a.timestamp) >= :from_ts AND MIN(a.timestamp) < :to_ts
+         ORDER BY MIN(a.timestamp) DESC
+         LIMIT :limit",
+    )?;
+    waves.bind((":from_ts", from_ts.unwrap_or(i64::MIN)))?;
+    waves.bind((":to_ts", to_ts.unwrap_or(i64::MAX)))?;
+    waves.bind((":limit", limit as i64))?;
+
+    let mut out = Vec::new();
+    while let State::Row = waves.next()? {
+        let mut threats: Vec<String> = Vec::new();
+        for t in waves.read::<String, _>(2)?.split(',') {
+            if !t.is_empty() && !threats.iter().any(|x| x == t) {
+                threats.push(t.to_string());
+            }
+        }
+        out.push(ConfirmedStrike {
+            wave_id: waves.read::<i64, _>(0)?,
+            wave_start: waves.read::<i64, _>(1)?,
+            threats: threats.join(","),
+            alert_count: waves.read::<i64, _>(3)?,
+            reports: Vec::new(),
+        });
+    }
+    drop(waves);
+
+    for strike in &mut out {
+        let mut reports = db.prepare(
+            "SELECT timestamp, channel_id, channel_title, text FROM damage_reports
+             WHERE wave_id = ? ORDER BY timestamp",
+        )?;
+        reports.bind((1, strike.wave_id))?;
+        while let State::Row = reports.next()? {
+            strike.reports.push(ArchivedMessage {
+                timestamp: reports.read::<i64, _>(0)?,
+                channel_id: reports.read::<i64, _>(1)?,
+                channel_title: reports.read::<String, _>(2)?,
+                text: reports.read::<String, _>(3)?,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Aggregate of archived alerts over a time range (see [`summarize`]).
+#[derive(Debug, Clone, Default)]
+pub struct AlertSummary {
+    /// Alerts that matched the configured area (any proximity or nationwide).
+    pub area_alerts: i64,
+    pub total_alerts: i64,
+    /// `(variant_name, count)` sorted by count, most frequent first.
+    pub threat_mix: Vec<(String, i64)>,
+    pub waves: i64,
+    /// Sum of wave durations (first alert → last alert / all-clear).
+    pub total_duration_secs: i64,
+}
+
+/// Summarize alerts in `[from_ts, to_ts)`.
+pub fn summarize(db: &SharedArchive, from_ts: i64, to_ts: i64) -> Result<AlertSummary> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "SELECT wave_id, timestamp, threats, proximity FROM alerts
+         WHERE timestamp >= ? AND timestamp < ?
+         ORDER BY timestamp",
+    )?;
+    stmt.bind((1, from_ts))?;
+    stmt.bind((2, to_ts))?;
+
+    let mut summary = AlertSummary::default();
+    let mut waves: Vec<(i64, i64, i64)> = Vec::new(); // (wave_id, first_ts, last_ts)
+    while let State::Row = stmt.next()? {
+        let wave_id = stmt.read::<i64, _>(0)?;
+        let ts = stmt.read::<i64, _>(1)?;
+        let threats = stmt.read::<String, _>(2)?;
+        let proximity = stmt.read::<String, _>(3)?;
+
+        summary.total_alerts += 1;
+        if proximity != "None" {
+            summary.area_alerts += 1;
+        }
+        for t in threats
+            .split(',')
+            .filter(|t| !t.is_empty() && *t != "AllClear")
+        {
+            match summary.threat_mix.iter_mut().find(|(name, _)| name == t) {
+                Some((_, n)) => *n += 1,
+                None => summary.threat_mix.push((t.to_string(), 1)),
+            }
+        }
+        match waves.iter_mut().find(|(id, _, _)| *id == wave_id) {
+            Some((_, _, last)) => *last = ts,
+            None => waves.push((wave_id, ts, ts)),
+        }
+    }
+
+    summary
+        .threat_mix
+        .sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    summary.waves = waves.len() as i64;
+    summary.total_duration_secs = waves.iter().map(|(_, first, last)| last - first).sum();
+    Ok(summary)
+}
+
+// ---------------------------------------------------------------------------
+// Search
+// ---------------------------------------------------------------------------
+
+/// Full-text query over the archive.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    /// Free-text terms, e.g. `циркон київ`.  Every term must match; each is
+    /// treated as a prefix so that stems catch declensions.
+    pub terms: String,
+    /// Inclusive lower bound (unix seconds).
+    pub from_ts: Option<i64>,
+    /// Exclusive upper bound (unix seconds).
+    pub to_ts: Option<i64>,
+    /// Substring of the channel title (SQLite `LIKE`, ASCII case-insensitive).
+    pub channel: Option<String>,
+    pub limit: usize,
+}
+
+/// Turn free-text terms into an FTS5 MATCH expression: `"циркон"* "київ"*`.
+fn fts_expression(terms: &str) -> String {
+    terms
+        .split_whitespace()
+        .map(|t| format!("\"{}\"*", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Run a search, newest messages first.
+pub fn search(db: &SharedArchive, query: &SearchQuery) -> Result<Vec<ArchivedMessage>> {
+    let expr = fts_expression(&query.terms);
+    let mut sql =
+        String::from("SELECT m.timestamp, m.channel_id, m.channel_title, m.text FROM messages m");
+    let mut clauses: Vec<&str> = Vec::new();
+    if !expr.is_empty() {
+        sql.push_str(" JOIN messages_fts f ON f.rowid = m.id");
+        clauses.push("messages_fts MATCH :expr");
+    }
+    if query.from_ts.is_some() {
+        clauses.push("m.timestamp >= :from_ts");
+    }
+    if query.to_ts.is_some() {
+        clauses.push("m.timestamp < :to_ts");
+    }
+    if query.channel.is_some() {
+        clauses.push("m.channel_title LIKE :channel");
+    }
+    if !clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY m.timestamp DESC LIMIT :limit");
+
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(&sql)?;
+    if !expr.is_empty() {
+        stmt.bind((":expr", expr.as_str()))?;
+    }
+    if let Some(ts) = query.from_ts {
+        stmt.bind((":from_ts", ts))?;
+    }
+    if let Some(ts) = query.to_ts {
+        stmt.bind((":to_ts", ts))?;
+    }
+    if let Some(channel) = &query.channel {
+        stmt.bind((":channel", format!("%{channel}%").as_str()))?;
+    }
+    stmt.bind((":limit", query.limit as i64))?;
+
+    let mut out = Vec::new();
+    while let State::Row = stmt.next()? {
+        out.push(ArchivedMessage {
+            timestamp: stmt.read::<i64, _>(0)?,
+            channel_id: stmt.read::<i64, _>(1)?,
+            channel_title: stmt.read::<String, _>(2)?,
+            text: stmt.read::<String, _>(3)?,
+        });
+    }
+    Ok(out)
+}
+
+/// Messages in `[from_ts, to_ts)` that did not become alerts, newest first.
+pub fn unforwarded_messages(
+    db: &SharedArchive,
+    from_ts: i64,
+    to_ts: i64,
+    limit: usize,
+) -> Result<Vec<ArchivedMessage>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "SELECT m.timestamp, m.channel_id, m.channel_title, m.text
+         FROM messages m
+         WHERE m.timestamp >= :from_ts AND m.timestamp < :to_ts
+           AND NOT EXISTS (
+               SELECT 1 FROM alerts a
+               WHERE a.channel_id = m.channel_id AND a.timestamp = m.timestamp
+           )
+         ORDER BY m.timestamp DESC
+         LIMIT :limit",
+    )?;
+    stmt.bind((":from_ts", from_ts))?;
+    stmt.bind((":to_ts", to_ts))?;
+    stmt.bind((":limit", limit as i64))?;
+
+    let mut out = Vec::new();
+    while let State::Row = stmt.next()? {
+        out.push(ArchivedMessage {
+            timestamp: stmt.read::<i64, _>(0)?,
+            channel_id: stmt.read::<i64, _>(1)?,
+            channel_title: stmt.read::<String, _>(2)?,
+            text: stmt.read::<String, _>(3)?,
+        });
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Channel analytics
+// ---------------------------------------------------------------------------
+
+/// Script mix of one channel, overall and per ISO week (`2025-W03`).
+#[derive(Debug, Clone)]
+pub struct ChannelScripts {
+    pub channel_id: i64,
+    pub channel_title: String,
+    pub total: ScriptCounts,
+    pub weeks: BTreeMap<String, ScriptCounts>,
+}
+
+/// Language/script statistics per channel over `[from_ts, to_ts)`, busiest
+/// channels first.  `channel` is a title substring as in [`SearchQuery`].
+pub fn script_stats(
+    db: &SharedArchive,
+    from_ts: Option<i64>,
+    to_ts: Option<i64>,
+    channel: Option<&str>,
+) -> Result<Vec<ChannelScripts>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "SELECT timestamp, channel_id, channel_title, text FROM messages
+         WHERE (:from_ts IS NULL OR timestamp >= :from_ts)
+           AND (:to_ts IS NULL OR timestamp < :to_ts)
+           AND (:channel IS NULL OR channel_title LIKE :channel)
+         ORDER BY timestamp",
+    )?;
+    stmt.bind((":from_ts", from_ts))?;
+    stmt.bind((":to_ts", to_ts))?;
+    stmt.bind((":channel", channel.map(|c| format!("%{c}%")).as_deref()))?;
+
+    let mut by_channel: HashMap<i64, ChannelScripts> = HashMap::new();
+    while let State::Row = stmt.next()? {
+        let timestamp = stmt.read::<i64, _>(0)?;
+        let channel_id = stmt.read::<i64, _>(1)?;
+        let script = detect_script(&stmt.read::<String, _>(3)?);
+        let week = DateTime::from_timestamp(timestamp, 0)
+            .map(|dt| dt.format("%G-W%V").to_string())
+            .unwrap_or_default();
+        let entry = match by_channel.entry(channel_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(ChannelScripts {
+                channel_id,
+                channel_title: stmt.read::<String, _>(2)?,
+                total: ScriptCounts::default(),
+                weeks: BTreeMap::new(),
+            }),
+        };
+        entry.total.add(script);
+        entry.weeks.entry(week).or_default().add(script);
+    }
+    let mut out: Vec<ChannelScripts> = by_channel.into_values().collect();
+    out.sort_by(|a, b| {
+        b.total
+            .total()
+            .cmp(&a.total.total())
+            .then(a.channel_title.cmp(&b.channel_title))
+    });
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::AlertType;
+    use crate::filter::threat_kind::ThreatKind;
+
+    fn message(timestamp: i64, text: &str) -> ArchivedMessage {
+        ArchivedMessage {
+            timestamp,
+            channel_id: 1,
+            channel_title: "ch".into(),
+            text: text.into(),
+        }
+    }
+
+    fn alert_info(threats: &[ThreatKind], proximity: Proximity) -> AlertInfo {
+        AlertInfo {
+            alert_type: AlertType::New,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn damage_reports_link_to_the_wave_before_them() {
+        let db = open_archive(":memory:").unwrap();
+        let cfg = CorrelationCfg {
+            wave_gap_secs: 3600,
+            damage_window_secs: 10800,
+        };
+        let alert = |ts, threats: &[ThreatKind], proximity| {
+            record_alert(
+                &db,
+                &message(ts, "alert"),
+                &alert_info(threats, proximity),
+                &cfg,
+            )
+            .unwrap()
+        };
+        let damage = |ts, proximity| {
+            record_damage_report(&db, &message(ts, &format!("damage {ts}")), proximity, &cfg)
+                .unwrap()
+        };
+
+        assert_eq!(damage(500, Proximity::City), None, "no alert yet");
+        let night = alert(1000, &[ThreatKind::Shahed], Proximity::City);
+        assert_eq!(
+            alert(1500, &[ThreatKind::Ballistic], Proximity::Oblast),
+            night
+        );
+        // Same-proximity alert preferred over the newer oblast one.
+        assert_eq!(damage(2000, Proximity::City), Some(night));
+        assert_eq!(alert(2500, &[ThreatKind::AllClear], Proximity::None), night);
+        // The all-clear itself is never the cause.
+        assert_eq!(damage(2600, Proximity::None), Some(night));
+
+        let quiet = alert(50_000, &[ThreatKind::Shahed], Proximity::City);
+        assert_ne!(quiet, night);
+        assert_eq!(
+            damage(50_000 + 10_801, Proximity::City),
+            None,
+            "window over"
+        );
+
+        let strikes = confirmed_strikes(&db, None, None, 10).unwrap();
+        assert_eq!(strikes.len(), 1);
+        let strike = &strikes[0];
+        assert_eq!((strike.wave_id, strike.wave_start), (night, 1000));
+        assert_eq!(strike.alert_count, 3);
+        let mut threats: Vec<&str> = strike.threats.split(',').collect();
+        threats.sort();
+        assert_eq!(threats, ["AllClear", "Ballistic", "Shahed"]);
+        let reports: Vec<&str> = strike.reports.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(reports, ["damage 2000", "damage 2600"]);
+
+        assert!(
+            confirmed_strikes(&db, Some(1001), None, 10)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            confirmed_strikes(&db, None, Some(1000), 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn archive_compaction_folds_duplicate_texts_and_keeps_sources() {
+        let db = open_archive(":memory:").unwrap();
+        let msg = |ts, channel_id, title: &str, text: &str| ArchivedMessage {
+            timestamp: ts,
+            channel_id,
+            channel_title: title.into(),
+            text: text.into(),
+        };
+        let original = msg(100, 1, "first", "шахеди на київ");
+        for m in [
+            original.clone(),
+            msg(160, 2, "copy", "шахеди на київ"),
+            msg(170, 3, "late copy", "шахеди на київ"),
+            msg(200, 1, "first", "відбій"),
+            msg(5000, 4, "recent", "шахеди на київ"),
+        ] {
+            archive_message(&db, &m).unwrap();
+        }
+
+        let stats = compact(&db, 1000).unwrap();
+        assert_eq!(stats.duplicates, 2);
+        assert_eq!(stats.messages, 3, "newer copy kept until it ages");
+        let sources: Vec<String> = other_sources(&db, &original)
+            .unwrap()
+            .into_iter()
+            .map(|(_, title)| title)
+            .collect();
+        assert_eq!(sources, ["copy", "late copy"]);
+
+        // The rebuilt index still finds the kept copies.
+        let query = SearchQuery {
+            terms: "шахед".into(),
+            from_ts: None,
+            to_ts: None,
+            channel: None,
+            limit: 10,
+        };
+        assert_eq!(search(&db, &query).unwrap().len(), 2);
+
+        // Running again is a no-op; retention drops attributions with their message.
+        assert_eq!(compact(&db, 1000).unwrap().duplicates, 0);
+        prune_before(&db, 150).unwrap();
+        assert!(other_sources(&db, &original).unwrap().is_empty());
+    }
+}
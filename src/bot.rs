@@ -1,14 +1,26 @@
-//! Bot management: subscriber store (SQLite), long-poll loop, broadcast helper.
+//! Bot management: subscriber store (SQLite), long-poll loop, broadcast helper,
+//! pinned status message maintenance.
 
-use anyhow::{Result, anyhow};
+use crate::errors::{self, BotApiError, ErrorClass};
+use crate::feedback::{self, FeedbackCfg, UserReport};
+use crate::filter::ReviewCandidate;
+use crate::filter::gazetteer::{self, GeoMatch};
+use crate::filter::render::{self, DeliveryFormat};
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::threat_level::{SharedStatus, ThreatLevel};
+use crate::metrics::{self, SharedMetrics};
+use crate::reminders::{self, Reminder, SharedReminders};
+use crate::review::{self, Decision};
+use anyhow::Result;
 use reqwest::Client as HttpClient;
 use serde::{Deserialize, Serialize};
 use sqlite::State;
 use std::{
-    sync::{Arc, Mutex},
-    time::Duration,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, OnceLock, RwLock},
+    time::{Duration, Instant},
 };
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 // ---------------------------------------------------------------------------
 // Shared database handle
@@ -23,6 +35,77 @@ pub fn open_db(path: &str) -> Result<SharedDb> {
         "CREATE TABLE IF NOT EXISTS subscribers (
             chat_id  INTEGER PRIMARY KEY,
             added_at TEXT    NOT NULL DEFAULT (datetime('now'))
+         );
+         CREATE TABLE IF NOT EXISTS morning_summary (
+            chat_id  INTEGER PRIMARY KEY,
+            added_at TEXT    NOT NULL DEFAULT (datetime('now'))
+         );
+         CREATE TABLE IF NOT EXISTS recap_subscribers (
+            chat_id  INTEGER PRIMARY KEY,
+            added_at TEXT    NOT NULL DEFAULT (datetime('now'))
+         );
+         CREATE TABLE IF NOT EXISTS voice_subscribers (
+            chat_id  INTEGER PRIMARY KEY,
+            added_at TEXT    NOT NULL DEFAULT (datetime('now'))
+         );
+         CREATE TABLE IF NOT EXISTS chat_locations (
+            chat_id    INTEGER PRIMARY KEY,
+            lat        REAL    NOT NULL,
+            lon        REAL    NOT NULL,
+            oblast     TEXT    NOT NULL,
+            raion      TEXT,
+            updated_at TEXT    NOT NULL DEFAULT (datetime('now'))
+         );
+         CREATE TABLE IF NOT EXISTS status_pins (
+            chat_id    INTEGER PRIMARY KEY,
+            message_id INTEGER NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS delivery_format (
+            chat_id     INTEGER PRIMARY KEY,
+            compact     INTEGER NOT NULL DEFAULT 0,
+            show_text   INTEGER NOT NULL DEFAULT 1,
+            show_source INTEGER NOT NULL DEFAULT 1
+         );
+         CREATE TABLE IF NOT EXISTS bot_events (
+            chat_id INTEGER NOT NULL,
+            event   TEXT    NOT NULL,
+            at      INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+         );
+         CREATE INDEX IF NOT EXISTS bot_events_at ON bot_events (at);
+         CREATE TABLE IF NOT EXISTS forum_topics (
+            chat_id   INTEGER NOT NULL,
+            family    TEXT    NOT NULL,
+            thread_id INTEGER NOT NULL,
+            PRIMARY KEY (chat_id, family)
+         );
+         CREATE TABLE IF NOT EXISTS banned_chats (
+            chat_id INTEGER PRIMARY KEY,
+            reason  TEXT    NOT NULL,
+            until   INTEGER,
+            at      INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+         );
+         CREATE TABLE IF NOT EXISTS delivered_alerts (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            sent_at       INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            timestamp     INTEGER NOT NULL,
+            channel_id    INTEGER NOT NULL,
+            channel_title TEXT    NOT NULL,
+            text          TEXT    NOT NULL,
+            alert         TEXT    NOT NULL,
+            threats       TEXT    NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS alert_deliveries (
+            chat_id    INTEGER NOT NULL,
+            message_id INTEGER NOT NULL,
+            alert_id   INTEGER NOT NULL,
+            PRIMARY KEY (chat_id, message_id)
+         );
+         CREATE TABLE IF NOT EXISTS alert_reports (
+            alert_id    INTEGER NOT NULL,
+            chat_id     INTEGER NOT NULL,
+            comment     TEXT    NOT NULL DEFAULT '',
+            reported_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            PRIMARY KEY (alert_id, chat_id)
          );",
     )?;
     info!("Subscriber DB opened at {path}");
@@ -34,6 +117,10 @@ pub fn add_subscriber(db: &SharedDb, chat_id: i64) -> Result<()> {
     let mut stmt = db.prepare("INSERT OR IGNORE INTO subscribers (chat_id) VALUES (?)")?;
     stmt.bind((1, chat_id))?;
     stmt.next()?;
+    drop(stmt);
+    if db.change_count() > 0 {
+        insert_event(&db, chat_id, EVENT_SUBSCRIBE)?;
+    }
     Ok(())
 }
 
@@ -42,113 +129,1819 @@ pub fn remove_subscriber(db: &SharedDb, chat_id: i64) -> Result<()> {
     let mut stmt = db.prepare("DELETE FROM subscribers WHERE chat_id = ?")?;
     stmt.bind((1, chat_id))?;
     stmt.next()?;
+    drop(stmt);
+    if db.change_count() > 0 {
+        insert_event(&db, chat_id, EVENT_UNSUBSCRIBE)?;
+    }
+    Ok(())
+}
+
+/// Subscribers together with their rendering preferences.
+pub fn get_subscribers_with_format(db: &SharedDb) -> Result<Vec<(i64, DeliveryFormat)>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "SELECT s.chat_id, f.compact, f.show_text, f.show_source
+         FROM subscribers s LEFT JOIN delivery_format f ON f.chat_id = s.chat_id",
+    )?;
+    let mut out = Vec::new();
+    while let Ok(State::Row) = stmt.next() {
+        let chat_id = stmt.read::<i64, _>(0)?;
+        let format = match stmt.read::<Option<i64>, _>(1)? {
+            Some(compact) => DeliveryFormat {
+                compact: compact != 0,
+                show_text: stmt.read::<i64, _>(2)? != 0,
+                show_source: stmt.read::<i64, _>(3)? != 0,
+            },
+            None => DeliveryFormat::default(),
+        };
+        out.push((chat_id, format));
+    }
+    Ok(out)
+}
+
+pub fn get_delivery_format(db: &SharedDb, chat_id: i64) -> Result<DeliveryFormat> {
+    let db = db.lock().unwrap();
+    let mut stmt = db
+        .prepare("SELECT compact, show_text, show_source FROM delivery_format WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    if let State::Row = stmt.next()? {
+        return Ok(DeliveryFormat {
+            compact: stmt.read::<i64, _>(0)? != 0,
+            show_text: stmt.read::<i64, _>(1)? != 0,
+            show_source: stmt.read::<i64, _>(2)? != 0,
+        });
+    }
+    Ok(DeliveryFormat::default())
+}
+
+pub fn set_delivery_format(db: &SharedDb, chat_id: i64, format: DeliveryFormat) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "INSERT OR REPLACE INTO delivery_format (chat_id, compact, show_text, show_source)
+         VALUES (?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, format.compact as i64))?;
+    stmt.bind((3, format.show_text as i64))?;
+    stmt.bind((4, format.show_source as i64))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Apply `/format <args>` to `current`.  `None` for unrecognised arguments.
+fn parse_format_args(current: DeliveryFormat, args: &str) -> Option<DeliveryFormat> {
+    let mut format = current;
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let flag = |w: &str| match w {
+        "on" | "show" | "yes" => Some(true),
+        "off" | "hide" | "no" => Some(false),
+        _ => None,
+    };
+    match words.as_slice() {
+        [] => {}
+        ["compact"] => format.compact = true,
+        ["full"] => format.compact = false,
+        ["text", v] => format.show_text = flag(v)?,
+        ["source", v] => format.show_source = flag(v)?,
+        _ => return None,
+    }
+    Some(format)
+}
+
+/// Toggle the daily morning summary for `chat_id`.  Returns the new state.
+pub fn toggle_morning_summary(db: &SharedDb, chat_id: i64) -> Result<bool> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM morning_summary WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    drop(stmt);
+    if db.change_count() > 0 {
+        return Ok(false);
+    }
+    let mut stmt = db.prepare("INSERT INTO morning_summary (chat_id) VALUES (?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    Ok(true)
+}
+
+pub fn get_morning_summary_subscribers(db: &SharedDb) -> Result<Vec<i64>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT chat_id FROM morning_summary")?;
+    let mut ids = Vec::new();
+    while let Ok(State::Row) = stmt.next() {
+        ids.push(stmt.read::<i64, _>(0)?);
+    }
+    Ok(ids)
+}
+
+/// Toggle delivery of "📊 Підсумок" recap posts for `chat_id`.  Returns the
+/// new state.
+pub fn toggle_recaps(db: &SharedDb, chat_id: i64) -> Result<bool> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM recap_subscribers WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    drop(stmt);
+    if db.change_count() > 0 {
+        return Ok(false);
+    }
+    let mut stmt = db.prepare("INSERT INTO recap_subscribers (chat_id) VALUES (?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    Ok(true)
+}
+
+pub fn get_recap_subscribers(db: &SharedDb) -> Result<HashSet<i64>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT chat_id FROM recap_subscribers")?;
+    let mut ids = HashSet::new();
+    while let Ok(State::Row) = stmt.next() {
+        ids.insert(stmt.read::<i64, _>(0)?);
+    }
+    Ok(ids)
+}
+
+/// Toggle spoken versions of critical alerts for `chat_id`.  Returns the
+/// new state.
+pub fn toggle_voice(db: &SharedDb, chat_id: i64) -> Result<bool> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM voice_subscribers WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    drop(stmt);
+    if db.change_count() > 0 {
+        return Ok(false);
+    }
+    let mut stmt = db.prepare("INSERT INTO voice_subscribers (chat_id) VALUES (?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    Ok(true)
+}
+
+pub fn get_voice_subscribers(db: &SharedDb) -> Result<HashSet<i64>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT chat_id FROM voice_subscribers")?;
+    let mut ids = HashSet::new();
+    while let Ok(State::Row) = stmt.next() {
+        ids.insert(stmt.read::<i64, _>(0)?);
+    }
+    Ok(ids)
+}
+
+// ---------------------------------------------------------------------------
+// Subscriber mirror
+// ---------------------------------------------------------------------------
+
+/// Commands that change what [`Audience::load`] returns.
+const AUDIENCE_COMMANDS: &[&str] = &[
+    "/start_receive",
+    "/stop_receive",
+    "/recaps",
+    "/voice",
+    "/delete_me",
+    "/format",
+    "/topic",
+];
+
+/// Interval of [`run_subscriber_reconcile_loop`].
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Everything a broadcast reads from the subscriber DB.
+#[derive(Debug, Clone, Default)]
+pub struct Audience {
+    pub subscribers: Vec<(i64, DeliveryFormat)>,
+    pub recap: HashSet<i64>,
+    pub voice: HashSet<i64>,
+    pub topics: HashMap<i64, HashMap<String, i64>>,
+}
+
+impl Audience {
+    pub fn load(db: &SharedDb) -> Result<Self> {
+        Ok(Self {
+            subscribers: get_subscribers_with_format(db)?,
+            recap: get_recap_subscribers(db)?,
+            voice: get_voice_subscribers(db)?,
+            topics: get_forum_topics(db)?,
+        })
+    }
+}
+
+/// In-memory copy of the [`Audience`]: broadcasts read only the copy, so a
+/// momentarily locked or damaged DB does not cost an alert.  The copy is
+/// refreshed after bot commands and reconciled with the DB periodically
+/// by [`run_subscriber_reconcile_loop`].
+#[derive(Clone)]
+pub struct SubscriberMirror {
+    db: SharedDb,
+    audience: Arc<RwLock<Option<Arc<Audience>>>>,
+}
+
+impl SubscriberMirror {
+    pub fn new(db: SharedDb) -> Self {
+        Self {
+            db,
+            audience: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn db(&self) -> &SharedDb {
+        &self.db
+    }
+
+    /// Reload the copy from the DB; on error the previous copy is kept.
+    pub fn refresh(&self) -> Result<Arc<Audience>> {
+        let audience = Arc::new(Audience::load(&self.db)?);
+        *self.audience.write().unwrap() = Some(audience.clone());
+        Ok(audience)
+    }
+
+    /// The mirrored audience.  Only the first call, before any copy
+    /// exists, reads the DB.
+    pub fn audience(&self) -> Result<Arc<Audience>> {
+        let cached = self.audience.read().unwrap().clone();
+        match cached {
+            Some(audience) => Ok(audience),
+            None => self.refresh(),
+        }
+    }
+}
+
+/// Runs forever: reloads the mirror every minute, so changes made outside
+/// the bot loop (retention, `/delete_me` from another process) reach it.
+pub async fn run_subscriber_reconcile_loop(mirror: SubscriberMirror) {
+    let mut failing = false;
+    loop {
+        tokio::time::sleep(RECONCILE_INTERVAL).await;
+        match mirror.refresh() {
+            Ok(audience) if failing => {
+                info!(
+                    "Subscriber DB readable again; mirror has {} subscriber(s)",
+                    audience.subscribers.len()
+                );
+                failing = false;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Subscriber DB unreadable, broadcasting from the cached list: {e}");
+                failing = true;
+            }
+        }
+    }
+}
+
+/// Forum-topic families alerts are sorted into (`/topic <family>`).  `*`
+/// catches alerts whose family has no topic of its own.
+pub const TOPIC_FAMILIES: &[&str] = &["ballistic", "missile", "drone", "aviation", "allclear", "*"];
+
+/// Topic family of an alert whose primary threat is `kind`.
+pub fn topic_family(kind: Option<ThreatKind>) -> &'static str {
+    match kind {
+        Some(ThreatKind::Ballistic | ThreatKind::Hypersonic | ThreatKind::BallisticWatch) => {
+            "ballistic"
+        }
+        Some(ThreatKind::CruiseMissile | ThreatKind::Missile | ThreatKind::NavalThreat) => {
+            "missile"
+        }
+        Some(ThreatKind::Shahed | ThreatKind::ReconDrone) => "drone",
+        Some(ThreatKind::Aircraft | ThreatKind::GuidedBomb) => "aviation",
+        Some(ThreatKind::AllClear) => "allclear",
+        Some(ThreatKind::Artillery | ThreatKind::Explosion | ThreatKind::Other) | None => "*",
+    }
+}
+
+/// Route `family` alerts of a forum supergroup to topic `thread_id`.
+pub fn set_forum_topic(db: &SharedDb, chat_id: i64, family: &str, thread_id: i64) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "INSERT OR REPLACE INTO forum_topics (chat_id, family, thread_id) VALUES (?, ?, ?)",
+    )?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, family))?;
+    stmt.bind((3, thread_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Returns `true` when a topic was configured for `family`.
+pub fn remove_forum_topic(db: &SharedDb, chat_id: i64, family: &str) -> Result<bool> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM forum_topics WHERE chat_id = ? AND family = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, family))?;
+    stmt.next()?;
+    Ok(db.change_count() > 0)
+}
+
+/// Topic ids by family, by chat.
+pub fn get_forum_topics(db: &SharedDb) -> Result<HashMap<i64, HashMap<String, i64>>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT chat_id, family, thread_id FROM forum_topics")?;
+    let mut out: HashMap<i64, HashMap<String, i64>> = HashMap::new();
+    while let State::Row = stmt.next()? {
+        out.entry(stmt.read::<i64, _>(0)?)
+            .or_default()
+            .insert(stmt.read::<String, _>(1)?, stmt.read::<i64, _>(2)?);
+    }
+    Ok(out)
+}
+
+/// `/topic` reply: the configured topics of one chat.
+fn describe_forum_topics(topics: Option<&HashMap<String, i64>>) -> String {
+    let Some(topics) = topics.filter(|t| !t.is_empty()) else {
+        return "🧵 No topics configured. In a forum group, send /topic <family> inside \
+                the topic that should receive it.\nFamilies: ballistic, missile, drone, \
+                aviation, allclear, * (everything else)."
+            .to_string();
+    };
+    let mut lines = vec!["🧵 Topics:".to_string()];
+    for family in TOPIC_FAMILIES {
+        if let Some(thread_id) = topics.get(*family) {
+            lines.push(format!("{family} → topic {thread_id}"));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Store the confirmed location of `chat_id`.
+pub fn set_chat_location(
+    db: &SharedDb,
+    chat_id: i64,
+    lat: f64,
+    lon: f64,
+    place: &GeoMatch,
+) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare(
+        "INSERT OR REPLACE INTO chat_locations (chat_id, lat, lon, oblast, raion) \
+         VALUES (?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, lat))?;
+    stmt.bind((3, lon))?;
+    stmt.bind((4, place.oblast))?;
+    stmt.bind((5, place.raion))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Returns `true` when a location was stored.
+pub fn remove_chat_location(db: &SharedDb, chat_id: i64) -> Result<bool> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM chat_locations WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    Ok(db.change_count() > 0)
+}
+
+/// Stored `(lat, lon)` of `chat_id`.
+pub fn get_chat_location(db: &SharedDb, chat_id: i64) -> Result<Option<(f64, f64)>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT lat, lon FROM chat_locations WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    if let State::Row = stmt.next()? {
+        return Ok(Some((stmt.read::<f64, _>(0)?, stmt.read::<f64, _>(1)?)));
+    }
+    Ok(None)
+}
+
+fn get_status_pin(db: &SharedDb, chat_id: i64) -> Result<Option<i64>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("SELECT message_id FROM status_pins WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    if let State::Row = stmt.next()? {
+        return Ok(Some(stmt.read::<i64, _>(0)?));
+    }
+    Ok(None)
+}
+
+fn set_status_pin(db: &SharedDb, chat_id: i64, message_id: i64) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt =
+        db.prepare("INSERT OR REPLACE INTO status_pins (chat_id, message_id) VALUES (?, ?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, message_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Operator stats
+// ---------------------------------------------------------------------------
+
+const EVENT_SUBSCRIBE: &str = "subscribe";
+const EVENT_UNSUBSCRIBE: &str = "unsubscribe";
+const EVENT_BROADCAST: &str = "broadcast";
+
+/// Commands counted in `/bot_stats`.
+const TRACKED_COMMANDS: &[&str] = &[
+    "/start",
+    "/start_receive",
+    "/stop_receive",
+    "/morning_summary",
+    "/recaps",
+    "/voice",
+    "/now",
+    "/report",
+    "/format",
+    "/topic",
+    "/location",
+    "/bot_stats",
+    "/metrics_summary",
+    "/review",
+    "/delete_me",
+];
+
+fn insert_event(db: &sqlite::Connection, chat_id: i64, event: &str) -> Result<()> {
+    let mut stmt = db.prepare("INSERT INTO bot_events (chat_id, event) VALUES (?, ?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, event))?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub fn record_event(db: &SharedDb, chat_id: i64, event: &str) -> Result<()> {
+    insert_event(&db.lock().unwrap(), chat_id, event)
+}
+
+/// Delete per-chat event records (subscriptions, command usage) older than
+/// `before_ts`.  Returns the number of rows removed.
+pub fn prune_events(db: &SharedDb, before_ts: i64) -> Result<usize> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM bot_events WHERE at < ?")?;
+    stmt.bind((1, before_ts))?;
+    stmt.next()?;
+    drop(stmt);
+    Ok(db.change_count())
+}
+
+/// Remove everything stored about `chat_id` (`/delete_me`).  Ban list entries
+/// are kept so a ban can't be lifted this way.
+pub fn delete_chat_data(db: &SharedDb, chat_id: i64) -> Result<()> {
+    let db = db.lock().unwrap();
+    db.execute("BEGIN")?;
+    let result = (|| -> Result<()> {
+        for table in [
+            "subscribers",
+            "morning_summary",
+            "recap_subscribers",
+            "voice_subscribers",
+            "status_pins",
+            "delivery_format",
+            "forum_topics",
+            "chat_locations",
+            "bot_events",
+            "alert_deliveries",
+            "alert_reports",
+        ] {
+            let mut stmt = db.prepare(format!("DELETE FROM {table} WHERE chat_id = ?"))?;
+            stmt.bind((1, chat_id))?;
+            stmt.next()?;
+        }
+        Ok(())
+    })();
+    db.execute(if result.is_ok() { "COMMIT" } else { "ROLLBACK" })?;
+    result
+}
+
+/// How `/bot_stats` defines a noisy night.
+#[derive(Debug, Clone)]
+pub struct BotStatsCfg {
+    pub tz_offset_minutes: i32,
+    /// Broadcasts between 22:00 and 06:00 local that make a night noisy.
+    pub noisy_night_alerts: i64,
+}
+
+impl BotStatsCfg {
+    /// | Env var                       | Default | Description                       |
+    /// |-------------------------------|---------|-----------------------------------|
+    /// | `BOT_STATS_TZ_OFFSET_MINUTES` | `0`     | Local timezone for night windows  |
+    /// | `NOISY_NIGHT_ALERTS`          | `5`     | Night broadcasts that count as noisy |
+    pub fn from_env() -> Self {
+        let tz_offset_minutes = std::env::var("BOT_STATS_TZ_OFFSET_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let noisy_night_alerts = std::env::var("NOISY_NIGHT_ALERTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &i64| *v > 0)
+            .unwrap_or(5);
+        Self {
+            tz_offset_minutes,
+            noisy_night_alerts,
+        }
+    }
+}
+
+/// Weekly operator stats reported by `/bot_stats`.
+#[derive(Debug, Default)]
+pub struct BotStats {
+    pub subscribers: i64,
+    pub subscribed: i64,
+    pub unsubscribed: i64,
+    pub broadcasts: i64,
+    pub noisy_nights: i64,
+    /// Unsubscribes during a noisy night or within 12h after it.
+    pub churn_after_noisy: i64,
+    pub commands: Vec<(String, i64)>,
+}
+
+const NIGHT_SHIFT_SECS: i64 = 2 * 3600; // 22:00 → 00:00
+const NIGHT_LEN_SECS: i64 = 8 * 3600; // 22:00–06:00
+const CHURN_WINDOW_SECS: i64 = 12 * 3600;
+
+pub fn weekly_stats(db: &SharedDb, now: i64, cfg: &BotStatsCfg) -> Result<BotStats> {
+    let since = now - 7 * 24 * 3600;
+    let db = db.lock().unwrap();
+    let mut stats = BotStats::default();
+
+    let mut stmt = db.prepare("SELECT COUNT(*) FROM subscribers")?;
+    if let State::Row = stmt.next()? {
+        stats.subscribers = stmt.read::<i64, _>(0)?;
+    }
+
+    let mut unsubscribes = Vec::new();
+    let mut broadcasts = Vec::new();
+    let mut commands: Vec<(String, i64)> = Vec::new();
+    let mut stmt = db.prepare("SELECT event, at FROM bot_events WHERE at >= ? ORDER BY at")?;
+    stmt.bind((1, since))?;
+    while let Ok(State::Row) = stmt.next() {
+        let event = stmt.read::<String, _>(0)?;
+        let at = stmt.read::<i64, _>(1)?;
+        match event.as_str() {
+            EVENT_SUBSCRIBE => stats.subscribed += 1,
+            EVENT_UNSUBSCRIBE => unsubscribes.push(at),
+            EVENT_BROADCAST => broadcasts.push(at),
+            cmd => match commands.iter_mut().find(|(c, _)| c == cmd) {
+                Some((_, n)) => *n += 1,
+                None => commands.push((event, 1)),
+            },
+        }
+    }
+    stats.unsubscribed = unsubscribes.len() as i64;
+    stats.broadcasts = broadcasts.len() as i64;
+    commands.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    stats.commands = commands;
+
+    // Bucket night-time broadcasts by night (shifted so a night is one "day").
+    let offset = cfg.tz_offset_minutes as i64 * 60;
+    let mut nights: Vec<(i64, i64)> = Vec::new();
+    for at in broadcasts {
+        let shifted = at + offset + NIGHT_SHIFT_SECS;
+        if shifted.rem_euclid(86400) >= NIGHT_LEN_SECS {
+            continue;
+        }
+        let night = shifted.div_euclid(86400);
+        match nights.iter_mut().find(|(n, _)| *n == night) {
+            Some((_, count)) => *count += 1,
+            None => nights.push((night, 1)),
+        }
+    }
+    for (night, count) in nights {
+        if count < cfg.noisy_night_alerts {
+            continue;
+        }
+        stats.noisy_nights += 1;
+        let start = night * 86400 - NIGHT_SHIFT_SECS - offset;
+        let end = start + NIGHT_LEN_SECS + CHURN_WINDOW_SECS;
+        stats.churn_after_noisy += unsubscribes
+            .iter()
+            .filter(|&&at| at >= start && at < end)
+            .count() as i64;
+    }
+    Ok(stats)
+}
+
+pub fn format_stats(stats: &BotStats) -> String {
+    let net = stats.subscribed - stats.unsubscribed;
+    let mut out = format!(
+        "📊 Bot stats (last 7 days)\n\
+         Subscribers: {} (net {net:+}: +{} / -{})\n\
+         Alerts broadcast: {}\n\
+         Noisy nights: {}, unsubscribes during/after them: {}",
+        stats.subscribers,
+        stats.subscribed,
+        stats.unsubscribed,
+        stats.broadcasts,
+        stats.noisy_nights,
+        stats.churn_after_noisy,
+    );
+    if !stats.commands.is_empty() {
+        out.push_str("\nCommands:");
+        for (cmd, n) in &stats.commands {
+            out.push_str(&format!("\n  {cmd} ×{n}"));
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Abuse protection
+// ---------------------------------------------------------------------------
+
+/// Ban `chat_id` until the unix time `until` (`None` = permanently).
+pub fn ban_chat(db: &SharedDb, chat_id: i64, until: Option<i64>, reason: &str) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db
+        .prepare("INSERT OR REPLACE INTO banned_chats (chat_id, reason, until) VALUES (?, ?, ?)")?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, reason))?;
+    stmt.bind((3, until))?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub fn unban_chat(db: &SharedDb, chat_id: i64) -> Result<()> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.prepare("DELETE FROM banned_chats WHERE chat_id = ?")?;
+    stmt.bind((1, chat_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Active bans: chat id → expiry (unix seconds, `None` = permanent).
+pub fn load_bans(db: &SharedDb, now: i64) -> Result<HashMap<i64, Option<i64>>> {
+    let db = db.lock().unwrap();
+    let mut stmt =
+        db.prepare("SELECT chat_id, until FROM banned_chats WHERE until IS NULL OR until > ?")?;
+    stmt.bind((1, now))?;
+    let mut bans = HashMap::new();
+    while let Ok(State::Row) = stmt.next() {
+        bans.insert(stmt.read::<i64, _>(0)?, stmt.read::<Option<i64>, _>(1)?);
+    }
+    Ok(bans)
+}
+
+/// Per-chat command rate limits and automatic bans.
+#[derive(Debug, Clone)]
+pub struct GuardCfg {
+    /// Max messages per chat per minute.
+    pub per_minute: usize,
+    /// Rate-limit violations (each a throttled minute) before an auto-ban.
+    pub strikes_to_ban: u32,
+    pub ban_secs: i64,
+}
+
+impl GuardCfg {
+    /// | Env var                | Default | Description                            |
+    /// |------------------------|---------|----------------------------------------|
+    /// | `BOT_RATE_LIMIT`       | `10`    | Messages per chat per minute           |
+    /// | `BOT_AUTO_BAN_STRIKES` | `3`     | Throttled minutes before an auto-ban   |
+    /// | `BOT_AUTO_BAN_SECS`    | `86400` | Auto-ban duration                      |
+    pub fn from_env() -> Self {
+        let per_minute = std::env::var("BOT_RATE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &usize| *v > 0)
+            .unwrap_or(10);
+        let strikes_to_ban = std::env::var("BOT_AUTO_BAN_STRIKES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        let ban_secs = std::env::var("BOT_AUTO_BAN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(86400);
+        Self {
+            per_minute,
+            strikes_to_ban,
+            ban_secs,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GuardVerdict {
+    Allow,
+    /// Over the limit; `notify` is set for the first dropped message of
+    /// each throttled minute.
+    Throttled {
+        notify: bool,
+    },
+    /// Just got auto-banned until the given unix time.
+    Banned {
+        until: i64,
+    },
+    /// Already banned: ignore silently.
+    Ignored,
+}
+
+/// Tracks recent messages per chat and decides what the poll loop handles.
+pub struct CommandGuard {
+    cfg: GuardCfg,
+    recent: HashMap<i64, VecDeque<Instant>>,
+    /// Strike count and when the last one was counted.
+    strikes: HashMap<i64, (u32, Instant)>,
+    bans: HashMap<i64, Option<i64>>,
+}
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+impl CommandGuard {
+    pub fn new(cfg: GuardCfg, bans: HashMap<i64, Option<i64>>) -> Self {
+        Self {
+            cfg,
+            recent: HashMap::new(),
+            strikes: HashMap::new(),
+            bans,
+        }
+    }
+
+    pub fn ban(&mut self, chat_id: i64, until: Option<i64>) {
+        self.bans.insert(chat_id, until);
+    }
+
+    pub fn unban(&mut self, chat_id: i64) {
+        self.bans.remove(&chat_id);
+        self.strikes.remove(&chat_id);
+    }
+
+    /// `now` is unix seconds (for ban expiry).
+    pub fn check(&mut self, chat_id: i64, now: i64) -> GuardVerdict {
+        self.check_at(chat_id, now, Instant::now())
+    }
+
+    /// [`check`](Self::check) with the rate window measured at `at`.
+    fn check_at(&mut self, chat_id: i64, now: i64, at: Instant) -> GuardVerdict {
+        if let Some(&until) = self.bans.get(&chat_id) {
+            if until.is_none_or(|t| t > now) {
+                return GuardVerdict::Ignored;
+            }
+            self.unban(chat_id);
+        }
+
+        if self.recent.len() > 10_000 {
+            self.recent.retain(|_, q| {
+                q.back()
+                    .is_some_and(|t| at.duration_since(*t) < RATE_WINDOW)
+            });
+        }
+        let queue = self.recent.entry(chat_id).or_default();
+        while queue
+            .front()
+            .is_some_and(|t| at.duration_since(*t) >= RATE_WINDOW)
+        {
+            queue.pop_front();
+        }
+        if queue.len() < self.cfg.per_minute {
+            queue.push_back(at);
+            return GuardVerdict::Allow;
+        }
+        queue.push_back(at);
+        // One strike per throttled minute, however long the spam goes on.
+        let strikes = match self.strikes.get_mut(&chat_id) {
+            Some((_, last)) if at.duration_since(*last) < RATE_WINDOW => {
+                return GuardVerdict::Throttled { notify: false };
+            }
+            Some((count, last)) => {
+                *last = at;
+                *count += 1;
+                *count
+            }
+            None => {
+                self.strikes.insert(chat_id, (1, at));
+                1
+            }
+        };
+        if self.cfg.strikes_to_ban > 0 && strikes >= self.cfg.strikes_to_ban {
+            let until = now + self.cfg.ban_secs;
+            self.ban(chat_id, Some(until));
+            self.recent.remove(&chat_id);
+            return GuardVerdict::Banned { until };
+        }
+        GuardVerdict::Throttled { notify: true }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Bot API types (getUpdates)
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct GetUpdatesResponse {
+    ok: bool,
+    result: Vec<TgUpdate>,
+}
+
+#[derive(Deserialize)]
+struct TgUpdate {
+    update_id: i64,
+    message: Option<TgMessage>,
+    callback_query: Option<TgCallbackQuery>,
+}
+
+#[derive(Deserialize)]
+struct TgMessage {
+    #[serde(default)]
+    message_id: i64,
+    chat: TgChat,
+    text: Option<String>,
+    /// Forum topic the message was posted in.
+    #[serde(default)]
+    message_thread_id: Option<i64>,
+    /// Shared location pin.
+    #[serde(default)]
+    location: Option<TgLocation>,
+    /// Message this one replies to (`/report`).
+    #[serde(default)]
+    reply_to_message: Option<Box<TgMessage>>,
+}
+
+#[derive(Deserialize)]
+struct TgLocation {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct TgCallbackQuery {
+    id: String,
+    message: Option<TgMessage>,
+    data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TgChat {
+    id: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Sending helpers
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct SendMessagePayload<'a> {
+    chat_id: i64,
+    text: &'a str,
+    disable_web_page_preview: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_parameters: Option<ReplyParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<InlineKeyboardMarkup>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disable_notification: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct InlineKeyboardMarkup {
+    inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+#[derive(Serialize)]
+struct InlineKeyboardButton {
+    text: &'static str,
+    callback_data: String,
+}
+
+#[derive(Serialize)]
+struct AnswerCallbackPayload<'a> {
+    callback_query_id: &'a str,
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+struct ReplyParameters {
+    message_id: i64,
+    allow_sending_without_reply: bool,
+}
+
+/// Send a single message to one chat via the Bot API.
+pub async fn send_message(
+    http: &HttpClient,
+    bot_token: &str,
+    chat_id: i64,
+    text: &str,
+) -> Result<()> {
+    send_reply(http, bot_token, chat_id, text, None).await?;
     Ok(())
 }
 
-pub fn get_subscribers(db: &SharedDb) -> Result<Vec<i64>> {
-    let db = db.lock().unwrap();
-    let mut stmt = db.prepare("SELECT chat_id FROM subscribers")?;
-    let mut ids = Vec::new();
-    while let Ok(State::Row) = stmt.next() {
-        ids.push(stmt.read::<i64, _>(0)?);
+/// Send a message, optionally as a reply to `reply_to` (still sent when that
+/// message was deleted).  Returns the new message id.
+pub async fn send_reply(
+    http: &HttpClient,
+    bot_token: &str,
+    chat_id: i64,
+    text: &str,
+    reply_to: Option<i64>,
+) -> Result<i64> {
+    send_reply_with(
+        http,
+        bot_token,
+        chat_id,
+        text,
+        reply_to,
+        SendOpts::default(),
+        None,
+    )
+    .await
+}
+
+/// How a message is delivered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SendOpts {
+    /// No notification sound.
+    pub silent: bool,
+    /// Attach the acknowledgment button (see [`crate::reminders`]).
+    pub ack_button: bool,
+}
+
+/// [`send_reply`] with `opts` and into forum topic `thread_id`.
+async fn send_reply_with(
+    http: &HttpClient,
+    bot_token: &str,
+    chat_id: i64,
+    text: &str,
+    reply_to: Option<i64>,
+    opts: SendOpts,
+    thread_id: Option<i64>,
+) -> Result<i64> {
+    let body = SendMessagePayload {
+        chat_id,
+        text,
+        disable_web_page_preview: true,
+        reply_parameters: reply_to.map(|message_id| ReplyParameters {
+            message_id,
+            allow_sending_without_reply: true,
+        }),
+        reply_markup: opts.ack_button.then(|| InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![InlineKeyboardButton {
+                text: reminders::ACK_LABEL,
+                callback_data: reminders::ACK_CALLBACK.to_string(),
+            }]],
+        }),
+        disable_notification: opts.silent,
+        message_thread_id: thread_id,
+    };
+    let sent: SentMessageResponse = post_bot_api(http, bot_token, "sendMessage", &body)
+        .await?
+        .json()
+        .await?;
+    Ok(sent.result.message_id)
+}
+
+/// Message ids of one broadcast, by chat id.
+pub type SentMessages = HashMap<i64, i64>;
+
+/// Send OGG/Opus `audio` as a voice message, as a reply to `reply_to`.
+async fn send_voice(
+    http: &HttpClient,
+    bot_token: &str,
+    chat_id: i64,
+    audio: &[u8],
+    reply_to: i64,
+) -> Result<()> {
+    let url = api_url(bot_token, "sendVoice");
+    let reply = serde_json::to_string(&ReplyParameters {
+        message_id: reply_to,
+        allow_sending_without_reply: true,
+    })?;
+    let voice = reqwest::multipart::Part::bytes(audio.to_vec())
+        .file_name("alert.ogg")
+        .mime_str("audio/ogg")?;
+    let form = reqwest::multipart::Form::new()
+        .text("chat_id", chat_id.to_string())
+        .text("reply_parameters", reply)
+        .part("voice", voice);
+    let resp = http.post(&url).multipart(form).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let raw = resp.text().await.unwrap_or_default();
+        return Err(BotApiError::from_response("sendVoice", status.as_u16(), &raw).into());
+    }
+    Ok(())
+}
+
+/// Send `audio` to the chats of `sent` that enabled `/voice`, each as a
+/// reply to the text alert it reads out, from the chat's shard bot (the
+/// primary one when the shard bot cannot reach the chat).
+pub async fn broadcast_voice(
+    http: &HttpClient,
+    bots: &BotShards,
+    subscribers: &SubscriberMirror,
+    audio: &[u8],
+    sent: &SentMessages,
+) -> Result<()> {
+    let audience = subscribers.audience()?;
+    let wanted = &audience.voice;
+    for (&chat_id, &message_id) in sent.iter().filter(|(c, _)| wanted.contains(c)) {
+        let shard = bots.shard_of(chat_id);
+        let mut result = errors::with_retry("sendVoice", || {
+            send_voice(http, &bots.tokens[shard], chat_id, audio, message_id)
+        })
+        .await;
+        let class = result.as_ref().err().map(errors::classify);
+        if shard > 0 && matches!(class, Some(ErrorClass::Recipient | ErrorClass::Auth)) {
+            if class == Some(ErrorClass::Recipient) {
+                bots.fall_back(chat_id);
+            }
+            result = errors::with_retry("sendVoice", || {
+                send_voice(http, bots.primary(), chat_id, audio, message_id)
+            })
+            .await;
+        }
+        if let Err(e) = result {
+            match errors::classify(&e) {
+                ErrorClass::Recipient => {
+                    info!("chat_id={chat_id} is unreachable (blocked or deleted): {e}")
+                }
+                ErrorClass::Auth => return Err(e),
+                class => warn!(
+                    "Failed to deliver voice to chat_id={chat_id} ({}): {e}",
+                    class.name()
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Virtual nodes per bot token on the hash ring.
+const SHARD_VNODES: u64 = 64;
+
+/// Bot tokens broadcasts are spread over, so delivery scales past the
+/// per-bot rate limit.  Each chat is mapped to one token by consistent
+/// hashing of its id (adding a token moves only the chats that land on
+/// it).  The first token is the primary bot: it runs the command loop,
+/// pins and admin notes, and takes over chats another bot cannot reach.
+#[derive(Debug, Clone)]
+pub struct BotShards {
+    tokens: Vec<String>,
+    /// `(point, token index)`, sorted by point.
+    ring: Vec<(u64, usize)>,
+    /// Chats their shard bot could not reach (a private chat that never
+    /// started it), served by the primary bot until restart.
+    fallback: Arc<Mutex<HashSet<i64>>>,
+}
+
+impl BotShards {
+    /// `primary` followed by `extra`, duplicates dropped.
+    pub fn new(primary: String, extra: Vec<String>) -> Self {
+        let mut tokens = vec![primary];
+        for token in extra {
+            if !token.is_empty() && !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        let mut ring: Vec<(u64, usize)> = (0..tokens.len())
+            .flat_map(|idx| {
+                // Hash the bot id (the part before ':'), not the secret.
+                let bot_id = tokens[idx].split(':').next().unwrap_or_default();
+                (0..SHARD_VNODES).map(move |v| (ring_point(&format!("{bot_id}#{v}")), idx))
+            })
+            .collect();
+        ring.sort_unstable();
+        Self {
+            tokens,
+            ring,
+            fallback: Arc::default(),
+        }
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.tokens[0]
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Token index serving `chat_id`.
+    pub fn shard_of(&self, chat_id: i64) -> usize {
+        if self.tokens.len() == 1 || self.fallback.lock().unwrap().contains(&chat_id) {
+            return 0;
+        }
+        let point = ring_point(&chat_id.to_string());
+        let pos = self.ring.partition_point(|(p, _)| *p < point);
+        self.ring[pos % self.ring.len()].1
+    }
+
+    /// Serve `chat_id` from the primary bot from now on.
+    fn fall_back(&self, chat_id: i64) {
+        self.fallback.lock().unwrap().insert(chat_id);
+    }
+}
+
+fn ring_point(key: &str) -> u64 {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(key.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"))
+}
+
+/// The chats of `sent` that got the acknowledgment button of a broadcast.
+pub fn reminders_for<'a>(
+    bots: &'a BotShards,
+    sent: &'a SentMessages,
+) -> impl Iterator<Item = (i64, i64)> + 'a {
+    sent.iter()
+        .filter(|(chat_id, _)| bots.shard_of(**chat_id) == 0)
+        .map(|(chat_id, message_id)| (*chat_id, *message_id))
+}
+
+/// Send `text` to one chat with retries.
+async fn deliver(
+    http: &HttpClient,
+    bot_token: &str,
+    chat_id: i64,
+    text: &str,
+    reply_to: Option<i64>,
+    opts: SendOpts,
+    thread_id: Option<i64>,
+) -> Result<i64> {
+    errors::with_retry("sendMessage", || {
+        send_reply_with(http, bot_token, chat_id, text, reply_to, opts, thread_id)
+    })
+    .await
+}
+
+/// Send a due reminder from the primary bot, as a reply to its alert and
+/// with the acknowledgment button.
+pub async fn send_reminder(http: &HttpClient, bot_token: &str, reminder: &Reminder) -> Result<i64> {
+    let opts = SendOpts {
+        silent: false,
+        ack_button: true,
+    };
+    deliver(
+        http,
+        bot_token,
+        reminder.chat_id,
+        &reminder.text,
+        Some(reminder.reply_to),
+        opts,
+        None,
+    )
+    .await
+}
+
+/// What one shard did with its chats.
+#[derive(Default)]
+struct ShardOutcome {
+    sent: SentMessages,
+    /// Chats the shard bot could not reach, to be retried by the primary.
+    handover: Vec<(i64, DeliveryFormat)>,
+    /// The shard's token was rejected.
+    auth_error: Option<anyhow::Error>,
+}
+
+/// Broadcast `text` to every active subscriber, rendered with each
+/// subscriber's `/format` preferences.  Recap posts only go to chats that
+/// enabled `/recaps`.  When `reply_to` holds an earlier
+/// broadcast (e.g. the alert this one escalates), each copy is sent as a
+/// reply to it.  Forum groups get the post in the `/topic` configured for
+/// `kind`'s family.
+///
+/// Subscribers are split across `bots`, whose shards deliver in parallel.
+/// Chats a secondary bot cannot reach (never started it, or its token was
+/// revoked) are handed over to the primary bot, which keeps the ones that
+/// never started their shard bot for later broadcasts.  Only the primary bot
+/// receives button presses, so only the chats of its shard get the
+/// acknowledgment button.  Returns the ids of the delivered messages.
+pub async fn broadcast(
+    http: &HttpClient,
+    bots: &BotShards,
+    recipients: &SubscriberMirror,
+    text: &str,
+    reply_to: Option<&SentMessages>,
+    opts: SendOpts,
+    kind: Option<ThreatKind>,
+) -> Result<SentMessages> {
+    let audience = recipients.audience()?;
+    let mut subscribers = audience.subscribers.clone();
+    if render::is_recap(text) {
+        subscribers.retain(|(chat_id, _)| audience.recap.contains(chat_id));
+    }
+    if subscribers.is_empty() {
+        info!("Broadcast skipped – no subscribers.");
+        return Ok(SentMessages::new());
+    }
+    info!("Broadcasting to {} subscriber(s).", subscribers.len());
+    if let Err(e) = record_event(recipients.db(), 0, EVENT_BROADCAST) {
+        warn!("Failed to record broadcast event: {e}");
+    }
+    let topics = &audience.topics;
+    let family = topic_family(kind);
+    let thread_of = |chat_id: i64| {
+        topics
+            .get(&chat_id)
+            .and_then(|t| t.get(family).or_else(|| t.get("*")))
+            .copied()
+    };
+
+    let send = |shard: usize, chat_id: i64, format: DeliveryFormat, ack_button: bool| {
+        let text = format.apply(text);
+        let reply = reply_to.and_then(|r| r.get(&chat_id).copied());
+        let opts = SendOpts { ack_button, ..opts };
+        let thread_id = thread_of(chat_id);
+        let bot_token = &bots.tokens[shard];
+        async move { deliver(http, bot_token, chat_id, &text, reply, opts, thread_id).await }
+    };
+    deliver_sharded(bots, subscribers, opts.ack_button, send).await
+}
+
+/// The delivery part of [`broadcast`]: `send(shard, chat_id, format,
+/// ack_button)` runs for each chat from its shard's bot, the shards in
+/// parallel, then for the chats handed over to the primary bot.
+async fn deliver_sharded<F, Fut>(
+    bots: &BotShards,
+    chats: Vec<(i64, DeliveryFormat)>,
+    ack_button: bool,
+    send: F,
+) -> Result<SentMessages>
+where
+    F: Fn(usize, i64, DeliveryFormat, bool) -> Fut,
+    Fut: Future<Output = Result<i64>>,
+{
+    let mut shards: Vec<Vec<(i64, DeliveryFormat)>> = vec![Vec::new(); bots.shard_count()];
+    for (chat_id, format) in chats {
+        shards[bots.shard_of(chat_id)].push((chat_id, format));
+    }
+    let send = &send;
+    let deliveries = shards
+        .into_iter()
+        .enumerate()
+        .map(|(shard, chats)| async move {
+            let mut outcome = ShardOutcome::default();
+            let mut chats = chats.into_iter();
+            for (chat_id, format) in chats.by_ref() {
+                match send(shard, chat_id, format, ack_button && shard == 0).await {
+                    Ok(message_id) => {
+                        outcome.sent.insert(chat_id, message_id);
+                    }
+                    Err(e) => match errors::classify(&e) {
+                        ErrorClass::Recipient if shard > 0 => {
+                            // Don't pay the failed attempt on every alert.
+                            bots.fall_back(chat_id);
+                            outcome.handover.push((chat_id, format));
+                        }
+                        ErrorClass::Recipient => {
+                            info!("chat_id={chat_id} is unreachable (blocked or deleted): {e}")
+                        }
+                        // Every other chat of this shard would fail the same way.
+                        ErrorClass::Auth => {
+                            outcome.handover.push((chat_id, format));
+                            outcome.auth_error = Some(e);
+                            break;
+                        }
+                        class => warn!(
+                            "Failed to deliver to chat_id={chat_id} via bot #{shard} ({}): {e}",
+                            class.name()
+                        ),
+                    },
+                }
+            }
+            outcome.handover.extend(chats);
+            outcome
+        });
+    let outcomes = futures_util::future::join_all(deliveries).await;
+
+    let mut sent = SentMessages::new();
+    let mut handover = Vec::new();
+    for (shard, outcome) in outcomes.into_iter().enumerate() {
+        sent.extend(outcome.sent);
+        match outcome.auth_error {
+            Some(e) if shard == 0 => return Err(e),
+            Some(e) => {
+                error!(
+                    "Bot #{shard} token rejected; handing {} chat(s) to the primary bot: {e}",
+                    outcome.handover.len()
+                );
+                handover.extend(outcome.handover);
+            }
+            None => handover.extend(outcome.handover),
+        }
+    }
+    for (chat_id, format) in handover {
+        // Chats still mapped to another shard get no button; see `reminders_for`.
+        let ack_button = ack_button && bots.shard_of(chat_id) == 0;
+        match send(0, chat_id, format, ack_button).await {
+            Ok(message_id) => {
+                sent.insert(chat_id, message_id);
+            }
+            Err(e) => match errors::classify(&e) {
+                ErrorClass::Recipient => {
+                    info!("chat_id={chat_id} is unreachable (blocked or deleted): {e}")
+                }
+                ErrorClass::Auth => return Err(e),
+                class => warn!(
+                    "Failed to deliver to chat_id={chat_id} ({}): {e}",
+                    class.name()
+                ),
+            },
+        }
+    }
+    Ok(sent)
+}
+
+// ---------------------------------------------------------------------------
+// Pinned status message
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct SentMessageResponse {
+    result: SentMessage,
+}
+
+#[derive(Deserialize)]
+struct SentMessage {
+    message_id: i64,
+}
+
+#[derive(Serialize)]
+struct EditMessagePayload<'a> {
+    chat_id: i64,
+    message_id: i64,
+    text: &'a str,
+    disable_web_page_preview: bool,
+}
+
+#[derive(Serialize)]
+struct PinMessagePayload {
+    chat_id: i64,
+    message_id: i64,
+    disable_notification: bool,
+}
+
+/// `TG_TEST_MODE=true`: talk to Telegram's test environment instead of
+/// production, so CI can exercise real API flows with test accounts and a
+/// bot registered with the test @BotFather.
+pub fn test_mode() -> bool {
+    static TEST_MODE: OnceLock<bool> = OnceLock::new();
+    *TEST_MODE.get_or_init(|| {
+        std::env::var("TG_TEST_MODE")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    })
+}
+
+/// Bot API endpoint of `method`; test-environment bots live under `/test/`.
+fn api_url(bot_token: &str, method: &str) -> String {
+    let env = if test_mode() { "/test" } else { "" };
+    format!("https://api.telegram.org/bot{bot_token}{env}/{method}")
+}
+
+async fn post_bot_api<T: Serialize>(
+    http: &HttpClient,
+    bot_token: &str,
+    method: &str,
+    body: &T,
+) -> Result<reqwest::Response> {
+    let url = api_url(bot_token, method);
+    let resp = http.post(&url).json(body).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let raw = resp.text().await.unwrap_or_default();
+        return Err(BotApiError::from_response(method, status.as_u16(), &raw).into());
+    }
+    Ok(resp)
+}
+
+/// Send a new status message to `chat_id`, pin it silently and remember its
+/// id so later updates edit it in place.
+async fn create_status_pin(
+    http: &HttpClient,
+    bot_token: &str,
+    db: &SharedDb,
+    chat_id: i64,
+    text: &str,
+) -> Result<()> {
+    let message_id = send_reply(http, bot_token, chat_id, text, None).await?;
+    set_status_pin(db, chat_id, message_id)?;
+
+    let pin = PinMessagePayload {
+        chat_id,
+        message_id,
+        disable_notification: true,
+    };
+    post_bot_api(http, bot_token, "pinChatMessage", &pin).await?;
+    info!("Pinned status message {message_id} in chat_id={chat_id}");
+    Ok(())
+}
+
+/// Edit the pinned status message in `chat_id` (creating and pinning it on
+/// first use or when the old message was deleted).  Other edit failures are
+/// returned after the usual retries, leaving the old pin in place.
+pub async fn update_status_pin(
+    http: &HttpClient,
+    bot_token: &str,
+    db: &SharedDb,
+    chat_id: i64,
+    text: &str,
+) -> Result<()> {
+    let Some(message_id) = get_status_pin(db, chat_id)? else {
+        return create_status_pin(http, bot_token, db, chat_id, text).await;
+    };
+
+    let body = EditMessagePayload {
+        chat_id,
+        message_id,
+        text,
+        disable_web_page_preview: true,
+    };
+    let edit = errors::with_retry("editMessageText", || {
+        post_bot_api(http, bot_token, "editMessageText", &body)
+    })
+    .await;
+    match edit {
+        Ok(_) => Ok(()),
+        Err(e) => match EditFailure::of(&e) {
+            EditFailure::NotModified => Ok(()),
+            EditFailure::MessageGone => {
+                warn!(
+                    "Status message {message_id} in chat_id={chat_id} is gone ({e}); re-creating it"
+                );
+                create_status_pin(http, bot_token, db, chat_id, text).await
+            }
+            // Outages and rate limits: keep the old pin, the loop tries again.
+            EditFailure::Other => Err(e),
+        },
+    }
+}
+
+/// Why an `editMessageText` call failed, as far as the status pin cares.
+#[derive(Debug, PartialEq, Eq)]
+enum EditFailure {
+    /// The text is already current.
+    NotModified,
+    /// The message was deleted; a new one has to be sent and pinned.
+    MessageGone,
+    Other,
+}
+
+impl EditFailure {
+    fn of(err: &anyhow::Error) -> Self {
+        let Some(api) = err.chain().find_map(|c| c.downcast_ref::<BotApiError>()) else {
+            return Self::Other;
+        };
+        if api.status != 400 {
+            Self::Other
+        } else if api.description.contains("message is not modified") {
+            Self::NotModified
+        } else if api.description.contains("message to edit not found") {
+            Self::MessageGone
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Runs forever: keeps one pinned "current situation" message per chat in
+/// `chat_ids` in sync with the threat level, editing it whenever the level
+/// changes (including time-based decay).
+pub async fn run_status_pin_loop(
+    http: HttpClient,
+    bot_token: String,
+    db: SharedDb,
+    status: SharedStatus,
+    chat_ids: Vec<i64>,
+) {
+    info!(
+        "Pinned status maintenance started for {} chat(s).",
+        chat_ids.len()
+    );
+    let mut published: Option<ThreatLevel> = None;
+
+    loop {
+        let (level, text) = {
+            let mut status = status.lock().unwrap();
+            let level = status.current().0;
+            (level, status.describe())
+        };
+
+        if published != Some(level) {
+            let mut all_ok = true;
+            for &chat_id in &chat_ids {
+                if let Err(e) = update_status_pin(&http, &bot_token, &db, chat_id, &text).await {
+                    warn!("Failed to update pinned status in chat_id={chat_id}: {e}");
+                    all_ok = false;
+                }
+            }
+            if all_ok {
+                published = Some(level);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(15)).await;
     }
-    Ok(ids)
 }
 
 // ---------------------------------------------------------------------------
-// Bot API types (getUpdates)
+// Review queue
 // ---------------------------------------------------------------------------
 
-#[derive(Deserialize)]
-struct GetUpdatesResponse {
-    ok: bool,
-    result: Vec<TgUpdate>,
+/// A subscriber's first `/report` of an alert: queue the source message for
+/// review, refresh the eval corpus and tell the admins.
+async fn handle_user_report(
+    http: &HttpClient,
+    bot_token: &str,
+    db: &SharedDb,
+    admin_chats: &[i64],
+    chat_id: i64,
+    report: &UserReport,
+) {
+    let candidate = ReviewCandidate {
+        keyword_threats: report.threats.clone(),
+        llm_threats: Vec::new(),
+        confidence: None,
+    };
+    if let Err(e) = review::enqueue(db, &report.message, &candidate) {
+        warn!("review::enqueue(report #{}): {e}", report.alert_id);
+    }
+    if let Some(cfg) = FeedbackCfg::from_env()
+        && let Err(e) = feedback::export_corpus(db, &cfg.corpus_path)
+    {
+        warn!("Feedback corpus export failed: {e}");
+    }
+    let notice = report.render(chat_id);
+    for admin in admin_chats {
+        if let Err(e) = send_message(http, bot_token, *admin, &notice).await {
+            warn!(
+                "Failed to notify admin {admin} of report #{}: {e}",
+                report.alert_id
+            );
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct TgUpdate {
-    update_id: i64,
-    message: Option<TgMessage>,
+/// Send the oldest pending review item to `chat_id` with ✅ / ❌ buttons.
+async fn send_next_review(
+    http: &HttpClient,
+    bot_token: &str,
+    db: &SharedDb,
+    chat_id: i64,
+) -> Result<()> {
+    let Some(item) = review::next_pending(db)? else {
+        return send_message(http, bot_token, chat_id, "✅ Review queue is empty.").await;
+    };
+    let text = item.render(review::pending_count(db)?);
+    let body = SendMessagePayload {
+        chat_id,
+        text: &text,
+        disable_web_page_preview: true,
+        reply_parameters: None,
+        reply_markup: Some(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![
+                InlineKeyboardButton {
+                    text: "✅ Alert",
+                    callback_data: Decision::Accept.callback_data(item.id),
+                },
+                InlineKeyboardButton {
+                    text: "❌ Not an alert",
+                    callback_data: Decision::Reject.callback_data(item.id),
+                },
+            ]],
+        }),
+        disable_notification: false,
+        message_thread_id: None,
+    };
+    post_bot_api(http, bot_token, "sendMessage", &body).await?;
+    Ok(())
 }
 
-#[derive(Deserialize)]
-struct TgMessage {
-    chat: TgChat,
-    text: Option<String>,
-}
+/// Handle a ✅ / ❌ press on a review item: store the verdict, strike the
+/// buttons from the item and show the next one.
+async fn handle_review_callback(
+    http: &HttpClient,
+    bot_token: &str,
+    db: &SharedDb,
+    admin_chats: &[i64],
+    query: TgCallbackQuery,
+) {
+    let parsed = query.data.as_deref().and_then(Decision::parse_callback);
+    let msg = query.message.filter(|m| admin_chats.contains(&m.chat.id));
+    let (answer, decided) = match (&msg, parsed) {
+        (Some(msg), Some((decision, id))) => match review::decide(db, id, decision, msg.chat.id) {
+            Ok(true) => {
+                info!("chat_id={} → review #{id} {decision:?}", msg.chat.id);
+                ("Saved", Some(decision))
+            }
+            Ok(false) => ("Already reviewed", None),
+            Err(e) => {
+                warn!("review::decide({id}): {e}");
+                ("Failed to save", None)
+            }
+        },
+        _ => ("Not allowed", None),
+    };
+    let body = AnswerCallbackPayload {
+        callback_query_id: &query.id,
+        text: answer,
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "answerCallbackQuery", &body).await {
+        warn!("answerCallbackQuery: {e}");
+    }
 
-#[derive(Deserialize)]
-struct TgChat {
-    id: i64,
+    let (Some(msg), Some(decision)) = (msg, decided) else {
+        return;
+    };
+    let verdict = match decision {
+        Decision::Accept => "✅ Alert",
+        Decision::Reject => "❌ Not an alert",
+    };
+    let text = format!("{}\n\n{verdict}", msg.text.unwrap_or_default());
+    // Editing without `reply_markup` removes the buttons.
+    let body = EditMessagePayload {
+        chat_id: msg.chat.id,
+        message_id: msg.message_id,
+        text: &text,
+        disable_web_page_preview: true,
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "editMessageText", &body).await {
+        warn!("Editing review message in chat_id={}: {e}", msg.chat.id);
+    }
+    if let Err(e) = send_next_review(http, bot_token, db, msg.chat.id).await {
+        warn!("send_next_review({}): {e}", msg.chat.id);
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Sending helpers
+// Shared locations
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize)]
-struct SendMessagePayload<'a> {
-    chat_id: i64,
-    text: &'a str,
-    disable_web_page_preview: bool,
-}
+const LOCATION_SAVE: &str = "loc:save";
+const LOCATION_CANCEL: &str = "loc:cancel";
 
-/// Send a single message to one chat via the Bot API.
-pub async fn send_message(
+/// Locations shared but not yet confirmed, by chat id.
+type PendingLocations = HashMap<i64, (f64, f64)>;
+
+/// Reply to a shared location pin with the place it resolves to and
+/// ✅ / ❌ buttons to store it.
+async fn offer_location(
     http: &HttpClient,
     bot_token: &str,
+    pending: &mut PendingLocations,
     chat_id: i64,
-    text: &str,
+    location: &TgLocation,
 ) -> Result<()> {
-    let url = format!("https://api.telegram.org/bot{bot_token}/sendMessage");
+    let (lat, lon) = (location.latitude, location.longitude);
+    let Some(place) = gazetteer::reverse_geocode(lat, lon) else {
+        pending.remove(&chat_id);
+        return send_message(
+            http,
+            bot_token,
+            chat_id,
+            "📍 This location is outside Ukraine – nothing saved.",
+        )
+        .await;
+    };
+    pending.insert(chat_id, (lat, lon));
+    let text = format!(
+        "📍 {lat:.4}, {lon:.4}\n{}\n\nSave as your location?",
+        place.describe()
+    );
     let body = SendMessagePayload {
         chat_id,
-        text,
+        text: &text,
         disable_web_page_preview: true,
+        reply_parameters: None,
+        reply_markup: Some(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![
+                InlineKeyboardButton {
+                    text: "✅ Save",
+                    callback_data: LOCATION_SAVE.to_string(),
+                },
+                InlineKeyboardButton {
+                    text: "❌ Cancel",
+                    callback_data: LOCATION_CANCEL.to_string(),
+                },
+            ]],
+        }),
+        disable_notification: false,
+        message_thread_id: None,
     };
-    let resp = http.post(&url).json(&body).send().await?;
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let raw = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("sendMessage failed: {status} body={raw}"));
-    }
+    post_bot_api(http, bot_token, "sendMessage", &body).await?;
     Ok(())
 }
 
-/// Broadcast `text` to every active subscriber.
-pub async fn broadcast(
+/// Handle a ✅ / ❌ press under a location offer.
+async fn handle_location_callback(
     http: &HttpClient,
     bot_token: &str,
     db: &SharedDb,
-    text: &str,
-) -> Result<()> {
-    let subscribers = get_subscribers(db)?;
-    if subscribers.is_empty() {
-        info!("Broadcast skipped – no subscribers.");
-        return Ok(());
+    pending: &mut PendingLocations,
+    query: TgCallbackQuery,
+) {
+    let Some(msg) = query.message else {
+        return;
+    };
+    let chat_id = msg.chat.id;
+    let save = query.data.as_deref() == Some(LOCATION_SAVE);
+    let (answer, outcome) = match pending.remove(&chat_id) {
+        Some((lat, lon)) if save => match gazetteer::reverse_geocode(lat, lon) {
+            Some(place) => match set_chat_location(db, chat_id, lat, lon, &place) {
+                Ok(()) => {
+                    info!("chat_id={chat_id} → location {}", place.describe());
+                    ("Saved", "✅ Location saved.")
+                }
+                Err(e) => {
+                    warn!("set_chat_location({chat_id}): {e}");
+                    ("Failed to save", "❌ Failed to save the location.")
+                }
+            },
+            None => ("Outside Ukraine", "❌ Not saved."),
+        },
+        Some(_) => ("Cancelled", "❌ Not saved."),
+        None => ("Expired – share the location again", "⌛ Expired."),
+    };
+    let body = AnswerCallbackPayload {
+        callback_query_id: &query.id,
+        text: answer,
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "answerCallbackQuery", &body).await {
+        warn!("answerCallbackQuery: {e}");
     }
-    info!("Broadcasting to {} subscriber(s).", subscribers.len());
-    for chat_id in subscribers {
-        if let Err(e) = send_message(http, bot_token, chat_id, text).await {
-            warn!("Failed to deliver to chat_id={chat_id}: {e}");
-        }
+    let text = format!("{}\n\n{outcome}", msg.text.unwrap_or_default());
+    // Editing without `reply_markup` removes the buttons.
+    let body = EditMessagePayload {
+        chat_id,
+        message_id: msg.message_id,
+        text: &text,
+        disable_web_page_preview: true,
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "editMessageText", &body).await {
+        warn!("Editing location message in chat_id={chat_id}: {e}");
+    }
+}
+
+#[derive(Serialize)]
+struct EditMarkupPayload {
+    chat_id: i64,
+    message_id: i64,
+}
+
+/// Handle a "✅ Прийнято" press under a critical alert or reminder: stop
+/// the chat's reminders and remove the button.
+async fn handle_ack_callback(
+    http: &HttpClient,
+    bot_token: &str,
+    reminders: Option<&SharedReminders>,
+    query: TgCallbackQuery,
+) {
+    let Some(msg) = query.message else {
+        return;
+    };
+    let chat_id = msg.chat.id;
+    let pending = reminders.is_some_and(|r| r.lock().unwrap().acknowledge(chat_id));
+    if pending {
+        info!("chat_id={chat_id} acknowledged the alert");
+    }
+    let body = AnswerCallbackPayload {
+        callback_query_id: &query.id,
+        text: if pending {
+            "Прийнято – нагадувань не буде"
+        } else {
+            "Прийнято"
+        },
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "answerCallbackQuery", &body).await {
+        warn!("answerCallbackQuery: {e}");
+    }
+    // Editing without `reply_markup` removes the button.
+    let body = EditMarkupPayload {
+        chat_id,
+        message_id: msg.message_id,
+    };
+    if let Err(e) = post_bot_api(http, bot_token, "editMessageReplyMarkup", &body).await {
+        warn!("Removing the acknowledgment button in chat_id={chat_id}: {e}");
     }
-    Ok(())
 }
 
 // ---------------------------------------------------------------------------
 // Long-poll loop
 // ---------------------------------------------------------------------------
 
-/// Runs forever: polls `getUpdates` and handles /start_receive / /stop_receive.
-pub async fn run_bot_polling(http: HttpClient, bot_token: String, db: SharedDb) {
+/// Runs forever: polls `getUpdates` and handles /start_receive, /stop_receive,
+/// /morning_summary, /recaps, /now, /format and (for `admin_chats`) /bot_stats,
+/// /metrics_summary, /review, /ban and /unban, plus the review and alert
+/// acknowledgment buttons.  Non-admin chats are rate limited by [`CommandGuard`].
+pub async fn run_bot_polling(
+    http: HttpClient,
+    bot_token: String,
+    subscribers: SubscriberMirror,
+    status: SharedStatus,
+    metrics: SharedMetrics,
+    reminders: Option<SharedReminders>,
+    admin_chats: Vec<i64>,
+) {
+    let db = subscribers.db().clone();
     let mut offset: i64 = 0;
+    let bans = load_bans(&db, chrono::Utc::now().timestamp()).unwrap_or_else(|e| {
+        warn!("Failed to load ban list: {e}");
+        HashMap::new()
+    });
+    if !bans.is_empty() {
+        info!("{} chat(s) banned.", bans.len());
+    }
+    let mut guard = CommandGuard::new(GuardCfg::from_env(), bans);
+    let mut pending_locations = PendingLocations::new();
     info!("Bot long-poll loop started.");
 
     loop {
         let url = format!(
-            "https://api.telegram.org/bot{bot_token}/getUpdates\
-             ?timeout=30&offset={offset}&allowed_updates=[\"message\"]"
+            "{}?timeout=30&offset={offset}&allowed_updates=[\"message\",\"callback_query\"]",
+            api_url(&bot_token, "getUpdates")
         );
 
         let resp = match tokio::time::timeout(Duration::from_secs(40), http.get(&url).send()).await
@@ -165,6 +1958,25 @@ pub async fn run_bot_polling(http: HttpClient, bot_token: String, db: SharedDb)
             }
         };
 
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let raw = resp.text().await.unwrap_or_default();
+            let err = BotApiError::from_response("getUpdates", status, &raw);
+            let backoff = match err.class() {
+                ErrorClass::RateLimited(wait) => wait,
+                class @ ErrorClass::Auth => {
+                    error!("{err} – {}", class.remediation());
+                    Duration::from_secs(60)
+                }
+                class => {
+                    warn!("{err} ({})", class.name());
+                    Duration::from_secs(5)
+                }
+            };
+            tokio::time::sleep(backoff).await;
+            continue;
+        }
+
         let updates: GetUpdatesResponse = match resp.json().await {
             Ok(u) => u,
             Err(e) => {
@@ -183,14 +1995,80 @@ pub async fn run_bot_polling(http: HttpClient, bot_token: String, db: SharedDb)
         for update in updates.result {
             offset = update.update_id + 1;
 
+            if let Some(query) = update.callback_query {
+                if query
+                    .data
+                    .as_deref()
+                    .is_some_and(|d| d == LOCATION_SAVE || d == LOCATION_CANCEL)
+                {
+                    handle_location_callback(&http, &bot_token, &db, &mut pending_locations, query)
+                        .await;
+                } else if query.data.as_deref() == Some(reminders::ACK_CALLBACK) {
+                    handle_ack_callback(&http, &bot_token, reminders.as_ref(), query).await;
+                } else {
+                    handle_review_callback(&http, &bot_token, &db, &admin_chats, query).await;
+                }
+                continue;
+            }
+
             let Some(msg) = update.message else {
                 continue;
             };
 
             let chat_id = msg.chat.id;
+            let is_admin = admin_chats.contains(&chat_id);
+            let now = chrono::Utc::now().timestamp();
+            if !is_admin {
+                match guard.check(chat_id, now) {
+                    GuardVerdict::Allow => {}
+                    GuardVerdict::Ignored => continue,
+                    GuardVerdict::Throttled { notify } => {
+                        if notify {
+                            let _ = send_message(
+                                &http,
+                                &bot_token,
+                                chat_id,
+                                "⏳ Too many commands – please slow down.",
+                            )
+                            .await;
+                        }
+                        continue;
+                    }
+                    GuardVerdict::Banned { until } => {
+                        warn!("chat_id={chat_id} auto-banned for command spam");
+                        if let Err(e) = ban_chat(&db, chat_id, Some(until), "command spam") {
+                            warn!("ban_chat({chat_id}): {e}");
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(location) = &msg.location {
+                if let Err(e) =
+                    offer_location(&http, &bot_token, &mut pending_locations, chat_id, location)
+                        .await
+                {
+                    warn!("offer_location({chat_id}): {e}");
+                }
+                continue;
+            }
+
+            let thread_id = msg.message_thread_id;
+            let replied_to = msg.reply_to_message.as_ref().map(|m| m.message_id);
             let raw_text = msg.text.unwrap_or_default();
+            let (head, args) = raw_text
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or((raw_text.trim(), ""));
             // Strip optional @BotName suffix (e.g. /start_receive@MyBot)
-            let cmd = raw_text.trim().split('@').next().unwrap_or("").trim();
+            let cmd = head.split('@').next().unwrap_or("").trim();
+            let args = args.trim();
+            if TRACKED_COMMANDS.contains(&cmd)
+                && let Err(e) = record_event(&db, chat_id, cmd)
+            {
+                warn!("record_event({chat_id}, {cmd}): {e}");
+            }
 
             match cmd {
                 "/start" => {
@@ -200,7 +2078,16 @@ pub async fn run_bot_polling(http: HttpClient, bot_token: String, db: SharedDb)
                         chat_id,
                         "👋 Hello!\n\
                          /start_receive – subscribe to channel updates\n\
-                         /stop_receive  – unsubscribe",
+                         /stop_receive  – unsubscribe\n\
+                         /morning_summary – toggle the daily morning digest\n\
+                         /recaps – toggle official recap posts (📊 Підсумок)\n\
+                         /voice – toggle voice messages for critical alerts\n\
+                         /now – current threat level\n\
+                         /report [comment] – reply to an alert to flag it as a false positive\n\
+                         /delete_me – delete all data stored for this chat\n\
+                         /format compact|full, /format text on|off, /format source on|off\n\
+                         /topic <family> – in a forum group, post that family in this topic\n\
+                         📍 Share a location pin to set your location (/location to view)",
                     )
                     .await;
                 }
@@ -237,8 +2124,582 @@ pub async fn run_bot_polling(http: HttpClient, bot_token: String, db: SharedDb)
                     }
                 }
 
+                "/morning_summary" => match toggle_morning_summary(&db, chat_id) {
+                    Ok(enabled) => {
+                        info!("chat_id={chat_id} → morning summary {enabled}");
+                        let reply = if enabled {
+                            "🌅 Morning summary enabled. You will get a daily overnight digest."
+                        } else {
+                            "🌙 Morning summary disabled."
+                        };
+                        let _ = send_message(&http, &bot_token, chat_id, reply).await;
+                    }
+                    Err(e) => warn!("toggle_morning_summary({chat_id}): {e}"),
+                },
+
+                "/recaps" => match toggle_recaps(&db, chat_id) {
+                    Ok(enabled) => {
+                        info!("chat_id={chat_id} → recaps {enabled}");
+                        let reply = if enabled {
+                            "📊 Recap posts enabled. You will also get official summaries."
+                        } else {
+                            "📊 Recap posts disabled."
+                        };
+                        let _ = send_message(&http, &bot_token, chat_id, reply).await;
+                    }
+                    Err(e) => warn!("toggle_recaps({chat_id}): {e}"),
+                },
+
+                "/voice" => match toggle_voice(&db, chat_id) {
+                    Ok(enabled) => {
+                        info!("chat_id={chat_id} → voice {enabled}");
+                        let reply = if enabled {
+                            "🔊 Voice messages enabled. Critical alerts will also arrive \
+                             as a short spoken message."
+                        } else {
+                            "🔇 Voice messages disabled."
+                        };
+                        let _ = send_message(&http, &bot_token, chat_id, reply).await;
+                    }
+                    Err(e) => warn!("toggle_voice({chat_id}): {e}"),
+                },
+
+                "/delete_me" => match delete_chat_data(&db, chat_id) {
+                    Ok(()) => {
+                        info!("chat_id={chat_id} → delete_me");
+                        let _ = send_message(
+                            &http,
+                            &bot_token,
+                            chat_id,
+                            "🗑 All data stored for this chat was deleted and you were \
+                             unsubscribed.",
+                        )
+                        .await;
+                    }
+                    Err(e) => warn!("delete_chat_data({chat_id}): {e}"),
+                },
+
+                "/now" => {
+                    let reply = status.lock().unwrap().describe();
+                    let _ = send_message(&http, &bot_token, chat_id, &reply).await;
+                }
+
+                "/report" => {
+                    let reply = match replied_to {
+                        None => {
+                            "🚩 Reply /report to the alert you think was wrong, \
+                                 optionally followed by a comment."
+                        }
+                        Some(message_id) => {
+                            match feedback::record_report(&db, chat_id, message_id, args) {
+                                Ok(Some(report)) => {
+                                    info!("chat_id={chat_id} → report alert #{}", report.alert_id);
+                                    if report.first {
+                                        handle_user_report(
+                                            &http,
+                                            &bot_token,
+                                            &db,
+                                            &admin_chats,
+                                            chat_id,
+                                            &report,
+                                        )
+                                        .await;
+                                    }
+                                    "🚩 Thanks! The alert was reported to the admins as a \
+                                     false positive."
+                                }
+                                Ok(None) => {
+                                    "🚩 That message is not an alert delivered by \
+                                             this bot (or it is too old to report)."
+                                }
+                                Err(e) => {
+                                    warn!("record_report({chat_id}): {e}");
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    let _ = send_message(&http, &bot_token, chat_id, reply).await;
+                }
+
+                "/format" => {
+                    let current = match get_delivery_format(&db, chat_id) {
+                        Ok(f) => f,
+                        Err(e) => {
+                            warn!("get_delivery_format({chat_id}): {e}");
+                            continue;
+                        }
+                    };
+                    let reply = match parse_format_args(current, args) {
+                        Some(format) if format == current => {
+                            format!("🎛 Format: {}", format.describe())
+                        }
+                        Some(format) => match set_delivery_format(&db, chat_id, format) {
+                            Ok(()) => {
+                                info!("chat_id={chat_id} → format {}", format.describe());
+                                format!("✅ Format updated: {}", format.describe())
+                            }
+                            Err(e) => {
+                                warn!("set_delivery_format({chat_id}): {e}");
+                                continue;
+                            }
+                        },
+                        None => "Usage: /format compact|full, /format text on|off, \
+                                 /format source on|off"
+                            .to_string(),
+                    };
+                    let _ = send_message(&http, &bot_token, chat_id, &reply).await;
+                }
+
+                "/location" => {
+                    let reply = if args == "off" {
+                        match remove_chat_location(&db, chat_id) {
+                            Ok(true) => "📍 Location removed.".to_string(),
+                            Ok(false) => "📍 No location was saved.".to_string(),
+                            Err(e) => {
+                                warn!("remove_chat_location({chat_id}): {e}");
+                                continue;
+                            }
+                        }
+                    } else {
+                        match get_chat_location(&db, chat_id) {
+                            Ok(Some((lat, lon))) => {
+                                let place = gazetteer::reverse_geocode(lat, lon)
+                                    .map(|p| p.describe())
+                                    .unwrap_or_default();
+                                format!(
+                                    "📍 {lat:.4}, {lon:.4}\n{place}\n\n\
+                                     Share a new pin to change it, /location off to remove it."
+                                )
+                            }
+                            Ok(None) => "📍 No location saved. Share a location pin \
+                                         (📎 → Location) to set one."
+                                .to_string(),
+                            Err(e) => {
+                                warn!("get_chat_location({chat_id}): {e}");
+                                continue;
+                            }
+                        }
+                    };
+                    let _ = send_message(&http, &bot_token, chat_id, &reply).await;
+                }
+
+                "/topic" => {
+                    let (off, family) = match args.split_once(char::is_whitespace) {
+                        Some(("off", family)) => (true, family.trim()),
+                        _ => (false, args),
+                    };
+                    let reply = if family.is_empty() {
+                        match get_forum_topics(&db) {
+                            Ok(topics) => describe_forum_topics(topics.get(&chat_id)),
+                            Err(e) => {
+                                warn!("get_forum_topics: {e}");
+                                continue;
+                            }
+                        }
+                    } else if !TOPIC_FAMILIES.contains(&family) {
+                        format!(
+                            "Unknown family {family:?}. Families: {}",
+                            TOPIC_FAMILIES.join(", ")
+                        )
+                    } else if off {
+                        match remove_forum_topic(&db, chat_id, family) {
+                            Ok(true) => format!("✅ {family} alerts go to the general topic."),
+                            Ok(false) => format!("No topic was set for {family}."),
+                            Err(e) => {
+                                warn!("remove_forum_topic({chat_id}): {e}");
+                                continue;
+                            }
+                        }
+                    } else if let Some(thread_id) = thread_id {
+                        match set_forum_topic(&db, chat_id, family, thread_id) {
+                            Ok(()) => {
+                                info!("chat_id={chat_id} → topic {family}={thread_id}");
+                                format!("✅ {family} alerts will be posted in this topic.")
+                            }
+                            Err(e) => {
+                                warn!("set_forum_topic({chat_id}): {e}");
+                                continue;
+                            }
+                        }
+                    } else {
+                        "Send /topic <family> inside the forum topic that should receive it."
+                            .to_string()
+                    };
+                    let _ = send_reply_with(
+                        &http,
+                        &bot_token,
+                        chat_id,
+                        &reply,
+                        None,
+                        SendOpts::default(),
+                        thread_id,
+                    )
+                    .await;
+                }
+
+                "/ban" | "/unban" if is_admin => {
+                    let Ok(target) = args.parse::<i64>() else {
+                        let _ =
+                            send_message(&http, &bot_token, chat_id, "Usage: /ban <chat_id>").await;
+                        continue;
+                    };
+                    let result = if cmd == "/ban" {
+                        guard.ban(target, None);
+                        ban_chat(&db, target, None, "banned by admin")
+                    } else {
+                        guard.unban(target);
+                        unban_chat(&db, target)
+                    };
+                    let reply = match result {
+                        Ok(()) => {
+                            info!("chat_id={chat_id} → {cmd} {target}");
+                            format!("✅ {cmd} {target}")
+                        }
+                        Err(e) => format!("❌ {cmd} {target} failed: {e}"),
+                    };
+                    let _ = send_message(&http, &bot_token, chat_id, &reply).await;
+                }
+
+                "/review" if is_admin => {
+                    if let Err(e) = send_next_review(&http, &bot_token, &db, chat_id).await {
+                        warn!("send_next_review({chat_id}): {e}");
+                    }
+                }
+
+                "/bot_stats" if is_admin => {
+                    let now = chrono::Utc::now().timestamp();
+                    match weekly_stats(&db, now, &BotStatsCfg::from_env()) {
+                        Ok(stats) => {
+                            let _ = send_message(&http, &bot_token, chat_id, &format_stats(&stats))
+                                .await;
+                        }
+                        Err(e) => warn!("weekly_stats: {e}"),
+                    }
+                }
+
+                "/metrics_summary" if is_admin => {
+                    let summary = metrics.lock().unwrap().summary_at(Instant::now());
+                    let reply = metrics::format_summary(&summary);
+                    let _ = send_message(&http, &bot_token, chat_id, &reply).await;
+                }
+
                 _ => {}
             }
+            if AUDIENCE_COMMANDS.contains(&cmd)
+                && let Err(e) = subscribers.refresh()
+            {
+                warn!("Subscriber mirror not refreshed after {cmd}, retrying later: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shards(count: usize) -> BotShards {
+        let tokens: Vec<String> = (1..=count).map(|i| format!("{i}00:secret{i}")).collect();
+        BotShards::new(tokens[0].clone(), tokens[1..].to_vec())
+    }
+
+    #[test]
+    fn chats_keep_their_shard_and_spread_evenly() {
+        let one = shards(1);
+        assert!((-50..50).all(|chat_id| one.shard_of(chat_id) == 0));
+
+        let three = shards(3);
+        let again = BotShards::new(
+            "100:rotated".into(),
+            vec!["200:other".into(), "300:x".into(), "200:other".into()],
+        );
+        assert_eq!(again.shard_count(), 3, "duplicates dropped");
+        let mut counts = [0; 3];
+        for chat_id in (1..3000).chain(-1_001_000_003_000..-1_001_000_000_000) {
+            let shard = three.shard_of(chat_id);
+            assert_eq!(shard, three.shard_of(chat_id));
+            assert_eq!(shard, again.shard_of(chat_id), "keyed by bot id only");
+            counts[shard] += 1;
+        }
+        assert!(counts.iter().all(|n| *n > 6000 / 5), "{counts:?}");
+
+        // A fourth bot only takes chats over; none move between the others.
+        let four = shards(4);
+        let mut moved = 0;
+        for chat_id in 1..3000 {
+            let (before, after) = (three.shard_of(chat_id), four.shard_of(chat_id));
+            if before != after {
+                assert_eq!(after, 3, "chat_id={chat_id}");
+                moved += 1;
+            }
+        }
+        assert!((3000 / 8..3000 / 2).contains(&moved), "{moved}");
+    }
+
+    #[tokio::test]
+    async fn unreachable_chats_are_handed_to_the_primary_bot() {
+        let bots = shards(3);
+        // Chats per shard, each shard's first one never started that bot.
+        let mut by_shard: Vec<Vec<i64>> = vec![Vec::new(); 3];
+        for chat_id in 1.. {
+            by_shard[bots.shard_of(chat_id)].push(chat_id);
+            if by_shard.iter().all(|c| c.len() >= 2) {
+                break;
+            }
+        }
+        let not_started = [by_shard[1][0], by_shard[2][0]];
+        let revoked = Mutex::new(false);
+        let calls = Mutex::new(Vec::new());
+        let send = |shard: usize, chat_id: i64, _: DeliveryFormat, ack_button: bool| {
+            calls.lock().unwrap().push((shard, chat_id, ack_button));
+            let reply = if shard == 2 && *revoked.lock().unwrap() {
+                Some((401, r#"{"description":"Unauthorized"}"#))
+            } else if shard > 0 && not_started.contains(&chat_id) {
+                Some((
+                    403,
+                    r#"{"description":"Forbidden: bot can't initiate conversation"}"#,
+                ))
+            } else {
+                None
+            };
+            async move {
+                match reply {
+                    Some((status, body)) => {
+                        Err(BotApiError::from_response("sendMessage", status, body).into())
+                    }
+                    None => Ok(chat_id * 10 + shard as i64),
+                }
+            }
+        };
+        let chats: Vec<(i64, DeliveryFormat)> = by_shard
+            .iter()
+            .flatten()
+            .map(|&chat_id| (chat_id, DeliveryFormat::default()))
+            .collect();
+        let calls_to = |chat_id: i64| -> Vec<(usize, bool)> {
+            calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, c, _)| *c == chat_id)
+                .map(|(shard, _, ack)| (*shard, *ack))
+                .collect()
+        };
+
+        let sent = deliver_sharded(&bots, chats.clone(), true, &send)
+            .await
+            .unwrap();
+        assert_eq!(sent.len(), chats.len());
+        for chat_id in not_started {
+            assert_eq!(sent[&chat_id], chat_id * 10, "sent by the primary bot");
+            assert_eq!(calls_to(chat_id)[1], (0, true));
+            assert_eq!(bots.shard_of(chat_id), 0, "remembered");
+        }
+        assert_eq!(calls_to(by_shard[1][1]), [(1, false)]);
+        assert_eq!(calls_to(by_shard[0][0]), [(0, true)]);
+
+        // Next time the primary bot sends right away.
+        calls.lock().unwrap().clear();
+        deliver_sharded(&bots, chats.clone(), true, &send)
+            .await
+            .unwrap();
+        for chat_id in not_started {
+            assert_eq!(calls_to(chat_id), [(0, true)]);
+        }
+
+        // A revoked token: one failed attempt, then the primary bot takes
+        // all of that shard's chats, without the button.
+        *revoked.lock().unwrap() = true;
+        calls.lock().unwrap().clear();
+        let sent = deliver_sharded(&bots, chats.clone(), true, &send)
+            .await
+            .unwrap();
+        assert_eq!(sent.len(), chats.len());
+        let on_revoked = calls.lock().unwrap().iter().filter(|c| c.0 == 2).count();
+        assert_eq!(on_revoked, 1);
+        for &chat_id in &by_shard[2][1..] {
+            assert_eq!(sent[&chat_id], chat_id * 10);
+            assert_eq!(calls_to(chat_id).last(), Some(&(0, false)));
+        }
+        assert_eq!(bots.shard_of(by_shard[2][1]), 2, "not remembered");
+
+        // The primary bot's own token failing is an error.
+        let primary_revoked = |shard: usize, _: i64, _: DeliveryFormat, _: bool| async move {
+            if shard == 0 {
+                Err(BotApiError::from_response("sendMessage", 401, "{}").into())
+            } else {
+                Ok(1)
+            }
+        };
+        assert!(
+            deliver_sharded(&bots, chats, false, primary_revoked)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn banned_chat_is_ignored_until_the_ban_expires() {
+        let cfg = GuardCfg {
+            per_minute: 10,
+            strikes_to_ban: 3,
+            ban_secs: 3600,
+        };
+        let mut guard = CommandGuard::new(cfg, HashMap::from([(1, Some(1000)), (2, None)]));
+        assert_eq!(guard.check(1, 999), GuardVerdict::Ignored);
+        assert_eq!(guard.check(2, i64::MAX), GuardVerdict::Ignored, "permanent");
+        assert_eq!(guard.check(3, 999), GuardVerdict::Allow);
+        assert_eq!(guard.check(1, 1000), GuardVerdict::Allow);
+
+        guard.ban(3, None);
+        assert_eq!(guard.check(3, 0), GuardVerdict::Ignored);
+        guard.unban(3);
+        assert_eq!(guard.check(3, 0), GuardVerdict::Allow);
+    }
+
+    #[test]
+    fn chat_over_the_limit_is_throttled_until_the_window_passes() {
+        let cfg = GuardCfg {
+            per_minute: 2,
+            strikes_to_ban: 2,
+            ban_secs: 3600,
+        };
+        let mut guard = CommandGuard::new(cfg, HashMap::new());
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+        assert_eq!(guard.check_at(1, 0, at(0)), GuardVerdict::Allow);
+        assert_eq!(guard.check_at(1, 1, at(1)), GuardVerdict::Allow);
+        assert_eq!(
+            guard.check_at(1, 2, at(2)),
+            GuardVerdict::Throttled { notify: true }
+        );
+        assert_eq!(
+            guard.check_at(1, 3, at(3)),
+            GuardVerdict::Throttled { notify: false }
+        );
+        // Other chats have their own budget.
+        assert_eq!(guard.check_at(2, 3, at(3)), GuardVerdict::Allow);
+
+        // Throttled messages count too: the chat is allowed again a minute
+        // after its last message.
+        assert_eq!(
+            guard.check_at(1, 59, at(59)),
+            GuardVerdict::Throttled { notify: false }
+        );
+        assert_eq!(guard.check_at(1, 119, at(119)), GuardVerdict::Allow);
+
+        // The next throttled minute is the second strike: auto-ban.
+        assert_eq!(guard.check_at(1, 120, at(120)), GuardVerdict::Allow);
+        assert_eq!(
+            guard.check_at(1, 120, at(120)),
+            GuardVerdict::Banned { until: 3720 }
+        );
+        assert_eq!(guard.check_at(1, 3719, at(3719)), GuardVerdict::Ignored);
+        assert_eq!(guard.check_at(1, 3720, at(3720)), GuardVerdict::Allow);
+    }
+
+    #[test]
+    fn sustained_spam_earns_a_strike_per_minute_and_a_ban() {
+        let cfg = GuardCfg {
+            per_minute: 2,
+            strikes_to_ban: 3,
+            ban_secs: 3600,
+        };
+        let mut guard = CommandGuard::new(cfg, HashMap::new());
+        let t0 = Instant::now();
+        let mut verdicts = Vec::new();
+        // A message every 5 s never lets the window drain.
+        for secs in (0..=130).step_by(5) {
+            let at = t0 + Duration::from_secs(secs);
+            let verdict = guard.check_at(1, secs as i64, at);
+            if verdict != (GuardVerdict::Throttled { notify: false }) {
+                verdicts.push((secs, verdict));
+            }
+        }
+        assert_eq!(
+            verdicts,
+            [
+                (0, GuardVerdict::Allow),
+                (5, GuardVerdict::Allow),
+                (10, GuardVerdict::Throttled { notify: true }),
+                (70, GuardVerdict::Throttled { notify: true }),
+                (130, GuardVerdict::Banned { until: 3730 }),
+            ]
+        );
+        let at = t0 + Duration::from_secs(135);
+        assert_eq!(guard.check_at(1, 135, at), GuardVerdict::Ignored);
+    }
+
+    #[test]
+    fn subscriber_mirror_keeps_serving_when_db_is_unreadable() {
+        let db = open_db(":memory:").unwrap();
+        add_subscriber(&db, 1).unwrap();
+        add_subscriber(&db, 2).unwrap();
+        let mirror = SubscriberMirror::new(db.clone());
+        assert_eq!(mirror.audience().unwrap().subscribers.len(), 2);
+
+        add_subscriber(&db, 3).unwrap();
+        assert_eq!(mirror.refresh().unwrap().subscribers.len(), 3);
+
+        // The DB breaks: refresh fails, broadcasts still get the last list.
+        db.lock()
+            .unwrap()
+            .execute("DROP TABLE subscribers")
+            .unwrap();
+        assert!(mirror.refresh().is_err());
+        let ids: Vec<i64> = mirror
+            .audience()
+            .unwrap()
+            .subscribers
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn only_a_missing_message_recreates_the_status_pin() {
+        let edit_error = |status, body: &str| -> anyhow::Error {
+            BotApiError::from_response("editMessageText", status, body).into()
+        };
+        assert_eq!(
+            EditFailure::of(&edit_error(
+                400,
+                r#"{"ok":false,"description":"Bad Request: message is not modified: specified new message content is exactly the same"}"#
+            )),
+            EditFailure::NotModified
+        );
+        assert_eq!(
+            EditFailure::of(&edit_error(
+                400,
+                r#"{"ok":false,"description":"Bad Request: message to edit not found"}"#
+            )),
+            EditFailure::MessageGone
+        );
+        // Found through context layers too.
+        let wrapped = edit_error(400, "Bad Request: message to edit not found")
+            .context("updating status pin");
+        assert_eq!(EditFailure::of(&wrapped), EditFailure::MessageGone);
+
+        for (status, body) in [
+            (502, "Bad Gateway"),
+            (
+                429,
+                r#"{"ok":false,"description":"Too Many Requests: retry after 40","parameters":{"retry_after":40}}"#,
+            ),
+            (400, "Bad Request: message can't be edited"),
+            (500, "message to edit not found"),
+        ] {
+            assert_eq!(
+                EditFailure::of(&edit_error(status, body)),
+                EditFailure::Other,
+                "{status} {body}"
+            );
         }
+        assert_eq!(
+            EditFailure::of(&anyhow::anyhow!("connection reset")),
+            EditFailure::Other
+        );
     }
 }
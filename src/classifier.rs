@@ -0,0 +1,579 @@
+//! Offline secondary filter for machines that cannot run Ollama: a naive
+//! Bayes model trained at startup on the labeled feedback corpus (see
+//! [`crate::feedback::export_corpus`]).  Like the LLM it only confirms or
+//! drops the keyword verdict, never adds threats.
+//!
+//! `SECONDARY_FILTER` picks the verifiers:
+//!
+//! | Value    | Verifier                                                |
+//! |----------|---------------------------------------------------------|
+//! | `llm`    | [`LlmFilter`] (implies `LLM_ENABLED`)                   |
+//! | `ml`     | [`MlFilter`]                                            |
+//! | `ml,llm` | both                                                    |
+//! | `none`   | keyword result only                                     |
+//!
+//! When unset, the LLM is used if `LLM_ENABLED` is set and nothing
+//! otherwise.  The keyword verdict and every verifier's verdict are then
+//! combined by a confidence-weighted vote ([`combine`]); with a single
+//! weighted source (e.g. `COMBINE_WEIGHTS=keyword=0`) that source's
+//! verdict is final.
+
+use crate::feedback::Label;
+use crate::filter::Proximity;
+use crate::filter::threat_kind::ThreatKind;
+use crate::llm::{LlmFilter, LlmVerdict};
+use crate::metrics::SharedMetrics;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use tracing::{debug, info, warn};
+
+/// Tokens are cut to this many characters, a crude stemmer that folds most
+/// Ukrainian/Russian case endings ("шахеди", "шахедів" → "шахед").
+const STEM_CHARS: usize = 6;
+
+/// Laplace smoothing.
+const ALPHA: f64 = 1.0;
+
+/// Stemmed, de-duplicated word tokens of `text`.
+pub fn tokenize(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .map(|w| w.trim_matches('-'))
+        .filter(|w| w.chars().count() >= 2)
+        .map(|w| w.chars().take(STEM_CHARS).collect())
+        .collect()
+}
+
+/// Bernoulli-style naive Bayes over binary token presence, two classes:
+/// active alert vs. not an alert.
+#[derive(Debug, Clone, Default)]
+pub struct NaiveBayes {
+    /// Documents per class, `[not_alert, alert]`.
+    docs: [usize; 2],
+    /// Per token, documents of each class containing it.
+    token_docs: HashMap<String, [usize; 2]>,
+    /// Sum of distinct-token counts per class.
+    token_totals: [usize; 2],
+}
+
+impl NaiveBayes {
+    /// Train on `(text, is_alert)` pairs.
+    pub fn train<'a>(examples: impl IntoIterator<Item = (&'a str, bool)>) -> Self {
+        let mut model = Self::default();
+        for (text, is_alert) in examples {
+            let class = usize::from(is_alert);
+            model.docs[class] += 1;
+            for token in tokenize(text) {
+                model.token_docs.entry(token).or_default()[class] += 1;
+                model.token_totals[class] += 1;
+            }
+        }
+        model
+    }
+
+    pub fn example_count(&self) -> usize {
+        self.docs[0] + self.docs[1]
+    }
+
+    /// Both classes have examples, so predictions mean something.
+    pub fn is_trained(&self) -> bool {
+        self.docs[0] > 0 && self.docs[1] > 0
+    }
+
+    /// Probability that `text` is an active alert.  Unknown tokens are
+    /// ignored; an untrained model returns `0.5`.
+    pub fn probability(&self, text: &str) -> f64 {
+        if !self.is_trained() {
+            return 0.5;
+        }
+        let vocab = self.token_docs.len() as f64;
+        let total = self.example_count() as f64;
+        let mut log_odds = (self.docs[1] as f64 / total).ln() - (self.docs[0] as f64 / total).ln();
+        for token in tokenize(text) {
+            let Some(counts) = self.token_docs.get(&token) else {
+                continue;
+            };
+            let likelihood = |class: usize| {
+                (counts[class] as f64 + ALPHA) / (self.token_totals[class] as f64 + ALPHA * vocab)
+            };
+            log_odds += likelihood(1).ln() - likelihood(0).ln();
+        }
+        1.0 / (1.0 + (-log_odds).exp())
+    }
+}
+
+/// One line of the feedback corpus; other fields are ignored.
+#[derive(Deserialize)]
+struct CorpusLine {
+    text: String,
+    label: String,
+}
+
+/// `(text, is_alert)` pairs from a feedback corpus file.
+pub fn load_corpus(path: &str) -> Result<Vec<(String, bool)>> {
+    let data = std::fs::read_to_string(path)?;
+    let mut out = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<CorpusLine>(line) {
+            Ok(l) if l.label == Label::Correct.as_str() => out.push((l.text, true)),
+            Ok(l) if l.label == Label::FalsePositive.as_str() => out.push((l.text, false)),
+            Ok(l) => warn!("{path}:{}: unknown label {:?}", idx + 1, l.label),
+            Err(e) => warn!("{path}:{}: bad corpus line: {e}", idx + 1),
+        }
+    }
+    Ok(out)
+}
+
+/// Naive Bayes verifier.
+pub struct MlFilter {
+    model: NaiveBayes,
+    /// Messages scoring below this are dropped.
+    threshold: f64,
+    /// Verdicts less sure than this are queued for review.
+    review_below: f32,
+}
+
+impl MlFilter {
+    /// | Env var            | Default                   | Description                           |
+    /// |--------------------|---------------------------|---------------------------------------|
+    /// | `ML_CORPUS_PATH`   | `./feedback_corpus.jsonl` | Labeled corpus files, comma-separated |
+    /// | `ML_THRESHOLD`     | `0.5`                     | Alert probability needed to forward   |
+    /// | `ML_REVIEW_BELOW`  | `0.65`                    | Confidence below which to review      |
+    /// | `ML_MIN_EXAMPLES`  | `20`                      | Smaller corpora leave the filter off  |
+    pub fn from_env() -> Self {
+        let paths =
+            std::env::var("ML_CORPUS_PATH").unwrap_or_else(|_| "./feedback_corpus.jsonl".into());
+        let min_examples: usize = std::env::var("ML_MIN_EXAMPLES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+
+        let mut examples = Vec::new();
+        for path in paths.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match load_corpus(path) {
+                Ok(mut e) => examples.append(&mut e),
+                Err(e) => warn!("Cannot read ML corpus {path}: {e}"),
+            }
+        }
+        let mut model = NaiveBayes::train(examples.iter().map(|(t, a)| (t.as_str(), *a)));
+        if model.example_count() < min_examples || !model.is_trained() {
+            warn!(
+                "ML filter has {} labeled example(s), need {min_examples} of both kinds – \
+                 keyword results pass unchanged",
+                model.example_count()
+            );
+            model = NaiveBayes::default();
+        } else {
+            info!(
+                "ML filter trained on {} labeled message(s)",
+                model.example_count()
+            );
+        }
+
+        let threshold = std::env::var("ML_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.5);
+        let review_below = std::env::var("ML_REVIEW_BELOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.65);
+        Self::new(model, threshold, review_below)
+    }
+
+    pub fn new(model: NaiveBayes, threshold: f64, review_below: f32) -> Self {
+        Self {
+            model,
+            threshold,
+            review_below,
+        }
+    }
+
+    /// Keep or drop `keyword_threats`.  An untrained model keeps them
+    /// (**fail-open**, like the LLM).
+    pub fn verify(&self, text: &str, keyword_threats: &[ThreatKind]) -> LlmVerdict {
+        if !self.model.is_trained() {
+            return LlmVerdict {
+                threats: keyword_threats.to_vec(),
+                confidence: None,
+            };
+        }
+        let p = self.model.probability(text);
+        let is_alert = p >= self.threshold;
+        LlmVerdict {
+            threats: if is_alert {
+                keyword_threats.to_vec()
+            } else {
+                Vec::new()
+            },
+            confidence: Some(if is_alert { p } else { 1.0 - p } as f32),
+        }
+    }
+
+    pub fn is_uncertain(&self, confidence: Option<f32>) -> bool {
+        confidence.is_some_and(|c| c < self.review_below)
+    }
+}
+
+/// Per-source weights of the combiner (`COMBINE_WEIGHTS`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub keyword: f64,
+    pub ml: f64,
+    pub llm: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            keyword: 1.0,
+            ml: 1.0,
+            llm: 2.0,
+        }
+    }
+}
+
+impl Weights {
+    /// `keyword=1,ml=1,llm=2`; sources left out keep their default.
+    pub fn parse(spec: &str) -> Self {
+        let mut weights = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parsed = part
+                .split_once('=')
+                .and_then(|(k, v)| Some((k.trim(), v.trim().parse::<f64>().ok()?)));
+            match parsed {
+                Some(("keyword", w)) => weights.keyword = w.max(0.0),
+                Some(("ml", w)) => weights.ml = w.max(0.0),
+                Some(("llm", w)) => weights.llm = w.max(0.0),
+                _ => warn!("Ignoring bad COMBINE_WEIGHTS entry {part:?}"),
+            }
+        }
+        weights
+    }
+}
+
+/// One source's verdict as seen by [`combine`].
+#[derive(Debug, Clone)]
+pub struct Vote {
+    pub weight: f64,
+    pub threats: Vec<ThreatKind>,
+    /// How sure the source is about its threat list, `0.0..=1.0`.
+    pub confidence: f64,
+}
+
+/// Weighted vote over every threat any source named.  A source that lists
+/// a threat supports it with its confidence; one that leaves it out
+/// supports it with `1 - confidence`.  Threats whose weighted support
+/// reaches `threshold` are kept.  The returned confidence is the mean
+/// support for each decision taken.
+pub fn combine(votes: &[Vote], threshold: f64) -> LlmVerdict {
+    let votes: Vec<&Vote> = votes.iter().filter(|v| v.weight > 0.0).collect();
+    let total: f64 = votes.iter().map(|v| v.weight).sum();
+    let mut candidates: Vec<ThreatKind> = Vec::new();
+    for t in votes.iter().flat_map(|v| &v.threats) {
+        if !candidates.contains(t) {
+            candidates.push(*t);
+        }
+    }
+    if total <= 0.0 || candidates.is_empty() {
+        return LlmVerdict {
+            threats: Vec::new(),
+            confidence: None,
+        };
+    }
+
+    let mut threats = Vec::new();
+    let mut certainty = 0.0;
+    for t in &candidates {
+        let support = votes
+            .iter()
+            .map(|v| {
+                let c = v.confidence.clamp(0.0, 1.0);
+                v.weight * if v.threats.contains(t) { c } else { 1.0 - c }
+            })
+            .sum::<f64>()
+            / total;
+        if support >= threshold {
+            threats.push(*t);
+            certainty += support;
+        } else {
+            certainty += 1.0 - support;
+        }
+    }
+    LlmVerdict {
+        threats,
+        confidence: Some((certainty / candidates.len() as f64) as f32),
+    }
+}
+
+/// The verifiers run after keyword detection, and how their verdicts are
+/// combined with the keywords.
+pub struct SecondaryFilter {
+    llm: Option<LlmFilter>,
+    ml: Option<MlFilter>,
+    weights: Weights,
+    /// Confidence given to the keyword verdict.
+    keyword_confidence: f64,
+    /// Weighted support a threat needs to be kept.
+    threshold: f64,
+    /// Combined verdicts less sure than this are queued for review.
+    review_below: f32,
+}
+
+impl SecondaryFilter {
+    /// | Env var                | Default                | Description                              |
+    /// |------------------------|------------------------|------------------------------------------|
+    /// | `SECONDARY_FILTER`     | follows `LLM_ENABLED`  | `llm`, `ml`, `ml,llm` or `none`          |
+    /// | `COMBINE_WEIGHTS`      | `keyword=1,ml=1,llm=2` | Source weights in the combined vote      |
+    /// | `KEYWORD_CONFIDENCE`   | `0.7`                  | Confidence of the keyword verdict        |
+    /// | `COMBINE_THRESHOLD`    | `0.5`                  | Support a threat needs to be kept        |
+    /// | `COMBINE_REVIEW_BELOW` | `0.65`                 | Combined confidence that triggers review |
+    pub fn from_env() -> Self {
+        let raw = std::env::var("SECONDARY_FILTER")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let (mut llm, mut ml) = (None, None);
+        if raw.is_empty() {
+            let filter = LlmFilter::from_env();
+            if filter.is_enabled() {
+                llm = Some(filter);
+            }
+        } else {
+            for name in raw.split(',').map(str::trim) {
+                match name {
+                    "llm" if llm.is_none() => llm = Some(LlmFilter::from_env().force_enabled()),
+                    "ml" if ml.is_none() => ml = Some(MlFilter::from_env()),
+                    "none" | "llm" | "ml" => {}
+                    other => warn!("Unknown SECONDARY_FILTER entry {other:?}"),
+                }
+            }
+        }
+
+        let num = |key: &str, default: f64| -> f64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            llm,
+            ml,
+            weights: Weights::parse(&std::env::var("COMBINE_WEIGHTS").unwrap_or_default()),
+            keyword_confidence: num("KEYWORD_CONFIDENCE", 0.7),
+            threshold: num("COMBINE_THRESHOLD", 0.5),
+            review_below: num("COMBINE_REVIEW_BELOW", 0.65) as f32,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.llm.as_ref().is_some_and(|l| l.is_enabled()) || self.ml.is_some()
+    }
+
+    /// Report LLM latency to `metrics`.
+    pub fn with_metrics(mut self, metrics: SharedMetrics) -> Self {
+        self.llm = self.llm.map(|l| l.with_metrics(metrics));
+        self
+    }
+
+    /// With a single weighted source its verdict is taken as-is, the way
+    /// the LLM used to override the keywords.
+    fn single_source(&self) -> bool {
+        let sources = [
+            self.weights.keyword > 0.0,
+            self.llm.is_some() && self.weights.llm > 0.0,
+            self.ml.is_some() && self.weights.ml > 0.0,
+        ];
+        sources.iter().filter(|s| **s).count() <= 1
+    }
+
+    pub async fn verify(
+        &self,
+        text: &str,
+        keyword_threats: &[ThreatKind],
+        proximity: Proximity,
+        nationwide: bool,
+    ) -> LlmVerdict {
+        let llm = match &self.llm {
+            Some(llm) if llm.is_enabled() => Some(
+                llm.verify(text, keyword_threats, proximity, nationwide)
+                    .await,
+            ),
+            _ => None,
+        };
+        let ml = self.ml.as_ref().map(|ml| ml.verify(text, keyword_threats));
+
+        if self.single_source() {
+            return llm.or(ml).unwrap_or_else(|| LlmVerdict {
+                threats: keyword_threats.to_vec(),
+                confidence: None,
+            });
+        }
+
+        let mut votes = vec![Vote {
+            weight: self.weights.keyword,
+            threats: keyword_threats.to_vec(),
+            confidence: self.keyword_confidence,
+        }];
+        // Verdicts without a confidence (fail-open, untrained model)
+        // abstain.
+        for (verdict, weight) in [(llm, self.weights.llm), (ml, self.weights.ml)] {
+            if let Some(LlmVerdict {
+                threats,
+                confidence: Some(c),
+            }) = verdict
+            {
+                votes.push(Vote {
+                    weight,
+                    threats,
+                    confidence: f64::from(c),
+                });
+            }
+        }
+        let verdict = combine(&votes, self.threshold);
+        debug!(
+            "Combined verdict {:?} (confidence {:?}) from {} vote(s)",
+            verdict.threats,
+            verdict.confidence,
+            votes.len()
+        );
+        verdict
+    }
+
+    pub fn is_uncertain(&self, confidence: Option<f32>) -> bool {
+        if self.single_source() {
+            return match (&self.llm, &self.ml) {
+                (Some(llm), _) => llm.is_uncertain(confidence),
+                (None, Some(ml)) => ml.is_uncertain(confidence),
+                (None, None) => false,
+            };
+        }
+        confidence.is_some_and(|c| c < self.review_below)
+    }
+
+    /// LLM prompts with no recorded reply (see `LLM_RECORD_MODE`).
+    pub fn replay_misses(&self) -> usize {
+        self.llm.as_ref().map_or(0, |l| l.replay_misses())
+    }
+}
+
+impl fmt::Display for SecondaryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(llm) = &self.llm {
+            parts.push(llm.to_string());
+        }
+        if let Some(ml) = &self.ml {
+            parts.push(format!(
+                "MlFilter(examples={}, threshold={:.2}, review_below={:.2})",
+                ml.model.example_count(),
+                ml.threshold,
+                ml.review_below
+            ));
+        }
+        if parts.is_empty() {
+            return write!(f, "none");
+        }
+        if !self.single_source() {
+            parts.push(format!(
+                "combine(keyword={}, ml={}, llm={}, keyword_confidence={:.2}, threshold={:.2})",
+                self.weights.keyword,
+                self.weights.ml,
+                self.weights.llm,
+                self.keyword_confidence,
+                self.threshold
+            ));
+        }
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naive_bayes_drops_analysis_and_keeps_live_alerts() {
+        let alerts = [
+            "Шахеди курсом на Бровари, будьте в укритті",
+            "Балістика на Київ! Швидкісна ціль",
+            "Група ударних БпЛА над Броварами",
+            "Крилаті ракети курсом на Київ, укриття",
+            "Шахед на Троєщину, працює ППО",
+        ];
+        let analysis = [
+            "Аналітика: скільки шахедів запустили за місяць",
+            "Підсумки ночі: статистика збитих ракет за тиждень",
+            "Прогноз: можливий масований удар на вихідних, аналітика",
+            "Огляд пускових зон балістики, статистика за рік",
+            "Підсумки: місяць шахедних атак у цифрах",
+        ];
+        let model = NaiveBayes::train(
+            alerts
+                .iter()
+                .map(|t| (*t, true))
+                .chain(analysis.iter().map(|t| (*t, false))),
+        );
+        assert_eq!(model.example_count(), 10);
+
+        let ml = MlFilter::new(model, 0.5, 0.65);
+        let live = ml.verify("Шахеди курсом на Бровари", &[ThreatKind::Shahed]);
+        assert_eq!(live.threats, vec![ThreatKind::Shahed]);
+        assert!(live.confidence.unwrap() > 0.5);
+
+        let recap = ml.verify(
+            "Статистика шахедів за місяць: аналітика",
+            &[ThreatKind::Shahed],
+        );
+        assert!(recap.threats.is_empty());
+
+        // An untrained model fails open.
+        let empty = MlFilter::new(NaiveBayes::default(), 0.5, 0.65);
+        let kept = empty.verify("Статистика за місяць", &[ThreatKind::Shahed]);
+        assert_eq!(kept.threats, vec![ThreatKind::Shahed]);
+        assert_eq!(kept.confidence, None);
+    }
+
+    #[test]
+    fn combiner_weighs_keyword_ml_and_llm_votes() {
+        let vote = |weight: f64, threats: &[ThreatKind], confidence: f64| Vote {
+            weight,
+            threats: threats.to_vec(),
+            confidence,
+        };
+        let keyword = vote(1.0, &[ThreatKind::Shahed], 0.7);
+
+        // A confident LLM veto outweighs the keywords…
+        let v = combine(&[keyword.clone(), vote(2.0, &[], 0.9)], 0.5);
+        assert!(v.threats.is_empty());
+        assert!(v.confidence.unwrap() > 0.6);
+
+        // …a hesitant one does not.
+        let v = combine(&[keyword.clone(), vote(2.0, &[], 0.55)], 0.5);
+        assert_eq!(v.threats, vec![ThreatKind::Shahed]);
+
+        // Keywords and ML together overrule the LLM.
+        let v = combine(
+            &[
+                keyword.clone(),
+                vote(2.0, &[], 0.6),
+                vote(1.0, &[ThreatKind::Shahed], 0.95),
+            ],
+            0.5,
+        );
+        assert_eq!(v.threats, vec![ThreatKind::Shahed]);
+        assert!(v.confidence.unwrap() < 0.65, "{v:?}");
+
+        // Zero-weight sources are ignored.
+        let v = combine(&[vote(0.0, &[ThreatKind::Shahed], 1.0)], 0.5);
+        assert!(v.threats.is_empty() && v.confidence.is_none());
+
+        let w = Weights::parse("keyword=0, llm=3, bogus");
+        assert_eq!((w.keyword, w.ml, w.llm), (0.0, 1.0, 3.0));
+    }
+}
@@ -0,0 +1,424 @@
+//! Startup configuration report.  Every setting falls back to a default
+//! when its env var is missing or unparsable, so a typo such as
+//! `DEDUP_WINDOWS_SECS` goes unnoticed.  At startup the values that differ
+//! from the defaults are logged per module (secrets masked), and env vars
+//! that look like settings but are not known are warned about.
+
+use std::collections::BTreeMap;
+
+use tracing::{info, warn};
+
+/// A known setting.
+#[derive(Debug, Clone, Copy)]
+pub struct Setting {
+    pub name: &'static str,
+    /// Effective value when unset; `None` means unset disables or requires
+    /// it.
+    pub default: Option<&'static str>,
+    /// Logged masked.
+    pub secret: bool,
+}
+
+const fn var(name: &'static str, default: &'static str) -> Setting {
+    Setting {
+        name,
+        default: Some(default),
+        secret: false,
+    }
+}
+
+const fn opt(name: &'static str) -> Setting {
+    Setting {
+        name,
+        default: None,
+        secret: false,
+    }
+}
+
+const fn secret(name: &'static str) -> Setting {
+    Setting {
+        name,
+        default: None,
+        secret: true,
+    }
+}
+
+const TELEGRAM: &[Setting] = &[
+    opt("TG_API_ID"),
+    secret("TG_API_HASH"),
+    secret("TG_PHONE"),
+    secret("TG_2FA_PASSWORD"),
+    opt("TG_CHANNELS"),
+    var("TG_FOLDER_SYNC_SECS", "300"),
+    var("TG_SESSION_PATH", "./telegram.session.sqlite"),
+    var("TG_TEST_MODE", "false"),
+    opt("TG_TEST_DC"),
+    var("TG_PEER_CACHE_PATH", "./channel_peers.sqlite"),
+    var("TG_PEER_CACHE_TTL_HOURS", "24"),
+    var("UPDATE_QUEUE_LIMIT", "2048"),
+    var("GAP_ALERT_THRESHOLD", "20"),
+    var("GAP_BACKFILL", "false"),
+    var("ACCOUNT_HEALTH_INTERVAL_SECS", "600"),
+    opt("PRIORITY_CHANNELS"),
+    opt("LOW_PRIORITY_CHANNELS"),
+    var("PRIORITY_MAX_WAIT_SECS", "30"),
+];
+
+const BOT: &[Setting] = &[
+    secret("BOT_TOKEN"),
+    secret("BOT_TOKENS"),
+    var("BOT_DB_PATH", "./bot_subscribers.sqlite"),
+    var("BOT_RATE_LIMIT", "10"),
+    var("BOT_AUTO_BAN_STRIKES", "3"),
+    var("BOT_AUTO_BAN_SECS", "86400"),
+    var("BOT_STATS_TZ_OFFSET_MINUTES", "0"),
+    var("BOT_EVENTS_RETENTION_DAYS", "90"),
+    var("NOISY_NIGHT_ALERTS", "5"),
+    opt("ADMIN_CHAT_IDS"),
+    opt("STATUS_PIN_CHATS"),
+    var("STATUS_ACTIVE_SECS", "1800"),
+    var("REMINDER_AFTER_MINS", "0"),
+    var("REMINDER_MAX", "3"),
+    opt("FEEDBACK_CHAT_ID"),
+    var("FEEDBACK_CORPUS_PATH", "./feedback_corpus.jsonl"),
+    var("MORNING_SUMMARY_TIME", "07:00"),
+    var("MORNING_SUMMARY_TZ_OFFSET_MINUTES", "0"),
+    var("MORNING_SUMMARY_LOOKBACK_HOURS", "12"),
+    var("MORNING_SUMMARY_LLM", "false"),
+    var("ERROR_ALERT_THRESHOLD", "3"),
+    var("ERROR_ALERT_WINDOW_SECS", "600"),
+];
+
+const FILTER: &[Setting] = &[
+    opt("MY_OBLAST"),
+    opt("MY_CITY"),
+    opt("MY_DISTRICT"),
+    opt("MY_LAT"),
+    opt("MY_LON"),
+    var("MY_RADIUS_KM", "50"),
+    var("DEDUP_WINDOW_SECS", "180"),
+    opt("DEDUP_WINDOW_SHAHED_SECS"),
+    opt("DEDUP_WINDOW_BALLISTIC_SECS"),
+    var("NEAR_DUPLICATE_SIMILARITY", "0.9"),
+    opt("FILTER_STATE_PATH"),
+    var("NAVAL_WATCH_HOURS", "6"),
+    var("DEDUP_BY_DIRECTION", "true"),
+    var("CONTEXT_WINDOW_SECS", "300"),
+    var("WARMUP_SECS", "900"),
+    opt("KEYWORDS_PATH"),
+    opt("URGENCY_KEYWORDS"),
+    opt("EXTRA_URGENCY_KEYWORDS"),
+    opt("SUPPRESS_KEYWORDS"),
+    var("KEYWORDS_RELOAD_SECS", "30"),
+    opt("CHANNEL_CONTEXT"),
+    var("FILTER_MAX_CHANNELS", "500"),
+    var("URGENT_COOLDOWN_SECS", "20"),
+    var("NEGATIVE_STATUS_COOLDOWN_SECS", "120"),
+    var("FORWARD_ALL_THREATS", "false"),
+    opt("NATIONWIDE_REDUCED_KINDS"),
+    opt("NATIONWIDE_REDUCED_OBLASTS"),
+    var("SANITIZE_ALERT_TEXT", "false"),
+    opt("RECAP_CHANNELS"),
+    var("CORRIDOR_WINDOW_SECS", "300"),
+    var("SHAHED_TRACK_WINDOW_SECS", "1800"),
+    var("TRAJECTORY_WINDOW_SECS", "1800"),
+    var("WAVE_IDLE_SECS", "3600"),
+    opt("THREAT_EXPIRY_MINS"),
+    var("MIN_CONFIDENCE", "0"),
+    var("SHOW_CONFIDENCE", "false"),
+    var("CORROBORATION_CHANNELS", "0"),
+    var("CORROBORATION_WINDOW_SECS", "300"),
+    var("DAMAGE_CORRELATION_WINDOW_SECS", "10800"),
+    opt("MAX_MESSAGE_AGE_SECS"),
+    opt("STALE_MESSAGE_POLICY"),
+    opt("SECONDARY_FILTER"),
+    var("COMBINE_WEIGHTS", "keyword=1,ml=1,llm=2"),
+    var("KEYWORD_CONFIDENCE", "0.7"),
+    var("COMBINE_THRESHOLD", "0.5"),
+    var("COMBINE_REVIEW_BELOW", "0.65"),
+    var("ML_CORPUS_PATH", "./feedback_corpus.jsonl"),
+    var("ML_THRESHOLD", "0.5"),
+    var("ML_REVIEW_BELOW", "0.65"),
+    var("ML_MIN_EXAMPLES", "20"),
+    var("ACTIVITY_SPIKE_FACTOR", "0"),
+    var("ACTIVITY_WINDOW_SECS", "300"),
+    var("ACTIVITY_BASELINE_MINS", "60"),
+    var("ACTIVITY_MIN_MESSAGES", "15"),
+    var("ACTIVITY_MIN_CHANNELS", "3"),
+    var("ACTIVITY_COOLDOWN_SECS", "1800"),
+    var("KEYWORD_SUGGEST_INTERVAL_HOURS", "0"),
+    var("KEYWORD_SUGGEST_LOOKBACK_HOURS", "24"),
+    var("KEYWORD_SUGGEST_MAX_MESSAGES", "200"),
+    var("KEYWORD_SUGGEST_MIN_COUNT", "3"),
+    var("KEYWORD_SUGGEST_PATH", "keyword_suggestions.md"),
+];
+
+const LLM: &[Setting] = &[
+    var("LLM_ENABLED", "false"),
+    var("LLM_MODEL", "qwen2.5:7b"),
+    var("LLM_ENDPOINT", "http://127.0.0.1:11434"),
+    var("LLM_TIMEOUT_MS", "3000"),
+    var("LLM_REVIEW_CONFIDENCE_MIN", "0.4"),
+    var("LLM_REVIEW_CONFIDENCE_MAX", "0.7"),
+    var("LLM_RECORD_MODE", "off"),
+    var("LLM_RECORDINGS_PATH", "llm_recordings.jsonl"),
+    var("REVIEW_TRUST_MIN_DECISIONS", "5"),
+    var("REVIEW_LOW_TRUST", "0.3"),
+];
+
+const OUTPUT: &[Setting] = &[
+    var("RUN_MODE", "live"),
+    opt("OUTPUT_FORMAT"),
+    var("OUTPUT_SUPPRESSED", "false"),
+    var("DUMP_OUTPUT_PATH", "./dump_today.jsonl"),
+    var("DUMP_TZ_OFFSET_MINUTES", "0"),
+    opt("LIVE_DUMP_DIR"),
+    var("LIVE_DUMP_COMPRESS", "none"),
+    var("LIVE_DUMP_RETENTION_DAYS", "0"),
+    opt("DECISIONS_LOG_DIR"),
+    var("TUI_LOG_PATH", "./tui.log"),
+    var("DECISIONS_LOG_COMPRESS", "none"),
+    var("DECISIONS_LOG_RETENTION_DAYS", "0"),
+    opt("ARCHIVE_DB_PATH"),
+    var("ARCHIVE_WAVE_GAP_SECS", "3600"),
+    var("ARCHIVE_RETENTION_DAYS", "0"),
+    opt("WEBHOOK_URL"),
+    secret("WEBHOOK_SECRET"),
+    opt("ALERT_ROUTES"),
+    opt("ROUTE_EXEC_CMD"),
+    var("SINK_SLO_SECS", "telegram=10,webhook=10,voice=30"),
+    var("SINK_SLO_WINDOW", "20"),
+    opt("HOOK_PRE_FILTER"),
+    opt("HOOK_POST_DETECTION"),
+    opt("HOOK_PRE_BROADCAST"),
+    var("HOOK_TIMEOUT_MS", "2000"),
+    opt("WASM_PLUGINS"),
+    var("WASM_PLUGIN_FUEL", "10000000"),
+    var("WASM_PLUGIN_MEMORY_MB", "16"),
+    secret("ESCALATION_PHONES"),
+    var("ESCALATION_COOLDOWN_SECS", "900"),
+    secret("TWILIO_ACCOUNT_SID"),
+    secret("TWILIO_AUTH_TOKEN"),
+    secret("TWILIO_FROM_NUMBER"),
+    opt("ESCALATION_WEBHOOK_URL"),
+    secret("ESCALATION_WEBHOOK_TOKEN"),
+    opt("TTS_COMMAND"),
+    opt("TTS_API_URL"),
+    secret("TTS_API_TOKEN"),
+    var("TTS_TIMEOUT_SECS", "20"),
+];
+
+const REPLAY: &[Setting] = &[
+    opt("REPLAY_INPUT_PATH"),
+    var("REPLAY_SPEED", "1.0"),
+    opt("REPLAY_STEP_MS"),
+    var("REPLAY_MIN_DELAY_MS", "0"),
+    var("REPLAY_MAX_DELAY_MS", "10000"),
+    var("REPLAY_BROADCAST", "false"),
+    opt("REPLAY_FROM_LINE"),
+    opt("REPLAY_TO_LINE"),
+    opt("REPLAY_LIMIT"),
+    opt("REPLAY_EXPECTED_PATH"),
+    var("REPLAY_UPDATE_EXPECTED", "false"),
+    opt("REPLAY_METRICS_PATH"),
+];
+
+/// Known settings by module.
+pub const SETTINGS: &[(&str, &[Setting])] = &[
+    ("telegram", TELEGRAM),
+    ("bot", BOT),
+    ("filter", FILTER),
+    ("llm", LLM),
+    ("output", OUTPUT),
+    ("replay", REPLAY),
+];
+
+/// Env vars with these prefixes are expected to be settings.
+const CHECKED_PREFIXES: &[&str] = &["TG_", "LLM_", "REPLAY_"];
+
+/// Unknown env vars this close to a known name are flagged as typos
+/// whatever their prefix.
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// A setting set to something other than its default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub name: &'static str,
+    /// Value as set, masked for secrets.
+    pub value: String,
+    pub default: Option<&'static str>,
+}
+
+/// What [`report`] found.
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigReport {
+    /// Non-default settings by module.
+    pub changed: BTreeMap<&'static str, Vec<Override>>,
+    /// Unknown env vars and the known name they probably meant.
+    pub unknown: Vec<(String, Option<&'static str>)>,
+}
+
+fn lookup(name: &str) -> Option<(&'static str, &'static Setting)> {
+    SETTINGS.iter().find_map(|(module, settings)| {
+        settings
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| (*module, s))
+    })
+}
+
+/// `value` as the setting reads it, so `1`/`true` and `180`/`180.0` compare
+/// equal.
+fn normalize(value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "1" | "true" | "yes" => return "true".into(),
+        "0" | "false" | "no" => return "false".into(),
+        _ => {}
+    }
+    match value.parse::<f64>() {
+        Ok(n) => n.to_string(),
+        Err(_) => value,
+    }
+}
+
+fn mask(value: &str) -> String {
+    if value.trim().is_empty() {
+        String::new()
+    } else {
+        "***".into()
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diag + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// Closest known setting name within [`MAX_TYPO_DISTANCE`].
+fn closest_setting(name: &str) -> Option<&'static str> {
+    SETTINGS
+        .iter()
+        .flat_map(|(_, settings)| settings.iter())
+        .map(|s| (edit_distance(name, s.name), s.name))
+        .filter(|(d, _)| *d <= MAX_TYPO_DISTANCE)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, name)| name)
+}
+
+/// Compare the environment `vars` against [`SETTINGS`].
+pub fn report(vars: impl IntoIterator<Item = (String, String)>) -> ConfigReport {
+    let mut out = ConfigReport::default();
+    let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+    vars.sort();
+    for (name, value) in vars {
+        match lookup(&name) {
+            Some((module, setting)) => {
+                let default_value = setting.default.map(normalize).unwrap_or_default();
+                if normalize(&value) == default_value {
+                    continue;
+                }
+                let value = if setting.secret {
+                    mask(&value)
+                } else {
+                    value.trim().to_string()
+                };
+                out.changed.entry(module).or_default().push(Override {
+                    name: setting.name,
+                    value,
+                    default: setting.default,
+                });
+            }
+            None => {
+                let suggestion = closest_setting(&name);
+                if suggestion.is_some() || CHECKED_PREFIXES.iter().any(|p| name.starts_with(p)) {
+                    out.unknown.push((name, suggestion));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Log the non-default settings and warn about unknown ones.
+pub fn log_startup_config() {
+    let report = report(std::env::vars());
+    let changed: usize = report.changed.values().map(Vec::len).sum();
+    info!("Configuration: {changed} setting(s) differ from the defaults");
+    for (module, settings) in &report.changed {
+        let line = settings
+            .iter()
+            .map(|o| match o.default {
+                Some(default) => format!("{}={} (default {default})", o.name, o.value),
+                None => format!("{}={}", o.name, o.value),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        info!("  [{module}] {line}");
+    }
+    for (name, suggestion) in &report.unknown {
+        match suggestion {
+            Some(known) => warn!("Unknown setting {name} is ignored; did you mean {known}?"),
+            None => warn!("Unknown setting {name} is ignored"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_report_lists_overrides_and_flags_typos() {
+        let vars = [
+            ("DEDUP_WINDOW_SECS", "180.0"),
+            ("LLM_ENABLED", "1"),
+            ("LLM_TIMEOUT_MS", "5000"),
+            ("BOT_TOKEN", "123:secret"),
+            ("DEDUP_WINDOWS_SECS", "600"),
+            ("TG_CHANELS", "@a"),
+            ("REPLAY_FOO", "x"),
+            ("HOME", "/root"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let report = report(vars);
+
+        let set = |name, value: &str, default| Override {
+            name,
+            value: value.to_string(),
+            default,
+        };
+        assert_eq!(
+            report.changed["llm"],
+            vec![
+                set("LLM_ENABLED", "1", Some("false")),
+                set("LLM_TIMEOUT_MS", "5000", Some("3000")),
+            ]
+        );
+        assert_eq!(report.changed["bot"], vec![set("BOT_TOKEN", "***", None)]);
+        assert!(
+            !report.changed.contains_key("filter"),
+            "180.0 is the default"
+        );
+        assert_eq!(
+            report.unknown,
+            vec![
+                ("DEDUP_WINDOWS_SECS".to_string(), Some("DEDUP_WINDOW_SECS")),
+                ("REPLAY_FOO".to_string(), None),
+                ("TG_CHANELS".to_string(), Some("TG_CHANNELS")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Decisions log: in live mode every message the filter saw is appended,
+//! with what it decided and why, to `<DECISIONS_LOG_DIR>/<YYYY-MM-DD>.jsonl`
+//! (rotated like the live dump).  Each line carries the replay dump fields
+//! and the `decision` / `alert` fields of a golden file, so a day of
+//! production traffic can be replayed as-is (`REPLAY_INPUT_PATH`) or taken
+//! as the accepted output to regress against (`REPLAY_EXPECTED_PATH`).
+
+use crate::archive::ArchivedMessage;
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{AlertInfo, ReviewCandidate};
+use serde::{Deserialize, Serialize};
+
+/// What the secondary filter (LLM / classifier) said about the message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub keyword_threats: Vec<String>,
+    pub llm_threats: Vec<String>,
+    pub confidence: Option<f32>,
+}
+
+impl From<&ReviewCandidate> for Verdict {
+    fn from(candidate: &ReviewCandidate) -> Self {
+        let names = |threats: &[ThreatKind]| {
+            threats
+                .iter()
+                .map(|t| t.variant_name().to_string())
+                .collect()
+        };
+        Self {
+            keyword_threats: names(&candidate.keyword_threats),
+            llm_threats: names(&candidate.llm_threats),
+            confidence: candidate.confidence,
+        }
+    }
+}
+
+/// One decisions-log line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub channel_title: String,
+    /// Raw channel text, as the filter saw it.
+    pub text: String,
+    /// `"forwarded"` or `"suppressed"`.
+    pub decision: String,
+    /// Suppression reason (metrics label); `None` when forwarded.
+    pub reason: Option<String>,
+    pub threats: Vec<String>,
+    pub proximity: Option<String>,
+    pub nationwide: bool,
+    /// Rendered alert, `None` when suppressed.
+    pub alert: Option<String>,
+    /// `None` when the secondary filter did not run.
+    pub verdict: Option<Verdict>,
+}
+
+impl DecisionRecord {
+    /// The decision on `msg`: forwarded when `alert` is set, otherwise
+    /// suppressed for `reason`.
+    pub fn new(
+        msg: &ArchivedMessage,
+        info: Option<&AlertInfo>,
+        alert: Option<&str>,
+        reason: &str,
+        verdict: Option<&ReviewCandidate>,
+    ) -> Self {
+        let forwarded = alert.is_some();
+        Self {
+            timestamp: msg.timestamp,
+            channel_id: msg.channel_id,
+            channel_title: msg.channel_title.clone(),
+            text: msg.text.clone(),
+            decision: if forwarded { "forwarded" } else { "suppressed" }.into(),
+            reason: (!forwarded).then(|| reason.to_string()),
+            threats: info
+                .map(|i| i.threats.iter().map(|t| t.variant_name().into()).collect())
+                .unwrap_or_default(),
+            proximity: info.map(|i| i.proximity.name().into()),
+            nationwide: info.is_some_and(|i| i.nationwide),
+            alert: alert.map(str::to_string),
+            verdict: verdict.map(Verdict::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{AlertType, Proximity};
+
+    fn msg(text: &str) -> ArchivedMessage {
+        ArchivedMessage {
+            timestamp: 1_700_000_000,
+            channel_id: 7,
+            channel_title: "ch".into(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn decision_records_carry_dump_and_golden_fields() {
+        let shahed = msg("Шахед на Київ");
+        let info = AlertInfo {
+            alert_type: AlertType::New,
+            threats: vec![ThreatKind::Shahed],
+            proximity: Proximity::City,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        };
+        let alert = "🛵 Шахед на Київ";
+        let record = DecisionRecord::new(&shahed, Some(&info), Some(alert), "-", None);
+        assert_eq!(record.decision, "forwarded");
+        assert_eq!(record.reason, None);
+        assert_eq!(record.threats, vec!["Shahed"]);
+        assert_eq!(record.proximity.as_deref(), Some("city"));
+
+        let line = serde_json::to_string(&record).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        // Replay reads the dump fields, golden runs the decision and alert.
+        assert_eq!(json["text"], "Шахед на Київ");
+        assert_eq!(json["channel_title"], "ch");
+        assert_eq!(json["alert"], alert);
+        assert_eq!(
+            serde_json::from_str::<DecisionRecord>(&line).unwrap(),
+            record
+        );
+
+        let record = DecisionRecord::new(&msg("Тихо"), None, None, "no_threat", None);
+        assert_eq!(record.decision, "suppressed");
+        assert_eq!(record.reason.as_deref(), Some("no_threat"));
+        assert!(record.threats.is_empty() && record.proximity.is_none());
+        assert!(record.alert.is_none() && record.verdict.is_none());
+    }
+}
@@ -0,0 +1,510 @@
+//! Error taxonomy for the delivery pipeline.  Failures from the Bot API, the
+//! LLM endpoint and SQLite are sorted into a few classes; each class has one
+//! retry policy, and hard failures (revoked bot token, locked database) that
+//! keep happening are escalated to admins instead of scrolling by as
+//! warnings.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, warn};
+
+/// SQLite result codes `SQLITE_BUSY` / `SQLITE_LOCKED`.
+const SQLITE_BUSY: isize = 5;
+const SQLITE_LOCKED: isize = 6;
+
+/// Longest `retry_after` we are willing to sleep through inline.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+/// A non-success Bot API reply, with Telegram's `error_code`/`description`.
+#[derive(Debug, Clone)]
+pub struct BotApiError {
+    pub method: String,
+    pub status: u16,
+    pub description: String,
+    /// `parameters.retry_after` of a 429 reply.
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct BotApiErrorBody {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    parameters: Option<BotApiErrorParams>,
+}
+
+#[derive(Deserialize)]
+struct BotApiErrorParams {
+    retry_after: Option<u64>,
+}
+
+impl BotApiError {
+    pub fn from_response(method: &str, status: u16, body: &str) -> Self {
+        let parsed: Option<BotApiErrorBody> = serde_json::from_str(body).ok();
+        Self {
+            method: method.to_string(),
+            status,
+            description: parsed
+                .as_ref()
+                .map(|b| b.description.clone())
+                .filter(|d| !d.is_empty())
+                .unwrap_or_else(|| body.to_string()),
+            retry_after: parsed
+                .and_then(|b| b.parameters)
+                .and_then(|p| p.retry_after),
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self.status {
+            429 => ErrorClass::RateLimited(Duration::from_secs(self.retry_after.unwrap_or(1))),
+            401 => ErrorClass::Auth,
+            403 => ErrorClass::Recipient,
+            400 if self.description.contains("chat not found")
+                || self.description.contains("user is deactivated") =>
+            {
+                ErrorClass::Recipient
+            }
+            500.. => ErrorClass::Transient,
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+impl fmt::Display for BotApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} failed: {} {}",
+            self.method, self.status, self.description
+        )
+    }
+}
+
+impl std::error::Error for BotApiError {}
+
+/// What went wrong, as far as retrying and alerting are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Connection reset, 5xx, … – likely to succeed on retry.
+    Transient,
+    /// The request timed out; retrying would only double the delay.
+    Timeout,
+    /// HTTP 429 with the server-suggested wait.
+    RateLimited(Duration),
+    /// SQLite is busy/locked by another connection or process.
+    DbLocked,
+    /// Credentials rejected (Bot API 401, revoked token).
+    Auth,
+    /// The recipient is gone: bot blocked, chat deleted or not found.
+    Recipient,
+    /// Anything else: bad request, unparsable reply, I/O error, …
+    Permanent,
+}
+
+impl ErrorClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Transient => "transient",
+            Self::Timeout => "timeout",
+            Self::RateLimited(_) => "rate_limited",
+            Self::DbLocked => "db_locked",
+            Self::Auth => "auth",
+            Self::Recipient => "recipient",
+            Self::Permanent => "permanent",
+        }
+    }
+
+    /// Failures that will not go away on their own and need an admin.
+    pub fn is_hard(&self) -> bool {
+        matches!(self, Self::Auth | Self::DbLocked)
+    }
+
+    /// Delay before each retry; empty means "do not retry".
+    pub fn retry_delays(&self) -> Vec<Duration> {
+        match self {
+            Self::Transient => vec![Duration::from_millis(500), Duration::from_secs(2)],
+            Self::RateLimited(wait) if *wait <= MAX_RATE_LIMIT_WAIT => vec![*wait],
+            Self::DbLocked => vec![Duration::from_millis(100), Duration::from_millis(500)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// What an admin should do about a hard failure.
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            Self::Auth => {
+                "The Bot API rejected the token – it was probably revoked. \
+                 Issue a new one with @BotFather, update TELEGRAM_BOT_TOKEN and restart."
+            }
+            Self::DbLocked => {
+                "The SQLite database stays locked. Check for another tg-osint \
+                 instance or a tool holding the database file open."
+            }
+            _ => "Check the logs for details.",
+        }
+    }
+}
+
+/// Classify an error from the Bot API, the LLM endpoint or SQLite.
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<BotApiError>() {
+            return e.class();
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                return ErrorClass::Timeout;
+            }
+            return match e.status().map(|s| s.as_u16()) {
+                Some(429) => ErrorClass::RateLimited(Duration::from_secs(1)),
+                Some(401) => ErrorClass::Auth,
+                Some(500..) => ErrorClass::Transient,
+                Some(_) => ErrorClass::Permanent,
+                // Connection refused/reset before any reply.
+                None if e.is_connect() || e.is_request() => ErrorClass::Transient,
+                None => ErrorClass::Permanent,
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<sqlite::Error>() {
+            return match e.code {
+                Some(SQLITE_BUSY | SQLITE_LOCKED) => ErrorClass::DbLocked,
+                _ => ErrorClass::Permanent,
+            };
+        }
+    }
+    ErrorClass::Permanent
+}
+
+/// Run `op`, retrying according to the class of each failure.  `what`
+/// names the operation in logs.
+pub async fn with_retry<T, F, Fut>(what: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let err = match op().await {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        let class = classify(&err);
+        let Some(delay) = class.retry_delays().get(attempt).copied() else {
+            return Err(err);
+        };
+        attempt += 1;
+        debug!(
+            "{what}: {} error, retry {attempt} in {}ms: {err}",
+            class.name(),
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Counts hard failures per (component, class) and decides when admins
+/// hear about them.
+#[derive(Debug)]
+pub struct FailureMonitor {
+    threshold: usize,
+    window: Duration,
+    failures: HashMap<(&'static str, ErrorClass), Vec<Instant>>,
+    notified: HashMap<(&'static str, ErrorClass), Instant>,
+}
+
+impl FailureMonitor {
+    /// | Env var                   | Default | Description                           |
+    /// |---------------------------|---------|---------------------------------------|
+    /// | `ERROR_ALERT_THRESHOLD`   | `3`     | Hard failures before admins are told  |
+    /// | `ERROR_ALERT_WINDOW_SECS` | `600`   | Counting window / re-notify interval  |
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("ERROR_ALERT_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            Duration::from_secs(
+                std::env::var("ERROR_ALERT_WINDOW_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(600),
+            ),
+        )
+    }
+
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            failures: HashMap::new(),
+            notified: HashMap::new(),
+        }
+    }
+
+    /// Log `err` at a level matching its class.  Returns the admin
+    /// notification when a hard failure crossed the threshold and admins
+    /// were not told about it within the window.
+    pub fn report(&mut self, component: &'static str, err: &anyhow::Error) -> Option<String> {
+        self.report_at(component, err, Instant::now())
+    }
+
+    /// [`report`](Self::report) at `now`.
+    fn report_at(
+        &mut self,
+        component: &'static str,
+        err: &anyhow::Error,
+        now: Instant,
+    ) -> Option<String> {
+        let class = classify(err);
+        match class {
+            ErrorClass::Recipient => {
+                debug!("{component}: recipient unavailable: {err}");
+                return None;
+            }
+            c if c.is_hard() => error!("{component}: {} failure: {err}", c.name()),
+            c => {
+                warn!("{component}: {} failure: {err}", c.name());
+                return None;
+            }
+        }
+
+        let key = (component, class);
+        let recent = self.failures.entry(key).or_default();
+        recent.retain(|at| now.duration_since(*at) < self.window);
+        recent.push(now);
+        if recent.len() < self.threshold
+            || self
+                .notified
+                .get(&key)
+                .is_some_and(|at| now.duration_since(*at) < self.window)
+        {
+            return None;
+        }
+        let count = recent.len();
+        self.notified.insert(key, now);
+        Some(format!(
+            "🚨 {component}: {count} {} failure(s) in the last {} min\n{err}\n\n{}",
+            class.name(),
+            self.window.as_secs() / 60,
+            class.remediation()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn api(status: u16, body: &str) -> BotApiError {
+        BotApiError::from_response("sendMessage", status, body)
+    }
+
+    fn sqlite_error(code: isize) -> anyhow::Error {
+        anyhow::Error::new(sqlite::Error {
+            code: Some(code),
+            message: Some("database is locked".into()),
+        })
+    }
+
+    #[test]
+    fn bot_api_replies_are_classified() {
+        let limited = api(
+            429,
+            r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 7","parameters":{"retry_after":7}}"#,
+        );
+        assert_eq!(limited.retry_after, Some(7));
+        assert_eq!(limited.description, "Too Many Requests: retry after 7");
+        assert_eq!(
+            limited.class(),
+            ErrorClass::RateLimited(Duration::from_secs(7))
+        );
+        assert_eq!(
+            api(429, "{}").class(),
+            ErrorClass::RateLimited(Duration::from_secs(1))
+        );
+        assert_eq!(
+            api(401, r#"{"description":"Unauthorized"}"#).class(),
+            ErrorClass::Auth
+        );
+        assert_eq!(
+            api(
+                403,
+                r#"{"description":"Forbidden: bot was blocked by the user"}"#
+            )
+            .class(),
+            ErrorClass::Recipient
+        );
+        assert_eq!(
+            api(400, r#"{"description":"Bad Request: chat not found"}"#).class(),
+            ErrorClass::Recipient
+        );
+        assert_eq!(
+            api(400, r#"{"description":"Bad Request: message is too long"}"#).class(),
+            ErrorClass::Permanent
+        );
+        let gateway = api(502, "<html>Bad Gateway</html>");
+        assert_eq!(
+            gateway.description, "<html>Bad Gateway</html>",
+            "raw body kept"
+        );
+        assert_eq!(gateway.class(), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn error_chains_are_classified_by_their_cause() {
+        let wrapped = Err::<(), _>(api(401, "{}"))
+            .context("broadcast failed")
+            .unwrap_err();
+        assert_eq!(classify(&wrapped), ErrorClass::Auth);
+        assert_eq!(classify(&sqlite_error(SQLITE_BUSY)), ErrorClass::DbLocked);
+        assert_eq!(
+            classify(&sqlite_error(SQLITE_LOCKED).context("saving subscriber")),
+            ErrorClass::DbLocked
+        );
+        assert_eq!(classify(&sqlite_error(1)), ErrorClass::Permanent);
+        assert_eq!(
+            classify(&anyhow::anyhow!("bad JSON")),
+            ErrorClass::Permanent
+        );
+    }
+
+    #[tokio::test]
+    async fn request_timeouts_are_classified() {
+        // Accepts the connection but never replies.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _server = tokio::spawn(async move {
+            let _conn = listener.accept().await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+        let err = reqwest::Client::new()
+            .get(format!("http://{addr}/"))
+            .timeout(Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err();
+        let err = anyhow::Error::new(err).context("LLM request");
+        assert_eq!(classify(&err), ErrorClass::Timeout);
+        assert!(ErrorClass::Timeout.retry_delays().is_empty());
+    }
+
+    #[test]
+    fn retry_delays_depend_on_the_class() {
+        assert_eq!(ErrorClass::Transient.retry_delays().len(), 2);
+        assert_eq!(ErrorClass::DbLocked.retry_delays().len(), 2);
+        assert_eq!(
+            ErrorClass::RateLimited(Duration::from_secs(30)).retry_delays(),
+            [Duration::from_secs(30)]
+        );
+        // Longer waits are not slept through inline.
+        assert!(
+            ErrorClass::RateLimited(Duration::from_secs(31))
+                .retry_delays()
+                .is_empty()
+        );
+        for class in [
+            ErrorClass::Timeout,
+            ErrorClass::Auth,
+            ErrorClass::Recipient,
+            ErrorClass::Permanent,
+        ] {
+            assert!(class.retry_delays().is_empty(), "{class:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn with_retry_retries_only_transient_failures() {
+        let calls = AtomicUsize::new(0);
+        let result: Result<()> = with_retry("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow::Error::new(api(400, "{}")))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "permanent: no retry");
+
+        let calls = AtomicUsize::new(0);
+        let result = with_retry("test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(sqlite_error(SQLITE_BUSY)),
+                n => Ok(n),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 1);
+
+        let calls = AtomicUsize::new(0);
+        let result: Result<()> = with_retry("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(sqlite_error(SQLITE_LOCKED))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3, "one call plus two retries");
+    }
+
+    #[test]
+    fn failure_monitor_notifies_once_per_window_past_the_threshold() {
+        let mut monitor = FailureMonitor::new(3, Duration::from_secs(600));
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+        let auth = || anyhow::Error::new(api(401, r#"{"description":"Unauthorized"}"#));
+
+        // Soft failures and gone recipients never notify.
+        for i in 0..5 {
+            assert!(
+                monitor
+                    .report_at("bot", &anyhow::Error::new(api(502, "{}")), at(i))
+                    .is_none()
+            );
+            assert!(
+                monitor
+                    .report_at("bot", &anyhow::Error::new(api(403, "{}")), at(i))
+                    .is_none()
+            );
+        }
+
+        assert!(monitor.report_at("bot", &auth(), at(0)).is_none());
+        assert!(monitor.report_at("bot", &auth(), at(100)).is_none());
+        // Another component counts separately.
+        assert!(monitor.report_at("llm", &auth(), at(100)).is_none());
+        let notice = monitor
+            .report_at("bot", &auth(), at(200))
+            .expect("third failure");
+        assert!(
+            notice.starts_with("🚨 bot: 3 auth failure(s) in the last 10 min"),
+            "{notice}"
+        );
+        assert!(notice.contains("@BotFather"));
+
+        // Told already: quiet until the window has passed.
+        assert!(monitor.report_at("bot", &auth(), at(300)).is_none());
+        assert!(monitor.report_at("bot", &auth(), at(790)).is_none());
+        assert!(monitor.report_at("bot", &auth(), at(800)).is_some());
+
+        // Failures older than the window no longer count.
+        let mut monitor = FailureMonitor::new(2, Duration::from_secs(600));
+        assert!(
+            monitor
+                .report_at("db", &sqlite_error(SQLITE_BUSY), at(0))
+                .is_none()
+        );
+        assert!(
+            monitor
+                .report_at("db", &sqlite_error(SQLITE_BUSY), at(600))
+                .is_none()
+        );
+        assert!(
+            monitor
+                .report_at("db", &sqlite_error(SQLITE_BUSY), at(700))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Voice-call escalation: rings configured phone numbers for critical alerts
+//! (ballistic threats to the area, anything at district level), since a
+//! muted phone at 3am never shows a text alert.
+//!
+//! Two providers are supported: Twilio's Calls API, and a generic JSON
+//! webhook for a self-hosted SIP/voice bridge.  Telegram calls from the
+//! userbot are not possible – grammers has no VoIP support.
+
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{AlertInfo, Proximity};
+use anyhow::{Result, anyhow};
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Where calls are placed.
+#[derive(Debug, Clone)]
+pub enum CallProvider {
+    Twilio {
+        account_sid: String,
+        auth_token: String,
+        from: String,
+    },
+    Webhook {
+        url: String,
+        token: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct EscalationCfg {
+    pub provider: CallProvider,
+    /// E.164 numbers to ring.
+    pub phones: Vec<String>,
+    /// Minimum delay between escalations, so one wave rings once.
+    pub cooldown: Duration,
+}
+
+impl EscalationCfg {
+    /// `None` unless phones and a provider are configured.  Twilio wins when
+    /// both providers are set.
+    ///
+    /// | Env var                     | Default | Description                          |
+    /// |-----------------------------|---------|--------------------------------------|
+    /// | `ESCALATION_PHONES`         | —       | Comma-separated numbers to call      |
+    /// | `ESCALATION_COOLDOWN_SECS`  | `900`   | Minimum delay between escalations    |
+    /// | `TWILIO_ACCOUNT_SID`        | —       | Twilio account SID                   |
+    /// | `TWILIO_AUTH_TOKEN`         | —       | Twilio auth token                    |
+    /// | `TWILIO_FROM_NUMBER`        | —       | Twilio caller number                 |
+    /// | `ESCALATION_WEBHOOK_URL`    | —       | Generic voice webhook (JSON POST)    |
+    /// | `ESCALATION_WEBHOOK_TOKEN`  | —       | Optional bearer token for the hook   |
+    pub fn from_env() -> Option<Self> {
+        let var = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let phones: Vec<String> = var("ESCALATION_PHONES")?
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        if phones.is_empty() {
+            return None;
+        }
+
+        let provider = match (
+            var("TWILIO_ACCOUNT_SID"),
+            var("TWILIO_AUTH_TOKEN"),
+            var("TWILIO_FROM_NUMBER"),
+        ) {
+            (Some(account_sid), Some(auth_token), Some(from)) => CallProvider::Twilio {
+                account_sid,
+                auth_token,
+                from,
+            },
+            _ => CallProvider::Webhook {
+                url: var("ESCALATION_WEBHOOK_URL")?,
+                token: var("ESCALATION_WEBHOOK_TOKEN"),
+            },
+        };
+
+        let cooldown_secs: u64 = var("ESCALATION_COOLDOWN_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900);
+        Some(Self {
+            provider,
+            phones,
+            cooldown: Duration::from_secs(cooldown_secs),
+        })
+    }
+}
+
+/// Ballistic/hypersonic threats to the area (or nationwide), or any threat
+/// at district level.  Reduced-severity nationwide alerts never qualify.
+pub fn is_critical(info: &AlertInfo) -> bool {
+    if info.threats.is_empty() || info.threats.contains(&ThreatKind::AllClear) || info.reduced {
+        return false;
+    }
+    // Explosions already happened; only an incoming threat warrants a call.
+    if info.threats.iter().all(|t| *t == ThreatKind::Explosion) {
+        return false;
+    }
+    let ballistic = info
+        .threats
+        .iter()
+        .any(|t| matches!(t, ThreatKind::Ballistic | ThreatKind::Hypersonic));
+    let in_area = info.proximity != Proximity::None || info.nationwide;
+    (ballistic && in_area) || info.proximity == Proximity::District
+}
+
+/// Text read out on the call (and in `/voice` messages).
+pub fn spoken_message(info: &AlertInfo) -> String {
+    let threats = info
+        .threats
+        .iter()
+        .map(|t| t.label())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let area = if info.nationwide {
+        "вся Україна"
+    } else {
+        match info.proximity {
+            Proximity::District => "ваш район",
+            Proximity::City => "ваше місто",
+            Proximity::Oblast => "ваша область",
+            Proximity::None => "",
+        }
+    };
+    if area.is_empty() {
+        format!("Увага! {threats}. Негайно пройдіть в укриття.")
+    } else {
+        format!("Увага! {threats}: {area}. Негайно пройдіть в укриття.")
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Twilio call instructions reading `message` out three times.
+fn twiml(message: &str) -> String {
+    let say = xml_escape(message);
+    format!("<Response><Say language=\"uk-UA\" loop=\"3\">{say}</Say></Response>")
+}
+
+#[derive(Serialize)]
+struct WebhookCall<'a> {
+    to: &'a str,
+    message: &'a str,
+    threats: Vec<&'static str>,
+    proximity: &'static str,
+    nationwide: bool,
+    text: &'a str,
+}
+
+/// Places escalation calls, rate-limited by [`EscalationCfg::cooldown`].
+pub struct Escalator {
+    cfg: EscalationCfg,
+    http: HttpClient,
+    last_escalation: Option<Instant>,
+}
+
+impl Escalator {
+    pub fn new(cfg: EscalationCfg, http: HttpClient) -> Self {
+        Self {
+            cfg,
+            http,
+            last_escalation: None,
+        }
+    }
+
+    /// Ring every configured phone if `info` is critical and the cooldown
+    /// has elapsed.  `text` is the original channel message.
+    pub async fn maybe_escalate(&mut self, info: &AlertInfo, text: &str) -> bool {
+        if !is_critical(info) {
+            return false;
+        }
+        self.escalate(info, text).await
+    }
+
+    /// Ring every configured phone, skipping the criticality check (used
+    /// when an `ALERT_ROUTES` rule asks for a call).  Still rate-limited.
+    /// Returns whether the provider accepted every call.
+    pub async fn escalate(&mut self, info: &AlertInfo, text: &str) -> bool {
+        if self
+            .last_escalation
+            .is_some_and(|at| at.elapsed() < self.cfg.cooldown)
+        {
+            return false;
+        }
+        self.last_escalation = Some(Instant::now());
+
+        let message = spoken_message(info);
+        info!(
+            "Escalating critical alert to {} phone(s)",
+            self.cfg.phones.len()
+        );
+        let mut placed = true;
+        for phone in &self.cfg.phones {
+            if let Err(e) = self.call(phone, &message, info, text).await {
+                warn!("Escalation call to {phone} failed: {e}");
+                placed = false;
+            }
+        }
+        placed
+    }
+
+    async fn call(&self, to: &str, message: &str, info: &AlertInfo, text: &str) -> Result<()> {
+        let resp = match &self.cfg.provider {
+            CallProvider::Twilio {
+                account_sid,
+                auth_token,
+                from,
+            } => {
+                let url =
+                    format!("https://api.twilio.com/2010-04-01/Accounts/{account_sid}/Calls.json");
+                let twiml = twiml(message);
+                self.http
+                    .post(&url)
+                    .basic_auth(account_sid, Some(auth_token))
+                    .form(&[("To", to), ("From", from.as_str()), ("Twiml", &twiml)])
+                    .timeout(Duration::from_secs(15))
+                    .send()
+                    .await?
+            }
+            CallProvider::Webhook { url, token } => {
+                let body = WebhookCall {
+                    to,
+                    message,
+                    threats: info.threats.iter().map(|t| t.variant_name()).collect(),
+                    proximity: info.proximity.name(),
+                    nationwide: info.nationwide,
+                    text,
+                };
+                let mut req = self
+                    .http
+                    .post(url)
+                    .json(&body)
+                    .timeout(Duration::from_secs(15));
+                if let Some(token) = token {
+                    req = req.bearer_auth(token);
+                }
+                req.send().await?
+            }
+        };
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let raw = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("call request failed: {status} body={raw}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::AlertType;
+
+    fn alert_info(threats: &[ThreatKind], proximity: Proximity) -> AlertInfo {
+        AlertInfo {
+            alert_type: AlertType::New,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn only_incoming_ballistics_to_the_area_or_district_threats_are_critical() {
+        use ThreatKind::*;
+        let critical = [
+            alert_info(&[Ballistic], Proximity::Oblast),
+            alert_info(&[Hypersonic], Proximity::City),
+            alert_info(&[Shahed], Proximity::District),
+            alert_info(&[Explosion, Ballistic], Proximity::Oblast),
+            AlertInfo {
+                nationwide: true,
+                ..alert_info(&[Ballistic], Proximity::None)
+            },
+        ];
+        for info in &critical {
+            assert!(is_critical(info), "{info:?}");
+        }
+        let not_critical = [
+            alert_info(&[Ballistic], Proximity::None),
+            alert_info(&[Shahed, CruiseMissile], Proximity::City),
+            alert_info(&[Explosion], Proximity::District),
+            alert_info(&[AllClear], Proximity::District),
+            alert_info(&[Ballistic, AllClear], Proximity::City),
+            alert_info(&[], Proximity::District),
+            AlertInfo {
+                nationwide: true,
+                reduced: true,
+                ..alert_info(&[Ballistic], Proximity::None)
+            },
+        ];
+        for info in &not_critical {
+            assert!(!is_critical(info), "{info:?}");
+        }
+    }
+
+    #[test]
+    fn spoken_message_names_threats_and_area() {
+        use ThreatKind::*;
+        assert_eq!(
+            spoken_message(&alert_info(&[Ballistic, Shahed], Proximity::City)),
+            "Увага! Балістика, Шахед / дрон: ваше місто. Негайно пройдіть в укриття."
+        );
+        assert_eq!(
+            spoken_message(&alert_info(&[Shahed], Proximity::District)),
+            "Увага! Шахед / дрон: ваш район. Негайно пройдіть в укриття."
+        );
+        assert_eq!(
+            spoken_message(&alert_info(&[GuidedBomb], Proximity::Oblast)),
+            "Увага! КАБ: ваша область. Негайно пройдіть в укриття."
+        );
+        assert_eq!(
+            spoken_message(&AlertInfo {
+                nationwide: true,
+                ..alert_info(&[Hypersonic], Proximity::None)
+            }),
+            "Увага! Гіперзвук: вся Україна. Негайно пройдіть в укриття."
+        );
+        assert_eq!(
+            spoken_message(&alert_info(&[Ballistic], Proximity::None)),
+            "Увага! Балістика. Негайно пройдіть в укриття."
+        );
+    }
+
+    #[test]
+    fn call_text_cannot_break_out_of_the_twiml() {
+        assert_eq!(
+            xml_escape(r#"a & b <c> "d""#),
+            "a &amp; b &lt;c&gt; &quot;d&quot;"
+        );
+        // `&` is replaced first, so entities in the text stay literal.
+        assert_eq!(xml_escape("&amp;"), "&amp;amp;");
+        assert_eq!(
+            twiml("</Say><Dial>+100</Dial><Say>"),
+            "<Response><Say language=\"uk-UA\" loop=\"3\">\
+             &lt;/Say&gt;&lt;Dial&gt;+100&lt;/Dial&gt;&lt;Say&gt;</Say></Response>"
+        );
+        assert_eq!(
+            twiml("Увага! Балістика & КАБ"),
+            "<Response><Say language=\"uk-UA\" loop=\"3\">Увага! Балістика &amp; КАБ</Say></Response>"
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Reaction feedback: forwarded alerts are mirrored to an admin control
+//! channel, where 👍 (correct) / 👎 (false positive) reactions – picked up by
+//! the user client – become labels on the original channel message.  Labeled
+//! messages are exported as a JSONL eval corpus that replay mode can read.
+//!
+//! Subscribers report false positives themselves by replying `/report` to a
+//! delivered alert; reported alerts join the corpus unless an admin labeled
+//! them.
+
+use crate::archive::ArchivedMessage;
+use crate::bot::{SentMessages, SharedDb};
+use crate::filter::threat_kind::ThreatKind;
+use anyhow::Result;
+use serde::Serialize;
+use sqlite::State;
+use std::io::{BufWriter, Write};
+use tracing::info;
+
+pub const UPVOTE: &str = "👍";
+pub const DOWNVOTE: &str = "👎";
+
+#[derive(Debug, Clone)]
+pub struct FeedbackCfg {
+    /// Bot API id of the control channel (`-100…`).
+    pub chat_id: i64,
+    pub corpus_path: String,
+}
+
+impl FeedbackCfg {
+    /// Disabled unless `FEEDBACK_CHAT_ID` is set.
+    ///
+    /// | Env var                | Default                     | Description                       |
+    /// |------------------------|-----------------------------|-----------------------------------|
+    /// | `FEEDBACK_CHAT_ID`     | —                           | Control channel for alert reviews |
+    /// | `FEEDBACK_CORPUS_PATH` | `./feedback_corpus.jsonl`   | Labeled eval corpus output        |
+    pub fn from_env() -> Option<Self> {
+        let chat_id = std::env::var("FEEDBACK_CHAT_ID")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())?;
+        Some(Self {
+            chat_id,
+            corpus_path: std::env::var("FEEDBACK_CORPUS_PATH")
+                .unwrap_or_else(|_| "./feedback_corpus.jsonl".into()),
+        })
+    }
+
+    /// MTProto channel id of the control channel, as seen in user-client
+    /// updates (`-1000000001234` → `1234`).
+    pub fn channel_id(&self) -> Option<i64> {
+        let bare = -self.chat_id - 1_000_000_000_000;
+        (bare > 0).then_some(bare)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Correct,
+    FalsePositive,
+}
+
+impl Label {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Correct => "correct",
+            Self::FalsePositive => "false_positive",
+        }
+    }
+
+    /// Majority vote; ties (including no votes) leave the message unlabeled.
+    pub fn from_votes(up: i64, down: i64) -> Option<Self> {
+        match up.cmp(&down) {
+            std::cmp::Ordering::Greater => Some(Self::Correct),
+            std::cmp::Ordering::Less => Some(Self::FalsePositive),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+pub fn ensure_tables(db: &SharedDb) -> Result<()> {
+    db.lock().unwrap().execute(
+        "CREATE TABLE IF NOT EXISTS feedback_alerts (
+            control_msg_id INTEGER PRIMARY KEY,
+            timestamp      INTEGER NOT NULL,
+            channel_id     INTEGER NOT NULL,
+            channel_title  TEXT    NOT NULL,
+            text           TEXT    NOT NULL,
+            alert          TEXT    NOT NULL,
+            up             INTEGER NOT NULL DEFAULT 0,
+            down           INTEGER NOT NULL DEFAULT 0,
+            label          TEXT
+         );",
+    )?;
+    Ok(())
+}
+
+/// Remember which channel message the control-channel post `control_msg_id`
+/// came from.
+pub fn record_sent(
+    db: &SharedDb,
+    control_msg_id: i64,
+    msg: &ArchivedMessage,
+    alert: &str,
+) -> Result<()> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO feedback_alerts
+            (control_msg_id, timestamp, channel_id, channel_title, text, alert)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, control_msg_id))?;
+    stmt.bind((2, msg.timestamp))?;
+    stmt.bind((3, msg.channel_id))?;
+    stmt.bind((4, msg.channel_title.as_str()))?;
+    stmt.bind((5, msg.text.as_str()))?;
+    stmt.bind((6, alert))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Store the current vote counts for a control-channel post.  Returns
+/// `true` when the post is a known alert and its label changed.
+pub fn record_votes(db: &SharedDb, control_msg_id: i64, up: i64, down: i64) -> Result<bool> {
+    let label = Label::from_votes(up, down).map(|l| l.as_str());
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare("SELECT label FROM feedback_alerts WHERE control_msg_id = ?")?;
+    stmt.bind((1, control_msg_id))?;
+    let State::Row = stmt.next()? else {
+        return Ok(false);
+    };
+    let previous = stmt.read::<Option<String>, _>(0)?;
+    drop(stmt);
+
+    let mut stmt = conn.prepare(
+        "UPDATE feedback_alerts SET up = ?, down = ?, label = ? WHERE control_msg_id = ?",
+    )?;
+    stmt.bind((1, up))?;
+    stmt.bind((2, down))?;
+    stmt.bind((3, label))?;
+    stmt.bind((4, control_msg_id))?;
+    stmt.next()?;
+    Ok(previous.as_deref() != label)
+}
+
+/// Remember which channel message the broadcast `sent` delivered, so a
+/// `/report` reply can be traced back to it.  Returns the alert id.
+pub fn record_delivery(
+    db: &SharedDb,
+    msg: &ArchivedMessage,
+    alert: &str,
+    threats: &[ThreatKind],
+    sent: &SentMessages,
+) -> Result<i64> {
+    let threats = threats
+        .iter()
+        .map(|t| t.variant_name())
+        .collect::<Vec<_>>()
+        .join(",");
+    let conn = db.lock().unwrap();
+    conn.execute("BEGIN")?;
+    let result = (|| -> Result<i64> {
+        let mut stmt = conn.prepare(
+            "INSERT INTO delivered_alerts
+                (timestamp, channel_id, channel_title, text, alert, threats)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        stmt.bind((1, msg.timestamp))?;
+        stmt.bind((2, msg.channel_id))?;
+        stmt.bind((3, msg.channel_title.as_str()))?;
+        stmt.bind((4, msg.text.as_str()))?;
+        stmt.bind((5, alert))?;
+        stmt.bind((6, threats.as_str()))?;
+        stmt.next()?;
+        drop(stmt);
+        let mut stmt = conn.prepare("SELECT last_insert_rowid()")?;
+        stmt.next()?;
+        let id = stmt.read::<i64, _>(0)?;
+        drop(stmt);
+        let mut stmt = conn.prepare(
+            "INSERT OR REPLACE INTO alert_deliveries (chat_id, message_id, alert_id)
+             VALUES (?, ?, ?)",
+        )?;
+        for (chat_id, message_id) in sent {
+            stmt.reset()?;
+            stmt.bind((1, *chat_id))?;
+            stmt.bind((2, *message_id))?;
+            stmt.bind((3, id))?;
+            stmt.next()?;
+        }
+        Ok(id)
+    })();
+    conn.execute(if result.is_ok() { "COMMIT" } else { "ROLLBACK" })?;
+    result
+}
+
+/// A subscriber's false-positive report of a delivered alert.
+#[derive(Debug, Clone)]
+pub struct UserReport {
+    pub alert_id: i64,
+    /// The channel message the alert came from.
+    pub message: ArchivedMessage,
+    pub alert: String,
+    pub threats: Vec<ThreatKind>,
+    pub comment: String,
+    /// Chats that reported this alert so far.
+    pub reporters: i64,
+    /// This chat had not reported the alert before.
+    pub first: bool,
+}
+
+impl UserReport {
+    /// Admin notice.
+    pub fn render(&self, chat_id: i64) -> String {
+        let mut out = format!(
+            "🚩 False-positive report #{} from chat {chat_id} ({} report(s))\n📡 {}\n\n{}",
+            self.alert_id, self.reporters, self.message.channel_title, self.alert
+        );
+        if !self.comment.is_empty() {
+            out.push_str(&format!("\n\n💬 {}", self.comment));
+        }
+        out.push_str("\n\nQueued for /review.");
+        out
+    }
+}
+
+/// Record that `chat_id` reported the alert it received as `message_id`.
+/// `None` when that message is not a known alert delivery.  A repeated
+/// report only updates the comment.
+pub fn record_report(
+    db: &SharedDb,
+    chat_id: i64,
+    message_id: i64,
+    comment: &str,
+) -> Result<Option<UserReport>> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.timestamp, a.channel_id, a.channel_title, a.text, a.alert, a.threats
+         FROM alert_deliveries d JOIN delivered_alerts a ON a.id = d.alert_id
+         WHERE d.chat_id = ? AND d.message_id = ?",
+    )?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, message_id))?;
+    let State::Row = stmt.next()? else {
+        return Ok(None);
+    };
+    let alert_id = stmt.read::<i64, _>(0)?;
+    let message = ArchivedMessage {
+        timestamp: stmt.read(1)?,
+        channel_id: stmt.read(2)?,
+        channel_title: stmt.read(3)?,
+        text: stmt.read(4)?,
+    };
+    let alert = stmt.read::<String, _>(5)?;
+    let threats = stmt
+        .read::<String, _>(6)?
+        .split(',')
+        .filter_map(ThreatKind::from_variant_name)
+        .collect();
+    drop(stmt);
+
+    let mut stmt =
+        conn.prepare("UPDATE alert_reports SET comment = ? WHERE alert_id = ? AND chat_id = ?")?;
+    stmt.bind((1, comment))?;
+    stmt.bind((2, alert_id))?;
+    stmt.bind((3, chat_id))?;
+    stmt.next()?;
+    drop(stmt);
+    let first = conn.change_count() == 0;
+    if first {
+        let mut stmt = conn
+            .prepare("INSERT INTO alert_reports (alert_id, chat_id, comment) VALUES (?, ?, ?)")?;
+        stmt.bind((1, alert_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, comment))?;
+        stmt.next()?;
+    }
+    let mut stmt = conn.prepare("SELECT COUNT(*) FROM alert_reports WHERE alert_id = ?")?;
+    stmt.bind((1, alert_id))?;
+    stmt.next()?;
+    Ok(Some(UserReport {
+        alert_id,
+        message,
+        alert,
+        threats,
+        comment: comment.to_string(),
+        reporters: stmt.read(0)?,
+        first,
+    }))
+}
+
+/// Forget deliveries of alerts sent before `before_ts`; alerts somebody
+/// reported are kept as labels.  Returns the number of alerts removed.
+pub fn prune_deliveries(db: &SharedDb, before_ts: i64) -> Result<usize> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "DELETE FROM alert_deliveries WHERE alert_id IN
+            (SELECT id FROM delivered_alerts WHERE sent_at < ?)",
+    )?;
+    stmt.bind((1, before_ts))?;
+    stmt.next()?;
+    drop(stmt);
+    let mut stmt = conn.prepare(
+        "DELETE FROM delivered_alerts
+         WHERE sent_at < ? AND id NOT IN (SELECT alert_id FROM alert_reports)",
+    )?;
+    stmt.bind((1, before_ts))?;
+    stmt.next()?;
+    drop(stmt);
+    Ok(conn.change_count())
+}
+
+/// One corpus line: the replay dump fields plus the verdict.
+#[derive(Debug, Serialize)]
+struct CorpusEntry {
+    timestamp: i64,
+    channel_id: i64,
+    channel_title: String,
+    text: String,
+    alert: String,
+    label: String,
+}
+
+/// Rewrite `path` with every labeled message, oldest first: reaction
+/// verdicts, plus subscriber-reported alerts as false positives where no
+/// verdict exists.  Returns the number of entries written.
+pub fn export_corpus(db: &SharedDb, path: &str) -> Result<usize> {
+    let entries = {
+        let conn = db.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, channel_id, channel_title, text, alert, label FROM (
+                SELECT timestamp, channel_id, channel_title, text, alert, label,
+                       control_msg_id AS ord
+                FROM feedback_alerts WHERE label IS NOT NULL
+                UNION ALL
+                SELECT timestamp, channel_id, channel_title, text, alert, ?, id
+                FROM delivered_alerts a
+                WHERE id IN (SELECT alert_id FROM alert_reports)
+                  AND NOT EXISTS (
+                    SELECT 1 FROM feedback_alerts f
+                    WHERE f.label IS NOT NULL AND f.channel_id = a.channel_id
+                      AND f.timestamp = a.timestamp AND f.text = a.text)
+             ) ORDER BY timestamp, ord",
+        )?;
+        stmt.bind((1, Label::FalsePositive.as_str()))?;
+        let mut entries = Vec::new();
+        while let State::Row = stmt.next()? {
+            entries.push(CorpusEntry {
+                timestamp: stmt.read(0)?,
+                channel_id: stmt.read(1)?,
+                channel_title: stmt.read(2)?,
+                text: stmt.read(3)?,
+                alert: stmt.read(4)?,
+                label: stmt.read(5)?,
+            });
+        }
+        entries
+    };
+
+    let tmp = format!("{path}.tmp");
+    let mut out = BufWriter::new(std::fs::File::create(&tmp)?);
+    for entry in &entries {
+        serde_json::to_writer(&mut out, entry)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    drop(out);
+    std::fs::rename(&tmp, path)?;
+    info!(
+        "Feedback corpus: {} labeled message(s) in {path}",
+        entries.len()
+    );
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+
+    #[test]
+    fn user_reports_trace_alert_replies_back_to_the_source_message() {
+        let db = bot::open_db(":memory:").unwrap();
+        ensure_tables(&db).unwrap();
+        let msg = ArchivedMessage {
+            timestamp: 1_700_000_000,
+            channel_id: 42,
+            channel_title: "monitor".into(),
+            text: "шахед на київ".into(),
+        };
+        let sent = SentMessages::from([(100, 7), (200, 9)]);
+        let id = record_delivery(&db, &msg, "🛵 Шахед", &[ThreatKind::Shahed], &sent).unwrap();
+
+        // Only a reply to a delivered alert can be reported.
+        assert!(record_report(&db, 100, 9, "").unwrap().is_none());
+        let report = record_report(&db, 100, 7, "тиша").unwrap().unwrap();
+        assert_eq!(report.alert_id, id);
+        assert_eq!(report.message.channel_id, 42);
+        assert_eq!(report.threats, vec![ThreatKind::Shahed]);
+        assert!(report.first);
+        assert!(report.render(100).contains("тиша"));
+        let again = record_report(&db, 100, 7, "").unwrap().unwrap();
+        assert!(!again.first);
+        assert_eq!(again.reporters, 1);
+        let other = record_report(&db, 200, 9, "").unwrap().unwrap();
+        assert_eq!(other.reporters, 2);
+
+        let path = std::env::temp_dir().join(format!("report_corpus_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(export_corpus(&db, path).unwrap(), 1);
+        let corpus = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(corpus.contains("\"label\":\"false_positive\""));
+
+        // Reported alerts survive retention; their deliveries do not.
+        assert_eq!(prune_deliveries(&db, i64::MAX).unwrap(), 0);
+        assert!(record_report(&db, 100, 7, "").unwrap().is_none());
+    }
+}
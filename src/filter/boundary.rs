@@ -0,0 +1,63 @@
+//! Word-boundary rules for keyword stems.  A stem is found at the start of
+//! a word by default, so "ракет" matches "ракети" but not "протиракетна";
+//! short tokens that must stand alone ("КР", "СА") are whole words, and the
+//! few stems meant to match inside compounds opt out of boundaries.
+//!
+//! In keyword lists the rule is a prefix: `=кр` is a whole word, `*авіа`
+//! matches anywhere, a bare stem starts a word.
+
+/// Where a stem may match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Anywhere, even inside a word (`*` prefix).
+    Infix,
+    /// At the start of a word, with any ending (no prefix).
+    Stem,
+    /// As a whole word (`=` prefix).
+    Word,
+}
+
+impl Boundary {
+    /// Split a keyword-list entry into its rule and the stem itself.
+    pub fn parse(entry: &str) -> (Self, &str) {
+        if let Some(stem) = entry.strip_prefix('=') {
+            (Self::Word, stem)
+        } else if let Some(stem) = entry.strip_prefix('*') {
+            (Self::Infix, stem)
+        } else {
+            (Self::Stem, entry)
+        }
+    }
+
+    /// Whether a match at `hay[start..end]` obeys the rule.
+    pub fn accepts(self, hay: &str, start: usize, end: usize) -> bool {
+        let starts_word = || {
+            hay[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !is_word_char(c))
+        };
+        let ends_word = || hay[end..].chars().next().is_none_or(|c| !is_word_char(c));
+        match self {
+            Self::Infix => true,
+            Self::Stem => starts_word(),
+            Self::Word => starts_word() && ends_word(),
+        }
+    }
+}
+
+/// Letters make up words; digits do not, so "2КР" and "5шахедів" still
+/// match.
+fn is_word_char(c: char) -> bool {
+    c.is_alphabetic()
+}
+
+/// Whether `hay` contains `needle` where `boundary` allows it.  For one-off
+/// checks; keyword lists are compiled into
+/// [`KeywordSet`](crate::filter::threat_keywords::KeywordSet).
+pub fn contains(hay: &str, needle: &str, boundary: Boundary) -> bool {
+    !needle.is_empty()
+        && hay
+            .match_indices(needle)
+            .any(|(start, m)| boundary.accepts(hay, start, start + m.len()))
+}
@@ -0,0 +1,149 @@
+//! Per-channel context settings.  Pure trajectory feeds benefit from a long
+//! context window and every inference phase; chatty discussion channels
+//! produce junk inferences and are better handled with a short window or
+//! none at all.  `CHANNEL_CONTEXT` overrides the defaults per channel title.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use tracing::warn;
+
+/// Context settings for one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextPolicy {
+    /// How long messages stay in the channel's context window.
+    pub window: Duration,
+    /// Messages kept in the window.
+    pub max_messages: usize,
+    /// Infer the threat type from the channel's recent messages (trigger
+    /// words like "ціль", location-only posts).
+    pub trigger: bool,
+    /// Infer a missing location from the channel's recent messages.
+    pub location: bool,
+    /// Refine generic threats from other channels' recent messages.
+    pub global_refine: bool,
+}
+
+/// Overrides of one `CHANNEL_CONTEXT` rule; `None` keeps the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct PolicyOverride {
+    window: Option<Duration>,
+    max_messages: Option<usize>,
+    trigger: Option<bool>,
+    location: Option<bool>,
+    global_refine: Option<bool>,
+}
+
+impl PolicyOverride {
+    /// `window:600,messages:40,infer:trigger+location`
+    fn parse(spec: &str) -> Result<Self> {
+        let mut out = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item
+                .split_once(':')
+                .ok_or_else(|| anyhow!("expected key:value, got {item:?}"))?;
+            let value = value.trim();
+            match key.trim() {
+                "window" => {
+                    let secs = value
+                        .parse()
+                        .map_err(|_| anyhow!("window must be seconds, got {value:?}"))?;
+                    out.window = Some(Duration::from_secs(secs));
+                }
+                "messages" => {
+                    let n: usize = value
+                        .parse()
+                        .map_err(|_| anyhow!("messages must be a count, got {value:?}"))?;
+                    out.max_messages = Some(n.max(1));
+                }
+                "infer" => {
+                    let (mut trigger, mut location, mut global) = (false, false, false);
+                    for phase in value.split('+').map(str::trim) {
+                        match phase {
+                            "all" => (trigger, location, global) = (true, true, true),
+                            "none" => {}
+                            "trigger" => trigger = true,
+                            "location" => location = true,
+                            "global" => global = true,
+                            other => bail!("unknown inference phase {other:?}"),
+                        }
+                    }
+                    out.trigger = Some(trigger);
+                    out.location = Some(location);
+                    out.global_refine = Some(global);
+                }
+                other => bail!("unknown key {other:?}"),
+            }
+        }
+        Ok(out)
+    }
+
+    fn apply(&self, mut policy: ContextPolicy) -> ContextPolicy {
+        policy.window = self.window.unwrap_or(policy.window);
+        policy.max_messages = self.max_messages.unwrap_or(policy.max_messages);
+        policy.trigger = self.trigger.unwrap_or(policy.trigger);
+        policy.location = self.location.unwrap_or(policy.location);
+        policy.global_refine = self.global_refine.unwrap_or(policy.global_refine);
+        policy
+    }
+}
+
+/// `CHANNEL_CONTEXT` rules: `title=key:value,…`, `;`-separated.  The title
+/// is a case-insensitive fragment of the channel title; the first matching
+/// rule wins.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelPolicies {
+    rules: Vec<(String, PolicyOverride)>,
+}
+
+impl ChannelPolicies {
+    /// | Env var           | Default | Description                                |
+    /// |-------------------|---------|--------------------------------------------|
+    /// | `CHANNEL_CONTEXT` | —       | `title=key:value,…` rules, `;`-separated   |
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var("CHANNEL_CONTEXT").unwrap_or_default())
+    }
+
+    /// Invalid rules are skipped with a warning.
+    pub fn parse(spec: &str) -> Self {
+        let mut rules = Vec::new();
+        for rule in spec.split([';', '\n']).map(str::trim) {
+            if rule.is_empty() {
+                continue;
+            }
+            match Self::parse_rule(rule) {
+                Ok(parsed) => rules.push(parsed),
+                Err(e) => warn!("Ignoring CHANNEL_CONTEXT rule {rule:?}: {e}"),
+            }
+        }
+        Self { rules }
+    }
+
+    fn parse_rule(rule: &str) -> Result<(String, PolicyOverride)> {
+        let (title, spec) = rule
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected title=settings"))?;
+        let title = title.trim().to_lowercase();
+        if title.is_empty() {
+            bail!("empty channel title");
+        }
+        Ok((title, PolicyOverride::parse(spec)?))
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Policy for `channel_title`: `default` with the first matching rule
+    /// applied.
+    pub fn resolve(&self, channel_title: &str, default: ContextPolicy) -> ContextPolicy {
+        if self.rules.is_empty() {
+            return default;
+        }
+        let title = channel_title.to_lowercase();
+        self.rules
+            .iter()
+            .find(|(fragment, _)| title.contains(fragment.as_str()))
+            .map_or(default, |(_, o)| o.apply(default))
+    }
+}
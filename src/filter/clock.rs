@@ -0,0 +1,54 @@
+//! Where the filter gets the time.  Dedup windows, context windows,
+//! cooldowns and tracks all age by [`Clock::now`]; live mode uses the
+//! system clock, replays and tests a [`SimulatedClock`] they move forward
+//! themselves, so a night of messages can be replayed at its own pace.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A source of monotonic time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that stands still until advanced.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    now: Mutex<Instant>,
+}
+
+impl SimulatedClock {
+    /// Starts at the current system time, so real and simulated instants
+    /// (e.g. restored state) compare sensibly.
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,78 @@
+//! How sure the filter is about an alert, 0–100.  An explicit "Балістика
+//! на Київ" scores high; a threat inferred from the channel's earlier
+//! posts, placed by context or at a namesake nothing could tell apart
+//! scores low.  When the secondary filter (LLM / classifier) ran or the
+//! channel has a review record, their say is fused in ([`fuse`]), so a
+//! noisy channel and a skeptical LLM together outvote the keywords.
+//! `MIN_CONFIDENCE` drops alerts below a threshold for users who prefer
+//! fewer false alarms to catching everything.
+
+use crate::filter::Proximity;
+use crate::filter::threat_kind::ThreatKind;
+
+/// What an alert's classification rests on.
+#[derive(Debug, Clone, Copy)]
+pub struct Signals {
+    /// The most specific threat kind.
+    pub primary: ThreatKind,
+    /// The threat came from context inference, not a keyword.
+    pub threat_inferred: bool,
+    /// The location came from the channel's earlier posts.
+    pub location_inferred: bool,
+    /// A namesake of the user's city that could not be told apart.
+    pub location_uncertain: bool,
+    pub proximity: Proximity,
+    pub nationwide: bool,
+    pub urgent: bool,
+}
+
+/// Score before any signal.
+const BASE: i32 = 40;
+
+pub fn score(s: &Signals) -> u8 {
+    let mut score = BASE;
+    score += match s.primary.specificity() {
+        0 => -10,
+        1 => 5,
+        2 => 10,
+        _ => 15,
+    };
+    score += if s.threat_inferred { -10 } else { 20 };
+    score += match (s.nationwide, s.proximity, s.location_inferred) {
+        (true, _, _) => 15,
+        (false, Proximity::None, _) => -10,
+        (false, _, true) => 0,
+        (false, _, false) => 15,
+    };
+    if s.location_uncertain {
+        score -= 15;
+    }
+    if s.urgent {
+        score += 10;
+    }
+    score.clamp(0, 100) as u8
+}
+
+/// Weights of the sources in [`fuse`].
+const KEYWORD_WEIGHT: f64 = 1.0;
+const SECONDARY_WEIGHT: f64 = 1.0;
+const TRUST_WEIGHT: f64 = 0.5;
+
+/// A fused score below this means the sources on balance speak against
+/// the alert; it is suppressed whatever `MIN_CONFIDENCE` says.
+pub const FUSED_MIN: u8 = 50;
+
+/// Weighted mean of the keyword `score` with the secondary filter's
+/// support for the alert and the channel's review trust (both
+/// `0.0..=1.0`); a source without a say abstains.
+pub fn fuse(score: u8, secondary: Option<f64>, trust: Option<f64>) -> u8 {
+    let mut sum = KEYWORD_WEIGHT * f64::from(score) / 100.0;
+    let mut weights = KEYWORD_WEIGHT;
+    for (value, weight) in [(secondary, SECONDARY_WEIGHT), (trust, TRUST_WEIGHT)] {
+        if let Some(value) = value {
+            sum += weight * value.clamp(0.0, 1.0);
+            weights += weight;
+        }
+    }
+    (sum / weights * 100.0).round() as u8
+}
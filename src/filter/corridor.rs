@@ -0,0 +1,281 @@
+//! Target-corridor estimation.  Channels reporting the same threat name
+//! different waypoints ("курсом на Бровари", "в напрямку Києва"); clustering
+//! those names across a short window gives the probable direction of travel.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::filter::threat_kind::ThreatKind;
+
+/// Phrases after which the next word is a waypoint.
+const WAYPOINT_MARKERS: &[&str] = &[
+    "курсом на ",
+    "курс на ",
+    "вектором на ",
+    "у напрямку ",
+    "в напрямку ",
+    "в направлении ",
+    "у бік ",
+    "в бік ",
+    "в сторону ",
+    "через ",
+    "повз ",
+];
+
+/// Settlement-type abbreviations that may precede the name.
+const SETTLEMENT_PREFIXES: &[&str] = &["м.", "с.", "смт", "смт.", "н.п.", "г.", "пос."];
+
+/// Words that follow a marker but are not places.
+const NON_PLACES: &[&str] = &[
+    "північ",
+    "південь",
+    "схід",
+    "захід",
+    "північн",
+    "південн",
+    "східн",
+    "західн",
+    "север",
+    "юг",
+    "восток",
+    "запад",
+    "северн",
+    "южн",
+    "восточн",
+    "западн",
+    "місто",
+    "город",
+    "область",
+    "области",
+    "області",
+    "район",
+    "столицю",
+    "столицы",
+    "столиці",
+];
+
+/// Reports kept per window, to bound memory during mass attacks.
+const MAX_REPORTS: usize = 50;
+
+/// At most this many waypoints are shown in the corridor line.
+const MAX_WAYPOINTS: usize = 4;
+
+/// Extract waypoint names from lowercased message text, in order of
+/// appearance.
+pub fn extract_waypoints(lower: &str) -> Vec<String> {
+    let mut found: Vec<(usize, String)> = Vec::new();
+    for marker in WAYPOINT_MARKERS {
+        for (pos, _) in lower.match_indices(marker) {
+            let boundary = lower[..pos]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric());
+            if !boundary {
+                continue;
+            }
+            let mut words = lower[pos + marker.len()..].split_whitespace();
+            let mut word = words.next();
+            if word.is_some_and(|w| SETTLEMENT_PREFIXES.contains(&w)) {
+                word = words.next();
+            }
+            let Some(word) = word else { continue };
+            let name: String = word
+                .trim_start_matches(|c: char| !c.is_alphabetic())
+                .chars()
+                .take_while(|c| c.is_alphabetic() || *c == '-' || *c == '\'' || *c == 'ʼ')
+                .collect();
+            let name = name.trim_end_matches(['-', '\'', 'ʼ']).to_string();
+            if name.chars().count() < 3 || NON_PLACES.iter().any(|np| name.starts_with(np)) {
+                continue;
+            }
+            found.push((pos, name));
+        }
+    }
+    found.sort_by_key(|(pos, _)| *pos);
+    let mut out: Vec<String> = Vec::new();
+    for (_, name) in found {
+        if !out
+            .iter()
+            .any(|n| same_place(&waypoint_key(n), &waypoint_key(&name)))
+        {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// Declension-insensitive cluster key: strips the case ending and masks the
+/// і/о/е alternation of the last syllable, so "київ", "києва" and "киев"
+/// share a key.  Apostrophe variants ("кам’янське") are unified.
+pub fn waypoint_key(name: &str) -> String {
+    const ENDINGS: &[&str] = &[
+        "ого", "ому", "ами", "ями", "ою", "ей", "ах", "ях", "ам", "ям", "ом", "ем", "а", "я", "у",
+        "ю", "і", "и", "ы", "е", "о",
+    ];
+    let mut stem: Vec<char> = name
+        .chars()
+        .map(|c| if matches!(c, 'ʼ' | '’') { '\'' } else { c })
+        .collect();
+    for ending in ENDINGS {
+        let len = ending.chars().count();
+        if stem.len() >= len + 3 && stem[stem.len() - len..].iter().copied().eq(ending.chars()) {
+            stem.truncate(stem.len() - len);
+            break;
+        }
+    }
+    let n = stem.len();
+    if n >= 3 && matches!(stem[n - 2], 'і' | 'ї' | 'о' | 'е' | 'є') {
+        stem[n - 2] = '*';
+    }
+    stem.into_iter().collect()
+}
+
+/// Keys name the same place when equal, or when the shorter one is a long
+/// enough prefix of the other (plural genitive: "бровар" / "бровар*в").
+pub fn same_place(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    short == long || (short.chars().count() >= 5 && long.starts_with(short))
+}
+
+pub fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+struct Report {
+    at: Instant,
+    channel_id: i64,
+    kind: ThreatKind,
+    waypoints: Vec<String>,
+}
+
+/// Sliding window of waypoint reports from all channels.
+pub struct CorridorTracker {
+    window: Duration,
+    reports: Vec<Report>,
+}
+
+impl CorridorTracker {
+    /// A zero `window` disables tracking.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            reports: Vec::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn clear(&mut self) {
+        self.reports.clear();
+    }
+
+    pub fn report_count(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Remember the waypoints one channel reported for `kind`.
+    pub fn record(
+        &mut self,
+        channel_id: i64,
+        kind: ThreatKind,
+        waypoints: Vec<String>,
+        now: Instant,
+    ) {
+        if self.window.is_zero() || waypoints.is_empty() {
+            return;
+        }
+        self.reports
+            .retain(|r| now.duration_since(r.at) < self.window);
+        self.reports.push(Report {
+            at: now,
+            channel_id,
+            kind,
+            waypoints,
+        });
+        if self.reports.len() > MAX_REPORTS {
+            self.reports.remove(0);
+        }
+    }
+
+    /// Probable corridor for `kind`, e.g. `["Бровари", "Київ"]`.
+    ///
+    /// Needs waypoints from at least two channels naming at least two
+    /// distinct places.  Clusters are ordered by first mention (earlier
+    /// waypoints lie further back along the route); when there are too
+    /// many, the least-mentioned ones are dropped.
+    pub fn estimate(&self, kind: ThreatKind, now: Instant) -> Option<Vec<String>> {
+        struct Cluster {
+            key: String,
+            first_seen: usize,
+            mentions: usize,
+            /// Distinct spellings, in order of first use.
+            forms: Vec<String>,
+        }
+
+        let reports: Vec<&Report> = self
+            .reports
+            .iter()
+            .filter(|r| r.kind == kind && now.duration_since(r.at) < self.window)
+            .collect();
+        let channels: HashSet<i64> = reports.iter().map(|r| r.channel_id).collect();
+        if channels.len() < 2 {
+            return None;
+        }
+
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for (seq, name) in reports.iter().flat_map(|r| &r.waypoints).enumerate() {
+            let key = waypoint_key(name);
+            let cluster = match clusters.iter_mut().position(|c| same_place(&c.key, &key)) {
+                Some(i) => &mut clusters[i],
+                None => {
+                    clusters.push(Cluster {
+                        key,
+                        first_seen: seq,
+                        mentions: 0,
+                        forms: Vec::new(),
+                    });
+                    clusters.last_mut().unwrap()
+                }
+            };
+            cluster.mentions += 1;
+            if !cluster.forms.contains(name) {
+                cluster.forms.push(name.clone());
+            }
+        }
+        if clusters.len() < 2 {
+            return None;
+        }
+
+        // Keep the most-mentioned clusters, then restore route order.
+        clusters.sort_by_key(|c| (std::cmp::Reverse(c.mentions), c.first_seen));
+        clusters.truncate(MAX_WAYPOINTS);
+        clusters.sort_by_key(|c| c.first_seen);
+        Some(
+            clusters
+                .iter()
+                .map(|c| {
+                    // Shortest spelling is usually the nominative
+                    // ("Київ" over "Києва"); ties go to the first one used.
+                    let best = c
+                        .forms
+                        .iter()
+                        .min_by_key(|f| f.chars().count())
+                        .map(String::as_str)
+                        .unwrap_or(&c.key);
+                    capitalize(best)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Alert footer line for an estimated corridor.
+pub fn format_corridor(waypoints: &[String]) -> String {
+    format!("🧭 Ймовірний напрямок: {}", waypoints.join("–"))
+}
@@ -0,0 +1,87 @@
+//! Corroboration of vague threats.  "Ракетна небезпека", "розвідувальний
+//! БпЛА" or an unclassified scare from one channel is often nothing; with
+//! `CORROBORATION_CHANNELS` set, such reports are held until that many
+//! distinct channels report the same primary threat within the window,
+//! then forwarded once, naming the channels.  Specific kinds (ballistic,
+//! hypersonic, shaheds, …) are never held.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::filter::threat_kind::ThreatKind;
+
+/// Kinds held for corroboration.
+pub fn needs_corroboration(kind: ThreatKind) -> bool {
+    matches!(
+        kind,
+        ThreatKind::Other | ThreatKind::Missile | ThreatKind::ReconDrone
+    )
+}
+
+struct Held {
+    first_at: Instant,
+    /// Reporting channels (id, title), in order of report.
+    channels: Vec<(i64, String)>,
+}
+
+/// Reports held until enough channels agree.
+pub struct Corroboration {
+    required: usize,
+    window: Duration,
+    held: HashMap<ThreatKind, Held>,
+}
+
+impl Corroboration {
+    /// `required` below 2 disables holding.
+    pub fn new(required: usize, window: Duration) -> Self {
+        Self {
+            required,
+            window,
+            held: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.required > 1
+    }
+
+    /// Hold a `kind` report from `channel_id`.  Returns the titles of the
+    /// reporting channels once `required` distinct ones agree (releasing
+    /// the hold), else `None`.
+    pub fn hold(
+        &mut self,
+        kind: ThreatKind,
+        channel_id: i64,
+        channel_title: &str,
+        now: Instant,
+    ) -> Option<Vec<String>> {
+        self.held
+            .retain(|_, h| now.duration_since(h.first_at) < self.window);
+        let held = self.held.entry(kind).or_insert_with(|| Held {
+            first_at: now,
+            channels: Vec::new(),
+        });
+        if !held.channels.iter().any(|(id, _)| *id == channel_id) {
+            held.channels.push((channel_id, channel_title.to_string()));
+        }
+        if held.channels.len() < self.required {
+            return None;
+        }
+        self.held
+            .remove(&kind)
+            .map(|h| h.channels.into_iter().map(|(_, title)| title).collect())
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+/// Alert footer naming the channels that agreed.
+pub fn format_corroboration(channels: &[String]) -> String {
+    format!(
+        "📡 Підтверджено {} каналами: {}",
+        channels.len(),
+        channels.join(", ")
+    )
+}
@@ -0,0 +1,109 @@
+//! Threat counts.  "4 ракети на Київ", "група ~10х БпЛА", "2х КР" tell the
+//! scale of an attack at a glance; the count goes next to its kind in the
+//! alert header ("‼️🚀 Балістика ×4").
+
+use crate::filter::detect_threats;
+use crate::filter::threat_kind::ThreatKind;
+
+/// Larger numbers are not counts (years, altitudes, unit numbers).
+const MAX_COUNT: u32 = 200;
+
+/// Words after a number that make it a measure, not a count.
+const UNITS: &[&str] = &[
+    "км",
+    "кілометр",
+    "километр",
+    "м",
+    "метр",
+    "хв",
+    "хвилин",
+    "мин",
+    "минут",
+    "год",
+    "годин",
+    "час",
+    "сек",
+    "раз",
+    "груп",
+];
+
+/// Words after a number searched for the counted threat ("4 балістичні
+/// ракети", "2х КР на Київ").
+const NOUN_WINDOW: usize = 3;
+
+/// Counts of `threats` named in `lower`: a number (optionally "~10",
+/// "6х", "6-ти") followed by a threat word.  A missile or drone count
+/// goes to the matching kind of the same family ("Балістика! 4 ракети"
+/// counts ballistic missiles).  The largest count per kind is kept, so a
+/// repeated "4 ракети" is not added up.
+pub fn threat_counts(lower: &str, threats: &[ThreatKind]) -> Vec<(ThreatKind, u32)> {
+    let tokens: Vec<&str> = lower.split_whitespace().collect();
+    let mut counts: Vec<(ThreatKind, u32)> = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let Some(n) = count(token) else {
+            continue;
+        };
+        let mut rest = &tokens[i + 1..];
+        if rest.first().is_some_and(|t| ["х", "x", "×"].contains(t)) {
+            rest = &rest[1..];
+        }
+        let Some(next) = rest.first() else {
+            continue;
+        };
+        let next = next.trim_matches(|c: char| !c.is_alphanumeric());
+        if next.is_empty()
+            || UNITS
+                .iter()
+                .any(|u| next == *u || (u.len() > 4 && next.starts_with(u)))
+        {
+            continue;
+        }
+        let window = rest[..rest.len().min(NOUN_WINDOW)].join(" ") + " ";
+        let Some(kind) = detect_threats(&window)
+            .into_iter()
+            .find_map(|k| counted_kind(k, threats))
+        else {
+            continue;
+        };
+        match counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, max)) => *max = (*max).max(n),
+            None => counts.push((kind, n)),
+        }
+    }
+    counts.sort_by_key(|(k, _)| threats.iter().position(|t| t == k));
+    counts
+}
+
+/// The number a count token holds: "4", "~10", "10х", "6-ти", "4,".
+fn count(token: &str) -> Option<u32> {
+    let token = token.trim_start_matches(['~', '≈', '+']);
+    let digits = token.len() - token.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let suffix = token[digits..].trim_start_matches(['х', 'x', '-']);
+    let suffix_ok = ["ти", "ть", "х", "ох", "ом"].contains(&suffix)
+        || suffix
+            .chars()
+            .all(|c| !c.is_alphanumeric() && c != ':' && c != '.');
+    token[..digits]
+        .parse()
+        .ok()
+        .filter(|n| suffix_ok && (1..=MAX_COUNT).contains(n))
+}
+
+/// `kind` as it appears among the alert's `threats`.
+fn counted_kind(kind: ThreatKind, threats: &[ThreatKind]) -> Option<ThreatKind> {
+    use ThreatKind::*;
+    if threats.contains(&kind) {
+        return Some(kind);
+    }
+    let family: &[ThreatKind] = match kind {
+        Missile | CruiseMissile | Ballistic | Hypersonic => {
+            &[Ballistic, Hypersonic, CruiseMissile, Missile]
+        }
+        Shahed | ReconDrone => &[Shahed, ReconDrone],
+        _ => return None,
+    };
+    threats.iter().copied().find(|t| family.contains(t))
+}
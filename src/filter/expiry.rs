@@ -0,0 +1,132 @@
+//! Threats that end without an all-clear.  Many channels never post
+//! "відбій" for a drone that simply stopped being reported; dedup entries
+//! age out silently and the subscriber is left waiting.  Each threat kind
+//! the user was alerted about is *active* until an all-clear or until no
+//! report of it arrives for its expiry time, when it is *expired* once
+//! and reported as probably over.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::filter::Proximity;
+use crate::filter::threat_kind::ThreatKind;
+
+/// Expiry of kinds without their own (`THREAT_EXPIRY_MINS` default).
+const DEFAULT_EXPIRY_MINS: u64 = 60;
+
+/// Ballistic and hypersonic missiles arrive within minutes.
+const FAST_EXPIRY_MINS: u64 = 20;
+
+/// Last alert of an active threat.
+#[derive(Debug, Clone, Copy)]
+struct Active {
+    last_seen: Instant,
+    proximity: Proximity,
+}
+
+/// Threat kinds alerted about and not yet over.
+pub struct ActiveThreats {
+    default: Duration,
+    per_kind: HashMap<ThreatKind, Duration>,
+    active: HashMap<ThreatKind, Active>,
+}
+
+impl ActiveThreats {
+    /// Parse `THREAT_EXPIRY_MINS`: comma-separated `Kind=mins` entries and
+    /// an optional bare number for every other kind ("Shahed=90,45").
+    /// `0` disables expiry for the kinds it applies to.
+    pub fn from_spec(spec: &str) -> Self {
+        let mut default = Duration::from_secs(DEFAULT_EXPIRY_MINS * 60);
+        let mut per_kind: HashMap<ThreatKind, Duration> =
+            [ThreatKind::Ballistic, ThreatKind::Hypersonic]
+                .into_iter()
+                .map(|k| (k, Duration::from_secs(FAST_EXPIRY_MINS * 60)))
+                .collect();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (kind, mins) = match entry.split_once('=') {
+                Some((kind, mins)) => (Some(kind.trim()), mins.trim()),
+                None => (None, entry),
+            };
+            let Ok(mins) = mins.parse::<u64>() else {
+                tracing::warn!("Invalid THREAT_EXPIRY_MINS entry {entry:?}");
+                continue;
+            };
+            let expiry = Duration::from_secs(mins * 60);
+            match kind.map(|k| (k, ThreatKind::from_variant_name(k))) {
+                None => default = expiry,
+                Some((_, Some(kind))) => {
+                    per_kind.insert(kind, expiry);
+                }
+                Some((name, None)) => {
+                    tracing::warn!("Unknown threat kind {name:?} in THREAT_EXPIRY_MINS");
+                }
+            }
+        }
+        Self {
+            default,
+            per_kind,
+            active: HashMap::new(),
+        }
+    }
+
+    fn expiry(&self, kind: ThreatKind) -> Duration {
+        self.per_kind.get(&kind).copied().unwrap_or(self.default)
+    }
+
+    /// An alert about `kind` was forwarded: it is active (again).  Kinds
+    /// closed by their own messages (pre-alerts, impacts) are not tracked.
+    pub fn alerted(&mut self, kind: ThreatKind, proximity: Proximity, now: Instant) {
+        if matches!(
+            kind,
+            ThreatKind::BallisticWatch
+                | ThreatKind::NavalThreat
+                | ThreatKind::Explosion
+                | ThreatKind::AllClear
+                | ThreatKind::Other
+        ) || self.expiry(kind).is_zero()
+        {
+            return;
+        }
+        let proximity = match self.active.get(&kind) {
+            Some(a) => a.proximity.max(proximity),
+            None => proximity,
+        };
+        self.active.insert(
+            kind,
+            Active {
+                last_seen: now,
+                proximity,
+            },
+        );
+    }
+
+    /// A report of `kind` about the user's area arrived, forwarded or not:
+    /// it is still going on.
+    pub fn reported(&mut self, kind: ThreatKind, now: Instant) {
+        if let Some(a) = self.active.get_mut(&kind) {
+            a.last_seen = now;
+        }
+    }
+
+    /// The all-clear ends every threat.
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// Active kinds with no report for their expiry time, with the closest
+    /// proximity alerted; they are no longer active.
+    pub fn expire(&mut self, now: Instant) -> Vec<(ThreatKind, Proximity)> {
+        let stale: Vec<ThreatKind> = self
+            .active
+            .iter()
+            .filter(|(kind, a)| now.duration_since(a.last_seen) >= self.expiry(**kind))
+            .map(|(kind, _)| *kind)
+            .collect();
+        let mut expired: Vec<(ThreatKind, Proximity)> = stale
+            .into_iter()
+            .filter_map(|kind| self.active.remove(&kind).map(|a| (kind, a.proximity)))
+            .collect();
+        expired.sort_by_key(|(kind, _)| std::cmp::Reverse(kind.specificity()));
+        expired
+    }
+}
@@ -411,6 +411,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn recap_channels_forward_statistics_as_summary() {
+        let mut filter = kyiv_filter();
+        filter.recap_channels = vec!["пс зсу".into()];
+        let msg = "⚡️ ЗБИТО/ПОДАВЛЕНО 33 РАКЕТИ ТА 274 ВОРОЖИХ БПЛА\n\
+                   У ніч на 22 лютого противник завдав комбінованого удару.\n\
+                   Усього зафіксовано 345 засобів повітряного нападу:\n\
+                   - 22 балістичні ракети Іскандер-М/С-400;\n\
+                   - 18 крилатих ракет Х-101;\n\
+                   - 297 ударних БпЛА.\n\
+                   За попередніми даними, станом на 10:00, збито/подавлено 307 цілей.\n\
+                   Зафіксовано влучання на 14 локаціях.";
+        let r = filter.process("ПС ЗСУ", msg).expect("recap forwarded");
+        assert!(crate::filter::render::is_recap(&r));
+        assert!(filter.last_alert().is_none(), "recaps stay outside alerts");
+        assert!(filter.process("Other channel", msg).is_none());
+        // No dedup state was touched: a live alert still goes through.
+        assert!(filter.process("ПС ЗСУ", "балістика на київ").is_some());
+    }
+
     #[test]
     fn informational_filter_keeps_live_movement_alert() {
         let mut filter = kyiv_filter();
@@ -611,6 +631,7 @@ mod tests {
             oblast: vec!["харківськ".into()],
             city: vec!["ізюм".into()],
             district: vec![],
+            radius: None,
         };
         let p = loc.check("загроза для харківської області");
         assert_eq!(p, Proximity::Oblast);
@@ -622,6 +643,7 @@ mod tests {
             oblast: vec!["київщин".into()],
             city: vec!["на київ".into()],
             district: vec![],
+            radius: None,
         };
         let p = loc.check("2 циркони, курсом на київщину");
         assert_eq!(
@@ -674,6 +696,77 @@ mod tests {
         assert!(r2.is_some()); // upgrade Oblast → District
     }
 
+    #[test]
+    fn proximity_upgrade_renders_escalation_banner() {
+        let mut filter = kharkiv_filter();
+        let r1 = filter
+            .process("Ch1", "шахеди увійшли в харківську область")
+            .unwrap();
+        assert!(!r1.contains("⬆️"));
+        assert_eq!(filter.last_alert().unwrap().escalated_from, None);
+
+        let r2 = filter
+            .process("Ch2", "шахеди над київським районом харкова")
+            .unwrap();
+        assert!(r2.starts_with("⬆️ Загроза наблизилась: ОБЛАСТЬ → РАЙОН\n"));
+        assert_eq!(
+            filter.last_alert().unwrap().escalated_from,
+            Some(Proximity::Oblast)
+        );
+    }
+
+    #[test]
+    fn alert_type_tells_new_wave_from_follow_ups() {
+        let mut filter = kharkiv_filter();
+        let alert_type = |f: &AlertFilter| f.last_alert().map(|i| i.alert_type);
+        assert!(
+            filter
+                .process("Ch1", "шахеди увійшли в харківську область")
+                .is_some()
+        );
+        assert_eq!(alert_type(&filter), Some(AlertType::New));
+        assert!(
+            filter
+                .process("Ch2", "шахеди над київським районом харкова")
+                .is_some()
+        );
+        assert_eq!(alert_type(&filter), Some(AlertType::Escalation));
+        assert!(
+            filter
+                .process("Ch2", "повторно шахеди над київським районом харкова")
+                .is_some()
+        );
+        assert_eq!(alert_type(&filter), Some(AlertType::Update));
+        assert!(filter.process("Ch1", "Відбій тривоги").is_some());
+        assert_eq!(alert_type(&filter), Some(AlertType::AllClear));
+        assert_eq!(AlertType::AllClear.name(), "all_clear");
+    }
+
+    #[test]
+    fn course_change_away_sends_receding_update_and_relaxes_dedup() {
+        let mut filter = kyiv_filter();
+        assert!(filter.process("Ch1", "шахеди на київ").is_some());
+        assert!(filter.process("Ch2", "шахеди над києвом").is_none());
+
+        let away = filter
+            .process("Ch1", "шахед змінив курс на захід")
+            .expect("receding update");
+        assert!(away.starts_with("⬇️ Загроза віддаляється"));
+        assert!(filter.last_alert().unwrap().threats.is_empty());
+        // Only once per wave.
+        assert!(
+            filter
+                .process("Ch2", "шахед змінив курс на захід")
+                .is_none()
+        );
+
+        // A renewed approach is forwarded again, without an escalation banner.
+        let back = filter
+            .process("Ch3", "шахеди знову на київ")
+            .expect("re-alert");
+        assert!(!back.contains("⬆️"));
+    }
+
     #[test]
     fn dedup_allows_nationwide_after_local() {
         let mut filter = kyiv_filter();
@@ -1117,6 +1210,46 @@ mod tests {
         assert!(r.unwrap().contains("ВСЯ УКРАЇНА"));
     }
 
+    #[test]
+    fn nationwide_mig_alert_is_reduced_for_far_western_oblast() {
+        let msg = "Зліт МіГ-31К, повітряна тривога по всій території України";
+
+        let mut lviv = kyiv_filter();
+        lviv.location = LocationConfig {
+            oblast: vec!["львівськ".into()],
+            city: vec!["львів".into()],
+            district: Vec::new(),
+            radius: None,
+        };
+        lviv.reduced_nationwide_kinds = reduced_nationwide_kinds(
+            &lviv.location,
+            &FAR_WESTERN_OBLASTS.join(","),
+            "Aircraft,BallisticWatch",
+        );
+        assert_eq!(
+            lviv.reduced_nationwide_kinds,
+            vec![ThreatKind::Aircraft, ThreatKind::BallisticWatch]
+        );
+        let r = lviv.process("ПС ЗСУ", msg).unwrap();
+        assert!(r.contains("низький ризик"), "{r}");
+        assert!(lviv.last_alert().unwrap().reduced);
+
+        // Ballistic stays at full severity even far west.
+        let r = lviv
+            .process("ПС ЗСУ", "Загроза балістики по всій території України")
+            .unwrap();
+        assert!(!r.contains("низький ризик"));
+        assert!(!lviv.last_alert().unwrap().reduced);
+
+        // Kyiv is not in the list.
+        let mut kyiv = kyiv_filter();
+        kyiv.reduced_nationwide_kinds =
+            reduced_nationwide_kinds(&kyiv.location, &FAR_WESTERN_OBLASTS.join(","), "Aircraft");
+        assert!(kyiv.reduced_nationwide_kinds.is_empty());
+        let r = kyiv.process("ПС ЗСУ", msg).unwrap();
+        assert!(!r.contains("низький ризик"));
+    }
+
     // ── The exact sample message ──
 
     #[test]
@@ -1158,6 +1291,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn warmup_seeds_context_without_alerting() {
+        let mut filter = kyiv_filter();
+        let minute = Duration::from_secs(60);
+
+        // Too old for the 5-minute window: ignored.
+        filter.warm_context(1, "TestChannel", "балістична загроза з півдня", 10 * minute);
+        assert_eq!(filter.stats().channel_contexts, 0);
+        filter.warm_context(1, "TestChannel", "балістична загроза з півдня", 2 * minute);
+        assert_eq!(filter.stats().context_messages, 1);
+        assert!(filter.last_alert().is_none());
+
+        let out = filter
+            .process_with_id(1, "TestChannel", "ціль на київ")
+            .expect("trigger word resolved from warmed context");
+        assert!(out.contains("Балістика"), "{out}");
+
+        // An all-clear in the history empties the windows.
+        let mut filter = kyiv_filter();
+        filter.warm_context(1, "TestChannel", "балістична загроза з півдня", 3 * minute);
+        filter.warm_context(2, "Other", "відбій загрози балістики", 2 * minute);
+        assert_eq!(filter.stats().channel_contexts, 0);
+    }
+
+    #[test]
+    fn channel_context_rules_disable_inference_per_channel() {
+        use crate::filter::channel_policy::ChannelPolicies;
+
+        let mut filter = kyiv_filter();
+        filter.channel_policies =
+            ChannelPolicies::parse("chat=infer:none; radar=window:60,infer:trigger; bad=speed:9");
+        assert_eq!(filter.channel_policies.rule_count(), 2);
+
+        // Discussion channel: no threat inferred from its own history.
+        let _ = filter.process_with_id(1, "Kyiv Chat", "балістична загроза з півдня");
+        assert!(
+            filter
+                .process_with_id(1, "Kyiv Chat", "ціль на київ")
+                .is_none()
+        );
+
+        // Feed without global refine keeps the generic label.
+        let seed = filter.process_with_id(2, "Seed", "загроза балістики з брянська");
+        assert!(seed.is_none());
+        let out = filter
+            .process_with_id(3, "Radar", "4 ракети на київ")
+            .expect("alert forwarded");
+        assert!(!out.contains("Балістика"), "{out}");
+
+        let policy = filter.context_policy("KYIV RADAR");
+        assert_eq!(policy.window, Duration::from_secs(60));
+        assert!(policy.trigger && !policy.location && !policy.global_refine);
+        assert_eq!(
+            filter.context_policy("Other"),
+            filter.context_policy("Seed")
+        );
+    }
+
     #[test]
     fn context_fallback_does_not_relabel_explicit_nonlocal_message() {
         let mut filter = kyiv_filter();
@@ -1590,4 +1781,1552 @@ mod tests {
         // "ще виходи" (more launches) should trigger urgency.
         assert!(is_urgent("ще виходи на київ"));
     }
+
+    // ── Damage-report correlation helpers ──
+
+    #[test]
+    fn damage_report_detected_with_location() {
+        let filter = kyiv_filter();
+        let prox =
+            filter.damage_report_proximity("Ch", "Влучання в енергооб'єкт у Києві, є пошкодження");
+        assert_eq!(prox, Some(Proximity::City));
+    }
+
+    #[test]
+    fn damage_stems_do_not_match_inside_other_words() {
+        use crate::filter::threat_keywords::is_damage_report;
+        for text in [
+            "двое ранены, один ранен",
+            "есть раненые после удара",
+            "получил ранение",
+            "є поранені",
+            "пострадавших нет, обесточено 3 дома",
+        ] {
+            assert!(is_damage_report(text), "{text}");
+        }
+        for text in [
+            "энергоснабжение сохранено",
+            "объект охраняется, периметр охранен",
+            "раненько зранку шахеди на київ",
+            "данные сохранены",
+        ] {
+            assert!(!is_damage_report(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn damage_report_ignores_live_movement() {
+        let filter = kyiv_filter();
+        assert!(
+            filter
+                .damage_report_proximity("Ch", "Шахед летить на Київ, можливі влучання")
+                .is_none()
+        );
+        assert!(
+            filter
+                .damage_report_proximity("Ch", "балістика на київ")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn last_alert_tracks_forwarded_classification() {
+        let mut filter = kyiv_filter();
+        let r = filter.process_with_id(1, "Ch", "балістика на київ");
+        assert!(r.is_some());
+        let info = filter.last_alert().expect("alert info");
+        assert_eq!(info.threats, vec![ThreatKind::Ballistic]);
+        assert_eq!(info.proximity, Proximity::City);
+
+        // Suppressed duplicate clears the last alert.
+        let r2 = filter.process_with_id(2, "Other", "балістика на київ");
+        assert!(r2.is_none());
+        assert!(filter.last_alert().is_none());
+    }
+
+    // ── Threat level state machine ──
+
+    fn alert_info(threats: &[ThreatKind], proximity: Proximity) -> AlertInfo {
+        AlertInfo {
+            alert_type: AlertType::New,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn threat_level_from_alert_kinds() {
+        use crate::filter::threat_level::ThreatLevel;
+        let lvl = |t: &[ThreatKind]| ThreatLevel::from_alert(&alert_info(t, Proximity::City));
+        assert_eq!(lvl(&[ThreatKind::Shahed]), ThreatLevel::ActiveDrone);
+        assert_eq!(
+            lvl(&[ThreatKind::CruiseMissile]),
+            ThreatLevel::ActiveMissile
+        );
+        assert_eq!(
+            lvl(&[ThreatKind::Shahed, ThreatKind::Ballistic]),
+            ThreatLevel::CriticalBallistic
+        );
+        assert_eq!(lvl(&[ThreatKind::AllClear]), ThreatLevel::Clear);
+        assert_eq!(lvl(&[]), ThreatLevel::Watch);
+        // Outside the configured area → watch only.
+        let far = alert_info(&[ThreatKind::Ballistic], Proximity::None);
+        assert_eq!(ThreatLevel::from_alert(&far), ThreatLevel::Watch);
+    }
+
+    #[test]
+    fn status_tracker_keeps_highest_level_until_all_clear() {
+        use crate::filter::threat_level::{StatusTracker, ThreatLevel};
+        let mut status = StatusTracker::new(Duration::from_secs(600));
+        assert_eq!(status.current().0, ThreatLevel::Clear);
+
+        status.record(
+            &alert_info(&[ThreatKind::Ballistic], Proximity::City),
+            "Ch",
+            "балістика на київ",
+        );
+        let level = status.record(
+            &alert_info(&[ThreatKind::Shahed], Proximity::City),
+            "Ch",
+            "шахед на київ",
+        );
+        assert_eq!(level, ThreatLevel::CriticalBallistic);
+        let (_, entry) = status.current();
+        assert_eq!(entry.unwrap().text, "балістика на київ");
+
+        let level = status.record(
+            &alert_info(&[ThreatKind::AllClear], Proximity::None),
+            "Ch",
+            "відбій",
+        );
+        assert_eq!(level, ThreatLevel::Clear);
+        assert!(status.describe().contains("відбій"));
+    }
+
+    #[test]
+    fn air_raid_timer_starts_on_local_alert_and_resets_on_all_clear() {
+        use crate::filter::threat_level::{StatusTracker, format_duration};
+        let mut status = StatusTracker::new(Duration::from_secs(600));
+        status.record(
+            &alert_info(&[ThreatKind::Shahed], Proximity::None),
+            "Ch",
+            "шахед на одесу",
+        );
+        assert!(status.alert_duration().is_none());
+
+        status.record(
+            &alert_info(&[ThreatKind::Shahed], Proximity::Oblast),
+            "Ch",
+            "шахед на київщину",
+        );
+        assert!(status.alert_duration().is_some());
+        // Under a minute: nothing to show yet.
+        assert!(status.duration_note().is_none());
+
+        status.record(
+            &alert_info(&[ThreatKind::AllClear], Proximity::None),
+            "Ch",
+            "відбій",
+        );
+        assert!(status.alert_duration().is_none());
+
+        assert_eq!(format_duration(12), "триває 12 хв");
+        assert_eq!(format_duration(60), "триває 1 год");
+        assert_eq!(format_duration(72), "триває 1 год 12 хв");
+    }
+
+    // ── Rendering ──
+
+    #[test]
+    fn sanitize_strips_links_hashtags_and_promo() {
+        use crate::filter::render::sanitize_text;
+        let text = "Балістика на Київ! #тривога #київ\n\
+                    Всім в укриття\n\
+                    \n\
+                    👉 Підписатися https://t.me/+AbCdEf\n\
+                    t.me/some_channel";
+        assert_eq!(sanitize_text(text), "Балістика на Київ!\nВсім в укриття");
+    }
+
+    #[test]
+    fn sanitize_drops_repeated_lines() {
+        use crate::filter::render::sanitize_text;
+        let text = "Шахеди на Київ\nШахеди на Київ #київ\nwww.example.com";
+        assert_eq!(sanitize_text(text), "Шахеди на Київ");
+    }
+
+    #[test]
+    fn sanitized_filter_keeps_informational_body() {
+        let mut filter = kyiv_filter();
+        filter.sanitize_text = true;
+        let out = filter
+            .process(
+                "Ch",
+                "Балістика на Київ! #тривога\nНадіслати новину: @bot https://t.me/bot",
+            )
+            .expect("alert forwarded");
+        assert!(out.contains("Балістика на Київ!"));
+        assert!(!out.contains("#тривога"));
+        assert!(!out.contains("t.me"));
+        assert!(!out.contains("Надіслати"));
+    }
+
+    #[test]
+    fn truncation_keeps_headline_and_key_sentences() {
+        use crate::filter::render::truncate_text;
+        let text = "Загальна обстановка. Довгий вступ без фактів. Ще одне речення.\n\
+                    Ракета на Київ. Кінець.";
+        let out = truncate_text(text, 45, |s| s.contains("Київ"));
+        assert_eq!(out, "Загальна обстановка. … Ракета на Київ. …");
+        assert!(out.chars().count() <= 45);
+
+        // Short text is left alone; one oversized sentence is cut.
+        assert_eq!(
+            truncate_text("Ракета на Київ.", 45, |_| false),
+            "Ракета на Київ."
+        );
+        assert_eq!(truncate_text("абвгдеєжз", 5, |_| false), "абвг…");
+    }
+
+    #[test]
+    fn long_official_message_keeps_location_and_count_after_trimming() {
+        let mut filter = kyiv_filter();
+        let filler = "Нагадуємо правила безпеки під час повітряної тривоги. ".repeat(80);
+        let text = format!("Повітряна тривога. {filler}Група з 6 шахедів курсом на Київ.");
+        let out = filter.process("Ch", &text).expect("alert forwarded");
+        assert!(out.contains("Повітряна тривога."));
+        assert!(out.contains("Група з 6 шахедів курсом на Київ."));
+        assert!(out.contains('…'));
+        assert!(out.chars().count() < 4096);
+    }
+
+    #[test]
+    fn delivery_format_compact_and_hidden_parts() {
+        use crate::filter::render::DeliveryFormat;
+        let formatted = "🚀 Ракета · 🔴 МІСТО\n———\nРакета на Київ\nДеталі згодом\n— 📡 ПС ЗСУ";
+        assert_eq!(DeliveryFormat::default().apply(formatted), formatted);
+
+        let compact = DeliveryFormat {
+            compact: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            compact.apply(formatted),
+            "🚀 Ракета · 🔴 МІСТО\nРакета на Київ\n— 📡 ПС ЗСУ"
+        );
+
+        let header_only = DeliveryFormat {
+            compact: false,
+            show_text: false,
+            show_source: false,
+        };
+        assert_eq!(header_only.apply(formatted), "🚀 Ракета · 🔴 МІСТО");
+    }
+
+    #[test]
+    fn review_candidate_disagreement_ignores_order_and_duplicates() {
+        let same = ReviewCandidate {
+            keyword_threats: vec![ThreatKind::Shahed, ThreatKind::Ballistic],
+            llm_threats: vec![
+                ThreatKind::Ballistic,
+                ThreatKind::Shahed,
+                ThreatKind::Shahed,
+            ],
+            confidence: Some(0.9),
+        };
+        assert!(!same.disagrees());
+
+        let dropped = ReviewCandidate {
+            llm_threats: vec![ThreatKind::Ballistic],
+            ..same.clone()
+        };
+        assert!(dropped.disagrees());
+    }
+
+    #[test]
+    fn corridor_clusters_waypoints_across_channels() {
+        let mut filter = kyiv_filter();
+        // Kyiv oblast, from the gazetteer; the waypoint is remembered.
+        filter
+            .process_with_id(1, "Моніторинг", "Шахеди курсом на Бровари")
+            .expect("oblast alert");
+        assert_eq!(filter.last_alert().unwrap().proximity, Proximity::Oblast);
+        let alert = filter
+            .process_with_id(2, "Радар", "Шахед над Броварами, курсом на Київ")
+            .expect("city alert");
+        assert!(
+            alert.contains("\n🧭 Ймовірний напрямок: Бровари–Київ"),
+            "{alert}"
+        );
+    }
+
+    #[test]
+    fn shahed_group_track_links_messages_and_drops_turn_away() {
+        let mut filter = kyiv_filter();
+        filter
+            .process_with_id(1, "Моніторинг", "Група з 6 БпЛА курсом на Бровари")
+            .expect("oblast alert");
+        let alert = filter
+            .process_with_id(2, "Радар", "Група з 6 БпЛА через Бровари курсом на Київ")
+            .expect("city alert");
+        assert!(
+            alert.contains("🛤 Маршрут групи (6): Бровари → Київ"),
+            "{alert}"
+        );
+        let alert = filter
+            .process_with_id(1, "Моніторинг", "6 шахедів курсом на Шевченківський район")
+            .expect("district escalation");
+        assert!(alert.contains("Бровари → Київ → Шевченківський"), "{alert}");
+        // Next waypoint is farther from the user: suppressed.
+        assert!(
+            filter
+                .process_with_id(
+                    2,
+                    "Радар",
+                    "Група з 6 БпЛА курсом на Обухів, Київська область"
+                )
+                .is_none()
+        );
+
+        let mut tracks = tracks::ShahedTracks::new(Duration::from_secs(600));
+        tracks.observe(4, &["бровари".into()], Proximity::City, Instant::now());
+        let update = tracks
+            .observe(4, &["обухів".into()], Proximity::Oblast, Instant::now())
+            .unwrap();
+        assert!(update.moving_away);
+        assert_eq!(tracks::group_size("група з 4-х бпла"), Some(4));
+    }
+
+    #[test]
+    fn channel_contexts_are_capped_with_lru_eviction() {
+        let mut filter = kyiv_filter();
+        filter.max_channel_contexts = 3;
+        for channel_id in 1..=3 {
+            filter.process_with_id(channel_id, "Ch", "шахеди на київщину");
+        }
+        // Channel 1 posts again, so channel 2 is now the least recently used.
+        filter.process_with_id(1, "Ch", "ціль на київ");
+        filter.process_with_id(4, "Ch", "шахед на київ");
+
+        let stats = filter.stats();
+        assert_eq!(stats.channel_contexts, 3);
+        assert!(!filter.channel_contexts.contains_key(&2));
+        assert!(filter.channel_contexts.contains_key(&1));
+        assert_eq!(stats.context_messages, 4);
+        assert_eq!(stats.max_context_messages, 2);
+        assert_eq!(stats.dedup_entries, 1);
+    }
+
+    #[test]
+    fn last_suppression_names_the_reason() {
+        let mut filter = kyiv_filter();
+        assert!(filter.process("ch", "Шахед на Київ").is_some());
+        assert_eq!(filter.last_suppression(), None);
+        assert!(filter.process("ch2", "Шахед на Київ").is_none());
+        assert_eq!(filter.last_suppression(), Some(Suppression::Dedup));
+        assert!(filter.process("ch", "Шахед на Одесу").is_none());
+        assert_eq!(filter.last_suppression(), Some(Suppression::OutOfArea));
+        assert!(filter.process("ch", "Доброго ранку").is_none());
+        assert_eq!(filter.last_suppression(), Some(Suppression::NoThreat));
+    }
+
+    #[test]
+    fn dedup_separates_approach_directions() {
+        use crate::filter::gazetteer::PLACES;
+        use crate::filter::gazetteer::PlaceContext;
+        use crate::filter::region::{Sector, home_place, sector_of};
+
+        let filter = kyiv_filter();
+        let home = home_place(&filter.location).expect("Kyiv in gazetteer");
+        assert_eq!(home.name, "Київ");
+        assert_eq!(
+            sector_of(home, "шахед курсом на бориспіль", &PlaceContext::default()),
+            Some(Sector::E)
+        );
+        assert_eq!(
+            sector_of(home, "шахеди над фастовом", &PlaceContext::default()),
+            Some(Sector::SW)
+        );
+        assert_eq!(
+            sector_of(home, "шахед курсом на київ", &PlaceContext::default()),
+            None
+        );
+        assert!(Sector::N.near(Sector::NW) && !Sector::N.near(Sector::E));
+        let kharkiv = PLACES.iter().find(|p| p.name == "Харків").unwrap();
+        assert_eq!(Sector::between(home, kharkiv), Sector::E);
+
+        let mut filter = kyiv_filter();
+        assert!(
+            filter
+                .process_with_id(1, "ch1", "Київщина: шахед курсом на Бориспіль")
+                .is_some()
+        );
+        // Same kind from the opposite side is a new approach.
+        assert!(
+            filter
+                .process_with_id(2, "ch2", "Київщина: шахед курсом на Фастів")
+                .is_some()
+        );
+        // Repeats of either direction, or without one, are still deduped.
+        assert!(
+            filter
+                .process_with_id(3, "ch3", "Київщина: шахеди на Бориспіль")
+                .is_none()
+        );
+        assert!(
+            filter
+                .process_with_id(3, "ch3", "Київщина: шахед, будьте уважні")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn keyword_file_replaces_or_extends_builtin_lists() {
+        use crate::filter::threat_keywords::KeywordSet;
+
+        let builtin = KeywordSet::builtin();
+        let set = KeywordSet::from_toml(
+            r#"
+            urgency = ["Терміново", "  "]
+
+            [threats]
+            ReconDrone = ["орлан"]
+
+            [extra]
+            nationwide = ["по всій території країни"]
+
+            [extra.threats]
+            shahed = ["Мопед"]
+            "#,
+        )
+        .unwrap();
+        let stems = |set: &KeywordSet, kind| {
+            set.threats()
+                .iter()
+                .find(|(k, _)| *k == kind)
+                .map(|(_, stems)| stems.clone())
+                .unwrap()
+        };
+
+        assert_eq!(
+            set.urgency(),
+            ["терміново"],
+            "replaced, lowercased, blanks dropped"
+        );
+        assert!(!set.is_urgent("повторно шахеди"));
+        assert_eq!(stems(&set, ThreatKind::ReconDrone), ["орлан"]);
+        let shahed = stems(&set, ThreatKind::Shahed);
+        assert_eq!(shahed.len(), stems(&builtin, ThreatKind::Shahed).len() + 1);
+        assert_eq!(shahed.last().unwrap(), "мопед");
+        assert!(set.is_nationwide("тривога по всій території країни"));
+        assert!(
+            set.is_nationwide("тривога по всій україні"),
+            "built-ins kept"
+        );
+        assert_eq!(
+            stems(&set, ThreatKind::Ballistic),
+            stems(&builtin, ThreatKind::Ballistic)
+        );
+
+        assert!(KeywordSet::from_toml("[threats]\nRocket = [\"x\"]").is_err());
+        assert!(KeywordSet::from_toml("urgncy = [\"x\"]").is_err());
+    }
+
+    #[test]
+    fn keyword_scan_matches_substring_search() {
+        use crate::filter::boundary::{self, Boundary};
+        use crate::filter::threat_keywords::KeywordSet;
+
+        let set = KeywordSet::from_toml(
+            r#"
+            [extra]
+            urgency = ["циркон"]
+            "#,
+        )
+        .unwrap();
+        let naive = |lower: &str| {
+            let any = |stems: &[String]| {
+                stems.iter().any(|s| {
+                    let (bound, stem) = Boundary::parse(s);
+                    boundary::contains(lower, stem, bound)
+                })
+            };
+            let threats: Vec<ThreatKind> = set
+                .threats()
+                .iter()
+                .filter(|(_, stems)| any(stems))
+                .map(|(kind, _)| *kind)
+                .collect();
+            (threats, any(set.urgency()), any(set.nationwide()))
+        };
+        for text in [
+            "циркон на київ",
+            "повторно балістика! іскандер з криму",
+            "увага! шахеди та ракети по всій україні",
+            "відбій тривоги",
+            "без загроз",
+        ] {
+            let hits = set.scan(text);
+            assert_eq!(
+                (hits.threats, hits.urgent, hits.nationwide),
+                naive(text),
+                "{text}"
+            );
+        }
+        // A stem shared by two lists counts for both.
+        let hits = set.scan("циркон");
+        assert!(hits.urgent && hits.threats == [ThreatKind::Hypersonic]);
+    }
+
+    #[test]
+    fn stemmed_words_match_any_inflection_but_not_longer_words() {
+        use crate::filter::stem::stem;
+
+        for word in ["дрон", "дрони", "дронів", "дронами", "дронов", "дроном"]
+        {
+            assert_eq!(stem(word), "дрон", "{word}");
+        }
+        assert_eq!(stem("каби"), "каб");
+        assert_eq!(stem("ту"), "ту", "too short to strip");
+        assert_eq!(stem("кабінет"), "кабінет");
+
+        let guided = |text: &str| detect_threats(text).contains(&ThreatKind::GuidedBomb);
+        assert!(
+            guided("на сумщину летять каб"),
+            "word at the end of the text"
+        );
+        assert!(guided("скидають каби на харків"));
+        assert!(guided("пуски кабів, фаб-500"));
+        assert!(!guided("нарада в кабінеті"));
+        assert!(detect_threats("група дронами на київ").contains(&ThreatKind::Shahed));
+        assert!(!detect_threats("кедрова шишка").contains(&ThreatKind::Ballistic));
+        assert!(detect_threats("пуск ракети кедр").contains(&ThreatKind::Ballistic));
+    }
+
+    #[test]
+    fn mig31k_takeoff_is_a_nationwide_watch_closed_by_landing() {
+        let mut f = kyiv_filter();
+        let takeoff = "Зліт МіГ-31К – носія Кінжалів";
+        assert_eq!(
+            detect_threats(&takeoff.to_lowercase()),
+            vec![ThreatKind::BallisticWatch]
+        );
+        let r = f.process("ПС ЗСУ", takeoff).expect("watch opened");
+        assert!(r.starts_with("🛫 Зліт МіГ-31К · 🟣 ВСЯ УКРАЇНА"), "{r}");
+        assert!(r.contains(render::WATCH_NOTE), "{r}");
+        assert!(f.last_alert().unwrap().nationwide);
+
+        // Own dedup wave: a local aviation alert still goes through, a
+        // repeated takeoff report does not.
+        assert!(
+            f.process("Моніторинг", "Активність тактичної авіації, Київщина")
+                .is_some()
+        );
+        assert!(f.process("Інший канал", "Взлет МиГ-31К").is_none());
+
+        let r = f
+            .process("ПС ЗСУ", "МіГ-31К приземлився")
+            .expect("watch closed");
+        assert!(r.starts_with(render::WATCH_CLOSED_HEADER), "{r}");
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::Status);
+        assert!(
+            f.process("Інший канал", "Посадка МіГ-31К").is_none(),
+            "closed once"
+        );
+
+        // The next takeoff opens a fresh watch.
+        assert!(f.process("ПС ЗСУ", takeoff).is_some());
+        assert!(f.process("ПС ЗСУ", "Відбій загрози МіГ-31К").is_some());
+    }
+
+    #[test]
+    fn keyword_stems_respect_word_boundaries() {
+        use crate::filter::boundary::{self, Boundary};
+        use crate::filter::threat_keywords::KeywordSet;
+
+        assert_eq!(Boundary::parse("=кр"), (Boundary::Word, "кр"));
+        assert_eq!(Boundary::parse("*авіа"), (Boundary::Infix, "авіа"));
+        assert_eq!(Boundary::parse("ракет"), (Boundary::Stem, "ракет"));
+        assert!(boundary::contains("2 ракети", "ракет", Boundary::Stem));
+        assert!(!boundary::contains(
+            "протиракетна оборона",
+            "ракет",
+            Boundary::Stem
+        ));
+        assert!(boundary::contains("протиракетна", "ракет", Boundary::Infix));
+        assert!(boundary::contains("2кр на київ", "кр", Boundary::Word));
+        assert!(!boundary::contains("крок вперед", "кр", Boundary::Word));
+
+        // Built-in combos no longer find "са" / "кр" inside other words.
+        assert!(!detect_threats("борти самі в повітрі").contains(&ThreatKind::Aircraft));
+        assert!(detect_threats("борти СА в повітрі").contains(&ThreatKind::Aircraft));
+        assert!(!detect_threats("кращі новини: курс валют").contains(&ThreatKind::CruiseMissile));
+
+        let set = KeywordSet::from_toml(
+            r#"
+            [extra.threats]
+            CruiseMissile = ["=кр"]
+            Shahed = ["*мопед"]
+            "#,
+        )
+        .unwrap();
+        assert!(
+            set.scan("кр на київ")
+                .threats
+                .contains(&ThreatKind::CruiseMissile)
+        );
+        assert!(
+            !set.scan("крок")
+                .threats
+                .contains(&ThreatKind::CruiseMissile)
+        );
+        assert!(
+            set.scan("електромопеди")
+                .threats
+                .contains(&ThreatKind::Shahed)
+        );
+        assert!(
+            !set.scan("протиракетна")
+                .threats
+                .contains(&ThreatKind::Missile)
+        );
+    }
+
+    #[test]
+    fn namesake_of_home_city_is_disambiguated() {
+        use crate::filter::gazetteer::{self, PlaceContext};
+        use crate::filter::region::home_place;
+
+        let mykolaiv = || {
+            let mut f = kyiv_filter();
+            f.location = LocationConfig {
+                oblast: vec!["миколаївськ".into()],
+                city: vec!["миколаїв".into(), "миколаєв".into()],
+                district: Vec::new(),
+                radius: None,
+            };
+            f.ambiguous_home = home_place(&f.location).filter(|p| gazetteer::has_namesakes(p));
+            f
+        };
+        assert_eq!(mykolaiv().ambiguous_home.unwrap().oblast, "Миколаївська");
+        let lviv = LocationConfig {
+            oblast: vec!["львівськ".into()],
+            city: vec!["миколаїв".into()],
+            district: Vec::new(),
+            radius: None,
+        };
+        assert_eq!(home_place(&lviv).unwrap().oblast, "Львівська");
+
+        // The message names the oblast.
+        let mut f = mykolaiv();
+        assert!(
+            f.process("Моніторинг", "Шахед на Миколаїв, Львівщина")
+                .is_none()
+        );
+        assert_eq!(f.last_suppression(), Some(Suppression::OutOfArea));
+
+        // A place named alongside it.
+        let mut f = mykolaiv();
+        let r = f
+            .process("Моніторинг", "Шахеди над Одесою, курсом на Миколаїв")
+            .unwrap();
+        assert!(!r.contains("❔"), "{r}");
+        assert!(!f.last_alert().unwrap().location_uncertain);
+
+        // The channel's region.
+        let mut f = mykolaiv();
+        assert!(
+            f.process("Радар Львівщини", "Дрон над Миколаєвом")
+                .is_none()
+        );
+
+        // Nothing to go by.
+        let mut f = mykolaiv();
+        let r = f.process("Моніторинг", "Шахед на Миколаїв").unwrap();
+        assert!(r.contains("❔ «Миколаїв» є в кількох областях"), "{r}");
+        assert!(f.last_alert().unwrap().location_uncertain);
+
+        let named = gazetteer::places_named("первомайська");
+        assert_eq!(named.len(), 2);
+        let ctx = PlaceContext {
+            channel_oblast: Some("Луганська"),
+            ..PlaceContext::default()
+        };
+        let resolved = gazetteer::resolve(&named, &ctx).unwrap();
+        assert!(resolved.confident && resolved.place.oblast == "Луганська");
+    }
+
+    #[test]
+    fn impact_reports_are_explosions_not_incoming_threats() {
+        assert_eq!(
+            detect_threats("вибухи у місті, зафіксовано влучання"),
+            vec![ThreatKind::Explosion]
+        );
+        assert_eq!(detect_threats("будет громко"), vec![ThreatKind::Other]);
+
+        let mut f = kyiv_filter();
+        let r = f
+            .process("Київ онлайн", "Вибухи у Шевченківському районі")
+            .unwrap();
+        assert!(r.starts_with("💥 Вибухи / влучання"), "{r}");
+        let info = f.last_alert().unwrap().clone();
+        assert!(
+            !crate::escalation::is_critical(&info),
+            "no call for impacts"
+        );
+        // Echoes are deduped; another part of the area is news.
+        assert!(
+            f.process("Інший канал", "Прильот у Шевченківському районі")
+                .is_none()
+        );
+        assert!(f.process("Інший канал", "Вибухи в Києві").is_some());
+
+        // Impacts do not re-open a Shahed wave as a new combination.
+        assert!(f.process("ch", "Шахед на Київ").is_some());
+        assert!(f.process("ch2", "Шахед на Київ, чути вибухи").is_none());
+    }
+
+    #[test]
+    fn shelling_reports_are_artillery() {
+        assert_eq!(
+            detect_threats("обстріл з рсзв по харкову"),
+            vec![ThreatKind::Artillery]
+        );
+        assert_eq!(detect_threats("працює арта"), vec![ThreatKind::Artillery]);
+        assert_eq!(
+            detect_threats("обстріл градами"),
+            vec![ThreatKind::Artillery]
+        );
+        assert!(detect_threats("мінус п'ять градусів, артисти").is_empty());
+        assert_eq!(detect_threats("обстріл міста"), vec![ThreatKind::Other]);
+
+        let mut f = kharkiv_filter();
+        let r = f
+            .process("ch", "Харків, обстріл з РСЗВ")
+            .expect("shelling of the city");
+        assert!(r.contains("💢 Артобстріл / РСЗВ"), "{r}");
+        // Own dedup wave: a drone alert still goes through, a repost does not.
+        assert!(f.process("ch2", "Шахед на Харків").is_some());
+        assert!(f.process("ch3", "Харків, обстріл з РСЗВ").is_none());
+    }
+
+    #[test]
+    fn carrier_deployment_is_a_naval_pre_alert_forwarded_once() {
+        let deployment = "Носії Калібрів вийшли в Чорне море, залп до 16 ракет";
+        assert_eq!(
+            detect_threats(&deployment.to_lowercase()),
+            vec![ThreatKind::NavalThreat]
+        );
+        // Launches are cruise missiles, not a deployment.
+        assert!(
+            detect_threats("пуски калібрів з чорного моря, курсом на київ")
+                .contains(&ThreatKind::CruiseMissile)
+        );
+
+        let mut f = kyiv_filter();
+        let r = f.process("ch", deployment).expect("pre-alert");
+        assert!(
+            r.starts_with("🚢 Носії «Калібрів» у морі · 🟣 ВСЯ УКРАЇНА"),
+            "{r}"
+        );
+        assert!(r.contains(render::NAVAL_NOTE), "{r}");
+        let info = f.last_alert().unwrap();
+        assert!(info.nationwide && !info.urgent);
+        assert!(
+            f.process("ch2", "УВАГА! У Чорному морі 2 кораблі-носії, залп до 16")
+                .is_none(),
+            "same deployment"
+        );
+    }
+
+    #[test]
+    fn bare_mig31k_takeoff_is_a_nationwide_pre_alert_anywhere() {
+        // No "балістика", no "по всій території", another oblast named.
+        for msg in [
+            "Зліт МіГ-31К",
+            "Піднято МіГ-31К",
+            "Увага! Зліт МіГ-31К, загроза для Одещини",
+        ] {
+            let mut f = kharkiv_filter();
+            let r = f.process("ПС ЗСУ", msg).expect(msg);
+            assert!(r.starts_with("🛫 Зліт МіГ-31К · 🟣 ВСЯ УКРАЇНА"), "{r}");
+            assert!(f.last_alert().unwrap().nationwide);
+        }
+
+        // "Увага" on a repeated takeoff report is not an urgent re-alert.
+        let mut f = kyiv_filter();
+        assert!(f.process("ПС ЗСУ", "Зліт МіГ-31К").is_some());
+        assert!(f.process("Інший канал", "Увага! Зліт МіГ-31К").is_none());
+    }
+
+    #[test]
+    fn only_target_locations_drive_proximity() {
+        use crate::filter::origin::targets_only;
+
+        assert_eq!(
+            targets_only("балістика з брянська на київ").trim(),
+            "балістика на київ"
+        );
+        assert_eq!(
+            targets_only("шахеди з боку брянської області, курс на суми").trim(),
+            "шахеди курс на суми"
+        );
+        assert!(targets_only("бпла в 20 км от киева").contains("киева"));
+        assert!(matches!(
+            targets_only("шахед на київ"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let mut f = kyiv_filter();
+        assert!(
+            f.process("Ch", "Пуски балістики з Київщини на Харків")
+                .is_none()
+        );
+        let mut f = kharkiv_filter();
+        let r = f
+            .process("Ch", "Пуски балістики з Київщини на Харків")
+            .expect("target is Kharkiv");
+        assert!(r.contains("Балістика"), "{r}");
+        assert_eq!(f.last_alert().unwrap().proximity, Proximity::City);
+
+        // The launch area is not "another region" either.
+        let mut f = kyiv_filter();
+        f.process("Ch", "Балістика з Брянська на Київ")
+            .expect("Kyiv");
+        assert_eq!(f.last_alert().unwrap().proximity, Proximity::City);
+    }
+
+    #[test]
+    fn gazetteer_extracts_named_settlements_into_alert_metadata() {
+        use crate::filter::gazetteer::{PlaceContext, mentioned_places};
+
+        let names = |lower: &str| -> Vec<(&str, Option<&str>, &str)> {
+            mentioned_places(lower, &PlaceContext::default())
+                .iter()
+                .map(|p| (p.name, p.raion, p.oblast))
+                .collect()
+        };
+        assert_eq!(
+            names("шахеди над кременчуком, курс на гадяч"),
+            vec![
+                ("Кременчук", Some("Кременчуцький"), "Полтавська"),
+                ("Гадяч", Some("Миргородський"), "Полтавська"),
+            ]
+        );
+        // Multi-word names, declined, without also matching their parts.
+        assert_eq!(
+            names("вибухи у новій каховці та білій церкві"),
+            vec![
+                ("Нова Каховка", Some("Каховський"), "Херсонська"),
+                ("Біла Церква", Some("Білоцерківський"), "Київська"),
+            ]
+        );
+        assert_eq!(names("загроза для кам’янського")[0].0, "Кам'янське");
+        assert!(names("шахеди в повітрі").is_empty());
+
+        let mut f = kyiv_filter();
+        f.process("Ch", "Шахеди на Київ через Бровари").unwrap();
+        let places: Vec<&str> = f
+            .last_alert()
+            .unwrap()
+            .places
+            .iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(places, vec!["Київ", "Бровари"]);
+    }
+
+    #[test]
+    fn gazetteer_settlements_infer_proximity_without_keywords() {
+        let only_city = |city: &str| LocationConfig {
+            oblast: Vec::new(),
+            city: vec![city.into()],
+            district: Vec::new(),
+            radius: None,
+        };
+        let kyiv = only_city("київ");
+        assert_eq!(kyiv.check("шахеди на бровари"), Proximity::Oblast);
+        assert_eq!(kyiv.check("вибухи у білій церкві"), Proximity::Oblast);
+        assert_eq!(kyiv.check("шахеди на суми"), Proximity::None);
+
+        // Same raion as home: city level.
+        let kremenchuk = only_city("кременчук");
+        assert_eq!(
+            kremenchuk.check("бпла над горішніми плавнями"),
+            Proximity::City
+        );
+        assert_eq!(kremenchuk.check("бпла на миргород"), Proximity::Oblast);
+
+        let mut f = kyiv_filter();
+        f.process("Ch", "Шахеди курсом на Бровари")
+            .expect("Kyiv oblast");
+        assert_eq!(f.last_alert().unwrap().proximity, Proximity::Oblast);
+        // A launch area in the home oblast does not count.
+        let mut f = kyiv_filter();
+        assert!(f.process("Ch", "Шахеди з Броварів на Чернігів").is_none());
+    }
+
+    #[test]
+    fn radius_location_uses_distance_bands_with_keyword_fallback() {
+        use crate::filter::Radius;
+        use crate::filter::region::home_place;
+
+        // Central Kyiv, 90 km: city up to 30 km, oblast up to 90 km.
+        let loc = LocationConfig {
+            oblast: Vec::new(),
+            city: Vec::new(),
+            district: vec!["оболон".into()],
+            radius: Some(Radius {
+                lat: 50.45,
+                lon: 30.52,
+                km: 90.0,
+            }),
+        };
+        assert_eq!(loc.check("шахед над броварами"), Proximity::City);
+        assert_eq!(loc.check("шахед над білою церквою"), Proximity::Oblast);
+        // Kyiv oblast, but farther than the radius.
+        assert_eq!(loc.check("шахед над славутичем"), Proximity::None);
+        assert_eq!(loc.check("шахед над житомиром"), Proximity::None);
+        // Keywords still apply.
+        assert_eq!(loc.check("бпла над оболонню"), Proximity::District);
+        assert_eq!(home_place(&loc).unwrap().name, "Київ");
+    }
+
+    #[test]
+    fn alerts_heading_for_the_user_carry_an_arrival_window() {
+        let mut f = kyiv_filter();
+        let r = f
+            .process("Ch", "Шахеди над Лубнами, курсом на Київ")
+            .expect("city alert");
+        assert!(
+            r.contains("⏳ Орієнтовний підліт: 45–75 хв (~182 км, останнє місце: Лубни)"),
+            "{r}"
+        );
+
+        // The position is remembered across channels; a heading elsewhere
+        // gets no estimate.
+        let mut f = kyiv_filter();
+        assert!(
+            f.process_with_id(1, "Полтава", "Шахеди над Лубнами")
+                .is_none()
+        );
+        let r = f
+            .process_with_id(2, "Київ", "Шахеди курсом на Київ")
+            .expect("city alert");
+        assert!(r.contains("останнє місце: Лубни"), "{r}");
+        let r = f
+            .process_with_id(
+                3,
+                "Київ",
+                "КР над Гадячем курсом на Полтаву, Київщина увага",
+            )
+            .expect("oblast alert");
+        assert!(!r.contains("⏳"), "{r}");
+    }
+
+    #[test]
+    fn threat_counts_show_in_the_alert_header() {
+        use crate::filter::counts::threat_counts;
+        use ThreatKind::*;
+
+        assert_eq!(
+            threat_counts("балістика! 4 ракети на київ", &[Ballistic]),
+            vec![(Ballistic, 4)]
+        );
+        assert_eq!(
+            threat_counts("група ~10х бпла на бровари", &[Shahed]),
+            vec![(Shahed, 10)]
+        );
+        assert_eq!(
+            threat_counts(
+                "2х кр на київ, 6 шахедів на бориспіль",
+                &[CruiseMissile, Shahed]
+            ),
+            vec![(CruiseMissile, 2), (Shahed, 6)]
+        );
+        // Measures and group counts are not threat counts.
+        assert!(threat_counts("шахед за 20 км від києва, 5 хв", &[Shahed]).is_empty());
+        assert!(threat_counts("2 групи шахедів на київ", &[Shahed]).is_empty());
+
+        let mut filter = kyiv_filter();
+        let alert = filter
+            .process("Ch", "Балістика! 4 ракети на Київ")
+            .expect("ballistic to the city");
+        assert!(alert.starts_with("‼️🚀 Балістика ×4 · "), "{alert}");
+    }
+
+    #[test]
+    fn threat_wave_spans_the_attack_until_the_all_clear() {
+        let mut f = kyiv_filter();
+        assert!(f.wave().is_none());
+        f.process("a", "Балістика! 4 ракети на Київ")
+            .expect("forwarded");
+        f.process("b", "Балістика на Київ");
+        f.process("b", "6 шахедів на Харківщину");
+        f.process("c", "Доброго ранку");
+
+        let wave = f.wave().expect("attack under way");
+        assert_eq!(wave.messages, 3, "only threat reports count");
+        assert_eq!(wave.forwarded, 1, "a duplicate and an out-of-area report");
+        assert_eq!(wave.kinds, vec![ThreatKind::Ballistic, ThreatKind::Shahed]);
+        assert_eq!(
+            wave.counts,
+            vec![(ThreatKind::Ballistic, 4), (ThreatKind::Shahed, 6)]
+        );
+        assert_eq!(wave.regions, vec!["Харківська"]);
+        assert_eq!(wave.max_proximity, Proximity::City);
+
+        f.process("a", "Відбій тривоги").expect("all-clear");
+        assert!(f.wave().is_none(), "the all-clear ends the wave");
+    }
+
+    #[test]
+    fn all_clear_carries_a_recap_of_the_wave() {
+        let mut f = kyiv_filter();
+        f.process("a", "Балістика! 2 ракети на Київ")
+            .expect("forwarded");
+        f.process("b", "12 шахедів на Київ").expect("another kind");
+        let all_clear = f.process("a", "Відбій тривоги").expect("all-clear");
+        assert!(
+            all_clear.ends_with(
+                "\n📋 Хвиля тривала 0 хв: Балістика ×2, Шахед / дрон ×12 · надіслано 2 алерти"
+            ),
+            "{all_clear}"
+        );
+
+        // No wave since: a bare all-clear has nothing to recap.
+        let again = f.process("b", "Відбій тривоги").expect("all-clear");
+        assert!(!again.contains("📋"), "{again}");
+    }
+
+    #[test]
+    fn all_clear_for_another_region_keeps_local_state() {
+        let mut f = kyiv_filter();
+        f.process("a", "Балістика на Київ").expect("forwarded");
+
+        for msg in ["Відбій у Харківській області", "Відбій тривоги в Лубнах"]
+        {
+            assert!(f.process("b", msg).is_none(), "{msg}");
+            assert_eq!(f.last_suppression(), Some(Suppression::OutOfArea));
+        }
+        assert!(f.wave().is_some(), "the attack here goes on");
+        assert!(
+            f.process("c", "Балістика на Київ").is_none(),
+            "dedup survives another region's all-clear"
+        );
+
+        let all_clear = f
+            .process("a", "Відбій тривоги у Київській області")
+            .expect("local all-clear");
+        assert!(all_clear.contains("📋 Хвиля тривала"), "{all_clear}");
+        assert!(f.wave().is_none());
+
+        let mut f = kyiv_filter();
+        f.forward_all_threats = true;
+        f.process("a", "Балістика на Київ").expect("forwarded");
+        f.process("b", "Відбій на Сумщині")
+            .expect("forwarded as status");
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::Status);
+        assert!(f.wave().is_some());
+    }
+
+    #[test]
+    fn threats_without_an_all_clear_expire_once() {
+        use crate::filter::clock::SimulatedClock;
+        use crate::filter::expiry::ActiveThreats;
+        use std::sync::Arc;
+
+        let clock = Arc::new(SimulatedClock::new());
+        let mut f = kyiv_filter().with_clock(clock.clone());
+        f.active_threats = ActiveThreats::from_spec("Shahed=30");
+        // Longer than the expiry, so only the expiry closes the wave.
+        f.dedup_window = Duration::from_secs(3600);
+        f.process("a", "Балістика на Київ").expect("forwarded");
+        f.process("b", "Шахеди на Київ").expect("forwarded");
+        assert!(f.expire_threats().is_none(), "both still fresh");
+
+        clock.advance(Duration::from_secs(21 * 60));
+        let notice = f
+            .expire_threats()
+            .expect("ballistic expires after 20 minutes");
+        assert!(notice.starts_with("🕓 Загроза ймовірно минула\n‼️🚀 Балістика · 🟠 МІСТО\n"));
+        assert!(!notice.contains("Шахед"), "{notice}");
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::Status);
+        clock.advance(Duration::from_secs(4 * 60));
+        assert!(f.expire_threats().is_none(), "reported once");
+        assert!(
+            f.process("c", "Балістика на Київ").is_some(),
+            "a new report alerts afresh"
+        );
+
+        f.process("b", "Відбій тривоги").expect("all-clear");
+        clock.advance(Duration::from_secs(3 * 3600));
+        assert!(f.expire_threats().is_none(), "the all-clear ended the rest");
+    }
+
+    #[test]
+    fn nationwide_alert_narrowing_to_the_city_is_an_escalation() {
+        let mut f = kyiv_filter();
+        let first = f
+            .process("a", "Загроза балістики по всій території України")
+            .expect("nationwide");
+        assert!(!first.contains("⬆️"), "{first}");
+
+        let local = f.process("b", "Балістика на Київ").expect("narrowed");
+        assert!(
+            local.starts_with("⬆️ Загроза наблизилась: ВСЯ УКРАЇНА → МІСТО\n"),
+            "{local}"
+        );
+        let info = f.last_alert().unwrap();
+        assert_eq!(info.escalated_from, Some(Proximity::Oblast));
+        assert_eq!(info.alert_type, AlertType::Escalation);
+    }
+
+    #[test]
+    fn passing_the_user_towards_elsewhere_is_a_de_escalation() {
+        let mut f = kyiv_filter();
+        assert!(f.is_passing_by("шахеди пройшли повз київ, курс на житомир"));
+        assert!(f.is_passing_by("пролетели мимо киева"));
+        assert!(!f.is_passing_by("шахеди пройшли бориспіль, курс на київ"));
+        assert!(!f.is_passing_by("шахеди пройшли житомир"));
+        assert!(f.is_passing_by("шахед пролетів над києвом і далі на захід"));
+        assert!(!f.is_passing_by("шахеди пройшли бровари і летять на київ"));
+
+        f.process("a", "Шахеди на Київ").expect("forwarded");
+        let passed = f
+            .process("b", "Шахеди пройшли повз Київ, курс на Житомир")
+            .expect("pass-by update");
+        assert!(
+            passed.starts_with("↪️ Загроза пройшла повз ваш район · 🔺 Шахед / дрон\n"),
+            "{passed}"
+        );
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::Status);
+        assert!(
+            f.process("c", "Шахеди пройшли повз Київ, курс на Житомир")
+                .is_none(),
+            "once per wave"
+        );
+    }
+
+    /// A pass-by marker that does not govern the user's area, or a
+    /// message that also names the area as the target, is still an alert.
+    #[test]
+    fn pass_by_elsewhere_in_the_message_does_not_hide_an_alert() {
+        for text in [
+            "Балістика на Київ! Попередні пройшли повз",
+            "Шахеди на Київ, перша група пройшла повз Бровари",
+            "Шахеди курсом на Київ, вже пройшли Бровари",
+            "Увага! Київ - шахеди, як і минулого разу прошли через Бровари",
+        ] {
+            let mut f = kyiv_filter();
+            assert!(f.process("a", text).is_some(), "{text}");
+        }
+    }
+
+    #[test]
+    fn confidence_score_ranks_explicit_over_inferred_alerts() {
+        let mut f = kyiv_filter();
+        f.show_confidence = true;
+        let explicit = f.process("a", "Балістика на Київ").expect("forwarded");
+        assert!(explicit.ends_with("\n🎯 Впевненість: 90%"), "{explicit}");
+        assert_eq!(f.last_alert().unwrap().confidence, Some(90));
+
+        // Threat and location both inferred from the channel's last post.
+        let mut f = kyiv_filter();
+        f.process("b", "Шахеди на Київ").expect("forwarded");
+        f.process("b", "Увага!").expect("urgent re-alert");
+        assert_eq!(f.last_alert().unwrap().confidence, Some(55));
+
+        let mut f = kyiv_filter();
+        f.min_confidence = 60;
+        f.process("b", "Шахеди на Київ").expect("forwarded");
+        assert!(f.process("b", "Увага!").is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::LowConfidence));
+    }
+
+    #[test]
+    fn distrusted_channel_needs_stronger_evidence() {
+        use crate::filter::confidence::fuse;
+        assert_eq!(fuse(80, None, None), 80);
+        assert_eq!(fuse(80, Some(0.2), None), 50);
+        assert_eq!(fuse(80, Some(0.2), Some(0.2)), 44);
+
+        let mut f = kyiv_filter();
+        f.set_channel_trust(HashMap::from([(7, 0.1)]));
+        let explicit = f.process_with_id(7, "noisy", "Балістика на Київ");
+        assert!(explicit.is_some());
+        assert_eq!(f.last_alert().unwrap().confidence, Some(63));
+
+        // An inferred re-alert that would pass from a trusted channel.
+        let mut f = kyiv_filter();
+        f.set_channel_trust(HashMap::from([(7, 0.1)]));
+        f.process_with_id(7, "noisy", "Шахеди на Київ")
+            .expect("forwarded");
+        assert!(f.process_with_id(7, "noisy", "Увага!").is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::LowConfidence));
+    }
+
+    #[test]
+    fn channel_reliability_learns_from_corroboration() {
+        use crate::filter::reliability::Reliability;
+        use ThreatKind::*;
+        let kinds = [
+            Ballistic,
+            Hypersonic,
+            CruiseMissile,
+            GuidedBomb,
+            Missile,
+            Shahed,
+            ReconDrone,
+            Aircraft,
+            Artillery,
+            BallisticWatch,
+        ];
+        let now = Instant::now();
+        let mut r = Reliability::default();
+        for kind in kinds {
+            r.report(1, kind, now); // first
+            r.report(2, kind, now); // echo
+            r.report(2, kind, now); // repeating itself
+        }
+        let score = |id| r.score(id).expect("enough reports");
+        assert!(score(1) > 0.9, "{}", score(1));
+        assert!((score(2) - 0.5).abs() < 0.01, "{}", score(2));
+
+        // Nobody confirms channel 7: its "повторно" does not reopen the wave.
+        let mut f = kyiv_filter();
+        for kind in kinds {
+            f.reliability.report(7, kind, now);
+        }
+        assert!(f.channel_reliability(7).unwrap() < 0.3);
+        f.process_with_id(8, "a", "Шахеди на Київ")
+            .expect("forwarded");
+        assert!(
+            f.process_with_id(7, "b", "Повторно шахеди на Київ")
+                .is_none()
+        );
+        assert!(
+            f.process_with_id(9, "c", "Повторно шахеди на Київ")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn vague_threat_waits_for_corroborating_channels() {
+        use crate::filter::corroboration::Corroboration;
+        let mut f = kyiv_filter();
+        f.corroboration = Corroboration::new(2, Duration::from_secs(300));
+        assert!(f.process("a", "Орлан над Києвом").is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::Uncorroborated));
+        assert!(f.process("a", "Орлан над Києвом").is_none());
+        let alert = f.process("b", "Орлан над Києвом").expect("corroborated");
+        assert!(
+            alert.contains("📡 Підтверджено 2 каналами: a, b"),
+            "{alert}"
+        );
+
+        // Specific threats stay instant.
+        assert!(f.process("c", "Балістика на Київ").is_some());
+    }
+
+    #[test]
+    fn suppress_keywords_drop_a_message_before_detection() {
+        use crate::filter::threat_keywords::KeywordSet;
+        let set = KeywordSet::from_toml(
+            r#"
+            suppress = ["Підсумки доби"]
+
+            [extra]
+            suppress = ["=донат"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(set.suppress(), ["підсумки доби", "=донат"]);
+        assert!(set.scan("підсумки доби: 40 шахедів").suppressed);
+        assert!(set.scan("донат на дрони, шахеди над києвом").suppressed);
+        assert!(!set.scan("донати: шахеди над києвом").suppressed);
+        assert!(!set.scan("шахеди над києвом").suppressed);
+
+        let mut f = kyiv_filter();
+        f.process("a", "Шахеди на Київ").expect("not suppressed");
+        assert_eq!(f.last_suppression(), None);
+    }
+
+    #[test]
+    fn added_urgency_phrasings_reopen_the_wave() {
+        use crate::filter::threat_keywords::KeywordSet;
+        let set =
+            KeywordSet::from_toml("[extra]\nurgency = [\"ще груп\", \"друга хвил\"]").unwrap();
+        assert!(set.is_urgent("ще група шахедів на київ"));
+        assert!(set.is_urgent("друга хвиля балістики"));
+        assert!(set.is_urgent("повторно шахеди"), "built-ins kept");
+        assert!(!KeywordSet::builtin().is_urgent("ще група шахедів на київ"));
+    }
+
+    #[test]
+    fn keyword_lists_from_the_environment_are_parsed_leniently() {
+        use crate::filter::threat_keywords::parse_list;
+        assert_eq!(
+            parse_list(" Ще груп, ,друга хвил ,"),
+            Some(vec!["ще груп".to_string(), "друга хвил".to_string()])
+        );
+        assert_eq!(parse_list("=донат"), Some(vec!["=донат".to_string()]));
+        // Empty means unset, so the built-ins stay.
+        assert_eq!(parse_list(""), None);
+        assert_eq!(parse_list(" , "), None);
+    }
+
+    #[test]
+    fn env_urgency_phrasings_reopen_an_open_wave() {
+        use crate::filter::threat_keywords::{self, KeywordSet, parse_list};
+        let run = || {
+            let mut f = kyiv_filter();
+            f.process("a", "Шахеди на Київ").expect("first report");
+            assert!(f.process("b", "Шахеди на Київ").is_none(), "duplicate");
+            f.process("b", "Ще група шахедів на Київ")
+        };
+        assert!(run().is_none(), "not urgent with the built-ins");
+
+        // What `EXTRA_URGENCY_KEYWORDS="ще груп"` installs.
+        let set = KeywordSet::builtin()
+            .with_lists(None, parse_list("ще груп").unwrap(), None)
+            .unwrap();
+        assert!(set.is_urgent("повторно шахеди"), "built-ins kept");
+        let replaced = KeywordSet::builtin()
+            .with_lists(parse_list("ще груп"), Vec::new(), None)
+            .unwrap();
+        assert!(!replaced.is_urgent("повторно шахеди"));
+
+        threat_keywords::install(set);
+        let alert = run();
+        threat_keywords::install(KeywordSet::from_env());
+        assert!(alert.is_some(), "re-alert on the added phrasing");
+    }
+
+    #[test]
+    fn dedup_window_can_differ_per_threat_kind() {
+        let mut f = kyiv_filter();
+        f.kind_dedup_windows
+            .insert(ThreatKind::Ballistic, Duration::from_secs(0));
+        assert!(f.process("a", "Балістика на Київ").is_some());
+        assert!(f.process("b", "Балістика на Київ").is_some(), "window over");
+        assert!(f.process("a", "Шахеди на Київ").is_some());
+        assert!(f.process("b", "Шахеди на Київ").is_none(), "default window");
+    }
+
+    #[test]
+    fn near_verbatim_repost_is_dropped_despite_new_threats() {
+        let mut f = kyiv_filter();
+        let original = "Шахеди з Чернігівщини курсом на Київ, група заходить на Бровари";
+        f.process_with_id(1, "a", original).expect("forwarded");
+        let repost =
+            "‼️ Шахеди з Чернігівщини курсом на Київ, група заходить на Бровари, КР 👉 t.me/b";
+        assert!(f.process_with_id(2, "b", repost).is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::Dedup));
+
+        let mut f = kyiv_filter();
+        f.process_with_id(1, "a", original).expect("forwarded");
+        assert!(
+            f.process_with_id(2, "b", "Крилаті ракети курсом на Київ")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn out_of_area_waves_are_kept_apart_by_target() {
+        let mut f = kyiv_filter();
+        f.forward_all_threats = true;
+        assert!(f.process("a", "Балістика на Харків").is_some());
+        assert!(f.process("b", "Балістика на Одесу").is_some());
+        assert!(f.process("c", "Балістика на Харків").is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::Dedup));
+    }
+
+    #[test]
+    fn out_of_area_wave_escalates_when_it_turns_local() {
+        let mut f = kyiv_filter();
+        f.forward_all_threats = true;
+        assert!(f.process("a", "Шахеди курсом на Житомир").is_some());
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::New);
+
+        let alert = f.process("b", "Шахеди курсом на Київ").expect("escalation");
+        assert!(
+            alert.starts_with("⬆️ Загроза наблизилась: ЖИТОМИР → МІСТО"),
+            "{alert}"
+        );
+        let info = f.last_alert().unwrap();
+        assert_eq!(info.alert_type, AlertType::Escalation);
+        assert_eq!(info.escalated_from, Some(Proximity::None));
+        assert_eq!(f.cache.len(), 1);
+
+        // The course change finds the same wave.
+        assert!(
+            f.process("c", "Шахеди пройшли повз Київ, курс на Житомир")
+                .is_some()
+        );
+        assert_eq!(f.last_alert().unwrap().alert_type, AlertType::Status);
+    }
+
+    #[test]
+    fn dedup_cache_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("filter_state_{}.json", std::process::id()));
+        let mut f = kyiv_filter();
+        f.state_path = Some(path.clone());
+        f.forward_all_threats = true;
+        f.process("a", "Балістика на Київ").expect("forwarded");
+        f.process("a", "Балістика на Харків").expect("forwarded");
+        f.save_state();
+
+        let mut f = kyiv_filter();
+        f.state_path = Some(path.clone());
+        f.forward_all_threats = true;
+        f.restore_state();
+        assert_eq!(f.cache.len(), 2);
+        assert!(f.process("b", "Балістика на Київ").is_none());
+        assert_eq!(f.last_suppression(), Some(Suppression::Dedup));
+        assert!(f.process("b", "Балістика на Харків").is_none());
+        std::fs::remove_file(&path).unwrap();
+
+        // No file yet: a fresh start.
+        f.restore_state();
+        assert!(f.cache.is_empty());
+    }
+
+    #[test]
+    fn channel_context_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("filter_ctx_{}.json", std::process::id()));
+        let mut f = kyiv_filter();
+        f.state_path = Some(path.clone());
+        f.process_with_id(5, "a", "Балістика з Криму");
+        f.save_state();
+
+        let mut f = kyiv_filter();
+        f.state_path = Some(path.clone());
+        f.restore_state();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(f.channel_contexts[&5].messages.len(), 1);
+        f.cache.clear();
+        f.process_with_id(5, "a", "Ціль на Київ").expect("inferred");
+        assert!(
+            f.last_alert()
+                .unwrap()
+                .threats
+                .contains(&ThreatKind::Ballistic),
+            "{:?}",
+            f.last_alert()
+        );
+    }
+
+    #[test]
+    fn saved_state_round_trips_every_proximity_and_sector() {
+        use crate::filter::persist;
+        use crate::filter::region::Sector;
+        let path = std::env::temp_dir().join(format!("filter_names_{}.json", std::process::id()));
+        let proximities = [
+            Proximity::None,
+            Proximity::Oblast,
+            Proximity::City,
+            Proximity::District,
+        ];
+        let sectors = [
+            None,
+            Some(Sector::N),
+            Some(Sector::NE),
+            Some(Sector::E),
+            Some(Sector::SE),
+            Some(Sector::S),
+            Some(Sector::SW),
+            Some(Sector::W),
+            Some(Sector::NW),
+        ];
+        let mut f = kyiv_filter();
+        f.process_with_id(7, "a", "Балістика з Криму");
+        let now = f.clock.now();
+        for (i, sector) in sectors.into_iter().enumerate() {
+            for (j, proximity) in proximities.into_iter().enumerate() {
+                let key = DedupKey {
+                    kind: [ThreatKind::Shahed, ThreatKind::Ballistic][j % 2],
+                    sector,
+                    target: (j > 1).then_some("Харків"),
+                };
+                let entry = DedupEntry {
+                    sent_at: now,
+                    max_proximity: proximity,
+                    seen_signature: 0,
+                    seen_nationwide: false,
+                    seen_local: false,
+                    was_urgent: false,
+                    last_urgent_at: None,
+                    last_channel_id: (i * 4 + j) as i64,
+                    last_geo_hint: None,
+                    receding: false,
+                };
+                f.cache.insert(key, entry);
+            }
+        }
+        let ctx = f.channel_contexts.get_mut(&7).unwrap();
+        for proximity in proximities {
+            ctx.add(String::new(), vec![ThreatKind::Shahed], proximity);
+        }
+        persist::save(&f, &path).unwrap();
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(json.contains(r#""sector":"NW""#), "{json}");
+        assert!(json.contains(r#""max_proximity":"district""#), "{json}");
+        let restored = persist::load(&path, &f.clock).unwrap();
+        assert_eq!(restored.cache.len(), f.cache.len());
+        for (key, entry) in &f.cache {
+            let got = &restored.cache[key];
+            assert_eq!(got.max_proximity, entry.max_proximity, "{key:?}");
+            assert_eq!(got.last_channel_id, entry.last_channel_id, "{key:?}");
+        }
+        let proximities_of = |ctx: &ChannelContext| -> Vec<Proximity> {
+            ctx.messages.iter().map(|m| m.detected_proximity).collect()
+        };
+        assert_eq!(
+            proximities_of(&restored.contexts[&7]),
+            proximities_of(&f.channel_contexts[&7])
+        );
+
+        // Files from before the stable names hold `Debug` output.
+        let at = chrono::Utc::now().timestamp();
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"waves":[{{"kind":"Shahed","sector":"NE","target":null,"sent_at":{at},"max_proximity":"City","seen_signature":0,"seen_nationwide":false,"seen_local":true,"was_urgent":false,"last_urgent_at":null,"last_channel_id":1,"last_geo_hint":null,"receding":false}}]}}"#
+            ),
+        )
+        .unwrap();
+        let restored = persist::load(&path, &f.clock).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let key = DedupKey {
+            kind: ThreatKind::Shahed,
+            sector: Some(Sector::NE),
+            target: None,
+        };
+        assert_eq!(restored.cache[&key].max_proximity, Proximity::City);
+    }
+
+    #[test]
+    fn simulated_clock_ages_the_dedup_window() {
+        use crate::filter::clock::SimulatedClock;
+        use std::sync::Arc;
+        let clock = Arc::new(SimulatedClock::new());
+        let mut f = kyiv_filter().with_clock(clock.clone());
+        f.dedup_window = Duration::from_secs(180);
+
+        assert!(f.process_with_id(1, "a", "Шахеди на Київ").is_some());
+        clock.advance(Duration::from_secs(120));
+        assert!(f.process_with_id(2, "b", "Шахеди на Київ").is_none());
+        // No real time passes: only the simulated clock closes the wave.
+        clock.advance(Duration::from_secs(61));
+        assert!(f.process_with_id(3, "c", "Шахеди на Київ").is_some());
+    }
 }
@@ -0,0 +1,789 @@
+//! Embedded gazetteer: reference settlements with their raion, oblast and
+//! coordinates.  Used to reverse-geocode a subscriber's shared location to
+//! the nearest known settlement and its oblast, and to tell apart
+//! settlements sharing a name ("Миколаїв" the oblast centre and the town in
+//! Lviv oblast) when a message names one.
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::filter::boundary::{self, Boundary};
+use crate::filter::corridor::{same_place, waypoint_key};
+use crate::filter::stem;
+
+/// One reference settlement.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Place {
+    pub name: &'static str,
+    /// `None` for cities outside any raion (Kyiv).
+    pub raion: Option<&'static str>,
+    pub oblast: &'static str,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+// Coordinates are constants, never NaN.
+impl Eq for Place {}
+
+const fn place(
+    name: &'static str,
+    raion: &'static str,
+    oblast: &'static str,
+    lat: f64,
+    lon: f64,
+) -> Place {
+    Place {
+        name,
+        raion: Some(raion),
+        oblast,
+        lat,
+        lon,
+    }
+}
+
+/// Oblast centres plus the Kyiv satellite towns (the oblast centre of
+/// Kyiv oblast is the capital itself, which belongs to no raion), raion
+/// centres and other towns alert channels name, and towns named like
+/// another place.  Of namesakes the better-known one
+/// comes first: it is the guess when nothing tells them apart.
+pub const PLACES: &[Place] = &[
+    Place {
+        name: "Київ",
+        raion: None,
+        oblast: "м. Київ",
+        lat: 50.450,
+        lon: 30.523,
+    },
+    place("Бровари", "Броварський", "Київська", 50.511, 30.790),
+    place("Бориспіль", "Бориспільський", "Київська", 50.352, 30.955),
+    place("Вишгород", "Вишгородський", "Київська", 50.585, 30.489),
+    place("Буча", "Бучанський", "Київська", 50.543, 30.212),
+    place("Обухів", "Обухівський", "Київська", 50.108, 30.620),
+    place("Фастів", "Фастівський", "Київська", 50.076, 29.918),
+    place("Біла Церква", "Білоцерківський", "Київська", 49.798, 30.131),
+    place("Вінниця", "Вінницький", "Вінницька", 49.233, 28.468),
+    place("Луцьк", "Луцький", "Волинська", 50.747, 25.325),
+    place("Дніпро", "Дніпровський", "Дніпропетровська", 48.465, 35.046),
+    place("Донецьк", "Донецький", "Донецька", 48.015, 37.803),
+    place("Житомир", "Житомирський", "Житомирська", 50.254, 28.658),
+    place("Ужгород", "Ужгородський", "Закарпатська", 48.621, 22.288),
+    place("Запоріжжя", "Запорізький", "Запорізька", 47.838, 35.139),
+    place(
+        "Івано-Франківськ",
+        "Івано-Франківський",
+        "Івано-Франківська",
+        48.922,
+        24.711,
+    ),
+    place(
+        "Кропивницький",
+        "Кропивницький",
+        "Кіровоградська",
+        48.508,
+        32.262,
+    ),
+    place("Луганськ", "Луганський", "Луганська", 48.574, 39.308),
+    place("Львів", "Львівський", "Львівська", 49.839, 24.029),
+    place("Миколаїв", "Миколаївський", "Миколаївська", 46.975, 31.994),
+    place("Одеса", "Одеський", "Одеська", 46.482, 30.723),
+    place("Полтава", "Полтавський", "Полтавська", 49.588, 34.551),
+    place("Рівне", "Рівненський", "Рівненська", 50.619, 26.251),
+    place("Суми", "Сумський", "Сумська", 50.907, 34.798),
+    place(
+        "Тернопіль",
+        "Тернопільський",
+        "Тернопільська",
+        49.553,
+        25.594,
+    ),
+    place("Харків", "Харківський", "Харківська", 49.993, 36.230),
+    place("Херсон", "Херсонський", "Херсонська", 46.635, 32.616),
+    place(
+        "Хмельницький",
+        "Хмельницький",
+        "Хмельницька",
+        49.422,
+        26.987,
+    ),
+    place("Черкаси", "Черкаський", "Черкаська", 49.444, 32.059),
+    place("Чернівці", "Чернівецький", "Чернівецька", 48.292, 25.935),
+    place("Чернігів", "Чернігівський", "Чернігівська", 51.498, 31.289),
+    place("Сімферополь", "Сімферопольський", "АР Крим", 44.952, 34.102),
+    // Raion centres and other towns alert channels name
+    place("Ірпінь", "Бучанський", "Київська", 50.521, 30.250),
+    place("Гостомель", "Бучанський", "Київська", 50.569, 30.265),
+    place("Макарів", "Бучанський", "Київська", 50.464, 29.811),
+    place("Боярка", "Фастівський", "Київська", 50.329, 30.288),
+    place("Переяслав", "Бориспільський", "Київська", 50.065, 31.445),
+    place("Яготин", "Бориспільський", "Київська", 50.279, 31.768),
+    place("Славутич", "Вишгородський", "Київська", 51.522, 30.720),
+    place("Іванків", "Вишгородський", "Київська", 50.936, 29.896),
+    place("Жмеринка", "Жмеринський", "Вінницька", 49.037, 28.112),
+    place(
+        "Могилів-Подільський",
+        "Могилів-Подільський",
+        "Вінницька",
+        48.446,
+        27.797,
+    ),
+    place("Тульчин", "Тульчинський", "Вінницька", 48.675, 28.849),
+    place("Гайсин", "Гайсинський", "Вінницька", 48.811, 29.390),
+    place("Хмільник", "Хмільницький", "Вінницька", 49.559, 27.957),
+    place("Калинівка", "Хмільницький", "Вінницька", 49.453, 28.523),
+    place("Ковель", "Ковельський", "Волинська", 51.215, 24.709),
+    place(
+        "Нововолинськ",
+        "Володимирський",
+        "Волинська",
+        50.726,
+        24.163,
+    ),
+    place(
+        "Камінь-Каширський",
+        "Камінь-Каширський",
+        "Волинська",
+        51.624,
+        24.960,
+    ),
+    place(
+        "Кривий Ріг",
+        "Криворізький",
+        "Дніпропетровська",
+        47.910,
+        33.392,
+    ),
+    place(
+        "Кам'янське",
+        "Кам'янський",
+        "Дніпропетровська",
+        48.512,
+        34.613,
+    ),
+    place(
+        "Жовті Води",
+        "Кам'янський",
+        "Дніпропетровська",
+        48.350,
+        33.500,
+    ),
+    place(
+        "Нікополь",
+        "Нікопольський",
+        "Дніпропетровська",
+        47.571,
+        34.397,
+    ),
+    place(
+        "Марганець",
+        "Нікопольський",
+        "Дніпропетровська",
+        47.648,
+        34.625,
+    ),
+    place(
+        "Павлоград",
+        "Павлоградський",
+        "Дніпропетровська",
+        48.520,
+        35.870,
+    ),
+    place(
+        "Синельникове",
+        "Синельниківський",
+        "Дніпропетровська",
+        48.318,
+        35.512,
+    ),
+    place("Краматорськ", "Краматорський", "Донецька", 48.738, 37.584),
+    place("Слов'янськ", "Краматорський", "Донецька", 48.852, 37.605),
+    place("Костянтинівка", "Краматорський", "Донецька", 48.528, 37.706),
+    place("Дружківка", "Краматорський", "Донецька", 48.622, 37.528),
+    place("Покровськ", "Покровський", "Донецька", 48.282, 37.176),
+    place("Мирноград", "Покровський", "Донецька", 48.302, 37.262),
+    place("Добропілля", "Покровський", "Донецька", 48.467, 37.083),
+    place("Бахмут", "Бахмутський", "Донецька", 48.595, 38.000),
+    place("Маріуполь", "Маріупольський", "Донецька", 47.097, 37.543),
+    place("Бердичів", "Бердичівський", "Житомирська", 49.899, 28.602),
+    place("Коростень", "Коростенський", "Житомирська", 50.951, 28.639),
+    place("Звягель", "Звягельський", "Житомирська", 50.590, 27.616),
+    place("Мукачево", "Мукачівський", "Закарпатська", 48.440, 22.718),
+    place("Берегове", "Берегівський", "Закарпатська", 48.205, 22.644),
+    place("Хуст", "Хустський", "Закарпатська", 48.176, 23.298),
+    place("Рахів", "Рахівський", "Закарпатська", 48.055, 24.204),
+    place(
+        "Мелітополь",
+        "Мелітопольський",
+        "Запорізька",
+        46.849,
+        35.365,
+    ),
+    place("Бердянськ", "Бердянський", "Запорізька", 46.756, 36.799),
+    place("Енергодар", "Василівський", "Запорізька", 47.499, 34.656),
+    place("Токмак", "Пологівський", "Запорізька", 47.255, 35.706),
+    place("Оріхів", "Пологівський", "Запорізька", 47.567, 35.785),
+    place(
+        "Коломия",
+        "Коломийський",
+        "Івано-Франківська",
+        48.531,
+        25.037,
+    ),
+    place("Калуш", "Калуський", "Івано-Франківська", 49.019, 24.373),
+    place(
+        "Надвірна",
+        "Надвірнянський",
+        "Івано-Франківська",
+        48.634,
+        24.579,
+    ),
+    place(
+        "Бурштин",
+        "Івано-Франківський",
+        "Івано-Франківська",
+        49.259,
+        24.628,
+    ),
+    place(
+        "Олександрія",
+        "Олександрійський",
+        "Кіровоградська",
+        48.669,
+        33.116,
+    ),
+    place(
+        "Світловодськ",
+        "Олександрійський",
+        "Кіровоградська",
+        49.050,
+        33.241,
+    ),
+    place(
+        "Знам'янка",
+        "Кропивницький",
+        "Кіровоградська",
+        48.714,
+        32.667,
+    ),
+    place(
+        "Голованівськ",
+        "Голованівський",
+        "Кіровоградська",
+        48.381,
+        30.451,
+    ),
+    place(
+        "Сєвєродонецьк",
+        "Сєвєродонецький",
+        "Луганська",
+        48.948,
+        38.487,
+    ),
+    place("Лисичанськ", "Сєвєродонецький", "Луганська", 48.904, 38.442),
+    place(
+        "Старобільськ",
+        "Старобільський",
+        "Луганська",
+        49.277,
+        38.910,
+    ),
+    place("Алчевськ", "Алчевський", "Луганська", 48.467, 38.798),
+    place("Дрогобич", "Дрогобицький", "Львівська", 49.350, 23.506),
+    place("Стрий", "Стрийський", "Львівська", 49.262, 23.853),
+    place("Самбір", "Самбірський", "Львівська", 49.518, 23.197),
+    place("Шептицький", "Шептицький", "Львівська", 50.386, 24.229),
+    place("Яворів", "Яворівський", "Львівська", 49.938, 23.383),
+    place(
+        "Вознесенськ",
+        "Вознесенський",
+        "Миколаївська",
+        47.567,
+        31.333,
+    ),
+    place(
+        "Южноукраїнськ",
+        "Вознесенський",
+        "Миколаївська",
+        47.817,
+        31.183,
+    ),
+    place("Очаків", "Миколаївський", "Миколаївська", 46.617, 31.545),
+    place("Баштанка", "Баштанський", "Миколаївська", 47.407, 32.438),
+    place("Ізмаїл", "Ізмаїльський", "Одеська", 45.351, 28.837),
+    place(
+        "Білгород-Дністровський",
+        "Білгород-Дністровський",
+        "Одеська",
+        46.186,
+        30.345,
+    ),
+    place("Чорноморськ", "Одеський", "Одеська", 46.301, 30.655),
+    place("Подільськ", "Подільський", "Одеська", 47.743, 29.533),
+    place("Болград", "Болградський", "Одеська", 45.677, 28.616),
+    place("Роздільна", "Роздільнянський", "Одеська", 46.848, 30.079),
+    place("Кременчук", "Кременчуцький", "Полтавська", 49.066, 33.420),
+    place(
+        "Горішні Плавні",
+        "Кременчуцький",
+        "Полтавська",
+        49.010,
+        33.643,
+    ),
+    place("Миргород", "Миргородський", "Полтавська", 49.964, 33.610),
+    place("Гадяч", "Миргородський", "Полтавська", 50.369, 33.990),
+    place("Лубни", "Лубенський", "Полтавська", 50.018, 32.988),
+    place("Хорол", "Лубенський", "Полтавська", 49.785, 33.276),
+    place("Карлівка", "Полтавський", "Полтавська", 49.458, 35.135),
+    place("Дубно", "Дубенський", "Рівненська", 50.417, 25.734),
+    place("Сарни", "Сарненський", "Рівненська", 51.338, 26.602),
+    place("Вараш", "Вараський", "Рівненська", 51.350, 25.850),
+    place("Здолбунів", "Рівненський", "Рівненська", 50.517, 26.250),
+    place("Конотоп", "Конотопський", "Сумська", 51.240, 33.202),
+    place("Шостка", "Шосткинський", "Сумська", 51.863, 33.469),
+    place("Глухів", "Шосткинський", "Сумська", 51.678, 33.916),
+    place("Охтирка", "Охтирський", "Сумська", 50.310, 34.899),
+    place("Тростянець", "Охтирський", "Сумська", 50.478, 34.965),
+    place("Ромни", "Роменський", "Сумська", 50.751, 33.475),
+    place("Лебедин", "Сумський", "Сумська", 50.587, 34.484),
+    place("Білопілля", "Сумський", "Сумська", 51.150, 34.313),
+    place("Чортків", "Чортківський", "Тернопільська", 49.017, 25.798),
+    place("Кременець", "Кременецький", "Тернопільська", 50.105, 25.728),
+    place("Чугуїв", "Чугуївський", "Харківська", 49.836, 36.688),
+    place("Вовчанськ", "Чугуївський", "Харківська", 50.291, 36.946),
+    place("Куп'янськ", "Куп'янський", "Харківська", 49.712, 37.616),
+    place("Ізюм", "Ізюмський", "Харківська", 49.212, 37.256),
+    place("Балаклія", "Ізюмський", "Харківська", 49.462, 36.860),
+    place("Лозова", "Лозівський", "Харківська", 48.889, 36.317),
+    place("Богодухів", "Богодухівський", "Харківська", 50.165, 35.527),
+    place("Берестин", "Берестинський", "Харківська", 49.368, 35.447),
+    place("Мерефа", "Харківський", "Харківська", 49.823, 36.053),
+    place("Золочів", "Богодухівський", "Харківська", 50.280, 35.981),
+    place("Нова Каховка", "Каховський", "Херсонська", 46.755, 33.375),
+    place("Каховка", "Каховський", "Херсонська", 46.813, 33.478),
+    place("Берислав", "Бериславський", "Херсонська", 46.838, 33.427),
+    place("Скадовськ", "Скадовський", "Херсонська", 46.114, 32.911),
+    place("Генічеськ", "Генічеський", "Херсонська", 46.175, 34.804),
+    place(
+        "Кам'янець-Подільський",
+        "Кам'янець-Подільський",
+        "Хмельницька",
+        48.678,
+        26.585,
+    ),
+    place("Шепетівка", "Шепетівський", "Хмельницька", 50.182, 27.066),
+    place("Нетішин", "Шепетівський", "Хмельницька", 50.340, 26.640),
+    place(
+        "Старокостянтинів",
+        "Хмельницький",
+        "Хмельницька",
+        49.756,
+        27.203,
+    ),
+    place("Умань", "Уманський", "Черкаська", 48.749, 30.221),
+    place(
+        "Звенигородка",
+        "Звенигородський",
+        "Черкаська",
+        49.079,
+        30.967,
+    ),
+    place("Золотоноша", "Золотоніський", "Черкаська", 49.668, 32.040),
+    place("Сміла", "Черкаський", "Черкаська", 49.222, 31.887),
+    place("Канів", "Черкаський", "Черкаська", 49.750, 31.460),
+    place(
+        "Новодністровськ",
+        "Дністровський",
+        "Чернівецька",
+        48.583,
+        27.441,
+    ),
+    place("Хотин", "Дністровський", "Чернівецька", 48.508, 26.497),
+    place("Сторожинець", "Чернівецький", "Чернівецька", 48.160, 25.720),
+    place("Ніжин", "Ніжинський", "Чернігівська", 51.048, 31.886),
+    place("Бахмач", "Ніжинський", "Чернігівська", 51.181, 32.827),
+    place("Прилуки", "Прилуцький", "Чернігівська", 50.593, 32.387),
+    place(
+        "Новгород-Сіверський",
+        "Новгород-Сіверський",
+        "Чернігівська",
+        51.986,
+        33.262,
+    ),
+    place("Корюківка", "Корюківський", "Чернігівська", 51.773, 32.249),
+    place("Джанкой", "Джанкойський", "АР Крим", 45.709, 34.393),
+    place("Керч", "Керченський", "АР Крим", 45.357, 36.468),
+    place("Феодосія", "Феодосійський", "АР Крим", 45.031, 35.382),
+    place("Євпаторія", "Євпаторійський", "АР Крим", 45.190, 33.367),
+    place("Саки", "Сакський", "АР Крим", 45.134, 33.600),
+    Place {
+        name: "Севастополь",
+        raion: None,
+        oblast: "АР Крим",
+        lat: 44.617,
+        lon: 33.525,
+    },
+    // Namesakes
+    place("Миколаїв", "Стрийський", "Львівська", 49.526, 23.980),
+    place("Золочів", "Золочівський", "Львівська", 49.808, 24.903),
+    place(
+        "Первомайськ",
+        "Первомайський",
+        "Миколаївська",
+        48.044,
+        30.850,
+    ),
+    place("Первомайськ", "Алчевський", "Луганська", 48.630, 38.548),
+];
+
+/// Stems (UA and RU) a message names an oblast by, keyed by
+/// [`Place::oblast`].
+const OBLAST_STEMS: &[(&str, &[&str])] = &[
+    ("Київська", &["київськ", "київщин", "киевск", "киевщин"]),
+    (
+        "Вінницька",
+        &["вінницьк", "вінниччин", "винницк", "винничин"],
+    ),
+    ("Волинська", &["волин", "волын"]),
+    (
+        "Дніпропетровська",
+        &[
+            "дніпропетровськ",
+            "дніпропетровщин",
+            "днепропетровск",
+            "днепропетровщин",
+        ],
+    ),
+    ("Донецька", &["донецьк", "донеччин", "донецк", "донетчин"]),
+    ("Житомирська", &["житомирськ", "житомирщин", "житомирск"]),
+    ("Закарпатська", &["закарпат"]),
+    ("Запорізька", &["запорізьк", "запорожск"]),
+    (
+        "Івано-Франківська",
+        &["івано-франківськ", "прикарпатт", "ивано-франковск"],
+    ),
+    (
+        "Кіровоградська",
+        &[
+            "кіровоградськ",
+            "кіровоградщин",
+            "кировоградск",
+            "кировоградщин",
+        ],
+    ),
+    ("Луганська", &["луганськ", "луганщин", "луганск"]),
+    (
+        "Львівська",
+        &["львівськ", "львівщин", "львовск", "львовщин"],
+    ),
+    (
+        "Миколаївська",
+        &["миколаївськ", "миколаївщин", "николаевск", "николаевщин"],
+    ),
+    ("Одеська", &["одеськ", "одещин", "одесск", "одесчин"]),
+    ("Полтавська", &["полтавськ", "полтавщин", "полтавск"]),
+    ("Рівненська", &["рівненськ", "рівненщин", "ровенск"]),
+    ("Сумська", &["сумськ", "сумщин", "сумск"]),
+    (
+        "Тернопільська",
+        &["тернопільськ", "тернопільщин", "тернопольск"],
+    ),
+    (
+        "Харківська",
+        &["харківськ", "харківщин", "харьковск", "харьковщин"],
+    ),
+    ("Херсонська", &["херсонськ", "херсонщин", "херсонск"]),
+    ("Хмельницька", &["хмельниц", "хмельнич"]),
+    ("Черкаська", &["черкаськ", "черкащин", "черкасск"]),
+    ("Чернівецька", &["чернівецьк", "буковин", "черновицк"]),
+    (
+        "Чернігівська",
+        &["чернігівськ", "чернігівщин", "черниговск", "черниговщин"],
+    ),
+    ("АР Крим", &["крим", "крым"]),
+];
+
+/// A namesake is settled by places named alongside it only when one
+/// candidate is within this distance of them.
+const ANCHOR_MATCH_KM: f64 = 150.0;
+
+/// Points farther than this from every reference settlement are taken to
+/// be outside Ukraine.
+const MAX_MATCH_KM: f64 = 250.0;
+
+/// Beyond this distance the nearest settlement's raion is only a guess and
+/// is not reported.
+const RAION_MATCH_KM: f64 = 25.0;
+
+/// Kyiv proper; farther out the capital's surroundings are Kyiv oblast.
+const KYIV_CITY_KM: f64 = 15.0;
+
+/// Great-circle distance in kilometres.
+pub fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = (lat2 - lat1).to_radians();
+    let dlambda = (lon2 - lon1).to_radians();
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Result of [`reverse_geocode`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMatch {
+    pub nearest: &'static Place,
+    pub distance_km: f64,
+    /// Raion of `nearest`, when the point is close enough to share it.
+    pub raion: Option<&'static str>,
+    pub oblast: &'static str,
+}
+
+impl GeoMatch {
+    /// "Бровари (3 км) · Броварський р-н · Київська обл."
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!(
+            "{} ({:.0} км)",
+            self.nearest.name, self.distance_km
+        )];
+        if let Some(raion) = self.raion {
+            parts.push(format!("{raion} р-н"));
+        }
+        if self.oblast.starts_with("м. ") || self.oblast.starts_with("АР ") {
+            parts.push(self.oblast.to_string());
+        } else {
+            parts.push(format!("{} обл.", self.oblast));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Nearest reference settlement to a point.  The oblast is that of the
+/// nearest settlement, which can be wrong near oblast borders; `None` for
+/// points outside Ukraine.
+pub fn reverse_geocode(lat: f64, lon: f64) -> Option<GeoMatch> {
+    let (nearest, distance_km) = PLACES
+        .iter()
+        .map(|p| (p, distance_km(lat, lon, p.lat, p.lon)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+    if distance_km > MAX_MATCH_KM {
+        return None;
+    }
+    let oblast = if nearest.raion.is_none() && distance_km > KYIV_CITY_KM {
+        "Київська"
+    } else {
+        nearest.oblast
+    };
+    Some(GeoMatch {
+        nearest,
+        distance_km,
+        raion: nearest.raion.filter(|_| distance_km <= RAION_MATCH_KM),
+        oblast,
+    })
+}
+
+/// Single-word gazetteer places whose name `word` declines ("миколаєві"),
+/// namesakes in [`PLACES`] order.
+pub fn places_named(word: &str) -> Vec<&'static Place> {
+    static KEYS: OnceLock<Vec<(String, &'static Place)>> = OnceLock::new();
+    let keys = KEYS.get_or_init(|| {
+        PLACES
+            .iter()
+            .filter(|p| !p.name.contains([' ', '-']))
+            .map(|p| (waypoint_key(&p.name.to_lowercase()), p))
+            .collect()
+    });
+    let key = waypoint_key(word);
+    if key.chars().count() < 3 {
+        return Vec::new();
+    }
+    keys.iter()
+        .filter(|(place_key, _)| same_place(place_key, &key))
+        .map(|(_, p)| *p)
+        .collect()
+}
+
+/// Settlements `lower` names, in order of first mention.  Names of several
+/// words ("Біла Церква", "Івано-Франківськ") match when each word starts
+/// like the name's ("у Білій Церкві"); namesakes are told apart by `ctx`
+/// (see [`resolve`]).
+pub fn mentioned_places(lower: &str, ctx: &PlaceContext) -> Vec<&'static Place> {
+    static COMPOUNDS: OnceLock<Vec<(Vec<String>, &'static Place)>> = OnceLock::new();
+    let compounds = COMPOUNDS.get_or_init(|| {
+        PLACES
+            .iter()
+            .filter(|p| p.name.contains([' ', '-']))
+            .map(|p| {
+                let stems = p
+                    .name
+                    .to_lowercase()
+                    .split([' ', '-'])
+                    .map(|w| {
+                        let len = w.chars().count();
+                        w.chars().take(len.saturating_sub(3).max(3)).collect()
+                    })
+                    .collect();
+                (stems, p)
+            })
+            .collect()
+    });
+    let words: Vec<String> = stem::words(lower)
+        .map(|w| w.replace(['ʼ', '’'], "'"))
+        .collect();
+    let mut covered = vec![false; words.len()];
+    let mut found: Vec<(usize, &'static Place)> = Vec::new();
+    for (stems, place) in compounds {
+        if let Some(at) = words.windows(stems.len()).position(|run| {
+            run.iter()
+                .zip(stems)
+                .all(|(word, stem)| word.starts_with(stem.as_str()))
+        }) {
+            covered[at..at + stems.len()].fill(true);
+            found.push((at, place));
+        }
+    }
+    for (at, word) in words.iter().enumerate() {
+        if !covered[at]
+            && let Some(resolved) = resolve(&places_named(word), ctx)
+        {
+            found.push((at, resolved.place));
+        }
+    }
+    found.sort_by_key(|(at, _)| *at);
+    let mut places: Vec<&'static Place> = Vec::new();
+    for (_, place) in found {
+        if !places.contains(&place) {
+            places.push(place);
+        }
+    }
+    places
+}
+
+/// Oblast `place` lies in: Kyiv, outside any oblast administratively, is
+/// surrounded by Kyiv oblast.
+pub fn surrounding_oblast(place: &Place) -> &'static str {
+    if place.oblast == "м. Київ" {
+        "Київська"
+    } else {
+        place.oblast
+    }
+}
+
+/// Another settlement in [`PLACES`] shares `place`'s name.
+pub fn has_namesakes(place: &Place) -> bool {
+    places_named(&place.name.to_lowercase()).len() > 1
+}
+
+/// The gazetteer's own copy of a place or oblast `name`, for state read
+/// back from disk.
+pub fn static_name(name: &str) -> Option<&'static str> {
+    PLACES
+        .iter()
+        .flat_map(|p| [p.name, p.oblast])
+        .chain(OBLAST_STEMS.iter().map(|(oblast, _)| *oblast))
+        .find(|n| *n == name)
+}
+
+/// Oblasts `lower` names ("на Львівщині", "Миколаївська обл.").
+pub fn mentioned_oblasts(lower: &str) -> Vec<&'static str> {
+    OBLAST_STEMS
+        .iter()
+        .filter(|(_, stems)| {
+            stems
+                .iter()
+                .any(|stem| boundary::contains(lower, stem, Boundary::Stem))
+        })
+        .map(|(oblast, _)| *oblast)
+        .collect()
+}
+
+/// What a namesake is told apart by, strongest first.
+#[derive(Debug, Clone, Default)]
+pub struct PlaceContext {
+    /// Oblasts the message names.
+    pub oblasts: Vec<&'static str>,
+    /// Unambiguous places named in the message or the channel's recent
+    /// trajectory posts.
+    pub anchors: Vec<&'static Place>,
+    /// Oblast the channel usually reports on.
+    pub channel_oblast: Option<&'static str>,
+}
+
+/// A named settlement, see [`resolve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolved {
+    pub place: &'static Place,
+    /// `false` when nothing told the namesakes apart and `place` is only
+    /// the better-known one.
+    pub confident: bool,
+}
+
+/// Pick one of `candidates` (namesakes, see [`places_named`]): the one in
+/// an oblast the message names, else the one close to the other places
+/// named, else the one in the channel's usual oblast, else – with low
+/// confidence – the first.
+pub fn resolve(candidates: &[&'static Place], ctx: &PlaceContext) -> Option<Resolved> {
+    let confident = |place: &'static Place| {
+        Some(Resolved {
+            place,
+            confident: true,
+        })
+    };
+    let first = *candidates.first()?;
+    if candidates.len() == 1 {
+        return confident(first);
+    }
+    let in_oblasts = |oblasts: &[&str]| -> Option<&'static Place> {
+        let mut matching = candidates.iter().filter(|p| oblasts.contains(&p.oblast));
+        match (matching.next(), matching.next()) {
+            (Some(place), None) => Some(*place),
+            _ => None,
+        }
+    };
+    if let Some(place) = in_oblasts(&ctx.oblasts) {
+        return confident(place);
+    }
+    if !ctx.anchors.is_empty() {
+        let mut by_distance: Vec<(&'static Place, f64)> = candidates
+            .iter()
+            .map(|p| {
+                let nearest = ctx
+                    .anchors
+                    .iter()
+                    .map(|a| distance_km(p.lat, p.lon, a.lat, a.lon))
+                    .fold(f64::INFINITY, f64::min);
+                (*p, nearest)
+            })
+            .collect();
+        by_distance.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, best_km) = by_distance[0];
+        if best_km <= ANCHOR_MATCH_KM && by_distance[1].1 >= 2.0 * best_km {
+            return confident(best);
+        }
+    }
+    if let Some(place) = ctx.channel_oblast.and_then(|o| in_oblasts(&[o])) {
+        return confident(place);
+    }
+    Some(Resolved {
+        place: first,
+        confident: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_location_resolves_to_nearest_settlement() {
+        let brovary = reverse_geocode(50.52, 30.80).unwrap();
+        assert_eq!(brovary.nearest.name, "Бровари");
+        assert_eq!(brovary.raion, Some("Броварський"));
+        assert_eq!(brovary.oblast, "Київська");
+
+        let kyiv = reverse_geocode(50.44, 30.50).unwrap();
+        assert_eq!(kyiv.oblast, "м. Київ");
+        assert_eq!(kyiv.raion, None);
+
+        // Warsaw
+        assert!(reverse_geocode(52.23, 21.01).is_none());
+    }
+}
@@ -3,22 +3,127 @@
 //!
 //! Supports **both Ukrainian and Russian** message text – most real-world
 //! alert channels post in a mix of both.
+pub mod boundary;
+pub mod channel_policy;
+pub mod clock;
+pub mod confidence;
+pub mod corridor;
+pub mod corroboration;
+pub mod counts;
+pub mod expiry;
 pub mod filter_tests;
+pub mod gazetteer;
+pub mod origin;
+pub mod persist;
+pub mod region;
+pub mod reliability;
+pub mod render;
+pub mod script;
+pub mod similarity;
+pub mod stem;
 pub mod threat_keywords;
 pub mod threat_kind;
+pub mod threat_level;
+pub mod tracks;
+pub mod trajectory;
+pub mod wave;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use tracing::debug;
 
-use crate::filter::threat_keywords::{NATIONWIDE_KEYWORDS, THREAT_KEYWORDS, is_urgent};
+use crate::filter::boundary::Boundary;
+use crate::filter::channel_policy::{ChannelPolicies, ContextPolicy};
+use crate::filter::clock::{Clock, SystemClock};
+use crate::filter::corridor::CorridorTracker;
+use crate::filter::corroboration::Corroboration;
+use crate::filter::expiry::ActiveThreats;
+use crate::filter::gazetteer::{Place, PlaceContext, Resolved};
+use crate::filter::region::Sector;
+use crate::filter::reliability::Reliability;
+use crate::filter::similarity::RecentTexts;
+use crate::filter::threat_keywords::{is_damage_report, is_suppressed, is_urgent};
 use crate::filter::threat_kind::ThreatKind;
+use crate::filter::tracks::{ShahedTracks, TrackUpdate};
+use crate::filter::trajectory::{Eta, Trajectories};
+use crate::filter::wave::ThreatWave;
+
+/// Oblast mentions a channel needs before its region profile is trusted.
+const CHANNEL_OBLAST_MIN: u32 = 3;
+
+/// Oblast stems for which `NATIONWIDE_REDUCED_KINDS` apply by default: far
+/// enough west that a nationwide MiG-31K alert rarely concerns them.
+const FAR_WESTERN_OBLASTS: &[&str] = &[
+    "львів",
+    "львов",
+    "волин",
+    "волын",
+    "закарпат",
+    "івано-франків",
+    "ивано-франков",
+    "тернопіл",
+    "тернопол",
+    "рівн",
+    "ровн",
+    "чернівец",
+    "черновиц",
+];
+
+/// Kinds from `kinds` (comma-separated variant names) when one of the
+/// user's `MY_OBLAST` variants matches a stem in `oblasts`, else nothing.
+fn reduced_nationwide_kinds(
+    location: &LocationConfig,
+    oblasts: &str,
+    kinds: &str,
+) -> Vec<ThreatKind> {
+    let stems: Vec<String> = oblasts
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let applies = location.oblast.iter().any(|mine| {
+        stems
+            .iter()
+            .any(|stem| mine.starts_with(stem.as_str()) || stem.starts_with(mine.as_str()))
+    });
+    if !applies {
+        return Vec::new();
+    }
+    kinds
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .filter_map(|k| {
+            let kind = ThreatKind::from_variant_name(k);
+            if kind.is_none() {
+                tracing::warn!("Unknown threat kind {k:?} in NATIONWIDE_REDUCED_KINDS");
+            }
+            kind
+        })
+        .collect()
+}
+
+/// `MY_LAT` / `MY_LON` / `MY_RADIUS_KM` (default 50 km).
+fn radius_from_env() -> Option<Radius> {
+    let coord = |key: &str| -> Option<f64> { std::env::var(key).ok()?.trim().parse().ok() };
+    let km: f64 = std::env::var("MY_RADIUS_KM")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50.0);
+    Some(Radius {
+        lat: coord("MY_LAT")?,
+        lon: coord("MY_LON")?,
+        km,
+    })
+}
 
 /// Returns `true` when the message is a nationwide alert that should bypass
 /// location filtering.
 fn is_nationwide(lower: &str) -> bool {
-    NATIONWIDE_KEYWORDS.iter().any(|kw| lower.contains(kw))
+    threat_keywords::active().is_nationwide(lower)
 }
 
 fn has_live_movement_markers(lower: &str) -> bool {
@@ -41,6 +146,90 @@ fn has_live_movement_markers(lower: &str) -> bool {
     markers.iter().any(|m| lower.contains(m))
 }
 
+/// Returns `true` when the message reports that a tracked threat turned
+/// away ("змінила курс на захід", "развернулся", "віддаляється").
+fn is_moving_away(lower: &str) -> bool {
+    let markers = [
+        "змінил курс",
+        "змінила курс",
+        "змінив курс",
+        "змінює курс",
+        "сменил курс",
+        "сменила курс",
+        "меняет курс",
+        "розвернул",
+        "розвернув",
+        "развернул",
+        "віддаля",
+        "удаляе",
+        "відлітає",
+        "улетает",
+        "покида",
+    ];
+    markers.iter().any(|m| lower.contains(m))
+}
+
+/// Stems saying a threat flew past a place ("пройшли повз Київ",
+/// "пролетели мимо Киева").
+const PASS_BY_MARKERS: &[&str] = &[
+    "повз",
+    "мимо",
+    "пройшл",
+    "пролетіл",
+    "пролетів",
+    "минули ",
+    "минув ",
+    "прошл",
+    "пролетел",
+];
+
+/// Stems opening where a threat heads next ("курс на Житомир", "далі у
+/// бік Фастова").
+const HEADING_MARKERS: &[&str] = &[
+    "курс",
+    "напрям",
+    "у бік",
+    "в бік",
+    "в сторону",
+    "далі",
+    "дальше",
+    "далее",
+];
+
+/// Words between a pass-by verb and the place passed ("пройшли повз
+/// Київ", "прошли через Бровари").
+const PASS_BY_CONNECTIVES: &[&str] = &["повз", "мимо", "через", "над", "біля", "возле"];
+
+/// Byte ranges of `lower` naming a place a threat flew past: the (at most
+/// two) words right after a pass-by marker and its connective, up to
+/// punctuation or a heading ("пройшли повз Київ, курс на Житомир" →
+/// "київ").  A marker governing nothing ("попередні пройшли повз") yields
+/// no range.
+fn pass_by_spans(lower: &str) -> Vec<std::ops::Range<usize>> {
+    let offset = |word: &str| word.as_ptr() as usize - lower.as_ptr() as usize;
+    PASS_BY_MARKERS
+        .iter()
+        .flat_map(|m| lower.match_indices(m).map(|(i, _)| i))
+        .filter_map(|start| {
+            let word_end = lower[start..]
+                .find(|c: char| !c.is_alphabetic())
+                .map_or(lower.len(), |i| start + i);
+            let tail = &lower[word_end..];
+            let clause = &tail[..tail
+                .find([',', '.', '!', '?', ';', ':', '\n', '—', '-', '('])
+                .unwrap_or(tail.len())];
+            let words: Vec<&str> = clause
+                .split_whitespace()
+                .skip_while(|w| PASS_BY_CONNECTIVES.contains(w))
+                .take_while(|w| !HEADING_MARKERS.iter().any(|h| w.starts_with(h)))
+                .take(2)
+                .collect();
+            let (first, last) = (words.first()?, words.last()?);
+            Some(offset(first)..offset(last) + last.len())
+        })
+        .collect()
+}
+
 /// Very short locality-only pings in local channels (e.g. "Жуляни!!!!",
 /// "Теремки 🤯") are usually drone sighting updates. Inferring missile type
 /// from previous context here is too noisy.
@@ -86,6 +275,82 @@ fn has_aircraft_markers(lower: &str) -> bool {
     markers.iter().any(|m| lower.contains(m))
 }
 
+/// A MiG-31K or a Kinzhal carrier is named ("МіГ-31К", "носій Кінжалів").
+fn has_mig31_markers(lower: &str) -> bool {
+    let carrier = (lower.contains("носі") || lower.contains("носител"))
+        && (lower.contains("кінжал") || lower.contains("кинжал"));
+    carrier
+        || ["міг-31", "миг-31", "mig-31"]
+            .iter()
+            .any(|m| lower.contains(m))
+}
+
+/// Takeoff of a MiG-31K: "зліт МіГ-31К", "взлет миг-31к", "піднято
+/// МіГ-31К", "МіГ-31К в повітрі".  Its Kinzhals reach all of Ukraine, so this opens a
+/// nationwide [`ThreatKind::BallisticWatch`].
+fn is_mig31_takeoff(lower: &str) -> bool {
+    let takeoff = [
+        "зліт",
+        "злетів",
+        "злетіл",
+        "взлет",
+        "взлёт",
+        "піднял",
+        "підняв",
+        "піднят",
+        "поднял",
+        "поднят",
+        "в повітрі",
+        "в воздухе",
+    ];
+    has_mig31_markers(lower) && takeoff.iter().any(|m| lower.contains(m))
+}
+
+/// Missile carriers put to sea ("носії Калібрів вийшли в Чорне море, залп
+/// до 16 ракет"): a pre-alert for a [`ThreatKind::NavalThreat`], not a
+/// launch.  Reports of actual launches stay cruise missiles.
+fn is_naval_deployment(lower: &str) -> bool {
+    let carrier = ["носі", "носител", "корабл", "фрегат", "підводн", "подводн"];
+    let sea = [
+        "море",
+        "морі",
+        "моря",
+        "чорномор",
+        "черномор",
+        "азовськ",
+        "азовск",
+        "каспі",
+        "каспи",
+    ];
+    let launch = [
+        "пуск",
+        "випущ",
+        "выпущ",
+        "курсом",
+        "летять",
+        "летят",
+        "стартув",
+    ];
+    carrier.iter().any(|m| lower.contains(m))
+        && sea.iter().any(|m| lower.contains(m))
+        && !launch.iter().any(|m| lower.contains(m))
+        && !has_mig31_markers(lower)
+}
+
+/// The MiG-31K landed or its threat was called off ("посадка МіГ-31К",
+/// "відбій загрози МіГ-31К"): closes the ballistic watch.  An air-raid
+/// all-clear ("відбій тривоги") is left to the regular all-clear.
+fn is_mig31_closure(lower: &str) -> bool {
+    if !has_mig31_markers(lower) || lower.contains("тривог") || lower.contains("тревог")
+    {
+        return false;
+    }
+    let landed = lower.contains("посадк")
+        || lower.contains("приземл")
+        || stem::words(lower).any(|w| matches!(w, "сів" | "сіли" | "сел" | "сели"));
+    landed || lower.contains("відбій") || lower.contains("отбой")
+}
+
 fn is_regional_swarm_digest(lower: &str) -> bool {
     let regions = [
         "київщин",
@@ -248,41 +513,18 @@ fn is_negative_update(lower: &str) -> bool {
 
 /// Scan lowercased text and return the set of detected threat kinds.
 /// More specific kinds suppress generic ones.
-fn detect_threats(lower: &str) -> Vec<ThreatKind> {
+pub fn detect_threats(lower: &str) -> Vec<ThreatKind> {
     // Be tolerant to call sites: normalize here even though process() already
     // lowercases once.
     let lower_owned = lower.to_lowercase();
     let lower = lower_owned.as_str();
 
-    fn contains_standalone_token(lower: &str, token: &str) -> bool {
-        let mut start = 0;
-        while let Some(rel) = lower[start..].find(token) {
-            let s = start + rel;
-            let e = s + token.len();
-            let prev_ok = lower[..s]
-                .chars()
-                .next_back()
-                .map(|c| !c.is_alphanumeric())
-                .unwrap_or(true);
-            let next_ok = lower[e..]
-                .chars()
-                .next()
-                .map(|c| !c.is_alphanumeric())
-                .unwrap_or(true);
-            if prev_ok && next_ok {
-                return true;
-            }
-            start = e;
-        }
-        false
-    }
-
     fn detect_combo_threats(lower: &str) -> Vec<ThreatKind> {
         let mut out = Vec::new();
 
         // Cruise missile shorthand used heavily in real channels:
         // "КР курсом на ...", "2х КР на ...".
-        if contains_standalone_token(lower, "кр")
+        if boundary::contains(lower, "кр", Boundary::Word)
             && (lower.contains("курс")
                 || lower.contains("ракет")
                 || lower.contains("груп")
@@ -294,7 +536,7 @@ fn detect_threats(lower: &str) -> Vec<ThreatKind> {
         // Strategic aviation shorthand pattern:
         // "борти СА ... в повітрі", "зліт ... бортів СА".
         let has_bort = lower.contains("борт");
-        let has_strat_marker = lower.contains("са")
+        let has_strat_marker = boundary::contains(lower, "са", Boundary::Word)
             || lower.contains("стратегічн")
             || lower.contains("стратегическ")
             || lower.contains("ту-95")
@@ -307,6 +549,14 @@ fn detect_threats(lower: &str) -> Vec<ThreatKind> {
             out.push(ThreatKind::Aircraft);
         }
 
+        if is_mig31_takeoff(lower) {
+            out.push(ThreatKind::BallisticWatch);
+        }
+
+        if is_naval_deployment(lower) {
+            out.push(ThreatKind::NavalThreat);
+        }
+
         // High-speed target shorthand from alert channels:
         // "швидкісна ціль ...", "скоростная цель ...".
         let has_fast_marker = lower.contains("швидкісн")
@@ -320,13 +570,7 @@ fn detect_threats(lower: &str) -> Vec<ThreatKind> {
         out
     }
 
-    let mut found: Vec<ThreatKind> = Vec::new();
-
-    for &(kind, stems) in THREAT_KEYWORDS {
-        if stems.iter().any(|s| lower.contains(s)) {
-            found.push(kind);
-        }
-    }
+    let mut found = threat_keywords::active().scan(lower).threats;
     for kind in detect_combo_threats(lower) {
         if !found.contains(&kind) {
             found.push(kind);
@@ -345,6 +589,21 @@ fn detect_threats(lower: &str) -> Vec<ThreatKind> {
     if found.contains(&ThreatKind::Hypersonic) {
         found.retain(|k| *k != ThreatKind::CruiseMissile);
     }
+    // A MiG-31K takeoff is its own event, not local aviation; the
+    // "Кінжал" in "носій Кінжалів" is not a launch unless one is reported.
+    // In an all-clear the MiG-31K is the reason, not a new threat.
+    if found.contains(&ThreatKind::BallisticWatch)
+        || (found.contains(&ThreatKind::AllClear) && has_mig31_markers(lower))
+    {
+        found.retain(|k| *k != ThreatKind::Aircraft);
+        if !lower.contains("пуск") {
+            found.retain(|k| *k != ThreatKind::Ballistic);
+        }
+    }
+    // The "Калібри" and "ракет" of a deployment report are its payload.
+    if found.contains(&ThreatKind::NavalThreat) {
+        found.retain(|k| !matches!(k, ThreatKind::CruiseMissile | ThreatKind::Missile));
+    }
     // Suppress generic "Other" if anything more specific matched
     // (including AllClear — "відбій тривоги" shouldn't also produce Other).
     if found.iter().any(|k| !matches!(k, ThreatKind::Other)) {
@@ -376,6 +635,33 @@ impl Proximity {
             Self::None => "",
         }
     }
+
+    /// Uppercase label without the colour marker, for escalation banners.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::District => "РАЙОН",
+            Self::City => "МІСТО",
+            Self::Oblast => "ОБЛАСТЬ",
+            Self::None => "—",
+        }
+    }
+
+    /// Stable lowercase name for machine-readable output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::District => "district",
+            Self::City => "city",
+            Self::Oblast => "oblast",
+            Self::None => "none",
+        }
+    }
+
+    /// Inverse of [`name`](Self::name); case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::None, Self::Oblast, Self::City, Self::District]
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
 }
 
 // ───────────────────────── Location config ────────────────────────────────
@@ -389,11 +675,44 @@ impl Proximity {
 /// MY_CITY=Київ,Киев,Kyiv,Києв
 /// MY_DISTRICT=Шевченківськ,Шевченковск
 /// ```
+///
+/// Alternatively (or in addition) a position and radius:
+/// ```env
+/// MY_LAT=50.45
+/// MY_LON=30.52
+/// MY_RADIUS_KM=60
+/// ```
 #[derive(Debug, Clone)]
 pub struct LocationConfig {
     pub oblast: Vec<String>,
     pub city: Vec<String>,
     pub district: Vec<String>,
+    /// `None` unless both `MY_LAT` and `MY_LON` are set.
+    pub radius: Option<Radius>,
+}
+
+/// The user's position and how far around it named settlements concern
+/// them: within a third of `km` they count as the user's city, within `km`
+/// as the oblast.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Radius {
+    pub lat: f64,
+    pub lon: f64,
+    pub km: f64,
+}
+
+impl Radius {
+    /// Distance band of `place`.
+    fn proximity(&self, place: &Place) -> Proximity {
+        let km = gazetteer::distance_km(self.lat, self.lon, place.lat, place.lon);
+        if km <= self.km / 3.0 {
+            Proximity::City
+        } else if km <= self.km {
+            Proximity::Oblast
+        } else {
+            Proximity::None
+        }
+    }
 }
 
 impl LocationConfig {
@@ -413,35 +732,10 @@ impl LocationConfig {
             oblast: parse("MY_OBLAST"),
             city: parse("MY_CITY"),
             district: parse("MY_DISTRICT"),
+            radius: radius_from_env(),
         }
     }
 
-    fn contains_with_boundary(lower: &str, kw: &str) -> bool {
-        if kw.is_empty() {
-            return false;
-        }
-        let mut start = 0;
-        while let Some(rel) = lower[start..].find(kw) {
-            let s = start + rel;
-            let e = s + kw.len();
-            let prev_ok = lower[..s]
-                .chars()
-                .next_back()
-                .map(|c| !c.is_alphabetic())
-                .unwrap_or(true);
-            let next_ok = lower[e..]
-                .chars()
-                .next()
-                .map(|c| !c.is_alphabetic())
-                .unwrap_or(true);
-            if prev_ok && next_ok {
-                return true;
-            }
-            start = e;
-        }
-        false
-    }
-
     /// Return the highest proximity level that matches `lower` (already
     /// lowercased text).
     fn check(&self, lower: &str) -> Proximity {
@@ -458,22 +752,61 @@ impl LocationConfig {
         Proximity::None
     }
 
-    /// Return booleans for district/city/oblast matches.
+    /// Return booleans for district/city/oblast matches.  Besides the
+    /// keywords, a gazetteer settlement named in `lower` matches the level
+    /// of its distance band when a [`Radius`] is set, else the city level
+    /// when it is in the home settlement's raion and the oblast level when
+    /// it is in the home oblast, so "Бровари" is Kyiv oblast for a Kyiv
+    /// user who only configured Kyiv.
     fn match_levels(&self, lower: &str) -> (bool, bool, bool) {
         fn matches_loc_kw(lower: &str, kw: &str) -> bool {
             if kw.contains(char::is_whitespace) {
                 // Phrases like "на київ" should not match "на київщину".
-                LocationConfig::contains_with_boundary(lower, kw)
+                boundary::contains(lower, kw, Boundary::Word)
             } else {
                 // Stems like "київщин", "харківськ" must match declensions.
                 lower.contains(kw)
             }
         }
         let district = self.district.iter().any(|kw| matches_loc_kw(lower, kw));
-        let city = self.city.iter().any(|kw| matches_loc_kw(lower, kw));
-        let oblast = self.oblast.iter().any(|kw| matches_loc_kw(lower, kw));
+        let mut city = self.city.iter().any(|kw| matches_loc_kw(lower, kw));
+        let mut oblast = self.oblast.iter().any(|kw| matches_loc_kw(lower, kw));
+        if !district && !city {
+            let (home_oblasts, home) = self.home_area();
+            let ctx = PlaceContext {
+                oblasts: gazetteer::mentioned_oblasts(lower),
+                ..PlaceContext::default()
+            };
+            for place in gazetteer::mentioned_places(lower, &ctx) {
+                let proximity = match self.radius {
+                    Some(radius) => radius.proximity(place),
+                    None if home.is_some_and(|h| h.raion.is_some() && h.raion == place.raion) => {
+                        Proximity::City
+                    }
+                    None if home_oblasts.contains(&gazetteer::surrounding_oblast(place)) => {
+                        Proximity::Oblast
+                    }
+                    None => Proximity::None,
+                };
+                city |= proximity == Proximity::City;
+                oblast |= proximity == Proximity::Oblast;
+            }
+        }
         (district, city, oblast)
     }
+
+    /// Oblasts of `MY_OBLAST` and of the home settlement (see
+    /// [`region::home_place`]), and that settlement.
+    fn home_area(&self) -> (Vec<&'static str>, Option<&'static Place>) {
+        let mut oblasts = gazetteer::mentioned_oblasts(&self.oblast.join(" "));
+        let home = region::home_place(self);
+        if let Some(home) = home
+            && !oblasts.contains(&gazetteer::surrounding_oblast(home))
+        {
+            oblasts.push(gazetteer::surrounding_oblast(home));
+        }
+        (oblasts, home)
+    }
 }
 
 // ─────────────────────────── Alert filter ─────────────────────────────────
@@ -519,39 +852,65 @@ struct ContextMessage {
     detected_proximity: Proximity,
 }
 
+/// Messages kept per channel context window unless `CHANNEL_CONTEXT`
+/// overrides it.
+const MAX_CONTEXT_MESSAGES: usize = 20;
+
 /// Per-channel context window that tracks recent messages to infer
 /// threat type from ambiguous words like "ціль" (target).
 struct ChannelContext {
     messages: Vec<ContextMessage>,
     window_duration: Duration,
+    max_messages: usize,
+    /// Last time the channel posted; the least recently used context is
+    /// dropped when `FILTER_MAX_CHANNELS` is reached.
+    last_used: Instant,
+    /// The filter's clock, which the window ages by.
+    clock: Arc<dyn Clock>,
 }
 
 impl ChannelContext {
-    fn new(window_duration: Duration) -> Self {
+    fn new(policy: &ContextPolicy, clock: Arc<dyn Clock>) -> Self {
         Self {
             messages: Vec::new(),
-            window_duration,
+            window_duration: policy.window,
+            max_messages: policy.max_messages,
+            last_used: clock.now(),
+            clock,
         }
     }
 
     /// Add a new message to the context window.
     fn add(&mut self, text_lower: String, threats: Vec<ThreatKind>, proximity: Proximity) {
+        let now = self.clock.now();
+        self.add_at(text_lower, threats, proximity, now);
+    }
+
+    /// [`ChannelContext::add`] for a message seen at `at`.
+    fn add_at(
+        &mut self,
+        text_lower: String,
+        threats: Vec<ThreatKind>,
+        proximity: Proximity,
+        at: Instant,
+    ) {
         self.evict();
         self.messages.push(ContextMessage {
-            timestamp: Instant::now(),
+            timestamp: at,
             text_lower,
             detected_threats: threats,
             detected_proximity: proximity,
         });
-        // Keep max 20 messages to avoid unbounded growth
-        if self.messages.len() > 20 {
-            self.messages.remove(0);
+        // Cap the window to avoid unbounded growth
+        if self.messages.len() > self.max_messages {
+            let excess = self.messages.len() - self.max_messages;
+            self.messages.drain(..excess);
         }
     }
 
     /// Remove messages older than the window duration.
     fn evict(&mut self) {
-        let now = Instant::now();
+        let now = self.clock.now();
         self.messages
             .retain(|msg| now.duration_since(msg.timestamp) < self.window_duration);
     }
@@ -559,29 +918,12 @@ impl ChannelContext {
     /// Check if the message contains trigger words ("ціль", "вихід", etc.)
     /// and infer the threat type from recent channel context.
     fn infer_threat_from_triggers(&mut self, lower: &str) -> Option<ThreatKind> {
-        fn has_celi_with_boundary(lower: &str) -> bool {
-            let mut start = 0;
-            while let Some(rel) = lower[start..].find("цели") {
-                let end = start + rel + "цели".len();
-                let boundary = lower[end..]
-                    .chars()
-                    .next()
-                    .map(|c| !c.is_alphabetic())
-                    .unwrap_or(true);
-                if boundary {
-                    return true;
-                }
-                start = end;
-            }
-            false
-        }
-
         // Check for target keywords
         let has_target = lower.contains("ціль")
             || lower.contains("цілі")
             || lower.contains("цілей")
             || lower.contains("цель")
-            || has_celi_with_boundary(lower)
+            || boundary::contains(lower, "цели", Boundary::Word)
             || lower.contains("целей");
 
         // Check for launch-related keywords (follow-up to an ongoing threat)
@@ -639,7 +981,12 @@ impl ChannelContext {
             if let Some(&threat) = msg
                 .detected_threats
                 .iter()
-                .filter(|t| !matches!(t, ThreatKind::Other | ThreatKind::AllClear))
+                .filter(|t| {
+                    !matches!(
+                        t,
+                        ThreatKind::Other | ThreatKind::AllClear | ThreatKind::Explosion
+                    )
+                })
                 .max_by_key(|t| t.specificity())
             {
                 debug!("Context: inferred recent threat {threat:?}");
@@ -664,7 +1011,20 @@ impl ChannelContext {
     }
 }
 
-/// Entry stored per `ThreatKind` in the dedup cache.
+/// Dedup wave: primary threat kind plus the approach direction, when the
+/// messages name one (see [`region`]), and for threats outside the user's
+/// area the place they target, so "балістика на Харків" does not swallow
+/// a later "балістика на Дніпро".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct DedupKey {
+    kind: ThreatKind,
+    sector: Option<Sector>,
+    /// Target settlement (or oblast) of an out-of-area threat; `None` for
+    /// the user's area and nationwide alerts.
+    target: Option<&'static str>,
+}
+
+/// Entry stored per [`DedupKey`] in the dedup cache.
 struct DedupEntry {
     sent_at: Instant,
     max_proximity: Proximity,
@@ -674,6 +1034,9 @@ struct DedupEntry {
     /// Whether a nationwide variant of this primary threat has already
     /// been forwarded during the dedup window.
     seen_nationwide: bool,
+    /// Whether an alert about the user's area itself (not a nationwide
+    /// one, whose proximity is assumed) has been forwarded.
+    seen_local: bool,
     /// `true` when the cached message itself was an urgency-tagged one
     /// ("повторно", "додатково", …).
     was_urgent: bool,
@@ -685,6 +1048,8 @@ struct DedupEntry {
     /// Last matched geo hint (district/city/oblast keyword stem) used to
     /// allow meaningful same-threat updates when location shifts.
     last_geo_hint: Option<String>,
+    /// A "threat is moving away" update was already sent for this wave.
+    receding: bool,
 }
 
 fn threat_bit(kind: ThreatKind) -> u16 {
@@ -697,6 +1062,11 @@ fn threat_bit(kind: ThreatKind) -> u16 {
         ThreatKind::Shahed => 1 << 5,
         ThreatKind::ReconDrone => 1 << 6,
         ThreatKind::Aircraft => 1 << 7,
+        ThreatKind::BallisticWatch => 1 << 8,
+        ThreatKind::Artillery => 1 << 9,
+        ThreatKind::NavalThreat => 1 << 10,
+        // Impacts during a wave do not make it a new threat combination.
+        ThreatKind::Explosion => 0,
         ThreatKind::AllClear => 0,
         ThreatKind::Other => 0,
     }
@@ -711,6 +1081,134 @@ struct ContextDetection {
     threats: Vec<ThreatKind>,
     proximity: Proximity,
     nationwide: bool,
+    /// No threat keyword: the threats were inferred from context.
+    threat_inferred: bool,
+    /// The proximity was inferred from the channel's earlier posts.
+    location_inferred: bool,
+}
+
+/// What a forwarded message means for the wave, so sinks can notify
+/// differently for a new threat and a follow-up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertType {
+    /// First alert of a dedup wave.
+    New,
+    /// Follow-up within a wave: urgent re-alert, new threat combination,
+    /// first nationwide report, location shift.
+    Update,
+    /// Proximity upgrade within a wave (oblast → city → district).
+    Escalation,
+    /// Negative-status or "moving away" update.
+    Status,
+    AllClear,
+}
+
+impl AlertType {
+    /// Stable name used in sink payloads.
+    pub fn name(self) -> &'static str {
+        match self {
+            AlertType::New => "new",
+            AlertType::Update => "update",
+            AlertType::Escalation => "escalation",
+            AlertType::Status => "status",
+            AlertType::AllClear => "all_clear",
+        }
+    }
+}
+
+/// Classification of the most recently forwarded alert, for callers that
+/// need more than the formatted text (archive, history, …).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertInfo {
+    pub alert_type: AlertType,
+    /// Empty for negative-status updates.
+    pub threats: Vec<ThreatKind>,
+    pub proximity: Proximity,
+    pub nationwide: bool,
+    pub urgent: bool,
+    /// Nationwide alert of a kind that carries little risk for the user's
+    /// oblast (`NATIONWIDE_REDUCED_KINDS`); delivered at reduced severity.
+    pub reduced: bool,
+    /// Proximity of the earlier alert in the same wave when this one is a
+    /// proximity upgrade (e.g. `Oblast` for an oblast → city escalation).
+    pub escalated_from: Option<Proximity>,
+    /// The message names a settlement that shares the user's city's name
+    /// and nothing told which one is meant.
+    pub location_uncertain: bool,
+    /// Settlements the message names, in order of mention.
+    pub places: Vec<&'static Place>,
+    /// How sure the filter is about a threat alert, 0–100; `None` for
+    /// status updates and all-clears.
+    pub confidence: Option<u8>,
+}
+
+impl AlertInfo {
+    /// The most specific threat kind, which keys the dedup wave.
+    pub fn primary(&self) -> Option<ThreatKind> {
+        self.threats.iter().copied().max_by_key(|k| k.specificity())
+    }
+}
+
+/// A message the keyword filter and the LLM were not sure about, worth a
+/// human look (see [`crate::review`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewCandidate {
+    pub keyword_threats: Vec<ThreatKind>,
+    pub llm_threats: Vec<ThreatKind>,
+    pub confidence: Option<f32>,
+}
+
+impl ReviewCandidate {
+    /// Keywords and LLM settled on different threat sets.
+    pub fn disagrees(&self) -> bool {
+        let set = |threats: &[ThreatKind]| {
+            let mut names: Vec<&str> = threats.iter().map(|t| t.variant_name()).collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        };
+        set(&self.keyword_threats) != set(&self.llm_threats)
+    }
+}
+
+/// Why the last `process*` call returned `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suppression {
+    /// Matched a `SUPPRESS_KEYWORDS` / keyword file `suppress` stem.
+    Keyword,
+    /// Recap/statistics post from a channel not in `RECAP_CHANNELS`.
+    Recap,
+    /// Negative-status update without an active wave, or already sent.
+    StatusThrottled,
+    /// No threat keyword, nothing inferred from context.
+    NoThreat,
+    /// Threat outside the configured area.
+    OutOfArea,
+    /// Vetoed by the secondary filter (LLM or classifier).
+    SecondaryFilter,
+    /// Already alerted in this wave, or the drone group moved away.
+    Dedup,
+    /// Confidence score below `MIN_CONFIDENCE`.
+    LowConfidence,
+    /// Vague threat held until `CORROBORATION_CHANNELS` channels agree.
+    Uncorroborated,
+}
+
+impl Suppression {
+    /// Stable name for metrics labels.
+    pub fn name(self) -> &'static str {
+        match self {
+            Suppression::Keyword => "keyword",
+            Suppression::Recap => "recap",
+            Suppression::StatusThrottled => "status_throttled",
+            Suppression::NoThreat => "no_threat",
+            Suppression::OutOfArea => "out_of_area",
+            Suppression::SecondaryFilter => "secondary_filter",
+            Suppression::Dedup => "dedup",
+            Suppression::LowConfidence => "low_confidence",
+            Suppression::Uncorroborated => "uncorroborated",
+        }
+    }
 }
 
 #[derive(Default)]
@@ -719,15 +1217,106 @@ struct NegativeStatusState {
     last_sent_at: Option<Instant>,
 }
 
+/// Gauges of [`AlertFilter`] internal state.  The dedup cache is keyed by
+/// threat kind and so bounded by the number of kinds; channel contexts are
+/// capped by `FILTER_MAX_CHANNELS`, each holding at most
+/// `MAX_CONTEXT_MESSAGES` (or its `CHANNEL_CONTEXT` override).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterStats {
+    pub dedup_entries: usize,
+    pub channel_contexts: usize,
+    /// Messages across all channel contexts.
+    pub context_messages: usize,
+    /// Messages in the fullest channel context.
+    pub max_context_messages: usize,
+    pub negative_status_entries: usize,
+    pub corridor_reports: usize,
+    pub shahed_tracks: usize,
+    /// Channels whose learned reliability is low.
+    pub unreliable_channels: usize,
+}
+
+/// An open dedup wave, for monitoring.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveView {
+    pub kind: ThreatKind,
+    pub sector: Option<Sector>,
+    /// Closest proximity forwarded in the wave.
+    pub proximity: Proximity,
+    pub nationwide: bool,
+    /// A "moving away" update was sent.
+    pub receding: bool,
+    /// Since the wave's last forwarded alert.
+    pub age: Duration,
+}
+
+impl fmt::Display for FilterStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "dedup={} contexts={} context_msgs={} (max {}/ctx) neg_status={} corridor={} tracks={} unreliable={}",
+            self.dedup_entries,
+            self.channel_contexts,
+            self.context_messages,
+            self.max_context_messages,
+            self.negative_status_entries,
+            self.corridor_reports,
+            self.shahed_tracks,
+            self.unreliable_channels,
+        )
+    }
+}
+
+/// `DEDUP_WINDOW_<KIND>_SECS` variables, the kind as in the LLM
+/// interchange in upper snake case (`DEDUP_WINDOW_SHAHED_SECS=3600`).
+fn kind_dedup_windows_from_env() -> HashMap<ThreatKind, Duration> {
+    let mut windows = HashMap::new();
+    for (name, value) in std::env::vars() {
+        let Some(kind) = name
+            .strip_prefix("DEDUP_WINDOW_")
+            .and_then(|rest| rest.strip_suffix("_SECS"))
+        else {
+            continue;
+        };
+        match (ThreatKind::from_variant_name(kind), value.parse()) {
+            (Some(kind), Ok(secs)) => {
+                windows.insert(kind, Duration::from_secs(secs));
+            }
+            _ => tracing::warn!("Ignoring {name}={value:?}: unknown threat kind or not seconds"),
+        }
+    }
+    windows
+}
+
 /// Stateful filter: detects threats, checks location, deduplicates.
 pub struct AlertFilter {
     location: LocationConfig,
     dedup_window: Duration,
-    cache: HashMap<ThreatKind, DedupEntry>,
+    /// Dedup window of [`ThreatKind::NavalThreat`] waves: reports of the
+    /// same deployment keep coming for hours.
+    naval_watch_window: Duration,
+    /// `DEDUP_WINDOW_<KIND>_SECS` overrides: slow drone waves dedup for
+    /// longer than ballistics.
+    kind_dedup_windows: HashMap<ThreatKind, Duration>,
+    /// Texts forwarded within the dedup window, to drop near-verbatim
+    /// reposts by other channels.
+    recent_texts: RecentTexts,
+    /// `FILTER_STATE_PATH`: where the dedup cache is saved across restarts.
+    state_path: Option<PathBuf>,
+    /// Source of "now" for every window, cooldown and track.
+    clock: Arc<dyn Clock>,
+    cache: HashMap<DedupKey, DedupEntry>,
+    /// Home settlement that approach directions are measured from; `None`
+    /// keys dedup by threat kind alone.
+    home: Option<&'static Place>,
     /// Per-channel context windows for better threat inference
     channel_contexts: HashMap<i64, ChannelContext>,
+    /// Hard cap on `channel_contexts`; the least recently used is evicted.
+    max_channel_contexts: usize,
     /// Duration for per-channel context windows.
     context_window: Duration,
+    /// `CHANNEL_CONTEXT`: per-channel window and inference overrides.
+    channel_policies: ChannelPolicies,
     /// Minimum delay between forwarded urgent re-alerts from the same channel
     /// for the same primary threat kind.
     urgent_same_channel_cooldown: Duration,
@@ -739,6 +1328,63 @@ pub struct AlertFilter {
     negative_status_state: HashMap<i64, NegativeStatusState>,
     /// Minimum delay between forwarded negative-status updates per channel.
     negative_status_cooldown: Duration,
+    /// Classification of the alert returned by the last `process*` call.
+    last_alert: Option<AlertInfo>,
+    /// Set when the last `process_with_llm` call was uncertain.
+    last_review: Option<ReviewCandidate>,
+    /// Secondary-filter verdict of the last `process_with_llm` call, when
+    /// it ran.
+    last_verdict: Option<ReviewCandidate>,
+    /// Why the last `process*` call returned `None`.
+    last_suppression: Option<Suppression>,
+    /// Strip links, hashtags and promo lines from the forwarded text.
+    sanitize_text: bool,
+    /// Lowercased channel-title fragments whose recap/statistics posts are
+    /// delivered as "📊 Підсумок" instead of being dropped.
+    recap_channels: Vec<String>,
+    /// Waypoints reported across channels, for the probable-direction line.
+    corridor: CorridorTracker,
+    /// Drone-group tracks linked across sequential messages.
+    shahed_tracks: ShahedTracks,
+    /// Track update from the message currently being processed.
+    current_track: Option<TrackUpdate>,
+    /// Last reported position per threat kind, for arrival estimates.
+    trajectories: Trajectories,
+    /// Arrival estimate for the message currently being processed, when
+    /// it heads for the user.
+    current_eta: Option<Eta>,
+    /// The attack under way, from its first threat report to the
+    /// all-clear.
+    wave: Option<ThreatWave>,
+    /// Silence after which the next threat report opens a new wave.
+    wave_idle: Duration,
+    /// Threat kinds alerted about, until an all-clear or their expiry.
+    active_threats: ActiveThreats,
+    /// Alerts scoring below this are suppressed.
+    min_confidence: u8,
+    /// Append the confidence score to alerts.
+    show_confidence: bool,
+    /// Confidence of the message currently being processed.
+    current_confidence: Option<u8>,
+    /// Review trust per channel id (`0.0..=1.0`), for channels with
+    /// enough reviews.
+    channel_trust: HashMap<i64, f64>,
+    /// Per-channel record of corroborated and rejected reports.
+    reliability: Reliability,
+    /// Vague threats held until enough channels report them.
+    corroboration: Corroboration,
+    /// Channels that corroborated the message currently being processed.
+    current_corroboration: Option<Vec<String>>,
+    /// `MY_CITY`'s settlement when other places share its name.
+    ambiguous_home: Option<&'static Place>,
+    /// Oblast mentions per channel: the region it usually reports on.
+    channel_oblasts: HashMap<i64, HashMap<&'static str, u32>>,
+    /// Namesake of the user's city the message being processed names,
+    /// when it could not be told apart.
+    uncertain_place: Option<&'static Place>,
+    /// Nationwide threat kinds delivered at reduced severity; empty unless
+    /// the user's oblast is in `NATIONWIDE_REDUCED_OBLASTS`.
+    reduced_nationwide_kinds: Vec<ThreatKind>,
 }
 
 impl AlertFilter {
@@ -749,17 +1395,47 @@ impl AlertFilter {
     /// | `MY_OBLAST`            | —       | Comma-separated oblast name variants    |
     /// | `MY_CITY`              | —       | Comma-separated city name variants      |
     /// | `MY_DISTRICT`          | —       | Comma-separated district name variants  |
+    /// | `MY_LAT` / `MY_LON`    | —       | Position for distance-band proximity    |
+    /// | `MY_RADIUS_KM`         | `50`    | Radius around it (a third = city)       |
     /// | `DEDUP_WINDOW_SECS`    | `180`   | Sliding dedup window in seconds         |
+    /// | `DEDUP_WINDOW_<KIND>_SECS` | —   | Window for one kind (`SHAHED`, `BALLISTIC`, `CRUISE_MISSILE`, …) |
+    /// | `NEAR_DUPLICATE_SIMILARITY` | `0.9` | Text similarity of a repost (0 = off) |
+    /// | `FILTER_STATE_PATH`    | —       | File dedup and context state survive restarts in |
+    /// | `NAVAL_WATCH_HOURS`    | `6`     | Dedup window of carrier deployments     |
     /// | `CONTEXT_WINDOW_SECS`  | `300`   | Channel context window in seconds       |
+    /// | `CHANNEL_CONTEXT`      | —       | Per-channel context overrides           |
     /// | `URGENT_COOLDOWN_SECS` | `20`    | Same-channel urgent re-alert cooldown   |
     /// | `NEGATIVE_STATUS_COOLDOWN_SECS` | `120` | Per-channel negative update cooldown |
     /// | `FORWARD_ALL_THREATS`  | `false` | Forward threats outside your area too   |
+    /// | `SANITIZE_ALERT_TEXT`  | `false` | Strip links/hashtags/promo from text    |
+    /// | `RECAP_CHANNELS`       | —       | Channel titles whose recaps are kept    |
+    /// | `DEDUP_BY_DIRECTION`   | `true`  | Separate dedup waves per direction      |
+    /// | `CORRIDOR_WINDOW_SECS` | `300`   | Waypoint clustering window (0 = off)    |
+    /// | `SHAHED_TRACK_WINDOW_SECS` | `1800` | Drone-group track lifetime (0 = off) |
+    /// | `TRAJECTORY_WINDOW_SECS` | `1800` | Position memory for arrival estimates (0 = off) |
+    /// | `WAVE_IDLE_SECS`       | `3600`  | Silence that ends an attack wave        |
+    /// | `THREAT_EXPIRY_MINS`   | `Ballistic=20,Hypersonic=20,60` | Silence after which a threat is probably over |
+    /// | `MIN_CONFIDENCE`       | `0`     | Suppress alerts scoring below (0–100)   |
+    /// | `SHOW_CONFIDENCE`      | `false` | Show the confidence score in alerts     |
+    /// | `CORROBORATION_CHANNELS` | `0`   | Channels that must agree on a vague threat (0 = off) |
+    /// | `CORROBORATION_WINDOW_SECS` | `300` | Window for them to agree in        |
+    /// | `FILTER_MAX_CHANNELS`  | `500`   | Channel context windows kept (LRU)      |
+    /// | `NATIONWIDE_REDUCED_KINDS` | `Aircraft,BallisticWatch` | Nationwide kinds of low risk far west |
+    /// | `NATIONWIDE_REDUCED_OBLASTS` | western oblasts | Oblasts those kinds are reduced for |
     pub fn from_env() -> Self {
         let location = LocationConfig::from_env();
         let dedup_secs: u64 = std::env::var("DEDUP_WINDOW_SECS")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(180);
+        let near_duplicate: f64 = std::env::var("NEAR_DUPLICATE_SIMILARITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.9);
+        let naval_hours: u64 = std::env::var("NAVAL_WATCH_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(6);
         let context_secs: u64 = std::env::var("CONTEXT_WINDOW_SECS")
             .ok()
             .and_then(|v| v.parse().ok())
@@ -776,25 +1452,187 @@ impl AlertFilter {
             .ok()
             .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
             .unwrap_or(false);
+        let sanitize_text: bool = std::env::var("SANITIZE_ALERT_TEXT")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        let recap_channels: Vec<String> = std::env::var("RECAP_CHANNELS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let by_direction: bool = std::env::var("DEDUP_BY_DIRECTION")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(true);
+        let corridor_secs: u64 = std::env::var("CORRIDOR_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let track_secs: u64 = std::env::var("SHAHED_TRACK_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1800);
+        let trajectory_secs: u64 = std::env::var("TRAJECTORY_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1800);
+        let wave_idle_secs: u64 = std::env::var("WAVE_IDLE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        let min_confidence: u8 = std::env::var("MIN_CONFIDENCE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let show_confidence: bool = std::env::var("SHOW_CONFIDENCE")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        let corroboration_channels: usize = std::env::var("CORROBORATION_CHANNELS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let corroboration_secs: u64 = std::env::var("CORROBORATION_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let max_channels: usize = std::env::var("FILTER_MAX_CHANNELS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+        let reduced_nationwide_kinds = reduced_nationwide_kinds(
+            &location,
+            &std::env::var("NATIONWIDE_REDUCED_OBLASTS")
+                .unwrap_or_else(|_| FAR_WESTERN_OBLASTS.join(",")),
+            &std::env::var("NATIONWIDE_REDUCED_KINDS")
+                .unwrap_or_else(|_| "Aircraft,BallisticWatch".into()),
+        );
 
-        Self {
+        let home_place = region::home_place(&location);
+        let home = home_place.filter(|_| by_direction);
+        let ambiguous_home = home_place.filter(|p| gazetteer::has_namesakes(p));
+
+        // Compile the keyword automaton now rather than on the first message.
+        threat_keywords::active();
+
+        let mut filter = Self {
             location,
             dedup_window: Duration::from_secs(dedup_secs),
+            naval_watch_window: Duration::from_secs(naval_hours * 3600),
+            kind_dedup_windows: kind_dedup_windows_from_env(),
+            recent_texts: RecentTexts::new(near_duplicate),
+            state_path: std::env::var("FILTER_STATE_PATH")
+                .ok()
+                .filter(|p| !p.trim().is_empty())
+                .map(PathBuf::from),
+            clock: Arc::new(SystemClock),
             cache: HashMap::new(),
+            home,
             channel_contexts: HashMap::new(),
+            max_channel_contexts: max_channels.max(1),
             context_window: Duration::from_secs(context_secs),
+            channel_policies: ChannelPolicies::from_env(),
             urgent_same_channel_cooldown: Duration::from_secs(urgent_cooldown_secs),
             forward_all_threats: forward_all,
             negative_status_state: HashMap::new(),
             negative_status_cooldown: Duration::from_secs(negative_status_cooldown_secs),
+            last_alert: None,
+            last_review: None,
+            last_verdict: None,
+            last_suppression: None,
+            sanitize_text,
+            recap_channels,
+            corridor: CorridorTracker::new(Duration::from_secs(corridor_secs)),
+            shahed_tracks: ShahedTracks::new(Duration::from_secs(track_secs)),
+            current_track: None,
+            trajectories: Trajectories::new(Duration::from_secs(trajectory_secs)),
+            current_eta: None,
+            wave: None,
+            wave_idle: Duration::from_secs(wave_idle_secs),
+            active_threats: ActiveThreats::from_spec(
+                &std::env::var("THREAT_EXPIRY_MINS").unwrap_or_default(),
+            ),
+            min_confidence,
+            show_confidence,
+            current_confidence: None,
+            channel_trust: HashMap::new(),
+            reliability: Reliability::default(),
+            corroboration: Corroboration::new(
+                corroboration_channels,
+                Duration::from_secs(corroboration_secs),
+            ),
+            current_corroboration: None,
+            ambiguous_home,
+            channel_oblasts: HashMap::new(),
+            uncertain_place: None,
+            reduced_nationwide_kinds,
+        };
+        filter.restore_state();
+        filter
+    }
+
+    /// Run on `clock` instead of the system clock, e.g. a
+    /// [`clock::SimulatedClock`] for replays and tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        for ctx in self.channel_contexts.values_mut() {
+            ctx.clock = clock.clone();
+        }
+        self.clock = clock;
+        self
+    }
+
+    /// Reload the dedup cache and context windows saved at
+    /// `FILTER_STATE_PATH`, keeping what is still within its window.
+    fn restore_state(&mut self) {
+        let Some(path) = self.state_path.clone() else {
+            return;
+        };
+        match persist::load(&path, &self.clock) {
+            Ok(restored) => {
+                self.cache = restored.cache;
+                self.channel_contexts = restored.contexts;
+                self.evict();
+                while self.channel_contexts.len() > self.max_channel_contexts {
+                    self.evict_lru_context();
+                }
+                if !self.cache.is_empty() || !self.channel_contexts.is_empty() {
+                    tracing::info!(
+                        "Restored {} dedup wave(s) and {} channel context(s) from {}",
+                        self.cache.len(),
+                        self.channel_contexts.len(),
+                        path.display()
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("Failed to restore filter state: {e:#}"),
+        }
+    }
+
+    /// Save the dedup cache and context windows to `FILTER_STATE_PATH`,
+    /// if set; call after each forwarded alert and now and then.
+    pub fn save_state(&self) {
+        if let Some(path) = &self.state_path
+            && let Err(e) = persist::save(self, path)
+        {
+            tracing::warn!("Failed to save filter state: {e:#}");
         }
     }
 
     /// Evict expired entries (called lazily on each `process()`).
     fn evict(&mut self) {
-        let now = Instant::now();
-        self.cache
-            .retain(|_, e| now.duration_since(e.sent_at) < self.dedup_window);
+        let now = self.clock.now();
+        let (window, naval) = (self.dedup_window, self.naval_watch_window);
+        let per_kind = &self.kind_dedup_windows;
+        self.cache.retain(|key, e| {
+            let window = match per_kind.get(&key.kind) {
+                Some(window) => *window,
+                None if key.kind == ThreatKind::NavalThreat => naval,
+                None => window,
+            };
+            now.duration_since(e.sent_at) < window
+        });
 
         // Drop stale per-channel windows to prevent unbounded map growth.
         self.channel_contexts.retain(|_, ctx| {
@@ -828,29 +1666,76 @@ impl AlertFilter {
         channel_title: &str,
         text: &str,
     ) -> Option<String> {
+        self.last_alert = None;
+        self.last_suppression = None;
+        self.current_track = None;
+        self.current_eta = None;
+        self.current_confidence = None;
+        self.current_corroboration = None;
+        self.uncertain_place = None;
         let lower = text.to_lowercase();
-        if is_informational_report(&lower) {
-            debug!("Informational recap/statistics post – skipping");
+        if is_suppressed(&lower) {
+            debug!("Suppression keyword matched – skipping");
+            self.last_suppression = Some(Suppression::Keyword);
             return None;
         }
+        if is_informational_report(&lower) {
+            let recap = self.handle_recap(channel_title, text);
+            return self.suppressed_unless(recap, Suppression::Recap);
+        }
         if is_negative_update(&lower) {
-            return self.handle_negative_status_update(channel_id, channel_title, text, &lower);
+            let status =
+                self.handle_negative_status_update(channel_id, channel_title, text, &lower);
+            return self.suppressed_unless(status, Suppression::StatusThrottled);
+        }
+        if is_mig31_closure(&lower) {
+            let closure = self.try_close_watch(channel_title, text);
+            return self.suppressed_unless(closure, Suppression::StatusThrottled);
         }
 
-        let det = self.detect_with_context(channel_id, &lower, channel_title)?;
+        let Some(det) = self.detect_with_context(channel_id, &lower, channel_title) else {
+            self.last_suppression = Some(Suppression::NoThreat);
+            return None;
+        };
         self.on_active_threat_seen(channel_id, &det.threats);
+        self.observe_movement(
+            channel_id,
+            &det.threats,
+            det.proximity,
+            &lower,
+            channel_title,
+        );
 
         // AllClear fast-path.
-        if let Some(alert) = self.try_all_clear(&det.threats, channel_title, text) {
-            return Some(alert);
+        if let Some(alert) =
+            self.try_all_clear(channel_id, &det.threats, &lower, channel_title, text)
+        {
+            return alert;
+        }
+        if let Some(update) =
+            self.try_receding(&det.threats, det.nationwide, &lower, channel_title, text)
+        {
+            return Some(update);
+        }
+        // Already past: not an approach, whether or not it was alerted.
+        if self.is_passing_by(&lower) {
+            debug!("Threat passed the user's area – skipping");
+            self.last_suppression = Some(Suppression::OutOfArea);
+            return None;
         }
 
         if det.proximity == Proximity::None && !det.nationwide && !self.forward_all_threats {
             debug!("Threat detected but no location match – skipping");
+            self.last_suppression = Some(Suppression::OutOfArea);
+            return None;
+        }
+        if !self.passes_confidence(channel_id, &det, &det.threats, &lower, None)
+            || !self.corroborated(channel_id, &det.threats, channel_title)
+        {
             return None;
         }
 
-        self.dedup_and_format(
+        let alert = self.dedup_and_format(
             channel_id,
             &det.threats,
             det.proximity,
@@ -858,47 +1743,144 @@ impl AlertFilter {
             &lower,
             channel_title,
             text,
-        )
+        );
+        self.suppressed_unless(alert, Suppression::Dedup)
+    }
+
+    /// Seed the channel's context window with a message posted `age` ago,
+    /// without alerting: used at startup so inference works before the
+    /// channel posts again.  Feed messages oldest first; an all-clear
+    /// empties the windows as it does live.
+    pub fn warm_context(
+        &mut self,
+        channel_id: i64,
+        channel_title: &str,
+        text: &str,
+        age: Duration,
+    ) {
+        let lower = text.to_lowercase();
+        if is_suppressed(&lower) || is_informational_report(&lower) || is_negative_update(&lower) {
+            return;
+        }
+        let threats = detect_threats(&lower);
+        if threats.contains(&ThreatKind::AllClear) {
+            self.channel_contexts.clear();
+            return;
+        }
+        let (proximity, _) = self.resolve_location(&lower, channel_title);
+        let policy = self.context_policy(channel_title);
+        if (threats.is_empty() && proximity == Proximity::None) || age >= policy.window {
+            return;
+        }
+        let Some(at) = self.clock.now().checked_sub(age) else {
+            return;
+        };
+        let ctx = self.get_context(channel_id, &policy);
+        // Already restored from `FILTER_STATE_PATH`.
+        if ctx.messages.iter().any(|m| m.text_lower == lower) {
+            return;
+        }
+        ctx.add_at(lower, threats, proximity, at);
     }
 
-    /// Async variant that runs the LLM secondary filter after keyword
-    /// detection but before formatting.  Falls back to keyword-only
-    /// when the LLM is disabled or errors out.
+    /// Async variant that runs the secondary filter (LLM or offline
+    /// classifier, see `SECONDARY_FILTER`) after keyword detection but
+    /// before formatting.  Falls back to keyword-only when it is disabled
+    /// or errors out.
     pub async fn process_with_llm(
         &mut self,
         channel_id: i64,
         channel_title: &str,
         text: &str,
-        llm: &crate::llm::LlmFilter,
+        llm: &crate::classifier::SecondaryFilter,
     ) -> Option<String> {
+        self.last_alert = None;
+        self.last_review = None;
+        self.last_verdict = None;
+        self.last_suppression = None;
+        self.current_track = None;
+        self.current_eta = None;
+        self.current_confidence = None;
+        self.current_corroboration = None;
+        self.uncertain_place = None;
         let lower = text.to_lowercase();
-        if is_informational_report(&lower) {
-            debug!("Informational recap/statistics post – skipping");
+        if is_suppressed(&lower) {
+            debug!("Suppression keyword matched – skipping");
+            self.last_suppression = Some(Suppression::Keyword);
             return None;
         }
+        if is_informational_report(&lower) {
+            let recap = self.handle_recap(channel_title, text);
+            return self.suppressed_unless(recap, Suppression::Recap);
+        }
         if is_negative_update(&lower) {
-            return self.handle_negative_status_update(channel_id, channel_title, text, &lower);
+            let status =
+                self.handle_negative_status_update(channel_id, channel_title, text, &lower);
+            return self.suppressed_unless(status, Suppression::StatusThrottled);
         }
-
-        let det = self.detect_with_context(channel_id, &lower, channel_title)?;
-
-        // AllClear fast-path (no LLM needed).
-        if let Some(alert) = self.try_all_clear(&det.threats, channel_title, text) {
-            return Some(alert);
+        if is_mig31_closure(&lower) {
+            let closure = self.try_close_watch(channel_title, text);
+            return self.suppressed_unless(closure, Suppression::StatusThrottled);
         }
 
-        let proximity = det.proximity;
-        let nationwide = det.nationwide;
+        let Some(det) = self.detect_with_context(channel_id, &lower, channel_title) else {
+            self.last_suppression = Some(Suppression::NoThreat);
+            return None;
+        };
+        self.observe_movement(
+            channel_id,
+            &det.threats,
+            det.proximity,
+            &lower,
+            channel_title,
+        );
+
+        // AllClear fast-path (no LLM needed).
+        if let Some(alert) =
+            self.try_all_clear(channel_id, &det.threats, &lower, channel_title, text)
+        {
+            return alert;
+        }
+        if let Some(update) =
+            self.try_receding(&det.threats, det.nationwide, &lower, channel_title, text)
+        {
+            return Some(update);
+        }
+        // Already past: not an approach, whether or not it was alerted.
+        if self.is_passing_by(&lower) {
+            debug!("Threat passed the user's area – skipping");
+            self.last_suppression = Some(Suppression::OutOfArea);
+            return None;
+        }
+
+        let proximity = det.proximity;
+        let nationwide = det.nationwide;
         if proximity == Proximity::None && !nationwide && !self.forward_all_threats {
             debug!("Threat detected but no location match – skipping");
+            self.last_suppression = Some(Suppression::OutOfArea);
             return None;
         }
 
-        // ── LLM verification (async) ──
+        // ── Secondary verification (LLM or classifier) ──
+        let mut support = None;
         let threats = if llm.is_enabled() {
-            let verified = llm.verify(text, &det.threats, proximity, nationwide).await;
+            let verdict = llm.verify(text, &det.threats, proximity, nationwide).await;
+            let candidate = ReviewCandidate {
+                keyword_threats: det.threats.clone(),
+                llm_threats: verdict.threats.clone(),
+                confidence: verdict.confidence,
+            };
+            if candidate.disagrees() || llm.is_uncertain(verdict.confidence) {
+                debug!("Uncertain classification queued for review: {candidate:?}");
+                self.last_review = Some(candidate.clone());
+            }
+            self.last_verdict = Some(candidate);
+            support = verdict.confidence.map(f64::from);
+            let verified = verdict.threats;
+            self.reliability.checked(channel_id, verified.is_empty());
             if verified.is_empty() {
                 debug!("LLM says not an active alert – suppressing");
+                self.last_suppression = Some(Suppression::SecondaryFilter);
                 return None;
             }
             // Update context with more accurate LLM-verified threats.
@@ -909,11 +1891,16 @@ impl AlertFilter {
             }
             verified
         } else {
-            det.threats
+            det.threats.clone()
         };
         self.on_active_threat_seen(channel_id, &threats);
+        if !self.passes_confidence(channel_id, &det, &threats, &lower, support)
+            || !self.corroborated(channel_id, &threats, channel_title)
+        {
+            return None;
+        }
 
-        self.dedup_and_format(
+        let alert = self.dedup_and_format(
             channel_id,
             &threats,
             proximity,
@@ -921,7 +1908,146 @@ impl AlertFilter {
             &lower,
             channel_title,
             text,
-        )
+        );
+        self.suppressed_unless(alert, Suppression::Dedup)
+    }
+
+    /// Hold a vague threat (`CORROBORATION_CHANNELS`) until enough
+    /// channels report it; `false` (suppressed) while held.  A wave already
+    /// open was corroborated and goes on to dedup.
+    fn corroborated(
+        &mut self,
+        channel_id: i64,
+        threats: &[ThreatKind],
+        channel_title: &str,
+    ) -> bool {
+        let Some(primary) = threats.iter().copied().max_by_key(|k| k.specificity()) else {
+            return true;
+        };
+        self.evict();
+        if !self.corroboration.is_enabled()
+            || !corroboration::needs_corroboration(primary)
+            || self.cache.keys().any(|k| k.kind == primary)
+        {
+            return true;
+        }
+        let now = self.clock.now();
+        match self
+            .corroboration
+            .hold(primary, channel_id, channel_title, now)
+        {
+            Some(channels) => {
+                debug!("{primary:?} corroborated by {channels:?} – forwarding");
+                self.current_corroboration = Some(channels);
+                true
+            }
+            None => {
+                debug!("{primary:?} from {channel_title} held for corroboration");
+                self.last_suppression = Some(Suppression::Uncorroborated);
+                false
+            }
+        }
+    }
+
+    /// Score the alert about to be sent, fusing the keyword score with the
+    /// secondary filter's `support` and the channel's trust; `false`
+    /// (suppressed) below `MIN_CONFIDENCE`.
+    fn passes_confidence(
+        &mut self,
+        channel_id: i64,
+        det: &ContextDetection,
+        threats: &[ThreatKind],
+        lower: &str,
+        support: Option<f64>,
+    ) -> bool {
+        let Some(primary) = threats.iter().copied().max_by_key(|k| k.specificity()) else {
+            return true;
+        };
+        let score = confidence::score(&confidence::Signals {
+            primary,
+            threat_inferred: det.threat_inferred,
+            location_inferred: det.location_inferred,
+            location_uncertain: self.uncertain_place.is_some(),
+            proximity: det.proximity,
+            nationwide: det.nationwide,
+            urgent: is_urgent(lower),
+        });
+        // Reviews, when there are enough, know better than corroboration.
+        let trust = self
+            .channel_trust
+            .get(&channel_id)
+            .copied()
+            .or_else(|| self.reliability.score(channel_id));
+        let fused = confidence::fuse(score, support, trust);
+        self.current_confidence = Some(fused);
+        let min = if support.is_some() || trust.is_some() {
+            self.min_confidence.max(confidence::FUSED_MIN)
+        } else {
+            self.min_confidence
+        };
+        if fused < min {
+            debug!(
+                "Confidence {fused} (keywords {score}, secondary {support:?}, trust {trust:?}) below {} – suppressing",
+                min
+            );
+            self.last_suppression = Some(Suppression::LowConfidence);
+            return false;
+        }
+        true
+    }
+
+    /// Learned reliability of `channel_id` (`0.0..=1.0`), once it has
+    /// reported enough.
+    pub fn channel_reliability(&self, channel_id: i64) -> Option<f64> {
+        self.reliability.score(channel_id)
+    }
+
+    /// Replace the per-channel review trust fused into confidence scores.
+    pub fn set_channel_trust(&mut self, trust: HashMap<i64, f64>) {
+        self.channel_trust = trust;
+    }
+
+    fn suppressed_unless(&mut self, alert: Option<String>, reason: Suppression) -> Option<String> {
+        if alert.is_none() {
+            self.last_suppression = Some(reason);
+        }
+        alert
+    }
+
+    /// Classification of the alert returned by the most recent `process*`
+    /// call, or `None` when that call suppressed the message.
+    pub fn last_alert(&self) -> Option<&AlertInfo> {
+        self.last_alert.as_ref()
+    }
+
+    /// Keyword/LLM disagreement or mid-range LLM confidence from the most
+    /// recent `process_with_llm` call, whether or not it forwarded.
+    pub fn last_review(&self) -> Option<&ReviewCandidate> {
+        self.last_review.as_ref()
+    }
+
+    /// What the secondary filter said in the most recent
+    /// `process_with_llm` call; `None` when it did not run.
+    pub fn last_verdict(&self) -> Option<&ReviewCandidate> {
+        self.last_verdict.as_ref()
+    }
+
+    /// Why the most recent `process*` call returned `None`; `None` when it
+    /// forwarded.
+    pub fn last_suppression(&self) -> Option<Suppression> {
+        self.last_suppression
+    }
+
+    /// Returns `Some(proximity)` when `text` reports strike consequences
+    /// (impact, damage, casualties) rather than an incoming threat.  Used to
+    /// correlate damage reports with the preceding alert wave.
+    pub fn damage_report_proximity(&self, channel_title: &str, text: &str) -> Option<Proximity> {
+        let lower = text.to_lowercase();
+        if !is_damage_report(&lower) || has_live_movement_markers(&lower) {
+            return None;
+        }
+        let (proximity, _) = self.resolve_location(&lower, channel_title);
+        Some(proximity)
     }
 
     // ── Private helpers ─────────────────────────────────────────────────
@@ -939,16 +2065,45 @@ impl AlertFilter {
         // Phase 1 — raw keyword detection (borrows &self only)
         let mut threats = detect_threats(lower);
         let (mut proximity, nationwide) = self.resolve_location(lower, channel_title);
+        self.record_channel_oblasts(channel_id, lower);
+        if !nationwide
+            && matches!(proximity, Proximity::City | Proximity::Oblast)
+            && let Some(named) = self.resolve_home_namesake(channel_id, lower, channel_title)
+        {
+            if !named.confident {
+                self.uncertain_place = Some(named.place);
+            } else if self.ambiguous_home != Some(named.place) {
+                // "Миколаїв" on Lviv oblast is not the user's Mykolaiv.
+                debug!(
+                    "{} is in {} – not your city",
+                    named.place.name, named.place.oblast
+                );
+                let (_, _, oblast_m) = self.location.match_levels(&origin::targets_only(lower));
+                proximity = if oblast_m {
+                    Proximity::Oblast
+                } else {
+                    Proximity::None
+                };
+            }
+        }
+        // A MiG-31K in the air or Kalibr carriers at sea put all of
+        // Ukraine in range.
+        let nationwide = nationwide
+            || threats.contains(&ThreatKind::BallisticWatch)
+            || threats.contains(&ThreatKind::NavalThreat);
         let explicit_nonlocal = self.has_explicit_nonlocal_location(lower);
         let urgent = is_urgent(lower);
+        let threat_inferred = threats.is_empty();
+        let mut location_inferred = false;
 
         // Phase 2 — context inference (borrows &mut self via get_context)
+        let policy = self.context_policy(channel_title);
         {
-            let context = self.get_context(channel_id);
+            let context = self.get_context(channel_id, &policy);
 
             // 2a. Trigger-word inference ("ціль", "вихід", etc.)
             //     Only when no explicit threat keyword was already detected.
-            if threats.is_empty() {
+            if threats.is_empty() && policy.trigger {
                 if let Some(inferred) = context.infer_threat_from_triggers(lower) {
                     debug!("Adding {inferred:?} from trigger inference");
                     threats.push(inferred);
@@ -963,7 +2118,9 @@ impl AlertFilter {
                 } else if is_regional_swarm_digest(lower) && !has_aircraft_markers(lower) {
                     debug!("Regional movement digest without explicit type -> default Shahed");
                     threats.push(ThreatKind::Shahed);
-                } else if let Some(inferred) = context.infer_recent_threat() {
+                } else if policy.trigger
+                    && let Some(inferred) = context.infer_recent_threat()
+                {
                     if inferred == ThreatKind::Aircraft && !has_aircraft_markers(lower) {
                         debug!("Skipping Aircraft context inference without aircraft markers");
                     } else {
@@ -974,9 +2131,12 @@ impl AlertFilter {
             }
 
             // 2c. Have threat or urgency, but no location → infer location once from context.
+            //     A threat turning away is not headed for the context area.
             if proximity == Proximity::None
+                && policy.location
                 && !nationwide
                 && !explicit_nonlocal
+                && !is_moving_away(lower)
                 && (!threats.is_empty() || urgent)
             {
                 let ctx_prox = context.infer_location();
@@ -984,6 +2144,7 @@ impl AlertFilter {
                 if ctx_prox != Proximity::None {
                     debug!("Inferred location {ctx_prox:?} from context");
                     proximity = ctx_prox;
+                    location_inferred = true;
                 }
             }
 
@@ -996,7 +2157,7 @@ impl AlertFilter {
         // Phase 3 — refine generic Missile using recent cross-channel context.
         // During dense bursts, "4 ракети на Київ" should align with the
         // already-established specific threat type (e.g. Ballistic).
-        if threats.len() == 1 && threats[0] == ThreatKind::Missile {
+        if policy.global_refine && threats.len() == 1 && threats[0] == ThreatKind::Missile {
             if let Some(inferred) = self.infer_recent_global_specific_threat() {
                 debug!("Refined generic Missile -> {inferred:?} from global context");
                 threats[0] = inferred;
@@ -1005,7 +2166,8 @@ impl AlertFilter {
         // Phase 4 — refine generic Other during clearly live movement.
         // Example: "Залітає ... Українка ..." inside an active missile wave
         // should inherit the recent missile-family context.
-        if threats.len() == 1
+        if policy.global_refine
+            && threats.len() == 1
             && threats[0] == ThreatKind::Other
             && has_live_movement_markers(lower)
             && (proximity != Proximity::None || nationwide || urgent)
@@ -1025,15 +2187,98 @@ impl AlertFilter {
             threats,
             proximity,
             nationwide,
+            threat_inferred,
+            location_inferred,
         })
     }
 
+    /// Context settings of a channel: the global window with every
+    /// inference phase on, unless a `CHANNEL_CONTEXT` rule matches.
+    fn context_policy(&self, channel_title: &str) -> ContextPolicy {
+        let default = ContextPolicy {
+            window: self.context_window,
+            max_messages: MAX_CONTEXT_MESSAGES,
+            trigger: true,
+            location: true,
+            global_refine: true,
+        };
+        self.channel_policies.resolve(channel_title, default)
+    }
+
     /// Get (or create) the per-channel context window.
-    fn get_context(&mut self, channel_id: i64) -> &mut ChannelContext {
-        let window = self.context_window;
-        self.channel_contexts
+    fn get_context(&mut self, channel_id: i64, policy: &ContextPolicy) -> &mut ChannelContext {
+        if !self.channel_contexts.contains_key(&channel_id)
+            && self.channel_contexts.len() >= self.max_channel_contexts
+        {
+            self.evict_lru_context();
+        }
+        let ctx = self
+            .channel_contexts
             .entry(channel_id)
-            .or_insert_with(|| ChannelContext::new(window))
+            .or_insert_with(|| ChannelContext::new(policy, self.clock.clone()));
+        // A renamed channel may match a different rule.
+        ctx.window_duration = policy.window;
+        ctx.max_messages = policy.max_messages;
+        ctx.last_used = self.clock.now();
+        ctx
+    }
+
+    /// Drop the context (and negative-status state) of the channel that
+    /// posted least recently.
+    fn evict_lru_context(&mut self) {
+        let Some(oldest) = self
+            .channel_contexts
+            .iter()
+            .min_by_key(|(_, ctx)| ctx.last_used)
+            .map(|(id, _)| *id)
+        else {
+            return;
+        };
+        debug!("Channel context cap reached – evicting channel {oldest}");
+        self.channel_contexts.remove(&oldest);
+        self.negative_status_state.remove(&oldest);
+    }
+
+    /// Sizes of the filter's internal state, for monitoring.
+    pub fn stats(&self) -> FilterStats {
+        FilterStats {
+            dedup_entries: self.cache.len(),
+            channel_contexts: self.channel_contexts.len(),
+            context_messages: self
+                .channel_contexts
+                .values()
+                .map(|ctx| ctx.messages.len())
+                .sum(),
+            max_context_messages: self
+                .channel_contexts
+                .values()
+                .map(|ctx| ctx.messages.len())
+                .max()
+                .unwrap_or(0),
+            negative_status_entries: self.negative_status_state.len(),
+            corridor_reports: self.corridor.report_count(),
+            shahed_tracks: self.shahed_tracks.track_count(),
+            unreliable_channels: self.reliability.unreliable_count(),
+        }
+    }
+
+    /// Open dedup waves, most recently forwarded first.
+    pub fn waves(&self) -> Vec<WaveView> {
+        let now = self.clock.now();
+        let mut waves: Vec<WaveView> = self
+            .cache
+            .iter()
+            .map(|(key, e)| WaveView {
+                kind: key.kind,
+                sector: key.sector,
+                proximity: e.max_proximity,
+                nationwide: e.seen_nationwide,
+                receding: e.receding,
+                age: now.duration_since(e.sent_at),
+            })
+            .collect();
+        waves.sort_by_key(|w| w.age);
+        waves
     }
 
     /// Infer the most recent specific threat across all channel windows.
@@ -1078,6 +2323,138 @@ impl AlertFilter {
         }
     }
 
+    /// Feed the corridor tracker, drone-group tracks and trajectories.
+    /// Runs before location filtering and dedup: far-away and duplicate
+    /// reports still help locate the threat.
+    fn observe_movement(
+        &mut self,
+        channel_id: i64,
+        threats: &[ThreatKind],
+        proximity: Proximity,
+        lower: &str,
+        channel_title: &str,
+    ) {
+        let Some(primary) = threats
+            .iter()
+            .copied()
+            .filter(|t| {
+                !matches!(
+                    t,
+                    ThreatKind::AllClear | ThreatKind::Other | ThreatKind::Explosion
+                )
+            })
+            .max_by_key(|k| k.specificity())
+        else {
+            return;
+        };
+        let now = self.clock.now();
+        self.observe_wave(threats, proximity, lower, now);
+        self.reliability.report(channel_id, primary, now);
+        if proximity != Proximity::None {
+            for kind in threats {
+                self.active_threats.reported(*kind, now);
+            }
+        }
+        let waypoints = corridor::extract_waypoints(lower);
+        if primary == ThreatKind::Shahed
+            && let Some(size) = tracks::group_size(lower)
+        {
+            self.current_track = self.shahed_tracks.observe(size, &waypoints, proximity, now);
+        }
+        let places = self.mentioned_places(channel_id, lower, channel_title);
+        let heading = waypoints.last().and_then(|w| {
+            let key = corridor::waypoint_key(w);
+            places.iter().copied().find(|p| {
+                corridor::same_place(&corridor::waypoint_key(&p.name.to_lowercase()), &key)
+            })
+        });
+        self.corridor.record(channel_id, primary, waypoints, now);
+        self.trajectories.observe(primary, &places, heading, now);
+        // Headed for the user: a named heading near home, else a message
+        // about the user's city.
+        self.current_eta = self.home_point().and_then(|home| {
+            let near = match heading {
+                Some(h) => {
+                    gazetteer::distance_km(h.lat, h.lon, home.0, home.1) <= trajectory::NEAR_KM
+                }
+                None => proximity >= Proximity::City,
+            };
+            near.then(|| self.trajectories.eta(primary, home, now))
+                .flatten()
+        });
+    }
+
+    /// Count a threat report towards the attack wave, opening a new one
+    /// after an all-clear or [`Self::wave_idle`] of silence.
+    fn observe_wave(
+        &mut self,
+        threats: &[ThreatKind],
+        proximity: Proximity,
+        lower: &str,
+        now: Instant,
+    ) {
+        if self
+            .wave
+            .as_ref()
+            .is_some_and(|w| now.duration_since(w.last_seen) >= self.wave_idle)
+        {
+            self.wave = None;
+        }
+        let counts = counts::threat_counts(lower, threats);
+        let regions = gazetteer::mentioned_oblasts(&origin::targets_only(lower));
+        self.wave
+            .get_or_insert_with(|| ThreatWave::new(now))
+            .observe(threats, &counts, &regions, proximity, now);
+    }
+
+    /// The attack under way, if any threat was reported since the last
+    /// all-clear (and within `WAVE_IDLE_SECS`).
+    pub fn wave(&self) -> Option<&ThreatWave> {
+        self.wave
+            .as_ref()
+            .filter(|w| self.clock.now().duration_since(w.last_seen) < self.wave_idle)
+    }
+
+    /// Threats alerted about that went without a report for their expiry
+    /// (`THREAT_EXPIRY_MINS`) and got no all-clear: a one-time "probably
+    /// over" status update, and their dedup waves are closed so a new
+    /// report alerts afresh.  Live mode calls this periodically.
+    pub fn expire_threats(&mut self) -> Option<String> {
+        self.last_alert = None;
+        let expired = self.active_threats.expire(self.clock.now());
+        if expired.is_empty() {
+            return None;
+        }
+        self.cache
+            .retain(|key, _| !expired.iter().any(|(kind, _)| *kind == key.kind));
+        let proximity = expired
+            .iter()
+            .map(|(_, p)| *p)
+            .max()
+            .unwrap_or(Proximity::None);
+        self.last_alert = Some(AlertInfo {
+            alert_type: AlertType::Status,
+            threats: Vec::new(),
+            proximity,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        });
+        Some(self.format_expired(&expired))
+    }
+
+    /// The user's position: `MY_LAT` / `MY_LON`, else the home settlement.
+    fn home_point(&self) -> Option<(f64, f64)> {
+        match self.location.radius {
+            Some(radius) => Some((radius.lat, radius.lon)),
+            None => region::home_place(&self.location).map(|p| (p.lat, p.lon)),
+        }
+    }
+
     fn handle_negative_status_update(
         &mut self,
         channel_id: i64,
@@ -1086,7 +2463,7 @@ impl AlertFilter {
         lower: &str,
     ) -> Option<String> {
         self.evict();
-        let now = Instant::now();
+        let now = self.clock.now();
 
         let has_recent_threat = if let Some(ctx) = self.channel_contexts.get_mut(&channel_id) {
             ctx.infer_recent_threat().is_some()
@@ -1127,36 +2504,239 @@ impl AlertFilter {
             }
         }
 
+        let places = self.mentioned_places(channel_id, lower, channel_title);
         let state = self.negative_status_state.entry(channel_id).or_default();
         state.latched_for_wave = true;
         state.last_sent_at = Some(now);
+        self.last_alert = Some(AlertInfo {
+            alert_type: AlertType::Status,
+            threats: Vec::new(),
+            proximity,
+            nationwide,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places,
+            confidence: None,
+        });
         Some(self.format_negative_status(proximity, nationwide, channel_title, text))
     }
 
     /// If the threats are a sole AllClear, format and clear cache.
-    /// Returns `Some(alert)` to short-circuit, or `None` to continue.
+    /// Returns `Some(result)` to short-circuit, or `None` to continue.
+    ///
+    /// An all-clear that names only places outside the user's area
+    /// ("Відбій у Харківській області") ends nothing here: state is kept,
+    /// and it is suppressed as out of area unless `FORWARD_ALL_THREATS`
+    /// forwards it as a status update.
     fn try_all_clear(
         &mut self,
+        channel_id: i64,
         threats: &[ThreatKind],
+        lower: &str,
         channel_title: &str,
         text: &str,
-    ) -> Option<String> {
+    ) -> Option<Option<String>> {
         if threats.len() == 1 && threats.contains(&ThreatKind::AllClear) {
-            let alert = self.format(threats, Proximity::None, channel_title, text, false, false);
+            if self.is_nonlocal_all_clear(channel_id, lower, channel_title) {
+                debug!("All-clear for another region – keeping state");
+                if !self.forward_all_threats {
+                    self.last_suppression = Some(Suppression::OutOfArea);
+                    return Some(None);
+                }
+                self.last_alert = Some(AlertInfo {
+                    alert_type: AlertType::Status,
+                    threats: Vec::new(),
+                    proximity: Proximity::None,
+                    nationwide: false,
+                    urgent: false,
+                    reduced: false,
+                    escalated_from: None,
+                    location_uncertain: false,
+                    places: self.mentioned_places(channel_id, lower, channel_title),
+                    confidence: None,
+                });
+                return Some(Some(self.format(
+                    threats,
+                    Proximity::None,
+                    channel_title,
+                    text,
+                    false,
+                    false,
+                )));
+            }
+            let mut alert =
+                self.format(threats, Proximity::None, channel_title, text, false, false);
+            if let Some(wave) = self.wave.take() {
+                alert.push('\n');
+                alert.push_str(&wave.summary(self.clock.now()));
+            }
             self.cache.clear();
             // Clear channel contexts to prevent stale inference into the next wave.
             self.channel_contexts.clear();
             self.negative_status_state.clear();
-            return Some(alert);
+            self.corridor.clear();
+            self.shahed_tracks.clear();
+            self.trajectories.clear();
+            self.active_threats.clear();
+            self.corroboration.clear();
+            self.recent_texts.clear();
+            self.last_alert = Some(AlertInfo {
+                alert_type: AlertType::AllClear,
+                threats: threats.to_vec(),
+                proximity: Proximity::None,
+                nationwide: false,
+                urgent: false,
+                reduced: false,
+                escalated_from: None,
+                location_uncertain: false,
+                places: Vec::new(),
+                confidence: None,
+            });
+            return Some(Some(alert));
         }
         None
     }
 
+    /// The all-clear names places, and none of them is in the user's area
+    /// or the whole country.
+    fn is_nonlocal_all_clear(&self, channel_id: i64, lower: &str, channel_title: &str) -> bool {
+        let (proximity, nationwide) = self.resolve_location(lower, channel_title);
+        proximity == Proximity::None
+            && !nationwide
+            && (self.has_explicit_nonlocal_location(lower)
+                || !self
+                    .mentioned_places(channel_id, lower, channel_title)
+                    .is_empty())
+    }
+
+    /// A MiG-31K landing or "відбій" closes the open ballistic watch: its
+    /// waves are dropped, so the next takeoff alerts afresh, and the
+    /// closure is forwarded once.  `None` when no watch is open.
+    fn try_close_watch(&mut self, channel_title: &str, text: &str) -> Option<String> {
+        self.evict();
+        if !self
+            .cache
+            .keys()
+            .any(|k| k.kind == ThreatKind::BallisticWatch)
+        {
+            debug!("MiG-31K closure without an open ballistic watch – skipping");
+            return None;
+        }
+        self.cache
+            .retain(|key, _| key.kind != ThreatKind::BallisticWatch);
+        self.last_alert = Some(AlertInfo {
+            alert_type: AlertType::Status,
+            threats: Vec::new(),
+            proximity: Proximity::None,
+            nationwide: true,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        });
+        let mut out = format!("{}\n———\n", render::WATCH_CLOSED_HEADER);
+        out.push_str(&self.render_text(text));
+        out.push_str(&format!("\n— 📡 {channel_title}"));
+        Some(out)
+    }
+
+    /// Recap/statistics posts bypass alert detection and dedup entirely:
+    /// they are forwarded as "📊 Підсумок" when the channel is listed in
+    /// `RECAP_CHANNELS`, and dropped otherwise.
+    fn handle_recap(&self, channel_title: &str, text: &str) -> Option<String> {
+        let title = channel_title.to_lowercase();
+        if !self.recap_channels.iter().any(|c| title.contains(c)) {
+            debug!("Informational recap/statistics post – skipping");
+            return None;
+        }
+        debug!("Recap post from {channel_title} – forwarding as summary");
+        let mut out = format!("{}\n———\n", render::RECAP_HEADER);
+        out.push_str(&self.render_text(text));
+        out.push_str(&format!("\n— 📡 {channel_title}"));
+        Some(out)
+    }
+
+    /// If a threat we already alerted on is reported turning away from the
+    /// user's area, or flying past it towards somewhere else ("пройшли повз
+    /// Київ, курс на Житомир"), send a one-time low-priority update and
+    /// relax dedup so a renewed approach is forwarded again.  Returns
+    /// `None` to continue normal processing.
+    fn try_receding(
+        &mut self,
+        threats: &[ThreatKind],
+        nationwide: bool,
+        lower: &str,
+        channel_title: &str,
+        text: &str,
+    ) -> Option<String> {
+        let passed = self.is_passing_by(lower);
+        let turned = is_moving_away(lower) && self.location.check(lower) == Proximity::None;
+        if nationwide || !(passed || turned) {
+            return None;
+        }
+        self.evict();
+        let primary = threats.iter().copied().max_by_key(|k| k.specificity())?;
+        let key = self.find_wave(primary, None, None, true)?;
+        let entry = self.cache.get_mut(&key)?;
+        if entry.receding || entry.max_proximity == Proximity::None {
+            return None;
+        }
+        debug!(
+            "Dedup: {primary:?} moving away from {:?} – relaxing",
+            entry.max_proximity
+        );
+        entry.receding = true;
+        entry.max_proximity = Proximity::None;
+        entry.last_geo_hint = None;
+
+        self.last_alert = Some(AlertInfo {
+            alert_type: AlertType::Status,
+            threats: Vec::new(),
+            proximity: Proximity::None,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        });
+        if let Some(wave) = &mut self.wave {
+            wave.forwarded += 1;
+        }
+        Some(self.format_receding(primary, passed, channel_title, text))
+    }
+
+    /// The message reports a threat passing the user's area ("пройшли
+    /// повз Київ") and names the area nowhere else, e.g. as a heading or a
+    /// target ("Шахеди на Київ, перша група пройшла повз Бровари").
+    fn is_passing_by(&self, lower: &str) -> bool {
+        let spans = pass_by_spans(lower);
+        if !spans
+            .iter()
+            .any(|span| self.location.check(&lower[span.clone()]) != Proximity::None)
+        {
+            return false;
+        }
+        let mut rest = lower.to_string();
+        for span in spans {
+            rest.replace_range(span.clone(), &" ".repeat(span.len()));
+        }
+        self.location.check(&rest) == Proximity::None
+    }
+
     /// Determine proximity and nationwide status from lowercased text.
+    /// Only targets count: "з Брянська на Київ" is about Kyiv.
     fn resolve_location(&self, lower: &str, channel_title: &str) -> (Proximity, bool) {
         let lower_title = channel_title.to_lowercase();
         let nationwide = is_nationwide(lower);
         let explicit_nonlocal = self.has_explicit_nonlocal_location(lower);
+        let targets = origin::targets_only(lower);
+        let lower = targets.as_ref();
         let (district_m, city_m, oblast_m) = self.location.match_levels(lower);
 
         // "на Київ та область" should be treated as oblast scope (broader risk).
@@ -1202,6 +2782,8 @@ impl AlertFilter {
     }
 
     fn has_explicit_nonlocal_location(&self, lower: &str) -> bool {
+        let targets = origin::targets_only(lower);
+        let lower = targets.as_ref();
         if self.location.check(lower) != Proximity::None {
             return false;
         }
@@ -1222,15 +2804,64 @@ impl AlertFilter {
         channel_title: &str,
         text: &str,
     ) -> Option<String> {
-        let urgent = is_urgent(lower);
+        // A takeoff or deployment is a pre-alert: "увага" does not make it
+        // re-alert.
+        let urgent = is_urgent(lower)
+            && !threats.contains(&ThreatKind::NavalThreat)
+            && !threats.contains(&ThreatKind::BallisticWatch);
         self.evict();
-        let now = Instant::now();
+        let now = self.clock.now();
+        if self
+            .recent_texts
+            .is_repost(channel_id, lower, self.dedup_window, now)
+        {
+            debug!("Dedup: near-verbatim repost of a forwarded text – suppressing");
+            return None;
+        }
         let geo_hint = self.extract_geo_hint(lower, proximity);
 
         let primary = threats.iter().copied().max_by_key(|k| k.specificity())?;
         let signature = threat_signature(threats);
+        let sector = self.home.and_then(|home| {
+            region::sector_of(
+                home,
+                lower,
+                &self.place_context(channel_id, lower, channel_title),
+            )
+        });
+        let target = if proximity == Proximity::None && !nationwide {
+            self.target_of(channel_id, lower, channel_title)
+        } else {
+            None
+        };
+        let key = self
+            .find_wave(primary, sector, target, proximity > Proximity::None)
+            .unwrap_or(DedupKey {
+                kind: primary,
+                sector,
+                target,
+            });
+
+        if primary == ThreatKind::Shahed
+            && let Some(track) = self.current_track.as_ref().filter(|t| t.moving_away)
+        {
+            debug!(
+                "Shahed group ({}) moved away via {:?} – suppressing",
+                track.size,
+                track.history.last()
+            );
+            return None;
+        }
 
-        if let Some(entry) = self.cache.get(&primary) {
+        // An unreliable channel's "повторно" does not reopen a wave.
+        let realert = urgent && !self.reliability.is_unreliable(channel_id);
+        if urgent && !realert {
+            debug!(
+                "Channel {channel_id} is unreliable ({:?}) – not re-alerting",
+                self.reliability.stats(channel_id).unwrap_or_default()
+            );
+        }
+        if let Some(entry) = self.cache.get(&key) {
             if proximity > entry.max_proximity {
                 debug!(
                     "Dedup upgrade: {primary:?} {:?} → {proximity:?}",
@@ -1240,9 +2871,9 @@ impl AlertFilter {
                 debug!("Dedup: first nationwide alert for {primary:?} – forwarding");
             } else if signature & !entry.seen_signature != 0 {
                 debug!("Dedup: new threat combination for {primary:?} – forwarding");
-            } else if urgent && !entry.was_urgent {
+            } else if realert && !entry.was_urgent {
                 debug!("Dedup: first urgent re-alert for {primary:?} – forwarding");
-            } else if urgent && entry.last_channel_id == channel_id {
+            } else if realert && entry.last_channel_id == channel_id {
                 let can_forward = entry.last_urgent_at.map_or(true, |ts| {
                     now.duration_since(ts) >= self.urgent_same_channel_cooldown
                 });
@@ -1264,6 +2895,14 @@ impl AlertFilter {
                     "Dedup: Shahed district geo shift {:?} -> {:?} – forwarding",
                     entry.last_geo_hint, geo_hint
                 );
+            } else if primary == ThreatKind::Explosion
+                && geo_hint.is_some()
+                && entry.last_geo_hint != geo_hint
+            {
+                debug!(
+                    "Dedup: explosions reported in {geo_hint:?} after {:?} – forwarding",
+                    entry.last_geo_hint
+                );
             } else {
                 debug!(
                     "Dedup: {primary:?}/{proximity:?} suppressed (already sent {:?}, urgent={}, ch_id={})",
@@ -1273,40 +2912,262 @@ impl AlertFilter {
             }
         }
 
-        let prev_max = self.cache.get(&primary).map(|e| e.max_proximity);
+        // A wave first reported without a direction takes the first one
+        // named.
+        let prev = self.cache.remove(&key);
+        let key = DedupKey {
+            sector: key.sector.or(sector),
+            ..key
+        };
+        let prev_max = prev.as_ref().map(|e| e.max_proximity);
+        // A wave first seen heading elsewhere escalates when it turns local.
+        let escalated_from =
+            prev_max.filter(|p| (*p != Proximity::None || key.target.is_some()) && proximity > *p);
+        let alert_type = match (&prev, escalated_from) {
+            (_, Some(_)) => AlertType::Escalation,
+            (Some(_), None) => AlertType::Update,
+            (None, None) => AlertType::New,
+        };
         self.cache.insert(
-            primary,
+            key,
             DedupEntry {
                 sent_at: now,
                 max_proximity: prev_max.map_or(proximity, |p| proximity.max(p)),
-                seen_signature: self
-                    .cache
-                    .get(&primary)
+                seen_signature: prev
+                    .as_ref()
                     .map_or(signature, |e| e.seen_signature | signature),
-                seen_nationwide: self
-                    .cache
-                    .get(&primary)
+                seen_nationwide: prev
+                    .as_ref()
                     .map_or(nationwide, |e| e.seen_nationwide || nationwide),
+                seen_local: prev
+                    .as_ref()
+                    .map_or(!nationwide, |e| e.seen_local || !nationwide),
                 was_urgent: urgent,
                 last_urgent_at: if urgent {
                     Some(now)
                 } else {
-                    self.cache.get(&primary).and_then(|e| e.last_urgent_at)
+                    prev.as_ref().and_then(|e| e.last_urgent_at)
                 },
                 last_channel_id: channel_id,
                 last_geo_hint: geo_hint,
+                receding: false,
             },
         );
 
-        let alert = self.format(threats, proximity, channel_title, text, urgent, nationwide);
+        let mut alert = self.format(threats, proximity, channel_title, text, urgent, nationwide);
+        if let Some(from) = escalated_from {
+            // A nationwide announcement narrowing to the user's area.
+            let from = if prev.as_ref().is_some_and(|e| !e.seen_local) {
+                "ВСЯ УКРАЇНА".to_string()
+            } else if let (Proximity::None, Some(target)) = (from, key.target) {
+                target.to_uppercase()
+            } else {
+                from.label().to_string()
+            };
+            alert.insert_str(
+                0,
+                &format!("⬆️ Загроза наблизилась: {from} → {}\n", proximity.label()),
+            );
+        }
+        if let Some(route) = self.current_track.as_ref().and_then(|t| t.render()) {
+            alert.push('\n');
+            alert.push_str(&route);
+        }
+        if let Some(waypoints) = self.corridor.estimate(primary, now) {
+            alert.push('\n');
+            alert.push_str(&corridor::format_corridor(&waypoints));
+        }
+        if let Some(eta) = self.current_eta.filter(|_| !nationwide) {
+            alert.push('\n');
+            alert.push_str(&trajectory::format_eta(&eta));
+        }
+        if let Some(place) = self.uncertain_place {
+            alert.push('\n');
+            alert.push_str(&render::uncertain_place_line(place.name));
+        }
+        if let Some(channels) = &self.current_corroboration {
+            alert.push('\n');
+            alert.push_str(&corroboration::format_corroboration(channels));
+        }
+        if let Some(score) = self.current_confidence.filter(|_| self.show_confidence) {
+            alert.push_str(&format!("\n🎯 Впевненість: {score}%"));
+        }
+        self.last_alert = Some(AlertInfo {
+            alert_type,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide,
+            urgent,
+            reduced: nationwide && self.is_reduced_nationwide(threats),
+            escalated_from,
+            location_uncertain: self.uncertain_place.is_some(),
+            places: self.mentioned_places(channel_id, lower, channel_title),
+            confidence: self.current_confidence,
+        });
+        if let Some(wave) = &mut self.wave {
+            wave.forwarded += 1;
+        }
+        self.active_threats.alerted(primary, proximity, now);
+        self.recent_texts.record(channel_id, lower, now);
         Some(alert)
     }
 
+    /// Count the oblasts `lower` names towards the channel's region
+    /// profile.  Profiles are kept for as many channels as context
+    /// windows; the one with the fewest mentions makes room.  Launch
+    /// areas ("пуски з Криму") say nothing about the channel's region.
+    fn record_channel_oblasts(&mut self, channel_id: i64, lower: &str) {
+        let oblasts = gazetteer::mentioned_oblasts(&origin::targets_only(lower));
+        if oblasts.is_empty() {
+            return;
+        }
+        if !self.channel_oblasts.contains_key(&channel_id)
+            && self.channel_oblasts.len() >= self.max_channel_contexts
+            && let Some(smallest) = self
+                .channel_oblasts
+                .iter()
+                .min_by_key(|(_, counts)| counts.values().sum::<u32>())
+                .map(|(id, _)| *id)
+        {
+            self.channel_oblasts.remove(&smallest);
+        }
+        let counts = self.channel_oblasts.entry(channel_id).or_default();
+        for oblast in oblasts {
+            *counts.entry(oblast).or_default() += 1;
+        }
+    }
+
+    /// The oblast a channel reports on: the one its title names, else the
+    /// one most of its oblast mentions (at least [`CHANNEL_OBLAST_MIN`])
+    /// are of.
+    fn channel_oblast(&self, channel_id: i64, channel_title: &str) -> Option<&'static str> {
+        if let [oblast] = gazetteer::mentioned_oblasts(&channel_title.to_lowercase())[..] {
+            return Some(oblast);
+        }
+        let counts = self.channel_oblasts.get(&channel_id)?;
+        let total: u32 = counts.values().sum();
+        let (oblast, count) = counts.iter().max_by_key(|(_, count)| **count)?;
+        (*count >= CHANNEL_OBLAST_MIN && *count * 2 > total).then_some(*oblast)
+    }
+
+    /// What tells namesakes in `lower` apart: the oblasts it names, the
+    /// unambiguous places it and the channel's recent posts name, and the
+    /// channel's region.
+    fn place_context(&self, channel_id: i64, lower: &str, channel_title: &str) -> PlaceContext {
+        let recent = self
+            .channel_contexts
+            .get(&channel_id)
+            .into_iter()
+            .flat_map(|ctx| ctx.messages.iter().map(|m| m.text_lower.as_str()));
+        let anchors = std::iter::once(lower)
+            .chain(recent)
+            .flat_map(stem::words)
+            .filter_map(|word| match gazetteer::places_named(word)[..] {
+                [place] => Some(place),
+                _ => None,
+            })
+            .collect();
+        PlaceContext {
+            oblasts: gazetteer::mentioned_oblasts(lower),
+            anchors,
+            channel_oblast: self.channel_oblast(channel_id, channel_title),
+        }
+    }
+
+    /// Settlements `lower` names, see [`gazetteer::mentioned_places`].
+    fn mentioned_places(
+        &self,
+        channel_id: i64,
+        lower: &str,
+        channel_title: &str,
+    ) -> Vec<&'static Place> {
+        gazetteer::mentioned_places(lower, &self.place_context(channel_id, lower, channel_title))
+    }
+
+    /// Where an out-of-area threat heads: the named heading ("курсом на
+    /// Дніпро"), else the first place named, else the first oblast.
+    fn target_of(&self, channel_id: i64, lower: &str, channel_title: &str) -> Option<&'static str> {
+        let places = self.mentioned_places(channel_id, lower, channel_title);
+        let heading = corridor::extract_waypoints(lower).last().and_then(|w| {
+            let key = corridor::waypoint_key(w);
+            places.iter().copied().find(|p| {
+                corridor::same_place(&corridor::waypoint_key(&p.name.to_lowercase()), &key)
+            })
+        });
+        heading
+            .or_else(|| places.first().copied())
+            .map(|p| p.name)
+            .or_else(|| {
+                self.place_context(channel_id, lower, channel_title)
+                    .oblasts
+                    .first()
+                    .copied()
+            })
+    }
+
+    /// Which of the user's city and its namesakes `lower` names, if it
+    /// names one of them and the city has namesakes at all.
+    fn resolve_home_namesake(
+        &self,
+        channel_id: i64,
+        lower: &str,
+        channel_title: &str,
+    ) -> Option<Resolved> {
+        let home = self.ambiguous_home?;
+        let candidates = stem::words(lower)
+            .map(gazetteer::places_named)
+            .find(|places| places.contains(&home))?;
+        gazetteer::resolve(
+            &candidates,
+            &self.place_context(channel_id, lower, channel_title),
+        )
+    }
+
+    /// Dedup wave a `kind` message from `sector` about `target` belongs
+    /// to.  Without a direction that is the kind's latest wave; with one,
+    /// the latest wave from the same or a neighbouring sector, else a wave
+    /// that has no direction yet.  With `any_target`, a wave about another
+    /// target is the fallback: a report about the user's area continues a
+    /// wave first seen heading elsewhere.
+    fn find_wave(
+        &self,
+        kind: ThreatKind,
+        sector: Option<Sector>,
+        target: Option<&'static str>,
+        any_target: bool,
+    ) -> Option<DedupKey> {
+        let latest = |keep: &dyn Fn(&DedupKey) -> bool| {
+            self.cache
+                .iter()
+                .filter(|(k, _)| k.kind == kind && keep(k))
+                .max_by_key(|(_, e)| e.sent_at)
+                .map(|(k, _)| *k)
+        };
+        let find = |same_target: bool| {
+            let on_target = move |k: &DedupKey| !same_target || k.target == target;
+            match sector {
+                None => latest(&on_target),
+                Some(s) => latest(&|k| on_target(k) && k.sector.is_some_and(|ks| ks.near(s)))
+                    .or_else(|| latest(&|k| on_target(k) && k.sector.is_none())),
+            }
+        };
+        find(true).or_else(|| if any_target { find(false) } else { None })
+    }
+
+    /// Every threat is a kind configured as low-risk for the user's oblast
+    /// when announced nationwide.
+    fn is_reduced_nationwide(&self, threats: &[ThreatKind]) -> bool {
+        !threats.is_empty()
+            && threats
+                .iter()
+                .all(|t| self.reduced_nationwide_kinds.contains(t))
+    }
+
     fn extract_geo_hint(&self, lower: &str, proximity: Proximity) -> Option<String> {
         fn find_kw(lower: &str, kws: &[String]) -> Option<String> {
             for kw in kws {
                 let matched = if kw.contains(char::is_whitespace) {
-                    LocationConfig::contains_with_boundary(lower, kw)
+                    boundary::contains(lower, kw, Boundary::Word)
                 } else {
                     lower.contains(kw)
                 };
@@ -1334,13 +3195,19 @@ impl AlertFilter {
         urgent: bool,
         nationwide: bool,
     ) -> String {
+        let counts = counts::threat_counts(&text.to_lowercase(), threats);
         let threat_line: String = threats
             .iter()
-            .map(|t| format!("{} {}", t.emoji(), t.label()))
+            .map(|t| match counts.iter().find(|(k, n)| k == t && *n > 1) {
+                Some((_, n)) => format!("{} {} ×{n}", t.emoji(), t.label()),
+                None => format!("{} {}", t.emoji(), t.label()),
+            })
             .collect::<Vec<_>>()
             .join(" + ");
 
-        let prox_tag = if nationwide {
+        let prox_tag = if nationwide && self.is_reduced_nationwide(threats) {
+            "🟣 ВСЯ УКРАЇНА · ⚪ низький ризик для вашої області"
+        } else if nationwide {
             "🟣 ВСЯ УКРАЇНА"
         } else {
             proximity.tag()
@@ -1360,16 +3227,20 @@ impl AlertFilter {
             out.push_str(&format!("{threat_line} · {prox_tag}\n"));
         }
 
+        if threats.contains(&ThreatKind::BallisticWatch) {
+            out.push_str(render::WATCH_NOTE);
+            out.push('\n');
+        }
+        if threats.contains(&ThreatKind::NavalThreat) {
+            out.push_str(render::NAVAL_NOTE);
+            out.push('\n');
+        }
+
         // Separator
         out.push_str("———\n");
 
-        // Original message (trim to ~3200 chars to stay under TG limit)
-        let trimmed = if text.len() > 3200 {
-            &text[..3200]
-        } else {
-            text
-        };
-        out.push_str(trimmed);
+        // Original message
+        out.push_str(&self.render_text(text));
         out.push('\n');
 
         // Source
@@ -1396,31 +3267,105 @@ impl AlertFilter {
             out.push_str(&format!("ℹ️ Статус · {prox_tag}\n"));
         }
         out.push_str("———\n");
-        out.push_str(text);
+        out.push_str(&self.render_text(text));
+        out.push_str(&format!("\n— 📡 {channel_title}"));
+        out
+    }
+
+    fn format_expired(&self, expired: &[(ThreatKind, Proximity)]) -> String {
+        let mut out = String::from("🕓 Загроза ймовірно минула\n");
+        for (kind, proximity) in expired {
+            out.push_str(&format!("{} {}", kind.emoji(), kind.label()));
+            if *proximity != Proximity::None {
+                out.push_str(&format!(" · {}", proximity.tag()));
+            }
+            out.push('\n');
+        }
+        out.push_str("———\n");
+        out.push_str("Нових повідомлень немає, офіційного відбою не було. Будьте уважні.");
+        out
+    }
+
+    fn format_receding(
+        &self,
+        threat: ThreatKind,
+        passed: bool,
+        channel_title: &str,
+        text: &str,
+    ) -> String {
+        let header = if passed {
+            "↪️ Загроза пройшла повз ваш район"
+        } else {
+            "⬇️ Загроза віддаляється"
+        };
+        let mut out = format!("{header} · {} {}\n", threat.emoji(), threat.label());
+        out.push_str("———\n");
+        out.push_str(&self.render_text(text));
         out.push_str(&format!("\n— 📡 {channel_title}"));
         out
     }
+
+    /// Forwarded body text, sanitized when `SANITIZE_ALERT_TEXT` is on
+    /// (falling back to the original if sanitizing would leave nothing) and
+    /// shortened to [`render::MAX_TEXT_CHARS`] keeping the sentences that
+    /// name a threat, your area or a count.
+    fn render_text<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        let clean = if self.sanitize_text {
+            render::sanitize_text(text)
+        } else {
+            String::new()
+        };
+        let text: std::borrow::Cow<'a, str> = if clean.is_empty() {
+            text.into()
+        } else {
+            clean.into()
+        };
+        if text.chars().count() <= render::MAX_TEXT_CHARS {
+            return text;
+        }
+        render::truncate_text(&text, render::MAX_TEXT_CHARS, |sentence| {
+            self.is_key_sentence(sentence)
+        })
+        .into()
+    }
+
+    /// Whether a sentence names a threat, a location of yours or a count
+    /// ("5 шахедів", "група з 6 БпЛА").
+    fn is_key_sentence(&self, sentence: &str) -> bool {
+        let lower = sentence.to_lowercase();
+        let has_count = lower.split_whitespace().any(|w| {
+            !w.contains(':')
+                && w.trim_start_matches(|c: char| !c.is_alphanumeric())
+                    .starts_with(|c: char| c.is_ascii_digit())
+        });
+        has_count
+            || !detect_threats(&lower).is_empty()
+            || self.location.check(&lower) != Proximity::None
+    }
 }
 
 impl fmt::Display for AlertFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "AlertFilter(oblast={:?}, city={:?}, district={:?}, dedup={}s, urgent_cd={}s, neg_status_cd={}s, fwd_all={})",
+            "AlertFilter(oblast={:?}, city={:?}, district={:?}, dedup={}s, urgent_cd={}s, neg_status_cd={}s, corridor={}s, tracks={}s, fwd_all={}, ctx_rules={})",
             self.location.oblast,
             self.location.city,
             self.location.district,
             self.dedup_window.as_secs(),
             self.urgent_same_channel_cooldown.as_secs(),
             self.negative_status_cooldown.as_secs(),
+            self.corridor.window().as_secs(),
+            self.shahed_tracks.window().as_secs(),
             self.forward_all_threats,
+            self.channel_policies.rule_count(),
         )
     }
 }
 
 /// Kyiv-based user config with both UA and RU name forms.
 pub fn kyiv_filter() -> AlertFilter {
-    AlertFilter {
+    let mut filter = AlertFilter {
         location: LocationConfig {
             oblast: vec!["київськ".into(), "киевск".into()],
             city: vec![
@@ -1432,33 +3377,105 @@ pub fn kyiv_filter() -> AlertFilter {
                 "васильков".into(), // satellite city
             ],
             district: vec!["шевченківськ".into(), "шевченковск".into()],
+            radius: None,
         },
         dedup_window: Duration::from_secs(180),
+        naval_watch_window: Duration::from_secs(6 * 3600),
+        kind_dedup_windows: HashMap::new(),
+        recent_texts: RecentTexts::new(0.9),
+        state_path: None,
+        clock: Arc::new(SystemClock),
         cache: HashMap::new(),
+        home: None,
         channel_contexts: HashMap::new(),
+        max_channel_contexts: 500,
         context_window: Duration::from_secs(300),
+        channel_policies: ChannelPolicies::default(),
         urgent_same_channel_cooldown: Duration::from_secs(0),
         forward_all_threats: false,
         negative_status_state: HashMap::new(),
         negative_status_cooldown: Duration::from_secs(120),
-    }
+        last_alert: None,
+        last_review: None,
+        last_verdict: None,
+        last_suppression: None,
+        sanitize_text: false,
+        recap_channels: Vec::new(),
+        corridor: CorridorTracker::new(Duration::from_secs(300)),
+        shahed_tracks: ShahedTracks::new(Duration::from_secs(1800)),
+        current_track: None,
+        trajectories: Trajectories::new(Duration::from_secs(1800)),
+        current_eta: None,
+        wave: None,
+        wave_idle: Duration::from_secs(3600),
+        active_threats: ActiveThreats::from_spec(""),
+        min_confidence: 0,
+        show_confidence: false,
+        current_confidence: None,
+        channel_trust: HashMap::new(),
+        reliability: Reliability::default(),
+        corroboration: Corroboration::new(0, Duration::from_secs(300)),
+        current_corroboration: None,
+        ambiguous_home: None,
+        channel_oblasts: HashMap::new(),
+        uncertain_place: None,
+        reduced_nationwide_kinds: Vec::new(),
+    };
+    filter.home = region::home_place(&filter.location);
+    filter
 }
 
 /// Kharkiv-based user config.
 pub fn kharkiv_filter() -> AlertFilter {
-    AlertFilter {
+    let mut filter = AlertFilter {
         location: LocationConfig {
             oblast: vec!["харківськ".into()],
             city: vec!["харків".into(), "харков".into()],
             district: vec!["київськ".into(), "шевченківськ".into()],
+            radius: None,
         },
         dedup_window: Duration::from_secs(180),
+        naval_watch_window: Duration::from_secs(6 * 3600),
+        kind_dedup_windows: HashMap::new(),
+        recent_texts: RecentTexts::new(0.9),
+        state_path: None,
+        clock: Arc::new(SystemClock),
         cache: HashMap::new(),
+        home: None,
         channel_contexts: HashMap::new(),
+        max_channel_contexts: 500,
         context_window: Duration::from_secs(300),
+        channel_policies: ChannelPolicies::default(),
         urgent_same_channel_cooldown: Duration::from_secs(0),
         forward_all_threats: false,
         negative_status_state: HashMap::new(),
         negative_status_cooldown: Duration::from_secs(120),
-    }
+        last_alert: None,
+        last_review: None,
+        last_verdict: None,
+        last_suppression: None,
+        sanitize_text: false,
+        recap_channels: Vec::new(),
+        corridor: CorridorTracker::new(Duration::from_secs(300)),
+        shahed_tracks: ShahedTracks::new(Duration::from_secs(1800)),
+        current_track: None,
+        trajectories: Trajectories::new(Duration::from_secs(1800)),
+        current_eta: None,
+        wave: None,
+        wave_idle: Duration::from_secs(3600),
+        active_threats: ActiveThreats::from_spec(""),
+        min_confidence: 0,
+        show_confidence: false,
+        current_confidence: None,
+        channel_trust: HashMap::new(),
+        reliability: Reliability::default(),
+        corroboration: Corroboration::new(0, Duration::from_secs(300)),
+        current_corroboration: None,
+        ambiguous_home: None,
+        channel_oblasts: HashMap::new(),
+        uncertain_place: None,
+        reduced_nationwide_kinds: Vec::new(),
+    };
+    filter.home = region::home_place(&filter.location);
+    filter
 }
@@ -0,0 +1,84 @@
+//! Origin vs target location mentions.  "Балістика з Брянська на Київ"
+//! names where a threat comes from and where it heads; only the target
+//! says anything about the user's proximity.  Launch areas ("пуски з
+//! Криму", "шахеди з боку Білорусі") are cut out before location matching,
+//! so they neither match the user's keywords nor count as another region.
+
+use std::borrow::Cow;
+
+/// Prepositions opening an origin: "з/із/зі", "из/с/со/от".
+const ORIGIN: &[&str] = &["з", "із", "зі", "из", "с", "со", "от"];
+
+/// Words closing an origin: a target or a waypoint follows ("на Київ",
+/// "у бік Сум", "курсом на", "через Житомирщину").
+const TARGET: &[&str] = &[
+    "на",
+    "в",
+    "у",
+    "во",
+    "до",
+    "к",
+    "ко",
+    "курсом",
+    "вектором",
+    "через",
+    "повз",
+    "мимо",
+    "над",
+    "по",
+];
+
+/// Words an origin spans at most ("з боку Брянської області").
+const MAX_ORIGIN_WORDS: usize = 3;
+
+/// `lower` with origin mentions (the preposition and up to
+/// [`MAX_ORIGIN_WORDS`] words after it) removed; borrowed when it has none.
+pub fn targets_only(lower: &str) -> Cow<'_, str> {
+    if !lower.split_whitespace().any(|t| ORIGIN.contains(&bare(t))) {
+        return Cow::Borrowed(lower);
+    }
+    let mut out = String::with_capacity(lower.len());
+    for line in lower.lines() {
+        let mut skip = 0;
+        let mut prev = "";
+        for token in line.split_whitespace() {
+            let word = bare(token);
+            if skip > 0 && !word.is_empty() && !TARGET.contains(&word) {
+                skip = if ends_clause(token) { 0 } else { skip - 1 };
+                prev = word;
+                continue;
+            }
+            skip = 0;
+            if is_origin(prev, word) && !ends_clause(token) {
+                skip = MAX_ORIGIN_WORDS;
+                prev = word;
+                continue;
+            }
+            out.push_str(token);
+            out.push(' ');
+            prev = word;
+        }
+        out.push('\n');
+    }
+    Cow::Owned(out)
+}
+
+/// "от" after a distance ("в 20 км от Киева") tells how close, not where
+/// from.
+fn is_origin(prev: &str, word: &str) -> bool {
+    ORIGIN.contains(&word)
+        && !(word == "от"
+            && (prev.starts_with("км")
+                || prev.starts_with("кілометр")
+                || prev.starts_with("километр")
+                || prev.starts_with("недалек")
+                || prev.starts_with("вблиз")))
+}
+
+fn bare(token: &str) -> &str {
+    token.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+fn ends_clause(token: &str) -> bool {
+    token.ends_with([',', '.', ';', ':', '!', '?', ')'])
+}
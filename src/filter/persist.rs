@@ -0,0 +1,231 @@
+//! Filter state across restarts.  A crash or redeploy in the middle of an
+//! attack would otherwise re-forward every wave as the stream catches up,
+//! and classify the first posts without the channel context that tells a
+//! bare "ціль" is a ballistic missile.  The dedup cache and the channel
+//! context windows are saved to `FILTER_STATE_PATH` (JSON) with
+//! wall-clock timestamps and restored at startup; waves and messages whose
+//! window ran out while the relay was down are dropped.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::filter::clock::Clock;
+use crate::filter::region::Sector;
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{
+    AlertFilter, ChannelContext, ContextMessage, DedupEntry, DedupKey, Proximity, gazetteer,
+};
+
+/// One dedup wave, see [`DedupKey`] and [`DedupEntry`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedWave {
+    kind: String,
+    /// [`Sector::name`].
+    sector: Option<String>,
+    target: Option<String>,
+    /// Unix seconds.
+    sent_at: i64,
+    /// [`Proximity::name`]; older files hold the variant name.
+    max_proximity: String,
+    seen_signature: u16,
+    seen_nationwide: bool,
+    seen_local: bool,
+    was_urgent: bool,
+    /// Unix seconds.
+    last_urgent_at: Option<i64>,
+    last_channel_id: i64,
+    last_geo_hint: Option<String>,
+    receding: bool,
+}
+
+/// One message of a channel context window, see [`ContextMessage`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedMessage {
+    /// Unix seconds.
+    at: i64,
+    text_lower: String,
+    threats: Vec<String>,
+    /// [`Proximity::name`].
+    proximity: String,
+}
+
+/// A channel's context window, see [`ChannelContext`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedContext {
+    channel_id: i64,
+    window_secs: u64,
+    max_messages: usize,
+    messages: Vec<SavedMessage>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedState {
+    waves: Vec<SavedWave>,
+    /// Absent in files written before contexts were saved.
+    #[serde(default)]
+    contexts: Vec<SavedContext>,
+}
+
+/// State read back by [`load`].
+#[derive(Default)]
+pub(super) struct Restored {
+    pub cache: HashMap<DedupKey, DedupEntry>,
+    pub contexts: HashMap<i64, ChannelContext>,
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// `at` in Unix seconds, `now` being the filter clock's present.
+fn to_unix(at: Instant, now: Instant) -> i64 {
+    unix_now() - now.saturating_duration_since(at).as_secs() as i64
+}
+
+/// `None` when the moment predates what `Instant` can represent.
+fn from_unix(secs: i64, now: Instant) -> Option<Instant> {
+    let age = Duration::from_secs(unix_now().saturating_sub(secs).max(0) as u64);
+    now.checked_sub(age)
+}
+
+/// Write `filter`'s dedup cache and context windows to `path`, replacing
+/// it atomically.
+pub(super) fn save(filter: &AlertFilter, path: &Path) -> Result<()> {
+    let now = filter.clock.now();
+    let state = SavedState {
+        waves: filter
+            .cache
+            .iter()
+            .map(|(key, e)| SavedWave {
+                kind: key.kind.variant_name().to_string(),
+                sector: key.sector.map(|s| s.name().to_string()),
+                target: key.target.map(str::to_string),
+                sent_at: to_unix(e.sent_at, now),
+                max_proximity: e.max_proximity.name().to_string(),
+                seen_signature: e.seen_signature,
+                seen_nationwide: e.seen_nationwide,
+                seen_local: e.seen_local,
+                was_urgent: e.was_urgent,
+                last_urgent_at: e.last_urgent_at.map(|at| to_unix(at, now)),
+                last_channel_id: e.last_channel_id,
+                last_geo_hint: e.last_geo_hint.clone(),
+                receding: e.receding,
+            })
+            .collect(),
+        contexts: filter
+            .channel_contexts
+            .iter()
+            .filter(|(_, ctx)| !ctx.messages.is_empty())
+            .map(|(channel_id, ctx)| SavedContext {
+                channel_id: *channel_id,
+                window_secs: ctx.window_duration.as_secs(),
+                max_messages: ctx.max_messages,
+                messages: ctx
+                    .messages
+                    .iter()
+                    .map(|m| SavedMessage {
+                        at: to_unix(m.timestamp, now),
+                        text_lower: m.text_lower.clone(),
+                        threats: m
+                            .detected_threats
+                            .iter()
+                            .map(|k| k.variant_name().to_string())
+                            .collect(),
+                        proximity: m.detected_proximity.name().to_string(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(&state)?)
+        .with_context(|| format!("cannot write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("cannot replace {}", path.display()))
+}
+
+/// The state saved at `path`; empty when there is no file yet.  Waves
+/// naming a kind, sector or place this build does not know are skipped,
+/// as are expired context messages.
+pub(super) fn load(path: &Path, clock: &Arc<dyn Clock>) -> Result<Restored> {
+    let now = clock.now();
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Restored::default()),
+        Err(e) => return Err(e).with_context(|| format!("cannot read {}", path.display())),
+    };
+    let state: SavedState =
+        serde_json::from_slice(&data).with_context(|| format!("bad {}", path.display()))?;
+    let mut cache = HashMap::new();
+    for w in state.waves {
+        let restored = (|| {
+            let key = DedupKey {
+                kind: ThreatKind::from_variant_name(&w.kind)?,
+                sector: match &w.sector {
+                    Some(s) => Some(Sector::from_name(s)?),
+                    None => None,
+                },
+                target: match &w.target {
+                    Some(t) => Some(gazetteer::static_name(t)?),
+                    None => None,
+                },
+            };
+            let entry = DedupEntry {
+                sent_at: from_unix(w.sent_at, now)?,
+                max_proximity: Proximity::from_name(&w.max_proximity)?,
+                seen_signature: w.seen_signature,
+                seen_nationwide: w.seen_nationwide,
+                seen_local: w.seen_local,
+                was_urgent: w.was_urgent,
+                last_urgent_at: w.last_urgent_at.and_then(|at| from_unix(at, now)),
+                last_channel_id: w.last_channel_id,
+                last_geo_hint: w.last_geo_hint.clone(),
+                receding: w.receding,
+            };
+            Some((key, entry))
+        })();
+        match restored {
+            Some((key, entry)) => {
+                cache.insert(key, entry);
+            }
+            None => tracing::warn!("Skipping unreadable saved dedup wave {w:?}"),
+        }
+    }
+    let mut contexts = HashMap::new();
+    for saved in state.contexts {
+        let mut ctx = ChannelContext {
+            messages: saved
+                .messages
+                .into_iter()
+                .filter_map(|m| {
+                    Some(ContextMessage {
+                        timestamp: from_unix(m.at, now)?,
+                        detected_threats: m
+                            .threats
+                            .iter()
+                            .filter_map(|k| ThreatKind::from_variant_name(k))
+                            .collect(),
+                        detected_proximity: Proximity::from_name(&m.proximity)?,
+                        text_lower: m.text_lower,
+                    })
+                })
+                .collect(),
+            window_duration: Duration::from_secs(saved.window_secs),
+            max_messages: saved.max_messages,
+            last_used: now,
+            clock: clock.clone(),
+        };
+        ctx.evict();
+        if let Some(last) = ctx.messages.last() {
+            ctx.last_used = last.timestamp;
+            contexts.insert(saved.channel_id, ctx);
+        }
+    }
+    Ok(Restored { cache, contexts })
+}
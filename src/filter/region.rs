@@ -0,0 +1,119 @@
+//! Coarse approach direction of a threat, for dedup.  A Shahed alert for the
+//! user's city must not suppress a later one coming from the other side, so
+//! dedup waves are split by the compass sector (seen from the user's home
+//! settlement) of the places a message names.
+
+use crate::filter::LocationConfig;
+use crate::filter::corridor::extract_waypoints;
+use crate::filter::gazetteer::{
+    self, PLACES, Place, PlaceContext, distance_km, mentioned_oblasts, places_named,
+};
+
+/// Places closer than this to home say nothing about the direction.
+const HOME_RADIUS_KM: f64 = 20.0;
+
+/// One of eight 45° compass sectors around the user's home.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sector {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Sector {
+    const ALL: [Sector; 8] = [
+        Sector::N,
+        Sector::NE,
+        Sector::E,
+        Sector::SE,
+        Sector::S,
+        Sector::SW,
+        Sector::W,
+        Sector::NW,
+    ];
+
+    /// Sector of `to` as seen from `from`.
+    pub fn between(from: &Place, to: &Place) -> Sector {
+        let dx = (to.lon - from.lon) * from.lat.to_radians().cos();
+        let dy = to.lat - from.lat;
+        let bearing = dx.atan2(dy).to_degrees().rem_euclid(360.0);
+        Self::ALL[((bearing / 45.0).round() as usize) % 8]
+    }
+
+    /// Stable name for saved state ("N", "NE", …).
+    pub fn name(self) -> &'static str {
+        match self {
+            Sector::N => "N",
+            Sector::NE => "NE",
+            Sector::E => "E",
+            Sector::SE => "SE",
+            Sector::S => "S",
+            Sector::SW => "SW",
+            Sector::W => "W",
+            Sector::NW => "NW",
+        }
+    }
+
+    /// Inverse of [`name`](Self::name); case-insensitive.
+    pub fn from_name(name: &str) -> Option<Sector> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Same or neighbouring sector: a threat drifting across a sector
+    /// boundary is still the same approach.
+    pub fn near(self, other: Sector) -> bool {
+        let diff = (self as i8 - other as i8).rem_euclid(8);
+        diff <= 1 || diff == 7
+    }
+}
+
+/// The user's home settlement: the one nearest `MY_LAT` / `MY_LON`, else
+/// the first gazetteer place matching `MY_CITY` (of namesakes, the one in
+/// `MY_OBLAST`), else `MY_OBLAST`'s centre.
+pub fn home_place(location: &LocationConfig) -> Option<&'static Place> {
+    if let Some(radius) = location.radius
+        && let Some(nearest) = gazetteer::reverse_geocode(radius.lat, radius.lon)
+    {
+        return Some(nearest.nearest);
+    }
+    let ctx = PlaceContext {
+        oblasts: mentioned_oblasts(&location.oblast.join(" ")),
+        ..PlaceContext::default()
+    };
+    location
+        .city
+        .iter()
+        .find_map(|city| gazetteer::resolve(&places_named(city), &ctx))
+        .map(|resolved| resolved.place)
+        .or_else(|| {
+            location.oblast.iter().find_map(|oblast| {
+                PLACES
+                    .iter()
+                    .filter(|p| p.raion.is_some())
+                    .find(|p| p.oblast.to_lowercase().starts_with(oblast.as_str()))
+            })
+        })
+}
+
+/// Direction of the first place in `lower` that is away from `home`:
+/// waypoints ("курсом на …") first, then any other place named.  Namesakes
+/// are told apart by `ctx`.
+pub fn sector_of(home: &Place, lower: &str, ctx: &PlaceContext) -> Option<Sector> {
+    let words = lower
+        .split(|c: char| !c.is_alphabetic() && c != '\'' && c != 'ʼ')
+        .filter(|w| !w.is_empty());
+    extract_waypoints(lower)
+        .into_iter()
+        .chain(words.map(str::to_string))
+        .filter_map(|w| gazetteer::resolve(&places_named(&w), ctx))
+        .map(|resolved| resolved.place)
+        .find(|p| distance_km(home.lat, home.lon, p.lat, p.lon) > HOME_RADIUS_KM)
+        .map(|p| Sector::between(home, p))
+}
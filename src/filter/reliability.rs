@@ -0,0 +1,144 @@
+//! Channel reliability learned from history.  Some channels are early and
+//! accurate, others echo everything or post what nobody else confirms.
+//! Every threat report is matched against other channels' reports of the
+//! same kind: a report some other channel also made is *corroborated*, and
+//! one that was the first of them is *early*.  Together with how often the
+//! secondary filter rejected the channel's alerts this gives a `0.0..=1.0`
+//! score: an early, accurate channel nears 1, an echo ~0.5, a channel
+//! nobody confirms nears 0.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::filter::threat_kind::ThreatKind;
+
+/// Reports of the same kind this close together corroborate each other.
+const CORROBORATION_WINDOW: Duration = Duration::from_secs(600);
+
+/// Reports before a channel's score counts.
+const MIN_REPORTS: u32 = 10;
+
+/// Channels scoring below this are unreliable.
+pub const UNRELIABLE: f64 = 0.3;
+
+/// One channel's record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// Threat reports, a channel repeating itself within the window
+    /// counted once.
+    pub reports: u32,
+    /// Reports another channel also made.
+    pub corroborated: u32,
+    /// Corroborated reports that came first.
+    pub early: u32,
+    /// Alerts the secondary filter checked.
+    pub checked: u32,
+    /// Alerts the secondary filter rejected.
+    pub rejected: u32,
+}
+
+impl ChannelStats {
+    /// Smoothed score; `None` before [`MIN_REPORTS`] reports.
+    pub fn score(&self) -> Option<f64> {
+        if self.reports < MIN_REPORTS {
+            return None;
+        }
+        let confirmed =
+            f64::from(self.corroborated + self.early + 1) / f64::from(2 * self.reports + 2);
+        // Unchecked channels are not penalized.
+        let accepted = f64::from(self.checked - self.rejected + 1) / f64::from(self.checked + 1);
+        Some(confirmed * accepted)
+    }
+}
+
+struct Report {
+    channel_id: i64,
+    kind: ThreatKind,
+    at: Instant,
+    /// No other channel had reported the kind yet.
+    first: bool,
+    corroborated: bool,
+}
+
+/// Per-channel stats and the recent reports still open to corroboration.
+#[derive(Default)]
+pub struct Reliability {
+    stats: HashMap<i64, ChannelStats>,
+    recent: VecDeque<Report>,
+}
+
+impl Reliability {
+    /// `channel_id` reported a `kind` threat.
+    pub fn report(&mut self, channel_id: i64, kind: ThreatKind, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|r| now.duration_since(r.at) >= CORROBORATION_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        let same_kind = |r: &&mut Report| r.kind == kind;
+        if self
+            .recent
+            .iter_mut()
+            .filter(same_kind)
+            .any(|r| r.channel_id == channel_id)
+        {
+            return;
+        }
+        let mut corroborated = false;
+        for other in self.recent.iter_mut().filter(same_kind) {
+            corroborated = true;
+            if !other.corroborated {
+                other.corroborated = true;
+                let stats = self.stats.entry(other.channel_id).or_default();
+                stats.corroborated += 1;
+                if other.first {
+                    stats.early += 1;
+                }
+            }
+        }
+        let stats = self.stats.entry(channel_id).or_default();
+        stats.reports += 1;
+        if corroborated {
+            stats.corroborated += 1;
+        }
+        self.recent.push_back(Report {
+            channel_id,
+            kind,
+            at: now,
+            first: !corroborated,
+            corroborated,
+        });
+    }
+
+    /// The secondary filter checked an alert of `channel_id`.
+    pub fn checked(&mut self, channel_id: i64, rejected: bool) {
+        let stats = self.stats.entry(channel_id).or_default();
+        stats.checked += 1;
+        if rejected {
+            stats.rejected += 1;
+        }
+    }
+
+    pub fn stats(&self, channel_id: i64) -> Option<ChannelStats> {
+        self.stats.get(&channel_id).copied()
+    }
+
+    pub fn score(&self, channel_id: i64) -> Option<f64> {
+        self.stats.get(&channel_id).and_then(ChannelStats::score)
+    }
+
+    /// Scored below [`UNRELIABLE`].
+    pub fn is_unreliable(&self, channel_id: i64) -> bool {
+        self.score(channel_id).is_some_and(|s| s < UNRELIABLE)
+    }
+
+    /// Channels currently scored unreliable.
+    pub fn unreliable_count(&self) -> usize {
+        self.stats
+            .values()
+            .filter(|s| s.score().is_some_and(|s| s < UNRELIABLE))
+            .count()
+    }
+}
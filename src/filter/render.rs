@@ -0,0 +1,249 @@
+// ───────────────────────────── Rendering ─────────────────────────────────
+
+/// Stems of "subscribe / send us news" call-to-action lines that channels
+/// append to every post.
+const PROMO_MARKERS: &[&str] = &[
+    "підпис", // підписатися, підписуйтесь
+    "подпис", // подписаться, подписывайтесь
+    "надіслати новину",
+    "прислать новость",
+    "наш канал",
+    "наш чат",
+    "резервний канал",
+    "резервный канал",
+];
+
+fn is_link_token(token: &str) -> bool {
+    let lower = token.to_lowercase();
+    lower.contains("://")
+        || lower.contains("t.me/")
+        || lower.contains("telegram.me/")
+        || lower.starts_with("www.")
+}
+
+fn is_hashtag_token(token: &str) -> bool {
+    token.starts_with('#') && token.chars().nth(1).is_some_and(|c| c.is_alphanumeric())
+}
+
+/// Strip links, hashtags and promotional call-to-action lines from a
+/// forwarded channel post, keeping the informational body.
+///
+/// Lines left empty by stripping are dropped, repeated lines are kept once,
+/// and runs of blank lines collapse into a single one.
+pub fn sanitize_text(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if lines.last().is_some_and(|l| !l.is_empty()) {
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        let had_link = line.split_whitespace().any(is_link_token);
+        let kept: Vec<&str> = line
+            .split_whitespace()
+            .filter(|t| !is_link_token(t) && !is_hashtag_token(t))
+            .collect();
+        let cleaned = kept.join(" ");
+
+        // Nothing informative left (only links/tags/emoji/punctuation).
+        if !cleaned.chars().any(|c| c.is_alphanumeric()) {
+            continue;
+        }
+        // Short call-to-action lines: "👉 Підписатися", "Надіслати новину".
+        let lower = cleaned.to_lowercase();
+        if (had_link || cleaned.chars().count() < 60)
+            && PROMO_MARKERS.iter().any(|m| lower.contains(m))
+        {
+            continue;
+        }
+        if lines.contains(&cleaned) {
+            continue;
+        }
+        lines.push(cleaned);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Character budget of the forwarded text; Telegram caps messages at 4096
+/// and the header, enrichment lines and source need the rest.
+pub const MAX_TEXT_CHARS: usize = 3200;
+
+/// Sentences of `text` with whether each one ends its line.  Blank lines
+/// are dropped.
+fn sentences(text: &str) -> Vec<(&str, bool)> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let mut start = 0;
+        let mut prev_end = false;
+        for (i, c) in line.char_indices() {
+            if prev_end && c.is_whitespace() {
+                let sentence = line[start..i].trim();
+                if !sentence.is_empty() {
+                    out.push((sentence, false));
+                }
+                start = i;
+            }
+            prev_end = matches!(c, '.' | '!' | '?' | '…');
+        }
+        let sentence = line[start..].trim();
+        if !sentence.is_empty() {
+            out.push((sentence, true));
+        } else if let Some(last) = out.last_mut() {
+            last.1 = true;
+        }
+    }
+    out
+}
+
+/// Shorten `text` to at most `max_chars` characters sentence by sentence.
+/// The first sentence (the headline) is kept, then every sentence
+/// `is_key` accepts (threat, location, counts), then the rest in order
+/// while they fit; each run of dropped sentences becomes "…".
+pub fn truncate_text(text: &str, max_chars: usize, is_key: impl Fn(&str) -> bool) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let parts = sentences(text);
+    let mut keep = vec![false; parts.len()];
+    // Each kept sentence also pays for a separator and a possible "…".
+    let mut used = 2;
+    let key = (1..parts.len()).filter(|i| is_key(parts[*i].0));
+    let order: Vec<usize> = (0..parts.len().min(1))
+        .chain(key)
+        .chain(1..parts.len())
+        .collect();
+    for i in order {
+        let cost = parts[i].0.chars().count() + 3;
+        if !keep[i] && used + cost <= max_chars {
+            keep[i] = true;
+            used += cost;
+        }
+    }
+    if !keep.iter().any(|k| *k) {
+        // A single sentence longer than the budget.
+        let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+        return format!("{}…", cut.trim_end());
+    }
+
+    let mut out = String::new();
+    let mut gap = false;
+    let mut sep = "";
+    for (i, (sentence, ends_line)) in parts.iter().enumerate() {
+        if !keep[i] {
+            gap = true;
+            continue;
+        }
+        out.push_str(sep);
+        if gap {
+            out.push_str("… ");
+            gap = false;
+        }
+        out.push_str(sentence);
+        sep = if *ends_line { "\n" } else { " " };
+    }
+    if gap {
+        out.push_str(if sep == "\n" { "\n…" } else { " …" });
+    }
+    out
+}
+
+/// Header of recap posts delivered outside the alert flow (see
+/// `RECAP_CHANNELS`).
+pub const RECAP_HEADER: &str = "📊 Підсумок";
+
+/// Line under the header of a MiG-31K takeoff alert.
+pub const WATCH_NOTE: &str =
+    "Можливі пуски «Кінжалів» по всій території. Відбій – після посадки борту.";
+
+/// Header of the message closing a MiG-31K ballistic watch.
+pub const WATCH_CLOSED_HEADER: &str = "🛬 Відбій загрози МіГ-31К";
+
+/// Line under the header of a missile-carrier deployment alert.
+pub const NAVAL_NOTE: &str = "Пусків ще не було – можлива ракетна атака найближчими годинами.";
+
+/// Note on an alert naming a settlement that shares the user's city's
+/// name, when the message does not say which one it is.
+pub fn uncertain_place_line(name: &str) -> String {
+    format!("❔ «{name}» є в кількох областях – з повідомлення не зрозуміло, який саме")
+}
+
+/// Whether a formatted message is a recap rather than an alert.
+pub fn is_recap(formatted: &str) -> bool {
+    formatted
+        .split_once("———\n")
+        .is_some_and(|(header, _)| header.contains(RECAP_HEADER))
+}
+
+/// Per-subscriber rendering preferences, applied at delivery time to the
+/// text produced by [`AlertFilter`](crate::filter::AlertFilter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryFormat {
+    /// Header plus the first line of the original text, no separator.
+    pub compact: bool,
+    /// Include the original channel text below the header.
+    pub show_text: bool,
+    /// Include the trailing `— 📡 <channel>` line.
+    pub show_source: bool,
+}
+
+impl Default for DeliveryFormat {
+    fn default() -> Self {
+        Self {
+            compact: false,
+            show_text: true,
+            show_source: true,
+        }
+    }
+}
+
+impl DeliveryFormat {
+    /// Re-render a formatted alert (`header ——— text — 📡 source`).
+    /// Messages that don't follow that layout are passed through unchanged.
+    pub fn apply(&self, formatted: &str) -> String {
+        if *self == Self::default() {
+            return formatted.to_string();
+        }
+        let Some((header, rest)) = formatted.split_once("———\n") else {
+            return formatted.to_string();
+        };
+        let (body, source) = match rest.rsplit_once("\n— 📡 ") {
+            Some((body, source)) => (body, Some(source)),
+            None => (rest, None),
+        };
+
+        let mut out = header.trim_end().to_string();
+        if self.show_text {
+            let body = if self.compact {
+                body.lines().find(|l| !l.trim().is_empty()).unwrap_or("")
+            } else {
+                body
+            };
+            if !body.trim().is_empty() {
+                out.push_str(if self.compact { "\n" } else { "\n———\n" });
+                out.push_str(body.trim_end());
+            }
+        }
+        if self.show_source
+            && let Some(source) = source
+        {
+            out.push_str(&format!("\n— 📡 {source}"));
+        }
+        out
+    }
+
+    /// One-line description for the `/format` reply.
+    pub fn describe(&self) -> String {
+        let on_off = |v: bool| if v { "on" } else { "off" };
+        format!(
+            "layout: {}, text: {}, source: {}",
+            if self.compact { "compact" } else { "full" },
+            on_off(self.show_text),
+            on_off(self.show_source)
+        )
+    }
+}
@@ -0,0 +1,129 @@
+//! Language/script of a message: Ukrainian vs. Russian Cyrillic (told apart
+//! by the letters only one alphabet has), a mix of both, or Latin.  Used
+//! for per-channel analytics, so operators can see which declension stems
+//! and slang variants their source mix actually needs.
+
+/// Script of one message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Script {
+    Ukrainian,
+    Russian,
+    /// Letters unique to both alphabets (surzhyk, quotes of Russian posts).
+    Mixed,
+    /// Mostly Latin letters (transliteration, English).
+    Latin,
+    /// No letters, or Cyrillic without any letter unique to either alphabet.
+    Undetermined,
+}
+
+impl Script {
+    pub const ALL: [Script; 5] = [
+        Script::Ukrainian,
+        Script::Russian,
+        Script::Mixed,
+        Script::Latin,
+        Script::Undetermined,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Script::Ukrainian => "UA",
+            Script::Russian => "RU",
+            Script::Mixed => "mixed",
+            Script::Latin => "Latin",
+            Script::Undetermined => "?",
+        }
+    }
+}
+
+/// Letters of the Ukrainian alphabet missing from the Russian one.
+const UKRAINIAN_ONLY: &[char] = &['і', 'ї', 'є', 'ґ'];
+
+/// Letters of the Russian alphabet missing from the Ukrainian one.
+const RUSSIAN_ONLY: &[char] = &['ы', 'э', 'ъ', 'ё'];
+
+pub fn detect_script(text: &str) -> Script {
+    let (mut cyrillic, mut latin, mut ua, mut ru) = (0usize, 0usize, 0usize, 0usize);
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphabetic() {
+            latin += 1;
+        } else if ('\u{0400}'..='\u{04FF}').contains(&c) {
+            cyrillic += 1;
+            if UKRAINIAN_ONLY.contains(&c) {
+                ua += 1;
+            } else if RUSSIAN_ONLY.contains(&c) {
+                ru += 1;
+            }
+        }
+    }
+    if latin > cyrillic {
+        return Script::Latin;
+    }
+    match (ua > 0, ru > 0) {
+        (true, true) => Script::Mixed,
+        (true, false) => Script::Ukrainian,
+        (false, true) => Script::Russian,
+        (false, false) => Script::Undetermined,
+    }
+}
+
+/// Message counts per [`Script`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptCounts {
+    counts: [usize; Script::ALL.len()],
+}
+
+impl ScriptCounts {
+    pub fn add(&mut self, script: Script) {
+        self.counts[script as usize] += 1;
+    }
+
+    pub fn get(&self, script: Script) -> usize {
+        self.counts[script as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// "UA 72% · RU 18% · mixed 6%"; scripts without messages are left out.
+    pub fn describe(&self) -> String {
+        let total = self.total().max(1) as f64;
+        Script::ALL
+            .iter()
+            .filter(|s| self.get(**s) > 0)
+            .map(|s| format!("{} {:.0}%", s.label(), 100.0 * self.get(*s) as f64 / total))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_script_tells_ukrainian_from_russian() {
+        assert_eq!(detect_script("Шахеди на Київщину"), Script::Ukrainian);
+        assert_eq!(
+            detect_script("Шахеды летят на Харьков, ещё 3"),
+            Script::Russian
+        );
+        assert_eq!(detect_script("Шахеды на Київ"), Script::Mixed);
+        assert_eq!(
+            detect_script("Shahed drones heading to Київ"),
+            Script::Latin
+        );
+        assert_eq!(detect_script("Дрони на Харків"), Script::Ukrainian);
+        assert_eq!(detect_script("Курс на Суми"), Script::Undetermined);
+        assert_eq!(detect_script("🚀 12:40"), Script::Undetermined);
+
+        let mut counts = ScriptCounts::default();
+        for text in ["Ракета на Київ", "Загроза балістики", "Ракета летит"]
+        {
+            counts.add(detect_script(text));
+        }
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.describe(), "UA 67% · ? 33%");
+    }
+}
@@ -0,0 +1,123 @@
+//! Near-duplicate texts.  Channels repost each other almost verbatim, with
+//! their own emoji, signature or link; the repost may detect a slightly
+//! different threat combination and so slip past dedup by threat kind.
+//! Texts are compared by Jaccard similarity of their character trigrams
+//! after dropping links, mentions, hashtags and everything but letters and
+//! digits.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Shorter texts ("Увага!", "Шахеди на Київ") say too little to tell a
+/// repost from an independent report.
+const MIN_CHARS: usize = 40;
+
+/// Forwarded texts remembered.
+const MAX_TEXTS: usize = 50;
+
+/// Hashed character trigrams of `lower`'s letters, digits and single
+/// spaces, links and tags left out; `None` when too short to compare.
+pub fn shingles(lower: &str) -> Option<HashSet<u64>> {
+    let mut normalized = String::new();
+    for word in lower
+        .split_whitespace()
+        .filter(|t| !(t.contains("://") || t.contains("t.me/") || t.starts_with(['@', '#'])))
+        .flat_map(|t| t.split(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+    {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+    let chars: Vec<char> = normalized.chars().collect();
+    if chars.len() < MIN_CHARS {
+        return None;
+    }
+    Some(
+        chars
+            .windows(3)
+            .map(|w| {
+                let mut h = DefaultHasher::new();
+                w.hash(&mut h);
+                h.finish()
+            })
+            .collect(),
+    )
+}
+
+pub fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+struct Sent {
+    at: Instant,
+    channel_id: i64,
+    shingles: HashSet<u64>,
+}
+
+/// Recently forwarded texts.
+pub struct RecentTexts {
+    /// Similarity from which a text is a repost; 0 disables.
+    threshold: f64,
+    sent: VecDeque<Sent>,
+}
+
+impl RecentTexts {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Whether `lower` from `channel_id` reposts a text another channel
+    /// had forwarded within `window`.  A channel repeating itself is left
+    /// to the re-alert rules.
+    pub fn is_repost(
+        &mut self,
+        channel_id: i64,
+        lower: &str,
+        window: Duration,
+        now: Instant,
+    ) -> bool {
+        if self.threshold <= 0.0 {
+            return false;
+        }
+        self.sent.retain(|s| now.duration_since(s.at) < window);
+        let Some(shingles) = shingles(lower) else {
+            return false;
+        };
+        self.sent.iter().any(|s| {
+            s.channel_id != channel_id && jaccard(&s.shingles, &shingles) >= self.threshold
+        })
+    }
+
+    /// `lower` from `channel_id` was forwarded.
+    pub fn record(&mut self, channel_id: i64, lower: &str, now: Instant) {
+        if self.threshold <= 0.0 {
+            return;
+        }
+        let Some(shingles) = shingles(lower) else {
+            return;
+        };
+        if self.sent.len() == MAX_TEXTS {
+            self.sent.pop_front();
+        }
+        self.sent.push_back(Sent {
+            at: now,
+            channel_id,
+            shingles,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.sent.clear();
+    }
+}
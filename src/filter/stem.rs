@@ -0,0 +1,37 @@
+//! Light Ukrainian / Russian stemmer.  Strips one inflectional ending
+//! (case, number, adjective agreement), so "дронами", "дронів", "дронов" and
+//! "дрон" all reduce to "дрон" and a keyword can be listed once in its base
+//! form (see [`crate::filter::threat_keywords::THREAT_WORDS`]).  It is
+//! deliberately crude: no derivational suffixes, no stress or alternation
+//! rules – enough for the short nouns and abbreviations that substring
+//! stems cannot match safely.
+
+/// Endings stripped by [`stem`], UA and RU mixed, tried in order: three
+/// letters, then two, then one.
+const ENDINGS: &[&str] = &[
+    "ами", "ями", "ові", "еві", "єві", "ого", "его", "ому", "ему", "ими", "іми", "ыми", "ах", "ях",
+    "ів", "їв", "ов", "ев", "ою", "ею", "єю", "ом", "ем", "ам", "ям", "ой", "ей", "ий", "ій", "ый",
+    "их", "іх", "ых", "им", "ім", "ым", "ая", "яя", "ое", "ее", "ую", "юю", "ої", "ія", "ие", "ия",
+    "а", "я", "у", "ю", "і", "ї", "и", "ы", "е", "є", "о", "ь", "й",
+];
+
+/// Stems keep at least this many letters, so "ту" or "су" stay whole.
+const MIN_STEM_CHARS: usize = 3;
+
+/// `word` (lowercase) without its inflectional ending.
+pub fn stem(word: &str) -> &str {
+    let len = word.chars().count();
+    ENDINGS
+        .iter()
+        .filter(|ending| len >= MIN_STEM_CHARS + ending.chars().count())
+        .find_map(|ending| word.strip_suffix(ending))
+        .unwrap_or(word)
+}
+
+/// Words of `lower`: runs of letters and digits, apostrophes included
+/// ("пʼять").
+pub fn words(lower: &str) -> impl Iterator<Item = &str> {
+    lower
+        .split(|c: char| !c.is_alphanumeric() && !matches!(c, '\'' | 'ʼ' | '’'))
+        .filter(|w| !w.is_empty())
+}
@@ -1,8 +1,17 @@
 use crate::filter::ThreatKind;
+use crate::filter::boundary::{self, Boundary};
+use crate::filter::stem::{stem, words};
+use aho_corasick::AhoCorasick;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
 
 /// Keyword stems for each threat kind.  **Order matters** – more specific
 /// variants must appear before generic ones so that the first match wins
-/// during detection.
+/// during detection.  Stems match at the start of a word; see
+/// [`boundary`] for the `=` (whole word) and `*` (anywhere) prefixes.
 ///
 /// Each entry contains **both Ukrainian (UA) and Russian (RU)** stems.
 pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
@@ -61,8 +70,6 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "межконтинентальн", // межконтинентальная
             // missile names / designations
             "iskander",
-            "кедр", // Kedr missile
-            "kedr",
             "рс-26",
             "rs-26", // RS-26 Rubezh
             "рубіж", // UA: Rubizh
@@ -129,21 +136,13 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "каб-500",
             "каб-1500",
             "каб-250",
-            "каб ",  // "КАБ " with trailing space
-            "каб,",  // "КАБ," punctuation variant
-            "каб.",  // "КАБ." end of sentence
-            "каб\n", // "КАБ" at end of line
-            "умпб",  // УМПБ (unified modular glide bomb)
-            "умпк",  // УМПК (glide kit)
+            "умпб", // УМПБ (unified modular glide bomb)
+            "умпк", // УМПК (glide kit)
             "jdam",
             "фаб-500",
             "фаб-1500",
             "фаб-250",
             "фаб-3000",
-            "фаб ", // "ФАБ " with trailing space
-            "фаб,",
-            "фаб.",
-            "фаб\n",
         ],
     ),
     // ── Shahed / attack drone ──────────────────────────────────────────
@@ -166,9 +165,6 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "беспилотник", // RU: generic UAV
             "mohajer",     // Iranian Mohajer
             "мохаджер",
-            "дрон ",  // "дрон " (with space to reduce false positives)
-            "дронів", // UA genitive plural
-            "дронов", // RU genitive plural
             "махаон", // newer Russian drone names
         ],
     ),
@@ -247,6 +243,39 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "зенитн ракет", // RU
         ],
     ),
+    // ── Artillery / MLRS shelling (frontline cities) ────────────────────
+    (
+        ThreatKind::Artillery,
+        &[
+            // UA
+            "рсзв",       // реактивна система залпового вогню
+            "артилері",   // артилерія, артилерійський
+            "артобстріл", // артобстріли
+            "міномет",    // мінометний обстріл
+            // RU
+            "рсзо",
+            "артиллери", // артиллерия, артиллерийский
+            "артобстрел",
+            "миномет",
+            // EN
+            "mlrs",
+        ],
+    ),
+    // ── Explosions / impacts (already happened) ─────────────────────────
+    (
+        ThreatKind::Explosion,
+        &[
+            // UA
+            "вибух",   // вибухи, вибухів
+            "прильот", // прильоти
+            "влучанн", // влучання
+            // RU
+            "взрыв",
+            "прилёт",
+            "прилет",
+            "попадани", // попадание
+        ],
+    ),
     // ── Other threat signals (catch-all) ───────────────────────────────
     (
         ThreatKind::Other,
@@ -256,9 +285,6 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "небезпек",
             "тривог", // тривога, тривоги
             "обстріл",
-            "вибух",
-            "прильот",
-            "влучанн",       // влучання
             "уламк",         // уламки (debris / intercept fragments)
             "укриття",       // shelter – "терміново в укриття!"
             "укрытие",       // RU: shelter
@@ -273,10 +299,6 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
             "опасност",
             "тревог", // тревога
             "обстрел",
-            "взрыв",
-            "прилёт",
-            "прилет",
-            "попадани",  // попадание
             "осколк",    // осколки (fragments)
             "пожар",     // fire
             "разрушени", // разрушение (destruction)
@@ -287,6 +309,20 @@ pub const THREAT_KEYWORDS: &[(ThreatKind, &[&str])] = &[
     ),
 ];
 
+// ──────────────────────── Whole-word keywords ────────────────────────────
+
+/// Base forms matched against whole words after [`stem`]ming, for short
+/// names a substring stem would find inside unrelated words ("каб" in
+/// "кабінет", "кедр" in "кедрова").  Any inflection of the listed form
+/// matches: "дрон" covers "дрони", "дронів", "дронами", "дронов".
+pub const THREAT_WORDS: &[(ThreatKind, &[&str])] = &[
+    (ThreatKind::Ballistic, &["кедр", "kedr"]),
+    (ThreatKind::GuidedBomb, &["каб", "фаб"]),
+    (ThreatKind::Shahed, &["дрон"]),
+    // "Град" (BM-21), "арта" / "арти" / "арту" – not "градус", "артист".
+    (ThreatKind::Artillery, &["град", "арт"]),
+];
+
 // ───────────────────────── Urgency keywords ──────────────────────────────
 
 /// Keywords that signal "this is a repeated / additional wave" and should
@@ -316,7 +352,57 @@ pub const URGENCY_KEYWORDS: &[&str] = &[
 /// Returns `true` when the message contains an urgency keyword that should
 /// bypass dedup.
 pub fn is_urgent(lower: &str) -> bool {
-    URGENCY_KEYWORDS.iter().any(|kw| lower.contains(kw))
+    active().is_urgent(lower)
+}
+
+/// Cheap pre-check for ballistic / hypersonic stems, used to prioritize a
+/// message before it goes through full classification.
+pub fn mentions_critical_threat(lower: &str) -> bool {
+    active()
+        .scan(lower)
+        .threats
+        .iter()
+        .any(|kind| matches!(kind, ThreatKind::Ballistic | ThreatKind::Hypersonic))
+}
+
+// ───────────────────── Damage / impact reports ───────────────────────────
+
+/// Stems that describe the *consequences* of a strike (impact, damage,
+/// casualties).  Used to correlate follow-up reports with the preceding
+/// alert wave, not to raise alerts.
+pub const DAMAGE_KEYWORDS: &[&str] = &[
+    // UA
+    "влучанн",   // влучання в енергооб'єкт
+    "пошкоджен", // пошкоджено, пошкодження
+    "руйнуванн",
+    "зруйнов",
+    "пожеж",
+    "загибл", // загиблі
+    "постраждал",
+    "поранен",
+    "знеструмлен", // знеструмлення after energy-facility hit
+    // RU
+    "попадани",
+    "поврежден",
+    "разрушени",
+    "пожар",
+    "погибш",
+    "пострадал",
+    // ранен / ранена / ранено, ранены / раненых, ранение – not "раненько"
+    "=ранен",
+    "=ранена",
+    "=ранено",
+    "ранены",
+    "ранени",
+    "обесточ",
+];
+
+/// Returns `true` when the message reports strike consequences.
+pub fn is_damage_report(lower: &str) -> bool {
+    DAMAGE_KEYWORDS.iter().any(|entry| {
+        let (bound, kw) = Boundary::parse(entry);
+        boundary::contains(lower, kw, bound)
+    })
 }
 
 // ───────────────────── Nationwide alert detection ────────────────────────
@@ -339,3 +425,457 @@ pub const NATIONWIDE_KEYWORDS: &[&str] = &[
     "всей украине",
     "по всей стране",
 ];
+
+// ───────────────────────── Suppression keywords ──────────────────────────
+
+/// A comma-separated stem list, e.g. user stems that drop a message before
+/// detection (`SUPPRESS_KEYWORDS`: fundraising posts, "підсумки доби",
+/// channel self-promo; none are built in).  `None` when no stem is left
+/// after trimming, so an empty variable counts as unset.
+pub fn parse_list(value: &str) -> Option<Vec<String>> {
+    let list = normalize(value.split(',').map(str::to_string).collect());
+    (!list.is_empty()).then_some(list)
+}
+
+fn env_list(name: &str) -> Option<Vec<String>> {
+    std::env::var(name).ok().as_deref().and_then(parse_list)
+}
+
+/// Returns `true` when the message matches a suppression stem and must be
+/// dropped before detection.
+pub fn is_suppressed(lower: &str) -> bool {
+    active().scan(lower).suppressed
+}
+
+// ───────────────────────── Runtime keyword set ───────────────────────────
+
+/// Keyword lists the filter actually uses: the built-in tables above,
+/// optionally overridden by a `KEYWORDS_PATH` file that live mode reloads
+/// when it changes.  All stems are compiled into one Aho-Corasick
+/// automaton, so classifying a message is a single pass over its text
+/// instead of a `contains` per stem, and each match is checked against its
+/// stem's [`Boundary`]; whole-word keywords are looked up by the stem of
+/// each word.
+#[derive(Debug, Clone)]
+pub struct KeywordSet {
+    /// Same order as [`THREAT_KEYWORDS`]; kinds without built-in stems are
+    /// appended.
+    threats: Vec<(ThreatKind, Vec<String>)>,
+    /// Base forms, see [`THREAT_WORDS`].
+    words: Vec<(ThreatKind, Vec<String>)>,
+    urgency: Vec<String>,
+    nationwide: Vec<String>,
+    /// Stems that drop a message outright.
+    suppress: Vec<String>,
+    matcher: AhoCorasick,
+    /// Lists each automaton pattern belongs to, by pattern id (a stem may
+    /// be in several).
+    pattern_lists: Vec<Vec<StemList>>,
+    /// Where each automaton pattern may match, by pattern id.
+    pattern_bounds: Vec<Boundary>,
+    /// Stemmed base form → indices into `threats`.
+    word_threats: HashMap<String, Vec<usize>>,
+}
+
+/// A list of a [`KeywordSet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StemList {
+    /// Index into `KeywordSet::threats`.
+    Threat(usize),
+    Urgency,
+    Nationwide,
+    Suppress,
+}
+
+/// What a message matched, see [`KeywordSet::scan`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeywordHits {
+    /// In keyword-table order, each kind once.
+    pub threats: Vec<ThreatKind>,
+    pub urgent: bool,
+    pub nationwide: bool,
+    /// A suppression stem matched.
+    pub suppressed: bool,
+}
+
+/// `KEYWORDS_PATH` file layout (TOML).  A list given at the top level
+/// replaces the built-in one, a list under `[extra]` is appended to it;
+/// lists the file leaves out keep the built-ins.  Stems take the
+/// [`boundary`] prefixes (`=кр` whole word, `*авіа` anywhere):
+///
+/// ```toml
+/// urgency = ["повторн", "ще ціл", "срочно"]
+/// suppress = ["підсумки доби", "збір на", "=донат"]
+///
+/// [threats]
+/// Aircraft = ["міг-31", "миг-31", "ту-95"]
+///
+/// [extra.threats]
+/// Shahed = ["мопед"]
+/// CruiseMissile = ["=кр"]
+///
+/// [extra.words]
+/// GuidedBomb = ["умпб"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeywordFile {
+    #[serde(default)]
+    threats: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    words: BTreeMap<String, Vec<String>>,
+    urgency: Option<Vec<String>>,
+    nationwide: Option<Vec<String>>,
+    suppress: Option<Vec<String>>,
+    #[serde(default)]
+    extra: KeywordExtra,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeywordExtra {
+    #[serde(default)]
+    threats: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    words: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    urgency: Vec<String>,
+    #[serde(default)]
+    nationwide: Vec<String>,
+    #[serde(default)]
+    suppress: Vec<String>,
+}
+
+/// Stems are matched against lowercased text; blank ones would match
+/// everything.
+fn normalize(list: Vec<String>) -> Vec<String> {
+    list.into_iter()
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Apply a file's per-kind lists to `lists`: `replace` ones take the place
+/// of the built-ins, `extra` ones are appended.
+fn merge_kinds(
+    lists: &mut Vec<(ThreatKind, Vec<String>)>,
+    replace: BTreeMap<String, Vec<String>>,
+    extra: BTreeMap<String, Vec<String>>,
+) -> Result<()> {
+    let tagged = replace
+        .into_iter()
+        .map(|(name, stems)| (name, stems, true))
+        .chain(extra.into_iter().map(|(name, stems)| (name, stems, false)));
+    for (name, stems, replace) in tagged {
+        let kind = ThreatKind::from_variant_name(&name)
+            .ok_or_else(|| anyhow!("unknown threat kind {name:?}"))?;
+        let stems = normalize(stems);
+        match lists.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, list)) if replace => *list = stems,
+            Some((_, list)) => list.extend(stems),
+            None => lists.push((kind, stems)),
+        }
+    }
+    Ok(())
+}
+
+impl KeywordSet {
+    fn new(
+        mut threats: Vec<(ThreatKind, Vec<String>)>,
+        words: Vec<(ThreatKind, Vec<String>)>,
+        urgency: Vec<String>,
+        nationwide: Vec<String>,
+        suppress: Vec<String>,
+    ) -> Result<Self> {
+        let mut word_threats: HashMap<String, Vec<usize>> = HashMap::new();
+        for (kind, forms) in &words {
+            let idx = match threats.iter().position(|(k, _)| k == kind) {
+                Some(idx) => idx,
+                None => {
+                    threats.push((*kind, Vec::new()));
+                    threats.len() - 1
+                }
+            };
+            for form in forms {
+                let indices = word_threats.entry(stem(form).to_string()).or_default();
+                if !indices.contains(&idx) {
+                    indices.push(idx);
+                }
+            }
+        }
+
+        let mut patterns: Vec<&str> = Vec::new();
+        let mut pattern_lists: Vec<Vec<StemList>> = Vec::new();
+        let mut pattern_bounds: Vec<Boundary> = Vec::new();
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let tagged = threats
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, (_, stems))| stems.iter().map(move |s| (s, StemList::Threat(idx))))
+            .chain(urgency.iter().map(|s| (s, StemList::Urgency)))
+            .chain(nationwide.iter().map(|s| (s, StemList::Nationwide)))
+            .chain(suppress.iter().map(|s| (s, StemList::Suppress)));
+        for (entry, list) in tagged {
+            let (bound, pattern) = Boundary::parse(entry);
+            if pattern.is_empty() {
+                continue;
+            }
+            let id = *ids.entry(entry.as_str()).or_insert_with(|| {
+                patterns.push(pattern);
+                pattern_lists.push(Vec::new());
+                pattern_bounds.push(bound);
+                patterns.len() - 1
+            });
+            if !pattern_lists[id].contains(&list) {
+                pattern_lists[id].push(list);
+            }
+        }
+        let matcher = AhoCorasick::new(&patterns).context("cannot build keyword matcher")?;
+        Ok(Self {
+            threats,
+            words,
+            urgency,
+            nationwide,
+            suppress,
+            matcher,
+            pattern_lists,
+            pattern_bounds,
+            word_threats,
+        })
+    }
+
+    /// The built-in tables.
+    pub fn builtin() -> Self {
+        let owned = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        let table = |table: &[(ThreatKind, &[&str])]| {
+            table
+                .iter()
+                .map(|(kind, stems)| (*kind, owned(stems)))
+                .collect()
+        };
+        Self::new(
+            table(THREAT_KEYWORDS),
+            table(THREAT_WORDS),
+            owned(URGENCY_KEYWORDS),
+            owned(NATIONWIDE_KEYWORDS),
+            Vec::new(),
+        )
+        .expect("built-in keyword lists compile")
+    }
+
+    /// The built-ins with the environment's lists applied.
+    ///
+    /// | Env var                  | Description                                          |
+    /// |--------------------------|------------------------------------------------------|
+    /// | `URGENCY_KEYWORDS`       | Replaces the urgency stems                           |
+    /// | `EXTRA_URGENCY_KEYWORDS` | Adds to them ("ще груп,друга хвил")                  |
+    /// | `SUPPRESS_KEYWORDS`      | The suppression list                                 |
+    ///
+    /// All are comma-separated; an empty value is the same as unset.
+    pub fn from_env() -> Self {
+        Self::builtin()
+            .with_lists(
+                env_list("URGENCY_KEYWORDS"),
+                env_list("EXTRA_URGENCY_KEYWORDS").unwrap_or_default(),
+                env_list("SUPPRESS_KEYWORDS"),
+            )
+            .expect("keyword lists from the environment compile")
+    }
+
+    /// `urgency` and `suppress` replace those lists when given,
+    /// `extra_urgency` is appended to the urgency stems.
+    pub fn with_lists(
+        self,
+        urgency: Option<Vec<String>>,
+        extra_urgency: Vec<String>,
+        suppress: Option<Vec<String>>,
+    ) -> Result<Self> {
+        let urgency = urgency
+            .unwrap_or(self.urgency)
+            .into_iter()
+            .chain(extra_urgency)
+            .collect();
+        Self::new(
+            self.threats,
+            self.words,
+            urgency,
+            self.nationwide,
+            suppress.unwrap_or(self.suppress),
+        )
+    }
+
+    /// The built-ins with a `KEYWORDS_PATH` file's lists applied.
+    pub fn from_toml(data: &str) -> Result<Self> {
+        Self::builtin().with_toml(data)
+    }
+
+    /// `self` with a `KEYWORDS_PATH` file's lists applied.
+    pub fn with_toml(self, data: &str) -> Result<Self> {
+        let file: KeywordFile = toml::from_str(data)?;
+        let Self {
+            mut threats,
+            mut words,
+            mut urgency,
+            mut nationwide,
+            mut suppress,
+            ..
+        } = self;
+        merge_kinds(&mut threats, file.threats, file.extra.threats)?;
+        merge_kinds(&mut words, file.words, file.extra.words)?;
+        if let Some(list) = file.urgency {
+            urgency = normalize(list);
+        }
+        urgency.extend(normalize(file.extra.urgency));
+        if let Some(list) = file.nationwide {
+            nationwide = normalize(list);
+        }
+        nationwide.extend(normalize(file.extra.nationwide));
+        if let Some(list) = file.suppress {
+            suppress = normalize(list);
+        }
+        suppress.extend(normalize(file.extra.suppress));
+        Self::new(threats, words, urgency, nationwide, suppress)
+    }
+
+    pub fn threats(&self) -> &[(ThreatKind, Vec<String>)] {
+        &self.threats
+    }
+
+    pub fn words(&self) -> &[(ThreatKind, Vec<String>)] {
+        &self.words
+    }
+
+    pub fn urgency(&self) -> &[String] {
+        &self.urgency
+    }
+
+    pub fn nationwide(&self) -> &[String] {
+        &self.nationwide
+    }
+
+    pub fn suppress(&self) -> &[String] {
+        &self.suppress
+    }
+
+    /// Every list `lower` has a stem or word form of, in one pass.
+    pub fn scan(&self, lower: &str) -> KeywordHits {
+        let mut threat_hit = vec![false; self.threats.len()];
+        let mut hits = KeywordHits::default();
+        for m in self.matcher.find_overlapping_iter(lower) {
+            let id = m.pattern().as_usize();
+            if !self.pattern_bounds[id].accepts(lower, m.start(), m.end()) {
+                continue;
+            }
+            for list in &self.pattern_lists[id] {
+                match list {
+                    StemList::Threat(idx) => threat_hit[*idx] = true,
+                    StemList::Urgency => hits.urgent = true,
+                    StemList::Nationwide => hits.nationwide = true,
+                    StemList::Suppress => hits.suppressed = true,
+                }
+            }
+        }
+        if !self.word_threats.is_empty() {
+            for word in words(lower) {
+                for idx in self.word_threats.get(stem(word)).into_iter().flatten() {
+                    threat_hit[*idx] = true;
+                }
+            }
+        }
+        hits.threats = self
+            .threats
+            .iter()
+            .zip(threat_hit)
+            .filter(|(_, hit)| *hit)
+            .map(|((kind, _), _)| *kind)
+            .collect();
+        hits
+    }
+
+    /// Stems over all lists.
+    pub fn stem_count(&self) -> usize {
+        self.threats
+            .iter()
+            .chain(&self.words)
+            .map(|(_, stems)| stems.len())
+            .sum::<usize>()
+            + self.urgency.len()
+            + self.nationwide.len()
+            + self.suppress.len()
+    }
+
+    pub fn is_urgent(&self, lower: &str) -> bool {
+        self.scan(lower).urgent
+    }
+
+    pub fn is_nationwide(&self, lower: &str) -> bool {
+        self.scan(lower).nationwide
+    }
+}
+
+fn active_slot() -> &'static RwLock<Arc<KeywordSet>> {
+    static ACTIVE: OnceLock<RwLock<Arc<KeywordSet>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| RwLock::new(Arc::new(KeywordSet::from_env())))
+}
+
+/// The keyword set in effect ([`KeywordSet::from_env`] until a file is
+/// installed).
+pub fn active() -> Arc<KeywordSet> {
+    active_slot().read().unwrap().clone()
+}
+
+/// Replace the keyword set for every message classified from now on.
+pub fn install(set: KeywordSet) {
+    *active_slot().write().unwrap() = Arc::new(set);
+}
+
+/// `KEYWORDS_PATH` file, reinstalled whenever its modification time changes.
+pub struct KeywordsFile {
+    pub path: String,
+    /// How often live mode checks the file; `None` = load once.
+    pub reload_every: Option<Duration>,
+    modified: Option<SystemTime>,
+}
+
+impl KeywordsFile {
+    /// `None` unless `KEYWORDS_PATH` is set.
+    ///
+    /// | Env var                | Default | Description                                  |
+    /// |------------------------|---------|----------------------------------------------|
+    /// | `KEYWORDS_PATH`        | —       | TOML file overriding the keyword lists       |
+    /// | `KEYWORDS_RELOAD_SECS` | `30`    | How often live mode checks it (0 = never)    |
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("KEYWORDS_PATH")
+            .ok()
+            .filter(|p| !p.trim().is_empty())?;
+        let secs: u64 = std::env::var("KEYWORDS_RELOAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        Some(Self {
+            path,
+            reload_every: (secs > 0).then(|| Duration::from_secs(secs)),
+            modified: None,
+        })
+    }
+
+    /// Load and install the file unless it is unchanged since the last
+    /// call; returns the installed set.  On error the active set is kept
+    /// and the same file version is not retried.
+    pub fn refresh(&mut self) -> Result<Option<Arc<KeywordSet>>> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .with_context(|| format!("cannot stat {}", self.path))?;
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        self.modified = Some(modified);
+        let data = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("cannot read {}", self.path))?;
+        let set = KeywordSet::from_env()
+            .with_toml(&data)
+            .with_context(|| format!("bad {}", self.path))?;
+        install(set);
+        Ok(Some(active()))
+    }
+}
@@ -11,8 +11,12 @@ pub enum ThreatKind {
     Shahed,
     ReconDrone,
     Aircraft,
-    AllClear, // "відбій" / "отбой" – threat over
-    Other,    // threat-sounding but unclassified
+    Artillery,      // РСЗВ / Град / арта – shelling of frontline cities
+    BallisticWatch, // зліт МіГ-31К – Kinzhal carrier airborne, nothing launched yet
+    NavalThreat,    // носії Калібрів у морі – missile carriers deployed, nothing launched yet
+    Explosion,      // вибухи / прильот / влучання – an impact that already happened
+    AllClear,       // "відбій" / "отбой" – threat over
+    Other,          // threat-sounding but unclassified
 }
 
 impl ThreatKind {
@@ -26,6 +30,10 @@ impl ThreatKind {
             Self::Shahed => "🔺",
             Self::ReconDrone => "🛸",
             Self::Aircraft => "✈️",
+            Self::Artillery => "💢",
+            Self::BallisticWatch => "🛫",
+            Self::NavalThreat => "🚢",
+            Self::Explosion => "💥",
             Self::AllClear => "✅",
             Self::Other => "⚠️",
         }
@@ -41,6 +49,10 @@ impl ThreatKind {
             Self::Shahed => "Шахед / дрон",
             Self::ReconDrone => "Розвідувальний БПЛА",
             Self::Aircraft => "Авіація",
+            Self::Artillery => "Артобстріл / РСЗВ",
+            Self::BallisticWatch => "Зліт МіГ-31К",
+            Self::NavalThreat => "Носії «Калібрів» у морі",
+            Self::Explosion => "Вибухи / влучання",
             Self::AllClear => "Відбій загрози",
             Self::Other => "Загроза",
         }
@@ -57,6 +69,10 @@ impl ThreatKind {
             Self::Shahed => 3,
             Self::ReconDrone => 2,
             Self::Aircraft => 2,
+            Self::Artillery => 3,
+            Self::BallisticWatch => 3,
+            Self::NavalThreat => 3,
+            Self::Explosion => 1,
             Self::AllClear => 6, // always most important
             Self::Other => 0,
         }
@@ -73,6 +89,10 @@ impl ThreatKind {
             Self::Shahed => "Shahed",
             Self::ReconDrone => "ReconDrone",
             Self::Aircraft => "Aircraft",
+            Self::Artillery => "Artillery",
+            Self::BallisticWatch => "BallisticWatch",
+            Self::NavalThreat => "NavalThreat",
+            Self::Explosion => "Explosion",
             Self::AllClear => "AllClear",
             Self::Other => "Other",
         }
@@ -89,6 +109,10 @@ impl ThreatKind {
             "shahed" => Some(Self::Shahed),
             "recondrone" | "recon_drone" => Some(Self::ReconDrone),
             "aircraft" => Some(Self::Aircraft),
+            "artillery" | "mlrs" => Some(Self::Artillery),
+            "ballisticwatch" | "ballistic_watch" => Some(Self::BallisticWatch),
+            "navalthreat" | "naval_threat" | "naval" => Some(Self::NavalThreat),
+            "explosion" | "impact" => Some(Self::Explosion),
             "allclear" | "all_clear" => Some(Self::AllClear),
             "other" => Some(Self::Other),
             _ => None,
@@ -0,0 +1,207 @@
+// ───────────────────────────── Threat level ──────────────────────────────
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{AlertInfo, Proximity};
+
+/// Coarse "current situation" for the configured area, ordered by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ThreatLevel {
+    Clear,
+    Watch,
+    ActiveDrone,
+    ActiveMissile,
+    CriticalBallistic,
+}
+
+impl ThreatLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Clear => "🟢 Спокійно",
+            Self::Watch => "🟡 Увага",
+            Self::ActiveDrone => "🟠 Активна загроза: дрони",
+            Self::ActiveMissile => "🔴 Активна загроза: ракети",
+            Self::CriticalBallistic => "‼️ Критична загроза: балістика",
+        }
+    }
+
+    /// Level implied by a single forwarded alert.
+    pub fn from_alert(info: &AlertInfo) -> Self {
+        if info.threats.contains(&ThreatKind::AllClear) {
+            return Self::Clear;
+        }
+        let level = info
+            .threats
+            .iter()
+            .map(|t| match t {
+                ThreatKind::Ballistic | ThreatKind::Hypersonic => Self::CriticalBallistic,
+                ThreatKind::CruiseMissile
+                | ThreatKind::Missile
+                | ThreatKind::GuidedBomb
+                | ThreatKind::Artillery => Self::ActiveMissile,
+                ThreatKind::Shahed | ThreatKind::ReconDrone => Self::ActiveDrone,
+                ThreatKind::Aircraft
+                | ThreatKind::BallisticWatch
+                | ThreatKind::NavalThreat
+                | ThreatKind::Explosion
+                | ThreatKind::Other
+                | ThreatKind::AllClear => Self::Watch,
+            })
+            .max()
+            // Negative-status updates carry no threats: the wave is calming down.
+            .unwrap_or(Self::Watch);
+        // Threats outside the area (FORWARD_ALL_THREATS) only warrant a watch.
+        if info.proximity == Proximity::None && !info.nationwide {
+            level.min(Self::Watch)
+        } else {
+            level
+        }
+    }
+}
+
+/// The alert that last supported the current level.
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub at: Instant,
+    pub level: ThreatLevel,
+    pub channel_title: String,
+    pub text: String,
+}
+
+/// Tracks the current threat level from forwarded alerts.
+///
+/// Active levels hold for `active_for` after the last supporting alert, then
+/// decay to [`ThreatLevel::Watch`] for another `active_for`, then to
+/// [`ThreatLevel::Clear`].  An all-clear resets immediately.
+///
+/// The first alert for the user's area (or a nationwide one) also starts the
+/// air-raid timer, which runs until the all-clear or until the level decays
+/// back to clear.
+pub struct StatusTracker {
+    entries: Vec<StatusEntry>,
+    last: Option<StatusEntry>,
+    active_for: Duration,
+    alert_since: Option<Instant>,
+}
+
+pub type SharedStatus = Arc<Mutex<StatusTracker>>;
+
+impl StatusTracker {
+    pub fn new(active_for: Duration) -> Self {
+        Self {
+            entries: Vec::new(),
+            last: None,
+            active_for,
+            alert_since: None,
+        }
+    }
+
+    /// | Env var              | Default | Description                              |
+    /// |----------------------|---------|------------------------------------------|
+    /// | `STATUS_ACTIVE_SECS` | `1800`  | How long an alert keeps its level active |
+    pub fn from_env() -> Self {
+        let secs: u64 = std::env::var("STATUS_ACTIVE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1800);
+        Self::new(Duration::from_secs(secs))
+    }
+
+    pub fn shared(self) -> SharedStatus {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Feed a forwarded alert.  Returns the level after the update.
+    pub fn record(&mut self, info: &AlertInfo, channel_title: &str, text: &str) -> ThreatLevel {
+        let entry = StatusEntry {
+            at: Instant::now(),
+            level: ThreatLevel::from_alert(info),
+            channel_title: channel_title.to_string(),
+            text: text.to_string(),
+        };
+        if entry.level == ThreatLevel::Clear {
+            self.entries.clear();
+            self.alert_since = None;
+        } else {
+            let local = info.proximity != Proximity::None || info.nationwide;
+            if local && !info.threats.is_empty() && self.alert_since.is_none() {
+                self.alert_since = Some(entry.at);
+            }
+            self.entries.push(entry.clone());
+        }
+        self.last = Some(entry);
+        self.current().0
+    }
+
+    /// Current level and the most recent alert that supports it.
+    pub fn current(&mut self) -> (ThreatLevel, Option<&StatusEntry>) {
+        let now = Instant::now();
+        let active_for = self.active_for;
+        self.entries
+            .retain(|e| now.duration_since(e.at) < active_for * 2);
+
+        let level = self
+            .entries
+            .iter()
+            .map(|e| {
+                if now.duration_since(e.at) < active_for {
+                    e.level
+                } else {
+                    e.level.min(ThreatLevel::Watch)
+                }
+            })
+            .max()
+            .unwrap_or(ThreatLevel::Clear);
+        if level == ThreatLevel::Clear {
+            self.alert_since = None;
+        }
+
+        let supporting = self
+            .entries
+            .iter()
+            .rev()
+            .find(|e| e.level >= level)
+            .or(self.last.as_ref());
+        (level, supporting)
+    }
+
+    /// How long the area has been under alert, if it is.
+    pub fn alert_duration(&mut self) -> Option<Duration> {
+        self.current();
+        self.alert_since.map(|since| since.elapsed())
+    }
+
+    /// "⏱ Тривога триває 1 год 12 хв" once the alert has lasted a minute.
+    pub fn duration_note(&mut self) -> Option<String> {
+        let mins = self.alert_duration()?.as_secs() / 60;
+        (mins > 0).then(|| format!("⏱ Тривога {}", format_duration(mins)))
+    }
+
+    /// Render the `/now` reply.
+    pub fn describe(&mut self) -> String {
+        let note = self.duration_note();
+        let (level, entry) = self.current();
+        let mut out = format!("{}\n", level.label());
+        if let Some(note) = note {
+            out.push_str(&format!("{note}\n"));
+        }
+        if let Some(entry) = entry {
+            let mins = entry.at.elapsed().as_secs() / 60;
+            out.push_str("———\n");
+            out.push_str(&entry.text);
+            out.push_str(&format!("\n— 📡 {} · {mins} хв тому", entry.channel_title));
+        }
+        out
+    }
+}
+
+/// "триває 1 год 12 хв" / "триває 12 хв".
+pub fn format_duration(mins: u64) -> String {
+    match (mins / 60, mins % 60) {
+        (0, m) => format!("триває {m} хв"),
+        (h, 0) => format!("триває {h} год"),
+        (h, m) => format!("триває {h} год {m} хв"),
+    }
+}
@@ -0,0 +1,179 @@
+//! Drone-group tracks.  Local channels follow a group through successive
+//! posts ("група з 6 БпЛА курсом на Бровари", "6 шахедів через Бориспіль");
+//! messages naming the same group size are linked into one track so alerts
+//! can show where the group has been and whether it is turning away.
+
+use std::time::{Duration, Instant};
+
+use crate::filter::Proximity;
+use crate::filter::corridor::{capitalize, same_place, waypoint_key};
+
+/// Stems that mark the counted noun as a drone.
+const DRONE_STEMS: &[&str] = &["бпла", "шахед", "дрон", "мопед", "герань", "ударн"];
+
+/// Live tracks kept at once.
+const MAX_TRACKS: usize = 20;
+
+/// Points kept per track (oldest dropped).
+const MAX_POINTS: usize = 12;
+
+fn leading_number(token: &str) -> Option<u32> {
+    let digits: String = token
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok().filter(|n| (1..=200).contains(n))
+}
+
+/// Group size from "група з 6 БпЛА", "группа из 6", "6 шахедів", "6х БпЛА".
+pub fn group_size(lower: &str) -> Option<u32> {
+    let tokens: Vec<&str> = lower.split_whitespace().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.starts_with("груп") {
+            // "група з 6", "группа из 6-ти"
+            if let Some(n) = tokens
+                .iter()
+                .skip(i + 1)
+                .take(2)
+                .find_map(|t| leading_number(t))
+            {
+                return Some(n);
+            }
+        }
+        if token.starts_with(|c: char| c.is_ascii_digit())
+            && token
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start_matches(['х', 'x', '-'])
+                .chars()
+                .all(|c| !c.is_alphanumeric())
+            && tokens
+                .iter()
+                .skip(i + 1)
+                .take(2)
+                .any(|t| DRONE_STEMS.iter().any(|s| t.starts_with(s)))
+        {
+            return leading_number(token);
+        }
+    }
+    None
+}
+
+struct Track {
+    size: u32,
+    /// Places in the order the group passed them.
+    points: Vec<String>,
+    last_proximity: Proximity,
+    last_seen: Instant,
+}
+
+/// What one message did to its group's track.
+#[derive(Debug, Clone)]
+pub struct TrackUpdate {
+    pub size: u32,
+    /// Places the group passed through, oldest first.
+    pub history: Vec<String>,
+    /// The message moved the group to a new waypoint farther from the user
+    /// than the previous one.
+    pub moving_away: bool,
+}
+
+impl TrackUpdate {
+    /// Alert footer line, or `None` while the track has a single point.
+    pub fn render(&self) -> Option<String> {
+        (self.history.len() >= 2).then(|| {
+            format!(
+                "🛤 Маршрут групи ({}): {}",
+                self.size,
+                self.history.join(" → ")
+            )
+        })
+    }
+}
+
+/// Tracks of drone groups seen within `window`.
+pub struct ShahedTracks {
+    window: Duration,
+    tracks: Vec<Track>,
+}
+
+impl ShahedTracks {
+    /// A zero `window` disables tracking.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            tracks: Vec::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Link a message about a group of `size` drones, currently at
+    /// `waypoints` (in message order) and `proximity` to the user, to the
+    /// group's track.
+    pub fn observe(
+        &mut self,
+        size: u32,
+        waypoints: &[String],
+        proximity: Proximity,
+        now: Instant,
+    ) -> Option<TrackUpdate> {
+        if self.window.is_zero() {
+            return None;
+        }
+        self.tracks
+            .retain(|t| now.duration_since(t.last_seen) < self.window);
+
+        let idx = match self.tracks.iter().position(|t| t.size == size) {
+            Some(i) => i,
+            None => {
+                if self.tracks.len() >= MAX_TRACKS {
+                    self.tracks.remove(0);
+                }
+                self.tracks.push(Track {
+                    size,
+                    points: Vec::new(),
+                    last_proximity: proximity,
+                    last_seen: now,
+                });
+                self.tracks.len() - 1
+            }
+        };
+        let track = &mut self.tracks[idx];
+
+        let mut moved = false;
+        for place in waypoints {
+            let key = waypoint_key(place);
+            let repeated = track
+                .points
+                .last()
+                .is_some_and(|p| same_place(&waypoint_key(p), &key));
+            if !repeated {
+                track.points.push(place.clone());
+                moved = true;
+            }
+        }
+        if track.points.len() > MAX_POINTS {
+            track.points.drain(..track.points.len() - MAX_POINTS);
+        }
+        let moving_away = moved && proximity < track.last_proximity;
+        track.last_proximity = proximity;
+        track.last_seen = now;
+
+        Some(TrackUpdate {
+            size,
+            history: track.points.iter().map(|p| capitalize(p)).collect(),
+            moving_away,
+        })
+    }
+}
@@ -0,0 +1,135 @@
+//! Arrival estimates.  "Шахеди над Лубнами, курсом на Київ" says where a
+//! threat is and where it heads; once the heading is near the user, the
+//! remaining distance over the kind's typical speed gives an arrival
+//! window.  The last position is remembered per kind, so a later "курсом
+//! на Київ" from another channel still gets an estimate, less the distance
+//! flown since.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::filter::gazetteer::{Place, distance_km};
+use crate::filter::threat_kind::ThreatKind;
+
+/// Closer than this the threat is already overhead: no estimate.
+const MIN_ETA_KM: f64 = 5.0;
+
+/// A heading this close to home counts as heading for the user.
+pub const NEAR_KM: f64 = 30.0;
+
+/// Spread of real speeds around the typical one.
+const SLOW: f64 = 0.8;
+const FAST: f64 = 1.25;
+
+/// Typical cruise speed; `None` for kinds without a trajectory worth
+/// estimating (aviation, bombs, impacts, pre-alerts).
+pub fn typical_speed_kmh(kind: ThreatKind) -> Option<f64> {
+    match kind {
+        ThreatKind::Shahed => Some(185.0),
+        ThreatKind::ReconDrone => Some(120.0),
+        ThreatKind::CruiseMissile | ThreatKind::Missile => Some(800.0),
+        ThreatKind::Ballistic => Some(5000.0),
+        ThreatKind::Hypersonic => Some(7000.0),
+        _ => None,
+    }
+}
+
+struct Fix {
+    place: &'static Place,
+    at: Instant,
+}
+
+/// Expected arrival of a threat at the user's home.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eta {
+    /// Where the threat was last reported.
+    pub from: &'static Place,
+    /// Distance left to home, after what it has flown since.
+    pub km: f64,
+    pub earliest: Duration,
+    pub latest: Duration,
+}
+
+/// Last reported position per threat kind.
+pub struct Trajectories {
+    window: Duration,
+    fixes: HashMap<ThreatKind, Fix>,
+}
+
+impl Trajectories {
+    /// A zero `window` disables estimates.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            fixes: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fixes.clear();
+    }
+
+    /// Remember where a `kind` threat is: the first of `places` (named in
+    /// message order) that is not its `heading`.
+    pub fn observe(
+        &mut self,
+        kind: ThreatKind,
+        places: &[&'static Place],
+        heading: Option<&'static Place>,
+        now: Instant,
+    ) {
+        if self.window.is_zero() || typical_speed_kmh(kind).is_none() {
+            return;
+        }
+        self.fixes
+            .retain(|_, fix| now.duration_since(fix.at) < self.window);
+        if let Some(&place) = places.iter().find(|p| Some(**p) != heading) {
+            self.fixes.insert(kind, Fix { place, at: now });
+        }
+    }
+
+    /// Arrival window of the `kind` threat at `home` (lat, lon) from its
+    /// last position, as of `now`.
+    pub fn eta(&self, kind: ThreatKind, home: (f64, f64), now: Instant) -> Option<Eta> {
+        let speed = typical_speed_kmh(kind)?;
+        let age = |fix: &Fix| now.saturating_duration_since(fix.at);
+        let fix = self.fixes.get(&kind).filter(|fix| age(fix) < self.window)?;
+        let flown = speed * age(fix).as_secs_f64() / 3600.0;
+        let km = distance_km(fix.place.lat, fix.place.lon, home.0, home.1) - flown;
+        if km < MIN_ETA_KM {
+            return None;
+        }
+        let hours = |kmh: f64| Duration::from_secs_f64(km / kmh * 3600.0);
+        Some(Eta {
+            from: fix.place,
+            km,
+            earliest: hours(speed * FAST),
+            latest: hours(speed * SLOW),
+        })
+    }
+}
+
+/// Alert footer line for an arrival estimate: minutes rounded to 5 (to 1
+/// under 10 minutes), hours past two.
+pub fn format_eta(eta: &Eta) -> String {
+    let mins = |d: Duration, up: bool| {
+        let m = d.as_secs_f64() / 60.0;
+        let step = if m < 10.0 { 1.0 } else { 5.0 };
+        let m = if up {
+            (m / step).ceil()
+        } else {
+            (m / step).floor()
+        } * step;
+        (m as u64).max(1)
+    };
+    let (earliest, latest) = (mins(eta.earliest, false), mins(eta.latest, true));
+    let window = if latest >= 120 {
+        format!("{}–{} год", (earliest / 60).max(1), latest.div_ceil(60))
+    } else {
+        format!("{earliest}–{latest} хв")
+    };
+    format!(
+        "⏳ Орієнтовний підліт: {window} (~{:.0} км, останнє місце: {})",
+        eta.km, eta.from.name
+    )
+}
@@ -0,0 +1,122 @@
+//! The attack wave as a whole.  Dedup waves are per threat kind and
+//! direction and expire within minutes; a [`ThreatWave`] spans the whole
+//! attack, from the first threat report to the all-clear (or a long
+//! enough silence), so recaps and statistics can speak about the night
+//! rather than one message.
+
+use std::time::{Duration, Instant};
+
+use crate::filter::Proximity;
+use crate::filter::threat_kind::ThreatKind;
+
+/// One attack: every threat report since the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreatWave {
+    pub started_at: Instant,
+    /// Last threat report of the wave.
+    pub last_seen: Instant,
+    /// Kinds reported, in order of first report.
+    pub kinds: Vec<ThreatKind>,
+    /// Largest count reported per kind ("4 ракети"), in order of first
+    /// report.
+    pub counts: Vec<(ThreatKind, u32)>,
+    /// Oblasts named as targets, in order of first mention.
+    pub regions: Vec<&'static str>,
+    /// Threat reports seen, wherever they were about.
+    pub messages: u32,
+    /// Alerts forwarded to subscribers.
+    pub forwarded: u32,
+    /// Closest proximity reported.
+    pub max_proximity: Proximity,
+}
+
+impl ThreatWave {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started_at: now,
+            last_seen: now,
+            kinds: Vec::new(),
+            counts: Vec::new(),
+            regions: Vec::new(),
+            messages: 0,
+            forwarded: 0,
+            max_proximity: Proximity::None,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.last_seen.duration_since(self.started_at)
+    }
+
+    /// Add a threat report.
+    pub fn observe(
+        &mut self,
+        threats: &[ThreatKind],
+        counts: &[(ThreatKind, u32)],
+        regions: &[&'static str],
+        proximity: Proximity,
+        now: Instant,
+    ) {
+        self.last_seen = now;
+        self.messages += 1;
+        self.max_proximity = self.max_proximity.max(proximity);
+        for kind in threats {
+            if !self.kinds.contains(kind) {
+                self.kinds.push(*kind);
+            }
+        }
+        for &(kind, n) in counts {
+            match self.counts.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, max)) => *max = (*max).max(n),
+                None => self.counts.push((kind, n)),
+            }
+        }
+        for region in regions {
+            if !self.regions.contains(region) {
+                self.regions.push(region);
+            }
+        }
+    }
+
+    /// Recap appended to the all-clear at `now`: "📋 Хвиля тривала 1 год
+    /// 42 хв: Балістика ×2, Шахед / дрон ×12 · надіслано 5 алертів".
+    pub fn summary(&self, now: Instant) -> String {
+        let mins = now.saturating_duration_since(self.started_at).as_secs() / 60;
+        let lasted = match (mins / 60, mins % 60) {
+            (0, m) => format!("{m} хв"),
+            (h, 0) => format!("{h} год"),
+            (h, m) => format!("{h} год {m} хв"),
+        };
+        let kinds: Vec<String> = self
+            .kinds
+            .iter()
+            .filter(|k| !matches!(k, ThreatKind::AllClear | ThreatKind::Other))
+            .map(
+                |k| match self.counts.iter().find(|(c, n)| c == k && *n > 1) {
+                    Some((_, n)) => format!("{} ×{n}", k.label()),
+                    None => k.label().to_string(),
+                },
+            )
+            .collect();
+        let mut out = format!("📋 Хвиля тривала {lasted}");
+        if !kinds.is_empty() {
+            out.push_str(&format!(": {}", kinds.join(", ")));
+        }
+        out.push_str(&format!(
+            " · надіслано {} {}",
+            self.forwarded,
+            alerts_word(self.forwarded)
+        ));
+        out
+    }
+}
+
+/// "алерт" agreeing with `n`: 1 алерт, 3 алерти, 5 алертів, 21 алерт.
+fn alerts_word(n: u32) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=14) => "алертів",
+        (1, _) => "алерт",
+        (2..=4, _) => "алерти",
+        _ => "алертів",
+    }
+}
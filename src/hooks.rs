@@ -0,0 +1,338 @@
+//! External command hooks: user scripts (Python, jq, …) plugged into the
+//! alert pipeline without touching Rust.
+//!
+//! Each configured command runs once per message via `sh -c`, receives a JSON
+//! object on stdin and may print a JSON object on stdout:
+//!
+//! ```json
+//! {"stage": "post_detection",
+//!  "message": {"channel_id": 1, "channel_title": "…", "timestamp": 0, "text": "…"},
+//!  "detection": {"type": "new", "threats": ["Shahed"], "proximity": "city", "nationwide": false, "urgent": false},
+//!  "alert": "…rendered alert or null…"}
+//! ```
+//!
+//! Reply fields are all optional: `{"drop": true}` stops the message,
+//! `"text"` replaces the message text (pre-filter) and `"alert"` replaces the
+//! rendered alert (post-detection, pre-broadcast; a post-detection hook may
+//! also forward a message the filter suppressed).  Empty output leaves the
+//! message unchanged.  Failures and timeouts are logged and ignored, so a
+//! broken hook never blocks alert delivery.
+
+use crate::filter::AlertInfo;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    /// Before the filter; may rewrite or drop the raw message.
+    PreFilter,
+    /// After detection; sees the classification and may change the decision.
+    PostDetection,
+    /// Right before delivery; may rewrite or drop the rendered alert.
+    PreBroadcast,
+}
+
+/// The channel message as seen by hooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookMessage {
+    pub channel_id: i64,
+    pub channel_title: String,
+    pub timestamp: i64,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Detection {
+    #[serde(rename = "type")]
+    alert_type: &'static str,
+    threats: Vec<&'static str>,
+    proximity: &'static str,
+    nationwide: bool,
+    urgent: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct HookInput<'a> {
+    stage: HookStage,
+    message: &'a HookMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detection: Option<Detection>,
+    alert: Option<&'a str>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HookOutput {
+    #[serde(default)]
+    drop: bool,
+    text: Option<String>,
+    alert: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HooksCfg {
+    pub pre_filter: Option<String>,
+    pub post_detection: Option<String>,
+    pub pre_broadcast: Option<String>,
+    pub timeout: Duration,
+}
+
+impl HooksCfg {
+    /// | Env var               | Default | Description                          |
+    /// |-----------------------|---------|--------------------------------------|
+    /// | `HOOK_PRE_FILTER`     | —       | Command run before the filter        |
+    /// | `HOOK_POST_DETECTION` | —       | Command run after detection          |
+    /// | `HOOK_PRE_BROADCAST`  | —       | Command run before delivery          |
+    /// | `HOOK_TIMEOUT_MS`     | `2000`  | Per-invocation timeout               |
+    pub fn from_env() -> Self {
+        let var = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let timeout_ms: u64 = var("HOOK_TIMEOUT_MS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2000);
+        Self {
+            pre_filter: var("HOOK_PRE_FILTER"),
+            post_detection: var("HOOK_POST_DETECTION"),
+            pre_broadcast: var("HOOK_PRE_BROADCAST"),
+            timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+}
+
+/// Runs the configured hooks.  Every method is a no-op for unset stages.
+pub struct Hooks {
+    cfg: HooksCfg,
+}
+
+impl Hooks {
+    pub fn new(cfg: HooksCfg) -> Self {
+        Self { cfg }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(HooksCfg::from_env())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.cfg.pre_filter.is_some()
+            || self.cfg.post_detection.is_some()
+            || self.cfg.pre_broadcast.is_some()
+    }
+
+    /// Returns `false` when the hook drops the message.
+    pub async fn pre_filter(&self, msg: &mut HookMessage) -> bool {
+        let Some(cmd) = &self.cfg.pre_filter else {
+            return true;
+        };
+        let input = HookInput {
+            stage: HookStage::PreFilter,
+            message: msg,
+            detection: None,
+            alert: None,
+        };
+        let Some(out) = self.run(cmd, &input).await else {
+            return true;
+        };
+        if out.drop {
+            return false;
+        }
+        if let Some(text) = out.text {
+            msg.text = text;
+        }
+        true
+    }
+
+    /// `info` is the filter's classification (`None` when suppressed),
+    /// `alert` the rendered alert, which the hook may replace or clear.
+    pub async fn post_detection(
+        &self,
+        msg: &HookMessage,
+        info: Option<&AlertInfo>,
+        alert: &mut Option<String>,
+    ) {
+        let Some(cmd) = &self.cfg.post_detection else {
+            return;
+        };
+        let input = HookInput {
+            stage: HookStage::PostDetection,
+            message: msg,
+            detection: info.map(|i| Detection {
+                alert_type: i.alert_type.name(),
+                threats: i.threats.iter().map(|t| t.variant_name()).collect(),
+                proximity: i.proximity.name(),
+                nationwide: i.nationwide,
+                urgent: i.urgent,
+            }),
+            alert: alert.as_deref(),
+        };
+        let Some(out) = self.run(cmd, &input).await else {
+            return;
+        };
+        if out.drop {
+            *alert = None;
+        } else if out.alert.is_some() {
+            *alert = out.alert;
+        }
+    }
+
+    /// Returns `false` when the hook drops the alert.
+    pub async fn pre_broadcast(&self, msg: &HookMessage, alert: &mut String) -> bool {
+        let Some(cmd) = &self.cfg.pre_broadcast else {
+            return true;
+        };
+        let input = HookInput {
+            stage: HookStage::PreBroadcast,
+            message: msg,
+            detection: None,
+            alert: Some(alert),
+        };
+        let Some(out) = self.run(cmd, &input).await else {
+            return true;
+        };
+        if out.drop {
+            return false;
+        }
+        if let Some(text) = out.alert {
+            *alert = text;
+        }
+        true
+    }
+
+    async fn run(&self, cmd: &str, input: &HookInput<'_>) -> Option<HookOutput> {
+        match tokio::time::timeout(self.cfg.timeout, exec(cmd, input)).await {
+            Ok(Ok(out)) => {
+                debug!("{:?} hook: {out:?}", input.stage);
+                Some(out)
+            }
+            Ok(Err(e)) => {
+                warn!("{:?} hook failed: {e}", input.stage);
+                None
+            }
+            Err(_) => {
+                warn!(
+                    "{:?} hook timed out after {:?}",
+                    input.stage, self.cfg.timeout
+                );
+                None
+            }
+        }
+    }
+}
+
+async fn exec(cmd: &str, input: &HookInput<'_>) -> Result<HookOutput> {
+    let body = serde_json::to_vec(input)?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("hook stdin unavailable"))?;
+    stdin.write_all(&body).await?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!("`{cmd}` exited with {}", output.status));
+    }
+    let raw = String::from_utf8_lossy(&output.stdout);
+    if raw.trim().is_empty() {
+        return Ok(HookOutput::default());
+    }
+    Ok(serde_json::from_str(raw.trim())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn message() -> HookMessage {
+        HookMessage {
+            channel_id: 1,
+            channel_title: "monitor".into(),
+            timestamp: 1_700_000_000,
+            text: "шахед на київ".into(),
+        }
+    }
+
+    fn pre_filter(cmd: &str) -> Hooks {
+        Hooks::new(HooksCfg {
+            pre_filter: Some(cmd.into()),
+            timeout: Duration::from_millis(500),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn hook_can_drop_or_rewrite_the_message() {
+        let mut msg = message();
+        assert!(
+            !pre_filter(r#"cat >/dev/null; echo '{"drop": true}'"#)
+                .pre_filter(&mut msg)
+                .await
+        );
+
+        // The hook sees the message on stdin.
+        let rewrite = r#"grep -q '"stage":"pre_filter"' && echo '{"text": "ракета на київ"}'"#;
+        assert!(pre_filter(rewrite).pre_filter(&mut msg).await);
+        assert_eq!(msg.text, "ракета на київ");
+    }
+
+    #[tokio::test]
+    async fn broken_hook_leaves_the_message_alone() {
+        let started = Instant::now();
+        for cmd in [
+            "cat >/dev/null",
+            "echo '{\"drop\": true}'; exit 3",
+            "echo 'not json'",
+            "sleep 5; echo '{\"drop\": true}'",
+            "/nonexistent/hook",
+        ] {
+            let mut msg = message();
+            assert!(pre_filter(cmd).pre_filter(&mut msg).await, "{cmd}");
+            assert_eq!(msg.text, "шахед на київ", "{cmd}");
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(4),
+            "timeout not enforced"
+        );
+    }
+
+    #[tokio::test]
+    async fn post_detection_hook_replaces_or_clears_the_alert() {
+        let hooks = |cmd: &str| {
+            Hooks::new(HooksCfg {
+                post_detection: Some(cmd.into()),
+                timeout: Duration::from_millis(500),
+                ..Default::default()
+            })
+        };
+        let msg = message();
+        let mut alert = None;
+        hooks(r#"cat >/dev/null; echo '{"alert": "forwarded"}'"#)
+            .post_detection(&msg, None, &mut alert)
+            .await;
+        assert_eq!(alert.as_deref(), Some("forwarded"));
+        hooks("exit 1").post_detection(&msg, None, &mut alert).await;
+        assert_eq!(alert.as_deref(), Some("forwarded"));
+        hooks(r#"cat >/dev/null; echo '{"drop": true}'"#)
+            .post_detection(&msg, None, &mut alert)
+            .await;
+        assert_eq!(alert, None);
+    }
+}
@@ -0,0 +1,30 @@
+//! Telegram OSINT alert relay.  The binary only sets up logging and runs
+//! the selected mode; the modules live here so integration tests under
+//! `tests/` can drive them.
+
+pub mod activity;
+pub mod archive;
+pub mod bot;
+pub mod classifier;
+pub mod config_report;
+pub mod decisions;
+pub mod errors;
+pub mod escalation;
+pub mod feedback;
+pub mod filter;
+pub mod hooks;
+pub mod llm;
+pub mod metrics;
+pub mod modes;
+pub mod monitor;
+pub mod plugins;
+pub mod reminders;
+pub mod retention;
+pub mod review;
+pub mod routing;
+pub mod slo;
+pub mod suggest;
+pub mod summary;
+pub mod telegram;
+pub mod tts;
+pub mod webhook;
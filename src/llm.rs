@@ -33,11 +33,26 @@
 //! LLM_ENDPOINT=http://127.0.0.1:8012
 //! LLM_MODEL=qwen2.5
 //! ```
+//!
+//! # Recorded responses
+//!
+//! `LLM_RECORD_MODE=record` appends every model reply to
+//! `LLM_RECORDINGS_PATH` (JSON lines keyed by a SHA-256 of the prompt);
+//! `LLM_RECORD_MODE=replay` answers from that file instead of calling the
+//! model, so eval and replay runs are reproducible without a GPU.  Replay
+//! implies `LLM_ENABLED`; prompts with no recording fail open.
 
+use crate::errors;
 use crate::filter::{Proximity, threat_kind::ThreatKind};
+use crate::metrics::SharedMetrics;
+use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 // ─────────────────────────── System prompt ────────────────────────────────
@@ -57,14 +72,43 @@ Rules:
 - When in doubt, confirm the keyword guess (better safe than sorry)
 - Do not categorize potencial threats, only factual
 - Drone != Ballistic. Ballistic = Ballistic missiles
+- BallisticWatch = a MiG-31K (Kinzhal carrier) took off; it is Ballistic only once a launch is reported
+- NavalThreat = missile carriers (ships, submarines) put to sea and ready to launch ("носії Калібрів у морі"); it is CruiseMissile only once launches are reported
+- Artillery = shelling by artillery, mortars or MLRS ("РСЗВ", "Град", "арта"), not missiles
+- Explosion = explosions or impacts that already happened ("вибухи", "прильот", "влучання"), not an incoming threat
 
 Reply ONLY with a JSON object, nothing else:
-{"threats": ["Ballistic", ...], "reasoning": ["Why",...]}
+{"threats": ["Ballistic", ...], "reasoning": ["Why",...], "confidence": 0.9}
+
+"confidence" is how sure you are about the threats list, from 0.0 to 1.0.
 
-Valid threat values: Ballistic, Hypersonic, CruiseMissile, GuidedBomb, Missile, Shahed, ReconDrone, Aircraft, AllClear
-Empty list = not an active alert: {"threats": [], "reasoning": []}
+Valid threat values: Ballistic, Hypersonic, CruiseMissile, GuidedBomb, Missile, Shahed, ReconDrone, Aircraft, Artillery, BallisticWatch, NavalThreat, Explosion, AllClear
+Empty list = not an active alert: {"threats": [], "reasoning": [], "confidence": 0.9}
 "#;
 
+const DISCOVERY_PROMPT: &str = r#"You are a Ukrainian air-raid alert classifier.
+
+You receive a Telegram message (Ukrainian or Russian) from an alert channel. An automated keyword filter found NO threat words in it.
+
+Your job: decide whether it nevertheless reports an ACTIVE, ONGOING, or IMMINENT threat RIGHT NOW, for example in slang, nicknames or unusual wording.
+
+Rules:
+- Analysis, forecasts, recaps, statistics, news and calm situation reports are not threats.
+- Only list threats the message itself describes as happening now.
+
+Reply ONLY with a JSON object, nothing else:
+{"threats": ["Shahed", ...], "reasoning": ["Why",...], "confidence": 0.9}
+
+Valid threat values: Ballistic, Hypersonic, CruiseMissile, GuidedBomb, Missile, Shahed, ReconDrone, Aircraft, Artillery, BallisticWatch, NavalThreat, Explosion
+Empty list = not an active threat: {"threats": [], "reasoning": [], "confidence": 0.9}
+"#;
+
+const SUMMARY_PROMPT: &str = r#"You edit a short Ukrainian morning digest about last night's air-raid alerts.
+
+Rewrite it as 2-4 calm, plain Ukrainian sentences for a civilian reader.
+Keep every number exactly as given. Do not invent facts, places or threats.
+Reply with the rewritten text only."#;
+
 // ─────────────────────────── Data types ──────────────────────────────────
 
 #[derive(Serialize)]
@@ -88,6 +132,25 @@ struct ResponseFormat {
     r#type: &'static str,
 }
 
+impl ChatRequest {
+    /// Recording key: SHA-256 over the prompt messages.  The model name and
+    /// sampling settings are left out so recordings survive a model swap.
+    fn recording_key(&self) -> String {
+        let mut hasher = Sha256::new();
+        for m in &self.messages {
+            hasher.update(m.role.as_bytes());
+            hasher.update([0]);
+            hasher.update(m.content.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct ChatResponse {
     choices: Vec<Choice>,
@@ -108,6 +171,134 @@ struct LlmResult {
     threats: Vec<String>,
     #[serde(default)]
     reasoning: Vec<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+/// Result of [`LlmFilter::verify`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmVerdict {
+    pub threats: Vec<ThreatKind>,
+    /// Self-reported confidence in `0.0..=1.0`; `None` when the model gave
+    /// none or the keyword result was kept (fail-open).
+    pub confidence: Option<f32>,
+}
+
+impl LlmVerdict {
+    fn fail_open(keyword_threats: &[ThreatKind]) -> Self {
+        Self {
+            threats: keyword_threats.to_vec(),
+            confidence: None,
+        }
+    }
+}
+
+// ─────────────────────────── Recordings ──────────────────────────────────
+
+/// `LLM_RECORD_MODE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    Off,
+    /// Call the model and append each reply to the recordings file.
+    Record,
+    /// Answer from the recordings file; never call the model.
+    Replay,
+}
+
+impl RecordMode {
+    fn parse(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "record" => Self::Record,
+            "replay" => Self::Replay,
+            "" | "off" => Self::Off,
+            other => {
+                warn!("Unknown LLM_RECORD_MODE {other:?}, recording disabled");
+                Self::Off
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Record => "record",
+            Self::Replay => "replay",
+        }
+    }
+}
+
+/// One line of the recordings file.
+#[derive(Serialize, Deserialize)]
+struct Recording {
+    key: String,
+    content: String,
+}
+
+struct Recordings {
+    mode: RecordMode,
+    path: String,
+    /// Loaded in replay mode; a key recorded twice keeps the last reply.
+    responses: HashMap<String, String>,
+    misses: AtomicUsize,
+}
+
+impl Recordings {
+    fn new(mode: RecordMode, path: String) -> Self {
+        let mut responses = HashMap::new();
+        if mode == RecordMode::Replay {
+            match std::fs::read_to_string(&path) {
+                Ok(data) => {
+                    for (idx, line) in data.lines().enumerate() {
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        match serde_json::from_str::<Recording>(line) {
+                            Ok(r) => {
+                                responses.insert(r.key, r.content);
+                            }
+                            Err(e) => warn!("{path}:{}: bad LLM recording: {e}", idx + 1),
+                        }
+                    }
+                    info!("Loaded {} LLM recordings from {path}", responses.len());
+                }
+                Err(e) => warn!("Cannot read LLM recordings {path}: {e}"),
+            }
+        }
+        Self {
+            mode,
+            path,
+            responses,
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Result<String> {
+        self.responses.get(key).cloned().ok_or_else(|| {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            anyhow!("no recorded response for {key} in {}", self.path)
+        })
+    }
+
+    fn store(&self, key: String, content: &str) {
+        let line = match serde_json::to_string(&Recording {
+            key,
+            content: content.to_string(),
+        }) {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("Cannot encode LLM recording: {e}");
+                return;
+            }
+        };
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{line}"));
+        if let Err(e) = written {
+            warn!("Cannot write LLM recording to {}: {e}", self.path);
+        }
+    }
 }
 
 // ─────────────────────────── LlmFilter ───────────────────────────────────
@@ -119,22 +310,36 @@ pub struct LlmFilter {
     model: String,
     enabled: bool,
     timeout: Duration,
+    /// Verdicts with a confidence inside this range are queued for review.
+    review_band: (f32, f32),
+    recordings: Recordings,
+    /// Latency of answered requests, for `/metrics_summary`.
+    metrics: Option<SharedMetrics>,
 }
 
 impl LlmFilter {
     /// Build from environment variables.
     ///
-    /// | Env var          | Default                    | Description                 |
-    /// |------------------|----------------------------|-----------------------------|
-    /// | `LLM_ENABLED`    | `false`                    | Enable LLM secondary filter |
-    /// | `LLM_MODEL`      | `qwen2.5:7b`               | Ollama model name           |
-    /// | `LLM_ENDPOINT`   | `http://127.0.0.1:11434`   | Ollama / llama-server URL   |
-    /// | `LLM_TIMEOUT_MS` | `3000`                     | Request timeout in ms       |
+    /// | Env var                     | Default                  | Description                 |
+    /// |-----------------------------|--------------------------|-----------------------------|
+    /// | `LLM_ENABLED`               | `false`                  | Enable LLM secondary filter |
+    /// | `LLM_MODEL`                 | `qwen2.5:7b`             | Ollama model name           |
+    /// | `LLM_ENDPOINT`              | `http://127.0.0.1:11434` | Ollama / llama-server URL   |
+    /// | `LLM_TIMEOUT_MS`            | `3000`                   | Request timeout in ms       |
+    /// | `LLM_REVIEW_CONFIDENCE_MIN` | `0.4`                    | Lower bound of "uncertain"  |
+    /// | `LLM_REVIEW_CONFIDENCE_MAX` | `0.7`                    | Upper bound of "uncertain"  |
+    /// | `LLM_RECORD_MODE`           | `off`                    | `off`, `record` or `replay` |
+    /// | `LLM_RECORDINGS_PATH`       | `llm_recordings.jsonl`   | Recorded responses file     |
     pub fn from_env() -> Self {
-        let enabled = std::env::var("LLM_ENABLED")
-            .ok()
-            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-            .unwrap_or(false);
+        let record_mode = RecordMode::parse(&std::env::var("LLM_RECORD_MODE").unwrap_or_default());
+        let recordings_path =
+            std::env::var("LLM_RECORDINGS_PATH").unwrap_or_else(|_| "llm_recordings.jsonl".into());
+
+        let enabled = record_mode == RecordMode::Replay
+            || std::env::var("LLM_ENABLED")
+                .ok()
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
 
         let endpoint =
             std::env::var("LLM_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:11434".into());
@@ -146,20 +351,93 @@ impl LlmFilter {
             .and_then(|v| v.parse().ok())
             .unwrap_or(3000);
 
+        let bound = |key: &str, default: f32| -> f32 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
         Self {
             client: Client::new(),
             endpoint,
             model,
             enabled,
             timeout: Duration::from_millis(timeout_ms),
+            review_band: (
+                bound("LLM_REVIEW_CONFIDENCE_MIN", 0.4),
+                bound("LLM_REVIEW_CONFIDENCE_MAX", 0.7),
+            ),
+            recordings: Recordings::new(record_mode, recordings_path),
+            metrics: None,
         }
     }
 
+    /// Enable regardless of `LLM_ENABLED` (`SECONDARY_FILTER=llm`).
+    pub fn force_enabled(mut self) -> Self {
+        self.enabled = true;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: SharedMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Returns `true` when the LLM filter is enabled.
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
+    /// Prompts that had no recording in replay mode.
+    pub fn replay_misses(&self) -> usize {
+        self.recordings.misses.load(Ordering::Relaxed)
+    }
+
+    /// POST `request` and return the first choice's text.  Goes through the
+    /// recordings file when `LLM_RECORD_MODE` is set.
+    async fn chat(&self, what: &str, request: &ChatRequest, timeout: Duration) -> Result<String> {
+        let key = request.recording_key();
+        if self.recordings.mode == RecordMode::Replay {
+            return self.recordings.lookup(&key);
+        }
+
+        let url = format!("{}/v1/chat/completions", self.endpoint);
+        let started = Instant::now();
+        let response = errors::with_retry(what, || async {
+            Ok(self
+                .client
+                .post(&url)
+                .timeout(timeout)
+                .json(request)
+                .send()
+                .await?
+                .error_for_status()?)
+        })
+        .await?;
+        let body: ChatResponse = response.json().await?;
+        let content = body
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content)
+            .ok_or_else(|| anyhow!("LLM returned no choices"))?;
+        if let Some(metrics) = &self.metrics {
+            metrics.lock().unwrap().observe_llm(started.elapsed());
+        }
+
+        if self.recordings.mode == RecordMode::Record {
+            self.recordings.store(key, &content);
+        }
+        Ok(content)
+    }
+
+    /// Whether a verdict with `confidence` is too unsure to trust blindly.
+    pub fn is_uncertain(&self, confidence: Option<f32>) -> bool {
+        let (min, max) = self.review_band;
+        confidence.is_some_and(|c| (min..=max).contains(&c))
+    }
+
     /// Ask the LLM to verify / correct the keyword-detected threats.
     ///
     /// On any error (timeout, server down, parse failure) the original
@@ -170,7 +448,7 @@ impl LlmFilter {
         keyword_threats: &[ThreatKind],
         proximity: Proximity,
         nationwide: bool,
-    ) -> Vec<ThreatKind> {
+    ) -> LlmVerdict {
         let threats_str: String = keyword_threats
             .iter()
             .map(|t| t.variant_name())
@@ -217,52 +495,31 @@ impl LlmFilter {
             }),
         };
 
-        let url = format!("{}/v1/chat/completions", self.endpoint);
-
-        let result = self
-            .client
-            .post(&url)
-            .timeout(self.timeout)
-            .json(&request)
-            .send()
-            .await;
-
-        let response = match result {
-            Ok(resp) => resp,
+        let content = match self.chat("LLM request", &request, self.timeout).await {
+            Ok(c) => c,
             Err(e) => {
-                warn!("LLM request failed (fail-open): {e}");
-                return keyword_threats.to_vec();
+                warn!(
+                    "LLM request failed (fail-open, {}): {e}",
+                    errors::classify(&e).name()
+                );
+                return LlmVerdict::fail_open(keyword_threats);
             }
         };
+        info!("Query:{}, Chat response {:?}", text, content);
 
-        let body = match response.json::<ChatResponse>().await {
-            Ok(b) => b,
-            Err(e) => {
-                warn!("LLM response parse failed (fail-open): {e}");
-                return keyword_threats.to_vec();
-            }
-        };
-        info!("Query:{}, Chat response {:?}", text, body);
-        let content = match body.choices.first() {
-            Some(c) => &c.message.content,
-            None => {
-                warn!("LLM returned no choices (fail-open)");
-                return keyword_threats.to_vec();
-            }
-        };
-
-        let llm_result: LlmResult = match serde_json::from_str(content) {
+        let llm_result: LlmResult = match serde_json::from_str(&content) {
             Ok(r) => r,
             Err(e) => {
                 warn!("LLM JSON parse failed (fail-open): {e} — raw: {content}");
-                return keyword_threats.to_vec();
+                return LlmVerdict::fail_open(keyword_threats);
             }
         };
 
         debug!(
-            "LLM verdict: threats={:?}, reasoning={:?}",
-            llm_result.threats, llm_result.reasoning
+            "LLM verdict: threats={:?}, reasoning={:?}, confidence={:?}",
+            llm_result.threats, llm_result.reasoning, llm_result.confidence
         );
+        let confidence = llm_result.confidence.map(|c| c.clamp(0.0, 1.0));
 
         // Convert string names back to ThreatKind.
         let verified: Vec<ThreatKind> = llm_result
@@ -274,7 +531,10 @@ impl LlmFilter {
         // If LLM returned something parseable, use it. Otherwise fail-open.
         if llm_result.threats.is_empty() && !keyword_threats.is_empty() {
             debug!("LLM says NOT an active alert — suppressing");
-            return vec![];
+            return LlmVerdict {
+                threats: vec![],
+                confidence,
+            };
         }
 
         if verified.is_empty() && !llm_result.threats.is_empty() {
@@ -283,10 +543,106 @@ impl LlmFilter {
                 "LLM returned unparseable threats {:?} (fail-open)",
                 llm_result.threats
             );
-            return keyword_threats.to_vec();
+            return LlmVerdict::fail_open(keyword_threats);
+        }
+
+        LlmVerdict {
+            threats: verified,
+            confidence,
+        }
+    }
+
+    /// Classify a message the keyword filter found nothing in, without
+    /// keyword hints (keyword-list mining, see [`crate::suggest`]).  `None`
+    /// when the LLM is disabled or fails.
+    pub async fn discover(&self, text: &str) -> Option<LlmVerdict> {
+        if !self.enabled {
+            return None;
+        }
+        let truncated: String = text.chars().take(800).collect();
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: DISCOVERY_PROMPT.into(),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: format!("Message from channel:\n```\n{truncated}\n```\n\nClassify:"),
+                },
+            ],
+            temperature: 0.0,
+            max_tokens: 500,
+            response_format: Some(ResponseFormat {
+                r#type: "json_object",
+            }),
+        };
+        let content = match self.chat("LLM discovery", &request, self.timeout).await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("LLM discovery request failed: {e}");
+                return None;
+            }
+        };
+        let result: LlmResult = match serde_json::from_str(&content) {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("LLM discovery JSON parse failed: {e} — raw: {content}");
+                return None;
+            }
+        };
+        Some(LlmVerdict {
+            threats: result
+                .threats
+                .iter()
+                .filter_map(|name| ThreatKind::from_variant_name(name))
+                .filter(|t| *t != ThreatKind::AllClear)
+                .collect(),
+            confidence: result.confidence.map(|c| c.clamp(0.0, 1.0)),
+        })
+    }
+
+    /// Rephrase a generated morning summary into friendlier prose.
+    ///
+    /// Returns `None` when the LLM is disabled or fails; callers then send
+    /// the original text.
+    pub async fn polish_summary(&self, summary: &str) -> Option<String> {
+        if !self.enabled {
+            return None;
         }
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: SUMMARY_PROMPT.into(),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: summary.to_string(),
+                },
+            ],
+            temperature: 0.2,
+            max_tokens: 400,
+            response_format: None,
+        };
 
-        verified
+        // Summaries are not latency-critical; allow a longer budget than
+        // per-alert verification.
+        match self
+            .chat("LLM summary request", &request, self.timeout * 5)
+            .await
+        {
+            Ok(content) => Some(content.trim().to_string()).filter(|c| !c.is_empty()),
+            Err(e) => {
+                warn!(
+                    "LLM summary request failed ({}): {e}",
+                    errors::classify(&e).name()
+                );
+                None
+            }
+        }
     }
 }
 
@@ -294,11 +650,14 @@ impl std::fmt::Display for LlmFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "LlmFilter(enabled={}, model={}, endpoint={}, timeout={}ms)",
+            "LlmFilter(enabled={}, model={}, endpoint={}, timeout={}ms, review={:.2}..{:.2}, record={})",
             self.enabled,
             self.model,
             self.endpoint,
             self.timeout.as_millis(),
+            self.review_band.0,
+            self.review_band.1,
+            self.recordings.mode.name(),
         )
     }
 }
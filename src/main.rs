@@ -1,15 +1,33 @@
-mod bot;
-mod filter;
-mod llm;
-mod modes;
-mod telegram;
-
-use anyhow::Result;
+use anyhow::{Context, Result};
 use dotenvy::dotenv;
+use tg_osint_v0::modes;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    tracing_subscriber::fmt().with_target(false).init();
+    // Keep stdout clean for OUTPUT_FORMAT=json consumers.
+    let json_output = std::env::var("OUTPUT_FORMAT")
+        .is_ok_and(|v| matches!(v.trim().to_lowercase().as_str(), "json" | "jsonl"));
+    if std::env::args().nth(1).as_deref() == Some("tui") {
+        // The console owns the terminal.
+        let path = std::env::var("TUI_LOG_PATH").unwrap_or_else(|_| "./tui.log".into());
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {path}"))?;
+        tracing_subscriber::fmt()
+            .with_target(false)
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(file))
+            .init();
+    } else if json_output {
+        tracing_subscriber::fmt()
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt().with_target(false).init();
+    }
     modes::run_from_env().await
 }
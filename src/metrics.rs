@@ -0,0 +1,427 @@
+//! Self-metrics for operators without a monitoring stack: rolling
+//! last-hour message counts, broadcast failures and LLM latency
+//! percentiles, shown by the admin-only `/metrics_summary` bot command.
+//! Replay runs record the same metrics and write them as an OpenMetrics
+//! snapshot (`REPLAY_METRICS_PATH`), so filter changes can be compared
+//! between runs.
+
+use crate::filter::threat_kind::ThreatKind;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Span of the rolling window.
+const WINDOW: Duration = Duration::from_secs(3600);
+
+/// Counters are kept per minute; the window is the last 60 buckets.
+const BUCKET: Duration = Duration::from_secs(60);
+
+/// LLM latency samples kept within the window (oldest dropped first).
+const MAX_LATENCY_SAMPLES: usize = 10_000;
+
+/// Upper bounds of the LLM latency histogram, seconds.
+const LLM_LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Upper bounds of the alert lag histogram, seconds of message time.
+const ALERT_LAG_BUCKETS: &[f64] = &[0.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0];
+
+/// A threat first mentioned longer ago than this without an alert starts
+/// a new lag measurement.
+const ALERT_LAG_HORIZON_SECS: i64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// Channel message received by the pipeline.
+    Received,
+    /// Message that produced a broadcast alert.
+    Forwarded,
+    /// Message the filter (or a hook) dropped.
+    Suppressed,
+    /// Broadcast that failed as a whole.
+    BroadcastFailed,
+}
+
+impl Counter {
+    const ALL: [Counter; 4] = [
+        Counter::Received,
+        Counter::Forwarded,
+        Counter::Suppressed,
+        Counter::BroadcastFailed,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Counter::Received => "received",
+            Counter::Forwarded => "forwarded",
+            Counter::Suppressed => "suppressed",
+            Counter::BroadcastFailed => "broadcast_failed",
+        }
+    }
+}
+
+/// Cumulative histogram over fixed upper bounds.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bound (not cumulative); the last slot is `+Inf`.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let slot = self
+            .bounds
+            .iter()
+            .position(|b| value <= *b)
+            .unwrap_or(self.bounds.len());
+        self.counts[slot] += 1;
+        self.sum += value;
+    }
+
+    #[cfg(test)]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn render(&self, name: &str, out: &mut String) {
+        out.push_str(&format!("# TYPE {name} histogram\n# UNIT {name} seconds\n"));
+        let mut cumulative = 0;
+        for (i, n) in self.counts.iter().enumerate() {
+            cumulative += n;
+            let le = self
+                .bounds
+                .get(i)
+                .map_or("+Inf".to_string(), |b| format!("{b:?}"));
+            out.push_str(&format!("{name}_bucket{{le=\"{le}\"}} {cumulative}\n"));
+        }
+        out.push_str(&format!("{name}_sum {}\n", self.sum));
+        out.push_str(&format!("{name}_count {cumulative}\n"));
+    }
+}
+
+pub type SharedMetrics = Arc<Mutex<Metrics>>;
+
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    /// `(bucket start, counts indexed by Counter)`, oldest first.
+    buckets: VecDeque<(Instant, [u64; Counter::ALL.len()])>,
+    totals: [u64; Counter::ALL.len()],
+    /// `(when, latency)` of successful LLM requests, oldest first.
+    llm_latencies: VecDeque<(Instant, Duration)>,
+    /// All LLM latencies since start.
+    llm_histogram: Histogram,
+    /// Suppressed messages since start by reason.
+    suppressed_by: BTreeMap<&'static str, u64>,
+    /// Forwarded alerts since start by threat kind.
+    alerts_by_kind: BTreeMap<&'static str, u64>,
+    /// Message time of the first mention of each threat not alerted yet.
+    first_mention: HashMap<ThreatKind, i64>,
+    /// Message-time delay between a threat's first mention and its alert.
+    alert_lag: Histogram,
+}
+
+/// Snapshot of the last hour, see [`Metrics::summary_at`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSummary {
+    pub uptime: Duration,
+    /// Last-hour counts indexed like [`Counter`].
+    pub last_hour: [u64; Counter::ALL.len()],
+    /// Counts since start.
+    pub totals: [u64; Counter::ALL.len()],
+    pub llm_calls: usize,
+    pub llm_p50: Option<Duration>,
+    pub llm_p95: Option<Duration>,
+}
+
+impl MetricsSummary {
+    pub fn last_hour(&self, counter: Counter) -> u64 {
+        self.last_hour[counter as usize]
+    }
+
+    pub fn total(&self, counter: Counter) -> u64 {
+        self.totals[counter as usize]
+    }
+}
+
+impl Metrics {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            buckets: VecDeque::new(),
+            totals: [0; Counter::ALL.len()],
+            llm_latencies: VecDeque::new(),
+            llm_histogram: Histogram::new(LLM_LATENCY_BUCKETS),
+            suppressed_by: BTreeMap::new(),
+            alerts_by_kind: BTreeMap::new(),
+            first_mention: HashMap::new(),
+            alert_lag: Histogram::new(ALERT_LAG_BUCKETS),
+        }
+    }
+
+    pub fn shared() -> SharedMetrics {
+        Arc::new(Mutex::new(Self::new(Instant::now())))
+    }
+
+    pub fn incr(&mut self, counter: Counter) {
+        self.incr_at(Instant::now(), counter);
+    }
+
+    pub fn incr_at(&mut self, now: Instant, counter: Counter) {
+        self.prune(now);
+        match self.buckets.back_mut() {
+            Some((start, counts)) if now.saturating_duration_since(*start) < BUCKET => {
+                counts[counter as usize] += 1;
+            }
+            _ => {
+                let mut counts = [0; Counter::ALL.len()];
+                counts[counter as usize] = 1;
+                self.buckets.push_back((now, counts));
+            }
+        }
+        self.totals[counter as usize] += 1;
+    }
+
+    pub fn observe_llm(&mut self, latency: Duration) {
+        self.observe_llm_at(Instant::now(), latency);
+    }
+
+    pub fn observe_llm_at(&mut self, now: Instant, latency: Duration) {
+        self.prune(now);
+        if self.llm_latencies.len() >= MAX_LATENCY_SAMPLES {
+            self.llm_latencies.pop_front();
+        }
+        self.llm_latencies.push_back((now, latency));
+        self.llm_histogram.observe(latency.as_secs_f64());
+    }
+
+    /// Count a suppressed message under `reason`.
+    pub fn suppress(&mut self, reason: &'static str) {
+        self.incr(Counter::Suppressed);
+        *self.suppressed_by.entry(reason).or_default() += 1;
+    }
+
+    /// Note the threats a message posted at `ts` mentions, whether or not
+    /// it is forwarded; the first mention starts the alert lag clock.
+    pub fn observe_mentions(&mut self, ts: i64, threats: &[ThreatKind]) {
+        for kind in threats {
+            if matches!(
+                kind,
+                ThreatKind::AllClear | ThreatKind::Explosion | ThreatKind::Other
+            ) {
+                continue;
+            }
+            let first = self.first_mention.entry(*kind).or_insert(ts);
+            if ts - *first > ALERT_LAG_HORIZON_SECS {
+                *first = ts;
+            }
+        }
+    }
+
+    /// Count an alert forwarded for a message posted at `ts`.  Its primary
+    /// threat's lag since the first mention is recorded; an all-clear
+    /// resets every mention.
+    pub fn record_alert(&mut self, ts: i64, threats: &[ThreatKind]) {
+        for kind in threats {
+            *self.alerts_by_kind.entry(kind.variant_name()).or_default() += 1;
+        }
+        if threats.contains(&ThreatKind::AllClear) {
+            self.first_mention.clear();
+            return;
+        }
+        let Some(primary) = threats.iter().copied().max_by_key(|k| k.specificity()) else {
+            return;
+        };
+        let first = self.first_mention.remove(&primary).unwrap_or(ts);
+        self.alert_lag.observe((ts - first).max(0) as f64);
+    }
+
+    #[cfg(test)]
+    pub fn suppressed_by(&self, reason: &str) -> u64 {
+        self.suppressed_by.get(reason).copied().unwrap_or(0)
+    }
+
+    #[cfg(test)]
+    pub fn alert_lag(&self) -> &Histogram {
+        &self.alert_lag
+    }
+
+    /// Counters and histograms since start in the OpenMetrics text format.
+    pub fn openmetrics(&self) -> String {
+        let mut out = String::from("# TYPE tg_osint_messages counter\n");
+        for counter in Counter::ALL {
+            out.push_str(&format!(
+                "tg_osint_messages_total{{outcome=\"{}\"}} {}\n",
+                counter.name(),
+                self.totals[counter as usize]
+            ));
+        }
+        out.push_str("# TYPE tg_osint_suppressed counter\n");
+        for (reason, n) in &self.suppressed_by {
+            out.push_str(&format!(
+                "tg_osint_suppressed_total{{reason=\"{reason}\"}} {n}\n"
+            ));
+        }
+        out.push_str("# TYPE tg_osint_alerts counter\n");
+        for (kind, n) in &self.alerts_by_kind {
+            out.push_str(&format!("tg_osint_alerts_total{{kind=\"{kind}\"}} {n}\n"));
+        }
+        self.llm_histogram
+            .render("tg_osint_llm_latency_seconds", &mut out);
+        self.alert_lag
+            .render("tg_osint_alert_lag_seconds", &mut out);
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn prune(&mut self, now: Instant) {
+        let expired = |at: &Instant| now.saturating_duration_since(*at) >= WINDOW;
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(start, _)| expired(start))
+        {
+            self.buckets.pop_front();
+        }
+        while self
+            .llm_latencies
+            .front()
+            .is_some_and(|(at, _)| expired(at))
+        {
+            self.llm_latencies.pop_front();
+        }
+    }
+
+    pub fn summary_at(&mut self, now: Instant) -> MetricsSummary {
+        self.prune(now);
+        let mut last_hour = [0; Counter::ALL.len()];
+        for (_, counts) in &self.buckets {
+            for (sum, n) in last_hour.iter_mut().zip(counts) {
+                *sum += n;
+            }
+        }
+        let mut latencies: Vec<Duration> = self.llm_latencies.iter().map(|(_, d)| *d).collect();
+        latencies.sort();
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p * latencies.len() as f64).ceil() as usize;
+            latencies.get(rank.saturating_sub(1)).copied()
+        };
+        MetricsSummary {
+            uptime: now.saturating_duration_since(self.started),
+            last_hour,
+            totals: self.totals,
+            llm_calls: latencies.len(),
+            llm_p50: percentile(0.50),
+            llm_p95: percentile(0.95),
+        }
+    }
+}
+
+/// `/metrics_summary` reply.
+pub fn format_summary(summary: &MetricsSummary) -> String {
+    let secs = summary.uptime.as_secs();
+    let mut out = format!(
+        "📈 Metrics (last hour · since start, up {}h {:02}m)",
+        secs / 3600,
+        secs / 60 % 60
+    );
+    for (counter, label) in
+        Counter::ALL
+            .iter()
+            .zip(["Received", "Forwarded", "Suppressed", "Broadcast failures"])
+    {
+        out.push_str(&format!(
+            "\n{label}: {} · {}",
+            summary.last_hour(*counter),
+            summary.total(*counter)
+        ));
+    }
+    match (summary.llm_p50, summary.llm_p95) {
+        (Some(p50), Some(p95)) => out.push_str(&format!(
+            "\nLLM latency: p50 {} ms, p95 {} ms ({} call(s))",
+            p50.as_millis(),
+            p95.as_millis(),
+            summary.llm_calls
+        )),
+        _ => out.push_str("\nLLM latency: no calls"),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_metrics_record_suppression_reasons_and_alert_lag() {
+        let mut metrics = Metrics::new(Instant::now());
+        metrics.observe_mentions(1000, &[ThreatKind::Shahed]);
+        metrics.observe_mentions(1040, &[ThreatKind::Shahed]);
+        metrics.suppress("out_of_area");
+        metrics.record_alert(1090, &[ThreatKind::Shahed]);
+        metrics.incr(Counter::Forwarded);
+        // The next alert in the wave counts from its own first mention.
+        metrics.observe_mentions(1200, &[ThreatKind::Shahed]);
+        metrics.record_alert(1200, &[ThreatKind::Shahed]);
+        assert_eq!(metrics.suppressed_by("out_of_area"), 1);
+        assert_eq!(metrics.alert_lag().count(), 2);
+
+        let text = metrics.openmetrics();
+        for line in [
+            "tg_osint_messages_total{outcome=\"forwarded\"} 1",
+            "tg_osint_messages_total{outcome=\"suppressed\"} 1",
+            "tg_osint_suppressed_total{reason=\"out_of_area\"} 1",
+            "tg_osint_alerts_total{kind=\"Shahed\"} 2",
+            "tg_osint_alert_lag_seconds_bucket{le=\"0.0\"} 1",
+            "tg_osint_alert_lag_seconds_bucket{le=\"60.0\"} 1",
+            "tg_osint_alert_lag_seconds_bucket{le=\"120.0\"} 2",
+            "tg_osint_alert_lag_seconds_sum 90",
+            "tg_osint_llm_latency_seconds_count 0",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {line:?} in\n{text}"
+            );
+        }
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn metrics_summary_covers_the_last_hour() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut metrics = Metrics::new(start);
+        for secs in [0, 10, 3000, 3590] {
+            metrics.incr_at(at(secs), Counter::Received);
+        }
+        metrics.incr_at(at(3000), Counter::Forwarded);
+        metrics.incr_at(at(3590), Counter::Suppressed);
+        for ms in 1..=20 {
+            metrics.observe_llm_at(at(3000), Duration::from_millis(ms * 100));
+        }
+
+        // An hour after the first messages their bucket has expired.
+        let summary = metrics.summary_at(at(3700));
+        assert_eq!(summary.last_hour(Counter::Received), 2);
+        assert_eq!(summary.total(Counter::Received), 4);
+        assert_eq!(summary.last_hour(Counter::Forwarded), 1);
+        assert_eq!(summary.last_hour(Counter::BroadcastFailed), 0);
+        assert_eq!(summary.llm_calls, 20);
+        assert_eq!(summary.llm_p50, Some(Duration::from_millis(1000)));
+        assert_eq!(summary.llm_p95, Some(Duration::from_millis(1900)));
+
+        let summary = metrics.summary_at(at(7200));
+        assert_eq!(summary.last_hour(Counter::Received), 0);
+        assert_eq!(summary.llm_p50, None);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::archive;
+use anyhow::{Context, Result, anyhow};
+use chrono::{Duration as ChronoDuration, Utc};
+use std::path::Path;
+use tracing::info;
+
+use super::dump_rotation::{compression_from_env, parse_compression, recompress_dir};
+use super::shared::DumpCompression;
+
+/// `compact [--older-than DAYS] [--compress gz|zst|none]`
+///
+/// Archive maintenance: folds duplicate texts in `ARCHIVE_DB_PATH` into their
+/// first copy (other channels are kept as attributions), rebuilds the FTS
+/// index and vacuums the database; then rewrites the closed daily files in
+/// `LIVE_DUMP_DIR` in one compression format (`--compress`, else
+/// `LIVE_DUMP_COMPRESS`, else zstd).  `--older-than` limits both to data at
+/// least that many days old.
+pub(super) fn run(args: &[String]) -> Result<()> {
+    let mut older_than_days = 0i64;
+    let mut compression = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow!("{arg} requires a value"));
+        match arg.as_str() {
+            "--older-than" => {
+                older_than_days = value()?
+                    .parse()
+                    .with_context(|| format!("{arg} must be a number of days"))?
+            }
+            "--compress" => {
+                let raw = value()?;
+                compression = Some(
+                    parse_compression(raw)
+                        .ok_or_else(|| anyhow!("{arg} must be gz, zst or none, got {raw:?}"))?,
+                );
+            }
+            other => {
+                return Err(anyhow!(
+                    "unknown argument {other:?}; usage: compact [--older-than DAYS] \
+                     [--compress gz|zst|none]"
+                ));
+            }
+        }
+    }
+    let now = Utc::now();
+    let archive_path = archive::archive_path_from_env();
+    let dump_dir = std::env::var("LIVE_DUMP_DIR")
+        .ok()
+        .filter(|d| !d.trim().is_empty());
+    if archive_path.is_none() && dump_dir.is_none() {
+        return Err(anyhow!(
+            "compact needs ARCHIVE_DB_PATH and/or LIVE_DUMP_DIR"
+        ));
+    }
+
+    if let Some(path) = archive_path {
+        let db = archive::open_archive(&path)?;
+        let before = now.timestamp() - older_than_days.max(0) * 86400;
+        let stats = archive::compact(&db, before)?;
+        info!(
+            "Archive compacted: {} duplicate message(s) folded, {} left",
+            stats.duplicates, stats.messages
+        );
+    }
+
+    if let Some(dir) = dump_dir {
+        let compression = compression
+            .or_else(compression_from_env)
+            .unwrap_or(DumpCompression::Zstd);
+        // Yesterday's file may still be open in a timezone behind UTC.
+        let before = now.date_naive() - ChronoDuration::days(older_than_days.max(1));
+        let rewritten = recompress_dir(Path::new(&dir), compression, before)?;
+        info!("Live dump: {rewritten} file(s) rewritten as {compression:?}");
+    }
+    Ok(())
+}
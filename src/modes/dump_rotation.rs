@@ -0,0 +1,424 @@
+//! Continuous dump for live mode: every received message is appended to
+//! `<LIVE_DUMP_DIR>/<YYYY-MM-DD>.jsonl`.  A new file starts at local
+//! midnight; closed files are optionally compressed and files older than the
+//! retention window are deleted, so the archiver can run unattended.
+//!
+//! The decisions log ([`crate::decisions`]) rotates the same way under
+//! `DECISIONS_LOG_DIR`.
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration as ChronoDuration, FixedOffset, NaiveDate, Utc};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::shared::{DumpCompression, DumpWriter, open_dump_reader};
+
+#[derive(Debug, Clone)]
+struct RotationCfg {
+    /// Log prefix, e.g. "Live dump".
+    name: &'static str,
+    dir: PathBuf,
+    /// Local timezone whose midnight closes a file.
+    offset: FixedOffset,
+    /// Applied to files once closed.
+    compression: DumpCompression,
+    /// Files older than this many days are deleted; `None` keeps them.
+    retention_days: Option<i64>,
+}
+
+pub(super) struct RotatingDump {
+    cfg: RotationCfg,
+    current: Option<(NaiveDate, BufWriter<File>)>,
+}
+
+impl RotatingDump {
+    /// `None` when `LIVE_DUMP_DIR` is not set.
+    ///
+    /// | Env var                    | Default | Description                          |
+    /// |----------------------------|---------|--------------------------------------|
+    /// | `LIVE_DUMP_DIR`            | —       | Directory of daily JSONL dumps       |
+    /// | `DUMP_TZ_OFFSET_MINUTES`   | `0`     | Timezone whose midnight rotates      |
+    /// | `LIVE_DUMP_COMPRESS`       | `none`  | `gz` / `zst` for closed files        |
+    /// | `LIVE_DUMP_RETENTION_DAYS` | `0`     | Delete dumps older than this (0=off) |
+    pub fn from_env() -> Result<Option<Self>> {
+        Self::open_env(
+            "Live dump",
+            "LIVE_DUMP_DIR",
+            compression_from_env(),
+            "LIVE_DUMP_RETENTION_DAYS",
+        )
+    }
+
+    /// The decisions log; `None` when `DECISIONS_LOG_DIR` is not set.
+    ///
+    /// | Env var                        | Default | Description                              |
+    /// |--------------------------------|---------|------------------------------------------|
+    /// | `DECISIONS_LOG_DIR`            | —       | Directory of daily decisions logs        |
+    /// | `DECISIONS_LOG_COMPRESS`       | `none`  | `gz` / `zst` for closed files            |
+    /// | `DECISIONS_LOG_RETENTION_DAYS` | `0`     | Delete logs older than this (0=off)      |
+    pub fn decisions_from_env() -> Result<Option<Self>> {
+        let compression = std::env::var("DECISIONS_LOG_COMPRESS")
+            .ok()
+            .and_then(|v| parse_compression(&v));
+        Self::open_env(
+            "Decisions log",
+            "DECISIONS_LOG_DIR",
+            compression,
+            "DECISIONS_LOG_RETENTION_DAYS",
+        )
+    }
+
+    fn open_env(
+        name: &'static str,
+        dir_var: &str,
+        compression: Option<DumpCompression>,
+        retention_var: &str,
+    ) -> Result<Option<Self>> {
+        let Some(dir) = std::env::var(dir_var).ok().filter(|d| !d.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let offset_minutes = std::env::var("DUMP_TZ_OFFSET_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .unwrap_or(0);
+        let offset = FixedOffset::east_opt(offset_minutes.saturating_mul(60))
+            .context("invalid DUMP_TZ_OFFSET_MINUTES")?;
+        let compression = compression.unwrap_or(DumpCompression::None);
+        let retention_days = std::env::var(retention_var)
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|d| *d > 0);
+
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {dir}"))?;
+        let cfg = RotationCfg {
+            name,
+            dir: PathBuf::from(&dir),
+            offset,
+            compression,
+            retention_days,
+        };
+        info!(
+            "{name}: {dir} (compress {:?}, keep {:?} days)",
+            cfg.compression, cfg.retention_days
+        );
+        // Files left open by an earlier run that stopped before midnight.
+        spawn_housekeeping(cfg.clone(), local_today(offset));
+        Ok(Some(Self { cfg, current: None }))
+    }
+
+    /// Append `event` to today's file, rotating first when the local date
+    /// changed.
+    pub fn write(&mut self, event: &impl Serialize) -> Result<()> {
+        let today = local_today(self.cfg.offset);
+        if self.current.as_ref().is_none_or(|(date, _)| *date != today) {
+            let rotated = self.current.take().is_some();
+            let path = self.cfg.dir.join(file_name(today));
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            self.current = Some((today, BufWriter::new(file)));
+            if rotated {
+                info!("{} rotated to {}", self.cfg.name, path.display());
+                spawn_housekeeping(self.cfg.clone(), today);
+            }
+        }
+        let (_, writer) = self.current.as_mut().expect("opened above");
+        serde_json::to_writer(&mut *writer, event)?;
+        writer.write_all(b"\n")?;
+        // Flush per line so a crash loses at most the message in flight.
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// `LIVE_DUMP_COMPRESS`, `None` when unset.
+pub(super) fn compression_from_env() -> Option<DumpCompression> {
+    parse_compression(&std::env::var("LIVE_DUMP_COMPRESS").ok()?)
+}
+
+/// `gz` / `zst` / `none`.
+pub(super) fn parse_compression(raw: &str) -> Option<DumpCompression> {
+    match raw.trim().to_lowercase().as_str() {
+        "gz" | "gzip" => Some(DumpCompression::Gzip),
+        "zst" | "zstd" => Some(DumpCompression::Zstd),
+        "none" | "off" | "" => Some(DumpCompression::None),
+        _ => None,
+    }
+}
+
+fn local_today(offset: FixedOffset) -> NaiveDate {
+    Utc::now().with_timezone(&offset).date_naive()
+}
+
+fn file_name(date: NaiveDate) -> String {
+    format!(
+        "{:04}-{:02}-{:02}.jsonl",
+        date.year(),
+        date.month(),
+        date.day()
+    )
+}
+
+/// Date of a dump file named `YYYY-MM-DD.jsonl[.gz|.zst]`.
+fn file_date(name: &str) -> Option<NaiveDate> {
+    let stem = name.get(..10)?;
+    if !matches!(&name[10..], ".jsonl" | ".jsonl.gz" | ".jsonl.zst") {
+        return None;
+    }
+    let mut parts = stem.split('-').map(|p| p.parse::<u32>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    NaiveDate::from_ymd_opt(y as i32, m, d)
+}
+
+/// Compress closed files and prune expired ones off the async runtime.
+fn spawn_housekeeping(cfg: RotationCfg, today: NaiveDate) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = housekeeping(&cfg, today) {
+            warn!("{} housekeeping failed: {e}", cfg.name);
+        }
+    });
+}
+
+fn housekeeping(cfg: &RotationCfg, today: NaiveDate) -> Result<()> {
+    let cutoff = cfg
+        .retention_days
+        .map(|days| today - ChronoDuration::days(days));
+    for entry in std::fs::read_dir(&cfg.dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(date) = file_date(name) else {
+            continue;
+        };
+        if cutoff.is_some_and(|c| date < c) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => info!("{}: removed expired {name}", cfg.name),
+                Err(e) => warn!("{}: cannot remove {name}: {e}", cfg.name),
+            }
+            continue;
+        }
+        if date < today && name.ends_with(".jsonl") && cfg.compression != DumpCompression::None {
+            convert(&path, date, cfg.compression)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite every daily file dated before `before` in `compression`: plain
+/// files are compressed, files compressed differently are converted.
+/// Returns the number of files rewritten.
+pub(super) fn recompress_dir(
+    dir: &Path,
+    compression: DumpCompression,
+    before: NaiveDate,
+) -> Result<usize> {
+    let mut rewritten = 0;
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(date) = file_date(name) else {
+            continue;
+        };
+        if date < before && DumpCompression::from_path(name) != compression {
+            convert(&path, date, compression)?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Replace the daily file `path` of `date` with a copy in `compression`.
+fn convert(path: &Path, date: NaiveDate, compression: DumpCompression) -> Result<()> {
+    let ext = match compression {
+        DumpCompression::Gzip => ".gz",
+        DumpCompression::Zstd => ".zst",
+        DumpCompression::None => "",
+    };
+    let target = path.with_file_name(format!("{}{ext}", file_name(date)));
+    let target = target.to_string_lossy();
+    let mut input = open_dump_reader(&path.to_string_lossy())?;
+    let mut writer = DumpWriter::create(&target)?;
+    std::io::copy(&mut input, &mut writer)?;
+    writer.finish()?;
+    std::fs::remove_file(path)?;
+    info!("Dump: rewrote {} → {target}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// An empty directory unique to this test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dump_rotation_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_dump(dir: &Path, name: &str, line: &str) {
+        let path = dir.join(name);
+        let mut writer = DumpWriter::create(&path.to_string_lossy()).unwrap();
+        writeln!(writer, "{line}").unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn read_dump(dir: &Path, name: &str) -> String {
+        let mut out = String::new();
+        std::io::Read::read_to_string(
+            &mut open_dump_reader(&dir.join(name).to_string_lossy()).unwrap(),
+            &mut out,
+        )
+        .unwrap();
+        out
+    }
+
+    fn listing(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn dump_file_names_carry_their_date() {
+        assert_eq!(file_name(date(2024, 3, 9)), "2024-03-09.jsonl");
+        assert_eq!(file_date("2024-03-09.jsonl"), Some(date(2024, 3, 9)));
+        assert_eq!(file_date("2024-03-09.jsonl.gz"), Some(date(2024, 3, 9)));
+        assert_eq!(file_date("2024-03-09.jsonl.zst"), Some(date(2024, 3, 9)));
+        for name in [
+            "notes.txt",
+            "2024-03-09.json",
+            "2024-03-09.jsonl.bak",
+            "2024-03-09.jsonl.tmp",
+            "2024-02-30.jsonl",
+            "2024-13-01.jsonl",
+            "2024-03.jsonl",
+            "дамп-03-09.jsonl",
+        ] {
+            assert_eq!(file_date(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn compression_names_are_parsed_leniently() {
+        assert_eq!(parse_compression(" GZ "), Some(DumpCompression::Gzip));
+        assert_eq!(parse_compression("gzip"), Some(DumpCompression::Gzip));
+        assert_eq!(parse_compression("zstd"), Some(DumpCompression::Zstd));
+        assert_eq!(parse_compression(""), Some(DumpCompression::None));
+        assert_eq!(parse_compression("off"), Some(DumpCompression::None));
+        assert_eq!(parse_compression("bz2"), None);
+        assert_eq!(parse_compression("gz,zst"), None);
+    }
+
+    #[test]
+    fn housekeeping_prunes_expired_dumps_and_compresses_closed_ones() {
+        let dir = scratch_dir("housekeeping");
+        for name in [
+            "2024-03-02.jsonl.gz",
+            "2024-03-03.jsonl.gz",
+            "2024-03-09.jsonl",
+            "2024-03-10.jsonl",
+        ] {
+            write_dump(&dir, name, name);
+        }
+        // Not dumps, however old.
+        std::fs::write(dir.join("2020-01-01.jsonl.bak"), "x").unwrap();
+        std::fs::write(dir.join("notes.txt"), "x").unwrap();
+
+        let cfg = RotationCfg {
+            name: "Test dump",
+            dir: dir.clone(),
+            offset: FixedOffset::east_opt(0).unwrap(),
+            compression: DumpCompression::Gzip,
+            retention_days: Some(7),
+        };
+        housekeeping(&cfg, date(2024, 3, 10)).unwrap();
+        assert_eq!(
+            listing(&dir),
+            [
+                "2020-01-01.jsonl.bak",
+                // Exactly at the cutoff: kept.
+                "2024-03-03.jsonl.gz",
+                "2024-03-09.jsonl.gz",
+                // Still being written.
+                "2024-03-10.jsonl",
+                "notes.txt",
+            ]
+        );
+        assert_eq!(read_dump(&dir, "2024-03-09.jsonl.gz"), "2024-03-09.jsonl\n");
+
+        // Without a retention window nothing is deleted.
+        let cfg = RotationCfg {
+            retention_days: None,
+            compression: DumpCompression::None,
+            ..cfg
+        };
+        housekeeping(&cfg, date(2030, 1, 1)).unwrap();
+        assert_eq!(listing(&dir).len(), 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recompress_dir_converts_older_files_only() {
+        let dir = scratch_dir("recompress");
+        for name in [
+            "2024-03-01.jsonl",
+            "2024-03-02.jsonl.gz",
+            "2024-03-03.jsonl.zst",
+            "2024-03-04.jsonl",
+        ] {
+            write_dump(&dir, name, name);
+        }
+        std::fs::write(dir.join("notes.txt"), "x").unwrap();
+
+        let rewritten = recompress_dir(&dir, DumpCompression::Zstd, date(2024, 3, 4)).unwrap();
+        assert_eq!(rewritten, 2);
+        assert_eq!(
+            listing(&dir),
+            [
+                "2024-03-01.jsonl.zst",
+                "2024-03-02.jsonl.zst",
+                "2024-03-03.jsonl.zst",
+                "2024-03-04.jsonl",
+                "notes.txt",
+            ]
+        );
+        assert_eq!(
+            read_dump(&dir, "2024-03-01.jsonl.zst"),
+            "2024-03-01.jsonl\n"
+        );
+        assert_eq!(
+            read_dump(&dir, "2024-03-02.jsonl.zst"),
+            "2024-03-02.jsonl.gz\n"
+        );
+
+        // Back to plain files.
+        let rewritten = recompress_dir(&dir, DumpCompression::None, date(2024, 3, 4)).unwrap();
+        assert_eq!(rewritten, 3);
+        assert_eq!(
+            read_dump(&dir, "2024-03-03.jsonl"),
+            "2024-03-03.jsonl.zst\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(recompress_dir(&dir, DumpCompression::Gzip, date(2024, 3, 4)).is_err());
+    }
+}
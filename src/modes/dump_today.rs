@@ -1,12 +1,9 @@
-use crate::telegram;
+use crate::{archive, telegram};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::Path;
-use tracing::{info, warn};
+use tracing::info;
 
-use super::shared::{DumpEvent, start_of_today_utc_from_offset};
+use super::shared::{DumpEvent, DumpWriter, start_of_today_utc_from_offset};
 
 pub(super) async fn run() -> Result<()> {
     let tg = telegram::load_tg_cfg()?;
@@ -28,25 +25,17 @@ pub(super) async fn run() -> Result<()> {
 
     telegram::ensure_user_login(&client, &tg).await?;
 
+    let resolved = telegram::resolve_watch_list(&client, &tg).await?;
     info!(
         "Dumping messages from {} channels since UTC timestamp={} (tz offset {} min)",
-        tg.channels.len(),
+        resolved.peers.len(),
         since_ts,
         offset_minutes
     );
 
     let mut events: Vec<DumpEvent> = Vec::new();
 
-    for uname in &tg.channels {
-        let peer = client
-            .resolve_username(uname)
-            .await
-            .with_context(|| format!("resolve_username failed for @{uname}"))?;
-        let Some(peer) = peer else {
-            warn!("Username @{uname} was not resolved; skipping");
-            continue;
-        };
-
+    for (uname, peer) in resolved.peers {
         let channel_id = peer.id().bare_id();
         let title = peer.name().unwrap_or("<unknown>").to_string();
         info!("Scanning @{uname} ({title}, id={channel_id})");
@@ -74,27 +63,18 @@ pub(super) async fn run() -> Result<()> {
 
     events.sort_by_key(|e| (e.timestamp, e.channel_id));
 
-    if let Some(parent) = Path::new(&output_path).parent() {
-        if !parent.as_os_str().is_empty() {
-            std::fs::create_dir_all(parent).with_context(|| {
-                format!(
-                    "failed to create parent directory for output dump file {}",
-                    output_path
-                )
-            })?;
-        }
+    let mut writer = DumpWriter::create(&output_path)?;
+    for event in &events {
+        writer.write_event(event)?;
     }
+    writer.finish()?;
 
-    let file = File::create(&output_path)
-        .with_context(|| format!("failed to create output dump file {output_path}"))?;
-    let mut writer = BufWriter::new(file);
-
-    for event in &events {
-        let line = serde_json::to_string(event)?;
-        writer.write_all(line.as_bytes())?;
-        writer.write_all(b"\n")?;
+    if let Some(db) = archive::open_from_env()? {
+        for event in &events {
+            archive::archive_message(&db, &event.to_archived())?;
+        }
+        info!("Archived {} events", events.len());
     }
-    writer.flush()?;
 
     info!(
         "Dump complete: {} events written to {}",
@@ -0,0 +1,276 @@
+//! Golden-file regression runs for replay: `REPLAY_EXPECTED_PATH` holds the
+//! alerts an earlier accepted run produced (the `OUTPUT_FORMAT=json` lines of
+//! that run), and replay reports every missing, extra or changed alert.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufWriter, Write};
+
+use super::shared::open_dump_reader;
+
+/// One forwarded alert, keyed by the message that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct ExpectedAlert {
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub channel_title: String,
+    pub alert: String,
+}
+
+/// An [`super::shared::OutputRecord`] line; suppressed records are skipped.
+#[derive(Deserialize)]
+struct RecordLine {
+    #[serde(default)]
+    decision: Option<String>,
+    timestamp: i64,
+    channel_id: i64,
+    #[serde(default)]
+    channel_title: String,
+    alert: Option<String>,
+}
+
+/// `(timestamp, channel_id, n)`: the n-th alert for that message.
+type Key = (i64, i64, usize);
+
+fn keyed(alerts: &[ExpectedAlert]) -> BTreeMap<Key, &ExpectedAlert> {
+    let mut seen: BTreeMap<(i64, i64), usize> = BTreeMap::new();
+    alerts
+        .iter()
+        .map(|a| {
+            let n = seen.entry((a.timestamp, a.channel_id)).or_default();
+            *n += 1;
+            ((a.timestamp, a.channel_id, *n), a)
+        })
+        .collect()
+}
+
+pub(super) fn load_expected(path: &str) -> Result<Vec<ExpectedAlert>> {
+    let reader = open_dump_reader(path)?;
+    let mut out = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("failed to read line {}", idx + 1))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: RecordLine = serde_json::from_str(&line)
+            .with_context(|| format!("invalid JSON at {path}:{}", idx + 1))?;
+        if record.decision.as_deref() == Some("suppressed") {
+            continue;
+        }
+        if let Some(alert) = record.alert {
+            out.push(ExpectedAlert {
+                timestamp: record.timestamp,
+                channel_id: record.channel_id,
+                channel_title: record.channel_title,
+                alert,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Write `alerts` in the format [`load_expected`] reads.
+pub(super) fn write_expected(path: &str, alerts: &[ExpectedAlert]) -> Result<()> {
+    let mut out = BufWriter::new(
+        std::fs::File::create(path).with_context(|| format!("failed to create {path}"))?,
+    );
+    for alert in alerts {
+        serde_json::to_writer(&mut out, alert)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Differences between an expected and an actual run.
+#[derive(Debug, Default)]
+pub(super) struct GoldenDiff {
+    pub missing: Vec<ExpectedAlert>,
+    pub extra: Vec<ExpectedAlert>,
+    /// `(expected, actual)`
+    pub changed: Vec<(ExpectedAlert, ExpectedAlert)>,
+}
+
+impl GoldenDiff {
+    pub fn compare(expected: &[ExpectedAlert], actual: &[ExpectedAlert]) -> Self {
+        let expected = keyed(expected);
+        let actual = keyed(actual);
+        let mut diff = Self::default();
+        for (key, want) in &expected {
+            match actual.get(key) {
+                None => diff.missing.push((*want).clone()),
+                Some(got) if got.alert != want.alert => {
+                    diff.changed.push(((*want).clone(), (*got).clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.extra = actual
+            .iter()
+            .filter(|(key, _)| !expected.contains_key(key))
+            .map(|(_, a)| (*a).clone())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} missing, {} extra, {} changed",
+            self.missing.len(),
+            self.extra.len(),
+            self.changed.len()
+        )
+    }
+
+    /// Human-readable report; changed alerts get a line diff.
+    pub fn render(&self) -> String {
+        let head = |a: &ExpectedAlert| format!("ts={} @{}", a.timestamp, a.channel_title);
+        let mut out = String::new();
+        for a in &self.missing {
+            out.push_str(&format!("MISSING {}\n", head(a)));
+            for line in a.alert.lines() {
+                out.push_str(&format!("  - {line}\n"));
+            }
+        }
+        for a in &self.extra {
+            out.push_str(&format!("EXTRA {}\n", head(a)));
+            for line in a.alert.lines() {
+                out.push_str(&format!("  + {line}\n"));
+            }
+        }
+        for (want, got) in &self.changed {
+            out.push_str(&format!("CHANGED {}\n", head(want)));
+            let got_lines: Vec<&str> = got.alert.lines().collect();
+            let want_lines: Vec<&str> = want.alert.lines().collect();
+            for line in &want_lines {
+                if !got_lines.contains(line) {
+                    out.push_str(&format!("  - {line}\n"));
+                }
+            }
+            for line in &got_lines {
+                if !want_lines.contains(line) {
+                    out.push_str(&format!("  + {line}\n"));
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(timestamp: i64, channel_id: i64, text: &str) -> ExpectedAlert {
+        ExpectedAlert {
+            timestamp,
+            channel_id,
+            channel_title: format!("Ch{channel_id}"),
+            alert: text.into(),
+        }
+    }
+
+    #[test]
+    fn compare_reports_missing_extra_and_changed_alerts() {
+        let expected = [
+            alert(10, 1, "🚀 Балістика\nКиїв"),
+            alert(20, 1, "🛵 Шахеди\nБровари"),
+            alert(30, 2, "✈️ КАБи"),
+        ];
+        let actual = [
+            alert(10, 1, "🚀 Балістика\nКиїв"),
+            alert(20, 1, "🛵 Шахеди\nБориспіль"),
+            alert(40, 2, "🛵 Шахеди"),
+        ];
+        let diff = GoldenDiff::compare(&expected, &actual);
+        assert_eq!(diff.missing, [expected[2].clone()]);
+        assert_eq!(diff.extra, [actual[2].clone()]);
+        assert_eq!(diff.changed, [(expected[1].clone(), actual[1].clone())]);
+        assert_eq!(diff.summary(), "1 missing, 1 extra, 1 changed");
+        assert!(GoldenDiff::compare(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn alerts_from_one_message_are_matched_in_order() {
+        let expected = [alert(10, 1, "first"), alert(10, 1, "second")];
+        let diff = GoldenDiff::compare(&expected, &[alert(10, 1, "first")]);
+        assert!(diff.extra.is_empty() && diff.changed.is_empty());
+        assert_eq!(diff.missing, [expected[1].clone()]);
+
+        let diff = GoldenDiff::compare(&expected, &[alert(10, 1, "second"), alert(10, 1, "x")]);
+        assert!(diff.missing.is_empty() && diff.extra.is_empty());
+        assert_eq!(diff.changed.len(), 2);
+
+        let actual = [
+            alert(10, 1, "first"),
+            alert(10, 1, "second"),
+            alert(10, 1, "third"),
+        ];
+        let diff = GoldenDiff::compare(&expected, &actual);
+        assert_eq!(diff.extra, [actual[2].clone()]);
+        assert!(diff.missing.is_empty() && diff.changed.is_empty());
+    }
+
+    #[test]
+    fn load_expected_keeps_only_forwarded_alerts() {
+        let path = std::env::temp_dir().join(format!("golden_load_{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"decision":"alert","timestamp":10,"channel_id":1,"channel_title":"Ch1","alert":"🚀 Балістика"}"#,
+                "\n\n",
+                r#"{"decision":"suppressed","timestamp":11,"channel_id":1,"alert":"dup"}"#,
+                "\n",
+                r#"{"timestamp":12,"channel_id":2,"alert":null}"#,
+                "\n",
+                r#"{"timestamp":13,"channel_id":2,"alert":"🛵 Шахеди"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let loaded = load_expected(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            loaded,
+            [
+                alert(10, 1, "🚀 Балістика"),
+                ExpectedAlert {
+                    channel_title: String::new(),
+                    ..alert(13, 2, "🛵 Шахеди")
+                }
+            ]
+        );
+
+        write_expected(path.to_str().unwrap(), &loaded).unwrap();
+        assert_eq!(load_expected(path.to_str().unwrap()).unwrap(), loaded);
+
+        std::fs::write(&path, "not json\n").unwrap();
+        let err = load_expected(path.to_str().unwrap()).unwrap_err();
+        assert!(format!("{err:#}").contains(":1"), "{err:#}");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn render_lists_each_difference() {
+        let diff = GoldenDiff::compare(
+            &[
+                alert(10, 1, "gone"),
+                alert(20, 1, "🛵 Шахеди\nБровари\nкурс на Київ"),
+            ],
+            &[
+                alert(20, 1, "🛵 Шахеди\nБориспіль\nкурс на Київ"),
+                alert(30, 2, "new\nalert"),
+            ],
+        );
+        assert_eq!(
+            diff.render(),
+            "MISSING ts=10 @Ch1\n  - gone\n\
+             EXTRA ts=30 @Ch2\n  + new\n  + alert\n\
+             CHANGED ts=20 @Ch1\n  - Бровари\n  + Бориспіль\n"
+        );
+    }
+}
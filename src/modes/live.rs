@@ -1,23 +1,158 @@
-use crate::{bot, filter, llm, telegram};
-use anyhow::{Context, Result};
-use grammers_client::Update;
+use crate::activity::{ActivityCfg, ActivityMonitor, Spike};
+use crate::decisions::DecisionRecord;
+use crate::errors::FailureMonitor;
+use crate::escalation::{self, EscalationCfg, Escalator};
+use crate::feedback::{self, FeedbackCfg};
+use crate::filter::threat_keywords::KeywordsFile;
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::threat_level::{SharedStatus, StatusTracker, ThreatLevel};
+use crate::hooks::{HookMessage, Hooks};
+use crate::metrics::{Counter, Metrics, SharedMetrics};
+use crate::monitor::SharedMonitor;
+use crate::plugins::WasmPlugins;
+use crate::reminders::{ReminderCfg, Reminders, SharedReminders};
+use crate::routing::{Router, Sink};
+use crate::slo::{SloCfg, SloMonitor};
+use crate::telegram::{AccountHealth, UpdateGapTracker};
+use crate::tts::{Tts, TtsCfg};
+use crate::webhook::{PushPayload, PushSource, WebhookCfg, WebhookSink};
+use crate::{archive, bot, classifier, filter, llm, retention, review, suggest, summary, telegram};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use grammers_client::grammers_tl_types as tl;
+use grammers_client::types::Peer;
+use grammers_client::{Client, InvocationError, Update};
 use reqwest::Client as HttpClient;
-use std::collections::HashSet;
-use tracing::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
 
-use super::shared::load_bot_cfg;
+use super::dump_rotation::RotatingDump;
+use super::priority::{MessageQueue, Prioritizer, PriorityCfg};
+use super::shared::{
+    ArchiveRecorder, BotCfg, DumpEvent, LiveCfg, OutputCfg, StalePolicy, load_bot_cfg,
+    load_live_cfg, load_output_cfg, suppression_reason,
+};
 
 pub(super) async fn run() -> Result<()> {
+    run_monitored(None).await
+}
+
+/// Live mode, feeding `monitor` (the `tui` console) when given.
+pub(super) async fn run_monitored(monitor: Option<SharedMonitor>) -> Result<()> {
     let tg = telegram::load_tg_cfg()?;
     let bot_cfg = load_bot_cfg()?;
+    let live_cfg = load_live_cfg();
+    if let Some(mut keywords) = KeywordsFile::from_env() {
+        if let Some(set) = keywords.refresh()? {
+            info!(
+                "{} keyword stems loaded from {}",
+                set.stem_count(),
+                keywords.path
+            );
+        }
+        if let Some(every) = keywords.reload_every {
+            tokio::spawn(run_keyword_reload_loop(keywords, every));
+        }
+    }
 
     let bot_db = bot::open_db(&bot_cfg.db_path)?;
+    review::ensure_tables(&bot_db)?;
+    let subscribers = bot::SubscriberMirror::new(bot_db.clone());
+    match subscribers.refresh() {
+        Ok(audience) => info!("{} subscriber(s) loaded", audience.subscribers.len()),
+        Err(e) => warn!("Failed to load subscribers, retrying in the background: {e}"),
+    }
+    tokio::spawn(bot::run_subscriber_reconcile_loop(subscribers.clone()));
+    if bot_cfg.shards.shard_count() > 1 {
+        info!(
+            "Broadcasts sharded across {} bot tokens",
+            bot_cfg.shards.shard_count()
+        );
+    }
+    let archive = ArchiveRecorder::from_env()?;
+    let status = StatusTracker::from_env().shared();
+    let metrics = Metrics::shared();
+    match &archive {
+        Some(archive) => {
+            let http = HttpClient::new();
+            let token = bot_cfg.token.clone();
+            let db = bot_db.clone();
+            let archive_db = archive.db().clone();
+            let cfg = summary::MorningSummaryCfg::from_env();
+            tokio::spawn(async move {
+                summary::run_morning_summary_loop(http, token, db, archive_db, cfg).await;
+            });
+        }
+        None => info!("Morning summary disabled – requires ARCHIVE_DB_PATH"),
+    }
     {
+        let db = bot_db.clone();
+        let archive_db = archive.as_ref().map(|a| a.db().clone());
+        let cfg = retention::RetentionCfg::from_env();
+        tokio::spawn(async move {
+            retention::run_retention_loop(db, archive_db, cfg).await;
+        });
+    }
+    if let Some(cfg) = suggest::SuggestCfg::from_env() {
+        let llm = llm::LlmFilter::from_env();
+        match &archive {
+            Some(archive) if llm.is_enabled() => {
+                let archive_db = archive.db().clone();
+                tokio::spawn(async move {
+                    suggest::run_suggestion_loop(archive_db, llm, cfg).await;
+                });
+            }
+            _ => warn!("Keyword suggestions disabled – require ARCHIVE_DB_PATH and the LLM filter"),
+        }
+    }
+    let reminders = ReminderCfg::from_env().map(|cfg| {
+        info!(
+            "Repeat reminders after {} min, at most {}",
+            cfg.after.as_secs() / 60,
+            cfg.max_reminders
+        );
+        Reminders::new(cfg).shared()
+    });
+    if let Some(reminders) = &reminders {
+        let http = HttpClient::new();
+        let token = bot_cfg.token.clone();
+        let reminders = reminders.clone();
+        let status = status.clone();
+        tokio::spawn(run_reminder_loop(http, token, reminders, status));
+    }
+    {
+        let http = HttpClient::new();
+        let token = bot_cfg.token.clone();
+        let subscribers = subscribers.clone();
+        let status = status.clone();
+        let metrics = metrics.clone();
+        let reminders = reminders.clone();
+        let admin_chats = bot_cfg.admin_chats.clone();
+        tokio::spawn(async move {
+            bot::run_bot_polling(
+                http,
+                token,
+                subscribers,
+                status,
+                metrics,
+                reminders,
+                admin_chats,
+            )
+            .await;
+        });
+    }
+    if !bot_cfg.status_pin_chats.is_empty() {
         let http = HttpClient::new();
         let token = bot_cfg.token.clone();
         let db = bot_db.clone();
+        let status = status.clone();
+        let chats = bot_cfg.status_pin_chats.clone();
         tokio::spawn(async move {
-            bot::run_bot_polling(http, token, db).await;
+            bot::run_status_pin_loop(http, token, db, status, chats).await;
         });
     }
 
@@ -32,66 +167,1336 @@ pub(super) async fn run() -> Result<()> {
 
     telegram::ensure_user_login(&client, &tg).await?;
 
-    let mut allowed_peer_ids: HashSet<i64> = HashSet::new();
-    for uname in &tg.channels {
-        let peer = client
-            .resolve_username(uname)
-            .await
-            .with_context(|| format!("resolve_username failed for @{uname}"))?;
-        if let Some(peer) = peer {
-            allowed_peer_ids.insert(peer.id().bare_id());
-            info!("Watching @{uname} (peer_id={})", peer.id().bare_id());
-        }
+    let mut resolved = telegram::resolve_channels(&client, &tg.channels).await?;
+    // Channels named in TG_CHANNELS stay watched whatever the folders contain.
+    let fixed = resolved.peers.clone();
+    if !tg.folders.is_empty() {
+        resolved.merge(telegram::folder_channels(&client, &tg.folders).await?);
+    }
+    let folder_ids: HashMap<i64, String> = resolved.peers[fixed.len()..]
+        .iter()
+        .map(|(name, peer)| (peer.id().bare_id(), name.clone()))
+        .collect();
+    let fixed_ids: HashSet<i64> = fixed.iter().map(|(_, peer)| peer.id().bare_id()).collect();
+    let priority_cfg = PriorityCfg::from_env();
+    let prioritizer = Prioritizer::new(
+        &priority_cfg,
+        resolved
+            .peers
+            .iter()
+            .map(|(uname, peer)| (uname.as_str(), peer.id().bare_id())),
+    );
+    let mut peers = HashMap::new();
+    for (uname, peer) in resolved.peers {
+        info!("Watching @{uname} (peer_id={})", peer.id().bare_id());
+        peers.insert(peer.id().bare_id(), peer);
+    }
+    if peers.is_empty() {
+        return Err(anyhow!("none of TG_CHANNELS could be resolved"));
     }
 
     let mut stream = client.stream_updates(
         updates_rx,
         grammers_client::UpdatesConfiguration {
             catch_up: true,
-            update_queue_limit: Some(2048),
+            update_queue_limit: Some(live_cfg.update_queue_limit),
         },
     );
 
-    let http = HttpClient::new();
-    let mut alert_filter = filter::AlertFilter::from_env();
-    info!("Filter config: {alert_filter}");
+    let admins = AdminNotifier {
+        http: HttpClient::new(),
+        token: bot_cfg.token.clone(),
+        chats: bot_cfg.admin_chats.clone(),
+    };
+    let feedback_cfg = FeedbackCfg::from_env().filter(|cfg| {
+        if cfg.channel_id().is_none() {
+            warn!("FEEDBACK_CHAT_ID must be a channel id (-100…); reaction feedback disabled");
+            return false;
+        }
+        match feedback::ensure_tables(&bot_db) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Reaction feedback disabled: {e}");
+                false
+            }
+        }
+    });
+    let feedback_db = bot_db.clone();
+    let mut pipeline = Pipeline {
+        alert_filter: filter::AlertFilter::from_env(),
+        llm_filter: classifier::SecondaryFilter::from_env().with_metrics(metrics.clone()),
+        archive,
+        dump: RotatingDump::from_env()?,
+        decisions: RotatingDump::decisions_from_env()?,
+        status,
+        metrics,
+        http: HttpClient::new(),
+        bot_cfg,
+        bot_db,
+        subscribers,
+        live_cfg: live_cfg.clone(),
+        output: load_output_cfg(),
+        hooks: Hooks::from_env(),
+        plugins: WasmPlugins::from_env(),
+        escalation: None,
+        reminders,
+        tts: TtsCfg::from_env().map(|cfg| {
+            info!("Voice messages for critical alerts: {}", cfg.describe());
+            Tts::new(cfg, HttpClient::new())
+        }),
+        activity: ActivityCfg::from_env().map(|cfg| {
+            info!(
+                "Posting-rate warning: {}× baseline over {}s",
+                cfg.factor, cfg.window_secs
+            );
+            ActivityMonitor::new(cfg)
+        }),
+        webhook: None,
+        router: Router::from_env()?,
+        failures: FailureAlerts {
+            monitor: FailureMonitor::from_env(),
+            admins: admins.clone(),
+        },
+        slo: SloAlerts {
+            monitor: SloMonitor::new(SloCfg::from_env()),
+            admins: admins.clone(),
+        },
+        feedback: feedback_cfg.clone(),
+        wave_messages: HashMap::new(),
+        monitor,
+    };
+    match EscalationCfg::from_env() {
+        Some(cfg) => {
+            info!(
+                "Voice-call escalation enabled for {} phone(s)",
+                cfg.phones.len()
+            );
+            pipeline.escalation = Some(Escalator::new(cfg, HttpClient::new()));
+        }
+        None => info!("Voice-call escalation disabled"),
+    }
+    if pipeline.router.rule_count() > 0 {
+        info!("Alert routing: {} rule(s)", pipeline.router.rule_count());
+    }
+    if let Some(cfg) = WebhookCfg::from_env() {
+        info!("Webhook sink enabled: {}", cfg.url);
+        pipeline.webhook = Some(WebhookSink::new(cfg, HttpClient::new()));
+    }
+    if let Some(interval) = live_cfg.account_check_interval {
+        let client = client.clone();
+        let admins = admins.clone();
+        let session_path = tg.session_path.clone();
+        tokio::spawn(async move {
+            run_account_health_loop(client, admins, session_path, interval).await;
+        });
+    }
+    let (folder_tx, mut folder_rx) = watch::channel(Vec::new());
+    if !tg.folders.is_empty()
+        && let Some(interval) = live_cfg.folder_sync_interval
+    {
+        let client = client.clone();
+        let folders = tg.folders.clone();
+        let admins = admins.clone();
+        tokio::spawn(async move {
+            let known = FolderSync {
+                channels: folder_ids,
+                fixed: fixed_ids,
+            };
+            run_folder_sync_loop(client, folders, interval, folder_tx, admins, known).await;
+        });
+    }
+    if !resolved.warnings.is_empty() {
+        let report = format!(
+            "⚠️ Channel resolution:\n• {}",
+            resolved.warnings.join("\n• ")
+        );
+        admins.send(&report).await;
+    }
+    info!("Filter config: {}", pipeline.alert_filter);
+    info!("Secondary filter: {}", pipeline.llm_filter);
+    if pipeline.hooks.is_enabled() {
+        info!("External command hooks enabled");
+    }
+    if let Some(cfg) = &feedback_cfg {
+        info!(
+            "Reaction feedback enabled: control channel {}, corpus {}",
+            cfg.chat_id, cfg.corpus_path
+        );
+    }
+
+    if let Some(span) = live_cfg.warmup {
+        warm_up(&client, &peers, &mut pipeline.alert_filter, span).await;
+    }
 
-    let llm_filter = llm::LlmFilter::from_env();
-    info!("LLM filter: {llm_filter}");
+    let queue = Arc::new(MessageQueue::new(prioritizer, priority_cfg.max_wait));
+    {
+        let db = feedback_db.clone();
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            run_trust_refresh_loop(db, queue, review::TrustCfg::from_env()).await;
+        });
+    }
+    let monitor = pipeline.monitor.clone();
+    {
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            pipeline.run(&queue).await;
+        });
+    }
+
+    let mut gaps = UpdateGapTracker::default();
+    let mut last_gap_alert: Option<Instant> = None;
+
+    let mut outage: Option<Outage> = None;
+    let mut recovery: Option<Recovery> = None;
 
     info!("Running in live mode. Waiting for new messages...");
     loop {
-        let Ok(update) = stream.next().await else {
-            warn!("Update stream ended.");
-            break;
+        // While recovering, wake up in time to send the health summary even
+        // if no further updates arrive.
+        let next = match recovery.as_ref().map(|r| r.report_at) {
+            Some(at) => tokio::time::timeout_at(at.into(), stream.next()).await.ok(),
+            None => Some(stream.next().await),
+        };
+        if recovery
+            .as_ref()
+            .is_some_and(|r| Instant::now() >= r.report_at)
+            && let Some(r) = recovery.take()
+        {
+            let report = r.report(&gaps, &peers, live_cfg.backfill_gaps);
+            info!("{report}");
+            admins.send(&report).await;
+        }
+        let Some(next) = next else {
+            continue;
         };
 
+        let update = match next {
+            Ok(update) => update,
+            Err(InvocationError::Dropped) => {
+                warn!("Update stream ended.");
+                break;
+            }
+            Err(e) if telegram::is_auth_error(&e) => {
+                warn!("Telegram session is no longer authorized: {e}");
+                if let Some(report) =
+                    AccountHealth::Unauthorized(e.to_string()).report(&tg.session_path)
+                {
+                    admins.send(&format!("{report}\nLive mode stopped.")).await;
+                }
+                break;
+            }
+            Err(e) => {
+                let o = outage.get_or_insert_with(Outage::new);
+                o.errors += 1;
+                o.last_error = e.to_string();
+                let backoff = Duration::from_secs(1 << o.errors.min(6));
+                warn!(
+                    "Update stream error #{} ({e}); retrying in {backoff:?}",
+                    o.errors
+                );
+                tokio::time::sleep(backoff).await;
+                continue;
+            }
+        };
+        if let Some(o) = outage.take() {
+            info!("Update stream recovered after {:?}", o.since.elapsed());
+            recovery = Some(Recovery::new(o, &gaps));
+        }
+
+        if folder_rx.has_changed().unwrap_or(false) {
+            let watch_list: Vec<(String, Peer)> = fixed
+                .iter()
+                .cloned()
+                .chain(folder_rx.borrow_and_update().iter().cloned())
+                .collect();
+            peers = watch_list
+                .iter()
+                .map(|(_, peer)| (peer.id().bare_id(), peer.clone()))
+                .collect();
+            queue.set_prioritizer(Prioritizer::new(
+                &priority_cfg,
+                watch_list
+                    .iter()
+                    .map(|(name, peer)| (name.as_str(), peer.id().bare_id())),
+            ));
+            info!("Watch list updated: {} channel(s)", peers.len());
+        }
+
+        if let Update::Raw(raw) = &update
+            && let tl::enums::Update::MessageReactions(u) = &raw.raw
+            && let Some(cfg) = &feedback_cfg
+            && let tl::enums::Peer::Channel(peer) = &u.peer
+            && cfg.channel_id() == Some(peer.channel_id)
+        {
+            record_feedback(&feedback_db, cfg, u.msg_id, &u.reactions);
+            continue;
+        }
+
         if let Update::NewMessage(msg) = update {
             let Ok(peer) = msg.peer() else {
                 continue;
             };
-            if !allowed_peer_ids.contains(&peer.id().bare_id()) {
+            let channel_id = peer.id().bare_id();
+            let Some(watched) = peers.get(&channel_id) else {
                 continue;
+            };
+            let title = peer.name().unwrap_or("<unknown>");
+            if let Some(r) = &mut recovery {
+                *r.caught_up.entry(channel_id).or_default() += 1;
             }
+
+            if let Some(missing) = gaps.observe(channel_id, msg.id()) {
+                warn!(
+                    "@{title}: {} post(s) missing before id={} (queue overflow or deletions; total missed={})",
+                    missing.len(),
+                    msg.id(),
+                    gaps.total_missed()
+                );
+                if missing.len() >= live_cfg.gap_alert_threshold
+                    && recovery.is_none()
+                    && last_gap_alert.is_none_or(|ts| ts.elapsed() >= GAP_ALERT_COOLDOWN)
+                {
+                    last_gap_alert = Some(Instant::now());
+                    admins.send(&gap_report(&gaps, &peers)).await;
+                }
+                if live_cfg.backfill_gaps {
+                    let recovered = backfill_gap(&client, watched, title, missing, &queue).await;
+                    if let Some(r) = &mut recovery {
+                        r.backfilled += recovered;
+                    }
+                }
+            }
+
             let text = msg.text().trim();
             if text.is_empty() {
                 continue;
             }
-            let title = peer.name().unwrap_or("<unknown>");
-            let channel_id = peer.id().bare_id();
+            queue.push(channel_id, msg.id(), title, msg.date().timestamp(), text);
+            if let Some(monitor) = &monitor {
+                monitor.lock().unwrap().queue_depth = queue.len();
+            }
+        }
+    }
+
+    let pending = queue.len();
+    if pending > 0 {
+        warn!("Live mode stopping with {pending} message(s) still queued");
+    }
+    Ok(())
+}
+
+/// Minimum delay between admin notifications about update gaps.
+const GAP_ALERT_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Max posts fetched per detected gap during backfill.
+const MAX_BACKFILL_PER_GAP: usize = 100;
+
+/// How long after the update stream recovers to keep collecting catch-up
+/// stats before reporting to admins.
+const RECOVERY_REPORT_DELAY: Duration = Duration::from_secs(60);
+
+/// Queueing delay after which a processed message is logged as a warning.
+const QUEUE_DELAY_WARN: Duration = Duration::from_secs(10);
+
+/// How often threats are checked for expiry while no message arrives.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the filter's internal state sizes are logged.
+const FILTER_STATS_INTERVAL: Duration = Duration::from_secs(600);
+
+/// How often the filter state (`FILTER_STATE_PATH`) is saved when no alert
+/// saved it, so channel context windows survive a restart too.
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A period during which the update stream kept failing.
+struct Outage {
+    since: Instant,
+    errors: u32,
+    last_error: String,
+}
+
+impl Outage {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            errors: 0,
+            last_error: String::new(),
+        }
+    }
+}
+
+/// Catch-up bookkeeping after an outage, reported once `report_at` passes.
+struct Recovery {
+    outage: Outage,
+    downtime: Duration,
+    report_at: Instant,
+    /// Gap counters at recovery time, to tell what this outage added.
+    missed_before: HashMap<i64, u64>,
+    caught_up: HashMap<i64, usize>,
+    backfilled: usize,
+}
+
+impl Recovery {
+    fn new(outage: Outage, gaps: &UpdateGapTracker) -> Self {
+        Self {
+            downtime: outage.since.elapsed(),
+            outage,
+            report_at: Instant::now() + RECOVERY_REPORT_DELAY,
+            missed_before: gaps.missed_by_channel(),
+            caught_up: HashMap::new(),
+            backfilled: 0,
+        }
+    }
+
+    fn report(
+        &self,
+        gaps: &UpdateGapTracker,
+        peers: &HashMap<i64, Peer>,
+        backfill: bool,
+    ) -> String {
+        let name = |id: &i64| {
+            peers
+                .get(id)
+                .and_then(|p| p.name())
+                .unwrap_or("<unknown>")
+                .to_string()
+        };
+        let mut out = format!(
+            "🩺 Update stream recovered after {} min ({} error(s), last: {})",
+            self.downtime.as_secs().div_ceil(60),
+            self.outage.errors,
+            self.outage.last_error
+        );
+
+        let mut missed: Vec<(i64, u64)> = gaps
+            .missed_by_channel()
+            .into_iter()
+            .map(|(id, n)| (id, n - self.missed_before.get(&id).copied().unwrap_or(0)))
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        missed.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        if missed.is_empty() {
+            out.push_str("\nNo missed posts detected.");
+        } else {
+            out.push_str("\nEstimated missed posts:");
+            for (id, n) in missed.iter().take(10) {
+                out.push_str(&format!("\n• {}: {n}", name(id)));
+            }
+        }
+
+        let caught_up: usize = self.caught_up.values().sum();
+        out.push_str(&format!(
+            "\nCaught up: {caught_up} post(s) from {} channel(s)",
+            self.caught_up.len()
+        ));
+        if backfill {
+            out.push_str(&format!(
+                "\nBackfill: ran, {} post(s) recovered",
+                self.backfilled
+            ));
+        } else {
+            out.push_str("\nBackfill: off (GAP_BACKFILL)");
+        }
+        out
+    }
+}
+
+/// Append the record `make` builds to the decisions log and the console,
+/// where kept.
+fn log_decision(
+    log: &mut Option<RotatingDump>,
+    monitor: Option<&SharedMonitor>,
+    make: impl FnOnce() -> DecisionRecord,
+) {
+    if log.is_none() && monitor.is_none() {
+        return;
+    }
+    let record = make();
+    if let Some(log) = log
+        && let Err(e) = log.write(&record)
+    {
+        warn!("Failed to write decisions log: {e}");
+    }
+    if let Some(monitor) = monitor {
+        monitor.lock().unwrap().record_decision(record);
+    }
+}
+
+/// Everything needed to turn one channel message into delivered alerts.
+struct Pipeline {
+    alert_filter: filter::AlertFilter,
+    llm_filter: classifier::SecondaryFilter,
+    archive: Option<ArchiveRecorder>,
+    /// `LIVE_DUMP_DIR`: daily JSONL files of every received message.
+    dump: Option<RotatingDump>,
+    /// `DECISIONS_LOG_DIR`: daily JSONL files of every filter decision.
+    decisions: Option<RotatingDump>,
+    /// The `tui` console's state, in that mode.
+    monitor: Option<SharedMonitor>,
+    status: SharedStatus,
+    /// Counts for `/metrics_summary`.
+    metrics: SharedMetrics,
+    http: HttpClient,
+    bot_cfg: BotCfg,
+    bot_db: bot::SharedDb,
+    /// Broadcast recipients, mirrored so a locked DB does not drop alerts.
+    subscribers: bot::SubscriberMirror,
+    live_cfg: LiveCfg,
+    output: OutputCfg,
+    hooks: Hooks,
+    plugins: WasmPlugins,
+    escalation: Option<Escalator>,
+    /// `REMINDER_AFTER_MINS`: repeat unacknowledged critical alerts.
+    reminders: Option<SharedReminders>,
+    /// Spoken copies of critical alerts for `/voice` chats.
+    tts: Option<Tts>,
+    /// `ACTIVITY_SPIKE_FACTOR`: heads-up when channels suddenly post far
+    /// more than usual.
+    activity: Option<ActivityMonitor>,
+    webhook: Option<WebhookSink>,
+    /// `ALERT_ROUTES`: which sinks get which alerts.
+    router: Router,
+    failures: FailureAlerts,
+    /// Per-sink delivery latency targets.
+    slo: SloAlerts,
+    feedback: Option<FeedbackCfg>,
+    /// Latest broadcast per primary threat kind, so a proximity escalation
+    /// is sent as a reply to the alert it upgrades.
+    wave_messages: HashMap<ThreatKind, bot::SentMessages>,
+}
+
+impl Pipeline {
+    /// Process queued messages forever, highest priority first.
+    async fn run(mut self, queue: &MessageQueue) {
+        let mut stats_logged = Instant::now();
+        let mut state_saved = Instant::now();
+        let trust_cfg = review::TrustCfg::from_env();
+        let mut trust_read: Option<Instant> = None;
+        loop {
+            if trust_read.is_none_or(|t| t.elapsed() >= TRUST_REFRESH_INTERVAL) {
+                self.refresh_channel_trust(&trust_cfg);
+                trust_read = Some(Instant::now());
+            }
+            let Ok(msg) = tokio::time::timeout(EXPIRY_CHECK_INTERVAL, queue.pop()).await else {
+                self.expire_threats().await;
+                continue;
+            };
+            if let Some(monitor) = &self.monitor {
+                monitor.lock().unwrap().queue_depth = queue.len();
+            }
+            let waited = msg.enqueued_at.elapsed();
+            if waited >= QUEUE_DELAY_WARN {
+                warn!(
+                    "{:?} post from @{} waited {waited:?} in queue ({} still queued)",
+                    msg.priority,
+                    msg.title,
+                    queue.len()
+                );
+            }
+            self.handle(
+                msg.channel_id,
+                msg.message_id,
+                &msg.title,
+                msg.timestamp,
+                &msg.text,
+                msg.enqueued_at,
+            )
+            .await;
+            if self.alert_filter.last_alert().is_some()
+                || state_saved.elapsed() >= STATE_SAVE_INTERVAL
+            {
+                self.alert_filter.save_state();
+                state_saved = Instant::now();
+            }
+            self.expire_threats().await;
+            if stats_logged.elapsed() >= FILTER_STATS_INTERVAL {
+                info!("Filter state: {}", self.alert_filter.stats());
+                stats_logged = Instant::now();
+            }
+        }
+    }
+
+    /// Re-read review trust for the filter's confidence score.
+    fn refresh_channel_trust(&mut self, cfg: &review::TrustCfg) {
+        match review::channel_trust(&self.bot_db, cfg.min_decisions) {
+            Ok(trust) => self.alert_filter.set_channel_trust(trust),
+            Err(e) => warn!("Failed to read review trust: {e}"),
+        }
+    }
+
+    /// Broadcast a "probably over" notice for threats that went quiet
+    /// without an all-clear (`THREAT_EXPIRY_MINS`).
+    async fn expire_threats(&mut self) {
+        let Some(notice) = self.alert_filter.expire_threats() else {
+            return;
+        };
+        info!("Threat(s) expired without an all-clear");
+        self.alert_filter.save_state();
+        let result = bot::broadcast(
+            &self.http,
+            &self.bot_cfg.shards,
+            &self.subscribers,
+            &notice,
+            None,
+            bot::SendOpts {
+                silent: true,
+                ..bot::SendOpts::default()
+            },
+            None,
+        )
+        .await;
+        if let Err(e) = result {
+            self.failures.report("expiry notice", e).await;
+        }
+    }
+
+    /// Broadcast a posting-rate spike notice.  Not deduplicated against
+    /// alerts: it precedes them.
+    async fn send_activity_notice(&mut self, spike: &Spike) {
+        info!(
+            "Posting-rate spike: {} message(s) from {} channel(s), baseline {:.1}",
+            spike.messages, spike.channels, spike.baseline
+        );
+        let result = bot::broadcast(
+            &self.http,
+            &self.bot_cfg.shards,
+            &self.subscribers,
+            &spike.notice(),
+            None,
+            bot::SendOpts::default(),
+            None,
+        )
+        .await;
+        if let Err(e) = result {
+            self.failures.report("activity notice", e).await;
+        }
+    }
 
-            let result = alert_filter
-                .process_with_llm(channel_id, title, text, &llm_filter)
-                .await;
+    /// `received`: when the message entered the queue, the start of the
+    /// delivery latency measured for [`SloAlerts`].
+    async fn handle(
+        &mut self,
+        channel_id: i64,
+        message_id: i32,
+        title: &str,
+        timestamp: i64,
+        text: &str,
+        received: Instant,
+    ) {
+        let archived = archive::ArchivedMessage {
+            timestamp,
+            channel_id,
+            channel_title: title.to_string(),
+            text: text.to_string(),
+        };
+        self.metrics.lock().unwrap().incr(Counter::Received);
+        if let Some(monitor) = &self.monitor {
+            monitor.lock().unwrap().record_raw(&archived);
+        }
+        if let Some(dump) = &mut self.dump {
+            let event = DumpEvent {
+                timestamp,
+                channel_id,
+                channel_title: title.to_string(),
+                text: text.to_string(),
+            };
+            if let Err(e) = dump.write(&event) {
+                warn!("Failed to write live dump: {e}");
+            }
+        }
 
-            if let Some(formatted) = result {
-                info!("Alert forwarded from @{title}");
-                if let Err(e) = bot::broadcast(&http, &bot_cfg.token, &bot_db, &formatted).await {
-                    warn!("Failed to broadcast alert: {e}");
+        let age_secs = Utc::now().timestamp() - timestamp;
+        let stale = self
+            .live_cfg
+            .max_message_age_secs
+            .is_some_and(|max| age_secs > max);
+        let spike = self
+            .activity
+            .as_mut()
+            .and_then(|a| a.observe(timestamp, channel_id));
+        if let Some(spike) = spike.filter(|_| !stale) {
+            self.send_activity_notice(&spike).await;
+        }
+        if stale && self.live_cfg.stale_policy == StalePolicy::Drop {
+            debug!("Dropping stale message from @{title} ({age_secs}s old)");
+            if let Some(archive) = &self.archive {
+                archive.record_raw(&archived);
+            }
+            self.output.emit(&archived, None, None);
+            log_decision(&mut self.decisions, self.monitor.as_ref(), || {
+                DecisionRecord::new(&archived, None, None, "stale", None)
+            });
+            self.metrics.lock().unwrap().suppress("stale");
+            return;
+        }
+
+        let mut msg = HookMessage {
+            channel_id,
+            channel_title: title.to_string(),
+            timestamp,
+            text: text.to_string(),
+        };
+        if !self.hooks.pre_filter(&mut msg).await {
+            debug!("Message from @{title} dropped by pre-filter hook");
+            if let Some(archive) = &self.archive {
+                archive.record_raw(&archived);
+            }
+            log_decision(&mut self.decisions, self.monitor.as_ref(), || {
+                DecisionRecord::new(&archived, None, None, "pre_filter_hook", None)
+            });
+            self.metrics.lock().unwrap().suppress("pre_filter_hook");
+            return;
+        }
+        let text = msg.text.as_str();
+        self.metrics
+            .lock()
+            .unwrap()
+            .observe_mentions(timestamp, &filter::detect_threats(&text.to_lowercase()));
+
+        let mut result = self
+            .alert_filter
+            .process_with_llm(channel_id, title, text, &self.llm_filter)
+            .await;
+        if stale && let Some(formatted) = &mut result {
+            formatted.insert_str(0, &format!("🕓 Із запізненням ({} хв)\n", age_secs / 60));
+        }
+        self.plugins
+            .apply(&msg, self.alert_filter.last_alert(), &mut result);
+        self.hooks
+            .post_detection(&msg, self.alert_filter.last_alert(), &mut result)
+            .await;
+
+        if let Some(archive) = &self.archive {
+            archive.record(&self.alert_filter, &archived);
+        }
+        let queued = self
+            .alert_filter
+            .last_review()
+            .map(|candidate| review::enqueue(&self.bot_db, &archived, candidate));
+        if let Some(Err(e)) = queued {
+            self.failures.report("review queue", e).await;
+        }
+        self.output
+            .emit(&archived, self.alert_filter.last_alert(), result.as_deref());
+        log_decision(&mut self.decisions, self.monitor.as_ref(), || {
+            DecisionRecord::new(
+                &archived,
+                self.alert_filter.last_alert(),
+                result.as_deref(),
+                suppression_reason(&self.alert_filter),
+                self.alert_filter.last_verdict(),
+            )
+        });
+
+        if let Some(monitor) = &self.monitor {
+            let mut monitor = monitor.lock().unwrap();
+            monitor.set_waves(self.alert_filter.waves());
+            monitor.set_attack(self.alert_filter.wave().cloned());
+        }
+
+        if let Some(info) = self.alert_filter.last_alert() {
+            let level = self.status.lock().unwrap().record(info, title, text);
+            debug!("Threat level now {level:?}");
+        }
+
+        let Some(mut formatted) = result else {
+            let reason = suppression_reason(&self.alert_filter);
+            self.metrics.lock().unwrap().suppress(reason);
+            return;
+        };
+        if self
+            .alert_filter
+            .last_alert()
+            .is_some_and(|i| !i.threats.contains(&ThreatKind::AllClear))
+            && let Some(note) = self.status.lock().unwrap().duration_note()
+        {
+            formatted.push('\n');
+            formatted.push_str(&note);
+        }
+        if !self.hooks.pre_broadcast(&msg, &mut formatted).await {
+            debug!("Alert from @{title} dropped by pre-broadcast hook");
+            self.metrics.lock().unwrap().suppress("pre_broadcast_hook");
+            return;
+        }
+        match self.alert_filter.channel_reliability(channel_id) {
+            Some(score) => info!("Alert forwarded from @{title} (reliability {score:.2})"),
+            None => info!("Alert forwarded from @{title}"),
+        }
+        let info = self.alert_filter.last_alert();
+        {
+            let mut metrics = self.metrics.lock().unwrap();
+            metrics.incr(Counter::Forwarded);
+            metrics.record_alert(timestamp, info.map_or(&[], |i| i.threats.as_slice()));
+        }
+        let route = self.router.route(info);
+        debug!("Route: {route:?}");
+        if info.is_some_and(|i| i.threats.contains(&ThreatKind::AllClear)) {
+            self.wave_messages.clear();
+            if let Some(reminders) = &self.reminders {
+                reminders.lock().unwrap().clear();
+            }
+        }
+        let silent = !route.has(Sink::Telegram);
+        // Only loud, fresh alerts are worth nagging about.
+        let remind = !silent
+            && !stale
+            && self
+                .reminders
+                .as_ref()
+                .zip(info)
+                .is_some_and(|(r, i)| r.lock().unwrap().wants(i));
+        let opts = bot::SendOpts {
+            silent,
+            ack_button: remind,
+        };
+        if route.has(Sink::Telegram) || route.has(Sink::TelegramSilent) {
+            let primary = info.and_then(|i| i.primary());
+            let reply_to = info
+                .filter(|i| i.escalated_from.is_some())
+                .and(primary)
+                .and_then(|kind| self.wave_messages.get(&kind));
+            match bot::broadcast(
+                &self.http,
+                &self.bot_cfg.shards,
+                &self.subscribers,
+                &formatted,
+                reply_to,
+                opts,
+                primary,
+            )
+            .await
+            {
+                Ok(sent) => {
+                    if !sent.is_empty() {
+                        self.slo.observe(Sink::Telegram, received).await;
+                    }
+                    if remind
+                        && let Some(reminders) = &self.reminders
+                        && let Some(info) = info
+                    {
+                        reminders.lock().unwrap().arm(
+                            Instant::now(),
+                            info,
+                            &formatted,
+                            bot::reminders_for(&self.bot_cfg.shards, &sent),
+                        );
+                    }
+                    if !sent.is_empty()
+                        && let Err(e) = feedback::record_delivery(
+                            &self.bot_db,
+                            &archived,
+                            &formatted,
+                            info.map_or(&[], |i| i.threats.as_slice()),
+                            &sent,
+                        )
+                    {
+                        warn!("Failed to record alert delivery: {e}");
+                    }
+                    if !silent
+                        && !stale
+                        && let Some(tts) = &self.tts
+                        && let Some(info) = info.filter(|i| escalation::is_critical(i))
+                    {
+                        let voice = send_voice(
+                            tts,
+                            &self.http,
+                            &self.bot_cfg,
+                            &self.subscribers,
+                            info,
+                            &sent,
+                        )
+                        .await;
+                        if let Err(e) = voice {
+                            self.failures.report("voice message", e).await;
+                        }
+                    }
+                    if let Some(kind) = primary
+                        && kind != ThreatKind::AllClear
+                        && !sent.is_empty()
+                    {
+                        self.wave_messages.insert(kind, sent);
+                    }
+                }
+                Err(e) => {
+                    self.metrics.lock().unwrap().incr(Counter::BroadcastFailed);
+                    self.failures.report("broadcast", e).await
+                }
+            }
+        }
+        if route.has(Sink::Exec) {
+            self.router.spawn_exec(info, &formatted);
+        }
+        if let Some(cfg) = &self.feedback {
+            match bot::send_reply(
+                &self.http,
+                &self.bot_cfg.token,
+                cfg.chat_id,
+                &formatted,
+                None,
+            )
+            .await
+            {
+                Ok(id) => {
+                    if let Err(e) = feedback::record_sent(&self.bot_db, id, &archived, &formatted) {
+                        self.failures.report("feedback", e).await;
+                    }
                 }
+                Err(e) => self.failures.report("feedback channel", e).await,
+            }
+        }
+        if route.has(Sink::Webhook)
+            && let Some(webhook) = &self.webhook
+            && let Some(info) = self.alert_filter.last_alert()
+        {
+            // One alert per post, so the alert index is always 0 here.
+            let source = PushSource {
+                channel_id,
+                channel_title: title,
+                message_id,
+                alert_index: 0,
+                timestamp,
+            };
+            let payload = PushPayload::new(info, &source, &formatted);
+            if webhook.send(&payload).await {
+                self.slo.observe(Sink::Webhook, received).await;
+            }
+        }
+
+        // Ring phones only for fresh alerts; a late call does more harm than good.
+        if !stale
+            && route.has(Sink::Voice)
+            && let Some(escalation) = &mut self.escalation
+            && let Some(info) = self.alert_filter.last_alert()
+        {
+            let placed = if route.explicit {
+                escalation.escalate(info, text).await
+            } else {
+                escalation.maybe_escalate(info, text).await
+            };
+            if placed {
+                self.slo.observe(Sink::Voice, received).await;
             }
         }
     }
+}
 
-    Ok(())
+/// Read `info` out to the `/voice` chats of a delivered broadcast.
+async fn send_voice(
+    tts: &Tts,
+    http: &HttpClient,
+    bot_cfg: &BotCfg,
+    subscribers: &bot::SubscriberMirror,
+    info: &filter::AlertInfo,
+    sent: &bot::SentMessages,
+) -> Result<()> {
+    let audio = tts.synthesize(&escalation::spoken_message(info)).await?;
+    bot::broadcast_voice(http, &bot_cfg.shards, subscribers, &audio, sent).await
+}
+
+/// Sends health notifications to `ADMIN_CHAT_IDS`.
+#[derive(Clone)]
+struct AdminNotifier {
+    http: HttpClient,
+    token: String,
+    chats: Vec<i64>,
+}
+
+impl AdminNotifier {
+    async fn send(&self, text: &str) {
+        for &chat_id in &self.chats {
+            if let Err(e) = bot::send_message(&self.http, &self.token, chat_id, text).await {
+                warn!("Failed to notify admin chat_id={chat_id}: {e}");
+            }
+        }
+    }
+}
+
+/// Logs pipeline failures by class and tells admins about repeated hard
+/// ones (see [`crate::errors`]).
+struct FailureAlerts {
+    monitor: FailureMonitor,
+    admins: AdminNotifier,
+}
+
+impl FailureAlerts {
+    async fn report(&mut self, component: &'static str, err: anyhow::Error) {
+        if let Some(note) = self.monitor.report(component, &err) {
+            self.admins.send(&note).await;
+        }
+    }
+}
+
+/// Tracks per-sink delivery latency and tells admins when a sink misses or
+/// meets its target again (see [`crate::slo`]).
+struct SloAlerts {
+    monitor: SloMonitor,
+    admins: AdminNotifier,
+}
+
+impl SloAlerts {
+    async fn observe(&mut self, sink: Sink, received: Instant) {
+        let latency = received.elapsed();
+        debug!("{} delivery took {latency:?}", sink.name());
+        if let Some(note) = self.monitor.observe(sink, latency) {
+            warn!("{note}");
+            self.admins.send(&note).await;
+        }
+    }
+}
+
+/// Folder channels watched so far, for the folder sync reports.
+struct FolderSync {
+    /// Channel id → name.
+    channels: HashMap<i64, String>,
+    /// `TG_CHANNELS` ids: watched either way, so never reported as added
+    /// or removed when a folder gains or loses them.
+    fixed: HashSet<i64>,
+}
+
+impl FolderSync {
+    /// Names of the channels `current` adds and drops, sorted.
+    fn diff<'a>(&'a self, current: &'a HashMap<i64, String>) -> (Vec<&'a str>, Vec<&'a str>) {
+        let changed = |from: &'a HashMap<i64, String>, to: &HashMap<i64, String>| {
+            let mut names: Vec<&str> = from
+                .iter()
+                .filter(|(id, _)| !to.contains_key(id) && !self.fixed.contains(id))
+                .map(|(_, name)| name.as_str())
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        (
+            changed(current, &self.channels),
+            changed(&self.channels, current),
+        )
+    }
+}
+
+/// Re-reads the chat folders every `interval` and publishes their channels
+/// (and tells admins) whenever the set changes.
+async fn run_folder_sync_loop(
+    client: Client,
+    folders: Vec<String>,
+    interval: Duration,
+    tx: watch::Sender<Vec<(String, Peer)>>,
+    admins: AdminNotifier,
+    mut known: FolderSync,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let synced = match telegram::folder_channels(&client, &folders).await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Folder sync failed: {e}");
+                continue;
+            }
+        };
+        let current: HashMap<i64, String> = synced
+            .peers
+            .iter()
+            .map(|(name, peer)| (peer.id().bare_id(), name.clone()))
+            .collect();
+        let (added, removed) = known.diff(&current);
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+        let mut report = format!(
+            "📁 Folder sync: now {} channel(s) from {}",
+            current.len(),
+            folders.join(", ")
+        );
+        if !added.is_empty() {
+            report.push_str(&format!("\n+ {}", added.join(", ")));
+        }
+        if !removed.is_empty() {
+            report.push_str(&format!("\n− {}", removed.join(", ")));
+        }
+        info!("{report}");
+        admins.send(&report).await;
+        known.channels = current;
+        if tx.send(synced.peers).is_err() {
+            return;
+        }
+    }
+}
+
+/// Checks the session right away and then every `interval`, notifying admins
+/// when its state changes.  Stops once the session is unauthorized, since
+/// that needs a manual re-login.
+async fn run_account_health_loop(
+    client: Client,
+    admins: AdminNotifier,
+    session_path: String,
+    interval: Duration,
+) {
+    let mut last = AccountHealth::Ok;
+    loop {
+        let health = telegram::check_account_health(&client).await;
+        match &health {
+            AccountHealth::Ok => debug!("Account health check: OK"),
+            AccountHealth::Unknown(e) => warn!("Account health check failed: {e}"),
+            other => warn!("Account health check: {other:?}"),
+        }
+        // Transient failures say nothing about the account; keep the last state.
+        if !matches!(health, AccountHealth::Unknown(_))
+            && std::mem::discriminant(&health) != std::mem::discriminant(&last)
+        {
+            match health.report(&session_path) {
+                Some(report) => admins.send(&report).await,
+                None => admins.send("✅ Telegram session is healthy again.").await,
+            }
+            if matches!(health, AccountHealth::Unauthorized(_)) {
+                return;
+            }
+            last = health;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// How often review verdicts are re-read to demote untrusted channels and
+/// weigh channels in the confidence score.
+const TRUST_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Runs forever: processes channels whose review trust dropped below
+/// `cfg.low_trust` at low priority.
+async fn run_trust_refresh_loop(
+    db: bot::SharedDb,
+    queue: Arc<MessageQueue>,
+    cfg: review::TrustCfg,
+) {
+    let mut demoted = HashSet::new();
+    loop {
+        match review::low_trust_channels(&db, &cfg) {
+            Ok(untrusted) if untrusted != demoted => {
+                info!("Review trust: {} channel(s) demoted", untrusted.len());
+                queue.set_untrusted(untrusted.clone());
+                demoted = untrusted;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to read review trust: {e}"),
+        }
+        tokio::time::sleep(TRUST_REFRESH_INTERVAL).await;
+    }
+}
+
+/// How often due reminders are checked.
+const REMINDER_TICK: Duration = Duration::from_secs(15);
+
+/// Runs forever: sends due reminders from the primary bot and drops them
+/// all once the threat status is back to clear.
+async fn run_reminder_loop(
+    http: HttpClient,
+    bot_token: String,
+    reminders: SharedReminders,
+    status: SharedStatus,
+) {
+    loop {
+        tokio::time::sleep(REMINDER_TICK).await;
+        if reminders.lock().unwrap().is_empty() {
+            continue;
+        }
+        if status.lock().unwrap().current().0 == ThreatLevel::Clear {
+            info!("Wave over; pending reminders dropped");
+            reminders.lock().unwrap().clear();
+            continue;
+        }
+        let due = reminders.lock().unwrap().due(Instant::now());
+        for reminder in due {
+            if let Err(e) = bot::send_reminder(&http, &bot_token, &reminder).await {
+                warn!("Failed to remind chat_id={}: {e}", reminder.chat_id);
+            }
+        }
+    }
+}
+
+/// Runs forever: reinstalls the `KEYWORDS_PATH` lists whenever the file
+/// changes, keeping the current ones when it cannot be read or parsed.
+async fn run_keyword_reload_loop(mut keywords: KeywordsFile, every: Duration) {
+    loop {
+        tokio::time::sleep(every).await;
+        match keywords.refresh() {
+            Ok(Some(set)) => info!(
+                "Keywords reloaded: {} stems from {}",
+                set.stem_count(),
+                keywords.path
+            ),
+            Ok(None) => {}
+            Err(e) => warn!("Keeping the current keywords: {e:#}"),
+        }
+    }
+}
+
+/// Turn the reaction counts on a control-channel post into a label and
+/// refresh the eval corpus when it changes.
+fn record_feedback(
+    db: &bot::SharedDb,
+    cfg: &FeedbackCfg,
+    msg_id: i32,
+    reactions: &tl::enums::MessageReactions,
+) {
+    let tl::enums::MessageReactions::Reactions(reactions) = reactions;
+    let votes = |emoji: &str| -> i64 {
+        reactions
+            .results
+            .iter()
+            .map(|tl::enums::ReactionCount::Count(c)| c)
+            .filter(|c| matches!(&c.reaction, tl::enums::Reaction::Emoji(e) if e.emoticon == emoji))
+            .map(|c| i64::from(c.count))
+            .sum()
+    };
+    let (up, down) = (votes(feedback::UPVOTE), votes(feedback::DOWNVOTE));
+    match feedback::record_votes(db, i64::from(msg_id), up, down) {
+        Ok(true) => {
+            info!("Feedback on control post {msg_id}: 👍{up} 👎{down}");
+            if let Err(e) = feedback::export_corpus(db, &cfg.corpus_path) {
+                warn!("Failed to export feedback corpus: {e}");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => warn!("Failed to record feedback for control post {msg_id}: {e}"),
+    }
+}
+
+fn gap_report(gaps: &UpdateGapTracker, peers: &HashMap<i64, Peer>) -> String {
+    let mut out = format!(
+        "⚠️ Update gaps detected: {} post(s) missed so far.\nMost affected:",
+        gaps.total_missed()
+    );
+    for (channel_id, missed, count) in gaps.most_affected(5) {
+        let name = peers
+            .get(&channel_id)
+            .and_then(|p| p.name())
+            .unwrap_or("<unknown>");
+        out.push_str(&format!("\n• {name}: {missed} missed in {count} gap(s)"));
+    }
+    out
+}
+
+/// Posts read per channel at most during the startup warmup.
+const MAX_WARMUP_PER_CHANNEL: usize = 50;
+
+/// Feed the last `span` of every watched channel into the filter's context
+/// windows, oldest first and without alerting, so trigger-word and location
+/// inference work right after a restart.
+async fn warm_up(
+    client: &Client,
+    peers: &HashMap<i64, Peer>,
+    filter: &mut filter::AlertFilter,
+    span: Duration,
+) {
+    let now = Utc::now().timestamp();
+    let since = now - span.as_secs() as i64;
+    let mut posts = Vec::new();
+    for (channel_id, peer) in peers {
+        let title = peer.name().unwrap_or("<unknown>").to_string();
+        let mut iter = client.iter_messages(peer).limit(MAX_WARMUP_PER_CHANNEL);
+        loop {
+            match iter.next().await {
+                Ok(Some(msg)) if msg.date().timestamp() >= since => {
+                    let text = msg.text().trim();
+                    if !text.is_empty() {
+                        posts.push((
+                            msg.date().timestamp(),
+                            *channel_id,
+                            title.clone(),
+                            text.to_string(),
+                        ));
+                    }
+                }
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("Warmup for @{title} failed: {e}");
+                    break;
+                }
+            }
+        }
+    }
+    posts.sort_by_key(|(ts, channel_id, ..)| (*ts, *channel_id));
+    for (ts, channel_id, title, text) in &posts {
+        let age = Duration::from_secs((now - ts).max(0) as u64);
+        filter.warm_context(*channel_id, title, text, age);
+    }
+    info!(
+        "Warmup: {} post(s) from the last {} min seeded {} context window(s)",
+        posts.len(),
+        span.as_secs() / 60,
+        filter.stats().channel_contexts
+    );
+}
+
+/// Fetch the posts in `missing` by id and queue them (oldest first) ahead of
+/// the message that revealed the gap.  Returns the number of posts recovered.
+async fn backfill_gap(
+    client: &Client,
+    peer: &Peer,
+    title: &str,
+    missing: Range<i32>,
+    queue: &MessageQueue,
+) -> usize {
+    let ids: Vec<i32> = missing.collect();
+    let ids = &ids[ids.len().saturating_sub(MAX_BACKFILL_PER_GAP)..];
+    let messages = match client.get_messages_by_id(peer, ids).await {
+        Ok(m) => m,
+        Err(e) => {
+            warn!("Backfill for @{title} failed: {e}");
+            return 0;
+        }
+    };
+    let mut recovered = 0usize;
+    for msg in messages.into_iter().flatten() {
+        let text = msg.text().trim();
+        if text.is_empty() {
+            continue;
+        }
+        recovered += 1;
+        queue.push(
+            peer.id().bare_id(),
+            msg.id(),
+            title,
+            msg.date().timestamp(),
+            text,
+        );
+    }
+    info!(
+        "Backfilled {recovered} of {} missing post(s) for @{title}",
+        ids.len()
+    );
+    recovered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(list: &[(i64, &str)]) -> HashMap<i64, String> {
+        list.iter()
+            .map(|(id, name)| (*id, name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn folder_diff_ignores_channels_watched_anyway() {
+        // @kpszsu is in TG_CHANNELS and in the folder, so startup left it
+        // out of the folder channels.
+        let known = FolderSync {
+            channels: channels(&[(2, "radar"), (3, "monitor")]),
+            fixed: HashSet::from([1, 9]),
+        };
+        let same = channels(&[(1, "kpszsu"), (2, "radar"), (3, "monitor")]);
+        assert_eq!(known.diff(&same), (vec![], vec![]));
+
+        let changed = channels(&[(2, "radar"), (4, "zeta"), (5, "alpha")]);
+        assert_eq!(
+            known.diff(&changed),
+            (vec!["alpha", "zeta"], vec!["monitor"])
+        );
+
+        // A TG_CHANNELS entry moving into a folder is no news either.
+        let mut known = known;
+        known.channels = changed;
+        let with_fixed = channels(&[(2, "radar"), (4, "zeta"), (5, "alpha"), (9, "official")]);
+        assert_eq!(known.diff(&with_fixed), (vec![], vec![]));
+    }
 }
@@ -1,7 +1,14 @@
+mod compact;
+mod dump_rotation;
 mod dump_today;
+mod golden;
 mod live;
+mod priority;
 mod replay;
+mod search;
+mod service;
 mod shared;
+mod tui;
 
 use anyhow::Result;
 
@@ -10,9 +17,25 @@ enum RunMode {
     Live,
     DumpToday,
     Replay,
+    Search,
+    Compact,
+    Service,
+    Tui,
 }
 
 impl RunMode {
+    /// A leading `search`, `compact`, `service` or `tui` CLI argument
+    /// selects that mode; otherwise the mode comes from `RUN_MODE`.
+    fn from_args_and_env(args: &[String]) -> Self {
+        match args.first().map(String::as_str) {
+            Some("search") => Self::Search,
+            Some("compact") => Self::Compact,
+            Some("service") => Self::Service,
+            Some("tui") => Self::Tui,
+            _ => Self::from_env(),
+        }
+    }
+
     fn from_env() -> Self {
         let raw = std::env::var("RUN_MODE").unwrap_or_else(|_| "live".into());
         match raw.trim().to_lowercase().as_str() {
@@ -24,9 +47,18 @@ impl RunMode {
 }
 
 pub async fn run_from_env() -> Result<()> {
-    match RunMode::from_env() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mode = RunMode::from_args_and_env(&args);
+    if !matches!(mode, RunMode::Search | RunMode::Compact | RunMode::Service) {
+        crate::config_report::log_startup_config();
+    }
+    match mode {
         RunMode::Live => live::run().await,
         RunMode::DumpToday => dump_today::run().await,
         RunMode::Replay => replay::run().await,
+        RunMode::Search => search::run(&args[1..]),
+        RunMode::Compact => compact::run(&args[1..]),
+        RunMode::Service => service::run(&args[1..]),
+        RunMode::Tui => tui::run().await,
     }
 }
@@ -0,0 +1,279 @@
+//! Priority queue between the update reader and the alert pipeline in live
+//! mode.  Messages from official channels and messages mentioning ballistic /
+//! hypersonic threats jump ahead of backlog from chatty aggregators, so a
+//! burst of low-value posts can't delay the ones that matter.
+
+use crate::filter::threat_keywords;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Processing priority, highest last (so `Ord` sorts by importance).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Priority {
+    /// Channels listed in `LOW_PRIORITY_CHANNELS`, or with low review trust.
+    Low,
+    Normal,
+    /// Channels listed in `PRIORITY_CHANNELS`.
+    Official,
+    /// Ballistic / hypersonic keywords, from any channel.
+    Critical,
+}
+
+impl Priority {
+    const COUNT: usize = 4;
+
+    fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+/// One channel message waiting for the pipeline.
+pub(super) struct QueuedMessage {
+    pub channel_id: i64,
+    /// Telegram message id within the channel.
+    pub message_id: i32,
+    pub title: String,
+    pub timestamp: i64,
+    pub text: String,
+    pub priority: Priority,
+    pub enqueued_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct PriorityCfg {
+    /// Lowercased usernames (without `@`).
+    pub official: HashSet<String>,
+    pub low: HashSet<String>,
+    /// A message waiting this long is processed next regardless of priority,
+    /// so low lanes can't starve.
+    pub max_wait: Duration,
+}
+
+impl PriorityCfg {
+    /// | Env var                 | Default | Description                                  |
+    /// |-------------------------|---------|----------------------------------------------|
+    /// | `PRIORITY_CHANNELS`     | —       | Official channels processed ahead of others  |
+    /// | `LOW_PRIORITY_CHANNELS` | —       | Chatty aggregators processed last            |
+    /// | `PRIORITY_MAX_WAIT_SECS`| `30`    | Max queueing delay before a post is promoted |
+    pub fn from_env() -> Self {
+        let names = |key: &str| -> HashSet<String> {
+            std::env::var(key)
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().trim_start_matches('@').to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let max_wait_secs: u64 = std::env::var("PRIORITY_MAX_WAIT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        Self {
+            official: names("PRIORITY_CHANNELS"),
+            low: names("LOW_PRIORITY_CHANNELS"),
+            max_wait: Duration::from_secs(max_wait_secs),
+        }
+    }
+}
+
+/// Assigns a [`Priority`] to incoming messages.
+pub(super) struct Prioritizer {
+    official: HashSet<i64>,
+    low: HashSet<i64>,
+    /// Channels demoted by the review queue (see [`crate::review`]).
+    untrusted: HashSet<i64>,
+}
+
+impl Prioritizer {
+    /// `channels` maps resolved usernames to channel ids.
+    pub fn new<'a>(cfg: &PriorityCfg, channels: impl IntoIterator<Item = (&'a str, i64)>) -> Self {
+        let mut official = HashSet::new();
+        let mut low = HashSet::new();
+        for (uname, id) in channels {
+            let uname = uname.trim_start_matches('@').to_lowercase();
+            if cfg.official.contains(&uname) {
+                official.insert(id);
+            } else if cfg.low.contains(&uname) {
+                low.insert(id);
+            }
+        }
+        Self {
+            official,
+            low,
+            untrusted: HashSet::new(),
+        }
+    }
+
+    pub fn classify(&self, channel_id: i64, text: &str) -> Priority {
+        if threat_keywords::mentions_critical_threat(&text.to_lowercase()) {
+            Priority::Critical
+        } else if self.official.contains(&channel_id) {
+            Priority::Official
+        } else if self.low.contains(&channel_id) || self.untrusted.contains(&channel_id) {
+            Priority::Low
+        } else {
+            Priority::Normal
+        }
+    }
+}
+
+/// FIFO lanes per priority, shared between the update reader and the
+/// pipeline worker.
+pub(super) struct MessageQueue {
+    prioritizer: Mutex<Prioritizer>,
+    lanes: Mutex<[VecDeque<QueuedMessage>; Priority::COUNT]>,
+    ready: Notify,
+    max_wait: Duration,
+}
+
+impl MessageQueue {
+    pub fn new(prioritizer: Prioritizer, max_wait: Duration) -> Self {
+        Self {
+            prioritizer: Mutex::new(prioritizer),
+            lanes: Mutex::new(Default::default()),
+            ready: Notify::new(),
+            max_wait,
+        }
+    }
+
+    pub fn push(&self, channel_id: i64, message_id: i32, title: &str, timestamp: i64, text: &str) {
+        let priority = self.prioritizer.lock().unwrap().classify(channel_id, text);
+        let msg = QueuedMessage {
+            channel_id,
+            message_id,
+            title: title.to_string(),
+            timestamp,
+            text: text.to_string(),
+            priority,
+            enqueued_at: Instant::now(),
+        };
+        self.lanes.lock().unwrap()[priority.lane()].push_back(msg);
+        self.ready.notify_one();
+    }
+
+    /// Swap in a prioritizer built for a changed watch list, keeping the
+    /// trust-based demotions.
+    pub fn set_prioritizer(&self, prioritizer: Prioritizer) {
+        let mut current = self.prioritizer.lock().unwrap();
+        let untrusted = std::mem::take(&mut current.untrusted);
+        *current = Prioritizer {
+            untrusted,
+            ..prioritizer
+        };
+    }
+
+    /// Replace the set of channels demoted for low review trust.
+    pub fn set_untrusted(&self, channels: HashSet<i64>) {
+        self.prioritizer.lock().unwrap().untrusted = channels;
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.lock().unwrap().iter().map(VecDeque::len).sum()
+    }
+
+    /// Wait for the next message: the oldest one past `max_wait` if any,
+    /// otherwise the head of the highest non-empty lane.
+    pub async fn pop(&self) -> QueuedMessage {
+        loop {
+            if let Some(msg) = self.try_pop(Instant::now()) {
+                return msg;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    fn try_pop(&self, now: Instant) -> Option<QueuedMessage> {
+        let mut lanes = self.lanes.lock().unwrap();
+        let overdue = lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, lane)| lane.front().map(|m| (i, m.enqueued_at)))
+            .filter(|(_, at)| now.duration_since(*at) >= self.max_wait)
+            .min_by_key(|(_, at)| *at)
+            .map(|(i, _)| i);
+        let lane = overdue.or_else(|| lanes.iter().rposition(|l| !l.is_empty()))?;
+        lanes[lane].pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFICIAL: i64 = 1;
+    const CHATTY: i64 = 2;
+    const OTHER: i64 = 3;
+
+    fn queue(max_wait: Duration) -> MessageQueue {
+        let cfg = PriorityCfg {
+            official: HashSet::from(["kpszsu".to_string()]),
+            low: HashSet::from(["chatty".to_string()]),
+            max_wait,
+        };
+        let prioritizer = Prioritizer::new(
+            &cfg,
+            [("@KPSZSU", OFFICIAL), ("chatty", CHATTY), ("other", OTHER)],
+        );
+        MessageQueue::new(prioritizer, max_wait)
+    }
+
+    fn drain(queue: &MessageQueue, now: Instant) -> Vec<(String, Priority)> {
+        std::iter::from_fn(|| queue.try_pop(now))
+            .map(|m| (m.text, m.priority))
+            .collect()
+    }
+
+    #[test]
+    fn higher_lanes_are_served_first() {
+        let q = queue(Duration::from_secs(30));
+        q.push(CHATTY, 0, "chatty", 1, "chatty 1");
+        q.push(OTHER, 0, "other", 2, "other");
+        q.push(OFFICIAL, 0, "kpszsu", 3, "official");
+        q.push(CHATTY, 0, "chatty", 4, "Балістика на Київ");
+        q.push(CHATTY, 0, "chatty", 5, "chatty 2");
+        assert_eq!(q.len(), 5);
+        assert_eq!(
+            drain(&q, Instant::now()),
+            [
+                ("Балістика на Київ".to_string(), Priority::Critical),
+                ("official".to_string(), Priority::Official),
+                ("other".to_string(), Priority::Normal),
+                ("chatty 1".to_string(), Priority::Low),
+                ("chatty 2".to_string(), Priority::Low),
+            ]
+        );
+        assert_eq!(q.len(), 0);
+    }
+
+    #[test]
+    fn untrusted_channels_are_demoted() {
+        let q = queue(Duration::from_secs(30));
+        q.set_untrusted(HashSet::from([OTHER]));
+        q.push(OTHER, 0, "other", 1, "other");
+        q.push(CHATTY, 0, "chatty", 2, "chatty");
+        q.push(OFFICIAL, 0, "kpszsu", 3, "official");
+        let order: Vec<_> = drain(&q, Instant::now()).into_iter().map(|m| m.0).collect();
+        assert_eq!(order, ["official", "other", "chatty"]);
+    }
+
+    #[test]
+    fn a_starved_message_is_promoted_after_max_wait() {
+        let max_wait = Duration::from_secs(30);
+        let q = queue(max_wait);
+        q.push(CHATTY, 0, "chatty", 1, "starved");
+        let later = Instant::now() + max_wait;
+        // Sustained official traffic, all of it newer than the chatty post.
+        for i in 0..3 {
+            q.push(OFFICIAL, 0, "kpszsu", 2 + i, &format!("official {i}"));
+        }
+        let now = Instant::now();
+        assert_eq!(q.try_pop(now).unwrap().text, "official 0");
+        q.push(OFFICIAL, 0, "kpszsu", 5, "official 3");
+
+        let order: Vec<_> = drain(&q, later).into_iter().map(|m| m.0).collect();
+        assert_eq!(order[0], "starved", "oldest overdue message first");
+        assert_eq!(order[1..], ["official 1", "official 2", "official 3"]);
+    }
+}
@@ -1,10 +1,20 @@
-use crate::{bot, filter, llm};
-use anyhow::{Result, anyhow};
+use crate::filter::clock::SimulatedClock;
+use crate::filter::threat_keywords::KeywordsFile;
+use crate::hooks::{HookMessage, Hooks};
+use crate::metrics::{Counter, Metrics};
+use crate::plugins::WasmPlugins;
+use crate::{bot, classifier, filter};
+use anyhow::{Context, Result, anyhow};
 use reqwest::Client as HttpClient;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{info, warn};
 
-use super::shared::{load_bot_cfg, load_dump_events, load_replay_cfg};
+use super::golden::{self, ExpectedAlert, GoldenDiff};
+use super::shared::{
+    ArchiveRecorder, OutputFormat, load_bot_cfg, load_dump_events, load_output_cfg,
+    load_replay_cfg, suppression_reason,
+};
 
 pub(super) async fn run() -> Result<()> {
     let replay = load_replay_cfg()?;
@@ -13,8 +23,22 @@ pub(super) async fn run() -> Result<()> {
         return Err(anyhow!("Replay input is empty: {}", replay.input_path));
     }
 
-    let llm_filter = llm::LlmFilter::from_env();
-    let mut alert_filter = filter::AlertFilter::from_env();
+    if let Some(mut keywords) = KeywordsFile::from_env()
+        && let Some(set) = keywords.refresh()?
+    {
+        info!(
+            "{} keyword stems loaded from {}",
+            set.stem_count(),
+            keywords.path
+        );
+    }
+
+    let metrics = Metrics::shared();
+    let llm_filter = classifier::SecondaryFilter::from_env().with_metrics(metrics.clone());
+    // Dedup windows and cooldowns age by the dump's timestamps, not by the
+    // (sped-up) replay pace.
+    let clock = Arc::new(SimulatedClock::new());
+    let mut alert_filter = filter::AlertFilter::from_env().with_clock(clock.clone());
     info!(
         "Replay started: {} events from {}",
         events.len(),
@@ -25,22 +49,35 @@ pub(super) async fn run() -> Result<()> {
         replay.from_line, replay.to_line, replay.limit
     );
     info!("Filter config: {alert_filter}");
-    info!("LLM filter: {llm_filter}");
+    info!("Secondary filter: {llm_filter}");
 
     let mut bot_ctx = None;
     if replay.broadcast {
         let bot_cfg = load_bot_cfg()?;
         let db = bot::open_db(&bot_cfg.db_path)?;
-        bot_ctx = Some((HttpClient::new(), bot_cfg.token, db));
+        bot_ctx = Some((
+            HttpClient::new(),
+            bot_cfg.shards,
+            bot::SubscriberMirror::new(db),
+        ));
         info!("Replay broadcast enabled; alerts will be sent to bot subscribers");
     }
 
+    let archive = ArchiveRecorder::from_env()?;
+    let output = load_output_cfg();
+    let hooks = Hooks::from_env();
+    let mut plugins = WasmPlugins::from_env();
+
     let mut forwarded = 0usize;
     let mut suppressed = 0usize;
+    let mut actual: Vec<ExpectedAlert> = Vec::new();
 
     for (idx, event) in events.iter().enumerate() {
         if idx > 0 {
             let prev = &events[idx - 1];
+            clock.advance(Duration::from_secs(
+                (event.timestamp - prev.timestamp).max(0) as u64,
+            ));
             let delay_ms = if let Some(step_ms) = replay.fixed_step_ms {
                 step_ms
             } else {
@@ -53,22 +90,84 @@ pub(super) async fn run() -> Result<()> {
             }
         }
 
-        let result = alert_filter
+        let mut msg = HookMessage {
+            channel_id: event.channel_id,
+            channel_title: event.channel_title.clone(),
+            timestamp: event.timestamp,
+            text: event.text.clone(),
+        };
+        metrics.lock().unwrap().incr(Counter::Received);
+        if !hooks.pre_filter(&mut msg).await {
+            suppressed += 1;
+            metrics.lock().unwrap().suppress("pre_filter_hook");
+            continue;
+        }
+        metrics.lock().unwrap().observe_mentions(
+            event.timestamp,
+            &filter::detect_threats(&msg.text.to_lowercase()),
+        );
+
+        let mut result = alert_filter
             .process_with_llm(
                 event.channel_id,
                 &event.channel_title,
-                &event.text,
+                &msg.text,
                 &llm_filter,
             )
             .await;
+        plugins.apply(&msg, alert_filter.last_alert(), &mut result);
+        hooks
+            .post_detection(&msg, alert_filter.last_alert(), &mut result)
+            .await;
+        let reason = suppression_reason(&alert_filter);
+        if let Some(formatted) = &mut result
+            && !hooks.pre_broadcast(&msg, formatted).await
+        {
+            metrics.lock().unwrap().suppress("pre_broadcast_hook");
+            result = None;
+        } else if result.is_none() {
+            metrics.lock().unwrap().suppress(reason);
+        }
+
+        let archived = event.to_archived();
+        if let Some(archive) = &archive {
+            archive.record(&alert_filter, &archived);
+        }
+        output.emit(&archived, alert_filter.last_alert(), result.as_deref());
 
         if let Some(formatted) = result {
             forwarded += 1;
-            if let Some((http, token, db)) = &bot_ctx {
-                if let Err(e) = bot::broadcast(http, token, db, &formatted).await {
+            {
+                let mut metrics = metrics.lock().unwrap();
+                metrics.incr(Counter::Forwarded);
+                let threats = alert_filter.last_alert().map_or(&[][..], |i| &i.threats);
+                metrics.record_alert(event.timestamp, threats);
+            }
+            if replay.expected_path.is_some() {
+                actual.push(ExpectedAlert {
+                    timestamp: event.timestamp,
+                    channel_id: event.channel_id,
+                    channel_title: event.channel_title.clone(),
+                    alert: formatted.clone(),
+                });
+            }
+            if let Some((http, bots, subscribers)) = &bot_ctx {
+                let primary = alert_filter.last_alert().and_then(|i| i.primary());
+                if let Err(e) = bot::broadcast(
+                    http,
+                    bots,
+                    subscribers,
+                    &formatted,
+                    None,
+                    bot::SendOpts::default(),
+                    primary,
+                )
+                .await
+                {
                     warn!("Failed to broadcast replayed alert: {e}");
+                    metrics.lock().unwrap().incr(Counter::BroadcastFailed);
                 }
-            } else {
+            } else if output.format == OutputFormat::Text {
                 println!("\n[REPLAY ALERT {}]\n{}\n", idx + 1, formatted);
             }
         } else {
@@ -82,6 +181,31 @@ pub(super) async fn run() -> Result<()> {
         forwarded,
         suppressed
     );
+    let misses = llm_filter.replay_misses();
+    if misses > 0 {
+        warn!("{misses} LLM prompts had no recorded response (kept keyword verdict)");
+    }
+    if let Some(path) = &replay.metrics_path {
+        std::fs::write(path, metrics.lock().unwrap().openmetrics())
+            .with_context(|| format!("failed to write {path}"))?;
+        info!("Wrote replay metrics to {path}");
+    }
+
+    if let Some(path) = &replay.expected_path {
+        if replay.update_expected {
+            golden::write_expected(path, &actual)?;
+            info!("Wrote {} expected alert(s) to {path}", actual.len());
+            return Ok(());
+        }
+        let expected = golden::load_expected(path)?;
+        let diff = GoldenDiff::compare(&expected, &actual);
+        if !diff.is_empty() {
+            // stderr, so JSON output on stdout stays parseable.
+            eprint!("{}", diff.render());
+            return Err(anyhow!("Replay does not match {path}: {}", diff.summary()));
+        }
+        info!("Replay matches {path} ({} alert(s))", expected.len());
+    }
 
     Ok(())
 }
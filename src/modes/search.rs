@@ -0,0 +1,140 @@
+use crate::archive::{self, SearchQuery};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDate};
+
+/// `search <terms...> [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--channel NAME] [--limit N]`
+/// `search --strikes [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--limit N]`
+/// `search --languages [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--channel NAME] [--limit N]`
+///
+/// Dates are UTC calendar days; `--to` is inclusive.  `--strikes` lists
+/// alert waves that were followed by damage reports instead of messages;
+/// `--languages` reports each channel's UA/RU/mixed/Latin script mix per
+/// week, for the `--limit` busiest channels.
+pub(super) fn run(args: &[String]) -> Result<()> {
+    let path = archive::archive_path_from_env()
+        .ok_or_else(|| anyhow!("ARCHIVE_DB_PATH must be set for search"))?;
+    let strikes = args.iter().any(|a| a == "--strikes");
+    let languages = args.iter().any(|a| a == "--languages");
+    let args: Vec<String> = args
+        .iter()
+        .filter(|a| *a != "--strikes" && *a != "--languages")
+        .cloned()
+        .collect();
+    let query = parse_args(&args, strikes || languages)?;
+    let db = archive::open_archive(&path)?;
+
+    if languages {
+        let channels =
+            archive::script_stats(&db, query.from_ts, query.to_ts, query.channel.as_deref())?;
+        for channel in channels.iter().take(query.limit) {
+            println!(
+                "📡 {} (id={}): {} message(s) – {}",
+                channel.channel_title,
+                channel.channel_id,
+                channel.total.total(),
+                channel.total.describe()
+            );
+            for (week, counts) in &channel.weeks {
+                println!("  {week}: {:>5} – {}", counts.total(), counts.describe());
+            }
+            println!();
+        }
+        println!("{} channel(s)", channels.len());
+        return Ok(());
+    }
+
+    if strikes {
+        let waves = archive::confirmed_strikes(&db, query.from_ts, query.to_ts, query.limit)?;
+        for wave in &waves {
+            println!(
+                "[{} UTC] wave #{}: {} alert(s), threats: {}",
+                fmt_ts(wave.wave_start),
+                wave.wave_id,
+                wave.alert_count,
+                wave.threats
+            );
+            for report in &wave.reports {
+                println!(
+                    "  💥 [{} UTC] 📡 {}\n  {}",
+                    fmt_ts(report.timestamp),
+                    report.channel_title,
+                    report.text.replace('\n', "\n  ")
+                );
+            }
+            println!();
+        }
+        println!("{} confirmed strike wave(s)", waves.len());
+        return Ok(());
+    }
+
+    let hits = archive::search(&db, &query)?;
+    for hit in &hits {
+        let when = fmt_ts(hit.timestamp);
+        println!(
+            "[{when} UTC] 📡 {} (id={})",
+            hit.channel_title, hit.channel_id
+        );
+        let sources = archive::other_sources(&db, hit)?;
+        if !sources.is_empty() {
+            let also: Vec<String> = sources
+                .iter()
+                .map(|(ts, title)| format!("{title} ({})", fmt_ts(*ts)))
+                .collect();
+            println!("↪ also posted by {}", also.join(", "));
+        }
+        println!("{}\n", hit.text);
+    }
+    println!("{} result(s)", hits.len());
+    Ok(())
+}
+
+fn fmt_ts(ts: i64) -> String {
+    DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+fn parse_args(args: &[String], listing: bool) -> Result<SearchQuery> {
+    fn day_start(raw: &str) -> Result<i64> {
+        let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+            .with_context(|| format!("invalid date {raw:?}, expected YYYY-MM-DD"))?;
+        Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+    }
+
+    let mut terms: Vec<&str> = Vec::new();
+    let mut query = SearchQuery {
+        terms: String::new(),
+        from_ts: None,
+        to_ts: None,
+        channel: None,
+        limit: 50,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow!("{arg} requires a value"));
+        match arg.as_str() {
+            "--from" => query.from_ts = Some(day_start(value()?)?),
+            "--to" => query.to_ts = Some(day_start(value()?)? + 24 * 60 * 60),
+            "--channel" => query.channel = Some(value()?.clone()),
+            "--limit" => {
+                query.limit = value()?
+                    .parse()
+                    .with_context(|| format!("{arg} must be a positive integer"))?
+            }
+            other => terms.push(other),
+        }
+    }
+
+    query.terms = terms.join(" ");
+    if !listing
+        && query.terms.trim().is_empty()
+        && query.from_ts.is_none()
+        && query.channel.is_none()
+    {
+        return Err(anyhow!(
+            "usage: search <terms...> [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--channel NAME] [--limit N]"
+        ));
+    }
+    Ok(query)
+}
@@ -0,0 +1,492 @@
+use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+const USAGE: &str = "usage: service install|uninstall|print [--name NAME] [--log-file PATH] \
+                     [--user] [--platform systemd|launchd|windows]";
+
+/// Service manager the unit is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Platform {
+    Systemd,
+    Launchd,
+    /// A Task Scheduler task started at boot (or logon with `--user`).
+    Windows,
+}
+
+impl Platform {
+    fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::Launchd
+        } else {
+            Self::Systemd
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "systemd" | "linux" => Some(Self::Systemd),
+            "launchd" | "macos" => Some(Self::Launchd),
+            "windows" | "schtasks" => Some(Self::Windows),
+            _ => None,
+        }
+    }
+}
+
+/// What the service runs and where.
+#[derive(Debug, Clone)]
+struct ServiceSpec {
+    name: String,
+    exe: PathBuf,
+    /// Where `.env` and the session / SQLite files live.
+    workdir: PathBuf,
+    log_file: PathBuf,
+    /// Per-user unit (systemd `--user`, a LaunchAgent, a logon task)
+    /// instead of a system-wide one.
+    user: bool,
+    /// Account a system-wide systemd / launchd service runs as: whoever
+    /// ran `sudo`, so the files in `workdir` stay theirs.
+    run_as: Option<String>,
+}
+
+/// `service install|uninstall|print [--name NAME] [--log-file PATH] [--user]
+/// [--platform systemd|launchd|windows]`
+///
+/// Runs live mode in the background on a home PC or a server: `install`
+/// registers the current binary as a systemd unit, a launchd job or a
+/// Windows boot-time task that starts in the current directory (so `.env`
+/// and the Telegram session are found), appends its output to
+/// `--log-file` (default `./logs/<name>.log`) and is restarted when it
+/// exits.  `print` shows the unit without installing it.  Log in
+/// interactively once before installing: the service cannot prompt for the
+/// login code.
+pub(super) fn run(args: &[String]) -> Result<()> {
+    let action = args
+        .first()
+        .ok_or_else(|| anyhow!("missing action; {USAGE}"))?
+        .clone();
+    let mut name = "tg-osint".to_string();
+    let mut log_file = None;
+    let mut user = false;
+    let mut platform = Platform::current();
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow!("{arg} requires a value"));
+        match arg.as_str() {
+            "--name" => name = value()?.clone(),
+            "--log-file" => log_file = Some(PathBuf::from(value()?)),
+            "--user" => user = true,
+            "--platform" => {
+                let raw = value()?;
+                platform = Platform::parse(raw)
+                    .ok_or_else(|| anyhow!("unknown platform {raw:?}; {USAGE}"))?;
+            }
+            other => return Err(anyhow!("unknown argument {other:?}; {USAGE}")),
+        }
+    }
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.".contains(c))
+    {
+        return Err(anyhow!(
+            "--name may only hold letters, digits, '-', '_' and '.'"
+        ));
+    }
+
+    let workdir = std::env::current_dir().context("cannot read the current directory")?;
+    let spec = ServiceSpec {
+        log_file: absolute(
+            &workdir,
+            log_file.unwrap_or_else(|| Path::new("logs").join(format!("{name}.log"))),
+        ),
+        name,
+        exe: std::env::current_exe().context("cannot locate the running binary")?,
+        workdir,
+        user,
+        run_as: std::env::var("SUDO_USER")
+            .ok()
+            .filter(|u| !user && !u.is_empty() && u != "root"),
+    };
+    match action.as_str() {
+        "print" => {
+            println!("{}", render(platform, &spec));
+            Ok(())
+        }
+        "install" => install(platform, &spec),
+        "uninstall" => uninstall(platform, &spec),
+        other => Err(anyhow!("unknown action {other:?}; {USAGE}")),
+    }
+}
+
+fn absolute(base: &Path, path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
+
+fn render(platform: Platform, spec: &ServiceSpec) -> String {
+    match platform {
+        Platform::Systemd => systemd_unit(spec),
+        Platform::Launchd => launchd_plist(spec),
+        Platform::Windows => task_xml(spec),
+    }
+}
+
+fn systemd_unit(spec: &ServiceSpec) -> String {
+    let log = systemd_path(&spec.log_file);
+    format!(
+        "[Unit]\n\
+         Description=tg-osint alert relay ({name})\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         {run_as}\
+         WorkingDirectory={workdir}\n\
+         ExecStart=\"{exe}\"\n\
+         Environment=RUN_MODE=live\n\
+         Environment=NO_COLOR=1\n\
+         Restart=always\n\
+         RestartSec=10\n\
+         StandardOutput=append:{log}\n\
+         StandardError=append:{log}\n\
+         \n\
+         [Install]\n\
+         WantedBy={target}\n",
+        name = spec.name,
+        workdir = systemd_path(&spec.workdir),
+        exe = systemd_path(&spec.exe)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "$$"),
+        run_as = spec
+            .run_as
+            .as_ref()
+            .map(|u| format!("User={u}\n"))
+            .unwrap_or_default(),
+        target = if spec.user {
+            "default.target"
+        } else {
+            "multi-user.target"
+        },
+    )
+}
+
+/// A path as a systemd setting value: `%` starts a specifier.
+fn systemd_path(path: &Path) -> String {
+    path.display().to_string().replace('%', "%%")
+}
+
+/// launchd job label, reverse-DNS as launchd expects.
+fn launchd_label(spec: &ServiceSpec) -> String {
+    format!("org.tg-osint.{}", spec.name)
+}
+
+fn launchd_plist(spec: &ServiceSpec) -> String {
+    let log = xml_escape(&spec.log_file.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>{run_as}
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+    </array>
+    <key>WorkingDirectory</key>
+    <string>{workdir}</string>
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>RUN_MODE</key>
+        <string>live</string>
+        <key>NO_COLOR</key>
+        <string>1</string>
+    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>ThrottleInterval</key>
+    <integer>10</integer>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = launchd_label(spec),
+        run_as = spec
+            .run_as
+            .as_ref()
+            .map(|u| format!(
+                "\n    <key>UserName</key>\n    <string>{}</string>",
+                xml_escape(u)
+            ))
+            .unwrap_or_default(),
+        exe = xml_escape(&spec.exe.display().to_string()),
+        workdir = xml_escape(&spec.workdir.display().to_string()),
+    )
+}
+
+/// Task Scheduler definition: started at boot (at logon with `--user`),
+/// never timed out, restarted every minute after a failure.  `cmd.exe`
+/// appends the output to the log file.
+fn task_xml(spec: &ServiceSpec) -> String {
+    let (trigger, principal) = if spec.user {
+        (
+            "<LogonTrigger><Enabled>true</Enabled></LogonTrigger>",
+            "<LogonType>InteractiveToken</LogonType><RunLevel>LeastPrivilege</RunLevel>",
+        )
+    } else {
+        (
+            "<BootTrigger><Enabled>true</Enabled></BootTrigger>",
+            "<UserId>S-1-5-18</UserId><RunLevel>HighestAvailable</RunLevel>",
+        )
+    };
+    let command = format!(
+        "/c set RUN_MODE=live&amp;&amp; set NO_COLOR=1&amp;&amp; \"{}\" &gt;&gt; \"{}\" 2&gt;&amp;1",
+        xml_escape(&spec.exe.display().to_string()),
+        xml_escape(&spec.log_file.display().to_string()),
+    );
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>tg-osint alert relay ({name})</Description>
+  </RegistrationInfo>
+  <Triggers>
+    {trigger}
+  </Triggers>
+  <Principals>
+    <Principal id="Author">{principal}</Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <RunOnlyIfNetworkAvailable>false</RunOnlyIfNetworkAvailable>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>cmd.exe</Command>
+      <Arguments>{command}</Arguments>
+      <WorkingDirectory>{workdir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        name = xml_escape(&spec.name),
+        workdir = xml_escape(&spec.workdir.display().to_string()),
+    )
+}
+
+fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Where the unit file is written.
+fn unit_path(platform: Platform, spec: &ServiceSpec) -> Result<PathBuf> {
+    let home = || {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("HOME is not set"))
+    };
+    Ok(match platform {
+        Platform::Systemd if spec.user => home()?
+            .join(".config/systemd/user")
+            .join(format!("{}.service", spec.name)),
+        Platform::Systemd => {
+            PathBuf::from("/etc/systemd/system").join(format!("{}.service", spec.name))
+        }
+        Platform::Launchd if spec.user => home()?
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", launchd_label(spec))),
+        Platform::Launchd => {
+            PathBuf::from("/Library/LaunchDaemons").join(format!("{}.plist", launchd_label(spec)))
+        }
+        // Registered from a file next to the logs; the task lives in
+        // Task Scheduler afterwards.
+        Platform::Windows => spec
+            .log_file
+            .with_file_name(format!("{}.task.xml", spec.name)),
+    })
+}
+
+fn install(platform: Platform, spec: &ServiceSpec) -> Result<()> {
+    if let Some(dir) = spec.log_file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create log directory {}", dir.display()))?;
+    }
+    let path = unit_path(platform, spec)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let unit = render(platform, spec);
+    match platform {
+        // Task Scheduler reads the XML as UTF-16, as declared.
+        Platform::Windows => {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(unit.encode_utf16().flat_map(u16::to_le_bytes));
+            std::fs::write(&path, bytes)
+        }
+        _ => std::fs::write(&path, unit),
+    }
+    .with_context(|| {
+        format!(
+            "failed to write {} (run as root/administrator or pass --user)",
+            path.display()
+        )
+    })?;
+    info!("Service definition written to {}", path.display());
+
+    let path = path.display().to_string();
+    match platform {
+        Platform::Systemd => {
+            let ctl = systemctl(spec);
+            run_cmd(&ctl, &["daemon-reload"])?;
+            run_cmd(&ctl, &["enable", "--now", &spec.name])?;
+        }
+        Platform::Launchd => run_cmd(&["launchctl"], &["load", "-w", &path])?,
+        Platform::Windows => {
+            run_cmd(
+                &["schtasks"],
+                &["/Create", "/F", "/TN", &spec.name, "/XML", &path],
+            )?;
+            run_cmd(&["schtasks"], &["/Run", "/TN", &spec.name])?;
+        }
+    }
+    info!(
+        "Service {} installed; logs go to {}",
+        spec.name,
+        spec.log_file.display()
+    );
+    Ok(())
+}
+
+fn uninstall(platform: Platform, spec: &ServiceSpec) -> Result<()> {
+    let path = unit_path(platform, spec)?;
+    let shown = path.display().to_string();
+    // Stopping a service that is not running is fine; keep going.
+    let stopped = match platform {
+        Platform::Systemd => run_cmd(&systemctl(spec), &["disable", "--now", &spec.name]),
+        Platform::Launchd => run_cmd(&["launchctl"], &["unload", "-w", &shown]),
+        Platform::Windows => run_cmd(&["schtasks"], &["/End", "/TN", &spec.name])
+            .and_then(|()| run_cmd(&["schtasks"], &["/Delete", "/F", "/TN", &spec.name])),
+    };
+    if let Err(e) = stopped {
+        warn!("{e}");
+    }
+    match std::fs::remove_file(&path) {
+        Ok(()) => info!("Removed {shown}"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("failed to remove {shown}")),
+    }
+    if platform == Platform::Systemd {
+        run_cmd(&systemctl(spec), &["daemon-reload"])?;
+    }
+    info!("Service {} uninstalled", spec.name);
+    Ok(())
+}
+
+fn systemctl(spec: &ServiceSpec) -> Vec<&'static str> {
+    if spec.user {
+        vec!["systemctl", "--user"]
+    } else {
+        vec!["systemctl"]
+    }
+}
+
+fn run_cmd(program: &[&str], args: &[&str]) -> Result<()> {
+    let (bin, pre) = program.split_first().expect("program is not empty");
+    let status = Command::new(bin)
+        .args(pre)
+        .args(args)
+        .status()
+        .with_context(|| format!("failed to run {bin}"))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "`{} {}` failed with {status}",
+            program.join(" "),
+            args.join(" ")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(exe: &str, user: bool) -> ServiceSpec {
+        ServiceSpec {
+            name: "tg-osint".into(),
+            exe: exe.into(),
+            workdir: "/home/me/tg osint".into(),
+            log_file: "/home/me/tg osint/logs/tg-osint.log".into(),
+            user,
+            run_as: (!user).then(|| "me".into()),
+        }
+    }
+
+    #[test]
+    fn systemd_unit_runs_live_mode_from_the_workdir() {
+        assert_eq!(
+            render(Platform::Systemd, &spec("/opt/tg osint/tg_osint_v0", false)),
+            "[Unit]\n\
+             Description=tg-osint alert relay (tg-osint)\n\
+             Wants=network-online.target\n\
+             After=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             User=me\n\
+             WorkingDirectory=/home/me/tg osint\n\
+             ExecStart=\"/opt/tg osint/tg_osint_v0\"\n\
+             Environment=RUN_MODE=live\n\
+             Environment=NO_COLOR=1\n\
+             Restart=always\n\
+             RestartSec=10\n\
+             StandardOutput=append:/home/me/tg osint/logs/tg-osint.log\n\
+             StandardError=append:/home/me/tg osint/logs/tg-osint.log\n\
+             \n\
+             [Install]\n\
+             WantedBy=multi-user.target\n"
+        );
+
+        let unit = render(Platform::Systemd, &spec("/opt/tg_osint_v0", true));
+        assert!(!unit.contains("User="));
+        assert!(unit.contains("WantedBy=default.target\n"));
+    }
+
+    #[test]
+    fn systemd_exec_start_escapes_specifiers_quotes_and_variables() {
+        let unit = render(
+            Platform::Systemd,
+            &spec(r#"/opt/100% "new"/$HOME\tg"#, false),
+        );
+        assert!(
+            unit.contains(r#"ExecStart="/opt/100%% \"new\"/$$HOME\\tg""#),
+            "{unit}"
+        );
+    }
+}
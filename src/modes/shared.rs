@@ -1,13 +1,132 @@
+use crate::archive::{self, ArchivedMessage, CorrelationCfg, SharedArchive};
+use crate::bot;
+use crate::filter::{AlertFilter, AlertInfo};
 use anyhow::{Context, Result, anyhow};
 use chrono::{FixedOffset, TimeZone, Utc};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, warn};
 
 #[derive(Clone)]
 pub(super) struct BotCfg {
     pub token: String,
+    /// `BOT_TOKEN` plus `BOT_TOKENS`, for sharded broadcasts.
+    pub shards: bot::BotShards,
     pub db_path: String,
+    /// Chats where the bot keeps a pinned, in-place edited status message.
+    pub status_pin_chats: Vec<i64>,
+    /// Operator chats that receive health notifications (update gaps, …).
+    pub admin_chats: Vec<i64>,
+}
+
+#[derive(Clone)]
+pub(super) struct LiveCfg {
+    pub update_queue_limit: usize,
+    /// Fetch posts missing from the update stream by id and process them.
+    pub backfill_gaps: bool,
+    /// Notify admins when a single gap is at least this many posts.
+    pub gap_alert_threshold: usize,
+    /// Messages whose Telegram date is older than this are stale
+    /// (e.g. replayed by `catch_up` after downtime).  `None` disables the check.
+    pub max_message_age_secs: Option<i64>,
+    pub stale_policy: StalePolicy,
+    /// How often to verify the Telegram session is still usable.  `None`
+    /// disables the check.
+    pub account_check_interval: Option<Duration>,
+    /// How often to re-read `folder:` entries of `TG_CHANNELS`.  `None`
+    /// only reads them at startup.
+    pub folder_sync_interval: Option<Duration>,
+    /// History read at startup to seed the filter's context windows.
+    /// `None` starts with empty windows.
+    pub warmup: Option<Duration>,
+}
+
+/// What live mode does with messages older than `MAX_MESSAGE_AGE_SECS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum StalePolicy {
+    /// Skip the message entirely (it is still archived).
+    Drop,
+    /// Process it, but prefix forwarded alerts with a delay banner.
+    Tag,
+}
+
+/// How replay/live report filter decisions on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OutputFormat {
+    /// Human-readable alert blocks (replay only).
+    Text,
+    /// One JSON object per line, see [`OutputRecord`].
+    Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct OutputCfg {
+    pub format: OutputFormat,
+    /// Also emit a record for every suppressed message (JSON only).
+    pub include_suppressed: bool,
+}
+
+/// One JSON output line.
+#[derive(Debug, Serialize)]
+pub(super) struct OutputRecord<'a> {
+    /// `"forwarded"` or `"suppressed"`.
+    pub decision: &'static str,
+    /// `"new"`, `"update"`, `"escalation"`, `"status"` or `"all_clear"`;
+    /// `None` when suppressed.
+    #[serde(rename = "type")]
+    pub alert_type: Option<&'static str>,
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub channel_title: &'a str,
+    pub threats: Vec<&'static str>,
+    pub proximity: Option<&'static str>,
+    pub nationwide: bool,
+    pub urgent: bool,
+    /// Rendered alert, `None` when suppressed.
+    pub alert: Option<&'a str>,
+    /// Original channel text.
+    pub text: &'a str,
+}
+
+impl OutputCfg {
+    /// Print the decision for one message as a JSON line.  No-op in text
+    /// mode, and for suppressed messages unless `OUTPUT_SUPPRESSED` is set.
+    pub fn emit(&self, msg: &ArchivedMessage, info: Option<&AlertInfo>, alert: Option<&str>) {
+        if self.format != OutputFormat::Json || (alert.is_none() && !self.include_suppressed) {
+            return;
+        }
+        let record = OutputRecord {
+            decision: if alert.is_some() {
+                "forwarded"
+            } else {
+                "suppressed"
+            },
+            alert_type: info
+                .filter(|_| alert.is_some())
+                .map(|i| i.alert_type.name()),
+            timestamp: msg.timestamp,
+            channel_id: msg.channel_id,
+            channel_title: &msg.channel_title,
+            threats: info
+                .map(|i| i.threats.iter().map(|t| t.variant_name()).collect())
+                .unwrap_or_default(),
+            proximity: info.map(|i| i.proximity.name()),
+            nationwide: info.is_some_and(|i| i.nationwide),
+            urgent: info.is_some_and(|i| i.urgent),
+            alert,
+            text: &msg.text,
+        };
+        match serde_json::to_string(&record) {
+            Ok(line) => println!("{line}"),
+            Err(e) => warn!("Failed to serialize output record: {e}"),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -21,6 +140,12 @@ pub(super) struct ReplayCfg {
     pub from_line: Option<usize>,
     pub to_line: Option<usize>,
     pub limit: Option<usize>,
+    /// Golden file of accepted alerts to compare the run against.
+    pub expected_path: Option<String>,
+    /// Rewrite `expected_path` with this run's alerts instead of comparing.
+    pub update_expected: bool,
+    /// OpenMetrics snapshot of the run's counters, written at the end.
+    pub metrics_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +156,79 @@ pub(super) struct DumpEvent {
     pub text: String,
 }
 
+impl DumpEvent {
+    pub fn to_archived(&self) -> ArchivedMessage {
+        ArchivedMessage {
+            timestamp: self.timestamp,
+            channel_id: self.channel_id,
+            channel_title: self.channel_title.clone(),
+            text: self.text.clone(),
+        }
+    }
+}
+
+/// Archive hooks applied to every message that went through the filter:
+/// stores the raw message, forwarded alerts (grouped into waves) and damage
+/// reports linked to the wave that preceded them.
+pub(super) struct ArchiveRecorder {
+    db: SharedArchive,
+    correlation: CorrelationCfg,
+}
+
+impl ArchiveRecorder {
+    /// `None` when `ARCHIVE_DB_PATH` is not configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        Ok(archive::open_from_env()?.map(|db| Self {
+            db,
+            correlation: CorrelationCfg::from_env(),
+        }))
+    }
+
+    pub fn db(&self) -> &SharedArchive {
+        &self.db
+    }
+
+    /// Store `msg` without filter bookkeeping (for messages that bypass the
+    /// filter, e.g. stale ones).
+    pub fn record_raw(&self, msg: &ArchivedMessage) {
+        if let Err(e) = archive::archive_message(&self.db, msg) {
+            warn!("Failed to archive message: {e}");
+        }
+    }
+
+    /// Record `msg` right after `filter` has processed it (the filter's
+    /// [`AlertFilter::last_alert`] tells whether it was forwarded).
+    /// Failures are logged, never propagated – archiving must not stop alert
+    /// delivery.
+    pub fn record(&self, filter: &AlertFilter, msg: &ArchivedMessage) {
+        if let Err(e) = archive::archive_message(&self.db, msg) {
+            warn!("Failed to archive message: {e}");
+        }
+
+        if let Some(info) = filter.last_alert() {
+            if let Err(e) = archive::record_alert(&self.db, msg, info, &self.correlation) {
+                warn!("Failed to archive alert: {e}");
+            }
+        } else if let Some(proximity) =
+            filter.damage_report_proximity(&msg.channel_title, &msg.text)
+        {
+            match archive::record_damage_report(&self.db, msg, proximity, &self.correlation) {
+                Ok(Some(wave_id)) => debug!("Damage report linked to wave {wave_id}"),
+                Ok(None) => debug!("Damage report without a preceding alert wave"),
+                Err(e) => warn!("Failed to archive damage report: {e}"),
+            }
+        }
+    }
+}
+
+/// Metrics label for a message the last `process*` call of `filter`
+/// dropped, or a plugin / post-detection hook dropped after it.
+pub(super) fn suppression_reason(filter: &AlertFilter) -> &'static str {
+    filter
+        .last_suppression()
+        .map_or("post_detection", |s| s.name())
+}
+
 pub(super) fn must_env(key: &str) -> Result<String> {
     std::env::var(key).map_err(|_| anyhow!("Missing env var {key}"))
 }
@@ -43,12 +241,102 @@ pub(super) fn parse_bool_env(key: &str, default: bool) -> bool {
 }
 
 pub(super) fn load_bot_cfg() -> Result<BotCfg> {
+    let token = must_env("BOT_TOKEN")?;
+    let extra = std::env::var("BOT_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .collect();
     Ok(BotCfg {
-        token: must_env("BOT_TOKEN")?,
+        shards: bot::BotShards::new(token.clone(), extra),
+        token,
         db_path: std::env::var("BOT_DB_PATH").unwrap_or_else(|_| "./bot_subscribers.sqlite".into()),
+        status_pin_chats: parse_chat_ids("STATUS_PIN_CHATS"),
+        admin_chats: parse_chat_ids("ADMIN_CHAT_IDS"),
     })
 }
 
+fn parse_chat_ids(key: &str) -> Vec<i64> {
+    std::env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|v| v.trim().parse::<i64>().ok())
+        .collect()
+}
+
+pub(super) fn load_live_cfg() -> LiveCfg {
+    LiveCfg {
+        update_queue_limit: std::env::var("UPDATE_QUEUE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(2048),
+        backfill_gaps: parse_bool_env("GAP_BACKFILL", false),
+        gap_alert_threshold: std::env::var("GAP_ALERT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(20),
+        max_message_age_secs: std::env::var("MAX_MESSAGE_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0),
+        stale_policy: match std::env::var("STALE_MESSAGE_POLICY")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "tag" => StalePolicy::Tag,
+            _ => StalePolicy::Drop,
+        },
+        account_check_interval: Some(
+            std::env::var("ACCOUNT_HEALTH_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(600),
+        )
+        .filter(|v| *v > 0)
+        .map(Duration::from_secs),
+        folder_sync_interval: Some(
+            std::env::var("TG_FOLDER_SYNC_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(300),
+        )
+        .filter(|v| *v > 0)
+        .map(Duration::from_secs),
+        warmup: Some(
+            std::env::var("WARMUP_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(900),
+        )
+        .filter(|v| *v > 0)
+        .map(Duration::from_secs),
+    }
+}
+
+/// | Env var             | Default | Description                              |
+/// |---------------------|---------|------------------------------------------|
+/// | `OUTPUT_FORMAT`     | `text`  | `json` for JSON lines on stdout          |
+/// | `OUTPUT_SUPPRESSED` | `false` | Also emit suppressed messages (JSON)     |
+pub(super) fn load_output_cfg() -> OutputCfg {
+    let format = match std::env::var("OUTPUT_FORMAT")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+        .as_str()
+    {
+        "json" | "jsonl" => OutputFormat::Json,
+        _ => OutputFormat::Text,
+    };
+    OutputCfg {
+        format,
+        include_suppressed: parse_bool_env("OUTPUT_SUPPRESSED", false),
+    }
+}
+
 pub(super) fn load_replay_cfg() -> Result<ReplayCfg> {
     let input_path = must_env("REPLAY_INPUT_PATH")?;
     let speed = std::env::var("REPLAY_SPEED")
@@ -98,6 +386,13 @@ pub(super) fn load_replay_cfg() -> Result<ReplayCfg> {
         from_line,
         to_line,
         limit,
+        expected_path: std::env::var("REPLAY_EXPECTED_PATH")
+            .ok()
+            .filter(|p| !p.trim().is_empty()),
+        update_expected: parse_bool_env("REPLAY_UPDATE_EXPECTED", false),
+        metrics_path: std::env::var("REPLAY_METRICS_PATH")
+            .ok()
+            .filter(|p| !p.trim().is_empty()),
     })
 }
 
@@ -117,9 +412,114 @@ pub(super) fn start_of_today_utc_from_offset(offset_minutes: i32) -> Result<i64>
     Ok(local_start.with_timezone(&Utc).timestamp())
 }
 
+/// Compression applied to a dump file, inferred from its extension
+/// (`.jsonl.gz` → gzip, `.jsonl.zst` → zstd, anything else → plain JSONL).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DumpCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl DumpCompression {
+    pub(super) fn from_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".gz") {
+            Self::Gzip
+        } else if lower.ends_with(".zst") || lower.ends_with(".zstd") {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Open a dump file for line-by-line reading, transparently decompressing
+/// `.gz` / `.zst` inputs.
+pub(super) fn open_dump_reader(path: &str) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path).with_context(|| format!("failed to open dump file {path}"))?;
+    Ok(match DumpCompression::from_path(path) {
+        DumpCompression::None => Box::new(BufReader::new(file)),
+        DumpCompression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        DumpCompression::Zstd => Box::new(BufReader::new(
+            zstd::Decoder::new(file)
+                .with_context(|| format!("failed to init zstd decoder for {path}"))?,
+        )),
+    })
+}
+
+/// Dump file writer.  Compression is picked from the output path extension;
+/// call [`DumpWriter::finish`] so the compressed stream trailer is flushed.
+pub(super) enum DumpWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl DumpWriter {
+    /// Create (or truncate) `path`, creating missing parent directories.
+    pub(super) fn create(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("failed to create parent directory for dump file {path}")
+            })?;
+        }
+
+        let file =
+            File::create(path).with_context(|| format!("failed to create dump file {path}"))?;
+        let writer = BufWriter::new(file);
+        Ok(match DumpCompression::from_path(path) {
+            DumpCompression::None => Self::Plain(writer),
+            DumpCompression::Gzip => Self::Gzip(GzEncoder::new(writer, Compression::default())),
+            DumpCompression::Zstd => Self::Zstd(
+                zstd::Encoder::new(writer, 0)
+                    .with_context(|| format!("failed to init zstd encoder for {path}"))?,
+            ),
+        })
+    }
+
+    /// Serialize one event as a JSONL line.
+    pub(super) fn write_event(&mut self, event: &DumpEvent) -> Result<()> {
+        let line = serde_json::to_string(event)?;
+        self.write_all(line.as_bytes())?;
+        self.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Finalize the compressed stream (if any) and flush to disk.
+    pub(super) fn finish(self) -> Result<()> {
+        let mut inner = match self {
+            Self::Plain(w) => w,
+            Self::Gzip(enc) => enc.finish()?,
+            Self::Zstd(enc) => enc.finish()?,
+        };
+        inner.flush()?;
+        Ok(())
+    }
+}
+
+impl Write for DumpWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(w) => w.write(buf),
+            Self::Gzip(w) => w.write(buf),
+            Self::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(w) => w.flush(),
+            Self::Gzip(w) => w.flush(),
+            Self::Zstd(w) => w.flush(),
+        }
+    }
+}
+
 pub(super) fn load_dump_events(path: &str, replay: &ReplayCfg) -> Result<Vec<DumpEvent>> {
-    let file = File::open(path).with_context(|| format!("failed to open replay file {path}"))?;
-    let reader = BufReader::new(file);
+    let reader = open_dump_reader(path)?;
 
     let from_line = replay.from_line.unwrap_or(1);
     let to_line = replay.to_line.unwrap_or(usize::MAX);
@@ -146,3 +546,91 @@ pub(super) fn load_dump_events(path: &str, replay: &ReplayCfg) -> Result<Vec<Dum
     events.sort_by_key(|e| (e.timestamp, e.channel_id));
     Ok(events)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("dump_{}_{name}", std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn read_lines(path: &str) -> Result<Vec<String>> {
+        open_dump_reader(path)?
+            .lines()
+            .collect::<std::io::Result<_>>()
+            .map_err(Into::into)
+    }
+
+    #[test]
+    fn compressed_dumps_round_trip() {
+        let events: Vec<DumpEvent> = (0..100)
+            .map(|i| DumpEvent {
+                timestamp: 1_700_000_000 + i,
+                channel_id: i % 3,
+                channel_title: format!("ch{}", i % 3),
+                text: format!("Шахеди курсом на Київ #{i}"),
+            })
+            .collect();
+        for (name, compression) in [
+            ("plain.jsonl", DumpCompression::None),
+            ("dump.jsonl.gz", DumpCompression::Gzip),
+            ("dump.jsonl.zst", DumpCompression::Zstd),
+        ] {
+            let path = temp_path(name);
+            assert_eq!(DumpCompression::from_path(&path), compression);
+            let mut writer = DumpWriter::create(&path).unwrap();
+            for event in &events {
+                writer.write_event(event).unwrap();
+            }
+            writer.finish().unwrap();
+
+            let raw = std::fs::read(&path).unwrap();
+            assert_eq!(
+                raw.starts_with(b"{"),
+                compression == DumpCompression::None,
+                "{name}"
+            );
+            let lines = read_lines(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(lines.len(), events.len(), "{name}");
+            for (line, event) in lines.iter().zip(&events) {
+                let read: DumpEvent = serde_json::from_str(line).unwrap();
+                assert_eq!(
+                    (read.timestamp, read.channel_id, &read.text),
+                    (event.timestamp, event.channel_id, &event.text)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_or_mislabelled_dumps_are_errors() {
+        let write = |name: &str, bytes: &[u8]| {
+            let path = temp_path(name);
+            std::fs::write(&path, bytes).unwrap();
+            path
+        };
+        let plain = b"{\"timestamp\":1,\"channel_id\":1,\"channel_title\":\"a\",\"text\":\"b\"}\n";
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(plain).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        for path in [
+            write("garbage.jsonl.gz", b"not gzip at all"),
+            write("garbage.jsonl.zst", b"not zstd at all"),
+            // Plain JSONL with a compressed extension.
+            write("plain.jsonl.zst", plain),
+            // Gzip cut off before its trailer.
+            write("truncated.jsonl.gz", &gzip[..gzip.len() - 6]),
+        ] {
+            let result = read_lines(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert!(result.is_err(), "{path}");
+        }
+        assert!(open_dump_reader(&temp_path("missing.jsonl.gz")).is_err());
+    }
+}
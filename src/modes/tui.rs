@@ -0,0 +1,233 @@
+use crate::monitor::{Monitor, SharedMonitor};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use std::time::Duration;
+
+use super::live;
+
+/// Redraw interval.
+const TICK: Duration = Duration::from_millis(250);
+
+/// `tui`: live mode with an operator console instead of a log stream.
+/// Panes show the incoming messages, the filter's decisions with their
+/// reasons and LLM verdicts, the open dedup waves and the queue of
+/// messages waiting for the filter.  ←/→ narrow the message panes to one
+/// channel, `q` quits.  Logs go to `TUI_LOG_PATH` (default `./tui.log`).
+pub(super) async fn run() -> Result<()> {
+    let monitor = Monitor::shared();
+    let mut live = tokio::spawn(live::run_monitored(Some(monitor.clone())));
+    let mut terminal = ratatui::try_init().context("failed to set up the terminal")?;
+    let result = ui_loop(&mut terminal, &monitor, &mut live).await;
+    ratatui::try_restore().context("failed to restore the terminal")?;
+    if live.is_finished() {
+        live.await.context("live mode panicked")??;
+    }
+    result
+}
+
+async fn ui_loop(
+    terminal: &mut DefaultTerminal,
+    monitor: &SharedMonitor,
+    live: &mut tokio::task::JoinHandle<Result<()>>,
+) -> Result<()> {
+    // `None` = all channels.
+    let mut channel: Option<String> = None;
+    loop {
+        {
+            let monitor = monitor.lock().unwrap();
+            terminal.draw(|frame| draw(frame, &monitor, channel.as_deref()))?;
+        }
+        if live.is_finished() {
+            return Ok(());
+        }
+        tokio::time::sleep(TICK).await;
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Right => {
+                    let monitor = monitor.lock().unwrap();
+                    let titles: Vec<&str> = monitor.channels().map(|(t, _)| t).collect();
+                    channel = cycle(&titles, channel.as_deref(), key.code == KeyCode::Right)
+                        .map(str::to_string);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Next (or previous) channel filter: all channels, then each title.
+fn cycle<'a>(titles: &[&'a str], current: Option<&str>, forward: bool) -> Option<&'a str> {
+    let slots = titles.len() + 1;
+    let at = current
+        .and_then(|c| titles.iter().position(|t| *t == c))
+        .map_or(0, |i| i + 1);
+    let next = if forward {
+        (at + 1) % slots
+    } else {
+        (at + slots - 1) % slots
+    };
+    next.checked_sub(1).map(|i| titles[i])
+}
+
+fn draw(frame: &mut ratatui::Frame, monitor: &Monitor, channel: Option<&str>) {
+    let [header, body] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+    let [raw, decisions] =
+        Layout::vertical([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(left);
+    let [waves, channels] =
+        Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(right);
+
+    let status = Line::from(vec![
+        Span::styled(" tg-osint ", Style::new().add_modifier(Modifier::REVERSED)),
+        Span::raw(format!(
+            " queue {} │ forwarded {} │ suppressed {} │ channel: {} (←/→) │ q quit",
+            monitor.queue_depth,
+            monitor.forwarded,
+            monitor.suppressed,
+            channel.unwrap_or("all"),
+        )),
+    ]);
+    frame.render_widget(Paragraph::new(status), header);
+
+    let raw_items = monitor.raw(channel).map(|m| {
+        ListItem::new(Line::from(vec![
+            Span::styled(time(m.timestamp), dim()),
+            Span::styled(
+                format!(" {} ", m.channel_title),
+                Style::new().fg(Color::Cyan),
+            ),
+            Span::raw(one_line(&m.text)),
+        ]))
+    });
+    render_list(frame, raw, "Incoming messages", raw_items);
+
+    let decision_items = monitor.decisions(channel).map(|d| {
+        let forwarded = d.alert.is_some();
+        let mut head = vec![
+            Span::styled(time(d.timestamp), dim()),
+            if forwarded {
+                Span::styled(" ✅ forwarded ", Style::new().fg(Color::Green))
+            } else {
+                Span::styled(" 🚫 suppressed ", Style::new().fg(Color::Red))
+            },
+            Span::styled(
+                format!("{} ", d.channel_title),
+                Style::new().fg(Color::Cyan),
+            ),
+        ];
+        if let Some(reason) = &d.reason {
+            head.push(Span::styled(
+                format!("[{reason}] "),
+                Style::new().fg(Color::Yellow),
+            ));
+        }
+        if !d.threats.is_empty() {
+            head.push(Span::raw(d.threats.join("+")));
+        }
+        if let Some(proximity) = &d.proximity {
+            head.push(Span::raw(format!(" @{proximity}")));
+        }
+        if d.nationwide {
+            head.push(Span::raw(" nationwide"));
+        }
+        if let Some(v) = &d.verdict {
+            head.push(Span::styled(
+                format!(
+                    " LLM: {} → {}{}",
+                    v.keyword_threats.join("+"),
+                    v.llm_threats.join("+"),
+                    v.confidence
+                        .map(|c| format!(" ({c:.2})"))
+                        .unwrap_or_default()
+                ),
+                Style::new().fg(Color::Magenta),
+            ));
+        }
+        ListItem::new(vec![
+            Line::from(head),
+            Line::styled(format!("    {}", one_line(&d.text)), dim()),
+        ])
+    });
+    render_list(frame, decisions, "Filter decisions", decision_items);
+
+    let wave_items = monitor.waves().iter().map(|w| {
+        let mut line = format!("{} {}", w.kind.emoji(), w.kind.label());
+        if let Some(sector) = w.sector {
+            line.push_str(&format!(" · {sector:?}"));
+        }
+        line.push_str(&format!(" · {}", w.proximity.name()));
+        if w.nationwide {
+            line.push_str(" · nationwide");
+        }
+        if w.receding {
+            line.push_str(" · receding");
+        }
+        ListItem::new(Line::from(vec![
+            Span::raw(line),
+            Span::styled(format!("  {}m ago", w.age.as_secs() / 60), dim()),
+        ]))
+    });
+    let waves_title = match monitor.attack() {
+        Some(a) => format!(
+            "Open waves · attack {}m: {} reports, {} forwarded",
+            a.duration().as_secs() / 60,
+            a.messages,
+            a.forwarded
+        ),
+        None => "Open waves".into(),
+    };
+    render_list(frame, waves, &waves_title, wave_items);
+
+    let channel_items = monitor.channels().map(|(title, count)| {
+        let style = if channel == Some(title) {
+            Style::new().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::new()
+        };
+        ListItem::new(Line::styled(format!("{count:>5}  {title}"), style))
+    });
+    render_list(frame, channels, "Channels", channel_items);
+}
+
+fn render_list<'a>(
+    frame: &mut ratatui::Frame,
+    area: Rect,
+    title: &str,
+    items: impl Iterator<Item = ListItem<'a>>,
+) {
+    // Newest first: only what fits is built.
+    let items: Vec<ListItem> = items.take(area.height as usize).collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(format!(" {title} "))),
+        area,
+    );
+}
+
+fn dim() -> Style {
+    Style::new().fg(Color::DarkGray)
+}
+
+fn time(ts: i64) -> String {
+    DateTime::from_timestamp(ts, 0)
+        .map(|t| t.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "--:--:--".into())
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
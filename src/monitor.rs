@@ -0,0 +1,174 @@
+//! State behind the `tui` operator console: the latest raw messages and
+//! filter decisions, the attack wave and its open dedup waves and the
+//! pipeline queue depth.
+//! Live mode feeds it as it processes messages; the console only reads.
+
+use crate::archive::ArchivedMessage;
+use crate::decisions::DecisionRecord;
+use crate::filter::WaveView;
+use crate::filter::wave::ThreatWave;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Raw messages and decisions kept for display.
+const MAX_LINES: usize = 500;
+
+pub type SharedMonitor = Arc<Mutex<Monitor>>;
+
+#[derive(Debug, Default)]
+pub struct Monitor {
+    raw: VecDeque<ArchivedMessage>,
+    decisions: VecDeque<DecisionRecord>,
+    waves: Vec<WaveView>,
+    attack: Option<ThreatWave>,
+    /// Messages received per channel title.
+    per_channel: BTreeMap<String, u64>,
+    /// Messages waiting for the filter (and its LLM call).
+    pub queue_depth: usize,
+    pub forwarded: u64,
+    pub suppressed: u64,
+}
+
+impl Monitor {
+    pub fn shared() -> SharedMonitor {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    pub fn record_raw(&mut self, msg: &ArchivedMessage) {
+        *self
+            .per_channel
+            .entry(msg.channel_title.clone())
+            .or_default() += 1;
+        push_capped(&mut self.raw, msg.clone());
+    }
+
+    pub fn record_decision(&mut self, record: DecisionRecord) {
+        if record.alert.is_some() {
+            self.forwarded += 1;
+        } else {
+            self.suppressed += 1;
+        }
+        push_capped(&mut self.decisions, record);
+    }
+
+    pub fn set_waves(&mut self, waves: Vec<WaveView>) {
+        self.waves = waves;
+    }
+
+    pub fn waves(&self) -> &[WaveView] {
+        &self.waves
+    }
+
+    pub fn set_attack(&mut self, attack: Option<ThreatWave>) {
+        self.attack = attack;
+    }
+
+    /// The attack under way, if any.
+    pub fn attack(&self) -> Option<&ThreatWave> {
+        self.attack.as_ref()
+    }
+
+    /// Channels seen so far with their message counts, by title.
+    pub fn channels(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.per_channel.iter().map(|(t, n)| (t.as_str(), *n))
+    }
+
+    /// Raw messages, newest first; only `channel`'s when given.
+    pub fn raw<'a>(
+        &'a self,
+        channel: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a ArchivedMessage> {
+        self.raw
+            .iter()
+            .rev()
+            .filter(move |m| channel.is_none_or(|c| m.channel_title == c))
+    }
+
+    /// Decisions, newest first; only `channel`'s when given.
+    pub fn decisions<'a>(
+        &'a self,
+        channel: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a DecisionRecord> {
+        self.decisions
+            .iter()
+            .rev()
+            .filter(move |d| channel.is_none_or(|c| d.channel_title == c))
+    }
+}
+
+fn push_capped<T>(lines: &mut VecDeque<T>, item: T) {
+    if lines.len() >= MAX_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Proximity;
+    use crate::filter::threat_kind::ThreatKind;
+    use std::time::Duration;
+
+    fn msg(title: &str, text: &str) -> ArchivedMessage {
+        ArchivedMessage {
+            timestamp: 0,
+            channel_id: 1,
+            channel_title: title.into(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn monitor_keeps_decisions_waves_and_channel_counts() {
+        let mut monitor = Monitor::default();
+        for (title, text, alert) in [
+            ("a", "Шахед на Київ", Some("🛵 Шахед")),
+            ("b", "Доброго ранку", None),
+            ("a", "Тихо", None),
+        ] {
+            let msg = msg(title, text);
+            monitor.record_raw(&msg);
+            monitor.record_decision(DecisionRecord::new(&msg, None, alert, "no_threat", None));
+        }
+        assert_eq!((monitor.forwarded, monitor.suppressed), (1, 2));
+        assert_eq!(
+            monitor.channels().collect::<Vec<_>>(),
+            vec![("a", 2), ("b", 1)]
+        );
+        let texts: Vec<&str> = monitor.raw(Some("a")).map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["Тихо", "Шахед на Київ"], "newest first");
+        assert_eq!(monitor.decisions(Some("b")).count(), 1);
+        assert_eq!(monitor.decisions(None).count(), 3);
+
+        monitor.set_waves(vec![WaveView {
+            kind: ThreatKind::Shahed,
+            sector: None,
+            proximity: Proximity::City,
+            nationwide: false,
+            receding: false,
+            age: Duration::from_secs(60),
+        }]);
+        assert_eq!(monitor.waves().len(), 1);
+        assert_eq!(monitor.waves()[0].kind, ThreatKind::Shahed);
+        monitor.set_waves(Vec::new());
+        assert!(monitor.waves().is_empty());
+    }
+
+    #[test]
+    fn monitor_keeps_only_the_latest_lines() {
+        let mut monitor = Monitor::default();
+        for i in 0..MAX_LINES + 10 {
+            monitor.record_raw(&msg("a", &i.to_string()));
+        }
+        assert_eq!(monitor.raw(None).count(), MAX_LINES);
+        assert_eq!(
+            monitor.raw(None).next().unwrap().text,
+            (MAX_LINES + 9).to_string()
+        );
+        assert_eq!(
+            monitor.channels().next(),
+            Some(("a", MAX_LINES as u64 + 10))
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! WASM rule plugins: user-provided modules that adjust alert decisions,
+//! sandboxed inside the process (no host imports, fuel-metered, memory
+//! capped).  A safer alternative to [`crate::hooks`] for custom logic.
+//!
+//! A plugin module must export:
+//!
+//! - `memory` – its linear memory;
+//! - `alloc(len: i32) -> i32` – returns a buffer of `len` bytes for the input;
+//! - `classify(ptr: i32, len: i32) -> i64` – reads the input JSON from
+//!   `ptr..ptr+len` and returns `(out_ptr << 32) | out_len` pointing at the
+//!   output JSON, or `0` for "no change".
+//!
+//! Input: `{"text", "channel_id", "channel_title", "timestamp",
+//! "detection": {"type", "threats", "proximity", "nationwide", "urgent"} | null,
+//! "alert": "…" | null}`.  Output (all fields optional):
+//! `{"suppress": true}` drops the alert, `{"alert": "…"}` replaces it (or
+//! forwards a suppressed message), `{"tag": "…"}` prepends a line.
+//!
+//! Plugins run in load order, each seeing the previous plugin's result.  A
+//! trapping or misbehaving plugin is logged and skipped.
+
+use crate::filter::AlertInfo;
+use crate::hooks::HookMessage;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use wasmi::{
+    Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+#[derive(Debug, Serialize)]
+struct PluginDetection {
+    #[serde(rename = "type")]
+    alert_type: &'static str,
+    threats: Vec<&'static str>,
+    proximity: &'static str,
+    nationwide: bool,
+    urgent: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginInput<'a> {
+    text: &'a str,
+    channel_id: i64,
+    channel_title: &'a str,
+    timestamp: i64,
+    detection: Option<PluginDetection>,
+    alert: Option<&'a str>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PluginOutput {
+    #[serde(default)]
+    suppress: bool,
+    alert: Option<String>,
+    tag: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginsCfg {
+    pub paths: Vec<String>,
+    /// Instructions budget per `classify` call.
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+}
+
+impl PluginsCfg {
+    /// | Env var                  | Default    | Description                          |
+    /// |--------------------------|------------|--------------------------------------|
+    /// | `WASM_PLUGINS`           | —          | Comma-separated `.wasm` module paths |
+    /// | `WASM_PLUGIN_FUEL`       | `10000000` | Fuel (≈ instructions) per call       |
+    /// | `WASM_PLUGIN_MEMORY_MB`  | `16`       | Linear memory cap per plugin         |
+    pub fn from_env() -> Self {
+        let paths = std::env::var("WASM_PLUGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        let fuel: u64 = std::env::var("WASM_PLUGIN_FUEL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000_000);
+        let memory_mb: usize = std::env::var("WASM_PLUGIN_MEMORY_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(16);
+        Self {
+            paths,
+            fuel,
+            max_memory_bytes: memory_mb * 1024 * 1024,
+        }
+    }
+}
+
+struct Plugin {
+    name: String,
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    classify: TypedFunc<(i32, i32), i64>,
+}
+
+impl Plugin {
+    fn load(engine: &Engine, path: &str, cfg: &PluginsCfg) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("reading {path}"))?;
+        Self::new(engine, path, &bytes, cfg)
+    }
+
+    fn new(engine: &Engine, name: &str, bytes: &[u8], cfg: &PluginsCfg) -> Result<Self> {
+        let module = Module::new(engine, bytes)?;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(cfg.max_memory_bytes)
+            .build();
+        let mut store = Store::new(engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(cfg.fuel).map_err(|e| anyhow!("{e}"))?;
+        // No host functions: a module with imports fails to instantiate.
+        let linker = Linker::<StoreLimits>::new(engine);
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow!("missing `memory` export"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+        let classify = instance.get_typed_func::<(i32, i32), i64>(&store, "classify")?;
+        Ok(Self {
+            name: name.to_string(),
+            store,
+            memory,
+            alloc,
+            classify,
+        })
+    }
+
+    fn call(&mut self, input: &[u8], fuel: u64) -> Result<Option<PluginOutput>> {
+        self.store.set_fuel(fuel).map_err(|e| anyhow!("{e}"))?;
+        let len = i32::try_from(input.len())?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|e| anyhow!("writing input: {e}"))?;
+        let packed = self.classify.call(&mut self.store, (ptr, len))?;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let out_ptr = (packed as u64 >> 32) as usize;
+        let out_len = (packed as u64 & 0xffff_ffff) as usize;
+        // Checked against the memory before copying: a bogus length must
+        // not make the host allocate gigabytes.
+        let out = self
+            .memory
+            .data(&self.store)
+            .get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| anyhow!("output {out_ptr}+{out_len} is outside plugin memory"))?;
+        Ok(Some(serde_json::from_slice(out)?))
+    }
+}
+
+/// Fuel-metered engine shared by the plugins.
+fn engine() -> Engine {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// Loaded plugins, applied after keyword/LLM detection.
+pub struct WasmPlugins {
+    plugins: Vec<Plugin>,
+    fuel: u64,
+}
+
+impl WasmPlugins {
+    /// Modules that fail to load are logged and skipped.
+    pub fn load(cfg: &PluginsCfg) -> Self {
+        let engine = engine();
+        let mut plugins = Vec::new();
+        for path in &cfg.paths {
+            match Plugin::load(&engine, path, cfg) {
+                Ok(p) => {
+                    info!("Loaded WASM plugin {path}");
+                    plugins.push(p);
+                }
+                Err(e) => warn!("Failed to load WASM plugin {path}: {e}"),
+            }
+        }
+        Self {
+            plugins,
+            fuel: cfg.fuel,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::load(&PluginsCfg::from_env())
+    }
+
+    /// Let every plugin adjust `alert` (`None` = suppressed) for `msg`.
+    pub fn apply(
+        &mut self,
+        msg: &HookMessage,
+        info: Option<&AlertInfo>,
+        alert: &mut Option<String>,
+    ) {
+        for plugin in &mut self.plugins {
+            let input = PluginInput {
+                text: &msg.text,
+                channel_id: msg.channel_id,
+                channel_title: &msg.channel_title,
+                timestamp: msg.timestamp,
+                detection: info.map(|i| PluginDetection {
+                    alert_type: i.alert_type.name(),
+                    threats: i.threats.iter().map(|t| t.variant_name()).collect(),
+                    proximity: i.proximity.name(),
+                    nationwide: i.nationwide,
+                    urgent: i.urgent,
+                }),
+                alert: alert.as_deref(),
+            };
+            let input = match serde_json::to_vec(&input) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("Failed to serialize plugin input: {e}");
+                    return;
+                }
+            };
+            let out = match plugin.call(&input, self.fuel) {
+                Ok(Some(out)) => out,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("WASM plugin {} failed: {e}", plugin.name);
+                    continue;
+                }
+            };
+            debug!("WASM plugin {}: {out:?}", plugin.name);
+            if out.suppress {
+                *alert = None;
+                continue;
+            }
+            if out.alert.is_some() {
+                *alert = out.alert;
+            }
+            if let (Some(tag), Some(text)) = (out.tag, alert.as_mut()) {
+                text.insert_str(0, &format!("{tag}\n"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: usize = 1024 * 1024;
+
+    fn cfg(max_memory_bytes: usize) -> PluginsCfg {
+        PluginsCfg {
+            paths: Vec::new(),
+            fuel: 100_000,
+            max_memory_bytes,
+        }
+    }
+
+    /// A plugin with the required exports around `classify_body`.
+    fn plugin(classify_body: &str, cfg: &PluginsCfg) -> Result<Plugin> {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{{\"tag\":\"[wasm]\"}}")
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "classify") (param i32 i32) (result i64) {classify_body}))"#
+        );
+        Plugin::new(&engine(), "test", &wat::parse_str(wat)?, cfg)
+    }
+
+    fn message() -> HookMessage {
+        HookMessage {
+            channel_id: 1,
+            channel_title: "Ch".into(),
+            timestamp: 0,
+            text: "Шахеди на Київ".into(),
+        }
+    }
+
+    #[test]
+    fn plugin_output_is_applied() {
+        let p = plugin("i64.const 0x1000000010", &cfg(MB)).unwrap();
+        let mut plugins = WasmPlugins {
+            plugins: vec![p],
+            fuel: 100_000,
+        };
+        let mut alert = Some("alert".to_string());
+        plugins.apply(&message(), None, &mut alert);
+        assert_eq!(alert.as_deref(), Some("[wasm]\nalert"));
+    }
+
+    #[test]
+    fn module_with_host_imports_is_rejected() {
+        let wat = r#"(module
+            (import "env" "log" (func))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 0)
+            (func (export "classify") (param i32 i32) (result i64) i64.const 0))"#;
+        let bytes = wat::parse_str(wat).unwrap();
+        assert!(Plugin::new(&engine(), "import", &bytes, &cfg(MB)).is_err());
+    }
+
+    #[test]
+    fn running_out_of_fuel_aborts_the_call() {
+        let mut p = plugin("(loop (br 0)) i64.const 0", &cfg(MB)).unwrap();
+        assert!(p.call(b"{}", 10_000).is_err());
+
+        // The plugin is skipped and the alert left as it was.
+        let mut plugins = WasmPlugins {
+            plugins: vec![p],
+            fuel: 10_000,
+        };
+        let mut alert = Some("alert".to_string());
+        plugins.apply(&message(), None, &mut alert);
+        assert_eq!(alert.as_deref(), Some("alert"));
+    }
+
+    #[test]
+    fn memory_cannot_grow_past_the_cap() {
+        // Traps when the 1 MB (16 page) growth is refused.
+        let grow = "(if (i32.eq (memory.grow (i32.const 16)) (i32.const -1)) (then unreachable)) \
+                    i64.const 0";
+        let mut capped = plugin(grow, &cfg(MB)).unwrap();
+        assert!(capped.call(b"{}", 100_000).is_err());
+        let mut roomy = plugin(grow, &cfg(4 * MB)).unwrap();
+        assert!(roomy.call(b"{}", 100_000).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_output_pointer_is_an_error() {
+        for packed in [
+            // Past the single 64 KiB page.
+            "i64.const 0x0001000000000010",
+            // A 4 GiB length.
+            "i64.const 0x00000010ffffffff",
+            // Negative: the top bit set.
+            "i64.const -1",
+        ] {
+            let mut p = plugin(packed, &cfg(MB)).unwrap();
+            assert!(p.call(b"{}", 100_000).is_err(), "{packed}");
+        }
+    }
+}
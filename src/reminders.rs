@@ -0,0 +1,267 @@
+//! Repeat reminders for critical alerts nobody acknowledged.  A ballistic
+//! alert for the user's district at night is easy to sleep through; when the
+//! chat has not pressed "✅ Прийнято" under it after a while and the wave is
+//! still open, the bot sends a louder reminder, up to `REMINDER_MAX` times.
+//! Reminders stop on acknowledgment, on an all-clear, or once the threat
+//! status falls back to clear.
+
+use crate::escalation;
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{AlertInfo, Proximity};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Callback data of the acknowledgment button.
+pub const ACK_CALLBACK: &str = "ack";
+
+/// Label of the acknowledgment button.
+pub const ACK_LABEL: &str = "✅ Прийнято";
+
+#[derive(Debug, Clone)]
+pub struct ReminderCfg {
+    /// Delay before the first reminder of the most severe alerts, and
+    /// between later ones.
+    pub after: Duration,
+    /// Reminders per alert at most.
+    pub max_reminders: u32,
+}
+
+impl ReminderCfg {
+    /// `None` unless `REMINDER_AFTER_MINS` is set.
+    ///
+    /// | Env var               | Default | Description                                    |
+    /// |-----------------------|---------|------------------------------------------------|
+    /// | `REMINDER_AFTER_MINS` | `0`     | Remind after this many minutes (0 = off)       |
+    /// | `REMINDER_MAX`        | `3`     | Reminders per unacknowledged alert at most     |
+    pub fn from_env() -> Option<Self> {
+        let num = |key: &str, default: u64| -> u64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let mins = num("REMINDER_AFTER_MINS", 0);
+        if mins == 0 {
+            return None;
+        }
+        Some(Self {
+            after: Duration::from_secs(mins * 60),
+            max_reminders: num("REMINDER_MAX", 3).max(1) as u32,
+        })
+    }
+
+    /// Reminder interval for `info`: `after` for ballistic / hypersonic
+    /// threats to the district, twice that for the other critical alerts
+    /// (see [`escalation::is_critical`]), `None` for the rest.
+    pub fn interval_for(&self, info: &AlertInfo) -> Option<Duration> {
+        if !escalation::is_critical(info) {
+            return None;
+        }
+        let ballistic = info
+            .threats
+            .iter()
+            .any(|t| matches!(t, ThreatKind::Ballistic | ThreatKind::Hypersonic));
+        if ballistic && info.proximity == Proximity::District {
+            Some(self.after)
+        } else {
+            Some(self.after * 2)
+        }
+    }
+}
+
+/// A reminder to send now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub chat_id: i64,
+    /// The alert message it replies to.
+    pub reply_to: i64,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct Pending {
+    reply_to: i64,
+    /// First line of the alert.
+    headline: String,
+    interval: Duration,
+    next: Instant,
+    sent: u32,
+}
+
+pub type SharedReminders = Arc<Mutex<Reminders>>;
+
+/// Unacknowledged critical alerts, at most one per chat (the latest).
+#[derive(Debug)]
+pub struct Reminders {
+    cfg: ReminderCfg,
+    pending: HashMap<i64, Pending>,
+}
+
+impl Reminders {
+    pub fn new(cfg: ReminderCfg) -> Self {
+        Self {
+            cfg,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn shared(self) -> SharedReminders {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Whether `info` gets the acknowledgment button and reminders.
+    pub fn wants(&self, info: &AlertInfo) -> bool {
+        self.cfg.interval_for(info).is_some()
+    }
+
+    /// Start reminding the chats of `sent` (chat id → message id) about
+    /// `alert`, replacing their earlier pending reminders.
+    pub fn arm(
+        &mut self,
+        now: Instant,
+        info: &AlertInfo,
+        alert: &str,
+        sent: impl IntoIterator<Item = (i64, i64)>,
+    ) {
+        let Some(interval) = self.cfg.interval_for(info) else {
+            return;
+        };
+        let headline = alert.lines().next().unwrap_or_default().to_string();
+        for (chat_id, message_id) in sent {
+            self.pending.insert(
+                chat_id,
+                Pending {
+                    reply_to: message_id,
+                    headline: headline.clone(),
+                    interval,
+                    next: now + interval,
+                    sent: 0,
+                },
+            );
+        }
+    }
+
+    /// The chat pressed the button; returns whether a reminder was pending.
+    pub fn acknowledge(&mut self, chat_id: i64) -> bool {
+        self.pending.remove(&chat_id).is_some()
+    }
+
+    /// The wave is over.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Reminders due at `now`; chats that got their last one are dropped.
+    pub fn due(&mut self, now: Instant) -> Vec<Reminder> {
+        let max = self.cfg.max_reminders;
+        let mut due = Vec::new();
+        for (&chat_id, pending) in &mut self.pending {
+            if now < pending.next {
+                continue;
+            }
+            pending.sent += 1;
+            pending.next = now + pending.interval;
+            due.push(Reminder {
+                chat_id,
+                reply_to: pending.reply_to,
+                text: reminder_text(&pending.headline, pending.sent, max),
+            });
+        }
+        self.pending.retain(|_, p| p.sent < max);
+        due
+    }
+}
+
+/// Each repeat gets one more bell and, from the second on, capitals.
+fn reminder_text(headline: &str, n: u32, max: u32) -> String {
+    let bells = "🔔".repeat(n as usize + 1);
+    let title = if n > 1 {
+        "ПОВТОРНЕ НАГАДУВАННЯ"
+    } else {
+        "Нагадування"
+    };
+    format!(
+        "{bells} {title} ({n}/{max})\n{headline}\n\
+         Загроза ще не минула. Натисніть «{ACK_LABEL}», щоб зупинити нагадування."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::AlertType;
+
+    fn alert_info(threats: &[ThreatKind], proximity: Proximity) -> AlertInfo {
+        AlertInfo {
+            alert_type: AlertType::New,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide: false,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn reminders_repeat_until_acknowledged_and_scale_with_severity() {
+        let cfg = ReminderCfg {
+            after: Duration::from_secs(300),
+            max_reminders: 2,
+        };
+        let mut reminders = Reminders::new(cfg);
+        let t0 = Instant::now();
+        let district = alert_info(&[ThreatKind::Ballistic], Proximity::District);
+        let city = alert_info(&[ThreatKind::Ballistic], Proximity::City);
+        let drones = alert_info(&[ThreatKind::Shahed], Proximity::Oblast);
+        assert!(reminders.wants(&district) && reminders.wants(&city));
+        assert!(!reminders.wants(&drones));
+
+        reminders.arm(t0, &district, "🚀 Балістика\nдеталі", [(1, 10), (2, 20)]);
+        reminders.arm(t0, &city, "🚀 Балістика на місто", [(3, 30)]);
+        reminders.arm(t0, &drones, "🛵 Шахеди", [(4, 40)]);
+        assert!(reminders.due(t0 + Duration::from_secs(299)).is_empty());
+
+        let mut first = reminders.due(t0 + Duration::from_secs(300));
+        first.sort_by_key(|r| r.chat_id);
+        assert_eq!(
+            first
+                .iter()
+                .map(|r| (r.chat_id, r.reply_to))
+                .collect::<Vec<_>>(),
+            [(1, 10), (2, 20)],
+            "city-level ballistic waits twice as long"
+        );
+        assert!(first[0].text.contains("🚀 Балістика") && !first[0].text.contains("деталі"));
+
+        assert!(reminders.acknowledge(1));
+        assert!(!reminders.acknowledge(1));
+        let mut second = reminders.due(t0 + Duration::from_secs(600));
+        second.sort_by_key(|r| r.chat_id);
+        assert_eq!(second.iter().map(|r| r.chat_id).collect::<Vec<_>>(), [2, 3]);
+        assert!(
+            second[0].text.starts_with("🔔🔔🔔 ПОВТОРНЕ"),
+            "louder each time"
+        );
+
+        // Chat 2 reached REMINDER_MAX; an all-clear drops the rest.
+        assert_eq!(
+            reminders
+                .due(t0 + Duration::from_secs(1200))
+                .iter()
+                .map(|r| r.chat_id)
+                .collect::<Vec<_>>(),
+            [3]
+        );
+        reminders.clear();
+        assert!(reminders.is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! Data retention: periodically deletes archived messages, per-chat bot
+//! event records and unreported alert deliveries older than the configured
+//! limits.
+
+use crate::archive::{self, SharedArchive};
+use crate::bot::{self, SharedDb};
+use crate::feedback;
+use chrono::Utc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How long stored data is kept.  `None` keeps it forever.
+#[derive(Debug, Clone)]
+pub struct RetentionCfg {
+    pub archive_days: Option<i64>,
+    pub bot_events_days: Option<i64>,
+}
+
+impl RetentionCfg {
+    /// `0` disables pruning for that store.
+    ///
+    /// | Env var                     | Default | Description                              |
+    /// |-----------------------------|---------|------------------------------------------|
+    /// | `ARCHIVE_RETENTION_DAYS`    | `0`     | Days of archived messages/alerts to keep |
+    /// | `BOT_EVENTS_RETENTION_DAYS` | `90`    | Days of bot events/deliveries to keep    |
+    pub fn from_env() -> Self {
+        let days = |key: &str, default: i64| {
+            Some(
+                std::env::var(key)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default),
+            )
+            .filter(|d| *d > 0)
+        };
+        Self {
+            archive_days: days("ARCHIVE_RETENTION_DAYS", 0),
+            bot_events_days: days("BOT_EVENTS_RETENTION_DAYS", 90),
+        }
+    }
+}
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Runs forever: prunes expired data once an hour.
+pub async fn run_retention_loop(
+    bot_db: SharedDb,
+    archive_db: Option<SharedArchive>,
+    cfg: RetentionCfg,
+) {
+    info!(
+        "Retention: archive {:?} days, bot events {:?} days",
+        cfg.archive_days, cfg.bot_events_days
+    );
+    loop {
+        let now = Utc::now().timestamp();
+        if let (Some(days), Some(db)) = (cfg.archive_days, &archive_db) {
+            match archive::prune_before(db, now - days * 86400) {
+                Ok(0) => {}
+                Ok(n) => info!("Retention: removed {n} archived message(s)"),
+                Err(e) => warn!("Archive retention failed: {e}"),
+            }
+        }
+        if let Some(days) = cfg.bot_events_days {
+            match bot::prune_events(&bot_db, now - days * 86400) {
+                Ok(0) => {}
+                Ok(n) => info!("Retention: removed {n} bot event(s)"),
+                Err(e) => warn!("Bot event retention failed: {e}"),
+            }
+            match feedback::prune_deliveries(&bot_db, now - days * 86400) {
+                Ok(0) => {}
+                Ok(n) => info!("Retention: removed {n} unreported delivered alert(s)"),
+                Err(e) => warn!("Delivered alert retention failed: {e}"),
+            }
+        }
+        tokio::time::sleep(PRUNE_INTERVAL).await;
+    }
+}
@@ -0,0 +1,406 @@
+//! Active-learning review queue: messages where the keyword filter and the
+//! LLM disagree, or the LLM is only moderately confident, wait here for an
+//! admin verdict (`/review` in the bot, ✅ / ❌ buttons).  Verdicts are stored
+//! as labels and feed a per-channel trust score.
+
+use crate::archive::ArchivedMessage;
+use crate::bot::SharedDb;
+use crate::feedback::Label;
+use crate::filter::ReviewCandidate;
+use crate::filter::threat_kind::ThreatKind;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlite::State;
+use std::collections::{HashMap, HashSet};
+
+/// Prefix of the inline-button callback data, `review:<accept|reject>:<id>`.
+const CALLBACK_PREFIX: &str = "review:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The message really was an active-threat alert.
+    Accept,
+    /// Not an alert (analysis, recap, rumour, …).
+    Reject,
+}
+
+impl Decision {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accept => "accept",
+            Self::Reject => "reject",
+        }
+    }
+
+    pub fn label(&self) -> Label {
+        match self {
+            Self::Accept => Label::Correct,
+            Self::Reject => Label::FalsePositive,
+        }
+    }
+
+    pub fn callback_data(&self, id: i64) -> String {
+        format!("{CALLBACK_PREFIX}{}:{id}", self.as_str())
+    }
+
+    /// Inverse of [`Decision::callback_data`].
+    pub fn parse_callback(data: &str) -> Option<(Self, i64)> {
+        let (decision, id) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+        let decision = match decision {
+            "accept" => Self::Accept,
+            "reject" => Self::Reject,
+            _ => return None,
+        };
+        Some((decision, id.parse().ok()?))
+    }
+}
+
+/// When a channel's review record makes it untrusted.
+#[derive(Debug, Clone)]
+pub struct TrustCfg {
+    /// Reviews needed before the score counts.
+    pub min_decisions: i64,
+    /// Channels scoring below this are processed at low priority.
+    pub low_trust: f64,
+}
+
+impl TrustCfg {
+    /// | Env var                       | Default | Description                          |
+    /// |-------------------------------|---------|--------------------------------------|
+    /// | `REVIEW_TRUST_MIN_DECISIONS`  | `5`     | Reviews before trust is applied      |
+    /// | `REVIEW_LOW_TRUST`            | `0.3`   | Trust below which a channel demotes  |
+    pub fn from_env() -> Self {
+        Self {
+            min_decisions: std::env::var("REVIEW_TRUST_MIN_DECISIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            low_trust: std::env::var("REVIEW_LOW_TRUST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.3),
+        }
+    }
+}
+
+/// One queued message.
+#[derive(Debug, Clone)]
+pub struct ReviewItem {
+    pub id: i64,
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub channel_title: String,
+    pub text: String,
+    pub keyword_threats: String,
+    pub llm_threats: String,
+    pub confidence: Option<f64>,
+}
+
+impl ReviewItem {
+    /// Bot message shown to the reviewing admin.
+    pub fn render(&self, pending: i64) -> String {
+        let confidence = self
+            .confidence
+            .map(|c| format!("{:.0}%", c * 100.0))
+            .unwrap_or_else(|| "—".into());
+        let posted = DateTime::<Utc>::from_timestamp(self.timestamp, 0)
+            .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+        let none = |s: &str| {
+            if s.is_empty() {
+                "—".to_string()
+            } else {
+                s.to_string()
+            }
+        };
+        format!(
+            "🔍 Review #{} ({pending} pending)\n📡 {} ({}) · {posted}\n\n{}\n\n\
+             Keywords: {}\nLLM: {} (confidence {confidence})\n\n\
+             Is this an active-threat alert?",
+            self.id,
+            self.channel_title,
+            self.channel_id,
+            self.text,
+            none(&self.keyword_threats),
+            none(&self.llm_threats),
+        )
+    }
+}
+
+pub fn ensure_tables(db: &SharedDb) -> Result<()> {
+    db.lock().unwrap().execute(
+        "CREATE TABLE IF NOT EXISTS review_queue (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp       INTEGER NOT NULL,
+            channel_id      INTEGER NOT NULL,
+            channel_title   TEXT    NOT NULL,
+            text            TEXT    NOT NULL,
+            keyword_threats TEXT    NOT NULL,
+            llm_threats     TEXT    NOT NULL,
+            confidence      REAL,
+            queued_at       INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            label           TEXT,
+            decided_by      INTEGER,
+            decided_at      INTEGER
+         );
+         CREATE UNIQUE INDEX IF NOT EXISTS review_queue_msg
+            ON review_queue (channel_id, timestamp, text);",
+    )?;
+    Ok(())
+}
+
+fn join_threats(threats: &[ThreatKind]) -> String {
+    threats
+        .iter()
+        .map(|t| t.variant_name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Queue `msg` for review.  A message already in the queue is ignored.
+pub fn enqueue(db: &SharedDb, msg: &ArchivedMessage, candidate: &ReviewCandidate) -> Result<()> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "INSERT OR IGNORE INTO review_queue
+            (timestamp, channel_id, channel_title, text, keyword_threats, llm_threats, confidence)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, msg.timestamp))?;
+    stmt.bind((2, msg.channel_id))?;
+    stmt.bind((3, msg.channel_title.as_str()))?;
+    stmt.bind((4, msg.text.as_str()))?;
+    stmt.bind((5, join_threats(&candidate.keyword_threats).as_str()))?;
+    stmt.bind((6, join_threats(&candidate.llm_threats).as_str()))?;
+    stmt.bind((7, candidate.confidence.map(f64::from)))?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub fn pending_count(db: &SharedDb) -> Result<i64> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare("SELECT COUNT(*) FROM review_queue WHERE label IS NULL")?;
+    stmt.next()?;
+    Ok(stmt.read::<i64, _>(0)?)
+}
+
+/// Oldest undecided message.
+pub fn next_pending(db: &SharedDb) -> Result<Option<ReviewItem>> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, channel_id, channel_title, text, keyword_threats,
+                llm_threats, confidence
+         FROM review_queue WHERE label IS NULL ORDER BY id LIMIT 1",
+    )?;
+    if let State::Row = stmt.next()? {
+        return Ok(Some(ReviewItem {
+            id: stmt.read(0)?,
+            timestamp: stmt.read(1)?,
+            channel_id: stmt.read(2)?,
+            channel_title: stmt.read(3)?,
+            text: stmt.read(4)?,
+            keyword_threats: stmt.read(5)?,
+            llm_threats: stmt.read(6)?,
+            confidence: stmt.read(7)?,
+        }));
+    }
+    Ok(None)
+}
+
+/// Record an admin verdict.  Returns `false` when the item is unknown or
+/// was already decided (e.g. two admins pressed a button).
+pub fn decide(db: &SharedDb, id: i64, decision: Decision, admin_chat: i64) -> Result<bool> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "UPDATE review_queue
+         SET label = ?, decided_by = ?, decided_at = strftime('%s', 'now')
+         WHERE id = ? AND label IS NULL",
+    )?;
+    stmt.bind((1, decision.label().as_str()))?;
+    stmt.bind((2, admin_chat))?;
+    stmt.bind((3, id))?;
+    stmt.next()?;
+    Ok(conn.change_count() > 0)
+}
+
+/// Per-channel share of reviewed messages that were real alerts, smoothed
+/// towards 0.5 (`(accepted + 1) / (decided + 2)`).  Only channels with at
+/// least `min_decisions` reviews are included.
+pub fn channel_trust(db: &SharedDb, min_decisions: i64) -> Result<HashMap<i64, f64>> {
+    let conn = db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT channel_id, SUM(label = ?), COUNT(*)
+         FROM review_queue WHERE label IS NOT NULL
+         GROUP BY channel_id HAVING COUNT(*) >= ?",
+    )?;
+    stmt.bind((1, Label::Correct.as_str()))?;
+    stmt.bind((2, min_decisions))?;
+    let mut out = HashMap::new();
+    while let State::Row = stmt.next()? {
+        let accepted = stmt.read::<i64, _>(1)?;
+        let decided = stmt.read::<i64, _>(2)?;
+        out.insert(
+            stmt.read::<i64, _>(0)?,
+            (accepted + 1) as f64 / (decided + 2) as f64,
+        );
+    }
+    Ok(out)
+}
+
+/// Channels whose trust fell below `cfg.low_trust`.
+pub fn low_trust_channels(db: &SharedDb, cfg: &TrustCfg) -> Result<HashSet<i64>> {
+    Ok(channel_trust(db, cfg.min_decisions)?
+        .into_iter()
+        .filter(|(_, trust)| *trust < cfg.low_trust)
+        .map(|(id, _)| id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review_db() -> SharedDb {
+        let db = crate::bot::open_db(":memory:").unwrap();
+        ensure_tables(&db).unwrap();
+        db
+    }
+
+    fn message(channel_id: i64, timestamp: i64, text: &str) -> ArchivedMessage {
+        ArchivedMessage {
+            timestamp,
+            channel_id,
+            channel_title: format!("Ch{channel_id}"),
+            text: text.into(),
+        }
+    }
+
+    fn candidate() -> ReviewCandidate {
+        ReviewCandidate {
+            keyword_threats: vec![ThreatKind::Other],
+            llm_threats: vec![ThreatKind::Shahed, ThreatKind::ReconDrone],
+            confidence: Some(0.5),
+        }
+    }
+
+    /// Queue `accepted + rejected` messages from `channel_id` and decide them.
+    fn review(db: &SharedDb, channel_id: i64, accepted: i64, rejected: i64) {
+        for i in 0..accepted + rejected {
+            enqueue(db, &message(channel_id, i, "ціль"), &candidate()).unwrap();
+            let item = next_pending(db).unwrap().unwrap();
+            let decision = if i < accepted {
+                Decision::Accept
+            } else {
+                Decision::Reject
+            };
+            assert!(decide(db, item.id, decision, 1).unwrap());
+        }
+    }
+
+    #[test]
+    fn a_message_is_queued_once() {
+        let db = review_db();
+        let msg = message(5, 100, "Ціль на Київ");
+        enqueue(&db, &msg, &candidate()).unwrap();
+        enqueue(&db, &msg, &candidate()).unwrap();
+        assert_eq!(pending_count(&db).unwrap(), 1);
+        enqueue(&db, &message(5, 101, "Ціль на Київ"), &candidate()).unwrap();
+        assert_eq!(pending_count(&db).unwrap(), 2);
+
+        let item = next_pending(&db).unwrap().unwrap();
+        assert_eq!(item.text, "Ціль на Київ");
+        assert_eq!(item.timestamp, 100);
+        assert_eq!(item.keyword_threats, "Other");
+        assert_eq!(item.llm_threats, "Shahed, ReconDrone");
+        assert_eq!(item.confidence, Some(0.5));
+    }
+
+    #[test]
+    fn a_second_verdict_is_rejected_and_not_counted() {
+        let db = review_db();
+        enqueue(&db, &message(5, 100, "ціль"), &candidate()).unwrap();
+        let id = next_pending(&db).unwrap().unwrap().id;
+        assert!(decide(&db, id, Decision::Reject, 1).unwrap());
+        assert!(
+            !decide(&db, id, Decision::Accept, 2).unwrap(),
+            "second admin"
+        );
+        assert!(
+            !decide(&db, id + 1, Decision::Accept, 1).unwrap(),
+            "unknown id"
+        );
+        assert_eq!(pending_count(&db).unwrap(), 0);
+        assert!(next_pending(&db).unwrap().is_none());
+        // The first verdict stands: 0 accepted of 1.
+        assert_eq!(channel_trust(&db, 1).unwrap()[&5], 1.0 / 3.0);
+    }
+
+    #[test]
+    fn trust_is_smoothed_and_needs_enough_reviews() {
+        let db = review_db();
+        review(&db, 1, 4, 0);
+        review(&db, 2, 1, 4);
+        review(&db, 3, 0, 4);
+        let trust = channel_trust(&db, 5).unwrap();
+        assert_eq!(trust.len(), 1, "channels with 4 reviews are left out");
+        assert_eq!(trust[&2], 2.0 / 7.0);
+
+        let trust = channel_trust(&db, 4).unwrap();
+        assert_eq!(trust[&1], 5.0 / 6.0);
+        assert_eq!(trust[&3], 1.0 / 6.0);
+
+        let cfg = TrustCfg {
+            min_decisions: 4,
+            low_trust: 0.3,
+        };
+        assert_eq!(
+            low_trust_channels(&db, &cfg).unwrap(),
+            HashSet::from([2, 3])
+        );
+    }
+
+    #[test]
+    fn callback_data_round_trips_and_rejects_garbage() {
+        for decision in [Decision::Accept, Decision::Reject] {
+            let data = decision.callback_data(42);
+            assert_eq!(Decision::parse_callback(&data), Some((decision, 42)));
+        }
+        assert_eq!(
+            Decision::parse_callback("review:accept:-1"),
+            Some((Decision::Accept, -1))
+        );
+        for data in [
+            "",
+            "review:",
+            "review:accept",
+            "review:accept:",
+            "review:accept:x",
+            "review:maybe:1",
+            "review:accept:1:2",
+            "feedback:accept:1",
+            "accept:1",
+        ] {
+            assert_eq!(Decision::parse_callback(data), None, "{data}");
+        }
+    }
+
+    #[test]
+    fn review_item_shows_source_and_time() {
+        let item = ReviewItem {
+            id: 7,
+            timestamp: 1_700_000_000,
+            channel_id: -100123,
+            channel_title: "Радар".into(),
+            text: "Ціль на Київ".into(),
+            keyword_threats: "Other".into(),
+            llm_threats: String::new(),
+            confidence: Some(0.55),
+        };
+        let text = item.render(3);
+        assert!(
+            text.starts_with(
+                "🔍 Review #7 (3 pending)\n📡 Радар (-100123) · 2023-11-14 22:13 UTC\n"
+            )
+        );
+        assert!(text.contains("Keywords: Other\nLLM: — (confidence 55%)"));
+    }
+}
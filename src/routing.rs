@@ -0,0 +1,366 @@
+//! Severity-based sink routing.  `ALERT_ROUTES` maps (threat kind,
+//! proximity / nationwide) to the set of sinks that receive the alert, e.g.
+//!
+//! ```text
+//! ALERT_ROUTES="ballistic@district=telegram,webhook,voice,exec; recon_drone@oblast=telegram_silent"
+//! ```
+//!
+//! Rules are checked in order and the first match wins.  `*` matches any
+//! kind or scope, and `kind` alone is the same as `kind@*`.  An empty sink
+//! list mutes matching alerts.  Alerts no rule matches go to Telegram and the
+//! webhook, and ring phones only when they meet the escalation criteria;
+//! reduced-severity nationwide alerts go to Telegram silently instead.
+
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::{AlertInfo, Proximity};
+use anyhow::{Result, anyhow, bail};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// How long an `exec` sink command may run.
+const EXEC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sink {
+    /// Bot broadcast to subscribers.
+    Telegram,
+    /// Bot broadcast without a notification sound.
+    TelegramSilent,
+    /// `WEBHOOK_URL` push.
+    Webhook,
+    /// Voice-call escalation, regardless of the escalation criteria.
+    Voice,
+    /// `ROUTE_EXEC_CMD` (e.g. a siren script).
+    Exec,
+}
+
+impl Sink {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "telegram" => Self::Telegram,
+            "telegram_silent" => Self::TelegramSilent,
+            "webhook" => Self::Webhook,
+            "voice" => Self::Voice,
+            "exec" => Self::Exec,
+            other => bail!("unknown sink {other:?}"),
+        })
+    }
+
+    /// Name as written in `ALERT_ROUTES`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Telegram => "telegram",
+            Self::TelegramSilent => "telegram_silent",
+            Self::Webhook => "webhook",
+            Self::Voice => "voice",
+            Self::Exec => "exec",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Any,
+    Nationwide,
+    Proximity(Proximity),
+}
+
+impl Scope {
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "*" => Self::Any,
+            "nationwide" => Self::Nationwide,
+            "district" => Self::Proximity(Proximity::District),
+            "city" => Self::Proximity(Proximity::City),
+            "oblast" => Self::Proximity(Proximity::Oblast),
+            "none" => Self::Proximity(Proximity::None),
+            other => bail!("unknown scope {other:?}"),
+        })
+    }
+
+    fn matches(&self, info: &AlertInfo) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Nationwide => info.nationwide,
+            Self::Proximity(p) => info.proximity == *p,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RouteRule {
+    /// `None` matches any kind.
+    kind: Option<ThreatKind>,
+    scope: Scope,
+    sinks: Vec<Sink>,
+}
+
+impl RouteRule {
+    /// `kind[@scope]=sink,sink`
+    fn parse(spec: &str) -> Result<Self> {
+        let (selector, sinks) = spec
+            .split_once('=')
+            .ok_or_else(|| anyhow!("missing '=' in route {spec:?}"))?;
+        let (kind, scope) = selector.split_once('@').unwrap_or((selector, "*"));
+        let kind = match kind.trim() {
+            "*" => None,
+            k => Some(
+                ThreatKind::from_variant_name(k)
+                    .ok_or_else(|| anyhow!("unknown threat kind {k:?} in route {spec:?}"))?,
+            ),
+        };
+        Ok(Self {
+            kind,
+            scope: Scope::parse(scope.trim())?,
+            sinks: sinks
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(Sink::parse)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn matches(&self, info: &AlertInfo) -> bool {
+        self.kind.is_none_or(|k| info.threats.contains(&k)) && self.scope.matches(info)
+    }
+}
+
+/// Where one alert goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub sinks: Vec<Sink>,
+    /// `true` when an `ALERT_ROUTES` rule chose the sinks; `false` for the
+    /// default route.
+    pub explicit: bool,
+}
+
+impl Route {
+    fn default_route() -> Self {
+        Self {
+            sinks: vec![Sink::Telegram, Sink::Webhook, Sink::Voice],
+            explicit: false,
+        }
+    }
+
+    /// Nationwide alerts of little concern to the user's oblast: no sound,
+    /// no call.
+    fn reduced_route() -> Self {
+        Self {
+            sinks: vec![Sink::TelegramSilent, Sink::Webhook],
+            explicit: false,
+        }
+    }
+
+    pub fn has(&self, sink: Sink) -> bool {
+        self.sinks.contains(&sink)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Router {
+    rules: Vec<RouteRule>,
+    exec_cmd: Option<String>,
+}
+
+impl Router {
+    /// | Env var          | Default | Description                              |
+    /// |------------------|---------|------------------------------------------|
+    /// | `ALERT_ROUTES`   | —       | `kind@scope=sink,…` rules, `;`-separated |
+    /// | `ROUTE_EXEC_CMD` | —       | Shell command run by the `exec` sink     |
+    pub fn from_env() -> Result<Self> {
+        let mut router = Self::parse(&std::env::var("ALERT_ROUTES").unwrap_or_default())?;
+        router.exec_cmd = std::env::var("ROUTE_EXEC_CMD")
+            .ok()
+            .filter(|c| !c.trim().is_empty());
+        if router.exec_cmd.is_none() && router.rules.iter().any(|r| r.sinks.contains(&Sink::Exec)) {
+            warn!("ALERT_ROUTES uses the exec sink but ROUTE_EXEC_CMD is not set");
+        }
+        Ok(router)
+    }
+
+    pub fn parse(spec: &str) -> Result<Self> {
+        let rules = spec
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(RouteRule::parse)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            exec_cmd: None,
+        })
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Sinks for an alert.  Alerts without a classification (e.g. forwarded
+    /// by a hook) take the default route.
+    pub fn route(&self, info: Option<&AlertInfo>) -> Route {
+        let Some(info) = info else {
+            return Route::default_route();
+        };
+        match self.rules.iter().find(|r| r.matches(info)) {
+            Some(rule) => Route {
+                sinks: rule.sinks.clone(),
+                explicit: true,
+            },
+            None if info.reduced => Route::reduced_route(),
+            None => Route::default_route(),
+        }
+    }
+
+    /// Start `ROUTE_EXEC_CMD` in the background with the alert on stdin
+    /// and `ALERT_TYPE` / `ALERT_THREATS` / `ALERT_PROXIMITY` /
+    /// `ALERT_NATIONWIDE` set.
+    pub fn spawn_exec(&self, info: Option<&AlertInfo>, alert: &str) {
+        let Some(cmd) = self.exec_cmd.clone() else {
+            return;
+        };
+        let threats = info
+            .map(|i| {
+                i.threats
+                    .iter()
+                    .map(|t| t.variant_name())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
+        let alert_type = info.map_or("new", |i| i.alert_type.name());
+        let proximity = info.map_or("none", |i| i.proximity.name());
+        let nationwide = info.is_some_and(|i| i.nationwide);
+        let alert = alert.to_string();
+        tokio::spawn(async move {
+            let run = async {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(&cmd)
+                    .env("ALERT_TYPE", alert_type)
+                    .env("ALERT_THREATS", threats)
+                    .env("ALERT_PROXIMITY", proximity)
+                    .env("ALERT_NATIONWIDE", nationwide.to_string())
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(alert.as_bytes()).await?;
+                }
+                let status = child.wait().await?;
+                anyhow::Ok(status)
+            };
+            match tokio::time::timeout(EXEC_TIMEOUT, run).await {
+                Ok(Ok(status)) if status.success() => debug!("Exec sink finished"),
+                Ok(Ok(status)) => warn!("Exec sink exited with {status}"),
+                Ok(Err(e)) => warn!("Exec sink failed: {e}"),
+                Err(_) => warn!("Exec sink timed out after {}s", EXEC_TIMEOUT.as_secs()),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::AlertType;
+
+    fn alert(threats: &[ThreatKind], proximity: Proximity, nationwide: bool) -> AlertInfo {
+        AlertInfo {
+            alert_type: AlertType::New,
+            threats: threats.to_vec(),
+            proximity,
+            nationwide,
+            urgent: false,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_picks_the_sinks() {
+        use Sink::*;
+
+        let router = Router::parse(
+            "ballistic@district=telegram,webhook,voice,exec; recon_drone@oblast=telegram_silent\n\
+             *@nationwide=telegram; other=",
+        )
+        .unwrap();
+        assert_eq!(router.rule_count(), 4);
+        let sinks = |info: &AlertInfo| router.route(Some(info)).sinks;
+        assert_eq!(
+            sinks(&alert(&[ThreatKind::Ballistic], Proximity::District, false)),
+            [Telegram, Webhook, Voice, Exec]
+        );
+        // Any of the alert's threats selects the rule.
+        assert_eq!(
+            sinks(&alert(
+                &[ThreatKind::Shahed, ThreatKind::Ballistic],
+                Proximity::District,
+                false
+            )),
+            [Telegram, Webhook, Voice, Exec]
+        );
+        assert_eq!(
+            sinks(&alert(&[ThreatKind::ReconDrone], Proximity::Oblast, false)),
+            [TelegramSilent]
+        );
+        assert_eq!(
+            sinks(&alert(&[ThreatKind::Ballistic], Proximity::None, true)),
+            [Telegram]
+        );
+        let muted = router.route(Some(&alert(&[ThreatKind::Other], Proximity::City, false)));
+        assert!(muted.sinks.is_empty() && muted.explicit);
+
+        // No rule: default route, or the quiet one for reduced alerts.
+        let unmatched = alert(&[ThreatKind::Ballistic], Proximity::City, false);
+        assert_eq!(router.route(Some(&unmatched)), Route::default_route());
+        assert!(!router.route(Some(&unmatched)).explicit);
+        let reduced = AlertInfo {
+            reduced: true,
+            ..alert(&[ThreatKind::Shahed], Proximity::Oblast, false)
+        };
+        assert_eq!(
+            router.route(Some(&reduced)).sinks,
+            [TelegramSilent, Webhook]
+        );
+        assert_eq!(router.route(None), Route::default_route());
+    }
+
+    #[test]
+    fn route_spec_is_lenient_about_case_and_spacing() {
+        let router = Router::parse(" Ballistic @ District = Telegram , , Voice ;; ").unwrap();
+        assert_eq!(router.rule_count(), 1);
+        let route = router.route(Some(&alert(
+            &[ThreatKind::Ballistic],
+            Proximity::District,
+            false,
+        )));
+        assert_eq!(route.sinks, [Sink::Telegram, Sink::Voice]);
+        assert_eq!(Router::parse("").unwrap().rule_count(), 0);
+    }
+
+    #[test]
+    fn malformed_routes_are_rejected() {
+        for (spec, error) in [
+            ("shahed", "missing '='"),
+            ("shahed@city", "missing '='"),
+            ("bogus=telegram", "unknown threat kind"),
+            ("=telegram", "unknown threat kind"),
+            ("shahed@town=telegram", "unknown scope"),
+            ("shahed@=telegram", "unknown scope"),
+            ("shahed@city=pager", "unknown sink"),
+            ("shahed=telegram; ballistic=sms", "unknown sink"),
+        ] {
+            let err = Router::parse(spec).expect_err(spec).to_string();
+            assert!(err.contains(error), "{spec:?}: {err}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Delivery latency SLOs per sink.  An alert's latency runs from the moment
+//! its channel message was received to the sink's acknowledgment (Bot API
+//! replies for every subscriber, webhook 2xx, a call accepted by the voice
+//! provider); the fire-and-forget `exec` sink is not measured.  When a
+//! sink's 90th percentile over its recent deliveries exceeds its target –
+//! e.g. the Bot API during a Telegram degradation – admins are told once,
+//! and again when it recovers, so they can point users to a backup channel.
+
+use crate::routing::Sink;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tracing::warn;
+
+/// Percentile compared against the target.
+const PERCENTILE: f64 = 0.9;
+
+/// Sinks whose latency is tracked, with their default targets in seconds.
+const DEFAULT_TARGETS: &[(Sink, u64)] =
+    &[(Sink::Telegram, 10), (Sink::Webhook, 10), (Sink::Voice, 30)];
+
+#[derive(Debug, Clone)]
+pub struct SloCfg {
+    targets: HashMap<Sink, Duration>,
+    /// Deliveries per sink the percentile is taken over.
+    pub window: usize,
+}
+
+impl SloCfg {
+    /// | Env var           | Default                           | Description                                   |
+    /// |-------------------|-----------------------------------|-----------------------------------------------|
+    /// | `SINK_SLO_SECS`   | `telegram=10,webhook=10,voice=30` | p90 delivery latency target per sink (0 = off) |
+    /// | `SINK_SLO_WINDOW` | `20`                              | Recent deliveries per sink the p90 is over     |
+    pub fn from_env() -> Self {
+        let window = std::env::var("SINK_SLO_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        Self::parse(&std::env::var("SINK_SLO_SECS").unwrap_or_default(), window)
+    }
+
+    /// `telegram=10,webhook=5`; sinks left out keep their default target.
+    pub fn parse(spec: &str, window: usize) -> Self {
+        let mut targets: HashMap<Sink, Duration> = DEFAULT_TARGETS
+            .iter()
+            .map(|(sink, secs)| (*sink, Duration::from_secs(*secs)))
+            .collect();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parsed = part.split_once('=').and_then(|(k, v)| {
+                let sink = Sink::parse(k.trim()).ok()?;
+                let secs = v.trim().parse::<f64>().ok()?;
+                Some((sink, secs))
+            });
+            match parsed {
+                Some((sink, secs)) if targets.contains_key(&sink) => {
+                    if secs > 0.0 {
+                        targets.insert(sink, Duration::from_secs_f64(secs));
+                    } else {
+                        targets.remove(&sink);
+                    }
+                }
+                _ => warn!("Ignoring bad SINK_SLO_SECS entry {part:?}"),
+            }
+        }
+        Self {
+            targets,
+            window: window.max(1),
+        }
+    }
+
+    pub fn target(&self, sink: Sink) -> Option<Duration> {
+        self.targets.get(&sink).copied()
+    }
+}
+
+/// Rolling delivery latencies and SLO state per sink.
+#[derive(Debug)]
+pub struct SloMonitor {
+    cfg: SloCfg,
+    samples: HashMap<Sink, VecDeque<Duration>>,
+    breached: HashSet<Sink>,
+}
+
+impl SloMonitor {
+    pub fn new(cfg: SloCfg) -> Self {
+        Self {
+            cfg,
+            samples: HashMap::new(),
+            breached: HashSet::new(),
+        }
+    }
+
+    /// Nearest-rank p90 of `sink`'s recent deliveries.
+    pub fn percentile(&self, sink: Sink) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.samples.get(&sink)?.iter().copied().collect();
+        sorted.sort();
+        let rank = (PERCENTILE * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// Record a delivery by `sink` that took `latency`.  Returns the admin
+    /// note when the sink starts or stops missing its target; a breach
+    /// needs at least half a window of deliveries.
+    pub fn observe(&mut self, sink: Sink, latency: Duration) -> Option<String> {
+        let target = self.cfg.target(sink)?;
+        let window = self.cfg.window;
+        let samples = self.samples.entry(sink).or_default();
+        if samples.len() >= window {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+        let count = samples.len();
+        let p90 = self.percentile(sink)?;
+        let slow = p90 > target;
+        if slow && count >= window.div_ceil(2) && self.breached.insert(sink) {
+            Some(format!(
+                "🐢 {} deliveries are slow: p90 {:.1}s over the last {count} (SLO {:.0}s)\n\
+                 Consider pointing subscribers to a backup channel.",
+                sink.name(),
+                p90.as_secs_f64(),
+                target.as_secs_f64()
+            ))
+        } else if !slow && self.breached.remove(&sink) {
+            Some(format!(
+                "✅ {} deliveries are back within SLO: p90 {:.1}s (SLO {:.0}s)",
+                sink.name(),
+                p90.as_secs_f64(),
+                target.as_secs_f64()
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sink_slo_notifies_on_sustained_breach_and_recovery() {
+        let cfg = SloCfg::parse("telegram=5, webhook=0, bogus=3", 4);
+        assert_eq!(cfg.target(Sink::Telegram), Some(Duration::from_secs(5)));
+        assert_eq!(cfg.target(Sink::Webhook), None, "0 turns tracking off");
+        assert_eq!(cfg.target(Sink::Voice), Some(Duration::from_secs(30)));
+
+        let mut slo = SloMonitor::new(cfg);
+        let secs = Duration::from_secs;
+        assert!(slo.observe(Sink::Webhook, secs(60)).is_none());
+        // Too few deliveries for a breach yet.
+        assert!(slo.observe(Sink::Telegram, secs(9)).is_none());
+        let note = slo
+            .observe(Sink::Telegram, secs(12))
+            .expect("p90 over target");
+        assert!(
+            note.contains("telegram") && note.contains("p90 12.0s"),
+            "{note}"
+        );
+        assert!(slo.observe(Sink::Telegram, secs(20)).is_none(), "told once");
+
+        for _ in 0..3 {
+            assert!(slo.observe(Sink::Telegram, secs(1)).is_none());
+        }
+        let note = slo.observe(Sink::Telegram, secs(1)).expect("recovered");
+        assert!(note.starts_with("✅ telegram"), "{note}");
+        assert_eq!(slo.percentile(Sink::Telegram), Some(secs(1)));
+    }
+}
@@ -0,0 +1,319 @@
+//! Keyword suggestions: a feedback loop for the keyword lists.  Archived
+//! messages the keyword tables missed entirely are periodically shown to the
+//! LLM without keyword hints; stems that are frequent in the ones it calls
+//! active threats, rare in the rest and not covered by any list are written
+//! to a report for maintainers (new slang, nicknames, misspellings).
+
+use crate::archive::{self, SharedArchive};
+use crate::classifier::tokenize;
+use crate::filter::detect_threats;
+use crate::filter::threat_keywords;
+use crate::filter::threat_kind::ThreatKind;
+use crate::llm::LlmFilter;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Unforwarded messages read per run, before the keyword check.
+const MAX_SCANNED: usize = 5000;
+
+/// Suggestions listed in the report.
+const MAX_SUGGESTIONS: usize = 30;
+
+/// LLM verdicts below this confidence are ignored.
+const MIN_CONFIDENCE: f32 = 0.6;
+
+#[derive(Debug, Clone)]
+pub struct SuggestCfg {
+    pub interval: Duration,
+    /// How far back each run looks.
+    pub lookback_secs: i64,
+    /// Keyword-less messages sent to the LLM per run.
+    pub max_messages: usize,
+    /// Threat messages a stem must appear in.
+    pub min_count: usize,
+    pub report_path: String,
+}
+
+impl SuggestCfg {
+    /// `None` unless `KEYWORD_SUGGEST_INTERVAL_HOURS` is set.
+    ///
+    /// | Env var                          | Default                   | Description                         |
+    /// |----------------------------------|---------------------------|-------------------------------------|
+    /// | `KEYWORD_SUGGEST_INTERVAL_HOURS` | `0`                       | Hours between runs (0 = off)        |
+    /// | `KEYWORD_SUGGEST_LOOKBACK_HOURS` | `24`                      | Archive window mined per run        |
+    /// | `KEYWORD_SUGGEST_MAX_MESSAGES`   | `200`                     | Messages sent to the LLM per run    |
+    /// | `KEYWORD_SUGGEST_MIN_COUNT`      | `3`                       | Threat messages a stem must be in   |
+    /// | `KEYWORD_SUGGEST_PATH`           | `keyword_suggestions.md`  | Report file (overwritten each run)  |
+    pub fn from_env() -> Option<Self> {
+        let num = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        let hours = num("KEYWORD_SUGGEST_INTERVAL_HOURS", 0);
+        if hours == 0 {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_secs(hours * 3600),
+            lookback_secs: num("KEYWORD_SUGGEST_LOOKBACK_HOURS", 24) as i64 * 3600,
+            max_messages: num("KEYWORD_SUGGEST_MAX_MESSAGES", 200) as usize,
+            min_count: num("KEYWORD_SUGGEST_MIN_COUNT", 3).max(1) as usize,
+            report_path: std::env::var("KEYWORD_SUGGEST_PATH")
+                .unwrap_or_else(|_| "keyword_suggestions.md".into()),
+        })
+    }
+}
+
+/// One candidate stem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub stem: String,
+    /// Threat messages containing the stem.
+    pub hits: usize,
+    /// Non-threat messages containing the stem.
+    pub misses: usize,
+    /// Threat kinds the LLM gave those messages, most frequent first.
+    pub kinds: Vec<ThreatKind>,
+    /// First threat message containing the stem.
+    pub example: String,
+}
+
+/// Whether `stem` is already covered by a keyword list entry (one is a
+/// prefix of the other).  Suppression keywords count too: the operator
+/// chose to drop those messages.
+pub fn is_known_stem(stem: &str) -> bool {
+    let keywords = threat_keywords::active();
+    keywords
+        .threats()
+        .iter()
+        .chain(keywords.words())
+        .flat_map(|(_, kws)| kws.iter())
+        .chain(keywords.urgency())
+        .chain(keywords.nationwide())
+        .chain(keywords.suppress())
+        .flat_map(|kw| kw.split_whitespace())
+        .filter(|w| w.chars().count() >= 3)
+        .any(|w| w.starts_with(stem) || stem.starts_with(w))
+}
+
+/// Rank stems of `samples` (message text, threats the LLM found – empty
+/// for "not a threat") by how much more often they occur in threat
+/// messages.  Stems in fewer than `min_count` threat messages, short or
+/// numeric stems and known ones are skipped.
+pub fn rank(samples: &[(String, Vec<ThreatKind>)], min_count: usize) -> Vec<Suggestion> {
+    struct Tally {
+        hits: usize,
+        misses: usize,
+        kinds: HashMap<ThreatKind, usize>,
+        example: String,
+    }
+
+    let threat_total = samples.iter().filter(|(_, t)| !t.is_empty()).count();
+    let other_total = samples.len() - threat_total;
+    let mut tallies: HashMap<String, Tally> = HashMap::new();
+    for (text, threats) in samples {
+        for stem in tokenize(text) {
+            if stem.chars().count() < 4 || stem.chars().any(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let tally = tallies.entry(stem).or_insert_with(|| Tally {
+                hits: 0,
+                misses: 0,
+                kinds: HashMap::new(),
+                example: String::new(),
+            });
+            if threats.is_empty() {
+                tally.misses += 1;
+                continue;
+            }
+            tally.hits += 1;
+            for kind in threats {
+                *tally.kinds.entry(*kind).or_default() += 1;
+            }
+            if tally.example.is_empty() {
+                tally.example = text.clone();
+            }
+        }
+    }
+
+    // Smoothed ratio of the stem's rate in threat vs. other messages.
+    let score = |t: &Tally| {
+        let hit_rate = (t.hits as f64 + 1.0) / (threat_total as f64 + 2.0);
+        let miss_rate = (t.misses as f64 + 1.0) / (other_total as f64 + 2.0);
+        hit_rate / miss_rate
+    };
+    let mut ranked: Vec<(f64, Suggestion)> = tallies
+        .into_iter()
+        .filter(|(stem, t)| t.hits >= min_count && t.hits > t.misses && !is_known_stem(stem))
+        .map(|(stem, t)| {
+            let mut kinds: Vec<(ThreatKind, usize)> =
+                t.kinds.iter().map(|(k, n)| (*k, *n)).collect();
+            kinds.sort_by_key(|(k, n)| (std::cmp::Reverse(*n), k.variant_name()));
+            let suggestion = Suggestion {
+                stem,
+                hits: t.hits,
+                misses: t.misses,
+                kinds: kinds.into_iter().map(|(k, _)| k).collect(),
+                example: t.example.clone(),
+            };
+            (score(&t), suggestion)
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then(b.1.hits.cmp(&a.1.hits))
+            .then(a.1.stem.cmp(&b.1.stem))
+    });
+    ranked.truncate(MAX_SUGGESTIONS);
+    ranked.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Result of one mining run.
+#[derive(Debug, Clone)]
+pub struct SuggestionReport {
+    pub from_ts: i64,
+    pub to_ts: i64,
+    /// Keyword-less messages the LLM classified.
+    pub classified: usize,
+    /// Of those, the ones it called active threats.
+    pub threats: usize,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl SuggestionReport {
+    /// Markdown report.
+    pub fn render(&self) -> String {
+        let day = |ts: i64| {
+            DateTime::<Utc>::from_timestamp(ts, 0)
+                .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_default()
+        };
+        let mut out = format!(
+            "# Keyword suggestions\n\n\
+             {} – {}: {} message(s) without keyword matches classified, {} called active threats by the LLM.\n\n",
+            day(self.from_ts),
+            day(self.to_ts),
+            self.classified,
+            self.threats
+        );
+        if self.suggestions.is_empty() {
+            out.push_str("No new stems.\n");
+            return out;
+        }
+        out.push_str("| Stem | Threat msgs | Other msgs | Kinds | Example |\n");
+        out.push_str("|---|---|---|---|---|\n");
+        for s in &self.suggestions {
+            let kinds: Vec<&str> = s.kinds.iter().map(|k| k.variant_name()).collect();
+            let example: String = s
+                .example
+                .chars()
+                .take(120)
+                .collect::<String>()
+                .replace(['\n', '|'], " ");
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {} | {} |\n",
+                s.stem,
+                s.hits,
+                s.misses,
+                kinds.join(", "),
+                example
+            ));
+        }
+        out
+    }
+}
+
+/// Classify the keyword-less messages of the last `cfg.lookback_secs` and
+/// rank candidate stems.
+pub async fn mine(
+    archive: &SharedArchive,
+    llm: &LlmFilter,
+    cfg: &SuggestCfg,
+) -> Result<SuggestionReport> {
+    let to_ts = Utc::now().timestamp();
+    let from_ts = to_ts - cfg.lookback_secs;
+    let messages = archive::unforwarded_messages(archive, from_ts, to_ts, MAX_SCANNED)?;
+    let mut samples = Vec::new();
+    for msg in messages
+        .into_iter()
+        .filter(|m| detect_threats(&m.text.to_lowercase()).is_empty())
+        .take(cfg.max_messages)
+    {
+        let Some(verdict) = llm.discover(&msg.text).await else {
+            continue;
+        };
+        let threats = if verdict.confidence.is_none_or(|c| c >= MIN_CONFIDENCE) {
+            verdict.threats
+        } else {
+            Vec::new()
+        };
+        samples.push((msg.text, threats));
+    }
+    Ok(SuggestionReport {
+        from_ts,
+        to_ts,
+        classified: samples.len(),
+        threats: samples.iter().filter(|(_, t)| !t.is_empty()).count(),
+        suggestions: rank(&samples, cfg.min_count),
+    })
+}
+
+/// Runs forever: mines the archive every `cfg.interval` and rewrites the
+/// report.
+pub async fn run_suggestion_loop(archive: SharedArchive, llm: LlmFilter, cfg: SuggestCfg) {
+    info!(
+        "Keyword suggestions every {}h → {}",
+        cfg.interval.as_secs() / 3600,
+        cfg.report_path
+    );
+    loop {
+        tokio::time::sleep(cfg.interval).await;
+        let result = mine(&archive, &llm, &cfg).await.and_then(|report| {
+            std::fs::write(&cfg.report_path, report.render())
+                .with_context(|| format!("failed to write {}", cfg.report_path))?;
+            Ok(report)
+        });
+        match result {
+            Ok(report) => info!(
+                "Keyword suggestions: {} stem(s) from {} threat message(s)",
+                report.suggestions.len(),
+                report.threats
+            ),
+            Err(e) => warn!("Keyword suggestion run failed: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_suggestions_rank_new_stems_from_missed_threats() {
+        let threat = |text: &str| (text.to_string(), vec![ThreatKind::Missile]);
+        let other = |text: &str| (text.to_string(), Vec::new());
+        let samples = vec![
+            threat("Балалайки летять на Київ"),
+            threat("Ще дві балалайки, Черкащина"),
+            threat("балалайка над Дніпром, мопеди теж"),
+            threat("мопеди над Полтавою, 2 шт"),
+            threat("мопеди знову, ранок тихий"),
+            other("ранок тихий, світло є"),
+            other("ранок у Києві спокійний"),
+        ];
+        assert!(is_known_stem("мопеди"));
+        assert!(!is_known_stem("балала"));
+
+        let suggestions = rank(&samples, 3);
+        let stems: Vec<&str> = suggestions.iter().map(|s| s.stem.as_str()).collect();
+        assert_eq!(stems, ["балала"]);
+        assert_eq!(suggestions[0].hits, 3);
+        assert_eq!(suggestions[0].kinds, [ThreatKind::Missile]);
+        assert_eq!(suggestions[0].example, "Балалайки летять на Київ");
+    }
+}
@@ -0,0 +1,319 @@
+//! Daily morning summary: an overnight digest built from the message
+//! archive and sent to chats that opted in with `/morning_summary`.
+
+use crate::archive::{self, AlertSummary, SharedArchive};
+use crate::bot::{self, SharedDb};
+use crate::filter::threat_kind::ThreatKind;
+use crate::llm::LlmFilter;
+use chrono::{DateTime, Duration as ChronoDuration, FixedOffset, Utc};
+use reqwest::Client as HttpClient;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// When and how the morning summary is sent.
+#[derive(Debug, Clone)]
+pub struct MorningSummaryCfg {
+    pub hour: u32,
+    pub minute: u32,
+    pub tz_offset_minutes: i32,
+    /// How far back the "overnight" window reaches.
+    pub lookback_hours: i64,
+    /// Rephrase the digest with the LLM (when `LLM_ENABLED`).
+    pub use_llm: bool,
+}
+
+impl MorningSummaryCfg {
+    /// | Env var                              | Default | Description                     |
+    /// |--------------------------------------|---------|---------------------------------|
+    /// | `MORNING_SUMMARY_TIME`               | `07:00` | Local send time (`HH:MM`)       |
+    /// | `MORNING_SUMMARY_TZ_OFFSET_MINUTES`  | `0`     | Local timezone offset from UTC  |
+    /// | `MORNING_SUMMARY_LOOKBACK_HOURS`     | `12`    | Size of the overnight window    |
+    /// | `MORNING_SUMMARY_LLM`                | `false` | Polish the digest with the LLM  |
+    pub fn from_env() -> Self {
+        let (hour, minute) = std::env::var("MORNING_SUMMARY_TIME")
+            .ok()
+            .and_then(|v| parse_hh_mm(&v))
+            .unwrap_or((7, 0));
+        let tz_offset_minutes = std::env::var("MORNING_SUMMARY_TZ_OFFSET_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let lookback_hours = std::env::var("MORNING_SUMMARY_LOOKBACK_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &i64| *v > 0)
+            .unwrap_or(12);
+        let use_llm = std::env::var("MORNING_SUMMARY_LLM")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        Self {
+            hour,
+            minute,
+            tz_offset_minutes,
+            lookback_hours,
+            use_llm,
+        }
+    }
+
+    /// Time left until the next scheduled send after `now`.
+    fn until_next(&self, now: DateTime<Utc>) -> Duration {
+        let offset = FixedOffset::east_opt(self.tz_offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        let local = now.with_timezone(&offset);
+        let today_at = local
+            .date_naive()
+            .and_hms_opt(self.hour, self.minute, 0)
+            .and_then(|t| t.and_local_timezone(offset).single());
+        let Some(mut next) = today_at else {
+            return Duration::from_secs(24 * 60 * 60);
+        };
+        if next <= local {
+            next += ChronoDuration::days(1);
+        }
+        (next.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::from_secs(60))
+    }
+}
+
+fn parse_hh_mm(raw: &str) -> Option<(u32, u32)> {
+    let (h, m) = raw.trim().split_once(':')?;
+    let (h, m) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some((h, m))
+}
+
+fn format_duration(secs: i64) -> String {
+    let mins = secs.max(0) / 60;
+    match (mins / 60, mins % 60) {
+        (0, m) => format!("{m} хв"),
+        (h, 0) => format!("{h} год"),
+        (h, m) => format!("{h} год {m} хв"),
+    }
+}
+
+/// Render the digest text for `summary` covering the last `lookback_hours`.
+pub fn format_summary(summary: &AlertSummary, lookback_hours: i64) -> String {
+    let mut out = format!("🌅 Ранкове зведення (останні {lookback_hours} год)\n———\n");
+    if summary.total_alerts == 0 {
+        out.push_str("Тривог для вашого району не було. Спокійного дня!");
+        return out;
+    }
+
+    out.push_str(&format!(
+        "Тривог для вашого району: {} (усього {})\n",
+        summary.area_alerts, summary.total_alerts
+    ));
+    if !summary.threat_mix.is_empty() {
+        let mix = summary
+            .threat_mix
+            .iter()
+            .map(|(name, n)| match ThreatKind::from_variant_name(name) {
+                Some(kind) => format!("{} {} ×{n}", kind.emoji(), kind.label()),
+                None => format!("{name} ×{n}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!("Загрози: {mix}\n"));
+    }
+    out.push_str(&format!(
+        "Хвиль: {}, загальна тривалість: {}",
+        summary.waves,
+        format_duration(summary.total_duration_secs)
+    ));
+    out
+}
+
+/// Runs forever: sleeps until the configured local time, then sends the
+/// overnight digest to every chat that enabled `/morning_summary`.
+pub async fn run_morning_summary_loop(
+    http: HttpClient,
+    bot_token: String,
+    bot_db: SharedDb,
+    archive_db: SharedArchive,
+    cfg: MorningSummaryCfg,
+) {
+    let llm = LlmFilter::from_env();
+    info!(
+        "Morning summary scheduled at {:02}:{:02} (tz offset {} min)",
+        cfg.hour, cfg.minute, cfg.tz_offset_minutes
+    );
+
+    loop {
+        tokio::time::sleep(cfg.until_next(Utc::now())).await;
+
+        let to_ts = Utc::now().timestamp();
+        let from_ts = to_ts - cfg.lookback_hours * 60 * 60;
+        let summary = match archive::summarize(&archive_db, from_ts, to_ts) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to build morning summary: {e}");
+                continue;
+            }
+        };
+        let mut text = format_summary(&summary, cfg.lookback_hours);
+        if cfg.use_llm
+            && let Some(polished) = llm.polish_summary(&text).await
+        {
+            text = polished;
+        }
+
+        let chats = match bot::get_morning_summary_subscribers(&bot_db) {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("Failed to load morning summary subscribers: {e}");
+                continue;
+            }
+        };
+        info!("Sending morning summary to {} chat(s).", chats.len());
+        for chat_id in chats {
+            if let Err(e) = bot::send_message(&http, &bot_token, chat_id, &text).await {
+                warn!("Failed to deliver morning summary to chat_id={chat_id}: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchivedMessage, CorrelationCfg};
+    use crate::filter::{AlertInfo, AlertType, Proximity};
+
+    fn cfg(hour: u32, minute: u32, tz_offset_minutes: i32) -> MorningSummaryCfg {
+        MorningSummaryCfg {
+            hour,
+            minute,
+            tz_offset_minutes,
+            lookback_hours: 12,
+            use_llm: false,
+        }
+    }
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn next_send_is_today_or_rolls_over_to_tomorrow() {
+        let at_seven = cfg(7, 0, 0);
+        let hours = |h: u64| Duration::from_secs(h * 3600);
+        assert_eq!(
+            at_seven.until_next(utc("2024-03-10T06:30:00Z")),
+            Duration::from_secs(30 * 60)
+        );
+        // Exactly at or past the send time: tomorrow.
+        assert_eq!(at_seven.until_next(utc("2024-03-10T07:00:00Z")), hours(24));
+        assert_eq!(at_seven.until_next(utc("2024-03-10T08:00:00Z")), hours(23));
+
+        // 07:00 in UTC+3 is 04:00 UTC.
+        let kyiv = cfg(7, 0, 180);
+        assert_eq!(kyiv.until_next(utc("2024-03-10T03:00:00Z")), hours(1));
+        assert_eq!(kyiv.until_next(utc("2024-03-10T05:00:00Z")), hours(23));
+        // 02:00 UTC is still the previous evening in UTC-5; 07:30 there is
+        // 12:30 UTC.
+        assert_eq!(
+            cfg(7, 30, -300).until_next(utc("2024-03-10T02:00:00Z")),
+            Duration::from_secs(10 * 3600 + 30 * 60)
+        );
+    }
+
+    #[test]
+    fn send_time_must_be_a_valid_clock_time() {
+        assert_eq!(parse_hh_mm("07:30"), Some((7, 30)));
+        assert_eq!(parse_hh_mm(" 23:59 "), Some((23, 59)));
+        assert_eq!(parse_hh_mm("0:0"), Some((0, 0)));
+        for raw in ["24:00", "7:60", "7", "07-30", "ab:cd", "-1:00", ""] {
+            assert_eq!(parse_hh_mm(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn durations_are_rounded_down_to_minutes() {
+        assert_eq!(format_duration(-5), "0 хв");
+        assert_eq!(format_duration(59), "0 хв");
+        assert_eq!(format_duration(45 * 60), "45 хв");
+        assert_eq!(format_duration(3600), "1 год");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60 + 30), "2 год 5 хв");
+    }
+
+    #[test]
+    fn digest_text_for_quiet_and_busy_nights() {
+        assert_eq!(
+            format_summary(&AlertSummary::default(), 12),
+            "🌅 Ранкове зведення (останні 12 год)\n———\n\
+             Тривог для вашого району не було. Спокійного дня!"
+        );
+        let summary = AlertSummary {
+            area_alerts: 2,
+            total_alerts: 3,
+            threat_mix: vec![("Shahed".into(), 2), ("Mystery".into(), 1)],
+            waves: 2,
+            total_duration_secs: 5400,
+        };
+        assert_eq!(
+            format_summary(&summary, 8),
+            "🌅 Ранкове зведення (останні 8 год)\n———\n\
+             Тривог для вашого району: 2 (усього 3)\n\
+             Загрози: 🔺 Шахед / дрон ×2, Mystery ×1\n\
+             Хвиль: 2, загальна тривалість: 1 год 30 хв"
+        );
+    }
+
+    #[test]
+    fn archive_summary_counts_alerts_threats_and_waves() {
+        let db = archive::open_archive(":memory:").unwrap();
+        let correlation = CorrelationCfg {
+            wave_gap_secs: 3600,
+            damage_window_secs: 10800,
+        };
+        let record = |ts: i64, threats: &[ThreatKind], proximity, nationwide| {
+            let msg = ArchivedMessage {
+                timestamp: ts,
+                channel_id: 1,
+                channel_title: "ch".into(),
+                text: String::new(),
+            };
+            let info = AlertInfo {
+                alert_type: AlertType::New,
+                threats: threats.to_vec(),
+                proximity,
+                nationwide,
+                urgent: false,
+                reduced: false,
+                escalated_from: None,
+                location_uncertain: false,
+                places: Vec::new(),
+                confidence: None,
+            };
+            archive::record_alert(&db, &msg, &info, &correlation).unwrap()
+        };
+        let first = record(1000, &[ThreatKind::Shahed], Proximity::City, false);
+        let joined = record(
+            1600,
+            &[ThreatKind::Shahed, ThreatKind::Ballistic],
+            Proximity::Oblast,
+            false,
+        );
+        record(2000, &[ThreatKind::AllClear], Proximity::None, false);
+        let second = record(2100, &[ThreatKind::Shahed], Proximity::None, true);
+        record(2200, &[ThreatKind::Shahed], Proximity::None, false);
+        record(20000, &[ThreatKind::Ballistic], Proximity::City, false);
+        assert_eq!(first, joined);
+        assert_ne!(first, second, "an all-clear ends the wave");
+
+        let summary = archive::summarize(&db, 0, 20000).unwrap();
+        assert_eq!(summary.total_alerts, 5);
+        assert_eq!(summary.area_alerts, 3, "nationwide counts, out of area not");
+        assert_eq!(
+            summary.threat_mix,
+            [("Shahed".to_string(), 4), ("Ballistic".to_string(), 1)]
+        );
+        assert_eq!(summary.waves, 2);
+        assert_eq!(summary.total_duration_secs, 1000 + 100);
+
+        let empty = archive::summarize(&db, 3000, 20000).unwrap();
+        assert_eq!(empty.total_alerts, 0);
+        assert!(format_summary(&empty, 12).contains("Тривог для вашого району не було"));
+    }
+}
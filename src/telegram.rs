@@ -1,11 +1,19 @@
 use anyhow::{Context, Result, anyhow};
-use grammers_client::{Client, SignInError};
+use grammers_client::grammers_tl_types as tl;
+use grammers_client::types::Peer;
+use grammers_client::{Client, InvocationError, SignInError};
 use grammers_mtsender::SenderPool;
+use grammers_session::Session;
+use grammers_session::defs::{DcOption, PeerAuth, PeerId, PeerRef};
 use grammers_session::storages::SqliteSession;
+use sqlite::State;
+use std::collections::HashMap;
 use std::io::Write;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+use std::ops::Range;
 use std::sync::Arc;
 use tokio::io::{self, AsyncBufReadExt};
-use tracing::info;
+use tracing::{debug, info, warn};
 
 #[derive(Clone)]
 pub struct TgCfg {
@@ -15,29 +23,112 @@ pub struct TgCfg {
     pub two_fa_password: Option<String>,
     pub session_path: String,
     pub channels: Vec<String>,
+    /// Chat folder titles (`folder:<title>` entries in `TG_CHANNELS`) whose
+    /// channels are watched as well.
+    pub folders: Vec<String>,
+    /// `TG_TEST_MODE`: home datacenter in Telegram's test environment.
+    pub test_dc: Option<i32>,
 }
 
 pub fn load_tg_cfg() -> Result<TgCfg> {
-    let channels = parse_channels(&must_env("TG_CHANNELS")?);
-    if channels.is_empty() {
+    let (folders, channels): (Vec<String>, Vec<String>) = parse_channels(&must_env("TG_CHANNELS")?)
+        .into_iter()
+        .partition(|c| c.to_lowercase().starts_with(FOLDER_PREFIX));
+    let folders: Vec<String> = folders
+        .iter()
+        .map(|f| f[FOLDER_PREFIX.len()..].trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect();
+    if channels.is_empty() && folders.is_empty() {
         return Err(anyhow!("TG_CHANNELS is empty"));
     }
 
+    let phone = must_env("TG_PHONE")?;
+    let test_dc = crate::bot::test_mode().then(|| {
+        std::env::var("TG_TEST_DC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| test_phone_dc(&phone))
+            .unwrap_or(2)
+    });
+    // Keys of one environment are useless in the other.
+    let default_session = if test_dc.is_some() {
+        "./telegram.test.session.sqlite"
+    } else {
+        "./telegram.session.sqlite"
+    };
+
     Ok(TgCfg {
         api_id: must_env("TG_API_ID")?
             .parse()
             .context("TG_API_ID must be i32")?,
         api_hash: must_env("TG_API_HASH")?,
-        phone: must_env("TG_PHONE")?,
+        phone,
         two_fa_password: std::env::var("TG_2FA_PASSWORD").ok(),
-        session_path: std::env::var("TG_SESSION_PATH")
-            .unwrap_or_else(|_| "./telegram.session.sqlite".into()),
+        session_path: std::env::var("TG_SESSION_PATH").unwrap_or_else(|_| default_session.into()),
         channels,
+        folders,
+        test_dc,
     })
 }
 
+/// Datacenters of Telegram's test environment.
+const TEST_DCS: &[(i32, [u8; 4])] = &[
+    (1, [149, 154, 175, 10]),
+    (2, [149, 154, 167, 40]),
+    (3, [149, 154, 175, 117]),
+];
+
+const TEST_DC_PORT: u16 = 443;
+
+/// DC of a test-environment phone number `99966XYYYY` (`X` is the DC).
+fn test_phone_dc(phone: &str) -> Option<i32> {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.len() != 10 || !digits.starts_with("99966") {
+        return None;
+    }
+    let dc = digits[5..6].parse().ok()?;
+    TEST_DCS.iter().any(|(id, _)| *id == dc).then_some(dc)
+}
+
+/// Test accounts get no SMS: the login code is the DC digit five times.
+fn test_login_code(phone: &str) -> Option<String> {
+    test_phone_dc(phone).map(|dc| dc.to_string().repeat(5))
+}
+
+/// Point `session` at the test datacenters.  Options already set to the
+/// test addresses keep their auth keys; a session without a login starts
+/// at `home_dc`.
+fn use_test_dcs(session: &SqliteSession, home_dc: i32) {
+    for &(id, ip) in TEST_DCS {
+        let ipv4 = SocketAddrV4::new(Ipv4Addr::from(ip), TEST_DC_PORT);
+        if session.dc_option(id).is_some_and(|o| o.ipv4 == ipv4) {
+            continue;
+        }
+        session.set_dc_option(&DcOption {
+            id,
+            ipv4,
+            ipv6: SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 0, 0),
+            auth_key: None,
+        });
+    }
+    let logged_in = session
+        .dc_option(session.home_dc_id())
+        .is_some_and(|o| o.auth_key.is_some());
+    if !logged_in {
+        session.set_home_dc_id(home_dc);
+    }
+}
+
+/// `TG_CHANNELS` entry prefix selecting a chat folder, e.g. `folder:OSINT sources`.
+const FOLDER_PREFIX: &str = "folder:";
+
 pub fn connect(cfg: &TgCfg) -> Result<(Client, SenderPool)> {
     let session = Arc::new(SqliteSession::open(&cfg.session_path)?);
+    if let Some(dc) = cfg.test_dc {
+        info!("TG_TEST_MODE: using Telegram test DC {dc}");
+        use_test_dcs(&session, dc);
+    }
     let pool = SenderPool::new(Arc::clone(&session), cfg.api_id);
     let client = Client::new(&pool);
     Ok((client, pool))
@@ -54,7 +145,10 @@ pub async fn ensure_user_login(client: &Client, cfg: &TgCfg) -> Result<()> {
         .await
         .context("request_login_code failed")?;
 
-    let code = read_line("Enter the login code you received: ").await?;
+    let code = match cfg.test_dc.and_then(|_| test_login_code(&cfg.phone)) {
+        Some(code) => code,
+        None => read_line("Enter the login code you received: ").await?,
+    };
 
     match client.sign_in(&token, &code).await {
         Ok(user) => {
@@ -107,3 +201,523 @@ async fn read_line(prompt: &str) -> Result<String> {
     stdin.read_line(&mut line).await?;
     Ok(line.trim().to_string())
 }
+
+// ───────────────────────── Account health ────────────────────────────────
+
+/// Result of a periodic session check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountHealth {
+    Ok,
+    /// The session was revoked or the account logged out; retrying won't help.
+    Unauthorized(String),
+    /// Telegram asked us to back off (`FLOOD_WAIT`).
+    RateLimited {
+        wait_secs: u32,
+    },
+    /// The account is restricted or frozen by Telegram.
+    Restricted(String),
+    /// The check itself failed (network, timeouts); usually transient.
+    Unknown(String),
+}
+
+impl AccountHealth {
+    /// Admin notification with remediation steps, `None` for states that
+    /// don't need operator action.
+    pub fn report(&self, session_path: &str) -> Option<String> {
+        match self {
+            Self::Ok | Self::Unknown(_) => None,
+            Self::Unauthorized(e) => Some(format!(
+                "⛔ Telegram session is no longer authorized ({e}).\n\
+                 To fix:\n\
+                 1. Stop the relay.\n\
+                 2. Check Settings → Devices on your phone: the session may have been terminated there.\n\
+                 3. Delete the session file `{session_path}`.\n\
+                 4. Start the relay in a terminal and enter the login code (and 2FA password)."
+            )),
+            Self::RateLimited { wait_secs } => Some(format!(
+                "⏳ Telegram is rate-limiting this account (FLOOD_WAIT {wait_secs}s).\n\
+                 Channel lookups and gap backfill may fail until it expires. If it keeps \
+                 happening, reduce TG_CHANNELS, disable GAP_BACKFILL and avoid frequent restarts."
+            )),
+            Self::Restricted(reasons) => Some(format!(
+                "🚫 Telegram has restricted this account: {reasons}.\n\
+                 Open @SpamBot from the account to see the details and appeal; channel updates \
+                 may stop arriving until the restriction is lifted."
+            )),
+        }
+    }
+}
+
+/// Errors that mean the session was revoked and won't recover by retrying.
+pub fn is_auth_error(e: &InvocationError) -> bool {
+    [
+        "AUTH_KEY_UNREGISTERED",
+        "AUTH_KEY_DUPLICATED",
+        "SESSION_REVOKED",
+        "SESSION_EXPIRED",
+        "USER_DEACTIVATED*",
+    ]
+    .iter()
+    .any(|name| e.is(name))
+}
+
+/// Verify the session with a cheap `users.getSelf` call.  Doubles as a
+/// keep-alive on quiet connections.
+pub async fn check_account_health(client: &Client) -> AccountHealth {
+    match client.get_me().await {
+        Ok(me) if me.restricted() => {
+            let reasons: Vec<String> = me
+                .restriction_reason()
+                .into_iter()
+                .map(|r| format!("{} ({})", r.text, r.reason))
+                .collect();
+            AccountHealth::Restricted(if reasons.is_empty() {
+                "no reason given".into()
+            } else {
+                reasons.join("; ")
+            })
+        }
+        Ok(_) => AccountHealth::Ok,
+        Err(e) if is_auth_error(&e) => AccountHealth::Unauthorized(e.to_string()),
+        Err(InvocationError::Rpc(rpc)) if rpc.is("FLOOD_WAIT") || rpc.code == 420 => {
+            AccountHealth::RateLimited {
+                wait_secs: rpc.value.unwrap_or(0),
+            }
+        }
+        Err(e) if e.is("FROZEN_*") => AccountHealth::Restricted(e.to_string()),
+        Err(e) => AccountHealth::Unknown(e.to_string()),
+    }
+}
+
+// ───────────────────────── Channel resolution ────────────────────────────
+
+/// Persistent `username → (channel id, access hash, title)` map.  Entries
+/// younger than the TTL are used without calling `resolve_username`, which
+/// Telegram rate-limits hard, so restarts do not burst resolve calls.  Older
+/// entries still let a channel that renamed its @username, or that fails to
+/// resolve while Telegram is flaky, be reached by id.
+pub struct PeerCache {
+    db: sqlite::Connection,
+    /// `None` always resolves usernames first.
+    ttl_hours: Option<i64>,
+}
+
+/// A cached channel.
+struct CachedPeer {
+    channel_id: i64,
+    access_hash: i64,
+    title: String,
+    broadcast: bool,
+    resolved_at: String,
+}
+
+impl CachedPeer {
+    /// A peer built from the cache alone, without a network call.  It has
+    /// no username or photo; id and access hash are all reading needs.
+    fn to_peer(&self) -> Peer {
+        Peer::from_raw(tl::enums::Chat::ChannelForbidden(
+            tl::types::ChannelForbidden {
+                broadcast: self.broadcast,
+                megagroup: !self.broadcast,
+                id: self.channel_id,
+                access_hash: self.access_hash,
+                title: self.title.clone(),
+                until_date: None,
+            },
+        ))
+    }
+}
+
+impl PeerCache {
+    /// | Env var                   | Default                  | Description                          |
+    /// |---------------------------|--------------------------|--------------------------------------|
+    /// | `TG_PEER_CACHE_PATH`      | `./channel_peers.sqlite` | Resolved channel cache               |
+    /// | `TG_PEER_CACHE_TTL_HOURS` | `24`                     | Skip resolving entries this fresh    |
+    pub fn open_from_env() -> Result<Self> {
+        let path =
+            std::env::var("TG_PEER_CACHE_PATH").unwrap_or_else(|_| "./channel_peers.sqlite".into());
+        let ttl_hours = std::env::var("TG_PEER_CACHE_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(24);
+        let db =
+            sqlite::open(&path).with_context(|| format!("failed to open peer cache at {path}"))?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS channel_peers (
+                username    TEXT    PRIMARY KEY,
+                channel_id  INTEGER NOT NULL,
+                access_hash INTEGER NOT NULL,
+                title       TEXT    NOT NULL,
+                resolved_at TEXT    NOT NULL DEFAULT (datetime('now'))
+             );",
+        )?;
+        // Caches written before the column existed; fails once it does.
+        let _ =
+            db.execute("ALTER TABLE channel_peers ADD COLUMN broadcast INTEGER NOT NULL DEFAULT 1");
+        Ok(Self {
+            db,
+            ttl_hours: Some(ttl_hours).filter(|h| *h > 0),
+        })
+    }
+
+    /// The cached entry for `username`; with `fresh`, only one resolved
+    /// within the TTL.
+    fn get(&self, username: &str, fresh: bool) -> Result<Option<CachedPeer>> {
+        let max_age = match (fresh, self.ttl_hours) {
+            (false, _) => None,
+            (true, Some(hours)) => Some(format!("-{hours} hours")),
+            (true, None) => return Ok(None),
+        };
+        let mut stmt = self.db.prepare(
+            "SELECT channel_id, access_hash, title, broadcast, resolved_at FROM channel_peers
+             WHERE username = ? AND (?2 IS NULL OR resolved_at >= datetime('now', ?2))",
+        )?;
+        stmt.bind((1, username.to_lowercase().as_str()))?;
+        stmt.bind((2, max_age.as_deref()))?;
+        if let State::Row = stmt.next()? {
+            return Ok(Some(CachedPeer {
+                channel_id: stmt.read::<i64, _>(0)?,
+                access_hash: stmt.read::<i64, _>(1)?,
+                title: stmt.read::<String, _>(2)?,
+                broadcast: stmt.read::<i64, _>(3)? != 0,
+                resolved_at: stmt.read::<String, _>(4)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    fn put(&self, username: &str, peer: &Peer) -> Result<()> {
+        let Some(access_hash) = channel_access_hash(peer) else {
+            return Ok(());
+        };
+        let mut stmt = self.db.prepare(
+            "INSERT OR REPLACE INTO channel_peers (username, channel_id, access_hash, title, broadcast)
+             VALUES (?, ?, ?, ?, ?)",
+        )?;
+        stmt.bind((1, username.to_lowercase().as_str()))?;
+        stmt.bind((2, peer.id().bare_id()))?;
+        stmt.bind((3, access_hash))?;
+        stmt.bind((4, peer.name().unwrap_or("")))?;
+        stmt.bind((5, matches!(peer, Peer::Channel(_)) as i64))?;
+        stmt.next()?;
+        Ok(())
+    }
+}
+
+fn channel_access_hash(peer: &Peer) -> Option<i64> {
+    match peer {
+        Peer::Channel(channel) => channel.raw.access_hash,
+        Peer::Group(group) => match &group.raw {
+            tl::enums::Chat::Channel(channel) => channel.access_hash,
+            _ => None,
+        },
+        Peer::User(_) => None,
+    }
+}
+
+/// Watched channels that could be reached, plus human-readable problems
+/// worth forwarding to admins.
+pub struct ResolvedChannels {
+    /// `(configured username, peer)` in configuration order.
+    pub peers: Vec<(String, Peer)>,
+    pub warnings: Vec<String>,
+}
+
+impl ResolvedChannels {
+    /// Append `other`, skipping channels already present.
+    pub fn merge(&mut self, other: ResolvedChannels) {
+        self.warnings.extend(other.warnings);
+        for (name, peer) in other.peers {
+            if !self
+                .peers
+                .iter()
+                .any(|(_, p)| p.id().bare_id() == peer.id().bare_id())
+            {
+                self.peers.push((name, peer));
+            }
+        }
+    }
+}
+
+/// Resolve `usernames`.  Channels resolved within the peer-cache TTL are
+/// taken from the cache; when a username no longer resolves (renamed,
+/// removed, or Telegram failing) the cached channel id is used instead.
+pub async fn resolve_channels(client: &Client, usernames: &[String]) -> Result<ResolvedChannels> {
+    let cache = match PeerCache::open_from_env() {
+        Ok(c) => Some(c),
+        Err(e) => {
+            warn!("Peer cache unavailable, renamed channels can't be recovered: {e}");
+            None
+        }
+    };
+    let mut out = ResolvedChannels {
+        peers: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let mut from_cache = 0;
+    for uname in usernames {
+        if let Some(cache) = &cache
+            && let Some(cached) = cache.get(uname, true).ok().flatten()
+        {
+            debug!("@{uname}: cached peer from {}", cached.resolved_at);
+            out.peers.push((uname.clone(), cached.to_peer()));
+            from_cache += 1;
+            continue;
+        }
+        let failure = match client.resolve_username(uname).await {
+            Ok(Some(peer)) => {
+                if let Some(cache) = &cache
+                    && let Err(e) = cache.put(uname, &peer)
+                {
+                    warn!("Failed to cache peer for @{uname}: {e}");
+                }
+                out.peers.push((uname.clone(), peer));
+                continue;
+            }
+            Ok(None) => "username not found".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        let cached = cache
+            .as_ref()
+            .and_then(|c| c.get(uname, false).ok().flatten());
+        let Some(cached) = cached else {
+            let msg = format!("@{uname} could not be resolved ({failure}); channel skipped");
+            warn!("{msg}");
+            out.warnings.push(msg);
+            continue;
+        };
+        let channel_id = cached.channel_id;
+        let peer_ref = PeerRef {
+            id: PeerId::channel(channel_id),
+            auth: PeerAuth::from_hash(cached.access_hash),
+        };
+        match client.resolve_peer(peer_ref).await {
+            Ok(peer) => {
+                let msg = format!(
+                    "@{uname} no longer resolves ({failure}); using cached id {channel_id} – now {} (@{}). Update TG_CHANNELS.",
+                    peer.name().unwrap_or("<unknown>"),
+                    peer.username().unwrap_or("no username"),
+                );
+                warn!("{msg}");
+                out.warnings.push(msg);
+                out.peers.push((uname.clone(), peer));
+            }
+            Err(e) => {
+                // Telegram itself is failing: read with what the cache knows.
+                let msg = format!(
+                    "@{uname} could not be resolved ({failure}) and cached id {channel_id} failed ({e}); \
+                     using the cached peer from {}",
+                    cached.resolved_at
+                );
+                warn!("{msg}");
+                out.warnings.push(msg);
+                out.peers.push((uname.clone(), cached.to_peer()));
+            }
+        }
+    }
+    if from_cache > 0 {
+        info!(
+            "{from_cache} of {} channel(s) taken from the peer cache",
+            usernames.len()
+        );
+    }
+    Ok(out)
+}
+
+/// Resolve the configured usernames plus the current contents of the
+/// configured chat folders.  A channel listed both ways is watched once.
+pub async fn resolve_watch_list(client: &Client, cfg: &TgCfg) -> Result<ResolvedChannels> {
+    let mut out = resolve_channels(client, &cfg.channels).await?;
+    if cfg.folders.is_empty() {
+        return Ok(out);
+    }
+    out.merge(folder_channels(client, &cfg.folders).await?);
+    Ok(out)
+}
+
+// ───────────────────────── Chat folders ──────────────────────────────────
+
+/// Channels in the chat folders titled `titles` (case-insensitive), keyed by
+/// username (or bare id for private channels).  Users and basic groups in
+/// the folder are ignored.
+pub async fn folder_channels(client: &Client, titles: &[String]) -> Result<ResolvedChannels> {
+    let tl::enums::messages::DialogFilters::Filters(resp) = client
+        .invoke(&tl::functions::messages::GetDialogFilters {})
+        .await
+        .context("messages.getDialogFilters failed")?;
+    let mut out = ResolvedChannels {
+        peers: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    for title in titles {
+        let wanted = title.to_lowercase();
+        let found = resp.filters.iter().find_map(|f| match f {
+            tl::enums::DialogFilter::Filter(f) => {
+                let tl::enums::TextWithEntities::Entities(t) = &f.title;
+                (t.text.to_lowercase() == wanted).then_some([&f.pinned_peers, &f.include_peers])
+            }
+            tl::enums::DialogFilter::Chatlist(f) => {
+                let tl::enums::TextWithEntities::Entities(t) = &f.title;
+                (t.text.to_lowercase() == wanted).then_some([&f.pinned_peers, &f.include_peers])
+            }
+            tl::enums::DialogFilter::Default => None,
+        });
+        let Some(lists) = found else {
+            let msg = format!("Chat folder \"{title}\" not found; no channels taken from it");
+            warn!("{msg}");
+            out.warnings.push(msg);
+            continue;
+        };
+
+        for input in lists.into_iter().flatten() {
+            let tl::enums::InputPeer::Channel(channel) = input else {
+                debug!("Folder \"{title}\": skipping non-channel entry {input:?}");
+                continue;
+            };
+            if out
+                .peers
+                .iter()
+                .any(|(_, p)| p.id().bare_id() == channel.channel_id)
+            {
+                continue;
+            }
+            let peer_ref = PeerRef {
+                id: PeerId::channel(channel.channel_id),
+                auth: PeerAuth::from_hash(channel.access_hash),
+            };
+            match client.resolve_peer(peer_ref).await {
+                Ok(peer) => {
+                    let name = peer
+                        .username()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| channel.channel_id.to_string());
+                    out.peers.push((name, peer));
+                }
+                Err(e) => {
+                    let msg = format!(
+                        "Folder \"{title}\": channel {} could not be resolved ({e}); skipped",
+                        channel.channel_id
+                    );
+                    warn!("{msg}");
+                    out.warnings.push(msg);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+// ───────────────────────── Update gap accounting ─────────────────────────
+
+/// Per-channel message-id gap tracker.
+///
+/// Channel post ids are sequential, so a jump from `n` to `n + k` means
+/// `k - 1` posts never reached us – typically because grammers dropped them
+/// when `update_queue_limit` overflowed (it only logs that).  Deleted posts
+/// also leave holes, so small gaps are expected noise.
+#[derive(Default)]
+pub struct UpdateGapTracker {
+    channels: HashMap<i64, ChannelGaps>,
+}
+
+#[derive(Default)]
+struct ChannelGaps {
+    last_id: i32,
+    missed: u64,
+    gaps: u64,
+}
+
+impl UpdateGapTracker {
+    /// Record `msg_id` for `channel_id`.  Returns the missing id range
+    /// (`start..end`, exclusive) when a gap is detected.
+    pub fn observe(&mut self, channel_id: i64, msg_id: i32) -> Option<Range<i32>> {
+        let entry = self.channels.entry(channel_id).or_default();
+        let prev = entry.last_id;
+        entry.last_id = entry.last_id.max(msg_id);
+        if prev == 0 || msg_id <= prev + 1 {
+            return None;
+        }
+        entry.missed += (msg_id - prev - 1) as u64;
+        entry.gaps += 1;
+        Some(prev + 1..msg_id)
+    }
+
+    /// Missed posts so far per channel.
+    pub fn missed_by_channel(&self) -> HashMap<i64, u64> {
+        self.channels
+            .iter()
+            .map(|(id, c)| (*id, c.missed))
+            .collect()
+    }
+
+    pub fn total_missed(&self) -> u64 {
+        self.channels.values().map(|c| c.missed).sum()
+    }
+
+    /// Channels with the most missed posts: `(channel_id, missed, gaps)`.
+    pub fn most_affected(&self, n: usize) -> Vec<(i64, u64, u64)> {
+        let mut out: Vec<_> = self
+            .channels
+            .iter()
+            .filter(|(_, c)| c.missed > 0)
+            .map(|(id, c)| (*id, c.missed, c.gaps))
+            .collect();
+        out.sort_by_key(|(_, missed, _)| std::cmp::Reverse(*missed));
+        out.truncate(n);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contiguous_ids_are_not_gaps() {
+        let mut gaps = UpdateGapTracker::default();
+        for id in 100..110 {
+            assert_eq!(gaps.observe(-1, id), None);
+        }
+        assert_eq!(gaps.total_missed(), 0);
+        assert!(gaps.most_affected(5).is_empty());
+    }
+
+    #[test]
+    fn a_jump_reports_the_missing_range() {
+        let mut gaps = UpdateGapTracker::default();
+        gaps.observe(-1, 10);
+        assert_eq!(gaps.observe(-1, 14), Some(11..14));
+        assert_eq!(gaps.observe(-1, 15), None);
+        assert_eq!(gaps.observe(-1, 17), Some(16..17));
+        assert_eq!(gaps.total_missed(), 4);
+        assert_eq!(gaps.most_affected(5), vec![(-1, 4, 2)]);
+    }
+
+    #[test]
+    fn duplicate_and_older_ids_neither_gap_nor_rewind() {
+        let mut gaps = UpdateGapTracker::default();
+        gaps.observe(-1, 20);
+        assert_eq!(gaps.observe(-1, 20), None);
+        assert_eq!(gaps.observe(-1, 12), None);
+        // Still measured from 20, not from the late 12.
+        assert_eq!(gaps.observe(-1, 21), None);
+        assert_eq!(gaps.observe(-1, 23), Some(22..23));
+        assert_eq!(gaps.total_missed(), 1);
+    }
+
+    #[test]
+    fn each_channel_starts_fresh_and_counts_alone() {
+        let mut gaps = UpdateGapTracker::default();
+        gaps.observe(-1, 5);
+        // A new channel's first id is a baseline, however far from 0.
+        assert_eq!(gaps.observe(-2, 9_000), None);
+        assert_eq!(gaps.observe(-2, 9_004), Some(9_001..9_004));
+        assert_eq!(gaps.observe(-1, 6), None);
+        assert_eq!(gaps.observe(-1, 8), Some(7..8));
+        assert_eq!(gaps.missed_by_channel(), HashMap::from([(-1, 1), (-2, 3)]));
+        assert_eq!(gaps.most_affected(1), vec![(-2, 3, 1)]);
+    }
+}
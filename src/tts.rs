@@ -0,0 +1,149 @@
+//! Spoken alerts: critical alerts are also synthesized into a short
+//! Ukrainian voice message and delivered with `sendVoice`, which plays
+//! audibly even with the screen off and message previews disabled.
+//!
+//! Two engines are supported: a local command (e.g. `piper` or
+//! `espeak-ng` piped through `opusenc`) that reads the text on stdin and
+//! writes OGG/Opus to stdout, and an HTTP API that answers a JSON POST with
+//! the audio bytes.
+
+use anyhow::{Result, anyhow, bail};
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Voice notes longer than this are unlikely to be a short alert.
+const MAX_AUDIO_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub enum TtsEngine {
+    /// Shell command: text on stdin, OGG/Opus on stdout.
+    Command(String),
+    Api {
+        url: String,
+        token: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TtsCfg {
+    pub engine: TtsEngine,
+    pub timeout: Duration,
+}
+
+impl TtsCfg {
+    /// `None` unless an engine is configured.  The command wins when both
+    /// are set.
+    ///
+    /// | Env var            | Default | Description                                   |
+    /// |--------------------|---------|-----------------------------------------------|
+    /// | `TTS_COMMAND`      | —       | Shell command: text on stdin, OGG/Opus stdout |
+    /// | `TTS_API_URL`      | —       | TTS API (JSON POST, audio in the response)    |
+    /// | `TTS_API_TOKEN`    | —       | Optional bearer token for the API             |
+    /// | `TTS_TIMEOUT_SECS` | `20`    | Synthesis time limit                          |
+    pub fn from_env() -> Option<Self> {
+        let var = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let engine = match var("TTS_COMMAND") {
+            Some(cmd) => TtsEngine::Command(cmd),
+            None => TtsEngine::Api {
+                url: var("TTS_API_URL")?,
+                token: var("TTS_API_TOKEN"),
+            },
+        };
+        let timeout_secs: u64 = var("TTS_TIMEOUT_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        Some(Self {
+            engine,
+            timeout: Duration::from_secs(timeout_secs),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.engine {
+            TtsEngine::Command(cmd) => format!("command `{cmd}`"),
+            TtsEngine::Api { url, .. } => format!("API {url}"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TtsRequest<'a> {
+    text: &'a str,
+    language: &'static str,
+    format: &'static str,
+}
+
+pub struct Tts {
+    cfg: TtsCfg,
+    http: HttpClient,
+}
+
+impl Tts {
+    pub fn new(cfg: TtsCfg, http: HttpClient) -> Self {
+        Self { cfg, http }
+    }
+
+    /// OGG/Opus audio of `text` read out in Ukrainian.
+    pub async fn synthesize(&self, text: &str) -> Result<Vec<u8>> {
+        let audio = match tokio::time::timeout(self.cfg.timeout, self.run(text)).await {
+            Ok(result) => result?,
+            Err(_) => bail!("TTS timed out after {}s", self.cfg.timeout.as_secs()),
+        };
+        if audio.is_empty() {
+            bail!("TTS produced no audio");
+        }
+        if audio.len() > MAX_AUDIO_BYTES {
+            bail!("TTS produced {} bytes of audio", audio.len());
+        }
+        Ok(audio)
+    }
+
+    async fn run(&self, text: &str) -> Result<Vec<u8>> {
+        match &self.cfg.engine {
+            TtsEngine::Command(cmd) => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(cmd)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(text.as_bytes()).await?;
+                }
+                let output = child.wait_with_output().await?;
+                if !output.status.success() {
+                    bail!("TTS command exited with {}", output.status);
+                }
+                Ok(output.stdout)
+            }
+            TtsEngine::Api { url, token } => {
+                let body = TtsRequest {
+                    text,
+                    language: "uk",
+                    format: "ogg_opus",
+                };
+                let mut req = self.http.post(url).json(&body);
+                if let Some(token) = token {
+                    req = req.bearer_auth(token);
+                }
+                let resp = req.send().await?;
+                if !resp.status().is_success() {
+                    let status = resp.status();
+                    let raw = resp.text().await.unwrap_or_default();
+                    return Err(anyhow!("TTS request failed: {status} body={raw}"));
+                }
+                Ok(resp.bytes().await?.to_vec())
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Webhook sink: POSTs every forwarded alert as JSON, following the push
+//! contract for companion mobile apps (see "Companion app push webhook" in
+//! the README).
+//!
+//! Payloads are versioned (`version: 1`); consumers should ignore unknown
+//! fields.  When `WEBHOOK_SECRET` is set every request is signed with
+//! HMAC-SHA256 over `"<timestamp>.<body>"`.
+
+use crate::filter::gazetteer::Place;
+use crate::filter::threat_kind::ThreatKind;
+use crate::filter::threat_level::ThreatLevel;
+use crate::filter::{AlertInfo, Proximity};
+use anyhow::{Result, anyhow};
+use hmac::{Hmac, Mac};
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::warn;
+
+pub const CONTRACT_VERSION: u32 = 1;
+
+/// Notification priority, mapped onto APNs interruption levels and FCM
+/// message priorities by [`PushPriority::apns`] / [`PushPriority::fcm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushPriority {
+    /// Ballistic/hypersonic threat to the area: bypass Do Not Disturb.
+    Critical,
+    /// Active missile or drone threat: break through Focus modes.
+    TimeSensitive,
+    /// Regular alert.
+    Active,
+    /// All-clear and status updates.
+    Passive,
+}
+
+impl PushPriority {
+    pub fn from_alert(info: &AlertInfo) -> Self {
+        match ThreatLevel::from_alert(info) {
+            ThreatLevel::CriticalBallistic => Self::Critical,
+            ThreatLevel::ActiveMissile | ThreatLevel::ActiveDrone => Self::TimeSensitive,
+            ThreatLevel::Watch if !info.threats.is_empty() => Self::Active,
+            ThreatLevel::Watch | ThreatLevel::Clear => Self::Passive,
+        }
+    }
+
+    /// APNs `interruption-level`.
+    pub fn apns(&self) -> &'static str {
+        match self {
+            Self::Critical => "critical",
+            Self::TimeSensitive => "time-sensitive",
+            Self::Active => "active",
+            Self::Passive => "passive",
+        }
+    }
+
+    /// FCM `android.priority`.
+    pub fn fcm(&self) -> &'static str {
+        match self {
+            Self::Critical | Self::TimeSensitive => "high",
+            Self::Active | Self::Passive => "normal",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApnsHints {
+    pub interruption_level: &'static str,
+    /// `1.0` for critical alerts, `0.0` otherwise (relevance score).
+    pub relevance_score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FcmHints {
+    pub priority: &'static str,
+    /// Suggested Android notification channel.
+    pub channel_id: &'static str,
+    /// Seconds after which the alert is stale and may be dropped.
+    pub ttl_secs: u32,
+}
+
+/// The channel post an alert came from.
+#[derive(Debug, Clone, Copy)]
+pub struct PushSource<'a> {
+    pub channel_id: i64,
+    pub channel_title: &'a str,
+    /// Telegram message id within the channel.
+    pub message_id: i32,
+    /// Which of the alerts the post produced this is, from 0.
+    pub alert_index: usize,
+    /// Unix seconds of the post.
+    pub timestamp: i64,
+}
+
+/// One webhook request body (contract version 1).
+#[derive(Debug, Serialize)]
+pub struct PushPayload {
+    pub version: u32,
+    /// Stable id, `"<channel_id>:<message_id>:<alert index>"`; use it to
+    /// de-duplicate retries.
+    pub id: String,
+    /// `"alert"`, `"all_clear"` or `"status"`.
+    pub kind: &'static str,
+    /// `"new"`, `"update"`, `"escalation"`, `"status"` or `"all_clear"`:
+    /// whether the alert opens a wave or follows up on one.
+    #[serde(rename = "type")]
+    pub alert_type: &'static str,
+    pub priority: PushPriority,
+    /// Notification title (threat labels and proximity, no emoji).
+    pub title: String,
+    /// Fully rendered alert text as sent to Telegram subscribers.
+    pub body: String,
+    pub threats: Vec<&'static str>,
+    /// `"district"`, `"city"`, `"oblast"` or `"none"`.
+    pub proximity: &'static str,
+    pub nationwide: bool,
+    pub urgent: bool,
+    /// Settlements the message names, from the built-in gazetteer.
+    pub places: Vec<&'static Place>,
+    pub channel_id: i64,
+    pub channel_title: String,
+    /// Telegram message id of the source post within the channel.
+    pub message_id: i32,
+    /// Unix seconds of the source post.
+    pub timestamp: i64,
+    pub apns: ApnsHints,
+    pub fcm: FcmHints,
+}
+
+impl PushPayload {
+    pub fn new(info: &AlertInfo, source: &PushSource, body: &str) -> Self {
+        let priority = PushPriority::from_alert(info);
+        let kind = if info.threats.contains(&ThreatKind::AllClear) {
+            "all_clear"
+        } else if info.threats.is_empty() {
+            "status"
+        } else {
+            "alert"
+        };
+        let mut title = info
+            .threats
+            .iter()
+            .map(|t| t.label())
+            .collect::<Vec<_>>()
+            .join(" + ");
+        if title.is_empty() {
+            title = "Статус".to_string();
+        }
+        if info.nationwide {
+            title.push_str(" · вся Україна");
+        } else if info.proximity != Proximity::None {
+            title.push_str(&format!(" · {}", info.proximity.name()));
+        }
+
+        Self {
+            version: CONTRACT_VERSION,
+            id: format!(
+                "{}:{}:{}",
+                source.channel_id, source.message_id, source.alert_index
+            ),
+            kind,
+            alert_type: info.alert_type.name(),
+            priority,
+            title,
+            body: body.to_string(),
+            threats: info.threats.iter().map(|t| t.variant_name()).collect(),
+            proximity: info.proximity.name(),
+            nationwide: info.nationwide,
+            urgent: info.urgent,
+            places: info.places.clone(),
+            channel_id: source.channel_id,
+            channel_title: source.channel_title.to_string(),
+            message_id: source.message_id,
+            timestamp: source.timestamp,
+            apns: ApnsHints {
+                interruption_level: priority.apns(),
+                relevance_score: if priority == PushPriority::Critical {
+                    1.0
+                } else {
+                    0.0
+                },
+            },
+            fcm: FcmHints {
+                priority: priority.fcm(),
+                channel_id: match priority {
+                    PushPriority::Critical => "critical_alerts",
+                    PushPriority::TimeSensitive | PushPriority::Active => "alerts",
+                    PushPriority::Passive => "updates",
+                },
+                ttl_secs: 900,
+            },
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// `sha256=<hex>` signature of `"<timestamp>.<body>"`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookCfg {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+impl WebhookCfg {
+    /// | Env var          | Default | Description                         |
+    /// |------------------|---------|-------------------------------------|
+    /// | `WEBHOOK_URL`    | —       | Endpoint receiving alert payloads   |
+    /// | `WEBHOOK_SECRET` | —       | HMAC-SHA256 signing secret          |
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("WEBHOOK_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())?;
+        let secret = std::env::var("WEBHOOK_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty());
+        Some(Self { url, secret })
+    }
+}
+
+pub struct WebhookSink {
+    cfg: WebhookCfg,
+    http: HttpClient,
+}
+
+impl WebhookSink {
+    pub fn new(cfg: WebhookCfg, http: HttpClient) -> Self {
+        Self { cfg, http }
+    }
+
+    /// POST `payload`, retrying once on failure.  Returns whether the
+    /// endpoint accepted it.
+    pub async fn send(&self, payload: &PushPayload) -> bool {
+        for attempt in 1..=2 {
+            match self.post(payload).await {
+                Ok(()) => return true,
+                Err(e) => warn!("Webhook delivery attempt {attempt} failed: {e}"),
+            }
+        }
+        false
+    }
+
+    async fn post(&self, payload: &PushPayload) -> Result<()> {
+        let body = serde_json::to_vec(payload)?;
+        let now = chrono::Utc::now().timestamp();
+        let mut req = self
+            .http
+            .post(&self.cfg.url)
+            .header("Content-Type", "application/json")
+            .header("X-TgOsint-Version", CONTRACT_VERSION.to_string())
+            .header("X-TgOsint-Timestamp", now.to_string())
+            .timeout(Duration::from_secs(10));
+        if let Some(secret) = &self.cfg.secret {
+            req = req.header("X-TgOsint-Signature", sign(secret, now, &body));
+        }
+        let resp = req.body(body).send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let raw = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("webhook POST failed: {status} body={raw}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::AlertType;
+    use crate::filter::gazetteer::places_named;
+    use serde_json::json;
+
+    #[test]
+    fn signature_is_hmac_sha256_of_timestamp_dot_body() {
+        // hmac.new(b"secret", b'1700000000.{"version":1}', sha256)
+        assert_eq!(
+            sign("secret", 1_700_000_000, br#"{"version":1}"#),
+            "sha256=6c49492c841520e805df0f6652ac4859b2bf8a94b97b7fe2664f0c02e62d2c85"
+        );
+        assert_ne!(
+            sign("secret", 1_700_000_001, br#"{"version":1}"#),
+            sign("secret", 1_700_000_000, br#"{"version":1}"#)
+        );
+    }
+
+    /// Contract version 1: renaming or dropping a field breaks receivers.
+    #[test]
+    fn payload_matches_contract_v1() {
+        let info = AlertInfo {
+            alert_type: AlertType::New,
+            threats: vec![ThreatKind::Ballistic],
+            proximity: Proximity::City,
+            nationwide: false,
+            urgent: true,
+            reduced: false,
+            escalated_from: None,
+            location_uncertain: false,
+            places: places_named("бровари"),
+            confidence: None,
+        };
+        let source = PushSource {
+            channel_id: -100123,
+            channel_title: "monitor",
+            message_id: 4711,
+            alert_index: 0,
+            timestamp: 1_700_000_000,
+        };
+        let payload = PushPayload::new(&info, &source, "🚀 Балістика");
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "version": 1,
+                "id": "-100123:4711:0",
+                "kind": "alert",
+                "type": "new",
+                "priority": "critical",
+                "title": "Балістика · city",
+                "body": "🚀 Балістика",
+                "threats": ["Ballistic"],
+                "proximity": "city",
+                "nationwide": false,
+                "urgent": true,
+                "places": [{
+                    "name": "Бровари",
+                    "raion": "Броварський",
+                    "oblast": "Київська",
+                    "lat": 50.511,
+                    "lon": 30.79
+                }],
+                "channel_id": -100123,
+                "channel_title": "monitor",
+                "message_id": 4711,
+                "timestamp": 1700000000,
+                "apns": {"interruption_level": "critical", "relevance_score": 1.0},
+                "fcm": {"priority": "high", "channel_id": "critical_alerts", "ttl_secs": 900}
+            })
+        );
+
+        // Two alerts from one channel in the same second stay distinct.
+        let next = PushSource {
+            message_id: 4712,
+            ..source
+        };
+        assert_eq!(PushPayload::new(&info, &next, "").id, "-100123:4712:0");
+        let second = PushSource {
+            alert_index: 1,
+            ..source
+        };
+        assert_eq!(PushPayload::new(&info, &second, "").id, "-100123:4711:1");
+    }
+}
@@ -0,0 +1,207 @@
+//! Soak test: weeks of synthetic channel traffic through a filter running
+//! on a [`SimulatedClock`], with the production windows.  `SOAK_DAYS=30
+//! cargo test soak` simulates a month (default: two weeks).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tg_osint_v0::filter::clock::SimulatedClock;
+use tg_osint_v0::filter::{AlertFilter, FilterStats, kyiv_filter};
+
+const CHANNELS: i64 = 120;
+const MSGS_PER_HOUR: u64 = 40;
+const BUSY_HOURS: u64 = 16;
+/// Longer than every filter window (the naval dedup window is 6 h).
+const QUIET_NIGHT: Duration = Duration::from_secs(8 * 3600);
+
+/// Tiny deterministic PRNG (xorshift64) so the soak stream is reproducible.
+struct SoakRng(u64);
+
+impl SoakRng {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+const PLACES: [&str; 12] = [
+    "Бровари",
+    "Бориспіль",
+    "Київ",
+    "Обухів",
+    "Васильків",
+    "Ірпінь",
+    "Біла Церква",
+    "Чернігів",
+    "Житомир",
+    "Умань",
+    "Фастів",
+    "Бучу",
+];
+
+const TEMPLATES: [&str; 16] = [
+    "Шахеди курсом на {p}",
+    "Група з {n} БпЛА через {p} курсом на {q}",
+    "{n} шахедів у напрямку {p}",
+    "Балістика на Київ!",
+    "Ракети на Київщину, курсом на {p}",
+    "КР курсом на {p}",
+    "Загроза застосування КАБів для Харківщини",
+    "Ціль на {p}",
+    "Вихід на {p}",
+    "Поки не фіксуються",
+    "Більше не спостерігається",
+    "Розвідувальний БпЛА над {p}",
+    "Шахед змінив курс на захід, віддаляється від {p}",
+    "Увага! Повторно балістика на Київ",
+    "Станом на 08:00 збито/подавлено 40 з 52 БпЛА. За попередніми даними зафіксовано влучання.",
+    "Гарного дня, підписуйтесь на наш канал",
+];
+
+fn render(rng: &mut SoakRng) -> String {
+    TEMPLATES[rng.next(TEMPLATES.len())]
+        .replace("{p}", PLACES[rng.next(PLACES.len())])
+        .replace("{q}", PLACES[rng.next(PLACES.len())])
+        .replace("{n}", &(rng.next(12) + 1).to_string())
+}
+
+/// Sent every morning from two channels the stream never uses; a soaked
+/// filter must decide it exactly like a fresh one.
+fn run_probe(filter: &mut AlertFilter) -> Vec<Option<String>> {
+    [
+        "Шахеди курсом на Бровари",
+        "Група з 6 БпЛА через Бровари курсом на Київ",
+        "Балістика на Київ!",
+        "Ракети на Київщину",
+        "Поки не фіксуються",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, text)| filter.process_with_id(1001 + i as i64 % 2, "Probe", text))
+    .collect()
+}
+
+/// Field-wise maximum.
+fn peak(a: FilterStats, b: FilterStats) -> FilterStats {
+    FilterStats {
+        dedup_entries: a.dedup_entries.max(b.dedup_entries),
+        channel_contexts: a.channel_contexts.max(b.channel_contexts),
+        context_messages: a.context_messages.max(b.context_messages),
+        max_context_messages: a.max_context_messages.max(b.max_context_messages),
+        negative_status_entries: a.negative_status_entries.max(b.negative_status_entries),
+        corridor_reports: a.corridor_reports.max(b.corridor_reports),
+        shahed_tracks: a.shahed_tracks.max(b.shahed_tracks),
+        unreliable_channels: a.unreliable_channels.max(b.unreliable_channels),
+    }
+}
+
+/// Every gauge in `later` stays within twice its week-one value.
+fn assert_flat(first: FilterStats, later: FilterStats, what: &str) {
+    let pairs = [
+        ("dedup_entries", first.dedup_entries, later.dedup_entries),
+        (
+            "channel_contexts",
+            first.channel_contexts,
+            later.channel_contexts,
+        ),
+        (
+            "context_messages",
+            first.context_messages,
+            later.context_messages,
+        ),
+        (
+            "max_context_messages",
+            first.max_context_messages,
+            later.max_context_messages,
+        ),
+        (
+            "negative_status_entries",
+            first.negative_status_entries,
+            later.negative_status_entries,
+        ),
+        (
+            "corridor_reports",
+            first.corridor_reports,
+            later.corridor_reports,
+        ),
+        ("shahed_tracks", first.shahed_tracks, later.shahed_tracks),
+        (
+            "unreliable_channels",
+            first.unreliable_channels,
+            later.unreliable_channels,
+        ),
+    ];
+    for (name, first, later) in pairs {
+        assert!(
+            later <= first.max(1) * 2,
+            "{what}: {name} grew from {first} in week 1 to {later}"
+        );
+    }
+}
+
+/// Each simulated day is 16 busy hours from up to 120 channels followed by
+/// a quiet night longer than every window; no all-clear is ever sent, so
+/// only expiry keeps state bounded.  The busiest point of each week and
+/// the state left after each night must not grow past week one.
+#[test]
+fn soak_simulated_weeks_keep_state_flat_and_decisions_stable() {
+    let days: u64 = std::env::var("SOAK_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(14);
+
+    let expected = run_probe(&mut kyiv_filter());
+    assert!(expected.iter().any(|a| a.is_some()));
+
+    let clock = Arc::new(SimulatedClock::new());
+    let mut filter = kyiv_filter().with_clock(clock.clone());
+    let mut rng = SoakRng(0x9E37_79B9_7F4A_7C15);
+    let mut alerts = 0u64;
+    let mut weeks: Vec<(FilterStats, FilterStats)> = Vec::new();
+    for day in 0..days {
+        let mut busiest = filter.stats();
+        for _ in 0..BUSY_HOURS * MSGS_PER_HOUR {
+            clock.advance(Duration::from_secs(3600 / MSGS_PER_HOUR));
+            let channel_id = rng.next(CHANNELS as usize) as i64 + 1;
+            if filter
+                .process_with_id(channel_id, &format!("Ch{channel_id}"), &render(&mut rng))
+                .is_some()
+            {
+                alerts += 1;
+            }
+            busiest = peak(busiest, filter.stats());
+        }
+        assert!(busiest.channel_contexts <= CHANNELS as usize);
+        assert!(busiest.max_context_messages <= 20, "{busiest:?}");
+
+        clock.advance(QUIET_NIGHT);
+        assert_eq!(run_probe(&mut filter), expected, "day {day}");
+        let morning = filter.stats();
+
+        if day % 7 == 0 {
+            weeks.push((busiest, morning));
+        } else if let Some(week) = weeks.last_mut() {
+            *week = (peak(week.0, busiest), peak(week.1, morning));
+        }
+    }
+    assert!(
+        alerts > days * BUSY_HOURS,
+        "stream produced only {alerts} alert(s)"
+    );
+
+    let (first_busy, first_morning) = weeks[0];
+    for (week, &(busy, morning)) in weeks.iter().enumerate().skip(1) {
+        assert_flat(
+            first_busy,
+            busy,
+            &format!("busiest point of week {}", week + 1),
+        );
+        assert_flat(
+            first_morning,
+            morning,
+            &format!("morning of week {}", week + 1),
+        );
+    }
+}